@@ -57,4 +57,9 @@ pub struct DebugArgs {
         conflicts_with = "hook_transaction"
     )]
     pub hook_all: bool,
+
+    /// Write every block that is rejected as invalid to the `bad-blocks` directory of the
+    /// datadir, as RLP alongside the validation error.
+    #[arg(long = "debug.dump-bad-blocks", help_heading = "Debug")]
+    pub dump_bad_blocks: bool,
 }
@@ -4,11 +4,16 @@ use crate::version::P2P_CLIENT_VERSION;
 use clap::Args;
 use reth_config::Config;
 use reth_discv4::{DEFAULT_DISCOVERY_ADDR, DEFAULT_DISCOVERY_PORT};
+use reth_discv5::{Discv5Config, Enr, DEFAULT_DISCOVERY_V5_PORT};
 use reth_net_nat::NatResolver;
 use reth_network::{HelloMessageWithProtocols, NetworkConfigBuilder};
 use reth_primitives::{mainnet_nodes, ChainSpec, NodeRecord};
 use secp256k1::SecretKey;
-use std::{net::Ipv4Addr, path::PathBuf, sync::Arc};
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    path::PathBuf,
+    sync::Arc,
+};
 
 /// Parameters for configuring the network more granularity via CLI
 #[derive(Debug, Args)]
@@ -55,7 +60,7 @@ pub struct NetworkArgs {
     pub no_persist_peers: bool,
 
     #[allow(rustdoc::invalid_html_tags)]
-    /// NAT resolution method (any|none|upnp|publicip|extip:<IP>)
+    /// NAT resolution method (any|none|upnp|natpmp|publicip|extip:<IP>)
     #[arg(long, default_value = "any")]
     pub nat: NatResolver,
 
@@ -74,6 +79,10 @@ pub struct NetworkArgs {
     /// Maximum number of inbound requests. default: 30
     #[arg(long)]
     pub max_inbound_peers: Option<usize>,
+
+    /// Serve state ranges of the recent blocks to peers via the `snap/1` protocol.
+    #[arg(long)]
+    pub serve_snap: bool,
 }
 
 impl NetworkArgs {
@@ -147,6 +156,34 @@ pub struct DiscoveryArgs {
     /// The UDP port to use for P2P discovery/networking
     #[arg(long = "discovery.port", name = "discovery.port", value_name = "DISCOVERY_PORT", default_value_t = DEFAULT_DISCOVERY_PORT)]
     pub port: u16,
+
+    /// Enable Discv5 discovery.
+    ///
+    /// Only nodes that advertise the `eth` fork id in their ENR are added as peers.
+    #[arg(long, conflicts_with = "disable_discovery")]
+    pub enable_discv5_discovery: bool,
+
+    /// The UDP address to use for Discv5 discovery
+    #[arg(long = "discovery.v5.addr", name = "discovery.v5.addr", value_name = "DISCOVERY_V5_ADDR", default_value_t = DEFAULT_DISCOVERY_ADDR)]
+    pub discv5_addr: Ipv4Addr,
+
+    /// The UDP port to use for Discv5 discovery.
+    ///
+    /// This can only be the same port as the discv4 port if Discv4 discovery is disabled.
+    #[arg(long = "discovery.v5.port", name = "discovery.v5.port", value_name = "DISCOVERY_V5_PORT", default_value_t = DEFAULT_DISCOVERY_V5_PORT)]
+    pub discv5_port: u16,
+
+    /// Comma separated ENRs of the Discv5 boot nodes.
+    ///
+    /// --discovery.v5.bootnodes
+    /// enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8
+    #[arg(
+        long = "discovery.v5.bootnodes",
+        name = "discovery.v5.bootnodes",
+        value_name = "ENRS",
+        value_delimiter = ','
+    )]
+    pub discv5_bootnodes: Vec<Enr>,
 }
 
 impl DiscoveryArgs {
@@ -162,6 +199,17 @@ impl DiscoveryArgs {
         if self.disable_discovery || self.disable_discv4_discovery {
             network_config_builder = network_config_builder.disable_discv4_discovery();
         }
+
+        if self.enable_discv5_discovery && !self.disable_discovery {
+            let mut discv5_builder = Discv5Config::builder();
+            discv5_builder
+                .discovery_addr(SocketAddr::V4(SocketAddrV4::new(
+                    self.discv5_addr,
+                    self.discv5_port,
+                )))
+                .add_boot_nodes(self.discv5_bootnodes.clone());
+            network_config_builder = network_config_builder.discovery_v5(discv5_builder);
+        }
         network_config_builder
     }
 }
@@ -206,6 +254,27 @@ mod tests {
         assert_eq!(args.max_inbound_peers, Some(15));
     }
 
+    #[test]
+    fn parse_discv5_args() {
+        let args = CommandParser::<NetworkArgs>::parse_from(["reth"]).args;
+        assert!(!args.discovery.enable_discv5_discovery);
+        assert_eq!(args.discovery.discv5_port, DEFAULT_DISCOVERY_V5_PORT);
+
+        let enr = "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8";
+        let args = CommandParser::<NetworkArgs>::parse_from([
+            "reth",
+            "--enable-discv5-discovery",
+            "--discovery.v5.port",
+            "9001",
+            "--discovery.v5.bootnodes",
+            enr,
+        ])
+        .args;
+        assert!(args.discovery.enable_discv5_discovery);
+        assert_eq!(args.discovery.discv5_port, 9001);
+        assert_eq!(args.discovery.discv5_bootnodes, vec![enr.parse::<Enr>().unwrap()]);
+    }
+
     #[test]
     fn parse_trusted_peer_args() {
         let args =
@@ -16,10 +16,11 @@ use clap::{
     Arg, Args, Command,
 };
 use futures::TryFutureExt;
+use reth_beacon_consensus::BeaconConsensusEngineHandle;
 use reth_network_api::{NetworkInfo, Peers};
 use reth_provider::{
-    AccountReader, BlockReaderIdExt, CanonStateSubscriptions, ChainSpecProvider, ChangeSetReader,
-    EvmEnvProvider, HeaderProvider, StateProviderFactory,
+    AccountReader, BadBlockReader, BlockReaderIdExt, CanonStateSubscriptions, ChainSpecProvider,
+    ChangeSetReader, EvmEnvProvider, HeaderProvider, HistoryReader, StateProviderFactory,
 };
 use reth_rpc::{
     eth::{
@@ -27,10 +28,11 @@ use reth_rpc::{
             DEFAULT_BLOCK_CACHE_MAX_LEN, DEFAULT_ENV_CACHE_MAX_LEN, DEFAULT_RECEIPT_CACHE_MAX_LEN,
         },
         gas_oracle::GasPriceOracleConfig,
-        RPC_DEFAULT_GAS_CAP,
+        DEFAULT_ETH_PROOF_WINDOW, RPC_DEFAULT_GAS_CAP,
     },
     JwtError, JwtSecret,
 };
+use reth_rpc_api::DebugApiServer;
 use reth_rpc_builder::{
     auth::{AuthServerConfig, AuthServerHandle},
     constants,
@@ -156,6 +158,17 @@ pub struct RpcServerArgs {
     #[arg(long, value_name = "COUNT", default_value_t = constants::DEFAULT_MAX_TRACING_REQUESTS)]
     pub rpc_max_tracing_requests: u32,
 
+    /// Maximum number of blocks a `debug_traceChain` subscription traces concurrently.
+    #[arg(long, value_name = "COUNT", default_value_t = constants::DEFAULT_TRACE_CHAIN_WINDOW)]
+    pub rpc_trace_chain_window: usize,
+
+    /// Directory the `debug` profiling methods, e.g. `debug_cpuProfile`, write their files to.
+    ///
+    /// The files requested via RPC must be relative paths inside this directory. Defaults to
+    /// `<DIR>/<CHAIN_ID>/profiles`.
+    #[arg(long, value_name = "PATH")]
+    pub rpc_profile_dir: Option<PathBuf>,
+
     /// Maximum number of blocks that could be scanned per filter request. (0 = entire chain)
     #[arg(long, value_name = "COUNT", default_value_t = ZeroAsNoneU64::new(constants::DEFAULT_MAX_BLOCKS_PER_FILTER))]
     pub rpc_max_blocks_per_filter: ZeroAsNoneU64,
@@ -174,6 +187,13 @@ pub struct RpcServerArgs {
     )]
     pub rpc_gas_cap: u64,
 
+    /// Maximum number of blocks `eth_getProof` can look back from the latest block.
+    ///
+    /// Proofs for older blocks are generated by reverting the changesets of all blocks after
+    /// them, which gets more expensive the further back they are.
+    #[arg(long, value_name = "BLOCKS", default_value_t = DEFAULT_ETH_PROOF_WINDOW)]
+    pub rpc_eth_proof_window: u64,
+
     /// Gas price oracle configuration.
     #[clap(flatten)]
     pub gas_price_oracle: GasPriceOracleArgs,
@@ -197,11 +217,15 @@ impl RpcServerArgs {
     /// Returns the handles for the launched regular RPC server(s) (if any) and the server handle
     /// for the auth server that handles the `engine_` API that's accessed by the consensus
     /// layer.
+    ///
+    /// The auth server additionally serves `debug_setHead` with access to the consensus engine,
+    /// so that unwinding the chain requires authentication.
     #[allow(clippy::too_many_arguments)]
     pub async fn start_servers<Reth, Engine, Conf>(
         &self,
         components: &Reth,
         engine_api: Engine,
+        beacon_engine_handle: BeaconConsensusEngineHandle,
         jwt_secret: JwtSecret,
         conf: &mut Conf,
     ) -> eyre::Result<RethRpcServerHandles>
@@ -215,14 +239,20 @@ impl RpcServerArgs {
         let module_config = self.transport_rpc_module_config();
         debug!(target: "reth::cli", http=?module_config.http(), ws=?module_config.ws(), "Using RPC module config");
 
-        let (mut modules, auth_module, mut registry) = RpcModuleBuilder::default()
+        let (mut modules, mut auth_module, mut registry) = RpcModuleBuilder::default()
             .with_provider(components.provider())
             .with_pool(components.pool())
             .with_network(components.network())
             .with_events(components.events())
             .with_executor(components.task_executor())
+            .with_bundle_pool(components.bundle_pool())
             .build_with_auth_server(module_config, engine_api);
 
+        // only `debug_setHead` of the `debug_` namespace is served on the auth server
+        let set_head =
+            registry.debug_api().with_beacon_engine(beacon_engine_handle).into_set_head_rpc();
+        auth_module.module_mut().merge(set_head).expect("No conflicting methods");
+
         let rpc_components = RethRpcComponents { registry: &mut registry, modules: &mut modules };
         // apply configured customization
         conf.extend_rpc_modules(self, components, rpc_components)?;
@@ -275,6 +305,8 @@ impl RpcServerArgs {
             + EvmEnvProvider
             + ChainSpecProvider
             + ChangeSetReader
+            + HistoryReader
+            + BadBlockReader
             + Clone
             + Unpin
             + 'static,
@@ -346,9 +378,12 @@ impl RethRpcConfig for RpcServerArgs {
     fn eth_config(&self) -> EthConfig {
         EthConfig::default()
             .max_tracing_requests(self.rpc_max_tracing_requests)
+            .trace_chain_window(self.rpc_trace_chain_window)
+            .profile_dir(self.rpc_profile_dir.clone())
             .max_blocks_per_filter(self.rpc_max_blocks_per_filter.unwrap_or_max())
             .max_logs_per_response(self.rpc_max_logs_per_response.unwrap_or_max() as usize)
             .rpc_gas_cap(self.rpc_gas_cap)
+            .eth_proof_window(self.rpc_eth_proof_window)
             .gpo_config(self.gas_price_oracle_config())
     }
 
@@ -527,6 +562,17 @@ mod tests {
         assert!(args.is_err());
     }
 
+    #[test]
+    fn test_rpc_eth_proof_window() {
+        let args = CommandParser::<RpcServerArgs>::parse_from(["reth"]).args;
+        assert_eq!(args.eth_config().eth_proof_window, DEFAULT_ETH_PROOF_WINDOW);
+
+        let args =
+            CommandParser::<RpcServerArgs>::parse_from(["reth", "--rpc-eth-proof-window", "100"])
+                .args;
+        assert_eq!(args.eth_config().eth_proof_window, 100);
+    }
+
     #[test]
     fn test_rpc_server_args_parser() {
         let args =
@@ -2,6 +2,7 @@
 
 use clap::Args;
 use reth_transaction_pool::{
+    blobstore::{DiskFileBlobStoreConfig, OpenDiskFileBlobStore, DEFAULT_MAX_CACHED_BLOBS},
     PoolConfig, PriceBumpConfig, SubPoolLimit, DEFAULT_PRICE_BUMP, REPLACE_BLOB_PRICE_BUMP,
     TXPOOL_MAX_ACCOUNT_SLOTS_PER_SENDER, TXPOOL_SUBPOOL_MAX_SIZE_MB_DEFAULT,
     TXPOOL_SUBPOOL_MAX_TXS_DEFAULT,
@@ -42,6 +43,21 @@ pub struct TxPoolArgs {
     /// Price bump percentage to replace an already existing blob transaction
     #[arg(long = "blobpool.pricebump", help_heading = "TxPool", default_value_t = REPLACE_BLOB_PRICE_BUMP)]
     pub blob_transaction_price_bump: u128,
+
+    /// Keep blob sidecars in memory instead of storing them on disk.
+    ///
+    /// NOTE: sidecars kept in memory are lost on restart.
+    #[arg(long = "blobpool.in-memory", help_heading = "TxPool")]
+    pub blob_store_in_memory: bool,
+
+    /// Max number of blob sidecars kept in the read cache of the on-disk blob store.
+    #[arg(long = "blobpool.max-cached-entries", help_heading = "TxPool", default_value_t = DEFAULT_MAX_CACHED_BLOBS)]
+    pub blob_cache_max_entries: u32,
+
+    /// Delete the blob sidecars stored on disk by a previous run on startup, instead of keeping
+    /// them.
+    #[arg(long = "blobpool.clear-on-start", help_heading = "TxPool")]
+    pub blob_store_clear_on_start: bool,
 }
 
 impl TxPoolArgs {
@@ -67,4 +83,16 @@ impl TxPoolArgs {
             },
         }
     }
+
+    /// Returns the configuration of the on-disk blob store.
+    pub fn blob_store_config(&self) -> DiskFileBlobStoreConfig {
+        let open = if self.blob_store_clear_on_start {
+            OpenDiskFileBlobStore::Clear
+        } else {
+            OpenDiskFileBlobStore::ReIndex
+        };
+        DiskFileBlobStoreConfig::default()
+            .with_max_cached_entries(self.blob_cache_max_entries)
+            .with_open(open)
+    }
 }
@@ -2,10 +2,11 @@
 
 use reth_network::NetworkEvents;
 use reth_network_api::{NetworkInfo, Peers};
+use reth_payload_builder::bundle::BundlePool;
 use reth_primitives::ChainSpec;
 use reth_provider::{
-    AccountReader, BlockReaderIdExt, CanonStateSubscriptions, ChainSpecProvider, ChangeSetReader,
-    EvmEnvProvider, StateProviderFactory,
+    AccountReader, BadBlockReader, BlockReaderIdExt, CanonStateSubscriptions, ChainSpecProvider,
+    ChangeSetReader, EvmEnvProvider, HistoryReader, StateProviderFactory,
 };
 use reth_rpc_builder::{
     auth::AuthServerHandle, RethModuleRegistry, RpcServerHandle, TransportRpcModules,
@@ -22,6 +23,8 @@ pub trait FullProvider:
     + EvmEnvProvider
     + ChainSpecProvider
     + ChangeSetReader
+    + HistoryReader
+    + BadBlockReader
     + Clone
     + Unpin
     + 'static
@@ -35,6 +38,8 @@ impl<T> FullProvider for T where
         + EvmEnvProvider
         + ChainSpecProvider
         + ChangeSetReader
+        + HistoryReader
+        + BadBlockReader
         + Clone
         + Unpin
         + 'static
@@ -69,6 +74,10 @@ pub trait RethNodeComponents: Clone + Send + Sync + 'static {
     /// Returns the instance of the events subscription handler.
     fn events(&self) -> Self::Events;
 
+    /// Returns the pool of bundles that are sent via `eth_sendBundle` and included in built
+    /// payloads.
+    fn bundle_pool(&self) -> BundlePool;
+
     /// Helper function to return the chain spec.
     fn chain_spec(&self) -> Arc<ChainSpec> {
         self.provider().chain_spec()
@@ -109,6 +118,7 @@ pub struct RethNodeComponentsImpl<Provider, Pool, Network, Events, Tasks> {
     pub network: Network,
     pub task_executor: Tasks,
     pub events: Events,
+    pub bundle_pool: BundlePool,
 }
 
 impl<Provider, Pool, Network, Events, Tasks> RethNodeComponents
@@ -145,6 +155,10 @@ where
     fn events(&self) -> Self::Events {
         self.events.clone()
     }
+
+    fn bundle_pool(&self) -> BundlePool {
+        self.bundle_pool.clone()
+    }
 }
 
 /// Contains the handles to the spawned RPC servers.
@@ -128,10 +128,11 @@ pub trait RethNodeCommandConfig: fmt::Debug {
         let payload_job_config =
             payload_job_config.compute_pending_block(conf.compute_pending_block());
 
-        // The default payload builder is implemented on the unit type.
+        // The default payload builder includes the bundles sent via `eth_sendBundle` at the top of
+        // the block.
         #[cfg(not(feature = "optimism"))]
-        #[allow(clippy::let_unit_value)]
-        let payload_builder = reth_basic_payload_builder::EthereumPayloadBuilder::default();
+        let payload_builder =
+            reth_basic_payload_builder::EthereumBundlePayloadBuilder::new(components.bundle_pool());
 
         // Optimism's payload builder is implemented on the OptimismPayloadBuilder type.
         #[cfg(feature = "optimism")]
@@ -7,7 +7,7 @@ use crate::{
     dirs::{LogsDir, PlatformPath},
     node, p2p, recover,
     runner::CliRunner,
-    stage, test_vectors,
+    snapshot, stage, test_vectors,
     version::{LONG_VERSION, SHORT_VERSION},
 };
 use clap::{value_parser, ArgAction, Args, Parser, Subcommand, ValueEnum};
@@ -89,6 +89,7 @@ impl<Ext: RethCliExt> Cli<Ext> {
             Commands::Init(command) => runner.run_blocking_until_ctrl_c(command.execute()),
             Commands::Import(command) => runner.run_blocking_until_ctrl_c(command.execute()),
             Commands::Db(command) => runner.run_blocking_until_ctrl_c(command.execute()),
+            Commands::Snapshot(command) => runner.run_blocking_until_ctrl_c(command.execute()),
             Commands::Stage(command) => runner.run_blocking_until_ctrl_c(command.execute()),
             Commands::P2P(command) => runner.run_until_ctrl_c(command.execute()),
             Commands::TestVectors(command) => runner.run_until_ctrl_c(command.execute()),
@@ -144,6 +145,9 @@ pub enum Commands<Ext: RethCliExt = ()> {
     /// Database debugging utilities
     #[command(name = "db")]
     Db(db::Command),
+    /// Snapshot verification and import utilities
+    #[command(name = "snapshot")]
+    Snapshot(snapshot::Command),
     /// Manipulate individual stages.
     #[command(name = "stage")]
     Stage(stage::Command),
@@ -17,10 +17,11 @@ use clap::Parser;
 use reth_db::{
     cursor::DbCursorRO, database::Database, open_db_read_only, table::Table, transaction::DbTx,
     AccountChangeSet, AccountHistory, AccountsTrie, BlockBodyIndices, BlockOmmers,
-    BlockWithdrawals, Bytecodes, CanonicalHeaders, DatabaseEnvRO, HashedAccount, HashedStorage,
-    HeaderNumbers, HeaderTD, Headers, PlainAccountState, PlainStorageState, PruneCheckpoints,
-    Receipts, StorageChangeSet, StorageHistory, StoragesTrie, SyncStage, SyncStageProgress, Tables,
-    TransactionBlock, Transactions, TxHashNumber, TxSenders,
+    BlockTraceAddresses, BlockWithdrawals, Bytecodes, CanonicalHeaders, DatabaseEnvRO,
+    HashedAccount, HashedStorage, HeaderNumbers, HeaderTD, Headers, PlainAccountState,
+    PlainStorageState, PruneCheckpoints, Receipts, StorageChangeSet, StorageHistory, StoragesTrie,
+    SyncStage, SyncStageProgress, Tables, TraceAddressHistory, TransactionBlock, Transactions,
+    TxHashNumber, TxSenders,
 };
 use tracing::info;
 
@@ -140,6 +141,12 @@ impl Command {
                 Tables::PruneCheckpoints => {
                     find_diffs::<PruneCheckpoints>(primary_tx, secondary_tx, output_dir)?
                 }
+                Tables::TraceAddressHistory => {
+                    find_diffs::<TraceAddressHistory>(primary_tx, secondary_tx, output_dir)?
+                }
+                Tables::BlockTraceAddresses => {
+                    find_diffs::<BlockTraceAddresses>(primary_tx, secondary_tx, output_dir)?
+                }
             };
         }
 
@@ -1,10 +1,11 @@
 use reth_db::DatabaseEnvRO;
+use reth_nippy_jar::{NippyJar, NippyJarCursor};
 use reth_primitives::{
-    snapshot::{Compression, Filters},
+    snapshot::{Compression, Filters, SegmentHeader},
     ChainSpec, SnapshotSegment,
 };
 use reth_provider::{DatabaseProviderRO, ProviderFactory};
-use std::{fmt::Debug, sync::Arc, time::Instant};
+use std::{fmt::Debug, path::Path, sync::Arc, time::Instant};
 
 #[derive(Debug)]
 pub(crate) enum BenchKind {
@@ -55,3 +56,41 @@ where
 
     Ok(())
 }
+
+/// Prints the compression ratio of every column of the snapshot, and the throughput of decoding
+/// all of its rows.
+pub(crate) fn compression_report(
+    segment: SnapshotSegment,
+    filters: Filters,
+    compression: Compression,
+    path: &Path,
+) -> eyre::Result<()> {
+    let jar = NippyJar::<SegmentHeader>::load(path)?;
+    let compressed = jar.column_sizes()?;
+    let mut decompressed = vec![0u64; jar.columns()];
+
+    let mut cursor = NippyJarCursor::new(&jar)?;
+    let start = Instant::now();
+    while let Some(row) = cursor.next_row()? {
+        for (column, value) in row.iter().enumerate() {
+            decompressed[column] += value.len() as u64;
+        }
+    }
+    let elapsed = start.elapsed();
+
+    println!();
+    println!("############");
+    println!("## [{segment:?}] [{compression:?}] [{filters:?}] [Compression]");
+    for (column, (compressed, decompressed)) in compressed.iter().zip(&decompressed).enumerate() {
+        let ratio = *decompressed as f64 / (*compressed).max(1) as f64;
+        println!(
+            "# column {column} | {decompressed} bytes -> {compressed} bytes | ratio {ratio:.2}"
+        );
+    }
+
+    let decompressed = decompressed.iter().sum::<u64>();
+    let throughput = decompressed as f64 / elapsed.as_secs_f64().max(f64::EPSILON) / 1_000_000.0;
+    println!("# decode {} rows | {} μs | {throughput:.2} MB/s", jar.rows(), elapsed.as_micros());
+
+    Ok(())
+}
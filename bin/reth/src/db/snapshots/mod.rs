@@ -1,13 +1,14 @@
 use clap::Parser;
 use itertools::Itertools;
-use reth_db::{open_db_read_only, DatabaseEnvRO};
+use reth_db::{database::Database, open_db_read_only, DatabaseEnvRO};
 use reth_interfaces::db::LogLevel;
 use reth_primitives::{
-    snapshot::{Compression, InclusionFilter, PerfectHashingFunction},
+    snapshot::{Compression, Filters, InclusionFilter, PerfectHashingFunction},
     BlockNumber, ChainSpec, SnapshotSegment,
 };
-use reth_provider::ProviderFactory;
-use std::{path::Path, sync::Arc};
+use reth_provider::{DatabaseProviderRO, ProviderFactory};
+use reth_snapshot::{segments, segments::Segment};
+use std::{ops::RangeInclusive, path::Path, sync::Arc};
 
 mod bench;
 mod headers;
@@ -37,6 +38,9 @@ pub struct Command {
     only_bench: bool,
 
     /// Compression algorithms to use.
+    ///
+    /// `per-column` compresses every column with its own algorithm, e.g. headers with a zstd
+    /// dictionary and block hashes uncompressed.
     #[arg(long, short, value_delimiter = ',', default_value = "lz4")]
     compression: Vec<Compression>,
 
@@ -92,6 +96,16 @@ impl Command {
                                 InclusionFilter::Cuckoo,
                                 *phf,
                             )?,
+                        SnapshotSegment::AccountChangeSets => self
+                            .generate_changesets_snapshot::<DatabaseEnvRO>(
+                                &provider,
+                                segments::AccountChangeSets::new(*compression),
+                            )?,
+                        SnapshotSegment::StorageChangeSets => self
+                            .generate_changesets_snapshot::<DatabaseEnvRO>(
+                                &provider,
+                                segments::StorageChangeSets::new(*compression),
+                            )?,
                     }
                 }
             }
@@ -99,6 +113,19 @@ impl Command {
 
         if self.only_bench || self.bench {
             for ((mode, compression), phf) in all_combinations {
+                let filters =
+                    if self.with_filters && matches!(mode.config().0, Filters::WithFilters(..)) {
+                        Filters::WithFilters(InclusionFilter::Cuckoo, *phf)
+                    } else {
+                        Filters::WithoutFilters
+                    };
+                bench::compression_report(
+                    *mode,
+                    filters,
+                    *compression,
+                    &mode.filename_with_configuration(filters, *compression, &self.block_range()),
+                )?;
+
                 match mode {
                     SnapshotSegment::Headers => self.bench_headers_snapshot(
                         db_path,
@@ -124,10 +151,30 @@ impl Command {
                         InclusionFilter::Cuckoo,
                         *phf,
                     )?,
+                    SnapshotSegment::AccountChangeSets | SnapshotSegment::StorageChangeSets => {
+                        println!("# database benchmarks are not supported for {mode:?}")
+                    }
                 }
             }
         }
 
         Ok(())
     }
+
+    /// Generates a changeset snapshot. Changeset snapshots are only looked up by block number, so
+    /// they never have filters.
+    fn generate_changesets_snapshot<DB: Database>(
+        &self,
+        provider: &DatabaseProviderRO<'_, DB>,
+        segment: impl Segment,
+    ) -> eyre::Result<()> {
+        segment.snapshot::<DB>(provider, std::env::current_dir()?, self.block_range())?;
+
+        Ok(())
+    }
+
+    /// Returns the block range of the snapshots.
+    fn block_range(&self) -> RangeInclusive<BlockNumber> {
+        self.from..=(self.from + self.block_interval - 1)
+    }
 }
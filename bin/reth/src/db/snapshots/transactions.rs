@@ -32,7 +32,11 @@ impl Command {
                 Filters::WithoutFilters
             },
         );
-        segment.snapshot::<DB>(provider, self.from..=(self.from + self.block_interval - 1))?;
+        segment.snapshot::<DB>(
+            provider,
+            std::env::current_dir()?,
+            self.from..=(self.from + self.block_interval - 1),
+        )?;
 
         Ok(())
     }
@@ -26,8 +26,11 @@ use reth_interfaces::{
 };
 use reth_network::{NetworkEvents, NetworkHandle};
 use reth_network_api::NetworkInfo;
-use reth_primitives::{fs, stage::StageId, BlockHashOrNumber, BlockNumber, ChainSpec, B256};
+use reth_primitives::{
+    fs, stage::StageId, BlockHashOrNumber, BlockNumber, ChainSpec, TxHash, B256,
+};
 use reth_provider::{BlockExecutionWriter, ProviderFactory, StageCheckpointReader};
+use reth_revm_inspectors::stack::{Hook, InspectorStackConfig};
 use reth_stages::{
     sets::DefaultStages,
     stages::{
@@ -84,6 +87,16 @@ pub struct Command {
     /// Defaults to `1000`.
     #[arg(long, default_value = "1000")]
     pub interval: u64,
+
+    /// Write an EIP-3155 trace of every executed transaction to this directory.
+    ///
+    /// Every trace is written to a `<block_number>-<tx_hash>.jsonl` file.
+    #[arg(long, value_name = "DIR")]
+    pub eip3155_dir: Option<PathBuf>,
+
+    /// Only write the EIP-3155 trace of this transaction.
+    #[arg(long, value_name = "TX_HASH", requires = "eip3155_dir")]
+    pub eip3155_tx: Option<TxHash>,
 }
 
 impl Command {
@@ -111,7 +124,14 @@ impl Command {
         let stage_conf = &config.stages;
 
         let (tip_tx, tip_rx) = watch::channel(B256::ZERO);
-        let factory = reth_revm::Factory::new(self.chain.clone());
+        let mut factory = reth_revm::Factory::new(self.chain.clone());
+        if let Some(dir) = &self.eip3155_dir {
+            factory = factory.with_stack_config(InspectorStackConfig {
+                eip3155_dir: Some(dir.clone()),
+                hook: self.eip3155_tx.map(Hook::Transaction).unwrap_or(Hook::All),
+                ..Default::default()
+            });
+        }
 
         let header_mode = HeaderSyncMode::Tip(tip_rx);
         let pipeline = Pipeline::builder()
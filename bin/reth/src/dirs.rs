@@ -293,6 +293,34 @@ impl<D> ChainPath<D> {
     pub fn jwt_path(&self) -> PathBuf {
         self.0.join("jwt.hex").into()
     }
+
+    /// Returns the path to the blob store directory for this chain.
+    ///
+    /// `<DIR>/<CHAIN_ID>/blobstore`
+    pub fn blobstore_path(&self) -> PathBuf {
+        self.0.join("blobstore").into()
+    }
+
+    /// Returns the path to the snapshots directory for this chain.
+    ///
+    /// `<DIR>/<CHAIN_ID>/snapshots`
+    pub fn snapshots_path(&self) -> PathBuf {
+        self.0.join("snapshots").into()
+    }
+
+    /// Returns the path to the directory rejected blocks are dumped to for this chain.
+    ///
+    /// `<DIR>/<CHAIN_ID>/bad-blocks`
+    pub fn bad_blocks_path(&self) -> PathBuf {
+        self.0.join("bad-blocks").into()
+    }
+
+    /// Returns the path to the directory the `debug` profiling methods write to for this chain.
+    ///
+    /// `<DIR>/<CHAIN_ID>/profiles`
+    pub fn profiles_path(&self) -> PathBuf {
+        self.0.join("profiles").into()
+    }
 }
 
 impl<D> AsRef<Path> for ChainPath<D> {
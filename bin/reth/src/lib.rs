@@ -8,7 +8,7 @@
 //! - `jemalloc-prof`: Enables [jemallocator's](https://github.com/tikv/jemallocator) heap profiling
 //!   and leak detection functionality. See [jemalloc's opt.prof](https://jemalloc.net/jemalloc.3.html#opt.prof)
 //!   documentation for usage details. This is **not recommended on Windows**. See [here](https://rust-lang.github.io/rfcs/1974-global-allocators.html#jemalloc)
-//!   for more info.
+//!   for more info. Heap profiles can be dumped at runtime with `debug_writeMemProfile`.
 //! - `min-error-logs`: Disables all logs below `error` level.
 //! - `min-warn-logs`: Disables all logs below `warn` level.
 //! - `min-info-logs`: Disables all logs below `info` level. This can speed up the node, since fewer
@@ -41,6 +41,7 @@ pub mod p2p;
 pub mod prometheus_exporter;
 pub mod recover;
 pub mod runner;
+pub mod snapshot;
 pub mod stage;
 pub mod test_vectors;
 pub mod utils;
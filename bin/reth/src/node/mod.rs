@@ -28,7 +28,7 @@ use futures::{future::Either, pin_mut, stream, stream_select, StreamExt};
 use metrics_exporter_prometheus::PrometheusHandle;
 use reth_auto_seal_consensus::{AutoSealBuilder, AutoSealConsensus, MiningMode};
 use reth_beacon_consensus::{
-    hooks::{EngineHooks, PruneHook},
+    hooks::{EngineHooks, PruneHook, SnapshotHook, TraceIndexHook},
     BeaconConsensus, BeaconConsensusEngine, MIN_BLOCKS_FOR_PIPELINE_RUN,
 };
 use reth_blockchain_tree::{
@@ -50,18 +50,24 @@ use reth_interfaces::{
     RethResult,
 };
 use reth_network::{
-    error::NetworkError, NetworkConfig, NetworkEvents, NetworkHandle, NetworkManager,
+    error::NetworkError,
+    snap::{
+        SnapFetchClient, SnapProtocolHandler, SnapRequestHandler, SNAP_REQUEST_CHANNEL_CAPACITY,
+    },
+    NetworkConfig, NetworkEvents, NetworkHandle, NetworkManager,
 };
 use reth_network_api::{NetworkInfo, PeersInfo};
 use reth_primitives::{
     constants::eip4844::{LoadKzgSettingsError, MAINNET_KZG_TRUSTED_SETUP},
     kzg::KzgSettings,
+    snapshot::HighestSnapshots,
     stage::StageId,
-    BlockHashOrNumber, BlockNumber, ChainSpec, DisplayHardforks, Head, SealedHeader, B256,
+    BlockHashOrNumber, BlockNumber, ChainSpec, DisplayHardforks, Head, PruneModes, SealedHeader,
+    B256,
 };
 use reth_provider::{
-    providers::BlockchainProvider, BlockHashReader, BlockReader, CanonStateSubscriptions,
-    HeaderProvider, ProviderFactory, StageCheckpointReader,
+    providers::BlockchainProvider, BadBlockStore, BlockHashReader, BlockReader,
+    CanonStateSubscriptions, HeaderProvider, ProviderFactory, StageCheckpointReader,
 };
 use reth_prune::{segments::SegmentSet, Pruner};
 use reth_revm::Factory;
@@ -72,13 +78,15 @@ use reth_stages::{
     prelude::*,
     stages::{
         AccountHashingStage, ExecutionStage, ExecutionStageThresholds, HeaderSyncMode,
-        IndexAccountHistoryStage, IndexStorageHistoryStage, MerkleStage, SenderRecoveryStage,
-        StorageHashingStage, TotalDifficultyStage, TransactionLookupStage,
+        IndexAccountHistoryStage, IndexStorageHistoryStage, IndexTraceAddressesStage, MerkleStage,
+        SenderRecoveryStage, SnapSyncStage, StorageHashingStage, TotalDifficultyStage,
+        TransactionLookupStage,
     },
 };
 use reth_tasks::TaskExecutor;
 use reth_transaction_pool::{
-    blobstore::InMemoryBlobStore, TransactionPool, TransactionValidationTaskExecutor,
+    blobstore::{BlobStore, DiskFileBlobStore, InMemoryBlobStore},
+    TransactionPool, TransactionValidationTaskExecutor,
 };
 use secp256k1::SecretKey;
 use std::{
@@ -86,7 +94,10 @@ use std::{
     path::PathBuf,
     sync::Arc,
 };
-use tokio::sync::{mpsc::unbounded_channel, oneshot, watch};
+use tokio::sync::{
+    mpsc::{self, unbounded_channel},
+    oneshot, watch,
+};
 use tracing::*;
 
 pub mod cl_events;
@@ -149,6 +160,12 @@ pub struct NodeCommand<Ext: RethCliExt = ()> {
     #[arg(long, value_name = "PATH")]
     pub trusted_setup_file: Option<PathBuf>,
 
+    /// Move finalized headers, transactions, receipts and changesets into snapshot files.
+    ///
+    /// Snapshotted data is deleted from the database once it can be served from the snapshots.
+    #[arg(long, default_value_t = false)]
+    pub snapshots: bool,
+
     /// All networking related arguments
     #[clap(flatten)]
     pub network: NetworkArgs,
@@ -200,6 +217,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
             chain,
             metrics,
             trusted_setup_file,
+            snapshots,
             instance,
             network,
             rpc,
@@ -220,6 +238,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
             metrics,
             instance,
             trusted_setup_file,
+            snapshots,
             network,
             rpc,
             txpool,
@@ -278,6 +297,12 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
         let prune_config =
             self.pruning.prune_config(Arc::clone(&self.chain))?.or(config.prune.clone());
 
+        // recently rejected blocks, shared by the tree, the engine and the rpc
+        let mut bad_blocks = BadBlockStore::default();
+        if self.debug.dump_bad_blocks {
+            bad_blocks = bad_blocks.with_dump_dir(data_dir.bad_blocks_path());
+        }
+
         // configure blockchain tree
         let tree_externals = TreeExternals::new(
             Arc::clone(&db),
@@ -290,7 +315,8 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
             BlockchainTreeConfig::default(),
             prune_config.clone().map(|config| config.segments),
         )?
-        .with_sync_metrics_tx(sync_metrics_tx.clone());
+        .with_sync_metrics_tx(sync_metrics_tx.clone())
+        .with_bad_block_store(bad_blocks.clone());
         let canon_state_notification_sender = tree.canon_state_notification_sender();
         let blockchain_tree = ShareableBlockchainTree::new(tree);
         debug!(target: "reth::cli", "configured blockchain tree");
@@ -298,10 +324,21 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
         // fetch the head block from the database
         let head = self.lookup_head(Arc::clone(&db)).wrap_err("the head block is missing")?;
 
+        let (highest_snapshots_tx, highest_snapshots_rx) = watch::channel(None);
+
         // setup the blockchain provider
-        let factory = ProviderFactory::new(Arc::clone(&db), Arc::clone(&self.chain));
-        let blockchain_db = BlockchainProvider::new(factory, blockchain_tree.clone())?;
-        let blob_store = InMemoryBlobStore::default();
+        let factory = ProviderFactory::new(Arc::clone(&db), Arc::clone(&self.chain))
+            .with_snapshots(data_dir.snapshots_path(), Some(highest_snapshots_rx.clone()))?;
+        let blockchain_db = BlockchainProvider::new(factory, blockchain_tree.clone())?
+            .with_bad_block_store(bad_blocks.clone());
+        let blob_store: Arc<dyn BlobStore> = if self.txpool.blob_store_in_memory {
+            Arc::new(InMemoryBlobStore::default())
+        } else {
+            Arc::new(DiskFileBlobStore::open(
+                data_dir.blobstore_path(),
+                self.txpool.blob_store_config(),
+            )?)
+        };
         let validator = TransactionValidationTaskExecutor::eth_builder(Arc::clone(&self.chain))
             .with_head_timestamp(head.timestamp)
             .kzg_settings(self.kzg_settings()?)
@@ -336,7 +373,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
         debug!(target: "reth::cli", ?network_secret_path, "Loading p2p key file");
         let secret_key = get_secret_key(&network_secret_path)?;
         let default_peers_path = data_dir.known_peers_path();
-        let network_config = self.load_network_config(
+        let (network_config, snap_protocol) = self.load_network_config(
             &config,
             Arc::clone(&db),
             ctx.task_executor.clone(),
@@ -355,6 +392,9 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
         info!(target: "reth::cli", peer_id = %network.peer_id(), local_addr = %network.local_addr(), enode = %network.local_node_record(), "Connected to P2P network");
         debug!(target: "reth::cli", peer_id = ?network.peer_id(), "Full peer ID");
         let network_client = network.fetch_client().await?;
+        let snap_client = snap_protocol
+            .filter(|_| config.stages.snap_sync.enabled)
+            .map(|snap| snap.client(network.peers_handle().clone()));
 
         let components = RethNodeComponentsImpl {
             provider: blockchain_db.clone(),
@@ -362,6 +402,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
             network: network.clone(),
             task_executor: ctx.task_executor.clone(),
             events: blockchain_db.clone(),
+            bundle_pool: Default::default(),
         };
         self.ext.on_components_initialized(&components)?;
 
@@ -413,6 +454,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
                     sync_metrics_tx,
                     prune_config.clone(),
                     max_block,
+                    None,
                 )
                 .await?;
 
@@ -433,6 +475,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
                     sync_metrics_tx,
                     prune_config.clone(),
                     max_block,
+                    snap_client,
                 )
                 .await?;
 
@@ -454,12 +497,13 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
             None
         };
 
-        let (highest_snapshots_tx, highest_snapshots_rx) = watch::channel(None);
-
         let mut hooks = EngineHooks::new();
 
+        let prune_modes = prune_config.as_ref().map(|config| config.segments.clone());
         let pruner_events = if let Some(prune_config) = prune_config {
-            let mut pruner = self.build_pruner(&prune_config, db.clone(), highest_snapshots_rx);
+            let mut pruner = self
+                .build_pruner(&prune_config, db.clone(), highest_snapshots_rx.clone())
+                .with_snapshots(data_dir.snapshots_path())?;
 
             let events = pruner.events();
             hooks.add(PruneHook::new(pruner, Box::new(ctx.task_executor.clone())));
@@ -470,12 +514,33 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
             Either::Right(stream::empty())
         };
 
-        let _snapshotter = reth_snapshot::Snapshotter::new(
-            db,
-            self.chain.clone(),
-            self.chain.snapshot_block_interval,
-            highest_snapshots_tx,
-        );
+        if config.stages.index_trace_addresses.enabled {
+            let provider_factory = ProviderFactory::new(db.clone(), self.chain.clone())
+                .with_snapshots(data_dir.snapshots_path(), Some(highest_snapshots_rx))?;
+            hooks.add(TraceIndexHook::new(
+                provider_factory,
+                self.chain.clone(),
+                Box::new(ctx.task_executor.clone()),
+            ));
+            info!(target: "reth::cli", "Trace address indexer initialized");
+        }
+
+        if self.snapshots {
+            let snapshotter = reth_snapshot::Snapshotter::new(
+                db,
+                data_dir.snapshots_path(),
+                self.chain.clone(),
+                self.chain.snapshot_block_interval,
+                highest_snapshots_tx,
+            )?
+            .with_prune_modes(prune_modes.unwrap_or_else(PruneModes::none));
+            hooks.add(SnapshotHook::new(snapshotter, Box::new(ctx.task_executor.clone())));
+            info!(target: "reth::cli", path = ?data_dir.snapshots_path(), "Snapshotter initialized");
+        } else if data_dir.snapshots_path().exists() {
+            // Snapshots are not produced, but existing ones (e.g. imported) are still served.
+            let highest_snapshots = HighestSnapshots::from_directory(data_dir.snapshots_path())?;
+            highest_snapshots_tx.send_replace(Some(highest_snapshots));
+        }
 
         // Configure the consensus engine
         let (beacon_consensus_engine, beacon_engine_handle) = BeaconConsensusEngine::with_channel(
@@ -493,6 +558,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
             consensus_engine_rx,
             hooks,
         )?;
+        let beacon_consensus_engine = beacon_consensus_engine.with_bad_block_store(bad_blocks);
         info!(target: "reth::cli", "Consensus engine initialized");
 
         let events = stream_select!(
@@ -517,7 +583,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
         let engine_api = EngineApi::new(
             blockchain_db.clone(),
             self.chain.clone(),
-            beacon_engine_handle,
+            beacon_engine_handle.clone(),
             payload_builder.into(),
             Box::new(ctx.task_executor.clone()),
         );
@@ -529,10 +595,13 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
 
         // adjust rpc port numbers based on instance number
         self.adjust_instance_ports();
+        self.rpc.rpc_profile_dir.get_or_insert_with(|| data_dir.profiles_path());
 
         // Start RPC servers
-        let _rpc_server_handles =
-            self.rpc.start_servers(&components, engine_api, jwt_secret, &mut self.ext).await?;
+        let _rpc_server_handles = self
+            .rpc
+            .start_servers(&components, engine_api, beacon_engine_handle, jwt_secret, &mut self.ext)
+            .await?;
 
         // Run consensus engine to completion
         let (tx, rx) = oneshot::channel();
@@ -602,6 +671,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
         metrics_tx: reth_stages::MetricEventsSender,
         prune_config: Option<PruneConfig>,
         max_block: Option<BlockNumber>,
+        snap_client: Option<SnapFetchClient>,
     ) -> eyre::Result<Pipeline<DB>>
     where
         DB: Database + Unpin + Clone + 'static,
@@ -627,6 +697,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
                 self.debug.continuous,
                 metrics_tx,
                 prune_config,
+                snap_client,
             )
             .await?;
 
@@ -809,11 +880,11 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
         head: Head,
         secret_key: SecretKey,
         default_peers_path: PathBuf,
-    ) -> NetworkConfig<ProviderFactory<Arc<DatabaseEnv>>> {
-        let cfg_builder = self
+    ) -> (NetworkConfig<ProviderFactory<Arc<DatabaseEnv>>>, Option<SnapProtocolHandler>) {
+        let mut cfg_builder = self
             .network
             .network_config(config, self.chain.clone(), secret_key, default_peers_path)
-            .with_task_executor(Box::new(executor))
+            .with_task_executor(Box::new(executor.clone()))
             .set_head(head)
             .listener_addr(SocketAddr::V4(SocketAddrV4::new(
                 self.network.addr,
@@ -826,6 +897,22 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
                 self.network.port + self.instance - 1,
             )));
 
+        // the protocol is also needed to request state for snap sync
+        let mut snap_protocol = None;
+        if self.network.serve_snap || config.stages.snap_sync.enabled {
+            let (tx, rx) = mpsc::channel(SNAP_REQUEST_CHANNEL_CAPACITY);
+            if self.network.serve_snap {
+                let snap_request_handler = SnapRequestHandler::new(
+                    ProviderFactory::new(db.clone(), self.chain.clone()),
+                    rx,
+                );
+                executor.spawn_critical_blocking("p2p snap request handler", snap_request_handler);
+            }
+            let snap = SnapProtocolHandler::new(tx);
+            cfg_builder = cfg_builder.add_rlpx_sub_protocol(snap.clone());
+            snap_protocol = Some(snap);
+        }
+
         // When `sequencer_endpoint` is configured, the node will forward all transactions to a
         // Sequencer node for execution and inclusion on L1, and disable its own txpool
         // gossip to prevent other parties in the network from learning about them.
@@ -834,7 +921,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
             .sequencer_endpoint(self.rollup.sequencer_http.clone())
             .disable_tx_gossip(self.rollup.disable_txpool_gossip);
 
-        cfg_builder.build(ProviderFactory::new(db, self.chain.clone()))
+        (cfg_builder.build(ProviderFactory::new(db, self.chain.clone())), snap_protocol)
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -849,6 +936,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
         continuous: bool,
         metrics_tx: reth_stages::MetricEventsSender,
         prune_config: Option<PruneConfig>,
+        snap_client: Option<SnapFetchClient>,
     ) -> eyre::Result<Pipeline<DB>>
     where
         DB: Database + Clone + 'static,
@@ -879,6 +967,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
             } else {
                 Hook::None
             },
+            eip3155_dir: None,
         };
 
         let factory = factory.with_stack_config(stack_config);
@@ -887,63 +976,77 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
 
         let header_mode =
             if continuous { HeaderSyncMode::Continuous } else { HeaderSyncMode::Tip(tip_rx) };
+        let mut stages = DefaultStages::new(
+            header_mode,
+            Arc::clone(&consensus),
+            header_downloader,
+            body_downloader,
+            factory.clone(),
+        )
+        .set(
+            TotalDifficultyStage::new(consensus)
+                .with_commit_threshold(stage_config.total_difficulty.commit_threshold),
+        )
+        .set(SenderRecoveryStage {
+            commit_threshold: stage_config.sender_recovery.commit_threshold,
+        })
+        .set(
+            ExecutionStage::new(
+                factory,
+                ExecutionStageThresholds {
+                    max_blocks: stage_config.execution.max_blocks,
+                    max_changes: stage_config.execution.max_changes,
+                    max_cumulative_gas: stage_config.execution.max_cumulative_gas,
+                },
+                stage_config
+                    .merkle
+                    .clean_threshold
+                    .max(stage_config.account_hashing.clean_threshold)
+                    .max(stage_config.storage_hashing.clean_threshold),
+                prune_modes.clone(),
+            )
+            .with_metrics_tx(metrics_tx),
+        )
+        .set(AccountHashingStage::new(
+            stage_config.account_hashing.clean_threshold,
+            stage_config.account_hashing.commit_threshold,
+        ))
+        .set(StorageHashingStage::new(
+            stage_config.storage_hashing.clean_threshold,
+            stage_config.storage_hashing.commit_threshold,
+        ))
+        .set(MerkleStage::new_execution(stage_config.merkle.clean_threshold))
+        .set(TransactionLookupStage::new(
+            stage_config.transaction_lookup.commit_threshold,
+            prune_modes.transaction_lookup,
+        ))
+        .set(IndexAccountHistoryStage::new(
+            stage_config.index_account_history.commit_threshold,
+            prune_modes.account_history,
+        ))
+        .set(IndexStorageHistoryStage::new(
+            stage_config.index_storage_history.commit_threshold,
+            prune_modes.storage_history,
+        ))
+        .add_before(
+            IndexTraceAddressesStage::new(
+                self.chain.clone(),
+                stage_config.index_trace_addresses.commit_threshold,
+            ),
+            StageId::Finish,
+        )
+        .disable_if(StageId::IndexTraceAddresses, || !stage_config.index_trace_addresses.enabled);
+        if let Some(client) = snap_client {
+            stages = stages.add_before(
+                SnapSyncStage::new(client, stage_config.snap_sync.commit_threshold),
+                StageId::Execution,
+            );
+        }
+
         let pipeline = builder
             .with_tip_sender(tip_tx)
             .with_metrics_tx(metrics_tx.clone())
-            .add_stages(
-                DefaultStages::new(
-                    header_mode,
-                    Arc::clone(&consensus),
-                    header_downloader,
-                    body_downloader,
-                    factory.clone(),
-                )
-                .set(
-                    TotalDifficultyStage::new(consensus)
-                        .with_commit_threshold(stage_config.total_difficulty.commit_threshold),
-                )
-                .set(SenderRecoveryStage {
-                    commit_threshold: stage_config.sender_recovery.commit_threshold,
-                })
-                .set(
-                    ExecutionStage::new(
-                        factory,
-                        ExecutionStageThresholds {
-                            max_blocks: stage_config.execution.max_blocks,
-                            max_changes: stage_config.execution.max_changes,
-                            max_cumulative_gas: stage_config.execution.max_cumulative_gas,
-                        },
-                        stage_config
-                            .merkle
-                            .clean_threshold
-                            .max(stage_config.account_hashing.clean_threshold)
-                            .max(stage_config.storage_hashing.clean_threshold),
-                        prune_modes.clone(),
-                    )
-                    .with_metrics_tx(metrics_tx),
-                )
-                .set(AccountHashingStage::new(
-                    stage_config.account_hashing.clean_threshold,
-                    stage_config.account_hashing.commit_threshold,
-                ))
-                .set(StorageHashingStage::new(
-                    stage_config.storage_hashing.clean_threshold,
-                    stage_config.storage_hashing.commit_threshold,
-                ))
-                .set(MerkleStage::new_execution(stage_config.merkle.clean_threshold))
-                .set(TransactionLookupStage::new(
-                    stage_config.transaction_lookup.commit_threshold,
-                    prune_modes.transaction_lookup,
-                ))
-                .set(IndexAccountHistoryStage::new(
-                    stage_config.index_account_history.commit_threshold,
-                    prune_modes.account_history,
-                ))
-                .set(IndexStorageHistoryStage::new(
-                    stage_config.index_storage_history.commit_threshold,
-                    prune_modes.storage_history,
-                )),
-            )
+            .add_stages(stages)
             .build(db, self.chain.clone());
 
         Ok(pipeline)
@@ -1113,6 +1216,15 @@ mod tests {
         assert_eq!(cmd.metrics, Some(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 9001)));
     }
 
+    #[test]
+    fn parse_snapshots() {
+        let cmd = NodeCommand::<()>::try_parse_from(["reth"]).unwrap();
+        assert!(!cmd.snapshots);
+
+        let cmd = NodeCommand::<()>::try_parse_from(["reth", "--snapshots"]).unwrap();
+        assert!(cmd.snapshots);
+    }
+
     #[test]
     fn parse_config_path() {
         let cmd = NodeCommand::<()>::try_parse_from(["reth", "--config", "my/path/to/reth.toml"])
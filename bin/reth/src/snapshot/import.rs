@@ -0,0 +1,513 @@
+//! `reth snapshot import` command.
+use super::{
+    body_snapshot_pairs, sort_snapshot_files,
+    verify::{verify_bodies, verify_snapshot, VerifyAgainst},
+};
+use crate::{
+    args::{
+        utils::{chain_help, genesis_value_parser, SUPPORTED_CHAINS},
+        DatabaseArgs,
+    },
+    dirs::{ChainPath, DataDirPath, MaybePlatformPath},
+    init::init_genesis,
+};
+use clap::Parser;
+use eyre::{ensure, eyre};
+use reth_db::{
+    codecs::CompactU256, database::Database, init_db, models::StoredBlockBodyIndices,
+    table::Decompress, tables, transaction::DbTxMut, DatabaseEnv,
+};
+use reth_interfaces::db::LogLevel;
+use reth_nippy_jar::{NippyJar, NippyJarCursor};
+use reth_primitives::{
+    snapshot::{HighestSnapshots, SegmentHeader},
+    stage::{StageCheckpoint, StageId},
+    BlockNumber, ChainSpec, Header, SnapshotSegment, TransactionSignedNoHash, B256,
+};
+use reth_provider::{
+    DatabaseProviderRW, ProviderFactory, StageCheckpointReader, StageCheckpointWriter,
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tracing::info;
+
+/// `reth snapshot import` command
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// The path to the data dir for all reth files and subdirectories.
+    ///
+    /// Defaults to the OS-specific data directory:
+    ///
+    /// - Linux: `$XDG_DATA_HOME/reth/` or `$HOME/.local/share/reth/`
+    /// - Windows: `{FOLDERID_RoamingAppData}/reth/`
+    /// - macOS: `$HOME/Library/Application Support/reth/`
+    #[arg(long, value_name = "DATA_DIR", verbatim_doc_comment, default_value_t)]
+    datadir: MaybePlatformPath<DataDirPath>,
+
+    /// The chain this node is running.
+    ///
+    /// Possible values are either a built-in chain or the path to a chain specification file.
+    #[arg(
+        long,
+        value_name = "CHAIN_OR_PATH",
+        long_help = chain_help(),
+        default_value = SUPPORTED_CHAINS[0],
+        value_parser = genesis_value_parser
+    )]
+    chain: Arc<ChainSpec>,
+
+    #[clap(flatten)]
+    db: DatabaseArgs,
+
+    /// What to verify the content of the snapshots against before importing them.
+    ///
+    /// Snapshots verified against the canonical chain bootstrap the datadir: the database is
+    /// initialized if needed, and the indices and stage checkpoints of the imported blocks are
+    /// written to it. Transaction and receipt snapshots have to be imported together, for the
+    /// same block range, after the header snapshots of their blocks.
+    ///
+    /// Snapshots verified against the database only have to be installed, since the database
+    /// already holds their blocks.
+    #[arg(long, value_enum, default_value_t = VerifyAgainst::Canonical)]
+    against: VerifyAgainst,
+
+    /// Snapshot data files to import. Auxiliary files of the jars are expected next to them.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+}
+
+impl Command {
+    /// Execute `snapshot import` command
+    pub async fn execute(self) -> eyre::Result<()> {
+        let data_dir = self.datadir.unwrap_or_chain_default(self.chain.chain);
+        import_snapshots(&data_dir, self.chain, self.db.log_level, self.against, self.files)
+    }
+}
+
+/// Verifies the snapshot files and installs them into the snapshots directory of the datadir.
+///
+/// When importing against the canonical chain, the following is written to the database for the
+/// imported blocks, so that a node started on the datadir continues syncing after them:
+/// - headers: the [`HeaderNumbers`][tables::HeaderNumbers], [`HeaderTD`][tables::HeaderTD] and
+///   [`CanonicalHeaders`][tables::CanonicalHeaders] entries, the header of the last block, and the
+///   checkpoints of the headers and total difficulty stages.
+/// - transactions and receipts: the [`BlockBodyIndices`][tables::BlockBodyIndices],
+///   [`TransactionBlock`][tables::TransactionBlock], [`TxHashNumber`][tables::TxHashNumber] and
+///   [`TxSenders`][tables::TxSenders] entries, and the checkpoints of the bodies, sender recovery
+///   and transaction lookup stages. These stages read the transactions from the database, which
+///   doesn't hold the imported ones.
+///
+/// Snapshots don't hold any state, so the node still executes the imported blocks, without having
+/// to download them.
+fn import_snapshots<D>(
+    data_dir: &ChainPath<D>,
+    chain: Arc<ChainSpec>,
+    log_level: Option<LogLevel>,
+    against: VerifyAgainst,
+    files: Vec<PathBuf>,
+) -> eyre::Result<()> {
+    let snapshots_path = data_dir.snapshots_path();
+    reth_primitives::fs::create_dir_all(&snapshots_path)?;
+
+    // A fresh datadir is initialized with the genesis block, like on the first start of the node
+    let db = Arc::new(init_db(data_dir.db_path(), log_level)?);
+    init_genesis(db.clone(), chain.clone())?;
+
+    let files = sort_snapshot_files(files)?;
+    let body_pairs = body_snapshot_pairs(&files)?;
+    let mut highest_snapshots = HighestSnapshots::from_directory(&snapshots_path)?;
+    let mut anchors = HashMap::new();
+    for (path, segment) in &files {
+        // Receipts are imported together with the transactions of the same block range
+        let receipts = if against == VerifyAgainst::Canonical {
+            match segment {
+                SnapshotSegment::Transactions => Some(
+                    body_pairs
+                        .iter()
+                        .find(|(transactions, _)| *transactions == path.as_path())
+                        .map(|(_, receipts)| *receipts)
+                        .ok_or_else(|| {
+                            eyre!("{}: no receipt snapshot of the same blocks", path.display())
+                        })?,
+                ),
+                SnapshotSegment::Receipts
+                    if body_pairs.iter().any(|(_, receipts)| *receipts == path.as_path()) =>
+                {
+                    continue
+                }
+                SnapshotSegment::Receipts => {
+                    return Err(eyre!(
+                        "{}: no transaction snapshot of the same blocks",
+                        path.display()
+                    ))
+                }
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        // The factory serves the snapshots that were imported so far
+        let factory = ProviderFactory::new(db.clone(), chain.clone())
+            .with_snapshots(&snapshots_path, None)?;
+        let provider = factory.provider()?;
+
+        let mut imported = Vec::new();
+        for path in std::iter::once(path.as_path()).chain(receipts) {
+            let verified = verify_snapshot::<Arc<DatabaseEnv>>(
+                path,
+                &chain,
+                Some(&provider),
+                against,
+                &mut anchors,
+            )
+            .map_err(|err| eyre!("{}: {err}", path.display()))?;
+
+            // Snapshots of a segment have to be imported without gaps, so that every block up to
+            // the highest snapshot is served by one.
+            let next_block =
+                highest_snapshots.highest(verified.segment).map_or(0, |highest| highest + 1);
+            ensure!(
+                *verified.block_range.start() == next_block,
+                "{}: expected {:?} snapshot starting at block {next_block}",
+                path.display(),
+                verified.segment
+            );
+            imported.push((path, verified));
+        }
+        let bodies = receipts
+            .map(|receipts| verify_bodies::<Arc<DatabaseEnv>>(path, receipts, &chain, &provider))
+            .transpose()
+            .map_err(|err| eyre!("{}: {err}", path.display()))?;
+        drop(provider);
+
+        for (path, verified) in &imported {
+            copy_snapshot(path, &snapshots_path)?;
+            *highest_snapshots.as_mut(verified.segment) = Some(*verified.block_range.end());
+        }
+
+        if against == VerifyAgainst::Canonical {
+            let provider = factory.provider_rw()?;
+            match bodies {
+                Some(bodies) => {
+                    write_body_indices(&provider, &bodies)?;
+                    write_transaction_indices(&provider, path, &bodies)?;
+                }
+                None => write_header_indices(&provider, path)?,
+            }
+            provider.commit()?;
+        }
+
+        for (path, verified) in imported {
+            info!(
+                target: "reth::cli",
+                path = %path.display(),
+                segment = ?verified.segment,
+                block_range = ?verified.block_range,
+                rows = verified.rows,
+                "Snapshot imported"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the indices of the headers of the snapshot to the database, and advances the headers
+/// and total difficulty stages to its last block.
+fn write_header_indices<DB: Database>(
+    provider: &DatabaseProviderRW<'_, DB>,
+    path: &Path,
+) -> eyre::Result<()> {
+    let jar = NippyJar::<SegmentHeader>::load(path)?;
+    let mut cursor = NippyJarCursor::new(&jar)?;
+    let tx = provider.tx_ref();
+
+    let mut last = None;
+    while let Some(row) = cursor.next_row()? {
+        let header = Header::decompress(row[0])?;
+        let td = CompactU256::decompress(row[1])?;
+        let hash = B256::decompress(row[2])?;
+
+        tx.put::<tables::CanonicalHeaders>(header.number, hash)?;
+        tx.put::<tables::HeaderNumbers>(hash, header.number)?;
+        tx.put::<tables::HeaderTD>(header.number, td)?;
+        last = Some(header);
+    }
+
+    // The headers stage continues from the header of its checkpoint, which it reads from the
+    // database
+    if let Some(last) = last {
+        let number = last.number;
+        tx.put::<tables::Headers>(number, last)?;
+        for stage_id in [StageId::Headers, StageId::TotalDifficulty] {
+            advance_stage_checkpoint(provider, stage_id, number)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the body indices of the blocks to the database, and advances the bodies stage to the
+/// last block.
+fn write_body_indices<DB: Database>(
+    provider: &DatabaseProviderRW<'_, DB>,
+    bodies: &[(BlockNumber, StoredBlockBodyIndices)],
+) -> eyre::Result<()> {
+    let tx = provider.tx_ref();
+    for (number, body) in bodies {
+        tx.put::<tables::BlockBodyIndices>(*number, body.clone())?;
+        if !body.is_empty() {
+            tx.put::<tables::TransactionBlock>(body.last_tx_num(), *number)?;
+        }
+    }
+
+    if let Some((number, _)) = bodies.last() {
+        advance_stage_checkpoint(provider, StageId::Bodies, *number)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the hash index and the senders of the transactions of the snapshot to the database, and
+/// advances the sender recovery and transaction lookup stages to the last block.
+fn write_transaction_indices<DB: Database>(
+    provider: &DatabaseProviderRW<'_, DB>,
+    path: &Path,
+    bodies: &[(BlockNumber, StoredBlockBodyIndices)],
+) -> eyre::Result<()> {
+    let jar = NippyJar::<SegmentHeader>::load(path)?;
+    let mut cursor = NippyJarCursor::new(&jar)?;
+    let tx = provider.tx_ref();
+
+    let mut tx_num = jar.user_header().tx_start();
+    while let Some(row) = cursor.next_row()? {
+        let transaction = TransactionSignedNoHash::decompress(row[0])?;
+        let sender = transaction
+            .recover_signer()
+            .ok_or_else(|| eyre!("failed to recover the sender of transaction {tx_num}"))?;
+
+        tx.put::<tables::TxHashNumber>(transaction.hash(), tx_num)?;
+        tx.put::<tables::TxSenders>(tx_num, sender)?;
+        tx_num += 1;
+    }
+
+    if let Some((number, _)) = bodies.last() {
+        for stage_id in [StageId::SenderRecovery, StageId::TransactionLookup] {
+            advance_stage_checkpoint(provider, stage_id, *number)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Sets the checkpoint of the stage to the given block, unless it's already past it.
+fn advance_stage_checkpoint<DB: Database>(
+    provider: &DatabaseProviderRW<'_, DB>,
+    stage_id: StageId,
+    block_number: BlockNumber,
+) -> eyre::Result<()> {
+    let checkpoint = provider.get_stage_checkpoint(stage_id)?.unwrap_or_default();
+    if checkpoint.block_number < block_number {
+        provider.save_stage_checkpoint(stage_id, StageCheckpoint::new(block_number))?;
+    }
+    Ok(())
+}
+
+/// Copies the snapshot data file and its auxiliary files into the snapshots directory.
+///
+/// The data file is copied last and moved into place atomically, so that an interrupted import
+/// never leaves a data file behind without its auxiliary files.
+fn copy_snapshot(path: &Path, snapshots_path: &Path) -> eyre::Result<()> {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| eyre!("{} is not a snapshot data file", path.display()))?;
+    let source_dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let aux_prefix = format!("{name}.");
+    for entry in std::fs::read_dir(source_dir)? {
+        let entry = entry?;
+        if entry.file_name().to_str().map_or(false, |file| file.starts_with(&aux_prefix)) {
+            std::fs::copy(entry.path(), snapshots_path.join(entry.file_name()))?;
+        }
+    }
+
+    let tmp_path = snapshots_path.join(format!("{name}.tmp"));
+    std::fs::copy(path, &tmp_path)?;
+    std::fs::rename(tmp_path, snapshots_path.join(name))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dirs::DataDirPath;
+    use reth_db::test_utils::{create_test_rw_db, TempDatabase};
+    use reth_interfaces::test_utils::generators::{self, random_block_range};
+    use reth_primitives::{
+        constants::EMPTY_OMMER_ROOT_HASH,
+        proofs::{calculate_receipt_root_ref, calculate_transaction_root},
+        snapshot::{Compression, Filters, InclusionFilter, PerfectHashingFunction},
+        Chain, ChainSpecBuilder, Genesis, Receipt, SealedBlock, U256,
+    };
+    use reth_provider::{
+        BlockNumReader, BlockReader, BlockWriter, HeaderProvider, ReceiptProvider,
+        TransactionsProvider,
+    };
+    use reth_snapshot::segments::{self, Segment};
+    use std::str::FromStr;
+
+    type DB = Arc<TempDatabase<DatabaseEnv>>;
+
+    /// Returns blocks that extend the genesis block with transactions, and their receipts.
+    fn random_chain(chain: &ChainSpec) -> Vec<(SealedBlock, Vec<Receipt>)> {
+        let blocks = random_block_range(&mut generators::rng(), 1..=4, B256::ZERO, 1..3);
+
+        let mut parent_hash = chain.genesis_hash();
+        blocks
+            .into_iter()
+            .map(|block| {
+                let receipts = block
+                    .body
+                    .iter()
+                    .enumerate()
+                    .map(|(index, transaction)| Receipt {
+                        tx_type: transaction.tx_type(),
+                        success: true,
+                        cumulative_gas_used: 21_000 * (index as u64 + 1),
+                        ..Default::default()
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut header = block.header.unseal();
+                header.parent_hash = parent_hash;
+                header.difficulty = U256::from(header.number);
+                header.ommers_hash = EMPTY_OMMER_ROOT_HASH;
+                header.gas_used = receipts.last().map_or(0, |receipt| receipt.cumulative_gas_used);
+                header.transactions_root = calculate_transaction_root(&block.body);
+                header.receipts_root =
+                    calculate_receipt_root_ref(&receipts.iter().collect::<Vec<_>>());
+                let header = header.seal_slow();
+                parent_hash = header.hash();
+
+                let block =
+                    SealedBlock { header, body: block.body, ommers: Vec::new(), withdrawals: None };
+                (block, receipts)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn import_into_fresh_datadir() {
+        let chain = Arc::new(
+            ChainSpecBuilder::default()
+                .chain(Chain::mainnet())
+                .genesis(Genesis::default())
+                .byzantium_activated()
+                .build(),
+        );
+        let blocks = random_chain(&chain);
+
+        // Snapshot the chain of another node
+        let db = create_test_rw_db();
+        init_genesis(db.clone(), chain.clone()).unwrap();
+        let factory = ProviderFactory::new(db, chain.clone());
+        let provider = factory.provider_rw().unwrap();
+        let mut tx_num = 0;
+        for (block, receipts) in &blocks {
+            provider.insert_block(block.clone(), None, None).unwrap();
+            for receipt in receipts {
+                provider.tx_ref().put::<tables::Receipts>(tx_num, receipt.clone()).unwrap();
+                tx_num += 1;
+            }
+        }
+        provider.commit().unwrap();
+
+        let snapshots_dir = tempfile::tempdir().unwrap();
+        let provider = factory.provider().unwrap();
+        let filters = Filters::WithFilters(InclusionFilter::Cuckoo, PerfectHashingFunction::Fmph);
+        segments::Headers::new(Compression::Lz4, filters)
+            .snapshot::<DB>(&provider, snapshots_dir.path(), 0..=4)
+            .unwrap();
+        segments::Transactions::new(Compression::Lz4, filters)
+            .snapshot::<DB>(&provider, snapshots_dir.path(), 0..=4)
+            .unwrap();
+        segments::Receipts::new(Compression::Lz4, filters)
+            .snapshot::<DB>(&provider, snapshots_dir.path(), 0..=4)
+            .unwrap();
+        let files =
+            [SnapshotSegment::Receipts, SnapshotSegment::Transactions, SnapshotSegment::Headers]
+                .map(|segment| snapshots_dir.path().join(segment.filename(&(0..=4))))
+                .to_vec();
+
+        // Import them into a fresh datadir
+        let datadir = tempfile::tempdir().unwrap();
+        let data_dir = MaybePlatformPath::<DataDirPath>::from_str(datadir.path().to_str().unwrap())
+            .unwrap()
+            .unwrap_or_chain_default(chain.chain);
+        import_snapshots(&data_dir, chain.clone(), None, VerifyAgainst::Canonical, files.clone())
+            .unwrap();
+
+        // The blocks can be read from the datadir, and syncing continues after them
+        let factory = ProviderFactory::new(
+            Arc::new(init_db(data_dir.db_path(), None).unwrap()),
+            chain.clone(),
+        )
+        .with_snapshots(data_dir.snapshots_path(), None)
+        .unwrap();
+        let provider = factory.provider().unwrap();
+        assert_eq!(provider.last_block_number().unwrap(), 4);
+        let mut td = U256::ZERO;
+        let mut tx_num = 0;
+        for (block, receipts) in &blocks {
+            td += block.difficulty;
+            for transaction in &block.body {
+                assert_eq!(provider.transaction_id(transaction.hash).unwrap(), Some(tx_num));
+                assert_eq!(
+                    provider.transaction_by_hash(transaction.hash).unwrap().as_ref(),
+                    Some(transaction)
+                );
+                assert_eq!(
+                    provider.transaction_sender(tx_num).unwrap(),
+                    transaction.recover_signer()
+                );
+                tx_num += 1;
+            }
+            assert_eq!(provider.block_by_hash(block.hash).unwrap(), Some(block.clone().unseal()));
+            assert_eq!(provider.header_td_by_number(block.number).unwrap(), Some(td));
+            assert_eq!(
+                provider.receipts_by_block(block.number.into()).unwrap().as_ref(),
+                Some(receipts)
+            );
+        }
+        for stage_id in [
+            StageId::Headers,
+            StageId::TotalDifficulty,
+            StageId::Bodies,
+            StageId::SenderRecovery,
+            StageId::TransactionLookup,
+        ] {
+            assert_eq!(
+                provider.get_stage_checkpoint(stage_id).unwrap(),
+                Some(StageCheckpoint::new(4))
+            );
+        }
+        assert_eq!(
+            provider.get_stage_checkpoint(StageId::Execution).unwrap(),
+            Some(StageCheckpoint::new(0))
+        );
+        drop(provider);
+        drop(factory);
+
+        // Snapshots can't be imported twice
+        assert!(import_snapshots(&data_dir, chain, None, VerifyAgainst::Canonical, files).is_err());
+    }
+}
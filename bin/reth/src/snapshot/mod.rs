@@ -0,0 +1,108 @@
+//! `reth snapshot` command.
+use crate::dirs::ChainPath;
+use clap::{Parser, Subcommand};
+use reth_db::{open_db_read_only, DatabaseEnvRO};
+use reth_interfaces::db::LogLevel;
+use reth_primitives::{BlockNumber, ChainSpec, SnapshotSegment};
+use reth_provider::ProviderFactory;
+use std::{
+    collections::HashMap,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+mod import;
+mod verify;
+
+pub use verify::VerifyAgainst;
+
+/// `reth snapshot` command
+#[derive(Debug, Parser)]
+pub struct Command {
+    #[clap(subcommand)]
+    command: Subcommands,
+}
+
+/// `reth snapshot` subcommands
+#[derive(Subcommand, Debug)]
+pub enum Subcommands {
+    /// Verify the structure and the content of snapshot files.
+    Verify(verify::Command),
+    /// Verify snapshot files and install them into the snapshots directory of a datadir.
+    Import(import::Command),
+}
+
+impl Command {
+    /// Execute `snapshot` command
+    pub async fn execute(self) -> eyre::Result<()> {
+        match self.command {
+            Subcommands::Verify(command) => command.execute().await,
+            Subcommands::Import(command) => command.execute().await,
+        }
+    }
+}
+
+/// Opens the database of the datadir read-only, if it exists.
+///
+/// Snapshots that are already installed in the datadir are served by the returned factory as well.
+fn open_provider_factory<D>(
+    data_dir: &ChainPath<D>,
+    chain: Arc<ChainSpec>,
+    log_level: Option<LogLevel>,
+) -> eyre::Result<Option<ProviderFactory<DatabaseEnvRO>>> {
+    let db_path = data_dir.db_path();
+    if !db_path.exists() {
+        return Ok(None)
+    }
+
+    let db = open_db_read_only(&db_path, log_level)?;
+    Ok(Some(ProviderFactory::new(db, chain).with_snapshots(data_dir.snapshots_path(), None)?))
+}
+
+/// Sorts the snapshot data files by segment and block range, so that every header snapshot is
+/// verified after the one it builds on.
+fn sort_snapshot_files(files: Vec<PathBuf>) -> eyre::Result<Vec<(PathBuf, SnapshotSegment)>> {
+    let mut files = files
+        .into_iter()
+        .map(|path| {
+            let (segment, block_range) = parse_snapshot_path(&path)?;
+            Ok((path, segment, *block_range.start()))
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+    files.sort_by_key(|(_, segment, start)| (*segment, *start));
+
+    Ok(files.into_iter().map(|(path, segment, _)| (path, segment)).collect())
+}
+
+/// Parses the segment and block range of a snapshot data file from its path.
+fn parse_snapshot_path(
+    path: &Path,
+) -> eyre::Result<(SnapshotSegment, RangeInclusive<BlockNumber>)> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(SnapshotSegment::parse_filename)
+        .ok_or_else(|| eyre::eyre!("{} is not a snapshot data file", path.display()))
+}
+
+/// Pairs every transaction snapshot with the receipt snapshot of the same block range.
+///
+/// Transaction snapshots without a matching receipt snapshot are skipped.
+fn body_snapshot_pairs(files: &[(PathBuf, SnapshotSegment)]) -> eyre::Result<Vec<(&Path, &Path)>> {
+    let mut receipts = HashMap::new();
+    for (path, segment) in files {
+        if *segment == SnapshotSegment::Receipts {
+            receipts.insert(parse_snapshot_path(path)?.1, path.as_path());
+        }
+    }
+
+    let mut pairs = Vec::new();
+    for (path, segment) in files {
+        if *segment == SnapshotSegment::Transactions {
+            if let Some(receipts) = receipts.get(&parse_snapshot_path(path)?.1) {
+                pairs.push((path.as_path(), *receipts));
+            }
+        }
+    }
+    Ok(pairs)
+}
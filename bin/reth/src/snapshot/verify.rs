@@ -0,0 +1,752 @@
+//! `reth snapshot verify` command.
+use super::{body_snapshot_pairs, open_provider_factory, parse_snapshot_path, sort_snapshot_files};
+use crate::{
+    args::{
+        utils::{chain_help, genesis_value_parser, SUPPORTED_CHAINS},
+        DatabaseArgs,
+    },
+    dirs::{DataDirPath, MaybePlatformPath},
+};
+use clap::{Parser, ValueEnum};
+use eyre::{ensure, eyre};
+use reth_db::{
+    codecs::CompactU256,
+    cursor::DbCursorRO,
+    database::Database,
+    models::{
+        AccountChangeSetBlock, BlockNumberAddress, StorageBeforeTx, StorageChangeSetBlock,
+        StoredBlockBodyIndices,
+    },
+    table::{Compress, Decompress, Table},
+    tables,
+    transaction::DbTx,
+    DatabaseEnvRO, DatabaseError, RawKey, RawTable, RawValue,
+};
+use reth_nippy_jar::{NippyJar, NippyJarCursor};
+use reth_primitives::{
+    constants::{EMPTY_OMMER_ROOT_HASH, EMPTY_ROOT_HASH},
+    keccak256,
+    proofs::{calculate_receipt_root_ref, calculate_transaction_root},
+    snapshot::SegmentHeader,
+    BlockNumber, ChainSpec, Hardfork, Header, Receipt, SnapshotSegment, TransactionSignedNoHash,
+    B256, U256,
+};
+use reth_provider::{
+    BlockHashReader, BlockReader, DatabaseProviderRO, HeaderProvider, TransactionsProvider,
+};
+use std::{collections::HashMap, ops::RangeInclusive, path::Path, sync::Arc};
+use tracing::{error, info, warn};
+
+/// What the content of a snapshot is verified against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum VerifyAgainst {
+    /// Compare the content hash of every row with the hash of the same row in the database.
+    ///
+    /// Rows that are no longer in the database, e.g. because they were pruned, are reported as
+    /// unverified.
+    #[default]
+    Database,
+    /// Recompute the hash of every header and check that the headers link up to the canonical
+    /// chain, starting from the genesis block or the last block of the preceding header snapshot.
+    /// The total difficulty of every header is recomputed from the difficulties of the chain.
+    ///
+    /// Transactions and receipts can only be checked against the headers of their blocks, which
+    /// requires the transaction and receipt snapshots of the same block range, see
+    /// [verify_bodies]. On their own, their rows are reported as unverified.
+    Canonical,
+}
+
+/// `reth snapshot verify` command
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// The path to the data dir for all reth files and subdirectories.
+    ///
+    /// Defaults to the OS-specific data directory:
+    ///
+    /// - Linux: `$XDG_DATA_HOME/reth/` or `$HOME/.local/share/reth/`
+    /// - Windows: `{FOLDERID_RoamingAppData}/reth/`
+    /// - macOS: `$HOME/Library/Application Support/reth/`
+    #[arg(long, value_name = "DATA_DIR", verbatim_doc_comment, default_value_t)]
+    datadir: MaybePlatformPath<DataDirPath>,
+
+    /// The chain this node is running.
+    ///
+    /// Possible values are either a built-in chain or the path to a chain specification file.
+    #[arg(
+        long,
+        value_name = "CHAIN_OR_PATH",
+        long_help = chain_help(),
+        default_value = SUPPORTED_CHAINS[0],
+        value_parser = genesis_value_parser
+    )]
+    chain: Arc<ChainSpec>,
+
+    #[clap(flatten)]
+    db: DatabaseArgs,
+
+    /// What to verify the content of the snapshots against.
+    #[arg(long, value_enum, default_value_t = VerifyAgainst::Database)]
+    against: VerifyAgainst,
+
+    /// Snapshot data files to verify.
+    ///
+    /// Defaults to all snapshots in the snapshots directory of the datadir.
+    files: Vec<std::path::PathBuf>,
+}
+
+impl Command {
+    /// Execute `snapshot verify` command
+    pub async fn execute(self) -> eyre::Result<()> {
+        let data_dir = self.datadir.unwrap_or_chain_default(self.chain.chain);
+
+        let files = if self.files.is_empty() {
+            let snapshots_path = data_dir.snapshots_path();
+            let mut files = Vec::new();
+            for entry in std::fs::read_dir(snapshots_path)? {
+                let path = entry?.path();
+                // Skip auxiliary files of the jars, like the offsets index
+                if parse_snapshot_path(&path).is_ok() {
+                    files.push(path);
+                }
+            }
+            files
+        } else {
+            self.files
+        };
+
+        let factory = open_provider_factory(&data_dir, self.chain.clone(), self.db.log_level)?;
+        let provider = factory.as_ref().map(|factory| factory.provider()).transpose()?;
+        if provider.is_none() {
+            warn!(target: "reth::cli", "No database found, content can only be verified against the canonical chain");
+        }
+
+        let mut anchors = HashMap::new();
+        let mut failed = 0;
+        let files = sort_snapshot_files(files)?;
+        for (path, _) in &files {
+            match verify_snapshot::<DatabaseEnvRO>(
+                path,
+                &self.chain,
+                provider.as_ref(),
+                self.against,
+                &mut anchors,
+            ) {
+                Ok(verified) => info!(
+                    target: "reth::cli",
+                    path = %path.display(),
+                    segment = ?verified.segment,
+                    block_range = ?verified.block_range,
+                    rows = verified.rows,
+                    unverified_rows = verified.unverified_rows,
+                    "Snapshot verified"
+                ),
+                Err(err) => {
+                    failed += 1;
+                    error!(target: "reth::cli", path = %path.display(), %err, "Snapshot verification failed");
+                }
+            }
+        }
+
+        // Transactions and receipts are verified against the headers of their blocks
+        if let (VerifyAgainst::Canonical, Some(provider)) = (self.against, provider.as_ref()) {
+            for (transactions, receipts) in body_snapshot_pairs(&files)? {
+                match verify_bodies::<DatabaseEnvRO>(transactions, receipts, &self.chain, provider)
+                {
+                    Ok(bodies) => info!(
+                        target: "reth::cli",
+                        transactions = %transactions.display(),
+                        receipts = %receipts.display(),
+                        blocks = bodies.len(),
+                        "Snapshot bodies verified"
+                    ),
+                    Err(err) => {
+                        failed += 1;
+                        error!(target: "reth::cli", path = %transactions.display(), %err, "Snapshot bodies verification failed");
+                    }
+                }
+            }
+        }
+
+        ensure!(failed == 0, "{failed} snapshot(s) failed verification");
+        Ok(())
+    }
+}
+
+/// Outcome of verifying a single snapshot.
+#[derive(Debug)]
+pub(crate) struct VerifiedSnapshot {
+    /// Segment of the snapshot.
+    pub(crate) segment: SnapshotSegment,
+    /// Block range of the snapshot.
+    pub(crate) block_range: RangeInclusive<BlockNumber>,
+    /// Number of rows in the snapshot.
+    pub(crate) rows: usize,
+    /// Number of rows that are missing from the database, and couldn't be compared.
+    pub(crate) unverified_rows: usize,
+}
+
+/// Verifies a snapshot data file.
+///
+/// Checks that:
+/// - the [`SegmentHeader`] of the jar matches the segment and block range of the file name.
+/// - the jar has the expected number of columns and rows, and the offsets point to values that
+///   decode into the types they were snapshotted from.
+/// - if the jar has filters, every row can be found by its key.
+/// - the content of every row matches the database or the canonical chain, see [VerifyAgainst].
+///
+/// `anchors` holds the hashes and total difficulties of the last blocks of header snapshots that
+/// were verified against the canonical chain before. It's used to link up subsequent header
+/// snapshots and extended with the last block of the verified snapshot.
+pub(crate) fn verify_snapshot<DB: Database>(
+    path: &Path,
+    chain: &ChainSpec,
+    provider: Option<&DatabaseProviderRO<'_, DB>>,
+    against: VerifyAgainst,
+    anchors: &mut HashMap<BlockNumber, (B256, U256)>,
+) -> eyre::Result<VerifiedSnapshot> {
+    let (segment, block_range) = parse_snapshot_path(path)?;
+
+    // Header
+    let jar = NippyJar::<SegmentHeader>::load(path)?;
+    let header = jar.user_header();
+    ensure!(
+        header.segment() == segment,
+        "snapshot holds {:?} instead of {segment:?}",
+        header.segment()
+    );
+    ensure!(
+        header.block_range() == block_range,
+        "snapshot holds blocks {:?} instead of {block_range:?}",
+        header.block_range()
+    );
+
+    // Offsets
+    let expected_columns = match segment {
+        SnapshotSegment::Headers => 3,
+        SnapshotSegment::Transactions |
+        SnapshotSegment::Receipts |
+        SnapshotSegment::AccountChangeSets |
+        SnapshotSegment::StorageChangeSets => 1,
+    };
+    ensure!(
+        jar.columns() == expected_columns,
+        "snapshot has {} columns instead of {expected_columns}",
+        jar.columns()
+    );
+    let expected_rows = (header.start()..=header.end()).count();
+    ensure!(
+        jar.rows() == expected_rows,
+        "snapshot has {} rows instead of {expected_rows}",
+        jar.rows()
+    );
+
+    if against == VerifyAgainst::Database && provider.is_none() {
+        return Err(eyre!("verifying against the database requires a database"))
+    }
+    if against == VerifyAgainst::Canonical &&
+        matches!(
+            segment,
+            SnapshotSegment::AccountChangeSets | SnapshotSegment::StorageChangeSets
+        )
+    {
+        return Err(eyre!("{segment:?} snapshots can only be verified against the database"))
+    }
+
+    let mut cursor = NippyJarCursor::new(&jar)?;
+    let mut key_cursor = NippyJarCursor::new(&jar)?;
+    let mut previous = None;
+    let mut verified = VerifiedSnapshot { segment, block_range, rows: 0, unverified_rows: 0 };
+
+    while let Some(row) = cursor.next_row()? {
+        let row = row.into_iter().map(<[u8]>::to_vec).collect::<Vec<_>>();
+        let row_index = verified.rows;
+        let number = header.start() + row_index as u64;
+
+        // Every column has to decode into the type it was snapshotted from. The key is what
+        // filters and the PHF are built from.
+        let key = match segment {
+            SnapshotSegment::Headers => {
+                let block = Header::decompress(&row[0])?;
+                let td = U256::from(CompactU256::decompress(&row[1])?);
+                let hash = B256::decompress(&row[2])?;
+                ensure!(block.number == number, "row {number} holds block {}", block.number);
+
+                if against == VerifyAgainst::Canonical {
+                    let computed = block.hash_slow();
+                    ensure!(
+                        computed == hash,
+                        "block {number}: stored hash {hash} doesn't match the header hash {computed}"
+                    );
+
+                    let (parent_hash, parent_td) = match previous {
+                        Some(previous) => previous,
+                        None if number == 0 => {
+                            ensure!(
+                                hash == chain.genesis_hash(),
+                                "block 0 doesn't match the genesis hash of the chain"
+                            );
+                            (block.parent_hash, U256::ZERO)
+                        }
+                        None => canonical_anchor::<DB>(provider, anchors, number - 1)?.ok_or_else(|| {
+                            eyre!(
+                                "no canonical hash known for block {}, verify the preceding header snapshot first",
+                                number - 1
+                            )
+                        })?,
+                    };
+                    ensure!(
+                        block.parent_hash == parent_hash,
+                        "block {number} doesn't link up to its parent {parent_hash}"
+                    );
+                    let expected_td = parent_td + block.difficulty;
+                    ensure!(
+                        td == expected_td,
+                        "block {number}: stored total difficulty {td} doesn't match the chain, expected {expected_td}"
+                    );
+
+                    if let Some((canonical, _)) =
+                        canonical_anchor::<DB>(provider, &HashMap::new(), number)?
+                    {
+                        ensure!(
+                            canonical == hash,
+                            "block {number}: hash {hash} isn't canonical, expected {canonical}"
+                        );
+                    }
+                    previous = Some((hash, td));
+                }
+
+                Some(hash)
+            }
+            SnapshotSegment::Transactions => {
+                Some(TransactionSignedNoHash::decompress(&row[0])?.hash())
+            }
+            SnapshotSegment::Receipts => {
+                Receipt::decompress(&row[0])?;
+                // Receipts are keyed by the hash of their transaction
+                provider
+                    .map(|provider| provider.transaction_by_id(number))
+                    .transpose()?
+                    .flatten()
+                    .map(|transaction| transaction.hash())
+            }
+            SnapshotSegment::AccountChangeSets => {
+                AccountChangeSetBlock::decompress(&row[0])?;
+                None
+            }
+            SnapshotSegment::StorageChangeSets => {
+                StorageChangeSetBlock::decompress(&row[0])?;
+                None
+            }
+        };
+
+        // Filters. Receipts are keyed by the hash of their transaction, which is only known if
+        // the transaction is in the database or in an installed snapshot.
+        let mut unverified = false;
+        if jar.uses_filters() {
+            match key {
+                Some(key) => verify_filter_key(&mut key_cursor, header, row_index, key)?,
+                None => unverified = true,
+            }
+        }
+
+        // Content
+        match against {
+            VerifyAgainst::Database => {
+                let provider = provider.expect("checked above");
+                match database_row::<DB>(provider, segment, number)? {
+                    Some(expected) => ensure!(
+                        keccak256(expected.concat()) == keccak256(row.concat()),
+                        "row {number} doesn't match the database"
+                    ),
+                    None => unverified = true,
+                }
+            }
+            VerifyAgainst::Canonical => unverified |= segment != SnapshotSegment::Headers,
+        }
+        if unverified {
+            verified.unverified_rows += 1;
+        }
+
+        verified.rows += 1;
+    }
+
+    ensure!(
+        verified.rows == expected_rows,
+        "snapshot has {} readable rows instead of {expected_rows}",
+        verified.rows
+    );
+    if let Some(last) = previous {
+        anchors.insert(*verified.block_range.end(), last);
+    }
+
+    Ok(verified)
+}
+
+/// Verifies the transaction and receipt snapshots of the same block range against the headers of
+/// their blocks, which are read from the database and the installed snapshots.
+///
+/// Snapshots don't record how many transactions a block has, so the blocks are split by the
+/// cumulative gas used of the receipts, which ends at the gas used of the block. Checks that:
+/// - both snapshots cover the same transactions, which follow the last transaction before the block
+///   range.
+/// - the transactions and, since Byzantium, the receipts of every block match the roots of its
+///   header.
+/// - if the receipt snapshot has filters, every receipt can be found by its transaction hash.
+///
+/// Ommers and withdrawals are not snapshotted, so blocks that have any are rejected.
+///
+/// Returns the body indices of the blocks.
+pub(crate) fn verify_bodies<DB: Database>(
+    transactions_path: &Path,
+    receipts_path: &Path,
+    chain: &ChainSpec,
+    provider: &DatabaseProviderRO<'_, DB>,
+) -> eyre::Result<Vec<(BlockNumber, StoredBlockBodyIndices)>> {
+    let transactions_jar = NippyJar::<SegmentHeader>::load(transactions_path)?;
+    let receipts_jar = NippyJar::<SegmentHeader>::load(receipts_path)?;
+    let header = transactions_jar.user_header();
+    ensure!(
+        header.segment() == SnapshotSegment::Transactions &&
+            receipts_jar.user_header().segment() == SnapshotSegment::Receipts,
+        "expected a transaction and a receipt snapshot"
+    );
+    ensure!(
+        receipts_jar.user_header().block_range() == header.block_range() &&
+            receipts_jar.user_header().tx_range() == header.tx_range(),
+        "receipt snapshot covers blocks {:?} instead of {:?}",
+        receipts_jar.user_header().block_range(),
+        header.block_range()
+    );
+
+    let block_range = header.block_range();
+    let mut next_tx_num = match block_range.start().checked_sub(1) {
+        Some(parent) => provider
+            .block_body_indices(parent)?
+            .ok_or_else(|| {
+                eyre!("no body known for block {parent}, import its transactions first")
+            })?
+            .next_tx_num(),
+        None => 0,
+    };
+    ensure!(
+        header.tx_start() == next_tx_num,
+        "snapshot starts at transaction {} instead of {next_tx_num}",
+        header.tx_start()
+    );
+
+    let mut transactions = NippyJarCursor::new(&transactions_jar)?;
+    let mut receipts = NippyJarCursor::new(&receipts_jar)?;
+    let mut key_cursor = NippyJarCursor::new(&receipts_jar)?;
+    let mut bodies = Vec::with_capacity(block_range.clone().count());
+    for number in block_range {
+        let block = provider
+            .header_by_number(number)?
+            .ok_or_else(|| eyre!("no header known for block {number}, import it first"))?;
+        ensure!(
+            block.ommers_hash == EMPTY_OMMER_ROOT_HASH,
+            "block {number} has ommers, which are not snapshotted"
+        );
+        ensure!(
+            block.withdrawals_root.map_or(true, |root| root == EMPTY_ROOT_HASH),
+            "block {number} has withdrawals, which are not snapshotted"
+        );
+
+        let first_tx_num = next_tx_num;
+        let mut block_transactions = Vec::new();
+        let mut block_receipts = Vec::new();
+        let mut cumulative_gas_used = 0;
+        while cumulative_gas_used < block.gas_used {
+            let receipt = receipts
+                .next_row()?
+                .map(|row| Receipt::decompress(row[0]))
+                .transpose()?
+                .ok_or_else(|| eyre!("block {number}: receipts end before its gas used"))?;
+            ensure!(
+                receipt.cumulative_gas_used > cumulative_gas_used,
+                "block {number}: receipt {next_tx_num} doesn't use any gas"
+            );
+            cumulative_gas_used = receipt.cumulative_gas_used;
+
+            let transaction = transactions
+                .next_row()?
+                .map(|row| TransactionSignedNoHash::decompress(row[0]))
+                .transpose()?
+                .ok_or_else(|| eyre!("block {number}: transactions end before its receipts"))?
+                .with_hash();
+            if receipts_jar.uses_filters() {
+                verify_filter_key(
+                    &mut key_cursor,
+                    receipts_jar.user_header(),
+                    (next_tx_num - header.tx_start()) as usize,
+                    transaction.hash(),
+                )?;
+            }
+
+            block_transactions.push(transaction);
+            block_receipts.push(receipt);
+            next_tx_num += 1;
+        }
+        ensure!(
+            cumulative_gas_used == block.gas_used,
+            "block {number}: receipts use {cumulative_gas_used} gas instead of {}",
+            block.gas_used
+        );
+
+        ensure!(
+            calculate_transaction_root(&block_transactions) == block.transactions_root,
+            "block {number}: transactions don't match the transactions root"
+        );
+        // Receipts before Byzantium commit to the intermediate state root, which isn't stored.
+        if chain.fork(Hardfork::Byzantium).active_at_block(number) {
+            ensure!(
+                calculate_receipt_root_ref(&block_receipts.iter().collect::<Vec<_>>()) ==
+                    block.receipts_root,
+                "block {number}: receipts don't match the receipts root"
+            );
+        }
+
+        bodies.push((
+            number,
+            StoredBlockBodyIndices { first_tx_num, tx_count: next_tx_num - first_tx_num },
+        ));
+    }
+
+    ensure!(
+        transactions.next_row()?.is_none() && receipts.next_row()?.is_none(),
+        "snapshots have transactions after the last block"
+    );
+
+    Ok(bodies)
+}
+
+/// Checks that the row of the jar is found by its key.
+fn verify_filter_key(
+    key_cursor: &mut NippyJarCursor<'_, SegmentHeader>,
+    header: &SegmentHeader,
+    row_index: usize,
+    key: B256,
+) -> eyre::Result<()> {
+    let number = header.start() + row_index as u64;
+    ensure!(
+        key_cursor.row_by_key(key.as_slice())?.is_some(),
+        "row {number} can't be found by its key {key}"
+    );
+    ensure!(
+        key_cursor.row_index() == row_index as u64 + 1,
+        "row {number} is found at row {} by its key {key}",
+        header.start() + key_cursor.row_index() - 1
+    );
+    Ok(())
+}
+
+/// Returns the canonical hash and total difficulty of the block from the anchors, or from the
+/// database and the installed snapshots.
+fn canonical_anchor<DB: Database>(
+    provider: Option<&DatabaseProviderRO<'_, DB>>,
+    anchors: &HashMap<BlockNumber, (B256, U256)>,
+    number: BlockNumber,
+) -> eyre::Result<Option<(B256, U256)>> {
+    if let Some(anchor) = anchors.get(&number) {
+        return Ok(Some(*anchor))
+    }
+    let Some(provider) = provider else { return Ok(None) };
+    match (provider.block_hash(number)?, provider.header_td_by_number(number)?) {
+        (Some(hash), Some(td)) => Ok(Some((hash, td))),
+        _ => Ok(None),
+    }
+}
+
+/// Returns the raw column values of the row as they're stored in the database, or [None] if
+/// the row is not in the database.
+fn database_row<DB: Database>(
+    provider: &DatabaseProviderRO<'_, DB>,
+    segment: SnapshotSegment,
+    number: u64,
+) -> eyre::Result<Option<Vec<Vec<u8>>>> {
+    let tx = provider.tx_ref();
+    let row = match segment {
+        SnapshotSegment::Headers => {
+            match (
+                raw_value::<tables::Headers>(tx, number)?,
+                raw_value::<tables::HeaderTD>(tx, number)?,
+                raw_value::<tables::CanonicalHeaders>(tx, number)?,
+            ) {
+                (Some(header), Some(td), Some(hash)) => Some(vec![header, td, hash]),
+                _ => None,
+            }
+        }
+        SnapshotSegment::Transactions => {
+            raw_value::<tables::Transactions>(tx, number)?.map(|v| vec![v])
+        }
+        SnapshotSegment::Receipts => raw_value::<tables::Receipts>(tx, number)?.map(|v| vec![v]),
+        SnapshotSegment::AccountChangeSets => {
+            let changes = tx
+                .cursor_read::<tables::AccountChangeSet>()?
+                .walk_range(number..=number)?
+                .map(|entry| entry.map(|(_, account)| account))
+                .collect::<Result<Vec<_>, _>>()?;
+            (!changes.is_empty()).then(|| vec![AccountChangeSetBlock(changes).compress()])
+        }
+        SnapshotSegment::StorageChangeSets => {
+            let changes = tx
+                .cursor_read::<tables::StorageChangeSet>()?
+                .walk_range(BlockNumberAddress::range(number..=number))?
+                .map(|entry| {
+                    entry.map(|(key, storage)| StorageBeforeTx {
+                        address: key.address(),
+                        entry: storage,
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            (!changes.is_empty()).then(|| vec![StorageChangeSetBlock(changes).compress()])
+        }
+    };
+
+    Ok(row)
+}
+
+/// Returns the raw value of the key in the table.
+fn raw_value<T: Table<Key = u64>>(
+    tx: &impl DbTx,
+    key: u64,
+) -> Result<Option<Vec<u8>>, DatabaseError> {
+    Ok(tx.get::<RawTable<T>>(RawKey::new(key))?.map(RawValue::into_value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_db::{
+        test_utils::{create_test_rw_db, TempDatabase},
+        DatabaseEnv,
+    };
+    use reth_interfaces::test_utils::generators::{self, random_block_range};
+    use reth_primitives::{
+        snapshot::{Compression, Filters, InclusionFilter, PerfectHashingFunction},
+        MAINNET,
+    };
+    use reth_provider::ProviderFactory;
+    use reth_snapshot::segments::{self, Segment};
+
+    type DB = Arc<TempDatabase<DatabaseEnv>>;
+
+    #[test]
+    fn verify_headers() {
+        let parent_hash = B256::with_last_byte(1);
+        let blocks = random_block_range(&mut generators::rng(), 1..=4, parent_hash, 0..2);
+
+        let db = create_test_rw_db();
+        let snapshots_dir = tempfile::tempdir().unwrap();
+        db.update(|tx| -> Result<(), DatabaseError> {
+            tx.put::<tables::CanonicalHeaders>(0, parent_hash)?;
+            tx.put::<tables::HeaderTD>(0, U256::ZERO.into())?;
+            let mut next_tx_num = 0;
+            let mut td = U256::ZERO;
+            for block in &blocks {
+                td += block.difficulty;
+                tx.put::<tables::CanonicalHeaders>(block.number, block.hash)?;
+                tx.put::<tables::Headers>(block.number, block.header.clone().unseal())?;
+                tx.put::<tables::HeaderTD>(block.number, td.into())?;
+                tx.put::<tables::BlockBodyIndices>(
+                    block.number,
+                    StoredBlockBodyIndices {
+                        first_tx_num: next_tx_num,
+                        tx_count: block.body.len() as u64,
+                    },
+                )?;
+                next_tx_num += block.body.len() as u64;
+            }
+            Ok(())
+        })
+        .unwrap()
+        .unwrap();
+
+        let factory = ProviderFactory::new(db.clone(), MAINNET.clone());
+        let provider = factory.provider().unwrap();
+        segments::Headers::new(
+            Compression::Lz4,
+            Filters::WithFilters(InclusionFilter::Cuckoo, PerfectHashingFunction::Fmph),
+        )
+        .snapshot::<DB>(&provider, snapshots_dir.path(), 1..=4)
+        .unwrap();
+        let path = snapshots_dir.path().join(SnapshotSegment::Headers.filename(&(1..=4)));
+
+        // Against the database
+        let mut anchors = HashMap::new();
+        let verified = verify_snapshot::<DB>(
+            &path,
+            &MAINNET,
+            Some(&provider),
+            VerifyAgainst::Database,
+            &mut anchors,
+        )
+        .unwrap();
+        assert_eq!((verified.rows, verified.unverified_rows), (4, 0));
+
+        // Against the canonical chain, anchored by the database
+        verify_snapshot::<DB>(
+            &path,
+            &MAINNET,
+            Some(&provider),
+            VerifyAgainst::Canonical,
+            &mut anchors,
+        )
+        .unwrap();
+        let td = blocks.iter().fold(U256::ZERO, |td, block| td + block.difficulty);
+        assert_eq!(anchors, HashMap::from([(4, (blocks[3].hash, td))]));
+
+        // Without the database, the snapshot can only be anchored by a preceding snapshot
+        let no_provider = None::<&DatabaseProviderRO<'_, DB>>;
+        assert!(verify_snapshot::<DB>(
+            &path,
+            &MAINNET,
+            no_provider,
+            VerifyAgainst::Canonical,
+            &mut HashMap::new()
+        )
+        .is_err());
+        verify_snapshot::<DB>(
+            &path,
+            &MAINNET,
+            no_provider,
+            VerifyAgainst::Canonical,
+            &mut HashMap::from([(0, (parent_hash, U256::ZERO))]),
+        )
+        .unwrap();
+        assert!(verify_snapshot::<DB>(
+            &path,
+            &MAINNET,
+            no_provider,
+            VerifyAgainst::Canonical,
+            &mut HashMap::from([(0, (B256::with_last_byte(2), U256::ZERO))]),
+        )
+        .is_err());
+
+        // Rows that differ from the database are detected
+        drop(provider);
+        db.update(|tx| tx.put::<tables::HeaderTD>(2, U256::MAX.into())).unwrap().unwrap();
+        let provider = factory.provider().unwrap();
+        assert!(verify_snapshot::<DB>(
+            &path,
+            &MAINNET,
+            Some(&provider),
+            VerifyAgainst::Database,
+            &mut HashMap::new()
+        )
+        .is_err());
+
+        // Total difficulties that don't add up are detected
+        let other_dir = tempfile::tempdir().unwrap();
+        segments::Headers::new(Compression::Uncompressed, Filters::WithoutFilters)
+            .snapshot::<DB>(&provider, other_dir.path(), 1..=4)
+            .unwrap();
+        assert!(verify_snapshot::<DB>(
+            &other_dir.path().join(SnapshotSegment::Headers.filename(&(1..=4))),
+            &MAINNET,
+            no_provider,
+            VerifyAgainst::Canonical,
+            &mut HashMap::from([(0, (parent_hash, U256::ZERO))]),
+        )
+        .is_err());
+    }
+}
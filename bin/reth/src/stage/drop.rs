@@ -145,6 +145,14 @@ impl Command {
                         Default::default(),
                     )?;
                 }
+                StageEnum::TraceAddresses => {
+                    tx.clear::<tables::TraceAddressHistory>()?;
+                    tx.clear::<tables::BlockTraceAddresses>()?;
+                    tx.put::<tables::SyncStage>(
+                        StageId::IndexTraceAddresses.to_string(),
+                        Default::default(),
+                    )?;
+                }
                 StageEnum::TotalDifficulty => {
                     tx.clear::<tables::HeaderTD>()?;
                     tx.put::<tables::SyncStage>(
@@ -22,7 +22,7 @@ use reth_primitives::{
 };
 use reth_provider::{
     chain::{ChainSplit, SplitAt},
-    BlockExecutionWriter, BlockNumReader, BlockWriter, BundleStateWithReceipts,
+    BadBlockStore, BlockExecutionWriter, BlockNumReader, BlockWriter, BundleStateWithReceipts,
     CanonStateNotification, CanonStateNotificationSender, CanonStateNotifications, Chain,
     DatabaseProvider, DisplayBlocksChain, ExecutorFactory, HeaderProvider,
 };
@@ -89,6 +89,8 @@ pub struct BlockchainTree<DB: Database, EF: ExecutorFactory> {
     metrics: TreeMetrics,
     /// Metrics for sync stages.
     sync_metrics_tx: Option<MetricEventsSender>,
+    /// Store of recently rejected blocks.
+    bad_blocks: Option<BadBlockStore>,
     prune_modes: Option<PruneModes>,
 }
 
@@ -132,6 +134,7 @@ impl<DB: Database, EF: ExecutorFactory> BlockchainTree<DB, EF> {
             canon_state_notification_sender,
             metrics: Default::default(),
             sync_metrics_tx: None,
+            bad_blocks: None,
             prune_modes,
         })
     }
@@ -142,6 +145,22 @@ impl<DB: Database, EF: ExecutorFactory> BlockchainTree<DB, EF> {
         self
     }
 
+    /// Set the store that blocks which fail validation are recorded to.
+    pub fn with_bad_block_store(mut self, bad_blocks: BadBlockStore) -> Self {
+        self.bad_blocks = Some(bad_blocks);
+        self
+    }
+
+    /// Records the block of the given error in the bad block store, if the error indicates that
+    /// the block is invalid.
+    fn record_bad_block(&self, err: &InsertBlockError) {
+        if let Some(bad_blocks) = &self.bad_blocks {
+            if err.kind().is_invalid_block() {
+                bad_blocks.insert(err.block().clone(), err.kind());
+            }
+        }
+    }
+
     /// Check if then block is known to blockchain tree or database and return its status.
     ///
     /// Function will check:
@@ -624,7 +643,9 @@ impl<DB: Database, EF: ExecutorFactory> BlockchainTree<DB, EF> {
     pub fn buffer_block(&mut self, block: SealedBlockWithSenders) -> Result<(), InsertBlockError> {
         // validate block consensus rules
         if let Err(err) = self.validate_block(&block) {
-            return Err(InsertBlockError::consensus_error(err, block.block))
+            let err = InsertBlockError::consensus_error(err, block.block);
+            self.record_bad_block(&err);
+            return Err(err)
         }
 
         self.state.buffered_blocks.insert_block(block);
@@ -713,12 +734,18 @@ impl<DB: Database, EF: ExecutorFactory> BlockchainTree<DB, EF> {
 
         // validate block consensus rules
         if let Err(err) = self.validate_block(&block) {
-            return Err(InsertBlockError::consensus_error(err, block.block))
+            let err = InsertBlockError::consensus_error(err, block.block);
+            self.record_bad_block(&err);
+            return Err(err)
         }
 
-        Ok(InsertPayloadOk::Inserted(
-            self.try_insert_validated_block(block, block_validation_kind)?,
-        ))
+        match self.try_insert_validated_block(block, block_validation_kind) {
+            Ok(status) => Ok(InsertPayloadOk::Inserted(status)),
+            Err(err) => {
+                self.record_bad_block(&err);
+                Err(err)
+            }
+        }
     }
 
     /// Finalize blocks up until and including `finalized_block`, and remove them from the tree.
@@ -832,6 +859,7 @@ impl<DB: Database, EF: ExecutorFactory> BlockchainTree<DB, EF> {
                         target: "blockchain_tree", ?err,
                         "Failed to insert buffered block",
                     );
+                    self.record_bad_block(&err);
                     err
                 });
         }
@@ -1126,6 +1154,28 @@ impl<DB: Database, EF: ExecutorFactory> BlockchainTree<DB, EF> {
         Ok(())
     }
 
+    /// Discards all chains and buffered blocks of the tree and reads the canonical chain from the
+    /// database again.
+    ///
+    /// The last finalized block is kept, unless it's no longer part of the canonical chain.
+    pub fn reset_canonical_chain(&mut self) -> RethResult<()> {
+        let last_canonical_hashes = self
+            .externals
+            .fetch_latest_canonical_hashes(self.config.num_of_canonical_hashes() as usize)?;
+        let canonical_tip = last_canonical_hashes.keys().next_back().copied().unwrap_or_default();
+        let last_finalized_block_number =
+            self.block_indices().last_finalized_block().min(canonical_tip);
+        info!(target: "blockchain_tree", canonical_tip, "Resetting canonical chain");
+
+        self.state = TreeState::new(
+            last_finalized_block_number,
+            last_canonical_hashes,
+            self.config.max_unconnected_blocks(),
+        );
+
+        Ok(())
+    }
+
     /// Revert canonical blocks from the database and return them.
     ///
     /// The block, `revert_until`, is non-inclusive, i.e. `revert_until` stays in the database.
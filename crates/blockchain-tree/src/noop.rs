@@ -59,6 +59,10 @@ impl BlockchainTreeEngine for NoopBlockchainTree {
     fn unwind(&self, _unwind_to: BlockNumber) -> RethResult<()> {
         Ok(())
     }
+
+    fn reset_canonical_chain(&self) -> RethResult<()> {
+        Ok(())
+    }
 }
 
 impl BlockchainTreeViewer for NoopBlockchainTree {
@@ -99,6 +99,14 @@ impl<DB: Database, EF: ExecutorFactory> BlockchainTreeEngine for ShareableBlockc
         tree.update_chains_metrics();
         res
     }
+
+    fn reset_canonical_chain(&self) -> RethResult<()> {
+        trace!(target: "blockchain_tree", "Resetting canonical chain");
+        let mut tree = self.tree.write();
+        let res = tree.reset_canonical_chain();
+        tree.update_chains_metrics();
+        res
+    }
 }
 
 impl<DB: Database, EF: ExecutorFactory> BlockchainTreeViewer for ShareableBlockchainTree<DB, EF> {
@@ -75,6 +75,10 @@ pub struct StageConfig {
     pub index_account_history: IndexHistoryConfig,
     /// Index Storage History stage configuration.
     pub index_storage_history: IndexHistoryConfig,
+    /// Index Trace Addresses stage configuration.
+    pub index_trace_addresses: IndexTraceAddressesConfig,
+    /// Snap Sync stage configuration.
+    pub snap_sync: SnapSyncConfig,
 }
 
 /// Header stage configuration.
@@ -285,6 +289,44 @@ impl Default for IndexHistoryConfig {
     }
 }
 
+/// Index Trace Addresses stage configuration.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct IndexTraceAddressesConfig {
+    /// Whether the stage is enabled.
+    ///
+    /// The index is used by `trace_filter` to only trace the blocks that touched the filtered
+    /// addresses.
+    pub enabled: bool,
+    /// The maximum number of blocks to process before committing progress to the database.
+    pub commit_threshold: u64,
+}
+
+impl Default for IndexTraceAddressesConfig {
+    fn default() -> Self {
+        Self { enabled: false, commit_threshold: 10_000 }
+    }
+}
+
+/// Snap Sync stage configuration.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct SnapSyncConfig {
+    /// Whether the stage is enabled.
+    ///
+    /// A node without any executed blocks downloads the state of a recent block from peers via the
+    /// `snap` protocol instead of executing all blocks since genesis.
+    pub enabled: bool,
+    /// The minimum number of accounts to download before committing progress to the database.
+    pub commit_threshold: u64,
+}
+
+impl Default for SnapSyncConfig {
+    fn default() -> Self {
+        Self { enabled: false, commit_threshold: 100_000 }
+    }
+}
+
 /// Pruning configuration.
 #[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
 #[serde(default)]
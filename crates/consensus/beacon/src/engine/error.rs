@@ -1,5 +1,6 @@
 use crate::engine::hooks::EngineHookError;
 use reth_interfaces::RethError;
+use reth_primitives::BlockNumber;
 use reth_rpc_types::engine::ForkchoiceUpdateError;
 use reth_stages::PipelineError;
 
@@ -87,3 +88,37 @@ pub enum BeaconOnNewPayloadError {
     #[error(transparent)]
     Internal(Box<dyn std::error::Error + Send + Sync>),
 }
+
+/// Represents all error cases when unwinding the canonical chain to a given block.
+#[derive(Debug, thiserror::Error)]
+pub enum BeaconSetHeadError {
+    /// Thrown when the target block is above the canonical head.
+    #[error("block {target} is above the canonical head {head}")]
+    AboveCanonicalHead {
+        /// The block the chain should be unwound to.
+        target: BlockNumber,
+        /// The number of the canonical head.
+        head: BlockNumber,
+    },
+    /// Thrown when another unwind is already in progress.
+    #[error("an unwind to block {0} is already in progress")]
+    InProgress(BlockNumber),
+    /// Thrown when a hook with database write access is running.
+    #[error("hook {0} is in progress")]
+    HookInProgress(String),
+    /// Thrown when the pipeline failed to unwind, which is fatal for the engine.
+    #[error("failed to unwind the pipeline: {0}")]
+    PipelineUnwind(String),
+    /// Internal errors, for example, error while reading from the database.
+    #[error(transparent)]
+    Internal(Box<RethError>),
+    /// Thrown when the engine task is unavailable/stopped.
+    #[error("beacon consensus engine task stopped")]
+    EngineUnavailable,
+}
+
+impl From<RethError> for BeaconSetHeadError {
+    fn from(e: RethError) -> Self {
+        Self::Internal(Box::new(e))
+    }
+}
@@ -2,10 +2,11 @@
 
 use crate::{
     engine::message::OnForkChoiceUpdated, BeaconConsensusEngineEvent, BeaconEngineMessage,
-    BeaconForkChoiceUpdateError, BeaconOnNewPayloadError,
+    BeaconForkChoiceUpdateError, BeaconOnNewPayloadError, BeaconSetHeadError,
 };
 use futures::TryFutureExt;
 use reth_interfaces::RethResult;
+use reth_primitives::BlockNumber;
 use reth_rpc_types::engine::{
     CancunPayloadFields, ExecutionPayload, ForkchoiceState, ForkchoiceUpdated, PayloadAttributes,
     PayloadStatus,
@@ -74,6 +75,16 @@ impl BeaconConsensusEngineHandle {
         rx
     }
 
+    /// Sends a message to the beacon consensus engine to unwind the canonical chain to the given
+    /// block and waits until it's done.
+    ///
+    /// The engine pauses syncing while the pipeline stages are unwound and resumes afterwards.
+    pub async fn set_head(&self, block_number: BlockNumber) -> Result<(), BeaconSetHeadError> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.to_engine.send(BeaconEngineMessage::SetHead { block_number, tx });
+        rx.await.map_err(|_| BeaconSetHeadError::EngineUnavailable)?
+    }
+
     /// Sends a transition configuration exchagne message to the beacon consensus engine.
     ///
     /// See also <https://github.com/ethereum/execution-apis/blob/3d627c95a4d3510a8187dd02e0250ecb4331d27e/src/engine/paris.md#engine_exchangetransitionconfigurationv1>
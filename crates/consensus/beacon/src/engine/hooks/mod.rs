@@ -14,6 +14,9 @@ pub use prune::PruneHook;
 mod snapshot;
 pub use snapshot::SnapshotHook;
 
+mod trace_index;
+pub use trace_index::TraceIndexHook;
+
 /// Collection of [engine hooks][`EngineHook`].
 #[derive(Default)]
 pub struct EngineHooks {
@@ -133,6 +133,8 @@ impl<DB: Database + 'static> EngineHook for SnapshotHook<DB> {
     }
 
     fn db_access_level(&self) -> EngineHookDBAccessLevel {
+        // The snapshotter deletes snapshotted data from the database in short write transactions,
+        // but only of finalized blocks that the engine never writes to.
         EngineHookDBAccessLevel::ReadOnly
     }
 }
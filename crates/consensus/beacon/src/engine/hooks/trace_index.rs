@@ -0,0 +1,365 @@
+//! Trace address index hook for the engine implementation.
+
+use crate::{
+    engine::hooks::{
+        EngineContext, EngineHook, EngineHookAction, EngineHookError, EngineHookEvent,
+    },
+    hooks::EngineHookDBAccessLevel,
+};
+use futures::FutureExt;
+use reth_db::database::Database;
+use reth_interfaces::RethResult;
+use reth_primitives::{
+    stage::{StageCheckpoint, StageId},
+    Address, BlockHash, BlockNumber, ChainSpec,
+};
+use reth_provider::{
+    BlockHashReader, HistoryWriter, ProviderError, ProviderFactory, StageCheckpointReader,
+    StageCheckpointWriter,
+};
+use reth_stages::{stages::IndexTraceAddressesStage, ExecInput, StageError};
+use reth_tasks::TaskSpawner;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::RangeInclusive,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+use tokio::sync::oneshot;
+use tracing::debug;
+
+/// The maximum number of blocks that are traced while holding the database write lock.
+///
+/// If the index is further behind the tip, the blocks are traced with a read-only transaction
+/// first, and only the results are written while holding the write lock.
+const MAX_LOCKED_BLOCKS: u64 = 16;
+
+/// The number of blocks that are traced with a read-only transaction and then written in a single
+/// run of the hook.
+const BACKFILL_BATCH_SIZE: u64 = 100;
+
+/// Keeps the trace address index up to date with the canonical chain under the control of the
+/// engine.
+///
+/// Blocks that are committed by the blockchain tree are not indexed by the pipeline, so this hook
+/// runs the [IndexTraceAddressesStage] up to the tip whenever the tip advances.
+///
+/// Only the last [MAX_LOCKED_BLOCKS] blocks are traced while holding the database write lock. If
+/// the index is further behind, for example because it was enabled on a synced node, the blocks
+/// are backfilled in batches: each batch is traced in the background with a read-only
+/// transaction, without blocking the engine, and the hook only holds the write lock to write the
+/// results.
+#[derive(Debug)]
+pub struct TraceIndexHook<DB> {
+    /// The current state of the indexer.
+    state: IndexerState<DB>,
+    /// The highest block indexed by the last run.
+    indexed_block: Option<BlockNumber>,
+    /// A batch of blocks that was traced in the background and needs to be written.
+    traced: Option<TracedBlocks>,
+    /// The type that can spawn the indexer task.
+    task_spawner: Box<dyn TaskSpawner>,
+}
+
+impl<DB: Database + 'static> TraceIndexHook<DB> {
+    /// Create a new instance
+    ///
+    /// Blocks that were snapshotted are traced with the snapshots of the provider factory.
+    pub fn new(
+        provider_factory: ProviderFactory<DB>,
+        chain_spec: Arc<ChainSpec>,
+        task_spawner: Box<dyn TaskSpawner>,
+    ) -> Self {
+        let stage = IndexTraceAddressesStage::new(chain_spec, MAX_LOCKED_BLOCKS);
+        Self {
+            state: IndexerState::Idle(Some(TraceIndexer { provider_factory, stage })),
+            indexed_block: None,
+            traced: None,
+            task_spawner,
+        }
+    }
+
+    /// Advances the indexer state.
+    ///
+    /// This checks for the result in the channel, or returns pending if the indexer is idle.
+    fn poll_indexer(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<RethResult<(EngineHookEvent, Option<EngineHookAction>)>> {
+        let result = match self.state {
+            IndexerState::Idle(_) | IndexerState::Tracing(_) => return Poll::Pending,
+            IndexerState::Running(ref mut fut) => {
+                ready!(fut.poll_unpin(cx))
+            }
+        };
+
+        let event = match result {
+            Ok((indexer, result)) => {
+                self.state = IndexerState::Idle(Some(indexer));
+
+                match result {
+                    Ok(checkpoint) => {
+                        self.indexed_block = Some(checkpoint.block_number);
+                        EngineHookEvent::Finished(Ok(()))
+                    }
+                    Err(err) => {
+                        EngineHookEvent::Finished(Err(EngineHookError::Internal(Box::new(err))))
+                    }
+                }
+            }
+            Err(_) => {
+                // failed to receive the indexer
+                EngineHookEvent::Finished(Err(EngineHookError::ChannelClosed))
+            }
+        };
+
+        Poll::Ready(Ok((event, None)))
+    }
+
+    /// Checks for the result of the background tracing, or returns pending if the indexer is not
+    /// tracing.
+    ///
+    /// Returns an event only if tracing failed. On success, the indexer is idle again.
+    fn poll_tracing(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<(EngineHookEvent, Option<EngineHookAction>)>> {
+        let IndexerState::Tracing(ref mut fut) = self.state else { return Poll::Ready(None) };
+
+        let event = match ready!(fut.poll_unpin(cx)) {
+            Ok((indexer, result)) => {
+                self.state = IndexerState::Idle(Some(indexer));
+
+                match result {
+                    Ok(Backfill::Traced(traced)) => {
+                        self.traced = Some(traced);
+                        None
+                    }
+                    Ok(Backfill::NearTip(checkpoint)) => {
+                        self.indexed_block = Some(checkpoint.block_number);
+                        None
+                    }
+                    Err(err) => Some(EngineHookEvent::Finished(Err(EngineHookError::Internal(
+                        Box::new(err),
+                    )))),
+                }
+            }
+            Err(_) => {
+                // failed to receive the indexer
+                Some(EngineHookEvent::Finished(Err(EngineHookError::ChannelClosed)))
+            }
+        };
+
+        Poll::Ready(event.map(|event| (event, None)))
+    }
+
+    /// This will try to spawn the indexer if it is idle and the tip is ahead of the last indexed
+    /// block.
+    ///
+    /// If the index is close to the tip or a traced batch is ready to be written, the indexer is
+    /// spawned with the write lock. Otherwise, the next batch is traced in the background and
+    /// [None] is returned.
+    ///
+    /// If indexer is already running, do nothing.
+    fn try_spawn_indexer(
+        &mut self,
+        tip_block_number: BlockNumber,
+    ) -> Option<(EngineHookEvent, Option<EngineHookAction>)> {
+        let IndexerState::Idle(indexer) = &mut self.state else { return None };
+        let indexer = indexer.take()?;
+
+        if let Some(traced) = self.traced.take() {
+            let (tx, rx) = oneshot::channel();
+            self.task_spawner.spawn_critical_blocking(
+                "trace index task",
+                Box::pin(async move {
+                    let result = indexer.write(traced);
+                    let _ = tx.send((indexer, result));
+                }),
+            );
+            self.state = IndexerState::Running(rx);
+
+            return Some((EngineHookEvent::Started, None))
+        }
+
+        match self.indexed_block {
+            Some(indexed) if indexed >= tip_block_number => {
+                self.state = IndexerState::Idle(Some(indexer));
+                Some((EngineHookEvent::NotReady, None))
+            }
+            Some(indexed) if tip_block_number - indexed <= MAX_LOCKED_BLOCKS => {
+                let (tx, rx) = oneshot::channel();
+                self.task_spawner.spawn_critical_blocking(
+                    "trace index task",
+                    Box::pin(async move {
+                        let result = indexer.run(tip_block_number);
+                        let _ = tx.send((indexer, result));
+                    }),
+                );
+                self.state = IndexerState::Running(rx);
+
+                Some((EngineHookEvent::Started, None))
+            }
+            _ => {
+                // the index is far behind the tip, or it's unknown how far
+                let (tx, rx) = oneshot::channel();
+                self.task_spawner.spawn_critical_blocking(
+                    "trace index backfill task",
+                    Box::pin(async move {
+                        let result = indexer.trace(tip_block_number);
+                        let _ = tx.send((indexer, result));
+                    }),
+                );
+                self.state = IndexerState::Tracing(rx);
+
+                None
+            }
+        }
+    }
+}
+
+impl<DB: Database + 'static> EngineHook for TraceIndexHook<DB> {
+    fn name(&self) -> &'static str {
+        "TraceIndex"
+    }
+
+    fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+        ctx: EngineContext,
+    ) -> Poll<RethResult<(EngineHookEvent, Option<EngineHookAction>)>> {
+        // Check if the background tracing is done
+        match self.poll_tracing(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(Some((event, action))) => return Poll::Ready(Ok((event, action))),
+            Poll::Ready(None) => (),
+        }
+
+        // Try to spawn an indexer
+        match self.try_spawn_indexer(ctx.tip_block_number) {
+            Some((EngineHookEvent::NotReady, _)) => return Poll::Pending,
+            Some((event, action)) => return Poll::Ready(Ok((event, action))),
+            None => (),
+        }
+
+        // Poll the background tracing in case it was just spawned, or the indexer and check its
+        // status
+        match self.poll_tracing(cx) {
+            Poll::Ready(Some((event, action))) => Poll::Ready(Ok((event, action))),
+            Poll::Ready(None) => self.poll_indexer(cx),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn db_access_level(&self) -> EngineHookDBAccessLevel {
+        EngineHookDBAccessLevel::ReadWrite
+    }
+}
+
+/// A batch of blocks whose trace addresses were computed with a read-only transaction.
+#[derive(Debug)]
+struct TracedBlocks {
+    /// The traced blocks.
+    range: RangeInclusive<BlockNumber>,
+    /// The hash of the last traced block, to detect if the blocks were reorged before they are
+    /// written.
+    last_hash: BlockHash,
+    /// The addresses that appear in the call traces of each block.
+    addresses: BTreeMap<BlockNumber, BTreeSet<Address>>,
+}
+
+/// The outcome of [TraceIndexer::trace].
+#[derive(Debug)]
+enum Backfill {
+    /// The index is close enough to the tip to trace the remaining blocks with the write lock.
+    NearTip(StageCheckpoint),
+    /// The next batch of blocks was traced.
+    Traced(TracedBlocks),
+}
+
+/// Runs the [IndexTraceAddressesStage] outside of the pipeline.
+#[derive(Debug)]
+struct TraceIndexer<DB> {
+    provider_factory: ProviderFactory<DB>,
+    stage: IndexTraceAddressesStage,
+}
+
+impl<DB: Database> TraceIndexer<DB> {
+    /// Indexes the next batch of blocks up to the given tip and returns the new checkpoint.
+    fn run(&self, tip_block_number: BlockNumber) -> Result<StageCheckpoint, StageError> {
+        let provider = self.provider_factory.provider_rw()?;
+
+        let checkpoint = provider.get_stage_checkpoint(StageId::IndexTraceAddresses)?;
+        let output = self
+            .stage
+            .execute_inner(&provider, ExecInput { target: Some(tip_block_number), checkpoint })?;
+        provider.save_stage_checkpoint(StageId::IndexTraceAddresses, output.checkpoint)?;
+        provider.commit()?;
+
+        Ok(output.checkpoint)
+    }
+
+    /// Traces the next batch of blocks with a read-only transaction, unless the index is close to
+    /// the given tip.
+    fn trace(&self, tip_block_number: BlockNumber) -> Result<Backfill, StageError> {
+        let provider = self.provider_factory.provider()?;
+
+        let checkpoint =
+            provider.get_stage_checkpoint(StageId::IndexTraceAddresses)?.unwrap_or_default();
+        if tip_block_number.saturating_sub(checkpoint.block_number) <= MAX_LOCKED_BLOCKS {
+            return Ok(Backfill::NearTip(checkpoint))
+        }
+
+        let range = checkpoint.block_number + 1..=
+            (checkpoint.block_number + BACKFILL_BATCH_SIZE).min(tip_block_number);
+        debug!(target: "consensus::engine::hooks::trace_index", ?range, "Tracing blocks to backfill the index");
+        let last_hash = provider
+            .block_hash(*range.end())?
+            .ok_or_else(|| ProviderError::HeaderNotFound((*range.end()).into()))?;
+        let addresses = self.stage.trace_block_range(&provider, range.clone())?;
+
+        Ok(Backfill::Traced(TracedBlocks { range, last_hash, addresses }))
+    }
+
+    /// Writes a batch of blocks traced by [Self::trace] and returns the new checkpoint.
+    ///
+    /// The batch is discarded if the index or the canonical chain changed since it was traced.
+    fn write(&self, traced: TracedBlocks) -> Result<StageCheckpoint, StageError> {
+        let provider = self.provider_factory.provider_rw()?;
+
+        let checkpoint =
+            provider.get_stage_checkpoint(StageId::IndexTraceAddresses)?.unwrap_or_default();
+        if checkpoint.block_number + 1 != *traced.range.start() ||
+            provider.block_hash(*traced.range.end())? != Some(traced.last_hash)
+        {
+            debug!(target: "consensus::engine::hooks::trace_index", range = ?traced.range, "Discarding outdated traced blocks");
+            return Ok(checkpoint)
+        }
+
+        let checkpoint = StageCheckpoint::new(*traced.range.end());
+        provider.insert_trace_address_index(traced.addresses)?;
+        provider.save_stage_checkpoint(StageId::IndexTraceAddresses, checkpoint)?;
+        provider.commit()?;
+
+        Ok(checkpoint)
+    }
+}
+
+/// The possible indexer states within the sync controller.
+///
+/// [IndexerState::Idle] means that the indexer is currently idle.
+/// [IndexerState::Tracing] means that the indexer is tracing blocks in the background with a
+/// read-only transaction.
+/// [IndexerState::Running] means that the indexer is currently running.
+///
+/// NOTE: The indexer acquires the write lock over the database while it runs, see
+/// [PruneHook](crate::hooks::PruneHook).
+#[derive(Debug)]
+enum IndexerState<DB> {
+    /// Indexer is idle.
+    Idle(Option<TraceIndexer<DB>>),
+    /// Indexer is tracing blocks with a read-only transaction and waiting for a response
+    Tracing(oneshot::Receiver<(TraceIndexer<DB>, Result<Backfill, StageError>)>),
+    /// Indexer is running and waiting for a response
+    Running(oneshot::Receiver<(TraceIndexer<DB>, Result<StageCheckpoint, StageError>)>),
+}
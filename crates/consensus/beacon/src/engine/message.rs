@@ -1,10 +1,14 @@
 use crate::{
-    engine::{error::BeaconOnNewPayloadError, forkchoice::ForkchoiceStatus},
+    engine::{
+        error::{BeaconOnNewPayloadError, BeaconSetHeadError},
+        forkchoice::ForkchoiceStatus,
+    },
     BeaconConsensusEngineEvent,
 };
 use futures::{future::Either, FutureExt};
 use reth_interfaces::{consensus::ForkchoiceState, RethResult};
 use reth_payload_builder::error::PayloadBuilderError;
+use reth_primitives::BlockNumber;
 use reth_rpc_types::engine::{
     CancunPayloadFields, ExecutionPayload, ForkChoiceUpdateResult, ForkchoiceUpdateError,
     ForkchoiceUpdated, PayloadAttributes, PayloadId, PayloadStatus, PayloadStatusEnum,
@@ -160,6 +164,13 @@ pub enum BeaconEngineMessage {
         /// The sender for returning forkchoice updated result.
         tx: oneshot::Sender<RethResult<OnForkChoiceUpdated>>,
     },
+    /// Message to unwind the canonical chain to the given block.
+    SetHead {
+        /// The block that becomes the canonical head.
+        block_number: BlockNumber,
+        /// The sender for returning the result once the chain was unwound.
+        tx: oneshot::Sender<Result<(), BeaconSetHeadError>>,
+    },
     /// Message with exchanged transition configuration.
     TransitionConfigurationExchanged,
     /// Add a new listener for [`BeaconEngineMessage`].
@@ -26,8 +26,8 @@ use reth_primitives::{
     SealedBlock, SealedHeader, B256, U256,
 };
 use reth_provider::{
-    BlockIdReader, BlockReader, BlockSource, CanonChainTracker, ChainSpecProvider, ProviderError,
-    StageCheckpointReader,
+    BadBlockStore, BlockIdReader, BlockReader, BlockSource, CanonChainTracker, ChainSpecProvider,
+    ProviderError, StageCheckpointReader,
 };
 use reth_rpc_types::engine::{
     CancunPayloadFields, ExecutionPayload, PayloadAttributes, PayloadError, PayloadStatus,
@@ -57,7 +57,7 @@ pub use message::BeaconEngineMessage;
 mod error;
 pub use error::{
     BeaconConsensusEngineError, BeaconEngineResult, BeaconForkChoiceUpdateError,
-    BeaconOnNewPayloadError,
+    BeaconOnNewPayloadError, BeaconSetHeadError,
 };
 
 mod invalid_headers;
@@ -192,6 +192,8 @@ where
     /// Tracks the header of invalid payloads that were rejected by the engine because they're
     /// invalid.
     invalid_headers: InvalidHeaderCache,
+    /// Store that invalid blocks rejected by the engine are recorded to, if configured.
+    bad_blocks: Option<BadBlockStore>,
     /// Consensus engine metrics.
     metrics: EngineMetrics,
     /// After downloading a block corresponding to a recent forkchoice update, the engine will
@@ -207,6 +209,9 @@ where
     /// be used to download and execute the missing blocks.
     pipeline_run_threshold: u64,
     hooks: EngineHooksController,
+    /// The sender for returning the result of the requested unwind of the canonical chain, if
+    /// any.
+    pending_set_head: Option<oneshot::Sender<Result<(), BeaconSetHeadError>>>,
 }
 
 impl<DB, BT, Client> BeaconConsensusEngine<DB, BT, Client>
@@ -301,9 +306,11 @@ where
             payload_builder,
             listeners: EventListeners::default(),
             invalid_headers: InvalidHeaderCache::new(MAX_INVALID_HEADERS),
+            bad_blocks: None,
             metrics: EngineMetrics::default(),
             pipeline_run_threshold,
             hooks: EngineHooksController::new(hooks),
+            pending_set_head: None,
         };
 
         let maybe_pipeline_target = match target {
@@ -319,6 +326,20 @@ where
         Ok((this, handle))
     }
 
+    /// Sets the store that full blocks rejected by the engine because they're invalid are
+    /// recorded to.
+    pub fn with_bad_block_store(mut self, bad_blocks: BadBlockStore) -> Self {
+        self.bad_blocks = Some(bad_blocks);
+        self
+    }
+
+    /// Records the invalid block and its validation error in the bad block store, if configured.
+    fn record_bad_block(&self, block: &SealedBlock, error: &InsertBlockErrorKind) {
+        if let Some(bad_blocks) = &self.bad_blocks {
+            bad_blocks.insert(block.clone(), error);
+        }
+    }
+
     /// Check if the pipeline is consistent (all stages have the checkpoint block numbers no less
     /// than the checkpoint of the first stage).
     ///
@@ -1380,7 +1401,8 @@ where
             // all of these occurred if the payload is invalid
             let parent_hash = block.parent_hash;
 
-            // keep track of the invalid header
+            // keep track of the invalid block
+            self.record_bad_block(&block, &error);
             self.invalid_headers.insert(block.header);
 
             let latest_valid_hash =
@@ -1478,6 +1500,7 @@ where
                     let (block, err) = err.split();
                     warn!(target: "consensus::engine", invalid_number=?block.number, invalid_hash=?block.hash, ?err, "Marking block as invalid");
 
+                    self.record_bad_block(&block, &err);
                     self.invalid_headers.insert(block.header);
                 }
             }
@@ -1596,6 +1619,81 @@ where
         }
     }
 
+    /// Invoked when the canonical chain should be unwound to the given block.
+    ///
+    /// The unwind is performed by the pipeline once it's idle. Until the unwind has finished,
+    /// the engine responds with `SYNCING` to forkchoice updates and new payloads, since the
+    /// pipeline is active.
+    fn on_set_head(
+        &mut self,
+        block_number: BlockNumber,
+        tx: oneshot::Sender<Result<(), BeaconSetHeadError>>,
+    ) {
+        if let Some(target) = self.sync.unwind_target() {
+            let _ = tx.send(Err(BeaconSetHeadError::InProgress(target)));
+            return
+        }
+
+        let head = self.blockchain.canonical_tip().number;
+        if block_number > head {
+            let _ =
+                tx.send(Err(BeaconSetHeadError::AboveCanonicalHead { target: block_number, head }));
+            return
+        }
+
+        if let Some(hook) = self.hooks.active_db_write_hook() {
+            // the pipeline requires exclusive access to the database
+            let _ = tx.send(Err(BeaconSetHeadError::HookInProgress(hook.name().to_string())));
+            return
+        }
+
+        info!(target: "consensus::engine", block_number, head, "Unwinding canonical chain");
+        self.sync.set_pipeline_unwind_target(block_number);
+        self.pending_set_head = Some(tx);
+    }
+
+    /// Invoked when the pipeline has finished unwinding to the given block.
+    ///
+    /// Resets the blockchain tree to the unwound canonical chain, so syncing can resume from the
+    /// new canonical head.
+    ///
+    /// Returns an error if the pipeline failed to unwind, which is fatal.
+    fn on_pipeline_unwound(
+        &mut self,
+        target: BlockNumber,
+        result: Result<(), PipelineError>,
+    ) -> Result<(), BeaconConsensusEngineError> {
+        let tx = self.pending_set_head.take();
+
+        if let Err(error) = result {
+            error!(target: "consensus::engine", target, ?error, "Failed to unwind pipeline");
+            if let Some(tx) = tx {
+                let _ = tx.send(Err(BeaconSetHeadError::PipelineUnwind(error.to_string())));
+            }
+            return Err(error.into())
+        }
+
+        let res = self.reset_canonical_head(target);
+        if let Some(tx) = tx {
+            let _ = tx.send(res.map_err(Into::into));
+        }
+        self.sync_state_updater.update_sync_state(SyncState::Idle);
+
+        Ok(())
+    }
+
+    /// Resets the blockchain tree and the tracked canonical head to the given block.
+    fn reset_canonical_head(&mut self, block_number: BlockNumber) -> RethResult<()> {
+        self.blockchain.reset_canonical_chain()?;
+        let head = self
+            .blockchain
+            .sealed_header(block_number)?
+            .ok_or(RethError::Provider(ProviderError::HeaderNotFound(block_number.into())))?;
+        self.update_head(head)?;
+        info!(target: "consensus::engine", block_number, "Canonical chain unwound");
+        Ok(())
+    }
+
     /// Event handler for events emitted by the [EngineSyncController].
     ///
     /// This returns a result to indicate whether the engine future should resolve (fatal error).
@@ -1612,6 +1710,15 @@ where
                 self.metrics.pipeline_runs.increment(1);
                 self.sync_state_updater.update_sync_state(SyncState::Syncing);
             }
+            EngineSyncEvent::PipelineUnwindStarted(target) => {
+                trace!(target: "consensus::engine", target, "Started unwinding the pipeline");
+                self.sync_state_updater.update_sync_state(SyncState::Syncing);
+            }
+            EngineSyncEvent::PipelineUnwound { target, result } => {
+                if let Err(err) = self.on_pipeline_unwound(target, result) {
+                    return Some(Err(err))
+                }
+            }
             EngineSyncEvent::PipelineTaskDropped => {
                 error!(target: "consensus::engine", "Failed to receive spawned pipeline");
                 return Some(Err(BeaconConsensusEngineError::PipelineChannelClosed))
@@ -1868,6 +1975,9 @@ where
                             let res = this.on_new_payload(payload, cancun_fields);
                             let _ = tx.send(res);
                         }
+                        BeaconEngineMessage::SetHead { block_number, tx } => {
+                            this.on_set_head(block_number, tx);
+                        }
                         BeaconEngineMessage::TransitionConfigurationExchanged => {
                             this.blockchain.on_transition_configuration_exchanged();
                         }
@@ -1946,9 +2056,14 @@ mod tests {
         BeaconForkChoiceUpdateError,
     };
     use assert_matches::assert_matches;
-    use reth_interfaces::test_utils::generators::{self, Rng};
+    use reth_interfaces::{
+        blockchain_tree::BlockchainTreeViewer,
+        test_utils::generators::{self, random_block, random_block_range, Rng},
+    };
     use reth_primitives::{stage::StageCheckpoint, ChainSpec, ChainSpecBuilder, B256, MAINNET};
-    use reth_provider::{BlockWriter, ProviderFactory};
+    use reth_provider::{
+        BlockHashReader, BlockNumReader, BlockWriter, ProviderFactory, StageCheckpointWriter,
+    };
     use reth_rpc_types::engine::{ForkchoiceState, ForkchoiceUpdated, PayloadStatus};
     use reth_rpc_types_compat::engine::payload::try_block_to_payload_v1;
     use reth_stages::{ExecOutput, PipelineError, StageError};
@@ -2119,6 +2234,115 @@ mod tests {
         assert_matches!(rx.await, Ok(Ok(())));
     }
 
+    // Test that the canonical chain can only be unwound to blocks below the canonical head.
+    #[tokio::test]
+    async fn set_head() {
+        let mut rng = generators::rng();
+        let chain_spec = Arc::new(
+            ChainSpecBuilder::default()
+                .chain(MAINNET.chain)
+                .genesis(MAINNET.genesis.clone())
+                .paris_activated()
+                .build(),
+        );
+
+        let (consensus_engine, env) = TestConsensusEngineBuilder::new(chain_spec.clone())
+            .disable_blockchain_tree_sync()
+            .build();
+
+        let genesis = random_block(&mut rng, 0, None, None, Some(0));
+        insert_blocks(env.db.as_ref(), chain_spec.clone(), [&genesis].into_iter());
+
+        let mut rx = spawn_consensus_engine(consensus_engine);
+
+        assert_matches!(
+            env.send_set_head(1).await,
+            Err(BeaconSetHeadError::AboveCanonicalHead { target: 1, head: 0 })
+        );
+
+        // retry until the prune hook has finished
+        loop {
+            match env.send_set_head(0).await {
+                Err(BeaconSetHeadError::HookInProgress(_)) => {}
+                res => {
+                    assert_matches!(res, Ok(()));
+                    break
+                }
+            }
+        }
+        assert_matches!(rx.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    // Test that setting the head below the canonical tip unwinds the stages and resets the tree.
+    #[tokio::test]
+    async fn set_head_below_tip() {
+        let mut rng = generators::rng();
+        let chain_spec = Arc::new(
+            ChainSpecBuilder::default()
+                .chain(MAINNET.chain)
+                .genesis(MAINNET.genesis.clone())
+                .paris_activated()
+                .build(),
+        );
+
+        let (mut consensus_engine, env) = TestConsensusEngineBuilder::new(chain_spec.clone())
+            .with_real_pipeline()
+            .disable_blockchain_tree_sync()
+            .build();
+
+        let blocks = random_block_range(&mut rng, 0..=3, B256::ZERO, 0..2);
+        insert_blocks(env.db.as_ref(), chain_spec.clone(), blocks.iter());
+        let factory = ProviderFactory::new(env.db.clone(), chain_spec.clone());
+        let provider = factory.provider_rw().unwrap();
+        for stage in [StageId::Headers, StageId::Bodies, StageId::Finish] {
+            provider.save_stage_checkpoint(stage, StageCheckpoint::new(3)).unwrap();
+        }
+        provider.commit().unwrap();
+
+        // make the tree and the engine aware of the inserted chain
+        consensus_engine.reset_canonical_head(3).unwrap();
+        let blockchain = consensus_engine.blockchain.clone();
+        assert_eq!(blockchain.canonical_tip(), blocks[3].num_hash());
+
+        let mut rx = spawn_consensus_engine(consensus_engine);
+
+        // retry until the prune hook has finished
+        loop {
+            match env.send_set_head(1).await {
+                Err(BeaconSetHeadError::HookInProgress(_)) => {}
+                res => {
+                    assert_matches!(res, Ok(()));
+                    break
+                }
+            }
+        }
+        assert_matches!(rx.try_recv(), Err(TryRecvError::Empty));
+
+        // the stages are unwound
+        let provider = factory.provider().unwrap();
+        for stage in [StageId::Headers, StageId::Bodies, StageId::Finish] {
+            assert_eq!(
+                provider.get_stage_checkpoint(stage).unwrap(),
+                Some(StageCheckpoint::new(1))
+            );
+        }
+
+        // the canonical chain ends at the new head
+        assert_eq!(provider.last_block_number().unwrap(), 1);
+        assert_eq!(provider.best_block_number().unwrap(), 1);
+        assert_eq!(provider.block_hash(1).unwrap(), Some(blocks[1].hash));
+        assert_eq!(provider.block_hash(2).unwrap(), None);
+        assert!(provider.block_body_indices(2).unwrap().is_none());
+
+        // the tree is reset to the new head
+        assert_eq!(blockchain.canonical_tip(), blocks[1].num_hash());
+        assert_eq!(blockchain.canonical_blocks().keys().next_back(), Some(&1));
+        assert_matches!(
+            env.send_set_head(2).await,
+            Err(BeaconSetHeadError::AboveCanonicalHead { target: 2, head: 1 })
+        );
+    }
+
     fn insert_blocks<'a, DB: Database>(
         db: DB,
         chain: Arc<ChainSpec>,
@@ -41,6 +41,10 @@ where
     pipeline_state: PipelineState<DB>,
     /// Pending target block for the pipeline to sync
     pending_pipeline_target: Option<B256>,
+    /// Pending block to unwind the pipeline to, takes precedence over the sync target.
+    pending_unwind_target: Option<BlockNumber>,
+    /// The block the running pipeline is unwinding to, if it's unwinding.
+    active_unwind_target: Option<BlockNumber>,
     /// In-flight full block requests in progress.
     inflight_full_block_requests: Vec<FetchFullBlockFuture<Client>>,
     /// In-flight full block _range_ requests in progress.
@@ -79,6 +83,8 @@ where
             pipeline_task_spawner,
             pipeline_state: PipelineState::Idle(Some(pipeline)),
             pending_pipeline_target: None,
+            pending_unwind_target: None,
+            active_unwind_target: None,
             inflight_full_block_requests: Vec::new(),
             inflight_block_range_requests: Vec::new(),
             range_buffered_blocks: BinaryHeap::new(),
@@ -189,6 +195,18 @@ where
         self.pending_pipeline_target = Some(target);
     }
 
+    /// Sets a block to unwind all pipeline stages to.
+    ///
+    /// The unwind is started as soon as the pipeline is idle, before syncing to any target.
+    pub(crate) fn set_pipeline_unwind_target(&mut self, target: BlockNumber) {
+        self.pending_unwind_target = Some(target);
+    }
+
+    /// Returns the block the pipeline is going to unwind to or is unwinding to, if any.
+    pub(crate) fn unwind_target(&self) -> Option<BlockNumber> {
+        self.active_unwind_target.or(self.pending_unwind_target)
+    }
+
     /// Check if the engine reached max block as specified by `max_block` parameter.
     ///
     /// Note: this is mainly for debugging purposes.
@@ -217,6 +235,11 @@ where
             }
         };
         let ev = match res {
+            Ok((pipeline, result)) if self.active_unwind_target.is_some() => {
+                let target = self.active_unwind_target.take().expect("is some");
+                self.pipeline_state = PipelineState::Idle(Some(pipeline));
+                EngineSyncEvent::PipelineUnwound { target, result: result.map(|_| ()) }
+            }
             Ok((pipeline, result)) => {
                 let minimum_block_number = pipeline.minimum_block_number();
                 let reached_max_block =
@@ -226,6 +249,7 @@ where
             }
             Err(_) => {
                 // failed to receive the pipeline
+                self.active_unwind_target = None;
                 EngineSyncEvent::PipelineTaskDropped
             }
         };
@@ -234,8 +258,33 @@ where
 
     /// This will spawn the pipeline if it is idle and a target is set or if the pipeline is set to
     /// run continuously.
+    ///
+    /// A pending unwind is spawned before anything else.
     fn try_spawn_pipeline(&mut self) -> Option<EngineSyncEvent> {
         match &mut self.pipeline_state {
+            PipelineState::Idle(pipeline) if self.pending_unwind_target.is_some() => {
+                let target = self.pending_unwind_target.take().expect("is some");
+                let (tx, rx) = oneshot::channel();
+
+                let mut pipeline = pipeline.take().expect("exists");
+                self.pipeline_task_spawner.spawn_critical_blocking(
+                    "pipeline unwind task",
+                    Box::pin(async move {
+                        let result = pipeline
+                            .unwind(target, None)
+                            .await
+                            .map(|_| ControlFlow::NoProgress { block_number: Some(target) });
+                        let _ = tx.send((pipeline, result));
+                    }),
+                );
+                self.pipeline_state = PipelineState::Running(rx);
+                self.active_unwind_target = Some(target);
+
+                // downloaded blocks are outdated once the chain is unwound
+                self.clear_block_download_requests();
+
+                Some(EngineSyncEvent::PipelineUnwindStarted(target))
+            }
             PipelineState::Idle(pipeline) => {
                 let target = self.pending_pipeline_target.take();
 
@@ -361,6 +410,17 @@ pub(crate) enum EngineSyncEvent {
         /// Note: this is only relevant in debugging scenarios.
         reached_max_block: bool,
     },
+    /// Pipeline started unwinding all stages to the given block.
+    PipelineUnwindStarted(BlockNumber),
+    /// Pipeline finished unwinding all stages to the given block.
+    ///
+    /// If this is returned, the pipeline is idle.
+    PipelineUnwound {
+        /// The block the stages were unwound to.
+        target: BlockNumber,
+        /// Result of the unwind.
+        result: Result<(), PipelineError>,
+    },
     /// Pipeline task was dropped after it was started, unable to receive it because channel
     /// closed. This would indicate a panicked pipeline task
     PipelineTaskDropped,
@@ -1,7 +1,7 @@
 use crate::{
     engine::hooks::PruneHook, hooks::EngineHooks, BeaconConsensus, BeaconConsensusEngine,
     BeaconConsensusEngineError, BeaconConsensusEngineHandle, BeaconForkChoiceUpdateError,
-    BeaconOnNewPayloadError, MIN_BLOCKS_FOR_PIPELINE_RUN,
+    BeaconOnNewPayloadError, BeaconSetHeadError, MIN_BLOCKS_FOR_PIPELINE_RUN,
 };
 use reth_blockchain_tree::{
     config::BlockchainTreeConfig, externals::TreeExternals, BlockchainTree, ShareableBlockchainTree,
@@ -95,6 +95,10 @@ impl<DB> TestEnv<DB> {
         }
     }
 
+    pub async fn send_set_head(&self, block_number: BlockNumber) -> Result<(), BeaconSetHeadError> {
+        self.engine_handle.set_head(block_number).await
+    }
+
     pub async fn send_forkchoice_updated(
         &self,
         state: ForkchoiceState,
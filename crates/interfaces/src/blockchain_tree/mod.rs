@@ -98,6 +98,13 @@ pub trait BlockchainTreeEngine: BlockchainTreeViewer + Send + Sync {
 
     /// Unwind tables and put it inside state
     fn unwind(&self, unwind_to: BlockNumber) -> RethResult<()>;
+
+    /// Discards all blocks of the tree and reads the canonical chain from the database again.
+    ///
+    /// This must be invoked after the canonical chain in the database was unwound by something
+    /// other than the tree, e.g. the pipeline, since the tree could otherwise extend blocks that
+    /// are no longer canonical.
+    fn reset_canonical_chain(&self) -> RethResult<()>;
 }
 
 /// Represents the kind of validation that should be performed when inserting a block.
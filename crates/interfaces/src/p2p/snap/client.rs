@@ -0,0 +1,28 @@
+use crate::p2p::{download::DownloadClient, error::PeerRequestResult};
+use futures::Future;
+use reth_eth_wire::{
+    AccountRange, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges, GetTrieNodes,
+    StorageRanges, TrieNodes,
+};
+use std::pin::Pin;
+
+/// The snap request future type
+pub type SnapFut<T> = Pin<Box<dyn Future<Output = PeerRequestResult<T>> + Send>>;
+
+/// A client capable of retrieving state from peers via the `snap` protocol.
+///
+/// The request ids of the requests are assigned by the client.
+#[auto_impl::auto_impl(&, Arc, Box)]
+pub trait SnapClient: DownloadClient {
+    /// Fetches a range of accounts of the state trie with the requested root.
+    fn get_account_range(&self, request: GetAccountRange) -> SnapFut<AccountRange>;
+
+    /// Fetches the storage ranges of the requested accounts.
+    fn get_storage_ranges(&self, request: GetStorageRanges) -> SnapFut<StorageRanges>;
+
+    /// Fetches contract bytecodes by their hashes.
+    fn get_byte_codes(&self, request: GetByteCodes) -> SnapFut<ByteCodes>;
+
+    /// Fetches trie nodes of the state trie with the requested root by their paths.
+    fn get_trie_nodes(&self, request: GetTrieNodes) -> SnapFut<TrieNodes>;
+}
@@ -0,0 +1,2 @@
+/// Traits and types for snap clients.
+pub mod client;
@@ -1,5 +1,6 @@
 use reth_primitives::{
-    Address, BlockHash, BlockHashOrNumber, BlockNumber, GotExpected, TxHashOrNumber, TxNumber, B256,
+    Address, BlockHash, BlockHashOrNumber, BlockNumber, GotExpected, SnapshotSegment,
+    TxHashOrNumber, TxNumber, B256,
 };
 use thiserror::Error;
 
@@ -91,9 +92,20 @@ pub enum ProviderError {
     /// State is not available for the given block number because it is pruned.
     #[error("state at block #{0} is pruned")]
     StateAtBlockPruned(BlockNumber),
+    /// The hashed state and the state trie are not at the same block or are behind the given
+    /// block, so historical state roots and proofs can't be computed for it.
+    #[error("hashed state and trie are not available for block #{0}")]
+    HashedStateNotAvailable(BlockNumber),
+    /// The plain state only holds the accounts and storage slots that changed after the pivot
+    /// block of snap sync, so it can't be iterated.
+    #[error("plain state is incomplete, the state was synced via snap sync")]
+    PlainStateIncomplete,
     /// Provider does not support this particular request.
     #[error("this provider does not support this request")]
     UnsupportedProvider,
+    /// Snapshot file is not found for the requested block or transaction number of the segment.
+    #[error("snapshot file of {0:?} segment not found for block or transaction #{1}")]
+    MissingSnapshot(SnapshotSegment, u64),
 }
 
 /// A root mismatch error at a given block height.
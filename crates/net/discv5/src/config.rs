@@ -0,0 +1,139 @@
+//! A set of configuration parameters for the discv5 service.
+
+use discv5::{Discv5ConfigBuilder as InnerConfigBuilder, Enr, ListenConfig};
+use reth_primitives::ForkId;
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+/// The default port for discv5 discovery.
+///
+/// discv5 binds its own UDP socket, so it can only share the port of discv4 if discv4 is disabled.
+pub const DEFAULT_DISCOVERY_V5_PORT: u16 = 9000;
+
+/// The default address for discv5 discovery: 0.0.0.0:9000
+pub const DEFAULT_DISCOVERY_V5_ADDRESS: SocketAddr =
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), DEFAULT_DISCOVERY_V5_PORT);
+
+/// Configuration of the discv5 service.
+#[derive(Clone, Debug)]
+pub struct Discv5Config {
+    /// The UDP address the service listens on.
+    pub discovery_addr: SocketAddr,
+    /// The configuration of the underlying [discv5::Discv5] instance.
+    pub discv5_config: discv5::Discv5Config,
+    /// Nodes to boot from.
+    pub bootstrap_nodes: Vec<Enr>,
+    /// The rate at which random lookups are triggered.
+    pub lookup_interval: Duration,
+    /// The duration for which banned nodes and ips are ignored. If `None`, bans last
+    /// indefinitely.
+    pub ban_duration: Option<Duration>,
+    /// The RLPx TCP port that is advertised in the local ENR.
+    pub tcp_port: u16,
+    /// The [ForkId] that is advertised under the `eth` key of the local ENR.
+    pub fork_id: Option<ForkId>,
+}
+
+impl Discv5Config {
+    /// Returns a new default builder instance
+    pub fn builder() -> Discv5ConfigBuilder {
+        Default::default()
+    }
+}
+
+impl Default for Discv5Config {
+    fn default() -> Self {
+        Discv5Config::builder().build()
+    }
+}
+
+/// Builder for [Discv5Config].
+#[derive(Clone, Debug)]
+pub struct Discv5ConfigBuilder {
+    discovery_addr: SocketAddr,
+    bootstrap_nodes: Vec<Enr>,
+    lookup_interval: Duration,
+    ban_duration: Option<Duration>,
+    tcp_port: u16,
+    fork_id: Option<ForkId>,
+}
+
+impl Default for Discv5ConfigBuilder {
+    fn default() -> Self {
+        Self {
+            discovery_addr: DEFAULT_DISCOVERY_V5_ADDRESS,
+            bootstrap_nodes: Vec::new(),
+            lookup_interval: Duration::from_secs(20),
+            ban_duration: Some(Duration::from_secs(60 * 60)), // 1 hour
+            tcp_port: reth_discv4::DEFAULT_DISCOVERY_PORT,
+            fork_id: None,
+        }
+    }
+}
+
+impl Discv5ConfigBuilder {
+    /// Sets the UDP address the service listens on.
+    pub fn discovery_addr(&mut self, addr: SocketAddr) -> &mut Self {
+        self.discovery_addr = addr;
+        self
+    }
+
+    /// Sets the UDP port the service listens on.
+    pub fn discovery_port(&mut self, port: u16) -> &mut Self {
+        self.discovery_addr.set_port(port);
+        self
+    }
+
+    /// Adds a boot node
+    pub fn add_boot_node(&mut self, node: Enr) -> &mut Self {
+        self.bootstrap_nodes.push(node);
+        self
+    }
+
+    /// Adds multiple boot nodes
+    pub fn add_boot_nodes(&mut self, nodes: impl IntoIterator<Item = Enr>) -> &mut Self {
+        self.bootstrap_nodes.extend(nodes);
+        self
+    }
+
+    /// Sets the rate at which random lookups are triggered.
+    pub fn lookup_interval(&mut self, interval: Duration) -> &mut Self {
+        self.lookup_interval = interval;
+        self
+    }
+
+    /// Sets the duration for which banned nodes and ips are ignored.
+    pub fn ban_duration(&mut self, ban_duration: Option<Duration>) -> &mut Self {
+        self.ban_duration = ban_duration;
+        self
+    }
+
+    /// Sets the RLPx TCP port that is advertised in the local ENR.
+    pub fn tcp_port(&mut self, port: u16) -> &mut Self {
+        self.tcp_port = port;
+        self
+    }
+
+    /// Sets the [ForkId] that is advertised under the `eth` key of the local ENR.
+    pub fn fork_id(&mut self, fork_id: ForkId) -> &mut Self {
+        self.fork_id = Some(fork_id);
+        self
+    }
+
+    /// Returns the configured [Discv5Config]
+    pub fn build(&self) -> Discv5Config {
+        let discv5_config =
+            InnerConfigBuilder::new(ListenConfig::from(self.discovery_addr)).build();
+        Discv5Config {
+            discovery_addr: self.discovery_addr,
+            discv5_config,
+            bootstrap_nodes: self.bootstrap_nodes.clone(),
+            lookup_interval: self.lookup_interval,
+            ban_duration: self.ban_duration,
+            tcp_port: self.tcp_port,
+            fork_id: self.fork_id,
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! Error types that can occur when launching the discv5 service.
+
+/// Errors that can occur when launching the discv5 service.
+#[derive(Debug, thiserror::Error)]
+pub enum Discv5Error {
+    /// The secret key can't be used as ENR key.
+    #[error("invalid secret key: {0}")]
+    SecretKey(String),
+    /// Failed to build the local ENR.
+    #[error("failed to build local ENR: {0}")]
+    Enr(String),
+    /// Failed to create the discv5 service.
+    #[error("failed to create discv5 service: {0}")]
+    Init(&'static str),
+    /// Failed to start the discv5 service.
+    #[error("failed to start discv5 service: {0:?}")]
+    Start(discv5::Discv5Error),
+}
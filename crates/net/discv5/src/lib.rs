@@ -0,0 +1,391 @@
+//! Discovery v5 support: <https://github.com/ethereum/devp2p/blob/master/discv5/discv5.md>
+//!
+//! This wraps the [discv5] implementation and translates the nodes it discovers into the
+//! [DiscoveryUpdate]s that are also produced by [reth_discv4], so both services can be used
+//! alongside each other.
+//!
+//! The discv5 network is shared with the consensus layer and other protocols, so only nodes that
+//! advertise the `eth` key in their ENR, see also [EIP-868](https://eips.ethereum.org/EIPS/eip-868),
+//! are reported. The advertised [ForkId] is reported along with the node, so that nodes of other
+//! chains can be filtered out by the network.
+
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/paradigmxyz/reth/main/assets/reth-docs.png",
+    html_favicon_url = "https://avatars0.githubusercontent.com/u/97369466?s=256",
+    issue_tracker_base_url = "https://github.com/paradigmxyz/reth/issues/"
+)]
+#![warn(missing_debug_implementations, missing_docs, rustdoc::all)]
+#![deny(unused_must_use, rust_2018_idioms, unreachable_pub, unused_crate_dependencies)]
+#![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
+
+use alloy_rlp::{Decodable, Encodable};
+use discv5::{
+    enr::{CombinedKey, CombinedPublicKey, EnrBuilder, EnrPublicKey, NodeId},
+    Discv5Event,
+};
+use reth_discv4::{DiscoveryUpdate, EnrForkIdEntry};
+use reth_primitives::{bytes::BytesMut, keccak256, ForkId, NodeRecord, PeerId};
+use secp256k1::{PublicKey, SecretKey};
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{sync::mpsc, task::JoinHandle};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, trace};
+
+mod config;
+mod error;
+
+pub use config::{
+    Discv5Config, Discv5ConfigBuilder, DEFAULT_DISCOVERY_V5_ADDRESS, DEFAULT_DISCOVERY_V5_PORT,
+};
+pub use error::Discv5Error;
+
+// re-export the ENR type of discv5
+pub use discv5::Enr;
+
+/// The ENR key of the `eth` [ForkId] entry.
+const ETH_ENR_KEY: &str = "eth";
+
+/// The capacity of the channel for [DiscoveryUpdate]s.
+const UPDATE_CHANNEL_CAPACITY: usize = 512;
+
+/// The frontend to interact with the spawned discv5 service.
+#[derive(Clone)]
+pub struct Discv5 {
+    /// The underlying discv5 instance.
+    discv5: Arc<discv5::Discv5>,
+    /// The address the discv5 socket is bound to.
+    local_addr: SocketAddr,
+    /// The duration for which banned nodes and ips are ignored.
+    ban_duration: Option<Duration>,
+}
+
+// === impl Discv5 ===
+
+impl Discv5 {
+    /// Starts the discv5 service with the given configuration.
+    ///
+    /// Returns the frontend, the stream of [DiscoveryUpdate]s for nodes that advertise the `eth`
+    /// ENR key and the handle of the spawned task that drives lookups and produces the updates.
+    pub async fn start(
+        secret_key: &SecretKey,
+        config: Discv5Config,
+    ) -> Result<(Self, ReceiverStream<DiscoveryUpdate>, JoinHandle<()>), Discv5Error> {
+        let Discv5Config {
+            discovery_addr,
+            discv5_config,
+            bootstrap_nodes,
+            lookup_interval,
+            ban_duration,
+            tcp_port,
+            fork_id,
+        } = config;
+
+        let enr_key = CombinedKey::secp256k1_from_bytes(&mut secret_key.secret_bytes())
+            .map_err(|err| Discv5Error::SecretKey(err.to_string()))?;
+        let local_enr = build_local_enr(&enr_key, discovery_addr, tcp_port, fork_id)?;
+
+        let mut discv5 =
+            discv5::Discv5::new(local_enr, enr_key, discv5_config).map_err(Discv5Error::Init)?;
+        discv5.start().await.map_err(Discv5Error::Start)?;
+        let events = discv5.event_stream().await.map_err(Discv5Error::Start)?;
+
+        for node in bootstrap_nodes {
+            if let Err(err) = discv5.add_enr(node) {
+                debug!(target: "discv5", %err, "Failed to add boot node");
+            }
+        }
+
+        let discv5 = Arc::new(discv5);
+        let (tx, rx) = mpsc::channel(UPDATE_CHANNEL_CAPACITY);
+        let service = tokio::task::spawn(run_service(discv5.clone(), events, tx, lookup_interval));
+
+        Ok((
+            Self { discv5, local_addr: discovery_addr, ban_duration },
+            ReceiverStream::new(rx),
+            service,
+        ))
+    }
+
+    /// Returns the local ENR of the node.
+    pub fn local_enr(&self) -> Enr {
+        self.discv5.local_enr()
+    }
+
+    /// Returns the address the discv5 socket is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Updates the RLPx TCP port advertised in the local ENR, for example to announce the external
+    /// port of a port mapping.
+    pub fn set_tcp_port(&self, port: u16) {
+        self.set_port(if self.local_addr.is_ipv4() { "tcp" } else { "tcp6" }, port)
+    }
+
+    /// Updates the UDP port advertised in the local ENR, for example to announce the external port
+    /// of a port mapping.
+    pub fn set_udp_port(&self, port: u16) {
+        self.set_port(if self.local_addr.is_ipv4() { "udp" } else { "udp6" }, port)
+    }
+
+    fn set_port(&self, key: &str, port: u16) {
+        if let Err(err) = self.discv5.enr_insert(key, &port) {
+            debug!(target: "discv5", ?err, key, port, "Failed to update port");
+        }
+    }
+
+    /// Updates the [ForkId] advertised under the `eth` key of the local ENR.
+    pub fn set_fork_id(&self, fork_id: ForkId) {
+        if let Err(err) = self.discv5.enr_insert(ETH_ENR_KEY, &RawRlp::encode(fork_id)) {
+            debug!(target: "discv5", ?err, "Failed to update eth fork id");
+        }
+    }
+
+    /// Bans the [PeerId] and [IpAddr].
+    pub fn ban(&self, peer_id: PeerId, ip: IpAddr) {
+        self.discv5.ban_node(&node_id(peer_id), self.ban_expiry());
+        self.ban_ip(ip)
+    }
+
+    /// Bans the [IpAddr].
+    pub fn ban_ip(&self, ip: IpAddr) {
+        self.discv5.ban_ip(ip, self.ban_expiry())
+    }
+
+    fn ban_expiry(&self) -> Option<Instant> {
+        self.ban_duration.map(|duration| Instant::now() + duration)
+    }
+}
+
+impl fmt::Debug for Discv5 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Discv5")
+            .field("local_enr", &self.discv5.local_enr())
+            .field("ban_duration", &self.ban_duration)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Drives random lookups and forwards discovered nodes as [DiscoveryUpdate]s until the receiver
+/// of the updates is dropped.
+async fn run_service(
+    discv5: Arc<discv5::Discv5>,
+    mut events: mpsc::Receiver<Discv5Event>,
+    updates: mpsc::Sender<DiscoveryUpdate>,
+    lookup_interval: Duration,
+) {
+    let lookups = async {
+        let mut interval = tokio::time::interval(lookup_interval);
+        loop {
+            interval.tick().await;
+            // the discovered nodes are reported as events
+            let target = NodeId::new(&rand::random());
+            if let Err(err) = discv5.find_node(target).await {
+                trace!(target: "discv5", ?err, "Lookup failed");
+            }
+        }
+    };
+
+    let forward_events = async {
+        while let Some(event) = events.recv().await {
+            let enr = match event {
+                Discv5Event::Discovered(enr) |
+                Discv5Event::EnrAdded { enr, .. } |
+                Discv5Event::SessionEstablished(enr, _) => enr,
+                _ => continue,
+            };
+            let Some(update) = enr_to_discovery_update(&enr) else {
+                trace!(target: "discv5", ?enr, "Ignoring node without eth fork id");
+                continue
+            };
+            if updates.send(update).await.is_err() {
+                // no more listeners
+                return
+            }
+        }
+    };
+
+    tokio::select! {
+        _ = lookups => {}
+        _ = forward_events => {}
+    }
+}
+
+/// Converts the ENR of a node that advertises the `eth` key to a [DiscoveryUpdate].
+///
+/// Returns `None` if the ENR lacks the `eth` entry, an address, the RLPx TCP port, or isn't signed
+/// with a secp256k1 key.
+pub fn enr_to_discovery_update(enr: &Enr) -> Option<DiscoveryUpdate> {
+    let fork_id = eth_fork_id(enr)?;
+
+    let (address, tcp_port, udp_port) = if let Some(ip) = enr.ip4() {
+        (IpAddr::V4(ip), enr.tcp4()?, enr.udp4()?)
+    } else {
+        (IpAddr::V6(enr.ip6()?), enr.tcp6()?, enr.udp6()?)
+    };
+    let id = peer_id(&enr.public_key())?;
+    let record = NodeRecord { address, tcp_port, udp_port, id };
+
+    Some(DiscoveryUpdate::Batch(vec![
+        DiscoveryUpdate::Added(record),
+        DiscoveryUpdate::EnrForkId(record, fork_id),
+    ]))
+}
+
+/// Returns the [ForkId] of the `eth` entry of the ENR.
+fn eth_fork_id(enr: &Enr) -> Option<ForkId> {
+    let mut rlp = enr.get_raw_rlp(ETH_ENR_KEY)?;
+    EnrForkIdEntry::decode(&mut rlp).ok().map(|entry| entry.fork_id)
+}
+
+/// Converts the public key of an ENR to the [PeerId] of the node.
+fn peer_id(public_key: &CombinedPublicKey) -> Option<PeerId> {
+    let CombinedPublicKey::Secp256k1(_) = public_key else { return None };
+    let public_key = PublicKey::from_slice(&public_key.encode()).ok()?;
+    Some(PeerId::from_slice(&public_key.serialize_uncompressed()[1..]))
+}
+
+/// Returns the discv5 [NodeId] of the node, which is the hash of its public key.
+fn node_id(peer_id: PeerId) -> NodeId {
+    NodeId::new(&keccak256(peer_id).0)
+}
+
+/// Builds the ENR of the local node, which advertises the RLPx TCP port and the `eth` [ForkId].
+fn build_local_enr(
+    enr_key: &CombinedKey,
+    discovery_addr: SocketAddr,
+    tcp_port: u16,
+    fork_id: Option<ForkId>,
+) -> Result<Enr, Discv5Error> {
+    let mut builder = EnrBuilder::new("v4");
+    // the address is updated by discv5 once it's confirmed by peers
+    if !discovery_addr.ip().is_unspecified() {
+        builder.ip(discovery_addr.ip());
+    }
+    if discovery_addr.is_ipv4() {
+        builder.udp4(discovery_addr.port()).tcp4(tcp_port);
+    } else {
+        builder.udp6(discovery_addr.port()).tcp6(tcp_port);
+    }
+    if let Some(fork_id) = fork_id {
+        builder.add_value(ETH_ENR_KEY, &RawRlp::encode(fork_id));
+    }
+    builder.build(enr_key).map_err(|err| Discv5Error::Enr(err.to_string()))
+}
+
+/// An [EnrForkIdEntry] that is already RLP encoded.
+///
+/// The ENR types expect values that implement the [rlp] traits, whereas reth types implement
+/// the [alloy_rlp] traits.
+struct RawRlp(Vec<u8>);
+
+impl RawRlp {
+    fn encode(fork_id: ForkId) -> Self {
+        let mut buf = BytesMut::new();
+        EnrForkIdEntry::from(fork_id).encode(&mut buf);
+        Self(buf.to_vec())
+    }
+}
+
+impl rlp::Encodable for RawRlp {
+    fn rlp_append(&self, s: &mut rlp::RlpStream) {
+        s.append_raw(&self.0, 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::ForkHash;
+    use secp256k1::SECP256K1;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    fn fork_id() -> ForkId {
+        ForkId { hash: ForkHash([0xdc, 0xe9, 0x6c, 0x2d]), next: 0 }
+    }
+
+    fn unpack(update: DiscoveryUpdate) -> (NodeRecord, ForkId) {
+        match update {
+            DiscoveryUpdate::Batch(updates) => match updates.as_slice() {
+                [DiscoveryUpdate::Added(added), DiscoveryUpdate::EnrForkId(record, fork_id)] => {
+                    assert_eq!(added, record);
+                    (*record, *fork_id)
+                }
+                _ => panic!("unexpected updates: {updates:?}"),
+            },
+            update => panic!("unexpected update: {update:?}"),
+        }
+    }
+
+    #[test]
+    fn enr_to_update() {
+        let secret_key = SecretKey::new(&mut rand::thread_rng());
+        let enr_key = CombinedKey::secp256k1_from_bytes(&mut secret_key.secret_bytes()).unwrap();
+        let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 9000));
+
+        let enr = build_local_enr(&enr_key, addr, 30303, Some(fork_id())).unwrap();
+        let expected = NodeRecord {
+            address: addr.ip(),
+            tcp_port: 30303,
+            udp_port: 9000,
+            id: PeerId::from_slice(&secret_key.public_key(SECP256K1).serialize_uncompressed()[1..]),
+        };
+        assert_eq!(unpack(enr_to_discovery_update(&enr).unwrap()), (expected, fork_id()));
+        assert_eq!(node_id(expected.id), enr.node_id());
+
+        // nodes without the eth entry are ignored
+        let enr = build_local_enr(&enr_key, addr, 30303, None).unwrap();
+        assert!(enr_to_discovery_update(&enr).is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn set_ports() {
+        let mut builder = Discv5Config::builder();
+        builder
+            .discovery_addr(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 30397)))
+            .tcp_port(30303)
+            .fork_id(fork_id());
+
+        let secret_key = SecretKey::new(&mut rand::thread_rng());
+        let (discv5, _updates, _service) =
+            Discv5::start(&secret_key, builder.build()).await.unwrap();
+        assert_eq!(discv5.local_addr().port(), 30397);
+
+        // announce the external ports of a port mapping
+        discv5.set_tcp_port(40303);
+        discv5.set_udp_port(40397);
+        let (record, _) = unpack(enr_to_discovery_update(&discv5.local_enr()).unwrap());
+        assert_eq!((record.tcp_port, record.udp_port), (40303, 40397));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn discover_boot_node() {
+        let mut builder = Discv5Config::builder();
+        builder
+            .discovery_addr(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 30399)))
+            .lookup_interval(Duration::from_millis(100))
+            .fork_id(fork_id());
+
+        let secret_key = SecretKey::new(&mut rand::thread_rng());
+        let (boot_node, _updates, _service) =
+            Discv5::start(&secret_key, builder.build()).await.unwrap();
+        let boot_node_id =
+            PeerId::from_slice(&secret_key.public_key(SECP256K1).serialize_uncompressed()[1..]);
+
+        let secret_key = SecretKey::new(&mut rand::thread_rng());
+        builder.discovery_port(30398).add_boot_node(boot_node.local_enr());
+        let (_node, mut updates, _service) =
+            Discv5::start(&secret_key, builder.build()).await.unwrap();
+
+        let update = tokio_stream::StreamExt::next(&mut updates).await.unwrap();
+        let (record, fork) = unpack(update);
+        assert_eq!(record.id, boot_node_id);
+        assert_eq!(record.udp_port, 30399);
+        assert_eq!(fork, fork_id());
+    }
+}
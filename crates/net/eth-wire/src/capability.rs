@@ -81,6 +81,11 @@ impl Capability {
         Self::eth(EthVersion::Eth68)
     }
 
+    /// Returns the `snap/1` capability.
+    pub const fn snap_1() -> Self {
+        Self::new_static("snap", 1)
+    }
+
     /// Whether this is eth v66 protocol.
     #[inline]
     pub fn is_eth_v66(&self) -> bool {
@@ -245,21 +250,37 @@ pub enum SharedCapability {
         /// This represents the message ID offset for the first message of the eth capability in
         /// the message id space.
         offset: u8,
+        /// The number of messages used by this capability.
+        messages: u8,
     },
 }
 
 impl SharedCapability {
-    /// Creates a new [`SharedCapability`] based on the given name, offset, and version.
+    /// Creates a new [`SharedCapability`] based on the given name, offset, version and number of
+    /// messages.
+    ///
+    /// The number of messages is ignored for the `eth` capability, which is determined by the
+    /// version.
     ///
     /// Returns an error if the offset is equal or less than [`MAX_RESERVED_MESSAGE_ID`].
-    pub(crate) fn new(name: &str, version: u8, offset: u8) -> Result<Self, SharedCapabilityError> {
+    pub(crate) fn new(
+        name: &str,
+        version: u8,
+        offset: u8,
+        messages: u8,
+    ) -> Result<Self, SharedCapabilityError> {
         if offset <= MAX_RESERVED_MESSAGE_ID {
             return Err(SharedCapabilityError::ReservedMessageIdOffset(offset));
         }
 
         match name {
             "eth" => Ok(Self::eth(EthVersion::try_from(version)?, offset)),
-            _ => Ok(Self::UnknownCapability { name: name.to_string().into(), version, offset }),
+            _ => Ok(Self::UnknownCapability {
+                name: name.to_string().into(),
+                version,
+                offset,
+                messages,
+            }),
         }
     }
 
@@ -309,12 +330,26 @@ impl SharedCapability {
     }
 
     /// Returns the number of protocol messages supported by this capability.
-    pub fn num_messages(&self) -> Result<u8, SharedCapabilityError> {
+    pub fn num_messages(&self) -> u8 {
         match self {
-            SharedCapability::Eth { version, .. } => Ok(version.total_messages()),
-            _ => Err(SharedCapabilityError::UnknownCapability),
+            SharedCapability::Eth { version, .. } => version.total_messages(),
+            SharedCapability::UnknownCapability { messages, .. } => *messages,
         }
     }
+
+    /// Returns true if the given message ID, relative to the start of the reserved message id
+    /// space: [`MAX_RESERVED_MESSAGE_ID`], belongs to this capability.
+    #[inline]
+    pub fn contains_relative_message_id(&self, id: u8) -> bool {
+        let offset = self.relative_message_id_offset();
+        id >= offset && id - offset < self.num_messages()
+    }
+
+    /// Returns true if this is the shared version of the given [Capability].
+    #[inline]
+    pub fn is_capability(&self, capability: &Capability) -> bool {
+        self.name() == capability.name && self.version() as usize == capability.version
+    }
 }
 
 /// Non-empty,ordered list of recognized shared capabilities.
@@ -351,6 +386,19 @@ impl SharedCapabilities {
     pub fn eth_version(&self) -> Result<u8, P2PStreamError> {
         self.eth().map(|cap| cap.version())
     }
+
+    /// Returns the shared capability that matches the given [Capability], if it was negotiated.
+    pub fn find(&self, capability: &Capability) -> Option<&SharedCapability> {
+        self.iter_caps().find(|cap| cap.is_capability(capability))
+    }
+
+    /// Returns the shared capability that the given message ID belongs to.
+    ///
+    /// The message ID is relative to the start of the reserved message id space:
+    /// [`MAX_RESERVED_MESSAGE_ID`].
+    pub fn find_by_relative_message_id(&self, id: u8) -> Option<&SharedCapability> {
+        self.iter_caps().find(|cap| cap.contains_relative_message_id(id))
+    }
 }
 
 /// Determines the offsets for each shared capability between the input list of peer
@@ -424,7 +472,12 @@ pub fn shared_capability_offsets(
     for name in shared_capability_names {
         let proto_version = shared_capabilities.get(&name).expect("shared; qed");
 
-        let shared_capability = SharedCapability::new(&name, proto_version.version as u8, offset)?;
+        let shared_capability = SharedCapability::new(
+            &name,
+            proto_version.version as u8,
+            offset,
+            proto_version.messages,
+        )?;
 
         offset += proto_version.messages;
         shared_with_offsets.push(shared_capability);
@@ -458,7 +511,7 @@ mod tests {
 
     #[test]
     fn from_eth_68() {
-        let capability = SharedCapability::new("eth", 68, MAX_RESERVED_MESSAGE_ID + 1).unwrap();
+        let capability = SharedCapability::new("eth", 68, MAX_RESERVED_MESSAGE_ID + 1, 0).unwrap();
 
         assert_eq!(capability.name(), "eth");
         assert_eq!(capability.version(), 68);
@@ -473,7 +526,7 @@ mod tests {
 
     #[test]
     fn from_eth_67() {
-        let capability = SharedCapability::new("eth", 67, MAX_RESERVED_MESSAGE_ID + 1).unwrap();
+        let capability = SharedCapability::new("eth", 67, MAX_RESERVED_MESSAGE_ID + 1, 0).unwrap();
 
         assert_eq!(capability.name(), "eth");
         assert_eq!(capability.version(), 67);
@@ -488,7 +541,7 @@ mod tests {
 
     #[test]
     fn from_eth_66() {
-        let capability = SharedCapability::new("eth", 66, MAX_RESERVED_MESSAGE_ID + 1).unwrap();
+        let capability = SharedCapability::new("eth", 66, MAX_RESERVED_MESSAGE_ID + 1, 0).unwrap();
 
         assert_eq!(capability.name(), "eth");
         assert_eq!(capability.version(), 66);
@@ -559,4 +612,46 @@ mod tests {
             Err(P2PStreamError::HandshakeError(P2PHandshakeError::NoSharedCapabilities))
         ))
     }
+
+    #[test]
+    fn test_multiple_capability_offsets() {
+        let local_capabilities = vec![
+            EthVersion::Eth68.into(),
+            Protocol::snap_1(),
+            Protocol::new(Capability::new_static("echo", 1), 2),
+        ];
+        let peer_capabilities = vec![
+            EthVersion::Eth68.into(),
+            Capability::new_static("echo", 1),
+            Capability::new_static("snap", 1),
+        ];
+
+        let shared = SharedCapabilities::try_new(local_capabilities, peer_capabilities).unwrap();
+        let caps = shared.iter_caps().collect::<Vec<_>>();
+
+        // capabilities are ordered alphabetically
+        assert_eq!(caps[0].name(), "echo");
+        assert_eq!(caps[0].message_id_offset(), MAX_RESERVED_MESSAGE_ID + 1);
+        assert_eq!(caps[0].num_messages(), 2);
+        assert_eq!(caps[1].name(), "eth");
+        assert_eq!(caps[1].relative_message_id_offset(), 2);
+        assert_eq!(caps[2].name(), "snap");
+        assert_eq!(caps[2].relative_message_id_offset(), 2 + EthVersion::Eth68.total_messages());
+        assert_eq!(caps[2].num_messages(), 8);
+
+        assert_eq!(shared.find_by_relative_message_id(1).unwrap().name(), "echo");
+        assert_eq!(shared.find_by_relative_message_id(2).unwrap().name(), "eth");
+        assert_eq!(
+            shared
+                .find_by_relative_message_id(2 + EthVersion::Eth68.total_messages())
+                .unwrap()
+                .name(),
+            "snap"
+        );
+        assert!(shared
+            .find_by_relative_message_id(2 + EthVersion::Eth68.total_messages() + 8)
+            .is_none());
+        assert_eq!(shared.find(&Capability::new_static("echo", 1)), Some(caps[0]));
+        assert!(shared.find(&Capability::new_static("echo", 2)).is_none());
+    }
 }
@@ -33,6 +33,12 @@ pub enum P2PStreamError {
     ParseSharedCapability(#[from] SharedCapabilityError),
     #[error("capability not supported on stream to this peer")]
     CapabilityNotShared,
+    #[error("message id {id} exceeds the {messages} messages of the capability")]
+    CapabilityMessageIdOutOfRange { id: u8, messages: u8 },
+    #[error("message id {0} does not belong to a shared capability")]
+    UnknownCapabilityMessageId(u8),
+    #[error("too many received messages of capability {0} buffered")]
+    CapabilityReceiveBufferFull(String),
     #[error("mismatched protocol version in Hello message: {0}")]
     MismatchedProtocolVersion(GotExpected<ProtocolVersion>),
     #[error("started ping task before the handshake completed")]
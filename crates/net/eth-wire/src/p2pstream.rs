@@ -21,9 +21,10 @@ use std::{
     task::{ready, Context, Poll},
     time::Duration,
 };
+use tokio::sync::mpsc::{self, error::TrySendError};
 use tokio_stream::Stream;
 
-use crate::capability::SharedCapabilities;
+use crate::capability::{SharedCapabilities, SharedCapability};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use tracing::{debug, trace};
@@ -63,6 +64,13 @@ const GRACE_PERIOD: Duration = Duration::from_secs(2);
 /// encoded data.
 const MAX_P2P_CAPACITY: usize = 2;
 
+/// [`CAPABILITY_CHANNEL_CAPACITY`] is the maximum number of received messages of a capability other
+/// than the primary one that are buffered until the capability consumes them, see
+/// [P2PStream::take_capability_messages].
+///
+/// Peers that send more messages than the capability consumes are disconnected.
+const CAPABILITY_CHANNEL_CAPACITY: usize = 1024;
+
 /// An un-authenticated [`P2PStream`]. This is consumed and returns a [`P2PStream`] after the
 /// `Hello` handshake is completed.
 #[pin_project]
@@ -225,9 +233,14 @@ where
 ///
 /// See also <https://github.com/ethereum/devp2p/blob/master/rlpx.md#message-id-based-multiplexing>
 ///
-/// This stream emits Bytes that start with the normalized message id, so that the first byte of
-/// each message starts from 0. If this stream only supports a single capability, for example `eth`
-/// then the first byte of each message will match [EthMessageID](crate::types::EthMessageID).
+/// This stream emits Bytes of the _primary_ capability, which is `eth` if it is shared, otherwise
+/// the first shared capability. Each message starts with the normalized message id, so that the
+/// first byte of each message starts from 0. For `eth` the first byte of each message will match
+/// [EthMessageID](crate::types::EthMessageID).
+///
+/// Messages of all other shared capabilities are not emitted by this stream, but are sent to a
+/// dedicated channel per capability, see [P2PStream::take_capability_messages], and can be sent
+/// with [P2PStream::start_send_capability].
 #[pin_project]
 #[derive(Debug)]
 pub struct P2PStream<S> {
@@ -246,6 +259,13 @@ pub struct P2PStream<S> {
     /// The supported capability for this stream.
     shared_capabilities: SharedCapabilities,
 
+    /// The relative message id offset of the primary capability, see
+    /// [SharedCapability::relative_message_id_offset].
+    primary_offset: u8,
+
+    /// The channels for the messages of all shared capabilities other than the primary one.
+    capability_channels: Vec<CapabilityChannel>,
+
     /// Outgoing messages buffered for sending to the underlying stream.
     outgoing_messages: VecDeque<Bytes>,
 
@@ -263,12 +283,28 @@ impl<S> P2PStream<S> {
     /// New [`P2PStream`]s are assumed to have completed the `p2p` handshake successfully and are
     /// ready to send and receive subprotocol messages.
     pub fn new(inner: S, shared_capabilities: SharedCapabilities) -> Self {
+        let primary = shared_capabilities
+            .eth()
+            .ok()
+            .or_else(|| shared_capabilities.iter_caps().next())
+            .cloned();
+        let primary_offset =
+            primary.as_ref().map(SharedCapability::relative_message_id_offset).unwrap_or_default();
+        let capability_channels = shared_capabilities
+            .iter_caps()
+            .filter(|cap| Some(*cap) != primary.as_ref())
+            .cloned()
+            .map(CapabilityChannel::new)
+            .collect();
+
         Self {
             inner,
             encoder: snap::raw::Encoder::new(),
             decoder: snap::raw::Decoder::new(),
             pinger: Pinger::new(PING_INTERVAL, PING_TIMEOUT),
             shared_capabilities,
+            primary_offset,
+            capability_channels,
             outgoing_messages: VecDeque::new(),
             outgoing_message_buffer_capacity: MAX_P2P_CAPACITY,
             disconnecting: false,
@@ -302,6 +338,77 @@ impl<S> P2PStream<S> {
         self.disconnecting
     }
 
+    /// Takes the receiver for all messages of the given shared capability.
+    ///
+    /// The first byte of each message is the message id relative to the capability's message id
+    /// offset. Messages received before the receiver is taken are buffered in the channel,
+    /// messages received after the receiver is dropped are discarded. The stream fails with
+    /// [P2PStreamError::CapabilityReceiveBufferFull] if more than [CAPABILITY_CHANNEL_CAPACITY]
+    /// messages are buffered.
+    ///
+    /// Returns `None` if the capability is the primary capability of this stream, which is emitted
+    /// by the [Stream] impl, if it is not shared, or if the receiver was already taken.
+    pub fn take_capability_messages(
+        &mut self,
+        capability: &SharedCapability,
+    ) -> Option<mpsc::Receiver<BytesMut>> {
+        self.capability_channels
+            .iter_mut()
+            .find(|channel| &channel.capability == capability)
+            .and_then(|channel| channel.from_wire.take())
+    }
+
+    /// Queues in a message of the given shared capability.
+    ///
+    /// The first byte of the message is the message id relative to the capability's message id
+    /// offset. Like [Sink::start_send], this must be preceded by a successful call to
+    /// [Sink::poll_ready].
+    pub fn start_send_capability(
+        &mut self,
+        capability: &SharedCapability,
+        item: Bytes,
+    ) -> Result<(), P2PStreamError> {
+        let id = *item.first().ok_or(P2PStreamError::EmptyProtocolMessage)?;
+        if id >= capability.num_messages() {
+            return Err(P2PStreamError::CapabilityMessageIdOutOfRange {
+                id,
+                messages: capability.num_messages(),
+            })
+        }
+        self.queue_subprotocol_message(capability.relative_message_id_offset() + id, item)
+    }
+
+    /// Compresses and queues in a subprotocol message with the given relative message id, see
+    /// [SharedCapability::relative_message_id_offset].
+    fn queue_subprotocol_message(&mut self, id: u8, item: Bytes) -> Result<(), P2PStreamError> {
+        // ensure we have free capacity
+        if !self.has_outgoing_capacity() {
+            return Err(P2PStreamError::SendBufferFull)
+        }
+
+        let mut compressed = BytesMut::zeroed(1 + snap::raw::max_compress_len(item.len() - 1));
+        let compressed_size =
+            self.encoder.compress(&item[1..], &mut compressed[1..]).map_err(|err| {
+                debug!(
+                    ?err,
+                    msg=%hex::encode(&item[1..]),
+                    "error compressing p2p message"
+                );
+                err
+            })?;
+
+        // truncate the compressed buffer to the actual compressed size (plus one for the message
+        // id)
+        compressed.truncate(compressed_size + 1);
+
+        // all messages sent in this stream are subprotocol messages, so we need to switch the
+        // message id based on the offset
+        compressed[0] = id + MAX_RESERVED_MESSAGE_ID + 1;
+        self.outgoing_messages.push_back(compressed.freeze());
+
+        Ok(())
+    }
+
     /// Returns `true` if the stream has outgoing capacity.
     fn has_outgoing_capacity(&self) -> bool {
         self.outgoing_messages.len() < self.outgoing_message_buffer_capacity
@@ -484,7 +591,32 @@ where
                     //  * `eth/67` is reserved message IDs 0x10 - 0x19.
                     //  * `qrs/65` is reserved message IDs 0x1a - 0x21.
                     //
-                    decompress_buf[0] = bytes[0] - MAX_RESERVED_MESSAGE_ID - 1;
+                    let relative_id = bytes[0] - MAX_RESERVED_MESSAGE_ID - 1;
+
+                    if let Some(channel) = this.capability_channels.iter().find(|channel| {
+                        channel.capability.contains_relative_message_id(relative_id)
+                    }) {
+                        decompress_buf[0] =
+                            relative_id - channel.capability.relative_message_id_offset();
+                        match channel.to_capability.try_send(decompress_buf) {
+                            // the receiver is dropped if the capability is not used
+                            Ok(()) | Err(TrySendError::Closed(_)) => {}
+                            Err(TrySendError::Full(_)) => {
+                                return Poll::Ready(Some(Err(
+                                    P2PStreamError::CapabilityReceiveBufferFull(
+                                        channel.capability.name().to_string(),
+                                    ),
+                                )))
+                            }
+                        }
+                        continue
+                    }
+
+                    // Unknown message ids above all capabilities are passed on to the primary
+                    // capability which rejects them.
+                    decompress_buf[0] = relative_id
+                        .checked_sub(this.primary_offset)
+                        .ok_or(P2PStreamError::UnknownCapabilityMessageId(relative_id))?;
 
                     return Poll::Ready(Some(Ok(decompress_buf)))
                 }
@@ -539,34 +671,10 @@ where
     }
 
     fn start_send(self: Pin<&mut Self>, item: Bytes) -> Result<(), Self::Error> {
-        // ensure we have free capacity
-        if !self.has_outgoing_capacity() {
-            return Err(P2PStreamError::SendBufferFull)
-        }
-
-        let this = self.project();
-
-        let mut compressed = BytesMut::zeroed(1 + snap::raw::max_compress_len(item.len() - 1));
-        let compressed_size =
-            this.encoder.compress(&item[1..], &mut compressed[1..]).map_err(|err| {
-                debug!(
-                    ?err,
-                    msg=%hex::encode(&item[1..]),
-                    "error compressing p2p message"
-                );
-                err
-            })?;
-
-        // truncate the compressed buffer to the actual compressed size (plus one for the message
-        // id)
-        compressed.truncate(compressed_size + 1);
-
-        // all messages sent in this stream are subprotocol messages, so we need to switch the
-        // message id based on the offset
-        compressed[0] = item[0] + MAX_RESERVED_MESSAGE_ID + 1;
-        this.outgoing_messages.push_back(compressed.freeze());
-
-        Ok(())
+        let this = self.get_mut();
+        // messages sent via the sink belong to the primary capability
+        let id = item[0] + this.primary_offset;
+        this.queue_subprotocol_message(id, item)
     }
 
     /// Returns Poll::Ready(Ok(())) when no buffered items remain and the sink has been successfully
@@ -595,6 +703,24 @@ where
     }
 }
 
+/// The channel for the messages of a shared capability that is multiplexed over a [P2PStream].
+#[derive(Debug)]
+struct CapabilityChannel {
+    /// The shared capability.
+    capability: SharedCapability,
+    /// Sender half for messages received from the wire.
+    to_capability: mpsc::Sender<BytesMut>,
+    /// Receiver half, until it is taken by [P2PStream::take_capability_messages].
+    from_wire: Option<mpsc::Receiver<BytesMut>>,
+}
+
+impl CapabilityChannel {
+    fn new(capability: SharedCapability) -> Self {
+        let (to_capability, from_wire) = mpsc::channel(CAPABILITY_CHANNEL_CAPACITY);
+        Self { capability, to_capability, from_wire: Some(from_wire) }
+    }
+}
+
 /// This represents only the reserved `p2p` subprotocol messages.
 #[derive_arbitrary(rlp)]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -785,7 +911,11 @@ impl Decodable for ProtocolVersion {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{capability::SharedCapability, DisconnectReason, EthVersion};
+    use crate::{
+        capability::{Capability, SharedCapability},
+        protocol::Protocol,
+        DisconnectReason, EthVersion,
+    };
     use reth_discv4::DEFAULT_DISCOVERY_PORT;
     use reth_ecies::util::pk2id;
     use secp256k1::{SecretKey, SECP256K1};
@@ -890,6 +1020,110 @@ mod tests {
         handle.await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_multiplexed_capabilities() {
+        // `echo` is ordered before `eth`, so the `eth` messages are offset by the `echo` messages
+        let echo = Protocol::new(Capability::new_static("echo", 1), 2);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let local_addr = listener.local_addr().unwrap();
+
+        let server_echo = echo.clone();
+        let handle = tokio::spawn(async move {
+            let (incoming, _) = listener.accept().await.unwrap();
+            let stream = crate::PassthroughCodec::default().framed(incoming);
+
+            let (mut server_hello, _) = eth_hello();
+            server_hello.protocols.push(server_echo.clone());
+
+            let (mut p2p_stream, _) =
+                UnauthedP2PStream::new(stream).handshake(server_hello).await.unwrap();
+            let echo_cap =
+                p2p_stream.shared_capabilities().find(&server_echo.cap).cloned().unwrap();
+            let mut echo_messages = p2p_stream.take_capability_messages(&echo_cap).unwrap();
+
+            // the `eth` message is emitted by the stream, the `echo` message is routed to its
+            // channel
+            let eth_msg = p2p_stream.next().await.unwrap().unwrap();
+            assert_eq!(&eth_msg[..], &[0x03, 0xc0]);
+            let echo_msg = echo_messages.try_recv().unwrap();
+            assert_eq!(&echo_msg[..], &[0x01, 0xc1, 0x80]);
+        });
+
+        let outgoing = TcpStream::connect(local_addr).await.unwrap();
+        let sink = crate::PassthroughCodec::default().framed(outgoing);
+
+        let (mut client_hello, _) = eth_hello();
+        client_hello.protocols.push(echo.clone());
+
+        let (mut p2p_stream, _) =
+            UnauthedP2PStream::new(sink).handshake(client_hello).await.unwrap();
+        let echo_cap = p2p_stream.shared_capabilities().find(&echo.cap).cloned().unwrap();
+        assert_eq!(echo_cap.relative_message_id_offset(), 0);
+        assert_eq!(p2p_stream.shared_capabilities().eth().unwrap().relative_message_id_offset(), 2);
+
+        // the primary capability can't be taken
+        let eth_cap = p2p_stream.shared_capabilities().eth().cloned().unwrap();
+        assert!(p2p_stream.take_capability_messages(&eth_cap).is_none());
+
+        // message ids must be within the range of the capability
+        assert!(matches!(
+            p2p_stream.start_send_capability(&echo_cap, Bytes::from_static(&[0x02, 0xc0])),
+            Err(P2PStreamError::CapabilityMessageIdOutOfRange { id: 2, messages: 2 })
+        ));
+
+        futures::future::poll_fn(|cx| p2p_stream.poll_ready_unpin(cx)).await.unwrap();
+        p2p_stream
+            .start_send_capability(&echo_cap, Bytes::from_static(&[0x01, 0xc1, 0x80]))
+            .unwrap();
+        p2p_stream.send(Bytes::from_static(&[0x03, 0xc0])).await.unwrap();
+
+        // make sure the server receives the messages and asserts before ending the test
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_capability_receive_buffer_full() {
+        let echo = Protocol::new(Capability::new_static("echo", 1), 2);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let local_addr = listener.local_addr().unwrap();
+
+        let server_echo = echo.clone();
+        let handle = tokio::spawn(async move {
+            let (incoming, _) = listener.accept().await.unwrap();
+            let stream = crate::PassthroughCodec::default().framed(incoming);
+
+            let (mut server_hello, _) = eth_hello();
+            server_hello.protocols.push(server_echo);
+
+            // the `echo` messages are never consumed
+            let (mut p2p_stream, _) =
+                UnauthedP2PStream::new(stream).handshake(server_hello).await.unwrap();
+            let err = p2p_stream.next().await.unwrap().unwrap_err();
+            assert!(
+                matches!(err, P2PStreamError::CapabilityReceiveBufferFull(name) if name == "echo")
+            );
+        });
+
+        let outgoing = TcpStream::connect(local_addr).await.unwrap();
+        let sink = crate::PassthroughCodec::default().framed(outgoing);
+
+        let (mut client_hello, _) = eth_hello();
+        client_hello.protocols.push(echo.clone());
+
+        let (mut p2p_stream, _) =
+            UnauthedP2PStream::new(sink).handshake(client_hello).await.unwrap();
+        let echo_cap = p2p_stream.shared_capabilities().find(&echo.cap).cloned().unwrap();
+        for _ in 0..=CAPABILITY_CHANNEL_CAPACITY {
+            futures::future::poll_fn(|cx| p2p_stream.poll_ready_unpin(cx)).await.unwrap();
+            p2p_stream
+                .start_send_capability(&echo_cap, Bytes::from_static(&[0x01, 0xc1, 0x80]))
+                .unwrap();
+        }
+        p2p_stream.flush().await.unwrap();
+
+        handle.await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_handshake_disconnect() {
         // create a p2p stream and server, then confirm that the two are authed
@@ -1,6 +1,6 @@
 //! A Protocol defines a P2P subprotocol in a RLPx connection
 
-use crate::{capability::Capability, EthVersion};
+use crate::{capability::Capability, types::snap::SNAP_MESSAGES, EthVersion};
 
 /// Type that represents a [Capability] and the number of messages it uses.
 ///
@@ -45,6 +45,11 @@ impl Protocol {
         Self::eth(EthVersion::Eth68)
     }
 
+    /// Returns the `snap/1` protocol.
+    pub const fn snap_1() -> Self {
+        Self::new(Capability::snap_1(), SNAP_MESSAGES)
+    }
+
     /// Consumes the type and returns a tuple of the [Capability] and number of messages.
     #[inline]
     pub(crate) fn split(self) -> (Capability, u8) {
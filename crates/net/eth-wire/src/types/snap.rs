@@ -0,0 +1,592 @@
+//! Implements the `snap/1` protocol messages.
+//!
+//! See also <https://github.com/ethereum/devp2p/blob/master/caps/snap.md>
+
+use alloy_rlp::{Decodable, Encodable, Header, RlpDecodable, RlpEncodable, EMPTY_STRING_CODE};
+use reth_codecs::derive_arbitrary;
+use reth_primitives::{
+    bytes::{Buf, BufMut},
+    constants::EMPTY_ROOT_HASH,
+    Account, Bytes, B256, KECCAK_EMPTY, U256,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The number of messages of the `snap/1` protocol.
+pub const SNAP_MESSAGES: u8 = 8;
+
+/// Represents message IDs for `snap/1` protocol messages.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SnapMessageId {
+    /// Requests an account range.
+    GetAccountRange = 0x00,
+    /// Response to [SnapMessageId::GetAccountRange].
+    AccountRange = 0x01,
+    /// Requests storage ranges of one or more accounts.
+    GetStorageRanges = 0x02,
+    /// Response to [SnapMessageId::GetStorageRanges].
+    StorageRanges = 0x03,
+    /// Requests contract bytecodes by hash.
+    GetByteCodes = 0x04,
+    /// Response to [SnapMessageId::GetByteCodes].
+    ByteCodes = 0x05,
+    /// Requests state trie nodes by path.
+    GetTrieNodes = 0x06,
+    /// Response to [SnapMessageId::GetTrieNodes].
+    TrieNodes = 0x07,
+}
+
+impl Encodable for SnapMessageId {
+    fn encode(&self, out: &mut dyn BufMut) {
+        out.put_u8(*self as u8);
+    }
+    fn length(&self) -> usize {
+        1
+    }
+}
+
+impl Decodable for SnapMessageId {
+    fn decode(buf: &mut &[u8]) -> alloy_rlp::Result<Self> {
+        let id = buf.first().ok_or(alloy_rlp::Error::InputTooShort)?;
+        let id = match id {
+            0x00 => SnapMessageId::GetAccountRange,
+            0x01 => SnapMessageId::AccountRange,
+            0x02 => SnapMessageId::GetStorageRanges,
+            0x03 => SnapMessageId::StorageRanges,
+            0x04 => SnapMessageId::GetByteCodes,
+            0x05 => SnapMessageId::ByteCodes,
+            0x06 => SnapMessageId::GetTrieNodes,
+            0x07 => SnapMessageId::TrieNodes,
+            _ => return Err(alloy_rlp::Error::Custom("Invalid message ID")),
+        };
+        buf.advance(1);
+        Ok(id)
+    }
+}
+
+/// A `snap/1` protocol message, consisting of the message id followed by the RLP encoded message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SnapMessage {
+    /// Represents a [GetAccountRange] request.
+    GetAccountRange(GetAccountRange),
+    /// Represents an [AccountRange] response.
+    AccountRange(AccountRange),
+    /// Represents a [GetStorageRanges] request.
+    GetStorageRanges(GetStorageRanges),
+    /// Represents a [StorageRanges] response.
+    StorageRanges(StorageRanges),
+    /// Represents a [GetByteCodes] request.
+    GetByteCodes(GetByteCodes),
+    /// Represents a [ByteCodes] response.
+    ByteCodes(ByteCodes),
+    /// Represents a [GetTrieNodes] request.
+    GetTrieNodes(GetTrieNodes),
+    /// Represents a [TrieNodes] response.
+    TrieNodes(TrieNodes),
+}
+
+impl SnapMessage {
+    /// Returns the message's ID.
+    pub fn message_id(&self) -> SnapMessageId {
+        match self {
+            SnapMessage::GetAccountRange(_) => SnapMessageId::GetAccountRange,
+            SnapMessage::AccountRange(_) => SnapMessageId::AccountRange,
+            SnapMessage::GetStorageRanges(_) => SnapMessageId::GetStorageRanges,
+            SnapMessage::StorageRanges(_) => SnapMessageId::StorageRanges,
+            SnapMessage::GetByteCodes(_) => SnapMessageId::GetByteCodes,
+            SnapMessage::ByteCodes(_) => SnapMessageId::ByteCodes,
+            SnapMessage::GetTrieNodes(_) => SnapMessageId::GetTrieNodes,
+            SnapMessage::TrieNodes(_) => SnapMessageId::TrieNodes,
+        }
+    }
+
+    /// Returns the request id of the message.
+    pub fn request_id(&self) -> u64 {
+        match self {
+            SnapMessage::GetAccountRange(msg) => msg.request_id,
+            SnapMessage::AccountRange(msg) => msg.request_id,
+            SnapMessage::GetStorageRanges(msg) => msg.request_id,
+            SnapMessage::StorageRanges(msg) => msg.request_id,
+            SnapMessage::GetByteCodes(msg) => msg.request_id,
+            SnapMessage::ByteCodes(msg) => msg.request_id,
+            SnapMessage::GetTrieNodes(msg) => msg.request_id,
+            SnapMessage::TrieNodes(msg) => msg.request_id,
+        }
+    }
+
+    /// Returns true if the message is a request.
+    pub fn is_request(&self) -> bool {
+        matches!(
+            self,
+            SnapMessage::GetAccountRange(_) |
+                SnapMessage::GetStorageRanges(_) |
+                SnapMessage::GetByteCodes(_) |
+                SnapMessage::GetTrieNodes(_)
+        )
+    }
+}
+
+impl Encodable for SnapMessage {
+    fn encode(&self, out: &mut dyn BufMut) {
+        self.message_id().encode(out);
+        match self {
+            SnapMessage::GetAccountRange(msg) => msg.encode(out),
+            SnapMessage::AccountRange(msg) => msg.encode(out),
+            SnapMessage::GetStorageRanges(msg) => msg.encode(out),
+            SnapMessage::StorageRanges(msg) => msg.encode(out),
+            SnapMessage::GetByteCodes(msg) => msg.encode(out),
+            SnapMessage::ByteCodes(msg) => msg.encode(out),
+            SnapMessage::GetTrieNodes(msg) => msg.encode(out),
+            SnapMessage::TrieNodes(msg) => msg.encode(out),
+        }
+    }
+    fn length(&self) -> usize {
+        let payload_length = match self {
+            SnapMessage::GetAccountRange(msg) => msg.length(),
+            SnapMessage::AccountRange(msg) => msg.length(),
+            SnapMessage::GetStorageRanges(msg) => msg.length(),
+            SnapMessage::StorageRanges(msg) => msg.length(),
+            SnapMessage::GetByteCodes(msg) => msg.length(),
+            SnapMessage::ByteCodes(msg) => msg.length(),
+            SnapMessage::GetTrieNodes(msg) => msg.length(),
+            SnapMessage::TrieNodes(msg) => msg.length(),
+        };
+        self.message_id().length() + payload_length
+    }
+}
+
+impl Decodable for SnapMessage {
+    fn decode(buf: &mut &[u8]) -> alloy_rlp::Result<Self> {
+        let message = match SnapMessageId::decode(buf)? {
+            SnapMessageId::GetAccountRange => {
+                SnapMessage::GetAccountRange(GetAccountRange::decode(buf)?)
+            }
+            SnapMessageId::AccountRange => SnapMessage::AccountRange(AccountRange::decode(buf)?),
+            SnapMessageId::GetStorageRanges => {
+                SnapMessage::GetStorageRanges(GetStorageRanges::decode(buf)?)
+            }
+            SnapMessageId::StorageRanges => SnapMessage::StorageRanges(StorageRanges::decode(buf)?),
+            SnapMessageId::GetByteCodes => SnapMessage::GetByteCodes(GetByteCodes::decode(buf)?),
+            SnapMessageId::ByteCodes => SnapMessage::ByteCodes(ByteCodes::decode(buf)?),
+            SnapMessageId::GetTrieNodes => SnapMessage::GetTrieNodes(GetTrieNodes::decode(buf)?),
+            SnapMessageId::TrieNodes => SnapMessage::TrieNodes(TrieNodes::decode(buf)?),
+        };
+        Ok(message)
+    }
+}
+
+/// A request for the consecutive accounts of the state trie with the given root, starting at
+/// `starting_hash`.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GetAccountRange {
+    /// The id of the request.
+    pub request_id: u64,
+    /// The root hash of the account trie to serve.
+    pub root_hash: B256,
+    /// The account hash of the first account to retrieve.
+    pub starting_hash: B256,
+    /// The account hash after which to stop serving data.
+    pub limit_hash: B256,
+    /// The soft limit at which to stop returning data.
+    pub response_bytes: u64,
+}
+
+/// The response to [`GetAccountRange`], containing the consecutive accounts starting at the
+/// requested hash and the merkle proofs for the boundaries of the range.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AccountRange {
+    /// The id of the request this is a response to.
+    pub request_id: u64,
+    /// The accounts of the range, in ascending order of their hashes.
+    pub accounts: Vec<AccountData>,
+    /// The trie nodes that prove the first and the last account of the range.
+    pub proof: Vec<Bytes>,
+}
+
+/// An account of an [`AccountRange`] response.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AccountData {
+    /// The hash of the account address.
+    pub hash: B256,
+    /// The account in slim format.
+    pub body: SlimAccount,
+}
+
+/// An account in the "slim" format of the snap protocol.
+///
+/// The slim format is the RLP encoding of the account as it is stored in the state trie, except
+/// that the empty storage root and the empty code hash are encoded as empty strings.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SlimAccount {
+    /// Account nonce.
+    pub nonce: u64,
+    /// Account balance.
+    pub balance: U256,
+    /// Account's storage root.
+    pub storage_root: B256,
+    /// Hash of the account's bytecode.
+    pub code_hash: B256,
+}
+
+impl SlimAccount {
+    /// Creates the slim account from the account and its storage root.
+    pub fn new(account: Account, storage_root: B256) -> Self {
+        Self {
+            nonce: account.nonce,
+            balance: account.balance,
+            storage_root,
+            code_hash: account.bytecode_hash.unwrap_or(KECCAK_EMPTY),
+        }
+    }
+
+    /// Returns the [Account] without the storage root.
+    pub fn account(&self) -> Account {
+        Account {
+            nonce: self.nonce,
+            balance: self.balance,
+            bytecode_hash: (self.code_hash != KECCAK_EMPTY).then_some(self.code_hash),
+        }
+    }
+
+    fn slim_storage_root(&self) -> &[u8] {
+        if self.storage_root == EMPTY_ROOT_HASH {
+            &[]
+        } else {
+            self.storage_root.as_slice()
+        }
+    }
+
+    fn slim_code_hash(&self) -> &[u8] {
+        if self.code_hash == KECCAK_EMPTY {
+            &[]
+        } else {
+            self.code_hash.as_slice()
+        }
+    }
+
+    fn payload_length(&self) -> usize {
+        self.nonce.length() +
+            self.balance.length() +
+            self.slim_storage_root().length() +
+            self.slim_code_hash().length()
+    }
+}
+
+impl Encodable for SlimAccount {
+    fn encode(&self, out: &mut dyn BufMut) {
+        Header { list: true, payload_length: self.payload_length() }.encode(out);
+        self.nonce.encode(out);
+        self.balance.encode(out);
+        self.slim_storage_root().encode(out);
+        self.slim_code_hash().encode(out);
+    }
+    fn length(&self) -> usize {
+        let payload_length = self.payload_length();
+        payload_length + alloy_rlp::length_of_length(payload_length)
+    }
+}
+
+impl Decodable for SlimAccount {
+    fn decode(buf: &mut &[u8]) -> alloy_rlp::Result<Self> {
+        let header = Header::decode(buf)?;
+        if !header.list {
+            return Err(alloy_rlp::Error::UnexpectedString)
+        }
+        let started_len = buf.len();
+
+        let nonce = u64::decode(buf)?;
+        let balance = U256::decode(buf)?;
+        let storage_root = decode_slim_hash(buf, EMPTY_ROOT_HASH)?;
+        let code_hash = decode_slim_hash(buf, KECCAK_EMPTY)?;
+
+        let consumed = started_len - buf.len();
+        if consumed != header.payload_length {
+            return Err(alloy_rlp::Error::ListLengthMismatch {
+                expected: header.payload_length,
+                got: consumed,
+            })
+        }
+
+        Ok(Self { nonce, balance, storage_root, code_hash })
+    }
+}
+
+/// Decodes a hash of a slim account, which is encoded as an empty string if it is the default.
+fn decode_slim_hash(buf: &mut &[u8], default: B256) -> alloy_rlp::Result<B256> {
+    if buf.first() == Some(&EMPTY_STRING_CODE) {
+        buf.advance(1);
+        return Ok(default)
+    }
+    B256::decode(buf)
+}
+
+/// A request for the storage slots of multiple accounts of the state trie with the given root.
+///
+/// The `starting_hash` and `limit_hash` only apply to the first and the last account
+/// respectively, and are intended to continue the retrieval of a large storage trie.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GetStorageRanges {
+    /// The id of the request.
+    pub request_id: u64,
+    /// The root hash of the account trie to serve.
+    pub root_hash: B256,
+    /// The hashes of the addresses of the accounts to retrieve the storage for.
+    pub account_hashes: Vec<B256>,
+    /// The storage slot hash of the first slot to retrieve, empty for the first slot.
+    pub starting_hash: Bytes,
+    /// The storage slot hash after which to stop serving, empty for the last slot.
+    pub limit_hash: Bytes,
+    /// The soft limit at which to stop returning data.
+    pub response_bytes: u64,
+}
+
+impl GetStorageRanges {
+    /// Returns the hash of the first storage slot to retrieve.
+    ///
+    /// Hashes shorter than 32 bytes are right padded with zeroes.
+    pub fn starting_hash(&self) -> B256 {
+        let mut hash = B256::ZERO;
+        let len = self.starting_hash.len().min(32);
+        hash[..len].copy_from_slice(&self.starting_hash[..len]);
+        hash
+    }
+
+    /// Returns the hash of the storage slot after which to stop serving.
+    ///
+    /// An empty limit is the maximum hash, shorter hashes are right padded with `0xff`.
+    pub fn limit_hash(&self) -> B256 {
+        let mut hash = B256::repeat_byte(0xff);
+        let len = self.limit_hash.len().min(32);
+        hash[..len].copy_from_slice(&self.limit_hash[..len]);
+        hash
+    }
+}
+
+/// The response to [`GetStorageRanges`], containing the storage slots of the requested accounts.
+///
+/// If the storage of the last account is incomplete, the proof for the boundaries of its range is
+/// attached.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StorageRanges {
+    /// The id of the request this is a response to.
+    pub request_id: u64,
+    /// The storage slots of every served account, in ascending order of their hashes.
+    #[cfg_attr(
+        any(test, feature = "arbitrary"),
+        proptest(
+            strategy = "proptest::collection::vec(proptest::collection::vec(proptest::arbitrary::any::<StorageData>(), 0..=20), 0..=5)"
+        )
+    )]
+    pub slots: Vec<Vec<StorageData>>,
+    /// The trie nodes that prove the boundaries of the storage range of the last account.
+    pub proof: Vec<Bytes>,
+}
+
+/// A storage slot of a [`StorageRanges`] response.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StorageData {
+    /// The hash of the storage slot key.
+    pub hash: B256,
+    /// The RLP encoded value of the storage slot.
+    pub data: Bytes,
+}
+
+/// A request for contract bytecodes by their code hashes.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GetByteCodes {
+    /// The id of the request.
+    pub request_id: u64,
+    /// The code hashes of the bytecodes to retrieve.
+    pub hashes: Vec<B256>,
+    /// The soft limit at which to stop returning data.
+    pub response_bytes: u64,
+}
+
+/// The response to [`GetByteCodes`], containing the requested bytecodes in request order.
+///
+/// Bytecodes that are not available are skipped.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ByteCodes {
+    /// The id of the request this is a response to.
+    pub request_id: u64,
+    /// The requested bytecodes.
+    pub codes: Vec<Bytes>,
+}
+
+/// A request for trie nodes of the state trie with the given root, by their paths.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GetTrieNodes {
+    /// The id of the request.
+    pub request_id: u64,
+    /// The root hash of the account trie to serve.
+    pub root_hash: B256,
+    /// The path sets of the nodes to retrieve.
+    ///
+    /// Every path set starts with the compact encoded path of a node in the account trie. If the
+    /// path set has more entries, these are the compact encoded paths of nodes in the storage
+    /// trie of the account with the full path given in the first entry.
+    #[cfg_attr(
+        any(test, feature = "arbitrary"),
+        proptest(
+            strategy = "proptest::collection::vec(proptest::collection::vec(proptest::arbitrary::any::<Bytes>(), 1..=5), 0..=5)"
+        )
+    )]
+    pub paths: Vec<Vec<Bytes>>,
+    /// The soft limit at which to stop returning data.
+    pub response_bytes: u64,
+}
+
+/// The response to [`GetTrieNodes`], containing the requested trie nodes in request order.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrieNodes {
+    /// The id of the request this is a response to.
+    pub request_id: u64,
+    /// The RLP encoded trie nodes.
+    pub nodes: Vec<Bytes>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::hex;
+
+    #[test]
+    fn slim_account_empty_roots() {
+        let account = SlimAccount::new(
+            Account { nonce: 1, balance: U256::from(2), bytecode_hash: None },
+            EMPTY_ROOT_HASH,
+        );
+        assert_eq!(account.code_hash, KECCAK_EMPTY);
+
+        let encoded = alloy_rlp::encode(account);
+        assert_eq!(encoded, hex!("c401028080"));
+        assert_eq!(account.length(), encoded.len());
+
+        let decoded = SlimAccount::decode(&mut &encoded[..]).unwrap();
+        assert_eq!(decoded, account);
+        assert_eq!(decoded.account().bytecode_hash, None);
+    }
+
+    #[test]
+    fn slim_account_roundtrip() {
+        let account = SlimAccount {
+            nonce: 7,
+            balance: U256::from(1_000_000),
+            storage_root: B256::repeat_byte(0x11),
+            code_hash: B256::repeat_byte(0x22),
+        };
+        let encoded = alloy_rlp::encode(account);
+        assert_eq!(account.length(), encoded.len());
+        assert_eq!(SlimAccount::decode(&mut &encoded[..]).unwrap(), account);
+        assert_eq!(account.account().bytecode_hash, Some(B256::repeat_byte(0x22)));
+    }
+
+    #[test]
+    fn snap_message_roundtrip() {
+        let messages = vec![
+            SnapMessage::GetAccountRange(GetAccountRange {
+                request_id: 1,
+                root_hash: B256::repeat_byte(1),
+                starting_hash: B256::ZERO,
+                limit_hash: B256::repeat_byte(0xff),
+                response_bytes: 512 * 1024,
+            }),
+            SnapMessage::AccountRange(AccountRange {
+                request_id: 1,
+                accounts: vec![AccountData {
+                    hash: B256::repeat_byte(2),
+                    body: SlimAccount { nonce: 1, ..Default::default() },
+                }],
+                proof: vec![Bytes::from_static(&[0xc0])],
+            }),
+            SnapMessage::GetStorageRanges(GetStorageRanges {
+                request_id: 2,
+                root_hash: B256::repeat_byte(1),
+                account_hashes: vec![B256::repeat_byte(2)],
+                starting_hash: Bytes::new(),
+                limit_hash: Bytes::new(),
+                response_bytes: 1024,
+            }),
+            SnapMessage::StorageRanges(StorageRanges {
+                request_id: 2,
+                slots: vec![vec![StorageData {
+                    hash: B256::repeat_byte(3),
+                    data: Bytes::from_static(&[0x01]),
+                }]],
+                proof: vec![],
+            }),
+            SnapMessage::GetByteCodes(GetByteCodes {
+                request_id: 3,
+                hashes: vec![B256::repeat_byte(4)],
+                response_bytes: 1024,
+            }),
+            SnapMessage::ByteCodes(ByteCodes {
+                request_id: 3,
+                codes: vec![Bytes::from_static(&[0x60, 0x00])],
+            }),
+            SnapMessage::GetTrieNodes(GetTrieNodes {
+                request_id: 4,
+                root_hash: B256::repeat_byte(1),
+                paths: vec![vec![Bytes::from_static(&[0x00])]],
+                response_bytes: 1024,
+            }),
+            SnapMessage::TrieNodes(TrieNodes { request_id: 4, nodes: vec![] }),
+        ];
+
+        for (id, message) in messages.into_iter().enumerate() {
+            let encoded = alloy_rlp::encode(&message);
+            assert_eq!(encoded[0], id as u8);
+            assert_eq!(message.length(), encoded.len());
+            assert_eq!(message.message_id() as usize, id);
+            assert_eq!(message.is_request(), id % 2 == 0);
+
+            let decoded = SnapMessage::decode(&mut &encoded[..]).unwrap();
+            assert_eq!(decoded, message);
+        }
+    }
+
+    #[test]
+    fn storage_range_bounds() {
+        let request = GetStorageRanges::default();
+        assert_eq!(request.starting_hash(), B256::ZERO);
+        assert_eq!(request.limit_hash(), B256::repeat_byte(0xff));
+
+        let request = GetStorageRanges {
+            starting_hash: Bytes::from_static(&[0x12]),
+            limit_hash: Bytes::from_static(&[0x34]),
+            ..Default::default()
+        };
+        let mut expected_start = B256::ZERO;
+        expected_start[0] = 0x12;
+        let mut expected_limit = B256::repeat_byte(0xff);
+        expected_limit[0] = 0x34;
+        assert_eq!(request.starting_hash(), expected_start);
+        assert_eq!(request.limit_hash(), expected_limit);
+    }
+}
@@ -1,4 +1,4 @@
-//! Helpers for resolving the external IP.
+//! Helpers for resolving the external IP and mapping ports via UPnP or NAT-PMP.
 //!
 //! ## Feature Flags
 //!
@@ -26,6 +26,18 @@ use std::{
 };
 use tracing::debug;
 
+mod natpmp;
+pub use natpmp::{default_gateway, NatPmpError, NatPmpPortMapper, NATPMP_PORT};
+
+mod port_mapping;
+pub use port_mapping::{
+    PortMapping, PortMappingHandle, PortMappingProtocol, PortMappingService,
+    DEFAULT_PORT_MAPPING_LEASE,
+};
+
+mod upnp;
+pub use upnp::{UpnpError, UpnpPortMapper};
+
 #[cfg(feature = "serde")]
 use serde_with::{DeserializeFromStr, SerializeDisplay};
 
@@ -38,6 +50,8 @@ pub enum NatResolver {
     Any,
     /// Resolve via Upnp
     Upnp,
+    /// Resolve via NAT-PMP
+    NatPmp,
     /// Resolve external IP via [public_ip::Resolver]
     PublicIp,
     /// Use the given [IpAddr]
@@ -53,6 +67,14 @@ impl NatResolver {
     pub async fn external_addr(self) -> Option<IpAddr> {
         external_addr_with(self).await
     }
+
+    /// Returns true if the resolver uses UPnP or NAT-PMP, in which case the ports of the node
+    /// should be mapped via the gateway as well, see [PortMappingService].
+    ///
+    /// [NatResolver::Any] tries UPnP first and falls back to NAT-PMP.
+    pub fn maps_ports(&self) -> bool {
+        matches!(self, NatResolver::Any | NatResolver::Upnp | NatResolver::NatPmp)
+    }
 }
 
 impl fmt::Display for NatResolver {
@@ -60,6 +82,7 @@ impl fmt::Display for NatResolver {
         match self {
             NatResolver::Any => f.write_str("any"),
             NatResolver::Upnp => f.write_str("upnp"),
+            NatResolver::NatPmp => f.write_str("natpmp"),
             NatResolver::PublicIp => f.write_str("publicip"),
             NatResolver::ExternalIp(ip) => write!(f, "extip:{ip}"),
             NatResolver::None => f.write_str("none"),
@@ -85,6 +108,7 @@ impl FromStr for NatResolver {
         let r = match s {
             "any" => NatResolver::Any,
             "upnp" => NatResolver::Upnp,
+            "natpmp" | "nat-pmp" => NatResolver::NatPmp,
             "none" => NatResolver::None,
             "publicip" | "public-ip" => NatResolver::PublicIp,
             s => {
@@ -192,6 +216,7 @@ pub async fn external_addr_with(resolver: NatResolver) -> Option<IpAddr> {
             .await
         }
         NatResolver::Upnp => resolve_external_ip_upnp().await,
+        NatResolver::NatPmp => resolve_external_ip_natpmp().await,
         NatResolver::PublicIp => resolve_external_ip().await,
         NatResolver::ExternalIp(ip) => Some(ip),
         NatResolver::None => None,
@@ -259,6 +284,24 @@ async fn resolve_external_ip_upnp() -> Option<IpAddr> {
         .ok()
 }
 
+async fn resolve_external_ip_natpmp() -> Option<IpAddr> {
+    NatPmpPortMapper::new(Duration::ZERO)
+        .await
+        .map_err(|err| {
+            debug!(target: "net::nat", ?err, "Failed to resolve external IP via NAT-PMP: failed to find gateway");
+            err
+        })
+        .ok()?
+        .external_ip()
+        .await
+        .map_err(|err| {
+            debug!(target: "net::nat", ?err, "Failed to resolve external IP via NAT-PMP");
+            err
+        })
+        .ok()
+        .map(IpAddr::V4)
+}
+
 async fn resolve_external_ip() -> Option<IpAddr> {
     public_ip::addr().await
 }
@@ -292,6 +335,8 @@ mod tests {
     fn test_from_str() {
         assert_eq!(NatResolver::Any, "any".parse().unwrap());
         assert_eq!(NatResolver::None, "none".parse().unwrap());
+        assert_eq!(NatResolver::NatPmp, "natpmp".parse().unwrap());
+        assert_eq!(NatResolver::NatPmp.to_string(), "natpmp");
 
         let ip = NatResolver::ExternalIp(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
         let s = "extip:0.0.0.0";
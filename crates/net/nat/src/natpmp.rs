@@ -0,0 +1,444 @@
+//! Port mapping via NAT-PMP, see [RFC 6886](https://datatracker.ietf.org/doc/html/rfc6886).
+//!
+//! NAT-PMP is supported by routers that don't implement UPnP, e.g. the ones made by Apple. The
+//! requests are sent via UDP to the NAT-PMP server of the default gateway.
+
+use crate::port_mapping::{PortMapping, PortMappingProtocol};
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+use tokio::{net::UdpSocket, time::Instant};
+use tracing::{debug, trace};
+
+/// The port of the NAT-PMP server of the gateway.
+pub const NATPMP_PORT: u16 = 5351;
+
+/// The NAT-PMP version.
+const VERSION: u8 = 0;
+
+/// The opcode of external address requests.
+const OP_EXTERNAL_ADDRESS: u8 = 0;
+
+/// Responses have the opcode of the request plus 128.
+const OP_RESPONSE: u8 = 128;
+
+/// The timeout of the first request, which is doubled on every retransmission.
+const INITIAL_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// The number of times a request is sent before giving up.
+///
+/// RFC 6886 allows up to 9 attempts, which takes more than a minute if the gateway doesn't
+/// support NAT-PMP.
+const MAX_ATTEMPTS: usize = 4;
+
+/// Errors that can occur when mapping ports via NAT-PMP.
+#[derive(Debug, thiserror::Error)]
+pub enum NatPmpError {
+    /// Failed to find the gateway or to communicate with it.
+    #[error("NAT-PMP I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The gateway didn't respond.
+    #[error("NAT-PMP gateway didn't respond")]
+    Timeout,
+    /// The gateway rejected the request with the result code.
+    #[error("NAT-PMP request failed with result code {0}")]
+    Failure(u16),
+}
+
+/// Maps ports of the NAT-PMP gateway of the local network to local ports.
+#[derive(Debug)]
+pub struct NatPmpPortMapper {
+    /// The socket connected to the NAT-PMP server of the gateway.
+    socket: UdpSocket,
+    /// The lease duration of the port mappings.
+    lease_duration: Duration,
+    /// All active port mappings.
+    mappings: Vec<PortMapping>,
+}
+
+// === impl NatPmpPortMapper ===
+
+impl NatPmpPortMapper {
+    /// Connects to the NAT-PMP server of the default gateway, see [default_gateway].
+    pub async fn new(lease_duration: Duration) -> Result<Self, NatPmpError> {
+        Self::with_gateway(default_gateway()?, lease_duration).await
+    }
+
+    /// Connects to the NAT-PMP server at the given address.
+    ///
+    /// Fails if the server doesn't answer an external address request.
+    pub async fn with_gateway(
+        gateway: SocketAddr,
+        lease_duration: Duration,
+    ) -> Result<Self, NatPmpError> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+        socket.connect(gateway).await?;
+        let mapper = Self { socket, lease_duration, mappings: Vec::new() };
+        let external_ip = mapper.external_ip().await?;
+        trace!(target: "net::nat", %gateway, %external_ip, "Found NAT-PMP gateway");
+        Ok(mapper)
+    }
+
+    /// Returns the external IP address of the gateway.
+    pub async fn external_ip(&self) -> Result<Ipv4Addr, NatPmpError> {
+        let response = self
+            .request(&[VERSION, OP_EXTERNAL_ADDRESS], OP_EXTERNAL_ADDRESS, |response| {
+                response.len() >= 12
+            })
+            .await?;
+        Ok(Ipv4Addr::new(response[8], response[9], response[10], response[11]))
+    }
+
+    /// Returns all active port mappings.
+    pub fn mappings(&self) -> &[PortMapping] {
+        &self.mappings
+    }
+
+    /// Forwards an external port to the given local port.
+    ///
+    /// The same port number is requested for the external port, but the gateway may choose
+    /// another one.
+    pub async fn add_port(
+        &mut self,
+        protocol: PortMappingProtocol,
+        local_port: u16,
+    ) -> Result<PortMapping, NatPmpError> {
+        let lease = self.lease_duration.as_secs() as u32;
+        let external_port = self.request_mapping(protocol, local_port, local_port, lease).await?;
+        let mapping = PortMapping { protocol, local_port, external_port };
+        self.mappings.retain(|m| m.protocol != protocol || m.local_port != local_port);
+        self.mappings.push(mapping);
+        Ok(mapping)
+    }
+
+    /// Renews the leases of all port mappings.
+    ///
+    /// Returns the mappings whose external port changed.
+    pub async fn renew(&mut self) -> Vec<PortMapping> {
+        let lease = self.lease_duration.as_secs() as u32;
+        let mut changed = Vec::new();
+        for idx in 0..self.mappings.len() {
+            let PortMapping { protocol, local_port, external_port } = self.mappings[idx];
+            match self.request_mapping(protocol, local_port, external_port, lease).await {
+                Ok(port) if port != external_port => {
+                    self.mappings[idx].external_port = port;
+                    changed.push(self.mappings[idx]);
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    debug!(target: "net::nat", ?protocol, external_port, %err, "Failed to renew port mapping");
+                }
+            }
+        }
+        changed
+    }
+
+    /// Removes all port mappings from the gateway.
+    pub async fn remove_ports(&mut self) {
+        for PortMapping { protocol, local_port, external_port } in
+            std::mem::take(&mut self.mappings)
+        {
+            // a mapping is removed by requesting a lease of zero for the external port zero
+            if let Err(err) = self.request_mapping(protocol, local_port, 0, 0).await {
+                debug!(target: "net::nat", ?protocol, external_port, %err, "Failed to remove port mapping");
+            }
+        }
+    }
+
+    /// Requests a mapping of the external port to the local port and returns the external port
+    /// assigned by the gateway.
+    async fn request_mapping(
+        &self,
+        protocol: PortMappingProtocol,
+        local_port: u16,
+        external_port: u16,
+        lease: u32,
+    ) -> Result<u16, NatPmpError> {
+        let op = match protocol {
+            PortMappingProtocol::Udp => 1,
+            PortMappingProtocol::Tcp => 2,
+        };
+        let mut request = [0u8; 12];
+        request[0] = VERSION;
+        request[1] = op;
+        request[4..6].copy_from_slice(&local_port.to_be_bytes());
+        request[6..8].copy_from_slice(&external_port.to_be_bytes());
+        request[8..12].copy_from_slice(&lease.to_be_bytes());
+
+        // skip responses to the requests for other ports
+        let response = self
+            .request(&request, op, |response| {
+                response.len() >= 16 && response[8..10] == local_port.to_be_bytes()
+            })
+            .await?;
+        Ok(u16::from_be_bytes([response[10], response[11]]))
+    }
+
+    /// Sends the request until a matching response is received, doubling the timeout after every
+    /// attempt.
+    async fn request(
+        &self,
+        request: &[u8],
+        op: u8,
+        is_response: impl Fn(&[u8]) -> bool,
+    ) -> Result<Vec<u8>, NatPmpError> {
+        let mut buf = [0u8; 16];
+        let mut timeout = INITIAL_TIMEOUT;
+        for _ in 0..MAX_ATTEMPTS {
+            self.socket.send(request).await?;
+            let deadline = Instant::now() + timeout;
+            while let Ok(len) = tokio::time::timeout_at(deadline, self.socket.recv(&mut buf)).await
+            {
+                let response = &buf[..len?];
+                if response.len() < 4 || response[0] != VERSION || response[1] != OP_RESPONSE + op {
+                    continue
+                }
+                let result = u16::from_be_bytes([response[2], response[3]]);
+                if result != 0 {
+                    return Err(NatPmpError::Failure(result))
+                }
+                if is_response(response) {
+                    return Ok(response.to_vec())
+                }
+            }
+            timeout *= 2;
+        }
+        Err(NatPmpError::Timeout)
+    }
+}
+
+/// Returns the address of the NAT-PMP server of the default gateway.
+///
+/// The default gateway is looked up in the routing table, which is only supported on Linux.
+pub fn default_gateway() -> io::Result<SocketAddr> {
+    #[cfg(target_os = "linux")]
+    {
+        let routes = std::fs::read_to_string("/proc/net/route")?;
+        let gateway = parse_default_gateway(&routes).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "no default gateway in the routing table")
+        })?;
+        Ok(SocketAddr::new(gateway.into(), NATPMP_PORT))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "looking up the default gateway is only supported on Linux",
+        ))
+    }
+}
+
+/// Returns the gateway of the default route in the format of `/proc/net/route`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_default_gateway(routes: &str) -> Option<Ipv4Addr> {
+    /// The route is usable.
+    const RTF_UP: u16 = 0x1;
+    /// The destination is reached via the gateway.
+    const RTF_GATEWAY: u16 = 0x2;
+
+    routes.lines().skip(1).find_map(|line| {
+        let mut fields = line.split_whitespace().skip(1);
+        let destination = u32::from_str_radix(fields.next()?, 16).ok()?;
+        let gateway = u32::from_str_radix(fields.next()?, 16).ok()?;
+        let flags = u16::from_str_radix(fields.next()?, 16).ok()?;
+        // the addresses are printed in network byte order as integers of the host
+        (destination == 0 && flags & (RTF_UP | RTF_GATEWAY) == RTF_UP | RTF_GATEWAY)
+            .then(|| Ipv4Addr::from(gateway.to_ne_bytes()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::port_mapping::PortMappingService;
+    use std::sync::{Arc, Mutex};
+
+    /// A port mapping request received by the [MockGateway].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Request {
+        op: u8,
+        local_port: u16,
+        external_port: u16,
+        lease: u32,
+    }
+
+    /// A local NAT-PMP server that records the port mapping requests it receives.
+    struct MockGateway {
+        addr: SocketAddr,
+        requests: Arc<Mutex<Vec<Request>>>,
+    }
+
+    impl MockGateway {
+        /// Spawns a gateway that maps requests for the given external ports to other ports, as if
+        /// they were in use.
+        async fn spawn(ports_in_use: Vec<u16>) -> Self {
+            let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let addr = socket.local_addr().unwrap();
+            let requests = Arc::new(Mutex::new(Vec::new()));
+
+            let recorded = requests.clone();
+            tokio::spawn(async move {
+                let mut buf = [0u8; 12];
+                while let Ok((len, from)) = socket.recv_from(&mut buf).await {
+                    let op = buf[1];
+                    let mut response = vec![VERSION, OP_RESPONSE + op, 0, 0, 0, 0, 0, 1];
+                    if op == OP_EXTERNAL_ADDRESS {
+                        response.extend_from_slice(&[203, 0, 113, 1]);
+                    } else {
+                        assert_eq!(len, 12);
+                        let local_port = u16::from_be_bytes([buf[4], buf[5]]);
+                        let mut external_port = u16::from_be_bytes([buf[6], buf[7]]);
+                        let lease = u32::from_be_bytes([buf[8], buf[9], buf[10], buf[11]]);
+                        recorded.lock().unwrap().push(Request {
+                            op,
+                            local_port,
+                            external_port,
+                            lease,
+                        });
+                        if ports_in_use.contains(&external_port) {
+                            external_port += 1000;
+                        }
+                        response.extend_from_slice(&local_port.to_be_bytes());
+                        response.extend_from_slice(&external_port.to_be_bytes());
+                        response.extend_from_slice(&lease.to_be_bytes());
+                    }
+                    let _ = socket.send_to(&response, from).await;
+                }
+            });
+
+            Self { addr, requests }
+        }
+
+        fn requests(&self) -> Vec<Request> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    fn request(op: u8, local_port: u16, external_port: u16, lease: u32) -> Request {
+        Request { op, local_port, external_port, lease }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn map_renew_and_remove_ports() {
+        reth_tracing::init_test_tracing();
+        let gateway = MockGateway::spawn(Vec::new()).await;
+
+        let mut mapper =
+            NatPmpPortMapper::with_gateway(gateway.addr, Duration::from_secs(60)).await.unwrap();
+        assert_eq!(mapper.external_ip().await.unwrap(), Ipv4Addr::new(203, 0, 113, 1));
+
+        let tcp = mapper.add_port(PortMappingProtocol::Tcp, 30303).await.unwrap();
+        let udp = mapper.add_port(PortMappingProtocol::Udp, 30303).await.unwrap();
+        assert_eq!(tcp.external_port, 30303);
+        assert_eq!(udp.external_port, 30303);
+        assert_eq!(mapper.mappings(), &[tcp, udp]);
+
+        // renewing keeps the external ports
+        assert!(mapper.renew().await.is_empty());
+
+        mapper.remove_ports().await;
+        assert!(mapper.mappings().is_empty());
+
+        assert_eq!(
+            gateway.requests(),
+            vec![
+                request(2, 30303, 30303, 60),
+                request(1, 30303, 30303, 60),
+                request(2, 30303, 30303, 60),
+                request(1, 30303, 30303, 60),
+                request(2, 30303, 0, 0),
+                request(1, 30303, 0, 0),
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn map_port_in_use() {
+        reth_tracing::init_test_tracing();
+        let gateway = MockGateway::spawn(vec![30303]).await;
+
+        let mut mapper =
+            NatPmpPortMapper::with_gateway(gateway.addr, Duration::from_secs(60)).await.unwrap();
+
+        // the gateway picks another external port
+        let mapping = mapper.add_port(PortMappingProtocol::Tcp, 30303).await.unwrap();
+        assert_eq!(mapping.external_port, 31303);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn no_gateway() {
+        // nothing listens on the port of the socket after it's dropped
+        let addr = std::net::UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let res = NatPmpPortMapper::with_gateway(addr, Duration::from_secs(60)).await;
+        assert!(res.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn service_falls_back_to_natpmp() {
+        reth_tracing::init_test_tracing();
+        let gateway = MockGateway::spawn(Vec::new()).await;
+
+        // nothing answers the UPnP search
+        let no_upnp = std::net::UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let service = PortMappingService {
+            ports: vec![(PortMappingProtocol::Tcp, 30303), (PortMappingProtocol::Udp, 9000)],
+            lease_duration: Duration::from_secs(60),
+            upnp: Some(igd::SearchOptions {
+                bind_addr: "127.0.0.1:0".parse().unwrap(),
+                broadcast_address: no_upnp,
+                timeout: Some(Duration::from_millis(500)),
+            }),
+            natpmp: true,
+            natpmp_gateway: Some(gateway.addr),
+        };
+        let (handle, mut updates) = service.spawn();
+
+        let tcp = updates.recv().await.unwrap();
+        let udp = updates.recv().await.unwrap();
+        assert_eq!(
+            (tcp, udp),
+            (
+                PortMapping {
+                    protocol: PortMappingProtocol::Tcp,
+                    local_port: 30303,
+                    external_port: 30303
+                },
+                PortMapping {
+                    protocol: PortMappingProtocol::Udp,
+                    local_port: 9000,
+                    external_port: 9000
+                },
+            )
+        );
+
+        handle.shutdown().await;
+        assert_eq!(
+            gateway.requests(),
+            vec![
+                request(2, 30303, 30303, 60),
+                request(1, 9000, 9000, 60),
+                request(2, 30303, 0, 0),
+                request(1, 9000, 0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_routes() {
+        let gateway = Ipv4Addr::new(192, 168, 1, 1);
+        let gateway_hex = format!("{:08X}", u32::from_ne_bytes(gateway.octets()));
+        let routes = format!(
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+             eth0\t0001A8C0\t00000000\t0001\t0\t0\t0\t00FFFFFF\t0\t0\t0\n\
+             eth0\t00000000\t{gateway_hex}\t0003\t0\t0\t100\t00000000\t0\t0\t0\n"
+        );
+        assert_eq!(parse_default_gateway(&routes), Some(gateway));
+
+        // no default route
+        let routes = "Iface\tDestination\tGateway \tFlags\n\
+                      eth0\t0001A8C0\t00000000\t0001\n";
+        assert_eq!(parse_default_gateway(routes), None);
+    }
+}
@@ -0,0 +1,254 @@
+//! Port mapping with lease renewal.
+//!
+//! Nodes behind a NAT router don't receive inbound connections unless the router forwards the
+//! ports of the node. The [PortMappingService] asks the gateway of the local network to forward
+//! the external ports to the node, via UPnP or NAT-PMP. Port mappings are leased, so the service
+//! renews them periodically and removes them again when it's shut down.
+
+use crate::{
+    natpmp::{NatPmpError, NatPmpPortMapper},
+    upnp::{UpnpError, UpnpPortMapper},
+    NatResolver,
+};
+use igd::SearchOptions;
+use std::{fmt, net::SocketAddr, time::Duration};
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
+use tracing::debug;
+
+/// The default lease duration of port mappings.
+pub const DEFAULT_PORT_MAPPING_LEASE: Duration = Duration::from_secs(20 * 60);
+
+/// The transport protocol of a port mapping.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum PortMappingProtocol {
+    /// The RLPx TCP port.
+    Tcp,
+    /// A discovery UDP port.
+    Udp,
+}
+
+/// A port of the gateway that is forwarded to a local port.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct PortMapping {
+    /// The transport protocol of the mapping.
+    pub protocol: PortMappingProtocol,
+    /// The local port the external port is forwarded to.
+    pub local_port: u16,
+    /// The port of the gateway.
+    pub external_port: u16,
+}
+
+/// Errors that can occur when mapping a port.
+#[derive(Debug, thiserror::Error)]
+enum PortMappingError {
+    /// Failed to map the port via UPnP.
+    #[error(transparent)]
+    Upnp(#[from] UpnpError),
+    /// Failed to map the port via NAT-PMP.
+    #[error(transparent)]
+    NatPmp(#[from] NatPmpError),
+}
+
+/// The port mapper of the gateway that was found.
+#[derive(Debug)]
+enum PortMapper {
+    Upnp(UpnpPortMapper),
+    NatPmp(NatPmpPortMapper),
+}
+
+impl PortMapper {
+    /// The name of the protocol used to map ports.
+    fn method(&self) -> &'static str {
+        match self {
+            PortMapper::Upnp(_) => "UPnP",
+            PortMapper::NatPmp(_) => "NAT-PMP",
+        }
+    }
+
+    async fn add_port(
+        &mut self,
+        protocol: PortMappingProtocol,
+        local_port: u16,
+    ) -> Result<PortMapping, PortMappingError> {
+        Ok(match self {
+            PortMapper::Upnp(mapper) => mapper.add_port(protocol, local_port).await?,
+            PortMapper::NatPmp(mapper) => mapper.add_port(protocol, local_port).await?,
+        })
+    }
+
+    async fn renew(&mut self) -> Vec<PortMapping> {
+        match self {
+            PortMapper::Upnp(mapper) => mapper.renew().await,
+            PortMapper::NatPmp(mapper) => mapper.renew().await,
+        }
+    }
+
+    async fn remove_ports(&mut self) {
+        match self {
+            PortMapper::Upnp(mapper) => mapper.remove_ports().await,
+            PortMapper::NatPmp(mapper) => mapper.remove_ports().await,
+        }
+    }
+}
+
+/// Maps the configured ports via UPnP or NAT-PMP, renews their leases and removes them on
+/// shutdown.
+///
+/// If both protocols are enabled, UPnP is tried first and NAT-PMP is used if no UPnP gateway is
+/// found. All port mappings and changes of the external ports are reported as [PortMapping]s.
+#[must_use = "Does nothing unless spawned"]
+pub struct PortMappingService {
+    /// The local ports to map.
+    pub(crate) ports: Vec<(PortMappingProtocol, u16)>,
+    /// The lease duration of the port mappings.
+    pub(crate) lease_duration: Duration,
+    /// How to search the UPnP gateway, if UPnP is enabled.
+    pub(crate) upnp: Option<SearchOptions>,
+    /// Whether NAT-PMP is enabled.
+    pub(crate) natpmp: bool,
+    /// The address of the NAT-PMP server, defaults to the one of the default gateway.
+    pub(crate) natpmp_gateway: Option<SocketAddr>,
+}
+
+// === impl PortMappingService ===
+
+impl PortMappingService {
+    /// Creates a new service that maps the given local ports with the port mapping protocols of
+    /// the resolver, see [NatResolver::maps_ports].
+    pub fn new(
+        resolver: NatResolver,
+        ports: Vec<(PortMappingProtocol, u16)>,
+        lease_duration: Duration,
+    ) -> Self {
+        Self {
+            ports,
+            lease_duration,
+            upnp: matches!(resolver, NatResolver::Any | NatResolver::Upnp)
+                .then(SearchOptions::default),
+            natpmp: matches!(resolver, NatResolver::Any | NatResolver::NatPmp),
+            natpmp_gateway: None,
+        }
+    }
+
+    /// Spawns the service onto a new task.
+    ///
+    /// Returns the handle to shut down the service and the receiver of the [PortMapping]s. The
+    /// port mappings are removed once the handle is dropped.
+    pub fn spawn(self) -> (PortMappingHandle, mpsc::UnboundedReceiver<PortMapping>) {
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let (updates_tx, updates_rx) = mpsc::unbounded_channel();
+        let task = tokio::task::spawn(self.run(shutdown_rx, updates_tx));
+        (PortMappingHandle { shutdown: Some(shutdown_tx), task: Some(task) }, updates_rx)
+    }
+
+    /// Finds the gateway of the local network with the enabled protocols.
+    async fn find_mapper(&mut self) -> Option<PortMapper> {
+        if let Some(options) = self.upnp.take() {
+            match UpnpPortMapper::search_with(options, self.lease_duration).await {
+                Ok(mapper) => return Some(PortMapper::Upnp(mapper)),
+                Err(err) => debug!(target: "net::nat", %err, "Failed to map ports via UPnP"),
+            }
+        }
+
+        if self.natpmp {
+            let mapper = match self.natpmp_gateway {
+                Some(gateway) => NatPmpPortMapper::with_gateway(gateway, self.lease_duration).await,
+                None => NatPmpPortMapper::new(self.lease_duration).await,
+            };
+            match mapper {
+                Ok(mapper) => return Some(PortMapper::NatPmp(mapper)),
+                Err(err) => debug!(target: "net::nat", %err, "Failed to map ports via NAT-PMP"),
+            }
+        }
+
+        None
+    }
+
+    async fn run(
+        mut self,
+        mut shutdown: oneshot::Receiver<()>,
+        updates: mpsc::UnboundedSender<PortMapping>,
+    ) {
+        let mut mapper = tokio::select! {
+            mapper = self.find_mapper() => match mapper {
+                Some(mapper) => mapper,
+                None => return,
+            },
+            _ = &mut shutdown => return,
+        };
+        let method = mapper.method();
+
+        for (protocol, local_port) in std::mem::take(&mut self.ports) {
+            match mapper.add_port(protocol, local_port).await {
+                Ok(mapping) => {
+                    debug!(target: "net::nat", method, ?mapping, "Mapped port");
+                    let _ = updates.send(mapping);
+                }
+                Err(err) => {
+                    debug!(target: "net::nat", method, ?protocol, local_port, %err, "Failed to map port");
+                }
+            }
+        }
+
+        // renew the leases well before they expire
+        let period = self.lease_duration / 2;
+        let mut renew = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            tokio::select! {
+                _ = renew.tick() => {
+                    for mapping in mapper.renew().await {
+                        debug!(target: "net::nat", method, ?mapping, "External port of mapping changed");
+                        let _ = updates.send(mapping);
+                    }
+                }
+                _ = &mut shutdown => break,
+            }
+        }
+
+        mapper.remove_ports().await;
+    }
+}
+
+impl fmt::Debug for PortMappingService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PortMappingService")
+            .field("ports", &self.ports)
+            .field("lease_duration", &self.lease_duration)
+            .field("upnp", &self.upnp.is_some())
+            .field("natpmp", &self.natpmp)
+            .field("natpmp_gateway", &self.natpmp_gateway)
+            .finish()
+    }
+}
+
+/// The handle to the spawned [PortMappingService].
+///
+/// Dropping the handle shuts down the service, which removes the port mappings in the background.
+#[derive(Debug)]
+pub struct PortMappingHandle {
+    shutdown: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl PortMappingHandle {
+    /// Shuts down the service and waits until the port mappings are removed.
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for PortMappingHandle {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
@@ -0,0 +1,506 @@
+//! Port mapping via UPnP.
+//!
+//! The [UpnpPortMapper] asks the UPnP Internet Gateway Device of the local network to forward
+//! external ports to the node.
+
+use crate::port_mapping::{PortMapping, PortMappingProtocol};
+use igd::{
+    aio::{search_gateway, Gateway},
+    AddAnyPortError, SearchError, SearchOptions,
+};
+use std::{
+    fmt, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+    time::Duration,
+};
+use tracing::{debug, trace};
+
+/// The description of the port mappings shown by the gateway.
+const PORT_MAPPING_DESCRIPTION: &str = "reth";
+
+impl From<PortMappingProtocol> for igd::PortMappingProtocol {
+    fn from(protocol: PortMappingProtocol) -> Self {
+        match protocol {
+            PortMappingProtocol::Tcp => igd::PortMappingProtocol::TCP,
+            PortMappingProtocol::Udp => igd::PortMappingProtocol::UDP,
+        }
+    }
+}
+
+/// Errors that can occur when mapping ports via UPnP.
+#[derive(Debug, thiserror::Error)]
+pub enum UpnpError {
+    /// No UPnP gateway was found.
+    #[error("failed to find UPnP gateway: {0}")]
+    Search(#[from] SearchError),
+    /// Failed to determine the address of the node in the local network.
+    #[error("failed to determine local address: {0}")]
+    LocalAddr(#[from] io::Error),
+    /// The gateway rejected the port mapping.
+    #[error("failed to add port mapping: {0}")]
+    AddPort(#[from] AddAnyPortError),
+}
+
+/// Maps ports of the UPnP gateway of the local network to local ports.
+pub struct UpnpPortMapper {
+    /// The UPnP gateway.
+    gateway: Gateway,
+    /// The address of the node in the local network.
+    local_ip: IpAddr,
+    /// The lease duration of the port mappings.
+    lease_duration: Duration,
+    /// All active port mappings.
+    mappings: Vec<PortMapping>,
+}
+
+// === impl UpnpPortMapper ===
+
+impl UpnpPortMapper {
+    /// Searches the UPnP gateway of the local network.
+    pub async fn search(lease_duration: Duration) -> Result<Self, UpnpError> {
+        Self::search_with(Default::default(), lease_duration).await
+    }
+
+    /// Searches the UPnP gateway with the given [SearchOptions].
+    pub(crate) async fn search_with(
+        options: SearchOptions,
+        lease_duration: Duration,
+    ) -> Result<Self, UpnpError> {
+        let gateway = search_gateway(options).await?;
+        let local_ip = local_ip(gateway.addr)?;
+        trace!(target: "net::nat", gateway=%gateway.addr, %local_ip, "Found UPnP gateway");
+        Ok(Self { gateway, local_ip, lease_duration, mappings: Vec::new() })
+    }
+
+    /// Returns all active port mappings.
+    pub fn mappings(&self) -> &[PortMapping] {
+        &self.mappings
+    }
+
+    /// Forwards an external port to the given local port.
+    ///
+    /// The same port number is requested for the external port. If the port is not available, the
+    /// gateway chooses the external port.
+    pub async fn add_port(
+        &mut self,
+        protocol: PortMappingProtocol,
+        local_port: u16,
+    ) -> Result<PortMapping, UpnpError> {
+        let external_port = self.request_port(protocol, local_port, local_port).await?;
+        let mapping = PortMapping { protocol, local_port, external_port };
+        self.mappings.retain(|m| m.protocol != protocol || m.local_port != local_port);
+        self.mappings.push(mapping);
+        Ok(mapping)
+    }
+
+    /// Renews the leases of all port mappings.
+    ///
+    /// Returns the mappings whose external port changed.
+    pub async fn renew(&mut self) -> Vec<PortMapping> {
+        let mut changed = Vec::new();
+        for idx in 0..self.mappings.len() {
+            let PortMapping { protocol, local_port, external_port } = self.mappings[idx];
+            match self.request_port(protocol, local_port, external_port).await {
+                Ok(port) if port != external_port => {
+                    self.mappings[idx].external_port = port;
+                    changed.push(self.mappings[idx]);
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    debug!(target: "net::nat", ?protocol, external_port, %err, "Failed to renew port mapping");
+                }
+            }
+        }
+        changed
+    }
+
+    /// Removes all port mappings from the gateway.
+    pub async fn remove_ports(&mut self) {
+        for PortMapping { protocol, external_port, .. } in std::mem::take(&mut self.mappings) {
+            if let Err(err) = self.gateway.remove_port(protocol.into(), external_port).await {
+                debug!(target: "net::nat", ?protocol, external_port, %err, "Failed to remove port mapping");
+            }
+        }
+    }
+
+    /// Requests the external port for the local port, or any port if it's not available.
+    async fn request_port(
+        &self,
+        protocol: PortMappingProtocol,
+        local_port: u16,
+        external_port: u16,
+    ) -> Result<u16, UpnpError> {
+        let local_addr = SocketAddr::new(self.local_ip, local_port);
+        let lease = self.lease_duration.as_secs() as u32;
+        let Err(err) = self
+            .gateway
+            .add_port(protocol.into(), external_port, local_addr, lease, PORT_MAPPING_DESCRIPTION)
+            .await
+        else {
+            return Ok(external_port)
+        };
+        trace!(target: "net::nat", ?protocol, external_port, %err, "Requested port is not available");
+        Ok(self
+            .gateway
+            .add_any_port(protocol.into(), local_addr, lease, PORT_MAPPING_DESCRIPTION)
+            .await?)
+    }
+}
+
+impl fmt::Debug for UpnpPortMapper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpnpPortMapper")
+            .field("gateway", &self.gateway.addr)
+            .field("local_ip", &self.local_ip)
+            .field("lease_duration", &self.lease_duration)
+            .field("mappings", &self.mappings)
+            .finish()
+    }
+}
+
+/// Returns the address of the interface that routes to the gateway.
+fn local_ip(gateway: SocketAddr) -> io::Result<IpAddr> {
+    let unspecified: IpAddr =
+        if gateway.is_ipv4() { Ipv4Addr::UNSPECIFIED.into() } else { Ipv6Addr::UNSPECIFIED.into() };
+    // connecting a UDP socket sends no packets, but selects the local interface
+    let socket = UdpSocket::bind((unspecified, 0))?;
+    socket.connect(gateway)?;
+    Ok(socket.local_addr()?.ip())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::port_mapping::{PortMappingService, DEFAULT_PORT_MAPPING_LEASE};
+    use std::sync::{Arc, Mutex};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    /// A SOAP action received by the [MockGateway].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Action {
+        name: String,
+        protocol: String,
+        external_port: u16,
+    }
+
+    /// A local UPnP Internet Gateway Device that answers SSDP searches and records the SOAP actions
+    /// it receives.
+    struct MockGateway {
+        /// The address that answers SSDP searches.
+        ssdp_addr: SocketAddr,
+        /// All received port mapping actions.
+        actions: Arc<Mutex<Vec<Action>>>,
+    }
+
+    impl MockGateway {
+        /// Spawns a gateway that rejects requests for the given external ports as in use.
+        async fn spawn(ports_in_use: Vec<u16>) -> Self {
+            let http = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let http_addr = http.local_addr().unwrap();
+            let ssdp = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let ssdp_addr = ssdp.local_addr().unwrap();
+            let actions = Arc::new(Mutex::new(Vec::new()));
+
+            tokio::spawn(async move {
+                let mut buf = [0u8; 1024];
+                while let Ok((_, from)) = ssdp.recv_from(&mut buf).await {
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\n\
+                         CACHE-CONTROL: max-age=120\r\n\
+                         ST: urn:schemas-upnp-org:device:InternetGatewayDevice:1\r\n\
+                         USN: uuid:mock::urn:schemas-upnp-org:device:InternetGatewayDevice:1\r\n\
+                         LOCATION: http://{http_addr}/rootDesc.xml\r\n\r\n"
+                    );
+                    let _ = ssdp.send_to(response.as_bytes(), from).await;
+                }
+            });
+
+            let recorded = actions.clone();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = http.accept().await {
+                    tokio::spawn(serve_http(stream, recorded.clone(), ports_in_use.clone()));
+                }
+            });
+
+            Self { ssdp_addr, actions }
+        }
+
+        fn search_options(&self) -> SearchOptions {
+            SearchOptions {
+                bind_addr: "127.0.0.1:0".parse().unwrap(),
+                broadcast_address: self.ssdp_addr,
+                timeout: Some(Duration::from_secs(5)),
+            }
+        }
+
+        fn actions(&self) -> Vec<Action> {
+            self.actions.lock().unwrap().clone()
+        }
+    }
+
+    const ROOT_DESC: &str = r#"<?xml version="1.0"?>
+<root xmlns="urn:schemas-upnp-org:device-1-0">
+  <device>
+    <deviceType>urn:schemas-upnp-org:device:InternetGatewayDevice:1</deviceType>
+    <deviceList>
+      <device>
+        <deviceType>urn:schemas-upnp-org:device:WANDevice:1</deviceType>
+        <deviceList>
+          <device>
+            <deviceType>urn:schemas-upnp-org:device:WANConnectionDevice:1</deviceType>
+            <serviceList>
+              <service>
+                <serviceType>urn:schemas-upnp-org:service:WANIPConnection:1</serviceType>
+                <serviceId>urn:upnp-org:serviceId:WANIPConn1</serviceId>
+                <controlURL>/ctl/IPConn</controlURL>
+                <eventSubURL>/evt/IPConn</eventSubURL>
+                <SCPDURL>/WANIPCn.xml</SCPDURL>
+              </service>
+            </serviceList>
+          </device>
+        </deviceList>
+      </device>
+    </deviceList>
+  </device>
+</root>"#;
+
+    const SCPD: &str = r#"<?xml version="1.0"?>
+<scpd xmlns="urn:schemas-upnp-org:service-1-0">
+  <actionList>
+    <action>
+      <name>AddPortMapping</name>
+      <argumentList>
+        <argument><name>NewRemoteHost</name><direction>in</direction></argument>
+        <argument><name>NewExternalPort</name><direction>in</direction></argument>
+        <argument><name>NewProtocol</name><direction>in</direction></argument>
+        <argument><name>NewInternalPort</name><direction>in</direction></argument>
+        <argument><name>NewInternalClient</name><direction>in</direction></argument>
+        <argument><name>NewEnabled</name><direction>in</direction></argument>
+        <argument><name>NewPortMappingDescription</name><direction>in</direction></argument>
+        <argument><name>NewLeaseDuration</name><direction>in</direction></argument>
+      </argumentList>
+    </action>
+    <action>
+      <name>DeletePortMapping</name>
+      <argumentList>
+        <argument><name>NewRemoteHost</name><direction>in</direction></argument>
+        <argument><name>NewExternalPort</name><direction>in</direction></argument>
+        <argument><name>NewProtocol</name><direction>in</direction></argument>
+      </argumentList>
+    </action>
+    <action>
+      <name>GetExternalIPAddress</name>
+      <argumentList>
+        <argument><name>NewExternalIPAddress</name><direction>out</direction></argument>
+      </argumentList>
+    </action>
+  </actionList>
+</scpd>"#;
+
+    /// Serves a single HTTP request of the UPnP client.
+    async fn serve_http(
+        mut stream: TcpStream,
+        actions: Arc<Mutex<Vec<Action>>>,
+        ports_in_use: Vec<u16>,
+    ) {
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        let (head, body) = loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            if n == 0 {
+                return
+            }
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            let Some(end) = text.find("\r\n\r\n") else { continue };
+            let (head, body) = text.split_at(end + 4);
+            let len = head
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse().ok())?
+                })
+                .unwrap_or(0usize);
+            if body.len() >= len {
+                break (head.to_string(), body.to_string())
+            }
+        };
+
+        let path = head.split_whitespace().nth(1).unwrap_or_default();
+        let (status, response) = match path {
+            "/rootDesc.xml" => ("200 OK", ROOT_DESC.to_string()),
+            "/WANIPCn.xml" => ("200 OK", SCPD.to_string()),
+            "/ctl/IPConn" => {
+                let name = ["AddPortMapping", "DeletePortMapping", "GetExternalIPAddress"]
+                    .into_iter()
+                    .find(|name| body.contains(&format!(":{name}")))
+                    .unwrap_or_default()
+                    .to_string();
+                let protocol = xml_value(&body, "NewProtocol").unwrap_or_default();
+                let external_port =
+                    xml_value(&body, "NewExternalPort").and_then(|p| p.parse().ok()).unwrap_or(0);
+
+                if name == "AddPortMapping" && ports_in_use.contains(&external_port) {
+                    ("500 Internal Server Error", soap_fault(718, "ConflictInMappingEntry"))
+                } else {
+                    actions.lock().unwrap().push(Action {
+                        name: name.clone(),
+                        protocol,
+                        external_port,
+                    });
+                    let content = if name == "GetExternalIPAddress" {
+                        "<NewExternalIPAddress>1.2.3.4</NewExternalIPAddress>"
+                    } else {
+                        ""
+                    };
+                    ("200 OK", soap_response(&format!(
+                        "<u:{name}Response xmlns:u=\"urn:schemas-upnp-org:service:WANIPConnection:1\">{content}</u:{name}Response>"
+                    )))
+                }
+            }
+            _ => ("404 Not Found", String::new()),
+        };
+
+        let response = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: text/xml; charset=\"utf-8\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+            response.len()
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.shutdown().await;
+    }
+
+    fn xml_value(xml: &str, tag: &str) -> Option<String> {
+        let start = xml.find(&format!("<{tag}>"))? + tag.len() + 2;
+        let end = xml[start..].find(&format!("</{tag}>"))? + start;
+        Some(xml[start..end].to_string())
+    }
+
+    fn soap_response(body: &str) -> String {
+        format!(
+            "<?xml version=\"1.0\"?><s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\"><s:Body>{body}</s:Body></s:Envelope>"
+        )
+    }
+
+    fn soap_fault(code: u16, description: &str) -> String {
+        soap_response(&format!(
+            "<s:Fault><faultcode>s:Client</faultcode><faultstring>UPnPError</faultstring><detail><UPnPError xmlns=\"urn:schemas-upnp-org:control-1-0\"><errorCode>{code}</errorCode><errorDescription>{description}</errorDescription></UPnPError></detail></s:Fault>"
+        ))
+    }
+
+    fn add(protocol: &str, external_port: u16) -> Action {
+        Action { name: "AddPortMapping".to_string(), protocol: protocol.to_string(), external_port }
+    }
+
+    fn delete(protocol: &str, external_port: u16) -> Action {
+        Action {
+            name: "DeletePortMapping".to_string(),
+            protocol: protocol.to_string(),
+            external_port,
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn map_renew_and_remove_ports() {
+        reth_tracing::init_test_tracing();
+        let gateway = MockGateway::spawn(Vec::new()).await;
+
+        let mut mapper =
+            UpnpPortMapper::search_with(gateway.search_options(), DEFAULT_PORT_MAPPING_LEASE)
+                .await
+                .unwrap();
+        assert_eq!(mapper.local_ip, IpAddr::V4(Ipv4Addr::LOCALHOST));
+
+        let tcp = mapper.add_port(PortMappingProtocol::Tcp, 30303).await.unwrap();
+        let udp = mapper.add_port(PortMappingProtocol::Udp, 30303).await.unwrap();
+        assert_eq!(tcp.external_port, 30303);
+        assert_eq!(udp.external_port, 30303);
+        assert_eq!(mapper.mappings(), &[tcp, udp]);
+
+        // renewing keeps the external ports
+        assert!(mapper.renew().await.is_empty());
+
+        mapper.remove_ports().await;
+        assert!(mapper.mappings().is_empty());
+
+        assert_eq!(
+            gateway.actions(),
+            vec![
+                add("TCP", 30303),
+                add("UDP", 30303),
+                add("TCP", 30303),
+                add("UDP", 30303),
+                delete("TCP", 30303),
+                delete("UDP", 30303),
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn map_port_in_use() {
+        reth_tracing::init_test_tracing();
+        let gateway = MockGateway::spawn(vec![30303]).await;
+
+        let mut mapper =
+            UpnpPortMapper::search_with(gateway.search_options(), DEFAULT_PORT_MAPPING_LEASE)
+                .await
+                .unwrap();
+
+        // the gateway picks another external port
+        let mapping = mapper.add_port(PortMappingProtocol::Tcp, 30303).await.unwrap();
+        assert_ne!(mapping.external_port, 30303);
+        assert_eq!(gateway.actions(), vec![add("TCP", mapping.external_port)]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn service_removes_ports_on_shutdown() {
+        reth_tracing::init_test_tracing();
+        let gateway = MockGateway::spawn(Vec::new()).await;
+
+        let service = PortMappingService {
+            ports: vec![(PortMappingProtocol::Tcp, 30303), (PortMappingProtocol::Udp, 30304)],
+            lease_duration: Duration::from_secs(2),
+            upnp: Some(gateway.search_options()),
+            natpmp: false,
+            natpmp_gateway: None,
+        };
+        let (handle, mut updates) = service.spawn();
+
+        let tcp = updates.recv().await.unwrap();
+        let udp = updates.recv().await.unwrap();
+        assert_eq!(
+            (tcp, udp),
+            (
+                PortMapping {
+                    protocol: PortMappingProtocol::Tcp,
+                    local_port: 30303,
+                    external_port: 30303
+                },
+                PortMapping {
+                    protocol: PortMappingProtocol::Udp,
+                    local_port: 30304,
+                    external_port: 30304
+                },
+            )
+        );
+
+        // wait for the lease to be renewed
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        handle.shutdown().await;
+
+        assert_eq!(
+            gateway.actions(),
+            vec![
+                add("TCP", 30303),
+                add("UDP", 30304),
+                add("TCP", 30303),
+                add("UDP", 30304),
+                delete("TCP", 30303),
+                delete("UDP", 30304),
+            ]
+        );
+    }
+}
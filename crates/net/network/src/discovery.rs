@@ -6,9 +6,13 @@ use crate::{
 };
 use futures::StreamExt;
 use reth_discv4::{DiscoveryUpdate, Discv4, Discv4Config, EnrForkIdEntry};
+use reth_discv5::{Discv5, Discv5Config};
 use reth_dns_discovery::{
     DnsDiscoveryConfig, DnsDiscoveryHandle, DnsDiscoveryService, DnsNodeRecordUpdate, DnsResolver,
 };
+use reth_net_nat::{
+    NatResolver, PortMapping, PortMappingHandle, PortMappingProtocol, PortMappingService,
+};
 use reth_primitives::{ForkId, NodeRecord, PeerId};
 use secp256k1::SecretKey;
 use std::{
@@ -17,9 +21,13 @@ use std::{
     pin::Pin,
     sync::Arc,
     task::{ready, Context, Poll},
+    time::Duration,
 };
 use tokio::{sync::mpsc, task::JoinHandle};
-use tokio_stream::{wrappers::ReceiverStream, Stream};
+use tokio_stream::{
+    wrappers::{ReceiverStream, UnboundedReceiverStream},
+    Stream,
+};
 
 /// An abstraction over the configured discovery protocol.
 ///
@@ -39,12 +47,22 @@ pub struct Discovery {
     discv4_updates: Option<ReceiverStream<DiscoveryUpdate>>,
     /// The handle to the spawned discv4 service
     _discv4_service: Option<JoinHandle<()>>,
+    /// Handler to interact with the Discovery v5 service
+    discv5: Option<Discv5>,
+    /// All updates for `eth` nodes from the discv5 service.
+    discv5_updates: Option<ReceiverStream<DiscoveryUpdate>>,
+    /// The handle to the spawned discv5 service
+    _discv5_service: Option<JoinHandle<()>>,
     /// Handler to interact with the DNS discovery service
     _dns_discovery: Option<DnsDiscoveryHandle>,
     /// Updates from the DNS discovery service.
     dns_discovery_updates: Option<ReceiverStream<DnsNodeRecordUpdate>>,
     /// The handle to the spawned DNS discovery service
     _dns_disc_service: Option<JoinHandle<()>>,
+    /// The handle to the spawned port mapping service, the ports are unmapped on drop.
+    _port_mapping: Option<PortMappingHandle>,
+    /// Updates of the external ports from the port mapping service.
+    port_mapping_updates: Option<UnboundedReceiverStream<PortMapping>>,
     /// Events buffered until polled.
     queued_events: VecDeque<DiscoveryEvent>,
     /// List of listeners subscribed to discovery events.
@@ -54,14 +72,22 @@ pub struct Discovery {
 impl Discovery {
     /// Spawns the discovery service.
     ///
-    /// This will spawn the [`reth_discv4::Discv4Service`] onto a new task and establish a listener
-    /// channel to receive all discovered nodes.
+    /// This will spawn the [`reth_discv4::Discv4Service`] and the [`Discv5`] service onto new tasks
+    /// and establish listener channels to receive all discovered nodes.
     pub async fn new(
         discovery_addr: SocketAddr,
         sk: SecretKey,
         discv4_config: Option<Discv4Config>,
+        discv5_config: Option<Discv5Config>,
         dns_discovery_config: Option<DnsDiscoveryConfig>,
     ) -> Result<Self, NetworkError> {
+        // discv4 and discv5 each bind their own UDP socket, so they can't share a port
+        if let (Some(_), Some(discv5_config)) = (&discv4_config, &discv5_config) {
+            if udp_addrs_conflict(discovery_addr, discv5_config.discovery_addr) {
+                return Err(NetworkError::DiscoveryPortConflict(discovery_addr.port()))
+            }
+        }
+
         // setup discv4
         let local_enr = NodeRecord::from_secret_key(discovery_addr, &sk);
         let (discv4, discv4_updates, _discv4_service) = if let Some(disc_config) = discv4_config {
@@ -77,6 +103,14 @@ impl Discovery {
             (None, None, None)
         };
 
+        // setup discv5
+        let (discv5, discv5_updates, _discv5_service) = if let Some(disc_config) = discv5_config {
+            let (discv5, discv5_updates, discv5_service) = Discv5::start(&sk, disc_config).await?;
+            (Some(discv5), Some(discv5_updates), Some(discv5_service))
+        } else {
+            (None, None, None)
+        };
+
         // setup DNS discovery
         let (_dns_discovery, dns_discovery_updates, _dns_disc_service) =
             if let Some(dns_config) = dns_discovery_config {
@@ -97,11 +131,16 @@ impl Discovery {
             discv4,
             discv4_updates,
             _discv4_service,
+            discv5,
+            discv5_updates,
+            _discv5_service,
             discovered_nodes: Default::default(),
             queued_events: Default::default(),
             _dns_disc_service,
             _dns_discovery,
             dns_discovery_updates,
+            _port_mapping: None,
+            port_mapping_updates: None,
         })
     }
 
@@ -116,27 +155,87 @@ impl Discovery {
         self.discovery_listeners.retain_mut(|listener| listener.send(event.clone()).is_ok());
     }
 
-    /// Updates the `eth:ForkId` field in discv4.
+    /// Updates the `eth:ForkId` field in discv4 and discv5.
     #[allow(unused)]
     pub(crate) fn update_fork_id(&self, fork_id: ForkId) {
         if let Some(discv4) = &self.discv4 {
             // use forward-compatible forkid entry
             discv4.set_eip868_rlp("eth".as_bytes().to_vec(), EnrForkIdEntry::from(fork_id))
         }
+        if let Some(discv5) = &self.discv5 {
+            discv5.set_fork_id(fork_id)
+        }
     }
 
-    /// Bans the [`IpAddr`] in the discovery service.
+    /// Bans the [`IpAddr`] in the discovery services.
     pub(crate) fn ban_ip(&self, ip: IpAddr) {
         if let Some(discv4) = &self.discv4 {
             discv4.ban_ip(ip)
         }
+        if let Some(discv5) = &self.discv5 {
+            discv5.ban_ip(ip)
+        }
     }
 
-    /// Bans the [`PeerId`] and [`IpAddr`] in the discovery service.
+    /// Bans the [`PeerId`] and [`IpAddr`] in the discovery services.
     pub(crate) fn ban(&self, peer_id: PeerId, ip: IpAddr) {
         if let Some(discv4) = &self.discv4 {
             discv4.ban(peer_id, ip)
         }
+        if let Some(discv5) = &self.discv5 {
+            discv5.ban(peer_id, ip)
+        }
+    }
+
+    /// Maps the given RLPx TCP port and the UDP ports of discv4 and discv5 via the gateway, using
+    /// the port mapping protocols of the resolver.
+    ///
+    /// The mapped external ports are announced in the discv4 [NodeRecord] and the discv5 ENR once
+    /// they're known.
+    pub(crate) fn map_ports(
+        &mut self,
+        resolver: NatResolver,
+        tcp_port: u16,
+        lease_duration: Duration,
+    ) {
+        let mut ports = vec![(PortMappingProtocol::Tcp, tcp_port)];
+        if let Some(discv4) = &self.discv4 {
+            ports.push((PortMappingProtocol::Udp, discv4.local_addr().port()));
+        }
+        if let Some(discv5) = &self.discv5 {
+            ports.push((PortMappingProtocol::Udp, discv5.local_addr().port()));
+        }
+        let (handle, updates) = PortMappingService::new(resolver, ports, lease_duration).spawn();
+        self._port_mapping = Some(handle);
+        self.port_mapping_updates = Some(UnboundedReceiverStream::new(updates));
+    }
+
+    /// Announces the external port of a port mapping.
+    fn on_port_mapping(&mut self, mapping: PortMapping) {
+        match mapping.protocol {
+            PortMappingProtocol::Tcp => {
+                self.local_enr.tcp_port = mapping.external_port;
+                if let Some(discv4) = &self.discv4 {
+                    discv4.set_tcp_port(mapping.external_port)
+                }
+                if let Some(discv5) = &self.discv5 {
+                    discv5.set_tcp_port(mapping.external_port)
+                }
+            }
+            PortMappingProtocol::Udp => {
+                if let Some(discv4) =
+                    self.discv4.as_ref().filter(|d| d.local_addr().port() == mapping.local_port)
+                {
+                    self.local_enr.udp_port = mapping.external_port;
+                    discv4.set_udp_port(mapping.external_port)
+                }
+                if let Some(discv5) =
+                    self.discv5.as_ref().filter(|d| d.local_addr().port() == mapping.local_port)
+                {
+                    discv5.set_udp_port(mapping.external_port)
+                }
+            }
+        }
     }
 
     /// Returns the id with which the local identifies itself in the network
@@ -166,7 +265,8 @@ impl Discovery {
         }
     }
 
-    fn on_discv4_update(&mut self, update: DiscoveryUpdate) {
+    /// Processes an update from the discv4 or discv5 service
+    fn on_discovery_update(&mut self, update: DiscoveryUpdate) {
         match update {
             DiscoveryUpdate::Added(record) => {
                 self.on_node_record_update(record, None);
@@ -179,7 +279,7 @@ impl Discovery {
             }
             DiscoveryUpdate::Batch(updates) => {
                 for update in updates {
-                    self.on_discv4_update(update);
+                    self.on_discovery_update(update);
                 }
             }
             DiscoveryUpdate::DiscoveredAtCapacity(record) => {
@@ -200,7 +300,19 @@ impl Discovery {
             while let Some(Poll::Ready(Some(update))) =
                 self.discv4_updates.as_mut().map(|updates| updates.poll_next_unpin(cx))
             {
-                self.on_discv4_update(update)
+                self.on_discovery_update(update)
+            }
+
+            while let Some(Poll::Ready(Some(update))) =
+                self.discv5_updates.as_mut().map(|updates| updates.poll_next_unpin(cx))
+            {
+                self.on_discovery_update(update)
+            }
+
+            while let Some(Poll::Ready(Some(mapping))) =
+                self.port_mapping_updates.as_mut().map(|updates| updates.poll_next_unpin(cx))
+            {
+                self.on_port_mapping(mapping)
             }
 
             while let Some(Poll::Ready(Some(update))) =
@@ -246,9 +358,14 @@ impl Discovery {
             discv4_updates: Default::default(),
             queued_events: Default::default(),
             _discv4_service: Default::default(),
+            discv5: None,
+            discv5_updates: None,
+            _discv5_service: None,
             _dns_discovery: None,
             dns_discovery_updates: None,
             _dns_disc_service: None,
+            _port_mapping: None,
+            port_mapping_updates: None,
             discovery_listeners: Default::default(),
         }
     }
@@ -263,6 +380,14 @@ pub enum DiscoveryEvent {
     EnrForkId(PeerId, ForkId),
 }
 
+/// Returns true if binding both UDP addresses would fail because they use the same port on
+/// overlapping interfaces.
+fn udp_addrs_conflict(a: SocketAddr, b: SocketAddr) -> bool {
+    a.port() != 0 &&
+        a.port() == b.port() &&
+        (a.ip() == b.ip() || a.ip().is_unspecified() || b.ip().is_unspecified())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -275,9 +400,43 @@ mod tests {
         let mut rng = thread_rng();
         let (secret_key, _) = SECP256K1.generate_keypair(&mut rng);
         let discovery_addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0));
-        let _discovery =
-            Discovery::new(discovery_addr, secret_key, Default::default(), Default::default())
-                .await
-                .unwrap();
+        let _discovery = Discovery::new(
+            discovery_addr,
+            secret_key,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_discv4_discv5_port_conflict() {
+        let mut rng = thread_rng();
+        let (secret_key, _) = SECP256K1.generate_keypair(&mut rng);
+        let discovery_addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 30399));
+        let discv5_config = Discv5Config::builder().discovery_addr(discovery_addr).build();
+
+        let err = Discovery::new(
+            discovery_addr,
+            secret_key,
+            Some(Default::default()),
+            Some(discv5_config),
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, NetworkError::DiscoveryPortConflict(30399)));
+
+        assert!(!udp_addrs_conflict(
+            "127.0.0.1:30399".parse().unwrap(),
+            "127.0.0.2:30399".parse().unwrap()
+        ));
+        assert!(udp_addrs_conflict(
+            "0.0.0.0:30399".parse().unwrap(),
+            "127.0.0.1:30399".parse().unwrap()
+        ));
+        assert!(!udp_addrs_conflict("0.0.0.0:0".parse().unwrap(), "0.0.0.0:0".parse().unwrap()));
     }
 }
@@ -1,6 +1,7 @@
 //! Possible errors when interacting with the network.
 
 use crate::session::PendingSessionHandshakeError;
+use reth_discv5::Discv5Error;
 use reth_dns_discovery::resolver::ResolveError;
 use reth_eth_wire::{
     errors::{EthHandshakeError, EthStreamError, P2PHandshakeError, P2PStreamError},
@@ -48,6 +49,14 @@ pub enum NetworkError {
     /// See also [DnsResolver](reth_dns_discovery::DnsResolver::from_system_conf)
     #[error("failed to configure DNS resolver: {0}")]
     DnsResolver(#[from] ResolveError),
+    /// Error when launching the discv5 service
+    #[error(transparent)]
+    Discv5(#[from] Discv5Error),
+    /// Error when discv4 and discv5 are configured to listen on the same UDP port.
+    ///
+    /// discv5 can only use the port of discv4 if discv4 is disabled.
+    #[error("discv4 and discv5 can't both listen on UDP port {0}, use a separate port for discv5 or disable discv4")]
+    DiscoveryPortConflict(u16),
 }
 
 impl NetworkError {
@@ -131,6 +131,7 @@ mod network;
 pub mod peers;
 pub mod protocol;
 mod session;
+pub mod snap;
 mod state;
 mod swarm;
 pub mod transactions;
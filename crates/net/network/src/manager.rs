@@ -40,6 +40,7 @@ use reth_eth_wire::{
 };
 use reth_metrics::common::mpsc::UnboundedMeteredSender;
 use reth_net_common::bandwidth_meter::BandwidthMeter;
+use reth_net_nat::DEFAULT_PORT_MAPPING_LEASE;
 use reth_network_api::ReputationChangeKind;
 use reth_primitives::{ForkId, NodeRecord, PeerId, B256};
 use reth_provider::{BlockNumReader, BlockReader};
@@ -169,6 +170,7 @@ where
             client,
             secret_key,
             mut discovery_v4_config,
+            mut discovery_v5_config,
             discovery_addr,
             listener_addr,
             peers_config,
@@ -203,9 +205,34 @@ where
             disc_config
         });
 
-        let discovery =
-            Discovery::new(discovery_addr, secret_key, discovery_v4_config, dns_discovery_config)
-                .await?;
+        discovery_v5_config = discovery_v5_config.map(|mut disc_config| {
+            // advertise the RLPx port and fork id of the node
+            disc_config.tcp_port = incoming.local_address().port();
+            disc_config.fork_id = Some(status.forkid);
+            disc_config
+        });
+
+        // map the ports via the gateway if the external IP is resolved via UPnP or NAT-PMP
+        let port_mapping_resolver = discovery_v4_config
+            .as_ref()
+            .and_then(|disc_config| disc_config.external_ip_resolver)
+            .filter(|resolver| resolver.maps_ports());
+
+        let mut discovery = Discovery::new(
+            discovery_addr,
+            secret_key,
+            discovery_v4_config,
+            discovery_v5_config,
+            dns_discovery_config,
+        )
+        .await?;
+        if let Some(resolver) = port_mapping_resolver {
+            discovery.map_ports(
+                resolver,
+                incoming.local_address().port(),
+                DEFAULT_PORT_MAPPING_LEASE,
+            );
+        }
         // need to retrieve the addr here since provided port could be `0`
         let local_peer_id = discovery.local_id();
 
@@ -3,7 +3,10 @@
 //! See also <https://github.com/ethereum/devp2p/blob/master/README.md>
 
 use futures::{Stream, StreamExt};
-use reth_eth_wire::{capability::SharedCapabilities, protocol::Protocol};
+use reth_eth_wire::{
+    capability::{SharedCapabilities, SharedCapability},
+    protocol::Protocol,
+};
 use reth_network_api::Direction;
 use reth_primitives::BytesMut;
 use reth_rpc_types::PeerId;
@@ -14,7 +17,8 @@ use std::{
     task::{Context, Poll},
 };
 
-use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 
 /// A trait that allows to offer additional RLPx-based application-level protocols when establishing
 /// a peer-to-peer connection.
@@ -51,8 +55,11 @@ pub trait ConnectionHandler: Send + Sync + 'static {
     /// This will be negotiated with the remote peer.
     fn protocol(&self) -> Protocol;
 
-    /// Invoked when the RLPx connection has been established by the peer does not share the
+    /// Invoked when the RLPx connection has been established but the peer does not share the
     /// protocol.
+    ///
+    /// If this returns [OnNotSupported::Disconnect], the connection is closed before the session
+    /// is established.
     fn on_unsupported_by_peer(
         self,
         supported: &SharedCapabilities,
@@ -62,7 +69,9 @@ pub trait ConnectionHandler: Send + Sync + 'static {
 
     /// Invoked when the RLPx connection was established.
     ///
-    /// The returned future should resolve when the connection should disconnect.
+    /// The returned stream yields the messages to send to the remote, the first byte of each
+    /// message is the message id relative to the protocol's message id offset. The RLPx connection
+    /// is closed when the stream resolves.
     fn into_connection(
         self,
         direction: Direction,
@@ -83,10 +92,21 @@ pub enum OnNotSupported {
 
 /// A connection channel to receive messages for the negotiated protocol.
 ///
-/// This is a [Stream] that returns raw bytes of the received messages for this protocol.
+/// This is a [Stream] that returns raw bytes of the received messages for this protocol. The first
+/// byte of each message is the message id relative to the protocol's message id offset, so the
+/// first message of the protocol always has the id `0`.
+///
+/// The stream ends when the RLPx connection is closed.
 #[derive(Debug)]
 pub struct ProtocolConnection {
-    from_wire: UnboundedReceiverStream<BytesMut>,
+    from_wire: ReceiverStream<BytesMut>,
+}
+
+impl ProtocolConnection {
+    /// Creates a new connection from the channel of received messages.
+    pub(crate) fn new(from_wire: mpsc::Receiver<BytesMut>) -> Self {
+        Self { from_wire: ReceiverStream::new(from_wire) }
+    }
 }
 
 impl Stream for ProtocolConnection {
@@ -128,6 +148,112 @@ impl RlpxSubProtocols {
     pub fn push(&mut self, protocol: impl IntoRlpxSubProtocol) {
         self.protocols.push(protocol.into_rlpx_sub_protocol());
     }
+
+    /// Returns the connection handlers of all protocols that should be announced for a new
+    /// incoming connection.
+    pub(crate) fn on_incoming(&self, socket_addr: SocketAddr) -> RlpxSubProtocolHandlers {
+        RlpxSubProtocolHandlers(
+            self.protocols
+                .iter()
+                .filter_map(|protocol| protocol.0.on_incoming(socket_addr))
+                .collect(),
+        )
+    }
+
+    /// Returns the connection handlers of all protocols that should be announced for a new
+    /// outgoing connection.
+    pub(crate) fn on_outgoing(
+        &self,
+        socket_addr: SocketAddr,
+        peer_id: PeerId,
+    ) -> RlpxSubProtocolHandlers {
+        RlpxSubProtocolHandlers(
+            self.protocols
+                .iter()
+                .filter_map(|protocol| protocol.0.on_outgoing(socket_addr, peer_id))
+                .collect(),
+        )
+    }
+}
+
+/// The connection handlers of the additional protocols that are announced for a single connection.
+#[derive(Default)]
+pub(crate) struct RlpxSubProtocolHandlers(Vec<Box<dyn DynConnectionHandler>>);
+
+impl RlpxSubProtocolHandlers {
+    /// Returns the protocols to announce in the `Hello` message.
+    pub(crate) fn protocols(&self) -> impl Iterator<Item = Protocol> + '_ {
+        self.0.iter().map(|handler| handler.protocol())
+    }
+}
+
+impl IntoIterator for RlpxSubProtocolHandlers {
+    type Item = Box<dyn DynConnectionHandler>;
+    type IntoIter = std::vec::IntoIter<Box<dyn DynConnectionHandler>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl fmt::Debug for RlpxSubProtocolHandlers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.protocols()).finish()
+    }
+}
+
+/// The established connections of all additional protocols that are shared with the peer of a
+/// session.
+///
+/// Each connection yields the messages of its [SharedCapability] that should be sent to the peer.
+#[derive(Default)]
+pub struct RlpxSubProtocolConnections {
+    connections: Vec<(SharedCapability, Pin<Box<dyn Stream<Item = BytesMut> + Send + 'static>>)>,
+}
+
+impl RlpxSubProtocolConnections {
+    /// Adds the connection of the given shared capability.
+    pub(crate) fn push(
+        &mut self,
+        capability: SharedCapability,
+        conn: Pin<Box<dyn Stream<Item = BytesMut> + Send + 'static>>,
+    ) {
+        self.connections.push((capability, conn));
+    }
+
+    /// Returns the number of established connections.
+    pub fn len(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// Returns `true` if there are no established connections.
+    pub fn is_empty(&self) -> bool {
+        self.connections.is_empty()
+    }
+
+    /// Polls all connections for the next message to send to the peer.
+    ///
+    /// Returns `None` if one of the connections resolved, in which case the RLPx connection should
+    /// be closed.
+    pub(crate) fn poll_next_message(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<(&SharedCapability, BytesMut)>> {
+        for (capability, conn) in self.connections.iter_mut() {
+            match conn.poll_next_unpin(cx) {
+                Poll::Ready(Some(msg)) => return Poll::Ready(Some((capability, msg))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => {}
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl fmt::Debug for RlpxSubProtocolConnections {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.connections.iter().map(|(capability, _)| capability)).finish()
+    }
 }
 
 pub(crate) trait DynProtocolHandler: fmt::Debug + Send + Sync + 'static {
@@ -164,14 +290,14 @@ pub(crate) trait DynConnectionHandler: Send + Sync + 'static {
     fn protocol(&self) -> Protocol;
 
     fn on_unsupported_by_peer(
-        self,
+        self: Box<Self>,
         supported: &SharedCapabilities,
         direction: Direction,
         peer_id: PeerId,
     ) -> OnNotSupported;
 
     fn into_connection(
-        self,
+        self: Box<Self>,
         direction: Direction,
         peer_id: PeerId,
         conn: ProtocolConnection,
@@ -187,20 +313,20 @@ where
     }
 
     fn on_unsupported_by_peer(
-        self,
+        self: Box<Self>,
         supported: &SharedCapabilities,
         direction: Direction,
         peer_id: PeerId,
     ) -> OnNotSupported {
-        T::on_unsupported_by_peer(self, supported, direction, peer_id)
+        T::on_unsupported_by_peer(*self, supported, direction, peer_id)
     }
 
     fn into_connection(
-        self,
+        self: Box<Self>,
         direction: Direction,
         peer_id: PeerId,
         conn: ProtocolConnection,
     ) -> Pin<Box<dyn Stream<Item = BytesMut> + Send + 'static>> {
-        Box::pin(T::into_connection(self, direction, peer_id, conn))
+        Box::pin(T::into_connection(*self, direction, peer_id, conn))
     }
 }
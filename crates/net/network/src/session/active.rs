@@ -2,6 +2,7 @@
 
 use crate::{
     message::{NewBlockMessage, PeerMessage, PeerRequest, PeerResponse, PeerResponseResult},
+    protocol::RlpxSubProtocolConnections,
     session::{
         config::INITIAL_REQUEST_TIMEOUT,
         handle::{ActiveSessionMessage, SessionCommand},
@@ -71,6 +72,8 @@ pub(crate) struct ActiveSession {
     pub(crate) next_id: u64,
     /// The underlying connection.
     pub(crate) conn: PeerConnection,
+    /// The connections of the additional RLPx sub-protocols that are multiplexed over `conn`.
+    pub(crate) extra_protocols: RlpxSubProtocolConnections,
     /// Identifier of the node we're connected to.
     pub(crate) remote_peer_id: PeerId,
     /// The address we're connected to.
@@ -573,6 +576,26 @@ impl Future for ActiveSession {
                 }
             }
 
+            // Send the messages of the additional sub-protocols
+            while this.conn.poll_ready_unpin(cx).is_ready() {
+                match this.extra_protocols.poll_next_message(cx) {
+                    Poll::Ready(Some((capability, msg))) => {
+                        progress = true;
+                        if let Err(err) =
+                            this.conn.inner_mut().start_send_capability(capability, msg.freeze())
+                        {
+                            debug!(target: "net::session", ?err, remote_peer_id=?this.remote_peer_id, "failed to send sub-protocol message");
+                            return this.close_on_error(err.into(), cx)
+                        }
+                    }
+                    Poll::Ready(None) => {
+                        debug!(target: "net::session", remote_peer_id=?this.remote_peer_id, "sub-protocol connection closed");
+                        return this.try_disconnect(DisconnectReason::DisconnectRequested, cx)
+                    }
+                    Poll::Pending => break,
+                }
+            }
+
             // read incoming messages from the wire
             'receive: loop {
                 // ensure we still have enough budget for another iteration
@@ -856,6 +879,7 @@ mod tests {
                 self.hello.clone(),
                 self.status,
                 self.fork_filter.clone(),
+                Default::default(),
             ));
 
             let mut stream = ReceiverStream::new(pending_sessions_rx);
@@ -867,6 +891,7 @@ mod tests {
                     peer_id,
                     capabilities,
                     conn,
+                    extra_protocols,
                     ..
                 } => {
                     let (_to_session_tx, messages_rx) = mpsc::channel(10);
@@ -890,6 +915,7 @@ mod tests {
                         internal_request_tx: ReceiverStream::new(messages_rx).fuse(),
                         inflight_requests: Default::default(),
                         conn,
+                        extra_protocols,
                         queued_outgoing: Default::default(),
                         received_requests_from_remote: Default::default(),
                         internal_request_timeout_interval: tokio::time::interval(
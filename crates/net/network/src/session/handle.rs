@@ -3,6 +3,7 @@
 use super::active::PeerConnection;
 use crate::{
     message::PeerMessage,
+    protocol::RlpxSubProtocolConnections,
     session::{Direction, SessionId},
 };
 use reth_ecies::ECIESError;
@@ -175,6 +176,8 @@ pub enum PendingSessionEvent {
         /// The actual connection stream which can be used to send and receive `eth` protocol
         /// messages
         conn: PeerConnection,
+        /// The connections of the additional RLPx sub-protocols shared with the peer
+        extra_protocols: RlpxSubProtocolConnections,
         /// The direction of the session, either `Inbound` or `Outgoing`
         direction: Direction,
         /// The remote node's user agent, usually containing the client name and version
@@ -9,7 +9,7 @@ use futures::{future::Either, io, FutureExt, StreamExt};
 use reth_ecies::{stream::ECIESStream, ECIESError};
 use reth_eth_wire::{
     capability::{Capabilities, CapabilityMessage},
-    errors::EthStreamError,
+    errors::{EthStreamError, P2PStreamError},
     DisconnectReason, EthVersion, HelloMessageWithProtocols, Status, UnauthedEthStream,
     UnauthedP2PStream,
 };
@@ -48,7 +48,10 @@ pub use handle::{
     SessionCommand,
 };
 
-use crate::protocol::RlpxSubProtocols;
+use crate::protocol::{
+    OnNotSupported, ProtocolConnection, RlpxSubProtocolConnections, RlpxSubProtocolHandlers,
+    RlpxSubProtocols,
+};
 pub use reth_network_api::{Direction, PeerInfo};
 
 /// Internal identifier for active sessions.
@@ -103,7 +106,6 @@ pub struct SessionManager {
     /// Receiver half that listens for [`ActiveSessionMessage`] produced by pending sessions.
     active_session_rx: ReceiverStream<ActiveSessionMessage>,
     /// Additional RLPx sub-protocols to be used by the session manager.
-    #[allow(unused)]
     extra_protocols: RlpxSubProtocols,
     /// Used to measure inbound & outbound bandwidth across all managed streams
     bandwidth_meter: BandwidthMeter,
@@ -221,7 +223,9 @@ impl SessionManager {
         let pending_events = self.pending_sessions_tx.clone();
         let metered_stream = MeteredStream::new_with_meter(stream, self.bandwidth_meter.clone());
         let secret_key = self.secret_key;
-        let hello_message = self.hello_message.clone();
+        let extra_handlers = self.extra_protocols.on_incoming(remote_addr);
+        let mut hello_message = self.hello_message.clone();
+        hello_message.protocols.extend(extra_handlers.protocols());
         let status = self.status;
         let fork_filter = self.fork_filter.clone();
         self.spawn(start_pending_incoming_session(
@@ -234,6 +238,7 @@ impl SessionManager {
             hello_message,
             status,
             fork_filter,
+            extra_handlers,
         ));
 
         let handle = PendingSessionHandle {
@@ -253,7 +258,9 @@ impl SessionManager {
             let (disconnect_tx, disconnect_rx) = oneshot::channel();
             let pending_events = self.pending_sessions_tx.clone();
             let secret_key = self.secret_key;
-            let hello_message = self.hello_message.clone();
+            let extra_handlers = self.extra_protocols.on_outgoing(remote_addr, remote_peer_id);
+            let mut hello_message = self.hello_message.clone();
+            hello_message.protocols.extend(extra_handlers.protocols());
             let fork_filter = self.fork_filter.clone();
             let status = self.status;
             let band_with_meter = self.bandwidth_meter.clone();
@@ -268,6 +275,7 @@ impl SessionManager {
                 status,
                 fork_filter,
                 band_with_meter,
+                extra_handlers,
             ));
 
             let handle = PendingSessionHandle {
@@ -406,6 +414,7 @@ impl SessionManager {
                 peer_id,
                 capabilities,
                 conn,
+                extra_protocols,
                 status,
                 direction,
                 client_id,
@@ -462,6 +471,7 @@ impl SessionManager {
                     internal_request_tx: ReceiverStream::new(messages_rx).fuse(),
                     inflight_requests: Default::default(),
                     conn,
+                    extra_protocols,
                     queued_outgoing: Default::default(),
                     received_requests_from_remote: Default::default(),
                     internal_request_timeout_interval: tokio::time::interval(
@@ -753,6 +763,7 @@ pub(crate) async fn start_pending_incoming_session(
     hello: HelloMessageWithProtocols,
     status: Status,
     fork_filter: ForkFilter,
+    extra_handlers: RlpxSubProtocolHandlers,
 ) {
     authenticate(
         disconnect_rx,
@@ -765,6 +776,7 @@ pub(crate) async fn start_pending_incoming_session(
         hello,
         status,
         fork_filter,
+        extra_handlers,
     )
     .await
 }
@@ -783,6 +795,7 @@ async fn start_pending_outbound_session(
     status: Status,
     fork_filter: ForkFilter,
     bandwidth_meter: BandwidthMeter,
+    extra_handlers: RlpxSubProtocolHandlers,
 ) {
     let stream = match TcpStream::connect(remote_addr).await {
         Ok(stream) => {
@@ -814,6 +827,7 @@ async fn start_pending_outbound_session(
         hello,
         status,
         fork_filter,
+        extra_handlers,
     )
     .await
 }
@@ -831,6 +845,7 @@ async fn authenticate(
     hello: HelloMessageWithProtocols,
     status: Status,
     fork_filter: ForkFilter,
+    extra_handlers: RlpxSubProtocolHandlers,
 ) {
     let local_addr = stream.inner().local_addr().ok();
     let stream = match get_eciess_stream(stream, secret_key, direction).await {
@@ -859,6 +874,7 @@ async fn authenticate(
         hello,
         status,
         fork_filter,
+        extra_handlers,
     )
     .boxed();
 
@@ -907,6 +923,7 @@ async fn authenticate_stream(
     hello: HelloMessageWithProtocols,
     status: Status,
     fork_filter: ForkFilter,
+    extra_handlers: RlpxSubProtocolHandlers,
 ) -> PendingSessionEvent {
     // conduct the p2p handshake and return the authenticated stream
     let (p2p_stream, their_hello) = match stream.handshake(hello).await {
@@ -939,7 +956,7 @@ async fn authenticate_stream(
     // Before trying status handshake, set up the version to shared_capability
     let status = Status { version, ..status };
     let eth_unauthed = UnauthedEthStream::new(p2p_stream);
-    let (eth_stream, their_status) = match eth_unauthed.handshake(status, fork_filter).await {
+    let (mut eth_stream, their_status) = match eth_unauthed.handshake(status, fork_filter).await {
         Ok(stream_res) => stream_res,
         Err(err) => {
             return PendingSessionEvent::Disconnected {
@@ -950,6 +967,46 @@ async fn authenticate_stream(
             }
         }
     };
+
+    // connect the additional sub-protocols, messages received in the meantime are buffered by the
+    // p2p stream
+    let mut extra_protocols = RlpxSubProtocolConnections::default();
+    for handler in extra_handlers {
+        let p2p_stream = eth_stream.inner_mut();
+        let shared = p2p_stream
+            .shared_capabilities()
+            .find(&handler.protocol().cap)
+            .cloned()
+            .and_then(|cap| Some((p2p_stream.take_capability_messages(&cap)?, cap)));
+
+        match shared {
+            Some((from_wire, capability)) => {
+                let conn = handler.into_connection(
+                    direction,
+                    their_hello.id,
+                    ProtocolConnection::new(from_wire),
+                );
+                extra_protocols.push(capability, conn);
+            }
+            None => {
+                let on_not_supported = handler.on_unsupported_by_peer(
+                    p2p_stream.shared_capabilities(),
+                    direction,
+                    their_hello.id,
+                );
+                if on_not_supported == OnNotSupported::Disconnect {
+                    let _ = p2p_stream.disconnect(DisconnectReason::UselessPeer).await;
+                    return PendingSessionEvent::Disconnected {
+                        remote_addr,
+                        session_id,
+                        direction,
+                        error: Some(P2PStreamError::CapabilityNotShared.into()),
+                    }
+                }
+            }
+        }
+    }
+
     PendingSessionEvent::Established {
         session_id,
         remote_addr,
@@ -958,6 +1015,7 @@ async fn authenticate_stream(
         capabilities: Arc::new(Capabilities::from(their_hello.capabilities)),
         status: Arc::new(their_status),
         conn: Box::new(eth_stream),
+        extra_protocols,
         direction,
         client_id: their_hello.client_version,
     }
@@ -0,0 +1,209 @@
+//! A client implementation that sends `snap` requests to peers.
+
+use crate::peers::PeersHandle;
+use futures::FutureExt;
+use parking_lot::Mutex;
+use reth_eth_wire::{
+    AccountRange, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges, GetTrieNodes,
+    SnapMessage, StorageRanges, TrieNodes,
+};
+use reth_interfaces::p2p::{
+    download::DownloadClient,
+    error::{RequestError, RequestResult},
+    snap::client::{SnapClient, SnapFut},
+};
+use reth_network_api::ReputationChangeKind;
+use reth_primitives::{PeerId, WithPeerId};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::{mpsc, oneshot};
+
+/// The time after which a `snap` request is considered timed out.
+const SNAP_REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// A request of the [SnapFetchClient] to a peer.
+#[derive(Debug)]
+pub(crate) struct SnapPeerRequest {
+    /// The request, the request id is assigned by the connection.
+    pub(crate) request: SnapMessage,
+    /// The channel to send the response to.
+    pub(crate) response: oneshot::Sender<RequestResult<SnapMessage>>,
+}
+
+/// The peers with an established `snap` connection.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SnapPeers {
+    inner: Arc<Mutex<HashMap<PeerId, mpsc::UnboundedSender<SnapPeerRequest>>>>,
+}
+
+impl SnapPeers {
+    /// Registers the channel to the connection with the peer.
+    pub(crate) fn insert(&self, peer_id: PeerId, tx: mpsc::UnboundedSender<SnapPeerRequest>) {
+        self.inner.lock().insert(peer_id, tx);
+    }
+
+    /// Removes the peer if it is still registered with the given channel.
+    pub(crate) fn remove(&self, peer_id: &PeerId, tx: &mpsc::UnboundedSender<SnapPeerRequest>) {
+        let mut peers = self.inner.lock();
+        if peers.get(peer_id).map_or(false, |peer| peer.same_channel(tx)) {
+            peers.remove(peer_id);
+        }
+    }
+
+    /// Returns the number of peers.
+    fn len(&self) -> usize {
+        self.inner.lock().len()
+    }
+
+    /// Sends the request to the peer at the given position, or the next peer with an open
+    /// connection.
+    ///
+    /// Returns the peer the request was sent to.
+    fn send(&self, position: usize, mut request: SnapPeerRequest) -> Option<PeerId> {
+        let mut peers = self.inner.lock();
+        while !peers.is_empty() {
+            let (peer_id, tx) = peers.iter().nth(position % peers.len())?;
+            let peer_id = *peer_id;
+            match tx.send(request) {
+                Ok(()) => return Some(peer_id),
+                Err(err) => {
+                    // the connection was closed
+                    request = err.0;
+                    peers.remove(&peer_id);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Front-end API for fetching state from the `snap` peers of the network.
+///
+/// Requests are distributed over all peers with an established `snap` connection in a round-robin
+/// fashion.
+#[derive(Debug, Clone)]
+pub struct SnapFetchClient {
+    /// The peers with an established `snap` connection.
+    peers: SnapPeers,
+    /// The handle to the peers
+    peers_handle: PeersHandle,
+    /// The position of the next peer to send a request to.
+    next_peer: Arc<AtomicUsize>,
+}
+
+impl SnapFetchClient {
+    /// Creates a new client that sends requests to the given peers.
+    pub(crate) fn new(peers: SnapPeers, peers_handle: PeersHandle) -> Self {
+        Self { peers, peers_handle, next_peer: Default::default() }
+    }
+
+    /// Sends the request to the next peer and returns the future that resolves to the response.
+    fn send_request<T, F>(&self, request: SnapMessage, into_response: F) -> SnapFut<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(SnapMessage) -> Option<T> + Send + 'static,
+    {
+        let (response, rx) = oneshot::channel();
+        let position = self.next_peer.fetch_add(1, Ordering::Relaxed);
+        let Some(peer_id) = self.peers.send(position, SnapPeerRequest { request, response }) else {
+            return Box::pin(futures::future::err(RequestError::UnsupportedCapability))
+        };
+
+        async move {
+            let response = match tokio::time::timeout(SNAP_REQUEST_TIMEOUT, rx).await {
+                Ok(Ok(response)) => response?,
+                Ok(Err(_)) => return Err(RequestError::ConnectionDropped),
+                Err(_) => return Err(RequestError::Timeout),
+            };
+            let response = into_response(response).ok_or(RequestError::BadResponse)?;
+            Ok(WithPeerId::new(peer_id, response))
+        }
+        .boxed()
+    }
+}
+
+impl DownloadClient for SnapFetchClient {
+    fn report_bad_message(&self, peer_id: PeerId) {
+        self.peers_handle.reputation_change(peer_id, ReputationChangeKind::BadMessage);
+    }
+
+    fn num_connected_peers(&self) -> usize {
+        self.peers.len()
+    }
+}
+
+impl SnapClient for SnapFetchClient {
+    fn get_account_range(&self, request: GetAccountRange) -> SnapFut<AccountRange> {
+        self.send_request(SnapMessage::GetAccountRange(request), |response| match response {
+            SnapMessage::AccountRange(response) => Some(response),
+            _ => None,
+        })
+    }
+
+    fn get_storage_ranges(&self, request: GetStorageRanges) -> SnapFut<StorageRanges> {
+        self.send_request(SnapMessage::GetStorageRanges(request), |response| match response {
+            SnapMessage::StorageRanges(response) => Some(response),
+            _ => None,
+        })
+    }
+
+    fn get_byte_codes(&self, request: GetByteCodes) -> SnapFut<ByteCodes> {
+        self.send_request(SnapMessage::GetByteCodes(request), |response| match response {
+            SnapMessage::ByteCodes(response) => Some(response),
+            _ => None,
+        })
+    }
+
+    fn get_trie_nodes(&self, request: GetTrieNodes) -> SnapFut<TrieNodes> {
+        self.send_request(SnapMessage::GetTrieNodes(request), |response| match response {
+            SnapMessage::TrieNodes(response) => Some(response),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer_request() -> (SnapPeerRequest, oneshot::Receiver<RequestResult<SnapMessage>>) {
+        let (response, rx) = oneshot::channel();
+        let request = SnapMessage::GetByteCodes(GetByteCodes::default());
+        (SnapPeerRequest { request, response }, rx)
+    }
+
+    #[test]
+    fn send_skips_closed_connections() {
+        let peers = SnapPeers::default();
+        let (closed_tx, closed_rx) = mpsc::unbounded_channel();
+        drop(closed_rx);
+        let (open_tx, mut open_rx) = mpsc::unbounded_channel();
+        let closed = PeerId::random();
+        let open = PeerId::random();
+        peers.insert(closed, closed_tx);
+        peers.insert(open, open_tx.clone());
+
+        for position in 0..2 {
+            let (request, _rx) = peer_request();
+            assert_eq!(peers.send(position, request), Some(open));
+            assert!(open_rx.try_recv().is_ok());
+        }
+        assert_eq!(peers.len(), 1);
+
+        // a stale connection must not remove the new connection of the same peer
+        let (stale_tx, _stale_rx) = mpsc::unbounded_channel();
+        peers.remove(&open, &stale_tx);
+        assert_eq!(peers.len(), 1);
+        peers.remove(&open, &open_tx);
+        assert_eq!(peers.len(), 0);
+
+        let (request, _rx) = peer_request();
+        assert_eq!(peers.send(0, request), None);
+    }
+}
@@ -0,0 +1,634 @@
+//! Serves `snap` requests from the database.
+
+use super::IncomingSnapRequest;
+use futures::StreamExt;
+use reth_db::{database::Database, tables, transaction::DbTx, DatabaseError};
+use reth_eth_wire::{
+    AccountData, AccountRange, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges,
+    GetTrieNodes, SlimAccount, StorageData, StorageRanges, TrieNodes,
+};
+use reth_interfaces::RethResult;
+use reth_primitives::{trie::Nibbles, BlockNumber, Bytes, B256};
+use reth_provider::{BlockNumReader, DatabaseProviderRO, HeaderProvider, ProviderFactory};
+use reth_trie::{
+    hashed_cursor::{
+        HashedAccountCursor, HashedCursorFactory, HashedPostState, HashedPostStateCursorFactory,
+        HashedStorageCursor,
+    },
+    Proof, StateRootError,
+};
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::sync::mpsc::Receiver;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::debug;
+
+// Limits: <https://github.com/ethereum/go-ethereum/blob/e6b6a8b738069ad0579f6798ee59fde93ed13b43/eth/protocols/snap/handler.go#L36-L56>
+
+/// Maximum size of replies to data retrievals.
+const SOFT_RESPONSE_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Maximum number of bytecodes to serve.
+///
+/// Used to limit lookups.
+const MAX_CODE_LOOKUPS: usize = 1024;
+
+/// Maximum number of trie nodes to serve.
+///
+/// Used to limit lookups.
+const MAX_TRIE_NODE_LOOKUPS: usize = 1024;
+
+/// The number of blocks below the latest block whose state is served.
+///
+/// Clients pin the state root of a pivot block that usually lags a few blocks behind the tip,
+/// geth serves the state of the last 128 blocks as well.
+const SERVED_STATE_DEPTH: u64 = 128;
+
+/// The maximum number of state roots whose reverts are cached.
+///
+/// Clients that sync concurrently usually pin different pivot blocks.
+const CACHED_STATE_REVERTS: usize = 4;
+
+/// Serves `snap` requests from the hashed state and the trie tables of the database.
+///
+/// The database only holds the hashed state and the trie of the latest block. Requests for the
+/// state root of one of the last [SERVED_STATE_DEPTH] blocks are served by reverting the
+/// changesets of the newer blocks on top of it. Requests for any other state root are answered
+/// with an empty response.
+///
+/// This is supposed to be run as background service. Serving requests is CPU and disk intensive,
+/// so it should be spawned on a dedicated blocking task.
+#[derive(Debug)]
+#[must_use = "Manager does nothing unless polled."]
+pub struct SnapRequestHandler<DB> {
+    /// The factory for database providers.
+    factory: ProviderFactory<DB>,
+    /// Incoming requests from the connections of the
+    /// [SnapProtocolHandler](super::SnapProtocolHandler).
+    incoming_requests: ReceiverStream<IncomingSnapRequest>,
+    /// The reverts of the most recently requested state roots, most recently used first.
+    reverts: VecDeque<StateReverts>,
+}
+
+// === impl SnapRequestHandler ===
+
+impl<DB> SnapRequestHandler<DB> {
+    /// Create a new instance
+    pub fn new(factory: ProviderFactory<DB>, incoming: Receiver<IncomingSnapRequest>) -> Self {
+        Self {
+            factory,
+            incoming_requests: ReceiverStream::new(incoming),
+            reverts: VecDeque::with_capacity(CACHED_STATE_REVERTS),
+        }
+    }
+}
+
+impl<DB: Database> SnapRequestHandler<DB> {
+    /// Returns the accounts of the requested range and the proof for its boundaries.
+    fn get_account_range(
+        &mut self,
+        request: &GetAccountRange,
+    ) -> RethResult<(Vec<AccountData>, Vec<Bytes>)> {
+        let provider = self.factory.provider()?;
+        let Some(reverts) = state_reverts(&provider, &mut self.reverts, request.root_hash)? else {
+            return Ok(Default::default())
+        };
+
+        let tx = provider.tx_ref();
+        let hashed_cursor_factory = HashedPostStateCursorFactory::new(tx, reverts);
+        let proof = reverted_proof(tx, reverts);
+        let response_limit = (request.response_bytes as usize).min(SOFT_RESPONSE_LIMIT);
+
+        let mut accounts = Vec::new();
+        let mut size = 0;
+        let mut cursor = hashed_cursor_factory.hashed_account_cursor()?;
+        let mut entry = cursor.seek(request.starting_hash)?;
+        while let Some((hashed_address, account)) = entry {
+            let storage_root = proof.storage_root(hashed_address).map_err(root_error)?;
+            let account =
+                AccountData { hash: hashed_address, body: SlimAccount::new(account, storage_root) };
+            size += alloy_rlp::Encodable::length(&account);
+            accounts.push(account);
+
+            // the first account past the limit is included to prove the end of the range
+            if hashed_address >= request.limit_hash || size >= response_limit {
+                break
+            }
+            entry = cursor.next()?;
+        }
+
+        let mut targets = Vec::from([Nibbles::unpack(request.starting_hash)]);
+        targets.extend(accounts.last().map(|account| Nibbles::unpack(account.hash)));
+        let (root, nodes) = proof.account_proof_nodes(targets).map_err(root_error)?;
+        if root != request.root_hash {
+            // the trie tables are not in sync with the hashed state
+            return Ok(Default::default())
+        }
+
+        Ok((accounts, nodes.into_values().collect()))
+    }
+
+    /// Returns the storage slots of the requested accounts and the proof for the boundaries of
+    /// the last, incomplete storage range.
+    fn get_storage_ranges(
+        &mut self,
+        request: &GetStorageRanges,
+    ) -> RethResult<(Vec<Vec<StorageData>>, Vec<Bytes>)> {
+        let provider = self.factory.provider()?;
+        let Some(reverts) = state_reverts(&provider, &mut self.reverts, request.root_hash)? else {
+            return Ok(Default::default())
+        };
+
+        let tx = provider.tx_ref();
+        let hashed_cursor_factory = HashedPostStateCursorFactory::new(tx, reverts);
+        let proof = reverted_proof(tx, reverts);
+        let response_limit = (request.response_bytes as usize).min(SOFT_RESPONSE_LIMIT);
+
+        let mut slots = Vec::new();
+        let mut size = 0;
+        let mut cursor = hashed_cursor_factory.hashed_storage_cursor()?;
+        for (idx, hashed_address) in request.account_hashes.iter().copied().enumerate() {
+            // the starting hash only applies to the first and the limit only to the last account
+            let start = if idx == 0 { request.starting_hash() } else { B256::ZERO };
+            let limit = if idx == request.account_hashes.len() - 1 {
+                request.limit_hash()
+            } else {
+                B256::repeat_byte(0xff)
+            };
+
+            let mut account_slots = Vec::new();
+            let mut complete = true;
+            let mut entry = cursor.seek(hashed_address, start)?;
+            while let Some(slot) = entry {
+                let data = StorageData {
+                    hash: slot.key,
+                    data: alloy_rlp::encode_fixed_size(&slot.value).to_vec().into(),
+                };
+                size += alloy_rlp::Encodable::length(&data);
+                account_slots.push(data);
+
+                if slot.key >= limit {
+                    break
+                }
+                if size >= response_limit {
+                    complete = false;
+                    break
+                }
+                entry = cursor.next()?;
+            }
+
+            // the range is proven if it does not cover the entire storage of the account
+            if !complete || start != B256::ZERO || limit != B256::repeat_byte(0xff) {
+                let mut targets = Vec::from([Nibbles::unpack(start)]);
+                targets.extend(account_slots.last().map(|slot| Nibbles::unpack(slot.hash)));
+                let (_, nodes) =
+                    proof.storage_proof_nodes(hashed_address, targets).map_err(root_error)?;
+                slots.push(account_slots);
+                return Ok((slots, nodes.into_values().collect()))
+            }
+
+            slots.push(account_slots);
+            if size >= response_limit {
+                break
+            }
+        }
+
+        Ok((slots, Vec::new()))
+    }
+
+    /// Returns the requested bytecodes in request order, skipping unknown ones.
+    fn get_byte_codes(&self, request: &GetByteCodes) -> RethResult<Vec<Bytes>> {
+        let provider = self.factory.provider()?;
+        let tx = provider.tx_ref();
+        let response_limit = (request.response_bytes as usize).min(SOFT_RESPONSE_LIMIT);
+
+        let mut codes = Vec::new();
+        let mut size = 0;
+        for hash in request.hashes.iter().take(MAX_CODE_LOOKUPS) {
+            if let Some(bytecode) = tx.get::<tables::Bytecodes>(*hash)? {
+                let code = bytecode.original_bytes();
+                size += code.len();
+                codes.push(code);
+
+                if size >= response_limit {
+                    break
+                }
+            }
+        }
+
+        Ok(codes)
+    }
+
+    /// Returns the requested trie nodes in request order.
+    ///
+    /// Serving stops at the first node that can't be found.
+    fn get_trie_nodes(&mut self, request: &GetTrieNodes) -> RethResult<Vec<Bytes>> {
+        let provider = self.factory.provider()?;
+        let Some(reverts) = state_reverts(&provider, &mut self.reverts, request.root_hash)? else {
+            return Ok(Vec::new())
+        };
+
+        let tx = provider.tx_ref();
+        let proof = reverted_proof(tx, reverts);
+        let response_limit = (request.response_bytes as usize).min(SOFT_RESPONSE_LIMIT);
+
+        // retain all requested nodes of the account trie in a single pass
+        let mut account_paths = Vec::new();
+        for path_set in request.paths.iter().take(MAX_TRIE_NODE_LOOKUPS) {
+            match path_set.first().and_then(|path| decode_compact_path(path)) {
+                Some(path) => account_paths.push(path),
+                None => break,
+            }
+        }
+        let (root, account_nodes) =
+            proof.account_proof_nodes(account_paths.clone()).map_err(root_error)?;
+        if root != request.root_hash {
+            // the trie tables are not in sync with the hashed state
+            return Ok(Vec::new())
+        }
+
+        let mut nodes = Vec::new();
+        let mut size = 0;
+        'paths: for (account_path, path_set) in account_paths.into_iter().zip(&request.paths) {
+            if path_set.len() == 1 {
+                let Some(node) = account_nodes.get(&account_path) else { break };
+                size += node.len();
+                nodes.push(node.clone());
+            } else {
+                // the storage trie of the account with the full account path
+                if account_path.len() != 64 {
+                    break
+                }
+                let hashed_address = B256::from_slice(&account_path.pack());
+                let mut storage_paths = Vec::with_capacity(path_set.len() - 1);
+                for path in &path_set[1..] {
+                    match decode_compact_path(path) {
+                        Some(path) => storage_paths.push(path),
+                        None => break 'paths,
+                    }
+                }
+                let (_, storage_nodes) = proof
+                    .storage_proof_nodes(hashed_address, storage_paths.clone())
+                    .map_err(root_error)?;
+                for path in storage_paths {
+                    let Some(node) = storage_nodes.get(&path) else { break 'paths };
+                    size += node.len();
+                    nodes.push(node.clone());
+                }
+            }
+
+            if size >= response_limit {
+                break
+            }
+        }
+
+        Ok(nodes)
+    }
+
+    fn on_account_range_request(&mut self, request: GetAccountRange) -> AccountRange {
+        let (accounts, proof) = self.get_account_range(&request).unwrap_or_else(|err| {
+            debug!(target: "net::snap", %err, "Failed to serve account range");
+            Default::default()
+        });
+        AccountRange { request_id: request.request_id, accounts, proof }
+    }
+
+    fn on_storage_ranges_request(&mut self, request: GetStorageRanges) -> StorageRanges {
+        let (slots, proof) = self.get_storage_ranges(&request).unwrap_or_else(|err| {
+            debug!(target: "net::snap", %err, "Failed to serve storage ranges");
+            Default::default()
+        });
+        StorageRanges { request_id: request.request_id, slots, proof }
+    }
+
+    fn on_byte_codes_request(&self, request: GetByteCodes) -> ByteCodes {
+        let codes = self.get_byte_codes(&request).unwrap_or_else(|err| {
+            debug!(target: "net::snap", %err, "Failed to serve bytecodes");
+            Default::default()
+        });
+        ByteCodes { request_id: request.request_id, codes }
+    }
+
+    fn on_trie_nodes_request(&mut self, request: GetTrieNodes) -> TrieNodes {
+        let nodes = self.get_trie_nodes(&request).unwrap_or_else(|err| {
+            debug!(target: "net::snap", %err, "Failed to serve trie nodes");
+            Default::default()
+        });
+        TrieNodes { request_id: request.request_id, nodes }
+    }
+}
+
+/// An endless future.
+///
+/// This should be spawned or used as part of `tokio::select!`.
+impl<DB: Database + Unpin> Future for SnapRequestHandler<DB> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        loop {
+            match this.incoming_requests.poll_next_unpin(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(None) => return Poll::Ready(()),
+                Poll::Ready(Some(incoming)) => match incoming {
+                    IncomingSnapRequest::GetAccountRange { request, response, .. } => {
+                        let _ = response.send(this.on_account_range_request(request));
+                    }
+                    IncomingSnapRequest::GetStorageRanges { request, response, .. } => {
+                        let _ = response.send(this.on_storage_ranges_request(request));
+                    }
+                    IncomingSnapRequest::GetByteCodes { request, response, .. } => {
+                        let _ = response.send(this.on_byte_codes_request(request));
+                    }
+                    IncomingSnapRequest::GetTrieNodes { request, response, .. } => {
+                        let _ = response.send(this.on_trie_nodes_request(request));
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// The changesets that revert the latest hashed state to the state with the given root.
+#[derive(Debug)]
+struct StateReverts {
+    /// The latest block the reverts apply to.
+    tip: BlockNumber,
+    /// The reverted state root.
+    root: B256,
+    /// The reverted hashed state.
+    state: HashedPostState,
+}
+
+/// Returns the reverts of the latest hashed state to the state with the given root, if it's the
+/// state root of one of the last [SERVED_STATE_DEPTH] blocks.
+///
+/// The reverts of the last [CACHED_STATE_REVERTS] requested roots are cached, since clients
+/// request many ranges of the same root.
+fn state_reverts<'a, DB: Database>(
+    provider: &DatabaseProviderRO<'_, DB>,
+    cache: &'a mut VecDeque<StateReverts>,
+    root: B256,
+) -> RethResult<Option<&'a HashedPostState>> {
+    let tip = provider.best_block_number()?;
+    // the reverts of an older tip don't apply to the latest state
+    cache.retain(|cached| cached.tip == tip);
+
+    if let Some(idx) = cache.iter().position(|cached| cached.root == root) {
+        let cached = cache.remove(idx).expect("index is in bounds");
+        cache.push_front(cached);
+        return Ok(cache.front().map(|cached| &cached.state))
+    }
+
+    for block in (tip.saturating_sub(SERVED_STATE_DEPTH)..=tip).rev() {
+        if provider.header_by_number(block)?.map_or(false, |header| header.state_root == root) {
+            let state = if block == tip {
+                HashedPostState::default()
+            } else {
+                HashedPostState::from_revert_range(provider.tx_ref(), block + 1..=tip)?
+            };
+            cache.truncate(CACHED_STATE_REVERTS - 1);
+            cache.push_front(StateReverts { tip, root, state });
+            return Ok(cache.front().map(|cached| &cached.state))
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the proof generator for the state of the latest block with the given reverts applied.
+fn reverted_proof<'a, TX: DbTx>(
+    tx: &'a TX,
+    reverts: &'a HashedPostState,
+) -> Proof<'a, TX, HashedPostStateCursorFactory<'a, 'a, TX>> {
+    let (account_prefix_set, storage_prefix_sets) = reverts.construct_prefix_sets_mut();
+    Proof::new(tx)
+        .with_hashed_cursor_factory(HashedPostStateCursorFactory::new(tx, reverts))
+        .with_changed_account_prefixes(account_prefix_set)
+        .with_changed_storage_prefixes(storage_prefix_sets)
+}
+
+/// Converts a trie error into the database error that caused it.
+fn root_error(err: impl Into<StateRootError>) -> DatabaseError {
+    DatabaseError::from(err.into())
+}
+
+/// Decodes the compact (hex-prefix) encoding of a trie path.
+///
+/// Returns `None` if the encoding is invalid.
+fn decode_compact_path(path: &[u8]) -> Option<Nibbles> {
+    let (first, rest) = path.split_first()?;
+    let flag = first >> 4;
+    if flag > 3 {
+        return None
+    }
+
+    let mut nibbles = Vec::with_capacity(rest.len() * 2 + 1);
+    // an odd path length is signalled by the lowest bit of the flag
+    if flag & 1 == 1 {
+        nibbles.push(first & 0x0f);
+    } else if first & 0x0f != 0 {
+        return None
+    }
+    for byte in rest {
+        nibbles.push(byte >> 4);
+        nibbles.push(byte & 0x0f);
+    }
+    Some(Nibbles::from_hex(nibbles))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_db::{
+        models::AccountBeforeTx,
+        test_utils::{create_test_rw_db, TempDatabase},
+        transaction::DbTxMut,
+        DatabaseEnv,
+    };
+    use reth_primitives::{
+        stage::{StageCheckpoint, StageId},
+        Account, Address, Header, StorageEntry, MAINNET, U256,
+    };
+    use reth_provider::{HashingWriter, StageCheckpointWriter};
+    use reth_trie::StateRoot;
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+
+    /// Creates a database with the given accounts and storage, and a latest block with the state
+    /// root of that state.
+    fn create_handler(
+        accounts: Vec<(Address, Account)>,
+        storage: Vec<(Address, Vec<StorageEntry>)>,
+    ) -> (SnapRequestHandler<Arc<TempDatabase<DatabaseEnv>>>, B256) {
+        let db = create_test_rw_db();
+        let factory = ProviderFactory::new(db, MAINNET.clone());
+        let mut provider = factory.provider_rw().unwrap();
+        provider
+            .insert_account_for_hashing(
+                accounts.into_iter().map(|(address, account)| (address, Some(account))),
+            )
+            .unwrap();
+        provider.insert_storage_for_hashing(storage).unwrap();
+        let (root, updates) = StateRoot::new(provider.tx_ref()).root_with_updates().unwrap();
+        updates.flush(provider.tx_mut()).unwrap();
+        provider
+            .tx_ref()
+            .put::<tables::Headers>(0, Header { state_root: root, ..Default::default() })
+            .unwrap();
+        provider.commit().unwrap();
+
+        let (_tx, rx) = mpsc::channel(1);
+        (SnapRequestHandler::new(factory, rx), root)
+    }
+
+    #[test]
+    fn decode_compact_paths() {
+        assert_eq!(decode_compact_path(&[0x00]), Some(Nibbles::default()));
+        assert_eq!(decode_compact_path(&[0x11]), Some(Nibbles::from_hex(vec![1])));
+        assert_eq!(decode_compact_path(&[0x00, 0x12]), Some(Nibbles::from_hex(vec![1, 2])));
+        assert_eq!(decode_compact_path(&[0x31, 0x23]), Some(Nibbles::from_hex(vec![1, 2, 3])));
+        assert_eq!(decode_compact_path(&[0x20, 0xab]), Some(Nibbles::from_hex(vec![0xa, 0xb])));
+        assert_eq!(decode_compact_path(&[]), None);
+        assert_eq!(decode_compact_path(&[0x01]), None);
+        assert_eq!(decode_compact_path(&[0x40]), None);
+    }
+
+    #[test]
+    fn serve_account_range() {
+        let accounts = (1..=10u64)
+            .map(|i| (Address::with_last_byte(i as u8), Account { nonce: i, ..Default::default() }))
+            .collect::<Vec<_>>();
+        let (mut handler, root) = create_handler(accounts, Vec::new());
+
+        let request = GetAccountRange {
+            request_id: 1,
+            root_hash: root,
+            starting_hash: B256::ZERO,
+            limit_hash: B256::repeat_byte(0xff),
+            response_bytes: 1024 * 1024,
+        };
+        let response = handler.on_account_range_request(request.clone());
+        assert_eq!(response.request_id, 1);
+        assert_eq!(response.accounts.len(), 10);
+        assert!(response.accounts.windows(2).all(|pair| pair[0].hash < pair[1].hash));
+        assert!(!response.proof.is_empty());
+
+        // accounts are served up to the soft limit
+        let response = handler
+            .on_account_range_request(GetAccountRange { response_bytes: 1, ..request.clone() });
+        assert_eq!(response.accounts.len(), 1);
+
+        // unknown state roots are not served
+        let response =
+            handler.on_account_range_request(GetAccountRange { root_hash: B256::ZERO, ..request });
+        assert!(response.accounts.is_empty());
+        assert!(response.proof.is_empty());
+    }
+
+    #[test]
+    fn serve_storage_ranges() {
+        let address = Address::with_last_byte(1);
+        let storage = (1..=10u64)
+            .map(|i| StorageEntry { key: B256::with_last_byte(i as u8), value: U256::from(i) })
+            .collect::<Vec<_>>();
+        let (mut handler, root) = create_handler(
+            Vec::from([(address, Account::default())]),
+            Vec::from([(address, storage)]),
+        );
+
+        let request = GetStorageRanges {
+            request_id: 1,
+            root_hash: root,
+            account_hashes: Vec::from([reth_primitives::keccak256(address)]),
+            starting_hash: Bytes::new(),
+            limit_hash: Bytes::new(),
+            response_bytes: 1024 * 1024,
+        };
+        let response = handler.on_storage_ranges_request(request.clone());
+        assert_eq!(response.slots.len(), 1);
+        assert_eq!(response.slots[0].len(), 10);
+        // complete storage ranges are not proven
+        assert!(response.proof.is_empty());
+
+        // incomplete storage ranges are proven
+        let response =
+            handler.on_storage_ranges_request(GetStorageRanges { response_bytes: 1, ..request });
+        assert_eq!(response.slots[0].len(), 1);
+        assert!(!response.proof.is_empty());
+    }
+
+    #[test]
+    fn serve_recent_state_root() {
+        let address = Address::with_last_byte(1);
+        let accounts = (1..=10u64)
+            .map(|i| (Address::with_last_byte(i as u8), Account { nonce: i, ..Default::default() }))
+            .collect::<Vec<_>>();
+        let (mut handler, old_root) = create_handler(accounts, Vec::new());
+
+        // change the nonce of the first account in block 1
+        let mut provider = handler.factory.provider_rw().unwrap();
+        let old_account = Account { nonce: 1, ..Default::default() };
+        provider
+            .tx_ref()
+            .put::<tables::AccountChangeSet>(
+                1,
+                AccountBeforeTx { address, info: Some(old_account) },
+            )
+            .unwrap();
+        provider
+            .insert_account_for_hashing([(address, Some(Account { nonce: 100, ..old_account }))])
+            .unwrap();
+        let (new_root, updates) = StateRoot::new(provider.tx_ref()).root_with_updates().unwrap();
+        updates.flush(provider.tx_mut()).unwrap();
+        provider
+            .tx_ref()
+            .put::<tables::Headers>(1, Header { state_root: new_root, ..Default::default() })
+            .unwrap();
+        provider.save_stage_checkpoint(StageId::Finish, StageCheckpoint::new(1)).unwrap();
+        provider.commit().unwrap();
+
+        let request = GetAccountRange {
+            request_id: 1,
+            root_hash: new_root,
+            starting_hash: B256::ZERO,
+            limit_hash: B256::repeat_byte(0xff),
+            response_bytes: 1024 * 1024,
+        };
+        let nonce_of = |response: &AccountRange| {
+            let hash = reth_primitives::keccak256(address);
+            response.accounts.iter().find(|account| account.hash == hash).unwrap().body.nonce
+        };
+
+        let response = handler.on_account_range_request(request.clone());
+        assert_eq!(response.accounts.len(), 10);
+        assert_eq!(nonce_of(&response), 100);
+
+        // the state of the previous block is served as well
+        let response = handler
+            .on_account_range_request(GetAccountRange { root_hash: old_root, ..request.clone() });
+        assert_eq!(response.accounts.len(), 10);
+        assert_eq!(nonce_of(&response), 1);
+        assert!(!response.proof.is_empty());
+
+        let response = handler.on_trie_nodes_request(GetTrieNodes {
+            request_id: 2,
+            root_hash: old_root,
+            paths: Vec::from([Vec::from([Bytes::from_static(&[0x00])])]),
+            response_bytes: 1024 * 1024,
+        });
+        assert_eq!(response.nodes.len(), 1);
+        assert_eq!(reth_primitives::keccak256(&response.nodes[0]), old_root);
+
+        // the reverts of both roots are cached, the most recently used first
+        let cached = handler.reverts.iter().map(|cached| cached.root).collect::<Vec<_>>();
+        assert_eq!(cached, Vec::from([old_root, new_root]));
+        handler.on_account_range_request(request);
+        let cached = handler.reverts.iter().map(|cached| cached.root).collect::<Vec<_>>();
+        assert_eq!(cached, Vec::from([new_root, old_root]));
+    }
+}
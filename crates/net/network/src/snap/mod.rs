@@ -0,0 +1,429 @@
+//! Support for the `snap/1` protocol.
+//!
+//! The `snap` protocol runs side-by-side with `eth` and allows peers to retrieve ranges of the
+//! state with merkle proofs.
+//!
+//! The [SnapProtocolHandler] is registered as an additional RLPx sub-protocol, see
+//! [NetworkConfigBuilder::add_rlpx_sub_protocol](crate::NetworkConfigBuilder::add_rlpx_sub_protocol).
+//! Requests received by the connections of the protocol are delegated to the
+//! [SnapRequestHandler], which serves them from the database. Requests to peers are sent with the
+//! [SnapFetchClient].
+//!
+//! See also <https://github.com/ethereum/devp2p/blob/master/caps/snap.md>
+
+use crate::{
+    peers::PeersHandle,
+    protocol::{ConnectionHandler, OnNotSupported, ProtocolConnection, ProtocolHandler},
+};
+use alloy_rlp::{Decodable, Encodable};
+use futures::{stream::FuturesUnordered, FutureExt, Stream, StreamExt};
+use reth_eth_wire::{
+    capability::SharedCapabilities, protocol::Protocol, AccountRange, ByteCodes, GetAccountRange,
+    GetByteCodes, GetStorageRanges, GetTrieNodes, SnapMessage, StorageRanges, TrieNodes,
+};
+use reth_interfaces::p2p::error::{RequestError, RequestResult};
+use reth_network_api::Direction;
+use reth_primitives::{BytesMut, PeerId};
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tokio::sync::{
+    mpsc::{self, error::TrySendError},
+    oneshot,
+};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tracing::trace;
+
+mod client;
+mod handler;
+pub use client::SnapFetchClient;
+use client::{SnapPeerRequest, SnapPeers};
+pub use handler::SnapRequestHandler;
+
+/// The max channel capacity of the [SnapRequestHandler].
+///
+/// Requests that are received while the channel is full are answered with an empty response.
+pub const SNAP_REQUEST_CHANNEL_CAPACITY: usize = 256;
+
+/// The [ProtocolHandler] of the `snap/1` protocol.
+///
+/// The protocol is announced to all peers, requests of peers that share it are forwarded to the
+/// [SnapRequestHandler]. If no handler is running, e.g. because the node only uses the protocol to
+/// request state, all requests are answered with empty responses, as the protocol requires.
+#[derive(Debug, Clone)]
+pub struct SnapProtocolHandler {
+    /// Sender half of the channel to the [SnapRequestHandler].
+    requests: mpsc::Sender<IncomingSnapRequest>,
+    /// The peers with an established `snap` connection.
+    peers: SnapPeers,
+}
+
+// === impl SnapProtocolHandler ===
+
+impl SnapProtocolHandler {
+    /// Creates a new protocol handler that forwards the received requests to the given channel.
+    pub fn new(requests: mpsc::Sender<IncomingSnapRequest>) -> Self {
+        Self { requests, peers: Default::default() }
+    }
+
+    /// Returns a new [SnapFetchClient] that sends requests to the peers of this protocol.
+    ///
+    /// The [PeersHandle] is used to penalize peers that respond with bad messages.
+    pub fn client(&self, peers_handle: PeersHandle) -> SnapFetchClient {
+        SnapFetchClient::new(self.peers.clone(), peers_handle)
+    }
+
+    fn connection_handler(&self) -> SnapConnectionHandler {
+        SnapConnectionHandler { requests: self.requests.clone(), peers: self.peers.clone() }
+    }
+}
+
+impl ProtocolHandler for SnapProtocolHandler {
+    type ConnectionHandler = SnapConnectionHandler;
+
+    fn on_incoming(&self, _socket_addr: SocketAddr) -> Option<Self::ConnectionHandler> {
+        Some(self.connection_handler())
+    }
+
+    fn on_outgoing(
+        &self,
+        _socket_addr: SocketAddr,
+        _peer_id: PeerId,
+    ) -> Option<Self::ConnectionHandler> {
+        Some(self.connection_handler())
+    }
+}
+
+/// The [ConnectionHandler] of the `snap/1` protocol.
+#[derive(Debug)]
+pub struct SnapConnectionHandler {
+    requests: mpsc::Sender<IncomingSnapRequest>,
+    peers: SnapPeers,
+}
+
+impl ConnectionHandler for SnapConnectionHandler {
+    type Connection = SnapConnection;
+
+    fn protocol(&self) -> Protocol {
+        Protocol::snap_1()
+    }
+
+    fn on_unsupported_by_peer(
+        self,
+        _supported: &SharedCapabilities,
+        _direction: Direction,
+        _peer_id: PeerId,
+    ) -> OnNotSupported {
+        OnNotSupported::KeepAlive
+    }
+
+    fn into_connection(
+        self,
+        _direction: Direction,
+        peer_id: PeerId,
+        conn: ProtocolConnection,
+    ) -> Self::Connection {
+        let (to_peer, peer_requests) = mpsc::unbounded_channel();
+        self.peers.insert(peer_id, to_peer.clone());
+        SnapConnection {
+            peer_id,
+            conn,
+            requests: self.requests,
+            pending_responses: FuturesUnordered::new(),
+            peers: self.peers,
+            to_peer,
+            peer_requests: UnboundedReceiverStream::new(peer_requests),
+            inflight_requests: HashMap::new(),
+            next_request_id: 0,
+        }
+    }
+}
+
+/// The response to a request that is served by the [SnapRequestHandler].
+type PendingResponse = Pin<Box<dyn Future<Output = Option<SnapMessage>> + Send>>;
+
+/// A `snap/1` connection with a peer.
+///
+/// This yields the encoded responses to the requests of the peer and the requests sent to the peer
+/// by the [SnapFetchClient]. The connection is closed if the peer sends a message that can't be
+/// decoded.
+#[must_use = "Streams do nothing unless polled"]
+pub struct SnapConnection {
+    /// The peer of the connection.
+    peer_id: PeerId,
+    /// The received messages.
+    conn: ProtocolConnection,
+    /// Sender half of the channel to the [SnapRequestHandler].
+    requests: mpsc::Sender<IncomingSnapRequest>,
+    /// The responses to the requests that are being served.
+    pending_responses: FuturesUnordered<PendingResponse>,
+    /// The peers with an established `snap` connection, this connection is removed on drop.
+    peers: SnapPeers,
+    /// The sender half of the channel this connection is registered with in `peers`.
+    to_peer: mpsc::UnboundedSender<SnapPeerRequest>,
+    /// Requests of the [SnapFetchClient] to send to the peer.
+    peer_requests: UnboundedReceiverStream<SnapPeerRequest>,
+    /// The requests sent to the peer that are awaiting a response, by request id.
+    inflight_requests: HashMap<u64, oneshot::Sender<RequestResult<SnapMessage>>>,
+    /// The request id of the next request sent to the peer.
+    next_request_id: u64,
+}
+
+impl SnapConnection {
+    /// Forwards a request to the [SnapRequestHandler] and tracks the response, or delivers a
+    /// response to the request of the [SnapFetchClient] it belongs to.
+    fn on_message(&mut self, msg: SnapMessage) {
+        let peer_id = self.peer_id;
+        let (incoming, response) = match msg {
+            SnapMessage::GetAccountRange(request) => {
+                let (tx, rx) = oneshot::channel();
+                let response = rx.map(|res| res.ok().map(SnapMessage::AccountRange)).boxed();
+                (IncomingSnapRequest::GetAccountRange { peer_id, request, response: tx }, response)
+            }
+            SnapMessage::GetStorageRanges(request) => {
+                let (tx, rx) = oneshot::channel();
+                let response = rx.map(|res| res.ok().map(SnapMessage::StorageRanges)).boxed();
+                (IncomingSnapRequest::GetStorageRanges { peer_id, request, response: tx }, response)
+            }
+            SnapMessage::GetByteCodes(request) => {
+                let (tx, rx) = oneshot::channel();
+                let response = rx.map(|res| res.ok().map(SnapMessage::ByteCodes)).boxed();
+                (IncomingSnapRequest::GetByteCodes { peer_id, request, response: tx }, response)
+            }
+            SnapMessage::GetTrieNodes(request) => {
+                let (tx, rx) = oneshot::channel();
+                let response = rx.map(|res| res.ok().map(SnapMessage::TrieNodes)).boxed();
+                (IncomingSnapRequest::GetTrieNodes { peer_id, request, response: tx }, response)
+            }
+            response => {
+                self.on_response(response);
+                return
+            }
+        };
+
+        // peers must receive a response to every request, which is empty if it can't be served
+        match self.requests.try_send(incoming) {
+            Ok(()) => self.pending_responses.push(response),
+            Err(TrySendError::Full(incoming)) => {
+                trace!(target: "net::snap", ?peer_id, "Request handler is busy, sending empty response");
+                self.pending_responses
+                    .push(futures::future::ready(Some(incoming.empty_response())).boxed());
+            }
+            Err(TrySendError::Closed(incoming)) => {
+                self.pending_responses
+                    .push(futures::future::ready(Some(incoming.empty_response())).boxed());
+            }
+        }
+    }
+
+    /// Delivers the response to the request it belongs to.
+    fn on_response(&mut self, response: SnapMessage) {
+        match self.inflight_requests.remove(&response.request_id()) {
+            Some(tx) => {
+                let _ = tx.send(Ok(response));
+            }
+            None => {
+                trace!(target: "net::snap", peer_id=?self.peer_id, id = ?response.message_id(), "Ignoring unrequested response");
+            }
+        }
+    }
+
+    /// Assigns a request id to the request of the [SnapFetchClient] and returns the encoded
+    /// request.
+    fn on_peer_request(&mut self, request: SnapPeerRequest) -> BytesMut {
+        let SnapPeerRequest { mut request, response } = request;
+        let request_id = self.next_request_id;
+        self.next_request_id = self.next_request_id.wrapping_add(1);
+        match &mut request {
+            SnapMessage::GetAccountRange(msg) => msg.request_id = request_id,
+            SnapMessage::GetStorageRanges(msg) => msg.request_id = request_id,
+            SnapMessage::GetByteCodes(msg) => msg.request_id = request_id,
+            SnapMessage::GetTrieNodes(msg) => msg.request_id = request_id,
+            _ => {}
+        }
+        // forget the requests the client stopped waiting for
+        self.inflight_requests.retain(|_, tx| !tx.is_closed());
+        self.inflight_requests.insert(request_id, response);
+
+        let mut buf = BytesMut::with_capacity(request.length());
+        request.encode(&mut buf);
+        buf
+    }
+}
+
+impl Stream for SnapConnection {
+    type Item = BytesMut;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            // send the served responses first
+            if let Poll::Ready(Some(response)) = this.pending_responses.poll_next_unpin(cx) {
+                if let Some(response) = response {
+                    let mut buf = BytesMut::with_capacity(response.length());
+                    response.encode(&mut buf);
+                    return Poll::Ready(Some(buf))
+                }
+                continue
+            }
+
+            // send the requests of the client
+            if let Poll::Ready(Some(request)) = this.peer_requests.poll_next_unpin(cx) {
+                return Poll::Ready(Some(this.on_peer_request(request)))
+            }
+
+            let Some(msg) = ready!(this.conn.poll_next_unpin(cx)) else {
+                // the RLPx connection was closed
+                return Poll::Ready(None)
+            };
+
+            match SnapMessage::decode(&mut &msg[..]) {
+                Ok(msg) => this.on_message(msg),
+                Err(err) => {
+                    trace!(target: "net::snap", peer_id=?this.peer_id, %err, "Failed to decode message, closing connection");
+                    return Poll::Ready(None)
+                }
+            }
+        }
+    }
+}
+
+impl Drop for SnapConnection {
+    fn drop(&mut self) {
+        self.peers.remove(&self.peer_id, &self.to_peer);
+        for (_, tx) in self.inflight_requests.drain() {
+            let _ = tx.send(Err(RequestError::ConnectionDropped));
+        }
+    }
+}
+
+impl std::fmt::Debug for SnapConnection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SnapConnection")
+            .field("peer_id", &self.peer_id)
+            .field("pending_responses", &self.pending_responses.len())
+            .field("inflight_requests", &self.inflight_requests.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// All `snap` requests delegated by the network.
+#[derive(Debug)]
+#[allow(missing_docs)]
+pub enum IncomingSnapRequest {
+    /// Request an account range from the peer.
+    ///
+    /// The response should be sent through the channel.
+    GetAccountRange {
+        peer_id: PeerId,
+        request: GetAccountRange,
+        response: oneshot::Sender<AccountRange>,
+    },
+    /// Request storage ranges from the peer.
+    ///
+    /// The response should be sent through the channel.
+    GetStorageRanges {
+        peer_id: PeerId,
+        request: GetStorageRanges,
+        response: oneshot::Sender<StorageRanges>,
+    },
+    /// Request bytecodes from the peer.
+    ///
+    /// The response should be sent through the channel.
+    GetByteCodes { peer_id: PeerId, request: GetByteCodes, response: oneshot::Sender<ByteCodes> },
+    /// Request trie nodes from the peer.
+    ///
+    /// The response should be sent through the channel.
+    GetTrieNodes { peer_id: PeerId, request: GetTrieNodes, response: oneshot::Sender<TrieNodes> },
+}
+
+// === impl IncomingSnapRequest ===
+
+impl IncomingSnapRequest {
+    /// Returns the response that signals that none of the requested data is available.
+    fn empty_response(self) -> SnapMessage {
+        match self {
+            IncomingSnapRequest::GetAccountRange { request, .. } => {
+                SnapMessage::AccountRange(AccountRange {
+                    request_id: request.request_id,
+                    accounts: Vec::new(),
+                    proof: Vec::new(),
+                })
+            }
+            IncomingSnapRequest::GetStorageRanges { request, .. } => {
+                SnapMessage::StorageRanges(StorageRanges {
+                    request_id: request.request_id,
+                    slots: Vec::new(),
+                    proof: Vec::new(),
+                })
+            }
+            IncomingSnapRequest::GetByteCodes { request, .. } => {
+                SnapMessage::ByteCodes(ByteCodes {
+                    request_id: request.request_id,
+                    codes: Vec::new(),
+                })
+            }
+            IncomingSnapRequest::GetTrieNodes { request, .. } => {
+                SnapMessage::TrieNodes(TrieNodes {
+                    request_id: request.request_id,
+                    nodes: Vec::new(),
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sends the request over a new connection and returns the response of the connection.
+    async fn respond(handler: &SnapProtocolHandler, request: SnapMessage) -> SnapMessage {
+        let (to_conn, from_wire) = mpsc::channel(1);
+        let mut conn = handler.connection_handler().into_connection(
+            Direction::Incoming,
+            PeerId::random(),
+            ProtocolConnection::new(from_wire),
+        );
+
+        let mut buf = BytesMut::new();
+        request.encode(&mut buf);
+        to_conn.try_send(buf).unwrap();
+        let response = conn.next().await.unwrap();
+        SnapMessage::decode(&mut &response[..]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn empty_response_if_not_served() {
+        let request = SnapMessage::GetByteCodes(GetByteCodes {
+            request_id: 7,
+            hashes: Vec::from([Default::default()]),
+            response_bytes: 1024,
+        });
+        let empty = SnapMessage::ByteCodes(ByteCodes { request_id: 7, codes: Vec::new() });
+
+        // no request handler is running
+        let (tx, rx) = mpsc::channel(1);
+        drop(rx);
+        let handler = SnapProtocolHandler::new(tx);
+        assert_eq!(respond(&handler, request.clone()).await, empty);
+
+        // the request handler is busy
+        let (tx, _rx) = mpsc::channel(1);
+        let handler = SnapProtocolHandler::new(tx);
+        handler
+            .requests
+            .try_send(IncomingSnapRequest::GetByteCodes {
+                peer_id: PeerId::random(),
+                request: GetByteCodes::default(),
+                response: oneshot::channel().0,
+            })
+            .unwrap();
+        assert_eq!(respond(&handler, request).await, empty);
+    }
+}
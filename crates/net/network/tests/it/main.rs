@@ -2,6 +2,7 @@ mod big_pooled_txs_req;
 mod clique;
 mod connect;
 mod geth;
+mod multiplex;
 mod requests;
 mod session;
 mod startup;
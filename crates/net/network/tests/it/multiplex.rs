@@ -0,0 +1,258 @@
+//! Testing RLPx sub-protocol multiplexing.
+
+use futures::{Stream, StreamExt};
+use reth_eth_wire::{
+    capability::{Capability, SharedCapabilities},
+    protocol::Protocol,
+};
+use reth_network::{
+    protocol::{ConnectionHandler, OnNotSupported, ProtocolConnection, ProtocolHandler},
+    test_utils::{NetworkEventStream, PeerConfig, Testnet},
+    NetworkEvents,
+};
+use reth_network_api::{Direction, NetworkInfo, Peers, PeersInfo};
+use reth_primitives::{BytesMut, PeerId};
+use std::{
+    net::SocketAddr,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tokio::sync::mpsc;
+
+/// The payload of the request that is sent on every outgoing echo connection.
+const ECHO_PAYLOAD: &[u8] = b"hello";
+
+/// The messages of the echo protocol.
+///
+/// The `echo` capability is ordered before `eth`, so the `eth` message ids are shifted by the
+/// echo messages.
+#[repr(u8)]
+enum EchoMessageId {
+    Request = 0x00,
+    Response = 0x01,
+}
+
+/// Events emitted by the echo protocol.
+#[derive(Debug, PartialEq, Eq)]
+enum EchoEvent {
+    /// The protocol was negotiated with the peer.
+    Established { direction: Direction, peer_id: PeerId },
+    /// The peer does not support the protocol.
+    Unsupported { peer_id: PeerId },
+    /// The peer responded to our request.
+    Response { peer_id: PeerId, payload: Vec<u8> },
+}
+
+#[derive(Debug)]
+struct EchoProtocolHandler {
+    events: mpsc::UnboundedSender<EchoEvent>,
+    on_not_supported: OnNotSupported,
+}
+
+impl EchoProtocolHandler {
+    fn new(events: mpsc::UnboundedSender<EchoEvent>, on_not_supported: OnNotSupported) -> Self {
+        Self { events, on_not_supported }
+    }
+
+    fn connection_handler(&self) -> EchoConnectionHandler {
+        EchoConnectionHandler {
+            events: self.events.clone(),
+            on_not_supported: self.on_not_supported,
+        }
+    }
+}
+
+impl ProtocolHandler for EchoProtocolHandler {
+    type ConnectionHandler = EchoConnectionHandler;
+
+    fn on_incoming(&self, _socket_addr: SocketAddr) -> Option<Self::ConnectionHandler> {
+        Some(self.connection_handler())
+    }
+
+    fn on_outgoing(
+        &self,
+        _socket_addr: SocketAddr,
+        _peer_id: PeerId,
+    ) -> Option<Self::ConnectionHandler> {
+        Some(self.connection_handler())
+    }
+}
+
+struct EchoConnectionHandler {
+    events: mpsc::UnboundedSender<EchoEvent>,
+    on_not_supported: OnNotSupported,
+}
+
+impl ConnectionHandler for EchoConnectionHandler {
+    type Connection = EchoConnection;
+
+    fn protocol(&self) -> Protocol {
+        Protocol::new(Capability::new_static("echo", 1), 2)
+    }
+
+    fn on_unsupported_by_peer(
+        self,
+        _supported: &SharedCapabilities,
+        _direction: Direction,
+        peer_id: PeerId,
+    ) -> OnNotSupported {
+        let _ = self.events.send(EchoEvent::Unsupported { peer_id });
+        self.on_not_supported
+    }
+
+    fn into_connection(
+        self,
+        direction: Direction,
+        peer_id: PeerId,
+        conn: ProtocolConnection,
+    ) -> Self::Connection {
+        let _ = self.events.send(EchoEvent::Established { direction, peer_id });
+
+        // the dialer sends a request as soon as the connection is established
+        let initial_request = direction.is_outgoing().then(|| {
+            let mut request = BytesMut::from(&[EchoMessageId::Request as u8][..]);
+            request.extend_from_slice(ECHO_PAYLOAD);
+            request
+        });
+
+        EchoConnection { peer_id, conn, initial_request, events: self.events }
+    }
+}
+
+/// Answers all requests of the peer and reports the responses to our requests.
+struct EchoConnection {
+    peer_id: PeerId,
+    conn: ProtocolConnection,
+    initial_request: Option<BytesMut>,
+    events: mpsc::UnboundedSender<EchoEvent>,
+}
+
+impl Stream for EchoConnection {
+    type Item = BytesMut;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(request) = this.initial_request.take() {
+            return Poll::Ready(Some(request))
+        }
+
+        loop {
+            let Some(mut msg) = ready!(this.conn.poll_next_unpin(cx)) else {
+                return Poll::Ready(None)
+            };
+
+            if msg[0] == EchoMessageId::Request as u8 {
+                msg[0] = EchoMessageId::Response as u8;
+                return Poll::Ready(Some(msg))
+            }
+
+            let _ = this
+                .events
+                .send(EchoEvent::Response { peer_id: this.peer_id, payload: msg[1..].to_vec() });
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rlpx_sub_protocol_echo() {
+    reth_tracing::init_test_tracing();
+
+    let (events_tx, mut events) = mpsc::unbounded_channel();
+    let mut net = Testnet::create(0).await;
+    for _ in 0..2 {
+        let config = PeerConfig::default().add_rlpx_sub_protocol(EchoProtocolHandler::new(
+            events_tx.clone(),
+            OnNotSupported::Disconnect,
+        ));
+        net.add_peer_with_config(config).await.unwrap();
+    }
+
+    let mut handles = net.handles();
+    let handle0 = handles.next().unwrap();
+    let handle1 = handles.next().unwrap();
+    drop(handles);
+
+    let handle = net.spawn();
+
+    let mut established = NetworkEventStream::new(handle0.event_listener());
+    handle0.add_peer(*handle1.peer_id(), handle1.local_addr());
+    assert_eq!(established.next_session_established().await.unwrap(), *handle1.peer_id());
+
+    let mut received = Vec::new();
+    while received.len() < 3 {
+        received.push(events.recv().await.unwrap());
+    }
+
+    assert!(received.contains(&EchoEvent::Established {
+        direction: Direction::Outgoing(*handle1.peer_id()),
+        peer_id: *handle1.peer_id(),
+    }));
+    assert!(received.contains(&EchoEvent::Established {
+        direction: Direction::Incoming,
+        peer_id: *handle0.peer_id(),
+    }));
+    assert!(received.contains(&EchoEvent::Response {
+        peer_id: *handle1.peer_id(),
+        payload: ECHO_PAYLOAD.to_vec(),
+    }));
+
+    handle.terminate().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rlpx_sub_protocol_not_supported_keep_alive() {
+    reth_tracing::init_test_tracing();
+
+    let (events_tx, mut events) = mpsc::unbounded_channel();
+    let mut net = Testnet::create(1).await;
+    let config = PeerConfig::default()
+        .add_rlpx_sub_protocol(EchoProtocolHandler::new(events_tx, OnNotSupported::KeepAlive));
+    net.add_peer_with_config(config).await.unwrap();
+
+    let mut handles = net.handles();
+    let handle0 = handles.next().unwrap();
+    let handle1 = handles.next().unwrap();
+    drop(handles);
+
+    let handle = net.spawn();
+
+    let mut established = NetworkEventStream::new(handle1.event_listener());
+    handle1.add_peer(*handle0.peer_id(), handle0.local_addr());
+
+    assert_eq!(established.next_session_established().await.unwrap(), *handle0.peer_id());
+    assert_eq!(
+        events.recv().await.unwrap(),
+        EchoEvent::Unsupported { peer_id: *handle0.peer_id() }
+    );
+
+    handle.terminate().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rlpx_sub_protocol_not_supported_disconnect() {
+    reth_tracing::init_test_tracing();
+
+    let (events_tx, mut events) = mpsc::unbounded_channel();
+    let mut net = Testnet::create(1).await;
+    let config = PeerConfig::default()
+        .add_rlpx_sub_protocol(EchoProtocolHandler::new(events_tx, OnNotSupported::Disconnect));
+    net.add_peer_with_config(config).await.unwrap();
+
+    let mut handles = net.handles();
+    let handle0 = handles.next().unwrap();
+    let handle1 = handles.next().unwrap();
+    drop(handles);
+
+    let handle = net.spawn();
+
+    handle1.add_peer(*handle0.peer_id(), handle0.local_addr());
+
+    assert_eq!(
+        events.recv().await.unwrap(),
+        EchoEvent::Unsupported { peer_id: *handle0.peer_id() }
+    );
+    // the session is closed before it is established
+    assert_eq!(handle1.num_connected_peers(), 0);
+
+    handle.terminate().await;
+}
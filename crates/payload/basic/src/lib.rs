@@ -15,8 +15,10 @@ use futures_core::ready;
 use futures_util::FutureExt;
 use reth_interfaces::{RethError, RethResult};
 use reth_payload_builder::{
-    database::CachedReads, error::PayloadBuilderError, BuiltPayload, KeepPayloadJobAlive,
-    PayloadBuilderAttributes, PayloadJob, PayloadJobGenerator,
+    bundle::{BundlePool, PoolBundle},
+    database::CachedReads,
+    error::PayloadBuilderError,
+    BuiltPayload, KeepPayloadJobAlive, PayloadBuilderAttributes, PayloadJob, PayloadJobGenerator,
 };
 use reth_primitives::{
     bytes::BytesMut,
@@ -741,6 +743,40 @@ where
     }
 }
 
+/// Ethereum payload builder that includes the bundles of a [BundlePool] at the top of the block.
+///
+/// See [bundle_payload_builder].
+#[derive(Debug, Clone, Default)]
+pub struct EthereumBundlePayloadBuilder {
+    /// The bundles to include.
+    bundles: BundlePool,
+}
+
+impl EthereumBundlePayloadBuilder {
+    /// Creates a new builder that includes the bundles of the given pool.
+    pub fn new(bundles: BundlePool) -> Self {
+        Self { bundles }
+    }
+
+    /// Returns the pool of bundles that are included in built payloads.
+    pub fn bundles(&self) -> &BundlePool {
+        &self.bundles
+    }
+}
+
+impl<Pool, Client> PayloadBuilder<Pool, Client> for EthereumBundlePayloadBuilder
+where
+    Client: StateProviderFactory,
+    Pool: TransactionPool,
+{
+    fn try_build(
+        &self,
+        args: BuildArguments<Pool, Client>,
+    ) -> Result<BuildOutcome, PayloadBuilderError> {
+        bundle_payload_builder(args, &self.bundles)
+    }
+}
+
 /// Constructs an Ethereum transaction payload using the best transactions from the pool.
 ///
 /// Given build arguments including an Ethereum client, transaction pool,
@@ -750,6 +786,39 @@ where
 pub fn default_payload_builder<Pool, Client>(
     args: BuildArguments<Pool, Client>,
 ) -> Result<BuildOutcome, PayloadBuilderError>
+where
+    Client: StateProviderFactory,
+    Pool: TransactionPool,
+{
+    build_payload(args, Vec::new())
+}
+
+/// Constructs an Ethereum transaction payload that starts with the bundles of the [BundlePool]
+/// that target the block, followed by the best transactions from the pool.
+///
+/// Each bundle is included atomically: if any of its transactions is invalid, or reverts without
+/// being listed in the reverting transaction hashes of the bundle, none of them are included.
+/// Bundles that target the parent block or earlier are removed from the pool.
+pub fn bundle_payload_builder<Pool, Client>(
+    args: BuildArguments<Pool, Client>,
+    bundles: &BundlePool,
+) -> Result<BuildOutcome, PayloadBuilderError>
+where
+    Client: StateProviderFactory,
+    Pool: TransactionPool,
+{
+    let parent_number = args.config.parent_block.number;
+    bundles.remove_expired(parent_number);
+    let bundles = bundles.best_bundles(parent_number + 1, args.config.attributes.timestamp);
+    build_payload(args, bundles)
+}
+
+/// Builds the payload with the given bundles at the top of the block, followed by the best
+/// transactions from the pool.
+fn build_payload<Pool, Client>(
+    args: BuildArguments<Pool, Client>,
+    bundles: Vec<PoolBundle>,
+) -> Result<BuildOutcome, PayloadBuilderError>
 where
     Client: StateProviderFactory,
     Pool: TransactionPool,
@@ -794,6 +863,46 @@ where
     )?;
 
     let mut receipts = Vec::new();
+    for bundle in bundles {
+        // check if the job was cancelled, if so we can exit early
+        if cancel.is_cancelled() {
+            return Ok(BuildOutcome::Cancelled)
+        }
+
+        // ensure we still have capacity for the entire bundle
+        if cumulative_gas_used.saturating_add(bundle.gas_limit()) > block_gas_limit {
+            trace!(target: "payload_builder", bundle=?bundle.hash(), "skipping bundle that exceeds the block gas limit");
+            continue
+        }
+
+        // blob transactions can't be bundled because their sidecars are not in the pool
+        if bundle.txs.iter().any(|tx| tx.is_eip4844()) {
+            trace!(target: "payload_builder", bundle=?bundle.hash(), "skipping bundle with blob transactions");
+            continue
+        }
+
+        // the state before the bundle, which is restored if the bundle can't be included
+        let checkpoint = (db.cache.clone(), db.transition_state.clone());
+        match execute_bundle(
+            &mut db,
+            &bundle,
+            &initialized_cfg,
+            &initialized_block_env,
+            cumulative_gas_used,
+        )? {
+            Some(outcome) => {
+                cumulative_gas_used = outcome.cumulative_gas_used;
+                total_fees += outcome.fees;
+                receipts.extend(outcome.receipts.into_iter().map(Some));
+                executed_txs.extend(bundle.txs.into_iter().map(|tx| tx.into_signed()));
+            }
+            None => {
+                trace!(target: "payload_builder", bundle=?bundle.hash(), "skipping bundle that can't be included");
+                (db.cache, db.transition_state) = checkpoint;
+            }
+        }
+    }
+
     while let Some(pool_tx) = best_txs.next() {
         // ensure we still have capacity for this transaction
         if cumulative_gas_used + pool_tx.gas_limit() > block_gas_limit {
@@ -989,6 +1098,80 @@ where
     Ok(BuildOutcome::Better { payload, cached_reads })
 }
 
+/// The result of a bundle that was executed successfully.
+struct BundleOutcome {
+    /// The receipts of the bundle's transactions.
+    receipts: Vec<Receipt>,
+    /// The cumulative gas used of the block after the bundle.
+    cumulative_gas_used: u64,
+    /// The fees paid to the beneficiary by the bundle's transactions.
+    fees: U256,
+}
+
+/// Executes all transactions of the bundle and commits them to the database.
+///
+/// Returns `None` if a transaction is invalid or reverts without being allowed to, in which case
+/// the caller is responsible for reverting the changes of the bundle's preceding transactions.
+fn execute_bundle<DB: Database<Error = RethError>>(
+    db: &mut State<DB>,
+    bundle: &PoolBundle,
+    initialized_cfg: &CfgEnv,
+    initialized_block_env: &BlockEnv,
+    mut cumulative_gas_used: u64,
+) -> Result<Option<BundleOutcome>, PayloadBuilderError> {
+    let base_fee = initialized_block_env.basefee.to::<u64>();
+    let mut receipts = Vec::with_capacity(bundle.txs.len());
+    let mut fees = U256::ZERO;
+
+    for tx in &bundle.txs {
+        // Configure the environment for the block.
+        let env = Env {
+            cfg: initialized_cfg.clone(),
+            block: initialized_block_env.clone(),
+            tx: tx_env_with_recovered(tx),
+        };
+
+        let mut evm = revm::EVM::with_env(env);
+        evm.database(&mut *db);
+
+        let ResultAndState { result, state } = match evm.transact() {
+            Ok(res) => res,
+            Err(EVMError::Transaction(err)) => {
+                trace!(target: "payload_builder", ?err, ?tx, "bundle contains invalid transaction");
+                return Ok(None)
+            }
+            Err(err) => return Err(PayloadBuilderError::EvmExecutionError(err)),
+        };
+
+        if !result.is_success() && !bundle.can_revert(&tx.hash) {
+            trace!(target: "payload_builder", ?tx, "bundle transaction reverted");
+            return Ok(None)
+        }
+
+        // commit changes, so they are visible to the next transaction of the bundle
+        db.commit(state);
+
+        let gas_used = result.gas_used();
+        cumulative_gas_used += gas_used;
+
+        receipts.push(Receipt {
+            tx_type: tx.tx_type(),
+            success: result.is_success(),
+            cumulative_gas_used,
+            logs: result.logs().into_iter().map(into_reth_log).collect(),
+            #[cfg(feature = "optimism")]
+            deposit_nonce: None,
+        });
+
+        let miner_fee = tx
+            .effective_tip_per_gas(Some(base_fee))
+            .expect("fee is always valid; execution succeeded");
+        fees += U256::from(miner_fee) * U256::from(gas_used);
+    }
+
+    Ok(Some(BundleOutcome { receipts, cumulative_gas_used, fees }))
+}
+
 /// Builds an empty payload without any transactions.
 fn build_empty_payload<Client>(
     client: &Client,
@@ -0,0 +1,381 @@
+//! A pool of transaction bundles that are included in built payloads.
+//!
+//! Bundles are sent via `eth_sendBundle` and included atomically at the top of the block they
+//! target, ahead of the regular transactions of the pool.
+
+use parking_lot::RwLock;
+use reth_primitives::{keccak256, Address, BlockNumber, TransactionSignedEcRecovered, B256};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::Arc,
+};
+
+/// The default maximum number of bundles in the [BundlePool].
+pub const DEFAULT_MAX_BUNDLES: usize = 1_000;
+
+/// The default maximum number of transactions of all bundles in the [BundlePool].
+pub const DEFAULT_MAX_BUNDLE_TRANSACTIONS: usize = 10_000;
+
+/// The default maximum number of bundles of a single sender in the [BundlePool].
+pub const DEFAULT_MAX_BUNDLES_PER_SENDER: usize = 100;
+
+/// A bundle of transactions that must be included in the given order and all together, or not
+/// at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolBundle {
+    /// The transactions of the bundle, in execution order.
+    pub txs: Vec<TransactionSignedEcRecovered>,
+    /// The block the bundle is valid for.
+    pub block_number: BlockNumber,
+    /// The minimum timestamp of the block the bundle can be included in.
+    pub min_timestamp: Option<u64>,
+    /// The maximum timestamp of the block the bundle can be included in.
+    pub max_timestamp: Option<u64>,
+    /// The hashes of the transactions that are allowed to revert.
+    pub reverting_tx_hashes: Vec<B256>,
+    /// The UUID that can be used to replace or cancel the bundle.
+    pub replacement_uuid: Option<String>,
+}
+
+// === impl PoolBundle ===
+
+impl PoolBundle {
+    /// Returns the hash of the bundle, the keccak256 hash of the concatenated transaction hashes.
+    pub fn hash(&self) -> B256 {
+        let mut hashes = Vec::with_capacity(self.txs.len() * 32);
+        for tx in &self.txs {
+            hashes.extend_from_slice(tx.hash.as_slice());
+        }
+        keccak256(hashes)
+    }
+
+    /// Returns `true` if the transaction with the given hash is allowed to revert.
+    pub fn can_revert(&self, tx_hash: &B256) -> bool {
+        self.reverting_tx_hashes.contains(tx_hash)
+    }
+
+    /// Returns the sender of the bundle, the signer of its first transaction.
+    pub fn sender(&self) -> Option<Address> {
+        self.txs.first().map(|tx| tx.signer())
+    }
+
+    /// Returns the sum of the gas limits of all transactions, saturating at `u64::MAX`.
+    pub fn gas_limit(&self) -> u64 {
+        self.txs.iter().fold(0u64, |gas_limit, tx| gas_limit.saturating_add(tx.gas_limit()))
+    }
+
+    /// Returns `true` if the bundle can be included in the block with the given number and
+    /// timestamp.
+    pub fn is_includable_at(&self, block_number: BlockNumber, timestamp: u64) -> bool {
+        self.block_number == block_number &&
+            self.min_timestamp.map_or(true, |min| min <= timestamp) &&
+            self.max_timestamp.map_or(true, |max| timestamp <= max)
+    }
+}
+
+/// Limits of the [BundlePool].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BundlePoolLimits {
+    /// The maximum number of bundles.
+    pub max_bundles: usize,
+    /// The maximum number of transactions of all bundles.
+    pub max_txs: usize,
+    /// The maximum number of bundles of a single sender.
+    pub max_bundles_per_sender: usize,
+}
+
+impl Default for BundlePoolLimits {
+    fn default() -> Self {
+        Self {
+            max_bundles: DEFAULT_MAX_BUNDLES,
+            max_txs: DEFAULT_MAX_BUNDLE_TRANSACTIONS,
+            max_bundles_per_sender: DEFAULT_MAX_BUNDLES_PER_SENDER,
+        }
+    }
+}
+
+/// Errors that can occur when adding a bundle to the [BundlePool].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum BundlePoolError {
+    /// The pool reached its [BundlePoolLimits].
+    #[error("bundle pool is full")]
+    PoolFull,
+    /// The sender reached the maximum number of bundles per sender of the [BundlePoolLimits].
+    #[error("too many bundles from sender {0}")]
+    SenderLimitExceeded(Address),
+}
+
+/// A shared pool of [PoolBundle]s, keyed by bundle hash.
+///
+/// Bundles are kept until the block they target is mined and are returned in the order they were
+/// received. A bundle with a replacement UUID replaces the previous bundle with the same UUID, so
+/// there is at most one bundle per replacement UUID.
+///
+/// The size of the pool is bounded by its [BundlePoolLimits], new bundles are rejected if the
+/// pool is full or their sender has too many bundles in the pool.
+#[derive(Debug, Clone, Default)]
+pub struct BundlePool {
+    inner: Arc<RwLock<BundlePoolInner>>,
+}
+
+// === impl BundlePool ===
+
+impl BundlePool {
+    /// Creates a new pool with the given limits.
+    pub fn new(limits: BundlePoolLimits) -> Self {
+        let inner = BundlePoolInner { limits, ..Default::default() };
+        Self { inner: Arc::new(RwLock::new(inner)) }
+    }
+
+    /// Adds the bundle to the pool and returns its hash.
+    ///
+    /// If the bundle has a replacement UUID, this replaces the bundle that was sent with the same
+    /// UUID.
+    ///
+    /// Returns an error if the pool would exceed its [BundlePoolLimits].
+    pub fn add_bundle(&self, bundle: PoolBundle) -> Result<B256, BundlePoolError> {
+        let hash = bundle.hash();
+        let mut inner = self.inner.write();
+
+        // the bundles that are replaced by this bundle don't count towards the limits
+        let mut replaced = vec![hash];
+        if let Some(replaced_hash) =
+            bundle.replacement_uuid.as_ref().and_then(|uuid| inner.by_uuid.get(uuid))
+        {
+            if *replaced_hash != hash {
+                replaced.push(*replaced_hash);
+            }
+        }
+        let sender = bundle.sender();
+        let (replaced_bundles, replaced_txs, replaced_sender_bundles) = replaced
+            .iter()
+            .filter_map(|hash| inner.bundles.get(hash))
+            .fold((0, 0, 0), |(bundles, txs, sender_bundles), (_, replaced)| {
+                let same_sender = (replaced.sender() == sender) as usize;
+                (bundles + 1, txs + replaced.txs.len(), sender_bundles + same_sender)
+            });
+        if inner.bundles.len() - replaced_bundles >= inner.limits.max_bundles ||
+            inner.num_txs - replaced_txs + bundle.txs.len() > inner.limits.max_txs
+        {
+            return Err(BundlePoolError::PoolFull)
+        }
+        if let Some(sender) = sender {
+            let sender_bundles = inner.by_sender.get(&sender).copied().unwrap_or_default();
+            if sender_bundles - replaced_sender_bundles >= inner.limits.max_bundles_per_sender {
+                return Err(BundlePoolError::SenderLimitExceeded(sender))
+            }
+        }
+
+        for hash in replaced {
+            inner.remove(&hash);
+        }
+        if let Some(uuid) = bundle.replacement_uuid.clone() {
+            inner.by_uuid.insert(uuid, hash);
+        }
+        let id = inner.next_id;
+        inner.next_id += 1;
+        inner.num_txs += bundle.txs.len();
+        if let Some(sender) = sender {
+            *inner.by_sender.entry(sender).or_default() += 1;
+        }
+        inner.bundles.insert(hash, (id, bundle));
+        Ok(hash)
+    }
+
+    /// Removes the bundle with the given replacement UUID from the pool.
+    ///
+    /// Returns the removed bundle, if any.
+    pub fn cancel_bundle(&self, replacement_uuid: &str) -> Option<PoolBundle> {
+        let mut inner = self.inner.write();
+        let hash = *inner.by_uuid.get(replacement_uuid)?;
+        inner.remove(&hash)
+    }
+
+    /// Returns the bundle with the given hash.
+    pub fn get(&self, bundle_hash: &B256) -> Option<PoolBundle> {
+        self.inner.read().bundles.get(bundle_hash).map(|(_, bundle)| bundle.clone())
+    }
+
+    /// Returns all bundles that can be included in the block with the given number and
+    /// timestamp, in the order they were received.
+    pub fn best_bundles(&self, block_number: BlockNumber, timestamp: u64) -> Vec<PoolBundle> {
+        let inner = self.inner.read();
+        let mut bundles = inner
+            .bundles
+            .values()
+            .filter(|(_, bundle)| bundle.is_includable_at(block_number, timestamp))
+            .collect::<Vec<_>>();
+        bundles.sort_unstable_by_key(|(id, _)| *id);
+        bundles.into_iter().map(|(_, bundle)| bundle.clone()).collect()
+    }
+
+    /// Removes all bundles that target the given block or an earlier one.
+    pub fn remove_expired(&self, block_number: BlockNumber) {
+        let mut inner = self.inner.write();
+        let expired = inner
+            .bundles
+            .iter()
+            .filter(|(_, (_, bundle))| bundle.block_number <= block_number)
+            .map(|(hash, _)| *hash)
+            .collect::<Vec<_>>();
+        for hash in expired {
+            inner.remove(&hash);
+        }
+    }
+
+    /// Returns the number of bundles in the pool.
+    pub fn len(&self) -> usize {
+        self.inner.read().bundles.len()
+    }
+
+    /// Returns `true` if the pool contains no bundles.
+    pub fn is_empty(&self) -> bool {
+        self.inner.read().bundles.is_empty()
+    }
+}
+
+/// Container type for [BundlePool] internals.
+#[derive(Debug, Default)]
+struct BundlePoolInner {
+    /// All bundles by hash, with the id of their arrival.
+    bundles: HashMap<B256, (u64, PoolBundle)>,
+    /// The hashes of the bundles that have a replacement UUID.
+    by_uuid: HashMap<String, B256>,
+    /// The number of bundles per sender.
+    by_sender: HashMap<Address, usize>,
+    /// The number of transactions of all bundles.
+    num_txs: usize,
+    /// The id of the next bundle.
+    next_id: u64,
+    /// The limits of the pool.
+    limits: BundlePoolLimits,
+}
+
+impl BundlePoolInner {
+    /// Removes the bundle with the given hash and its replacement UUID.
+    fn remove(&mut self, hash: &B256) -> Option<PoolBundle> {
+        let (_, bundle) = self.bundles.remove(hash)?;
+        self.num_txs -= bundle.txs.len();
+        if let Some(sender) = bundle.sender() {
+            if let Entry::Occupied(mut entry) = self.by_sender.entry(sender) {
+                *entry.get_mut() -= 1;
+                if *entry.get() == 0 {
+                    entry.remove();
+                }
+            }
+        }
+        if let Some(uuid) = &bundle.replacement_uuid {
+            if self.by_uuid.get(uuid) == Some(hash) {
+                self.by_uuid.remove(uuid);
+            }
+        }
+        Some(bundle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::{Address, Signature, Transaction, TransactionSigned, TxLegacy};
+
+    fn bundle(nonces: std::ops::Range<u64>, gas_limit: u64, uuid: Option<&str>) -> PoolBundle {
+        bundle_from(Address::ZERO, nonces, gas_limit, uuid)
+    }
+
+    fn bundle_from(
+        sender: Address,
+        nonces: std::ops::Range<u64>,
+        gas_limit: u64,
+        uuid: Option<&str>,
+    ) -> PoolBundle {
+        let txs = nonces
+            .map(|nonce| {
+                let tx = Transaction::Legacy(TxLegacy { nonce, gas_limit, ..Default::default() });
+                let tx =
+                    TransactionSigned::from_transaction_and_signature(tx, Signature::default());
+                TransactionSignedEcRecovered::from_signed_transaction(tx, sender)
+            })
+            .collect();
+        PoolBundle {
+            txs,
+            block_number: 1,
+            min_timestamp: None,
+            max_timestamp: None,
+            reverting_tx_hashes: Vec::new(),
+            replacement_uuid: uuid.map(String::from),
+        }
+    }
+
+    #[test]
+    fn gas_limit_saturates() {
+        assert_eq!(bundle(0..2, u64::MAX, None).gas_limit(), u64::MAX);
+        assert_eq!(bundle(0..2, 21_000, None).gas_limit(), 42_000);
+    }
+
+    #[test]
+    fn pool_limits() {
+        let pool = BundlePool::new(BundlePoolLimits {
+            max_bundles: 2,
+            max_txs: 4,
+            max_bundles_per_sender: 2,
+        });
+        pool.add_bundle(bundle(0..1, 21_000, None)).unwrap();
+        pool.add_bundle(bundle(1..3, 21_000, None)).unwrap();
+        assert_eq!(pool.add_bundle(bundle(3..4, 21_000, None)), Err(BundlePoolError::PoolFull));
+
+        // the same bundle can be sent again
+        pool.add_bundle(bundle(0..1, 21_000, None)).unwrap();
+        assert_eq!(pool.len(), 2);
+
+        // expired bundles free up space
+        pool.remove_expired(1);
+        assert!(pool.is_empty());
+        assert_eq!(pool.add_bundle(bundle(0..5, 21_000, None)), Err(BundlePoolError::PoolFull));
+        pool.add_bundle(bundle(0..4, 21_000, None)).unwrap();
+    }
+
+    #[test]
+    fn one_bundle_per_replacement_uuid() {
+        let pool = BundlePool::new(BundlePoolLimits {
+            max_bundles: 1,
+            max_txs: 10,
+            max_bundles_per_sender: 1,
+        });
+        let first = pool.add_bundle(bundle(0..1, 21_000, Some("uuid"))).unwrap();
+        // replacing the bundle doesn't count towards the limits
+        let second = pool.add_bundle(bundle(1..3, 21_000, Some("uuid"))).unwrap();
+        assert_eq!(pool.len(), 1);
+        assert!(pool.get(&first).is_none());
+        assert!(pool.get(&second).is_some());
+
+        // sending the bundle with another UUID releases the previous UUID
+        pool.add_bundle(bundle(1..3, 21_000, Some("other"))).unwrap();
+        assert!(pool.cancel_bundle("uuid").is_none());
+        assert_eq!(pool.cancel_bundle("other").map(|bundle| bundle.hash()), Some(second));
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn sender_limit() {
+        let pool = BundlePool::new(BundlePoolLimits {
+            max_bundles: 10,
+            max_txs: 10,
+            max_bundles_per_sender: 1,
+        });
+        let (alice, bob) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        pool.add_bundle(bundle_from(alice, 0..1, 21_000, Some("uuid"))).unwrap();
+        assert_eq!(
+            pool.add_bundle(bundle_from(alice, 1..2, 21_000, None)),
+            Err(BundlePoolError::SenderLimitExceeded(alice))
+        );
+        pool.add_bundle(bundle_from(bob, 0..1, 21_000, None)).unwrap();
+
+        // replacing a bundle of the sender doesn't count towards the limit
+        pool.add_bundle(bundle_from(alice, 1..2, 21_000, Some("uuid"))).unwrap();
+        assert_eq!(pool.len(), 2);
+
+        // removed bundles free up the limit of their sender
+        pool.cancel_bundle("uuid").unwrap();
+        pool.add_bundle(bundle_from(alice, 2..3, 21_000, None)).unwrap();
+    }
+}
@@ -101,6 +101,7 @@
 #![deny(unused_must_use, rust_2018_idioms)]
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
+pub mod bundle;
 pub mod database;
 pub mod error;
 mod metrics;
@@ -8,4 +8,7 @@ pub enum Compression {
     ZstdWithDictionary,
     #[default]
     Uncompressed,
+    /// Compresses every column with its own algorithm, as laid out by
+    /// [`SnapshotSegment::column_compression`](crate::SnapshotSegment::column_compression).
+    PerColumn,
 }
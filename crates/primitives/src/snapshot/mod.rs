@@ -8,5 +8,82 @@ pub use compression::Compression;
 pub use filters::{Filters, InclusionFilter, PerfectHashingFunction};
 pub use segment::{SegmentHeader, SnapshotSegment};
 
+use crate::BlockNumber;
+use std::path::Path;
+
 /// Default snapshot block count.
 pub const BLOCKS_PER_SNAPSHOT: u64 = 500_000;
+
+/// Highest snapshotted block numbers, per data part.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct HighestSnapshots {
+    /// Highest snapshotted block of headers, inclusive.
+    /// If [`None`], no snapshot is available.
+    pub headers: Option<BlockNumber>,
+    /// Highest snapshotted block of receipts, inclusive.
+    /// If [`None`], no snapshot is available.
+    pub receipts: Option<BlockNumber>,
+    /// Highest snapshotted block of transactions, inclusive.
+    /// If [`None`], no snapshot is available.
+    pub transactions: Option<BlockNumber>,
+    /// Highest snapshotted block of account changesets, inclusive.
+    /// If [`None`], no snapshot is available.
+    pub account_changesets: Option<BlockNumber>,
+    /// Highest snapshotted block of storage changesets, inclusive.
+    /// If [`None`], no snapshot is available.
+    pub storage_changesets: Option<BlockNumber>,
+}
+
+impl HighestSnapshots {
+    /// Returns the highest snapshotted block of the given segment.
+    pub fn highest(&self, segment: SnapshotSegment) -> Option<BlockNumber> {
+        match segment {
+            SnapshotSegment::Headers => self.headers,
+            SnapshotSegment::Transactions => self.transactions,
+            SnapshotSegment::Receipts => self.receipts,
+            SnapshotSegment::AccountChangeSets => self.account_changesets,
+            SnapshotSegment::StorageChangeSets => self.storage_changesets,
+        }
+    }
+
+    /// Returns a mutable reference to the highest snapshotted block of the given segment.
+    pub fn as_mut(&mut self, segment: SnapshotSegment) -> &mut Option<BlockNumber> {
+        match segment {
+            SnapshotSegment::Headers => &mut self.headers,
+            SnapshotSegment::Transactions => &mut self.transactions,
+            SnapshotSegment::Receipts => &mut self.receipts,
+            SnapshotSegment::AccountChangeSets => &mut self.account_changesets,
+            SnapshotSegment::StorageChangeSets => &mut self.storage_changesets,
+        }
+    }
+
+    /// Builds the highest snapshotted block numbers by scanning the snapshot files in the given
+    /// directory.
+    ///
+    /// Files that are not snapshot data files are ignored. If the directory doesn't exist, no
+    /// snapshots are available.
+    pub fn from_directory(directory: impl AsRef<Path>) -> std::io::Result<Self> {
+        let mut highest_snapshots = Self::default();
+
+        let entries = match std::fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(highest_snapshots),
+            Err(err) => return Err(err),
+        };
+
+        for entry in entries {
+            let entry = entry?;
+            let Some((segment, block_range)) =
+                entry.file_name().to_str().and_then(SnapshotSegment::parse_filename)
+            else {
+                continue
+            };
+
+            let highest = highest_snapshots.as_mut(segment);
+            *highest =
+                Some(highest.map_or(*block_range.end(), |highest| highest.max(*block_range.end())));
+        }
+
+        Ok(highest_snapshots)
+    }
+}
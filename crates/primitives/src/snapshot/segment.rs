@@ -14,11 +14,15 @@ pub enum SnapshotSegment {
     Transactions,
     /// Snapshot segment responsible for the `Receipts` table.
     Receipts,
+    /// Snapshot segment responsible for the `AccountChangeSet` table.
+    AccountChangeSets,
+    /// Snapshot segment responsible for the `StorageChangeSet` table.
+    StorageChangeSets,
 }
 
 impl SnapshotSegment {
     /// Returns the default configuration of the segment.
-    const fn config(&self) -> (Filters, Compression) {
+    pub const fn config(&self) -> (Filters, Compression) {
         let default_config = (
             Filters::WithFilters(InclusionFilter::Cuckoo, super::PerfectHashingFunction::Fmph),
             Compression::Lz4,
@@ -28,6 +32,28 @@ impl SnapshotSegment {
             SnapshotSegment::Headers => default_config,
             SnapshotSegment::Transactions => default_config,
             SnapshotSegment::Receipts => default_config,
+            // Changesets are only looked up by block number, so there are no keys to filter by.
+            SnapshotSegment::AccountChangeSets | SnapshotSegment::StorageChangeSets => {
+                (Filters::WithoutFilters, Compression::Lz4)
+            }
+        }
+    }
+
+    /// Returns the compression of each column of the segment when using
+    /// [Compression::PerColumn].
+    ///
+    /// Hashes are left uncompressed, since they don't compress at all, while the columns with
+    /// encoded structures get a zstd dictionary trained on that column alone.
+    pub const fn column_compression(&self) -> &'static [Compression] {
+        match self {
+            // Headers, HeaderTD, CanonicalHeaders
+            SnapshotSegment::Headers => {
+                &[Compression::ZstdWithDictionary, Compression::Lz4, Compression::Uncompressed]
+            }
+            SnapshotSegment::Transactions |
+            SnapshotSegment::Receipts |
+            SnapshotSegment::AccountChangeSets |
+            SnapshotSegment::StorageChangeSets => &[Compression::ZstdWithDictionary],
         }
     }
 
@@ -48,6 +74,8 @@ impl SnapshotSegment {
             SnapshotSegment::Headers => "headers",
             SnapshotSegment::Transactions => "transactions",
             SnapshotSegment::Receipts => "receipts",
+            SnapshotSegment::AccountChangeSets => "accountchangesets",
+            SnapshotSegment::StorageChangeSets => "storagechangesets",
         };
         let filters_name = match filters {
             Filters::WithFilters(inclusion_filter, phf) => {
@@ -67,6 +95,7 @@ impl SnapshotSegment {
             Compression::Zstd => "zstd",
             Compression::ZstdWithDictionary => "zstd-dict",
             Compression::Uncompressed => "uncompressed",
+            Compression::PerColumn => "percolumn",
         };
 
         format!(
@@ -76,6 +105,41 @@ impl SnapshotSegment {
         )
         .into()
     }
+
+    /// Parses the segment and block range from a snapshot file name, as produced by
+    /// [SnapshotSegment::filename_with_configuration].
+    ///
+    /// Returns `None` if the file name is not the name of a snapshot data file, e.g. an index or
+    /// offsets file of a snapshot.
+    pub fn parse_filename(name: &str) -> Option<(Self, RangeInclusive<BlockNumber>)> {
+        // auxiliary files of a snapshot share its name, but have an extension
+        if name.contains('.') {
+            return None
+        }
+
+        let mut parts = name.split('_');
+        if parts.next()? != "snapshot" {
+            return None
+        }
+
+        let segment = match parts.next()? {
+            "headers" => SnapshotSegment::Headers,
+            "transactions" => SnapshotSegment::Transactions,
+            "receipts" => SnapshotSegment::Receipts,
+            "accountchangesets" => SnapshotSegment::AccountChangeSets,
+            "storagechangesets" => SnapshotSegment::StorageChangeSets,
+            _ => return None,
+        };
+        let start = parts.next()?.parse().ok()?;
+        let end = parts.next()?.parse().ok()?;
+
+        // filters and compression
+        if parts.count() != 2 || start > end {
+            return None
+        }
+
+        Some((segment, start..=end))
+    }
 }
 
 /// A segment header that contains information common to all segments. Used for storage.
@@ -99,6 +163,21 @@ impl SegmentHeader {
         Self { block_range, tx_range, segment }
     }
 
+    /// Returns the block range of the segment.
+    pub fn block_range(&self) -> RangeInclusive<BlockNumber> {
+        self.block_range.clone()
+    }
+
+    /// Returns the transaction range of the segment.
+    pub fn tx_range(&self) -> RangeInclusive<TxNumber> {
+        self.tx_range.clone()
+    }
+
+    /// Returns the segment type.
+    pub fn segment(&self) -> SnapshotSegment {
+        self.segment
+    }
+
     /// Returns the first block number of the segment.
     pub fn block_start(&self) -> BlockNumber {
         *self.block_range.start()
@@ -112,8 +191,56 @@ impl SegmentHeader {
     /// Returns the row offset which depends on whether the segment is block or transaction based.
     pub fn start(&self) -> u64 {
         match self.segment {
-            SnapshotSegment::Headers => self.block_start(),
+            SnapshotSegment::Headers |
+            SnapshotSegment::AccountChangeSets |
+            SnapshotSegment::StorageChangeSets => self.block_start(),
             SnapshotSegment::Transactions | SnapshotSegment::Receipts => self.tx_start(),
         }
     }
+
+    /// Returns the last row number (inclusive) which depends on whether the segment is block or
+    /// transaction based.
+    pub fn end(&self) -> u64 {
+        match self.segment {
+            SnapshotSegment::Headers |
+            SnapshotSegment::AccountChangeSets |
+            SnapshotSegment::StorageChangeSets => *self.block_range.end(),
+            SnapshotSegment::Transactions | SnapshotSegment::Receipts => *self.tx_range.end(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_filename() {
+        for segment in [
+            SnapshotSegment::Headers,
+            SnapshotSegment::Transactions,
+            SnapshotSegment::Receipts,
+            SnapshotSegment::AccountChangeSets,
+            SnapshotSegment::StorageChangeSets,
+        ] {
+            for (filters, compression) in [
+                segment.config(),
+                (Filters::WithoutFilters, Compression::ZstdWithDictionary),
+                (Filters::WithoutFilters, Compression::PerColumn),
+            ] {
+                let filename = segment.filename_with_configuration(filters, compression, &(5..=10));
+                assert_eq!(
+                    SnapshotSegment::parse_filename(filename.to_str().unwrap()),
+                    Some((segment, 5..=10))
+                );
+            }
+        }
+
+        let filename = SnapshotSegment::Headers.filename(&(0..=499_999));
+        let filename = filename.to_str().unwrap();
+        assert_eq!(SnapshotSegment::parse_filename(&format!("{filename}.idx")), None);
+        assert_eq!(SnapshotSegment::parse_filename("snapshot_accounts_0_1_none_lz4"), None);
+        assert_eq!(SnapshotSegment::parse_filename("snapshot_headers_2_1_none_lz4"), None);
+        assert_eq!(SnapshotSegment::parse_filename("snapshot_headers_0_1"), None);
+    }
 }
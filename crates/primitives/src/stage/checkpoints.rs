@@ -162,6 +162,20 @@ pub struct IndexHistoryCheckpoint {
     pub progress: EntitiesCheckpoint,
 }
 
+/// Saves the progress of SnapSync stage.
+#[main_codec]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SnapSyncCheckpoint {
+    /// The pivot block whose state is downloaded.
+    pub pivot: BlockNumber,
+    /// The hash of the next account to download, [None] once all accounts are downloaded.
+    pub next_account: Option<B256>,
+    /// Whether the downloaded state matches the state root of the pivot block.
+    pub complete: bool,
+    /// Progress measured in the downloaded share of the account hash space.
+    pub progress: EntitiesCheckpoint,
+}
+
 /// Saves the progress of abstract stage iterating over or downloading entities.
 #[main_codec]
 #[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
@@ -241,7 +255,10 @@ impl StageCheckpoint {
             StageUnitCheckpoint::IndexHistory(IndexHistoryCheckpoint {
                 progress: entities,
                 ..
-            }) => Some(entities),
+            }) |
+            StageUnitCheckpoint::SnapSync(SnapSyncCheckpoint { progress: entities, .. }) => {
+                Some(entities)
+            }
         }
     }
 }
@@ -264,6 +281,8 @@ pub enum StageUnitCheckpoint {
     Headers(HeadersCheckpoint),
     /// Saves the progress of Index History stage.
     IndexHistory(IndexHistoryCheckpoint),
+    /// Saves the progress of SnapSync stage.
+    SnapSync(SnapSyncCheckpoint),
 }
 
 /// Generates:
@@ -379,6 +398,15 @@ stage_unit_checkpoints!(
         index_history_stage_checkpoint,
         /// Sets the stage checkpoint to index history.
         with_index_history_stage_checkpoint
+    ),
+    (
+        6,
+        SnapSync,
+        SnapSyncCheckpoint,
+        /// Returns the snap sync stage checkpoint, if any.
+        snap_sync_stage_checkpoint,
+        /// Sets the stage checkpoint to snap sync.
+        with_snap_sync_stage_checkpoint
     )
 );
 
@@ -447,6 +475,15 @@ mod tests {
                     total: u32::MAX as u64 + rng.gen::<u64>(),
                 },
             }),
+            StageUnitCheckpoint::SnapSync(SnapSyncCheckpoint {
+                pivot: rng.gen(),
+                next_account: Some(rng.gen()),
+                complete: rng.gen(),
+                progress: EntitiesCheckpoint {
+                    processed: rng.gen::<u32>() as u64,
+                    total: u32::MAX as u64 + rng.gen::<u64>(),
+                },
+            }),
         ];
 
         for checkpoint in checkpoints {
@@ -16,12 +16,17 @@ pub enum StageId {
     TransactionLookup,
     IndexStorageHistory,
     IndexAccountHistory,
+    IndexTraceAddresses,
+    SnapSync,
     Finish,
     Other(&'static str),
 }
 
 impl StageId {
     /// All supported Stages
+    ///
+    /// This does not include optional stages, such as [`StageId::IndexTraceAddresses`] and
+    /// [`StageId::SnapSync`], that are not part of the default pipeline.
     pub const ALL: [StageId; 13] = [
         StageId::Headers,
         StageId::TotalDifficulty,
@@ -53,6 +58,8 @@ impl StageId {
             StageId::TransactionLookup => "TransactionLookup",
             StageId::IndexAccountHistory => "IndexAccountHistory",
             StageId::IndexStorageHistory => "IndexStorageHistory",
+            StageId::IndexTraceAddresses => "IndexTraceAddresses",
+            StageId::SnapSync => "SnapSync",
             StageId::Finish => "Finish",
             StageId::Other(s) => s,
         }
@@ -93,6 +100,8 @@ mod tests {
         assert_eq!(StageId::IndexAccountHistory.to_string(), "IndexAccountHistory");
         assert_eq!(StageId::IndexStorageHistory.to_string(), "IndexStorageHistory");
         assert_eq!(StageId::TransactionLookup.to_string(), "TransactionLookup");
+        assert_eq!(StageId::IndexTraceAddresses.to_string(), "IndexTraceAddresses");
+        assert_eq!(StageId::SnapSync.to_string(), "SnapSync");
         assert_eq!(StageId::Finish.to_string(), "Finish");
 
         assert_eq!(StageId::Other("Foo").to_string(), "Foo");
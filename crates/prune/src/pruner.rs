@@ -6,11 +6,12 @@ use crate::{
     Metrics, PrunerError, PrunerEvent,
 };
 use reth_db::database::Database;
+use reth_interfaces::RethResult;
 use reth_primitives::{BlockNumber, ChainSpec, PruneMode, PruneProgress, PruneSegment};
 use reth_provider::{ProviderFactory, PruneCheckpointReader};
 use reth_snapshot::HighestSnapshotsTracker;
 use reth_tokio_util::EventListeners;
-use std::{collections::BTreeMap, sync::Arc, time::Instant};
+use std::{collections::BTreeMap, path::Path, sync::Arc, time::Instant};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::{debug, trace};
 
@@ -62,6 +63,15 @@ impl<DB: Database> Pruner<DB> {
         }
     }
 
+    /// Reads the data that was snapshotted into the given directory, and deleted from the
+    /// database, from the snapshots.
+    pub fn with_snapshots(mut self, snapshots_path: impl AsRef<Path>) -> RethResult<Self> {
+        self.provider_factory = self
+            .provider_factory
+            .with_snapshots(snapshots_path, Some(self.highest_snapshots_tracker.clone()))?;
+        Ok(self)
+    }
+
     /// Listen for events on the prune.
     pub fn events(&mut self) -> UnboundedReceiverStream<PrunerEvent> {
         self.listeners.new_listener()
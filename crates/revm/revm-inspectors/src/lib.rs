@@ -22,5 +22,8 @@ pub mod access_list;
 /// used in the main RETH executor.
 pub mod stack;
 
+/// An inspector that collects the addresses that can be matched by a trace filter
+pub mod trace_addresses;
+
 /// An inspector for recording traces
 pub mod tracing;
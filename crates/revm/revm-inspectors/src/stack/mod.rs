@@ -1,11 +1,17 @@
+use crate::tracing::{Eip3155Inspector, FourByteInspector, TracingInspector};
 use reth_primitives::{Address, Bytes, TxHash, B256, U256};
 use revm::{
     inspectors::CustomPrintTracer,
     interpreter::{CallInputs, CreateInputs, Gas, InstructionResult, Interpreter},
-    primitives::Env,
+    primitives::{Env, ExecutionResult},
     Database, EVMData, Inspector,
 };
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    fs::File,
+    io::{self, BufWriter},
+    path::PathBuf,
+};
 
 /// A wrapped [Inspector] that can be reused in the stack
 mod maybe_owned;
@@ -32,18 +38,45 @@ pub enum Hook {
 ///
 /// If a call to an inspector returns a value other than [InstructionResult::Continue] (or
 /// equivalent) the remaining inspectors are not called.
-#[derive(Default, Clone)]
+#[derive(Default)]
 pub struct InspectorStack {
     /// An inspector that prints the opcode traces to the console.
     pub custom_print_tracer: Option<CustomPrintTracer>,
+    /// The directory EIP-3155 traces of the inspected transactions are written to.
+    pub eip3155_dir: Option<PathBuf>,
+    /// The EIP-3155 inspector of the currently inspected transaction.
+    eip3155: Option<Eip3155Inspector<BufWriter<File>>>,
+    /// An inspector that collects the selectors of all calls.
+    pub four_byte: Option<FourByteInspector>,
+    /// Inspectors that record call traces, e.g. one per tracer of a
+    /// [MuxInspector](crate::tracing::MuxInspector).
+    pub tracers: Vec<TracingInspector>,
     /// The provided hook
     pub hook: Hook,
 }
 
+impl Clone for InspectorStack {
+    /// Clones the stack's configuration, without the traces of the currently inspected
+    /// transaction.
+    fn clone(&self) -> Self {
+        Self {
+            custom_print_tracer: self.custom_print_tracer.clone(),
+            eip3155_dir: self.eip3155_dir.clone(),
+            eip3155: None,
+            four_byte: None,
+            tracers: Vec::new(),
+            hook: self.hook.clone(),
+        }
+    }
+}
+
 impl Debug for InspectorStack {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("InspectorStack")
             .field("custom_print_tracer", &self.custom_print_tracer.is_some())
+            .field("eip3155_dir", &self.eip3155_dir)
+            .field("four_byte", &self.four_byte.is_some())
+            .field("tracers", &self.tracers.len())
             .field("hook", &self.hook)
             .finish()
     }
@@ -52,7 +85,11 @@ impl Debug for InspectorStack {
 impl InspectorStack {
     /// Create a new inspector stack.
     pub fn new(config: InspectorStackConfig) -> Self {
-        let mut stack = InspectorStack { hook: config.hook, ..Default::default() };
+        let mut stack = InspectorStack {
+            eip3155_dir: config.eip3155_dir,
+            hook: config.hook,
+            ..Default::default()
+        };
 
         if config.use_printer_tracer {
             stack.custom_print_tracer = Some(CustomPrintTracer::default());
@@ -70,6 +107,32 @@ impl InspectorStack {
             Hook::All => true,
         }
     }
+
+    /// Prepares the stack for inspecting the given transaction.
+    ///
+    /// If an EIP-3155 directory is configured, this creates the
+    /// `<block_number>-<tx_hash>.jsonl` trace file of the transaction in it.
+    pub fn start_transaction(&mut self, env: &Env, tx_hash: TxHash) -> io::Result<()> {
+        self.eip3155 = None;
+        if let Some(dir) = &self.eip3155_dir {
+            std::fs::create_dir_all(dir)?;
+            let path = dir.join(format!("{}-{tx_hash:?}.jsonl", env.block.number));
+            let file = BufWriter::new(File::create(path)?);
+            self.eip3155 = Some(Eip3155Inspector::new(file));
+        }
+        Ok(())
+    }
+
+    /// Finishes inspecting the transaction started with [InspectorStack::start_transaction].
+    ///
+    /// This completes the EIP-3155 trace of the transaction, if any, with the summary of the
+    /// result.
+    pub fn end_transaction(&mut self, result: &ExecutionResult) -> io::Result<()> {
+        if let Some(mut inspector) = self.eip3155.take() {
+            inspector.write_summary(result, None)?;
+        }
+        Ok(())
+    }
 }
 
 /// Configuration for the inspectors.
@@ -79,21 +142,35 @@ pub struct InspectorStackConfig {
     /// In execution this will print opcode level traces directly to console.
     pub use_printer_tracer: bool,
 
+    /// Write an EIP-3155 trace of every inspected transaction to this directory.
+    pub eip3155_dir: Option<PathBuf>,
+
     /// Hook on a specific block or transaction.
     pub hook: Hook,
 }
 
 /// Helper macro to call the same method on multiple inspectors without resorting to dynamic
 /// dispatch
+///
+/// The inspectors of the first list are [Option]s, the ones of the optional second list are
+/// collections of inspectors, e.g. a [Vec].
 #[macro_export]
 macro_rules! call_inspectors {
+    ($id:ident, [ $($inspector:expr),+ ], [ $($inspectors:expr),+ ], $call:block) => {
+        $crate::call_inspectors!($id, [ $($inspector),+ ], $call);
+        $({
+            for $id in $inspectors {
+                $call;
+            }
+        })+
+    };
     ($id:ident, [ $($inspector:expr),+ ], $call:block) => {
         $({
             if let Some($id) = $inspector {
                 $call;
             }
         })+
-    }
+    };
 }
 
 impl<DB> Inspector<DB> for InspectorStack
@@ -101,15 +178,25 @@ where
     DB: Database,
 {
     fn initialize_interp(&mut self, interpreter: &mut Interpreter<'_>, data: &mut EVMData<'_, DB>) {
-        call_inspectors!(inspector, [&mut self.custom_print_tracer], {
-            inspector.initialize_interp(interpreter, data);
-        });
+        call_inspectors!(
+            inspector,
+            [&mut self.custom_print_tracer, &mut self.eip3155, &mut self.four_byte],
+            [&mut self.tracers],
+            {
+                inspector.initialize_interp(interpreter, data);
+            }
+        );
     }
 
     fn step(&mut self, interpreter: &mut Interpreter<'_>, data: &mut EVMData<'_, DB>) {
-        call_inspectors!(inspector, [&mut self.custom_print_tracer], {
-            inspector.step(interpreter, data);
-        });
+        call_inspectors!(
+            inspector,
+            [&mut self.custom_print_tracer, &mut self.eip3155, &mut self.four_byte],
+            [&mut self.tracers],
+            {
+                inspector.step(interpreter, data);
+            }
+        );
     }
 
     fn log(
@@ -119,15 +206,25 @@ where
         topics: &[B256],
         data: &Bytes,
     ) {
-        call_inspectors!(inspector, [&mut self.custom_print_tracer], {
-            inspector.log(evm_data, address, topics, data);
-        });
+        call_inspectors!(
+            inspector,
+            [&mut self.custom_print_tracer, &mut self.eip3155, &mut self.four_byte],
+            [&mut self.tracers],
+            {
+                inspector.log(evm_data, address, topics, data);
+            }
+        );
     }
 
     fn step_end(&mut self, interpreter: &mut Interpreter<'_>, data: &mut EVMData<'_, DB>) {
-        call_inspectors!(inspector, [&mut self.custom_print_tracer], {
-            inspector.step_end(interpreter, data);
-        });
+        call_inspectors!(
+            inspector,
+            [&mut self.custom_print_tracer, &mut self.eip3155, &mut self.four_byte],
+            [&mut self.tracers],
+            {
+                inspector.step_end(interpreter, data);
+            }
+        );
     }
 
     fn call(
@@ -135,14 +232,19 @@ where
         data: &mut EVMData<'_, DB>,
         inputs: &mut CallInputs,
     ) -> (InstructionResult, Gas, Bytes) {
-        call_inspectors!(inspector, [&mut self.custom_print_tracer], {
-            let (status, gas, retdata) = inspector.call(data, inputs);
+        call_inspectors!(
+            inspector,
+            [&mut self.custom_print_tracer, &mut self.eip3155, &mut self.four_byte],
+            [&mut self.tracers],
+            {
+                let (status, gas, retdata) = inspector.call(data, inputs);
 
-            // Allow inspectors to exit early
-            if status != InstructionResult::Continue {
-                return (status, gas, retdata)
+                // Allow inspectors to exit early
+                if status != InstructionResult::Continue {
+                    return (status, gas, retdata)
+                }
             }
-        });
+        );
 
         (InstructionResult::Continue, Gas::new(inputs.gas_limit), Bytes::new())
     }
@@ -155,16 +257,21 @@ where
         ret: InstructionResult,
         out: Bytes,
     ) -> (InstructionResult, Gas, Bytes) {
-        call_inspectors!(inspector, [&mut self.custom_print_tracer], {
-            let (new_ret, new_gas, new_out) =
-                inspector.call_end(data, inputs, remaining_gas, ret, out.clone());
-
-            // If the inspector returns a different ret or a revert with a non-empty message,
-            // we assume it wants to tell us something
-            if new_ret != ret || (new_ret == InstructionResult::Revert && new_out != out) {
-                return (new_ret, new_gas, new_out)
+        call_inspectors!(
+            inspector,
+            [&mut self.custom_print_tracer, &mut self.eip3155, &mut self.four_byte],
+            [&mut self.tracers],
+            {
+                let (new_ret, new_gas, new_out) =
+                    inspector.call_end(data, inputs, remaining_gas, ret, out.clone());
+
+                // If the inspector returns a different ret or a revert with a non-empty message,
+                // we assume it wants to tell us something
+                if new_ret != ret || (new_ret == InstructionResult::Revert && new_out != out) {
+                    return (new_ret, new_gas, new_out)
+                }
             }
-        });
+        );
 
         (ret, remaining_gas, out)
     }
@@ -174,14 +281,19 @@ where
         data: &mut EVMData<'_, DB>,
         inputs: &mut CreateInputs,
     ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
-        call_inspectors!(inspector, [&mut self.custom_print_tracer], {
-            let (status, addr, gas, retdata) = inspector.create(data, inputs);
+        call_inspectors!(
+            inspector,
+            [&mut self.custom_print_tracer, &mut self.eip3155, &mut self.four_byte],
+            [&mut self.tracers],
+            {
+                let (status, addr, gas, retdata) = inspector.create(data, inputs);
 
-            // Allow inspectors to exit early
-            if status != InstructionResult::Continue {
-                return (status, addr, gas, retdata)
+                // Allow inspectors to exit early
+                if status != InstructionResult::Continue {
+                    return (status, addr, gas, retdata)
+                }
             }
-        });
+        );
 
         (InstructionResult::Continue, None, Gas::new(inputs.gas_limit), Bytes::new())
     }
@@ -195,21 +307,31 @@ where
         remaining_gas: Gas,
         out: Bytes,
     ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
-        call_inspectors!(inspector, [&mut self.custom_print_tracer], {
-            let (new_ret, new_address, new_gas, new_retdata) =
-                inspector.create_end(data, inputs, ret, address, remaining_gas, out.clone());
+        call_inspectors!(
+            inspector,
+            [&mut self.custom_print_tracer, &mut self.eip3155, &mut self.four_byte],
+            [&mut self.tracers],
+            {
+                let (new_ret, new_address, new_gas, new_retdata) =
+                    inspector.create_end(data, inputs, ret, address, remaining_gas, out.clone());
 
-            if new_ret != ret {
-                return (new_ret, new_address, new_gas, new_retdata)
+                if new_ret != ret {
+                    return (new_ret, new_address, new_gas, new_retdata)
+                }
             }
-        });
+        );
 
         (ret, address, remaining_gas, out)
     }
 
     fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
-        call_inspectors!(inspector, [&mut self.custom_print_tracer], {
-            Inspector::<DB>::selfdestruct(inspector, contract, target, value);
-        });
+        call_inspectors!(
+            inspector,
+            [&mut self.custom_print_tracer, &mut self.eip3155, &mut self.four_byte],
+            [&mut self.tracers],
+            {
+                Inspector::<DB>::selfdestruct(inspector, contract, target, value);
+            }
+        );
     }
 }
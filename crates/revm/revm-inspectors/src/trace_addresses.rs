@@ -0,0 +1,73 @@
+use reth_primitives::{Address, Bytes, U256};
+use revm::{
+    interpreter::{CallInputs, CreateInputs, Gas, InstructionResult},
+    Database, EVMData, Inspector,
+};
+use std::collections::BTreeSet;
+
+/// An [Inspector] that collects all addresses that appear as `from` or `to` of a call, create or
+/// selfdestruct trace.
+///
+/// These are exactly the addresses a parity `trace_filter` can match on, which makes this
+/// inspector suitable for building an address to block index.
+#[derive(Default, Debug, Clone)]
+pub struct TraceAddressInspector {
+    /// All addresses that participated in a call, create or selfdestruct.
+    addresses: BTreeSet<Address>,
+}
+
+impl TraceAddressInspector {
+    /// Returns all collected addresses.
+    pub fn addresses(&self) -> &BTreeSet<Address> {
+        &self.addresses
+    }
+
+    /// Consumes the inspector and returns all collected addresses.
+    pub fn into_addresses(self) -> BTreeSet<Address> {
+        self.addresses
+    }
+
+    /// Clears all collected addresses.
+    pub fn clear(&mut self) {
+        self.addresses.clear();
+    }
+}
+
+impl<DB> Inspector<DB> for TraceAddressInspector
+where
+    DB: Database,
+{
+    fn call(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        inputs: &mut CallInputs,
+    ) -> (InstructionResult, Gas, Bytes) {
+        // delegate calls and callcodes are traced from the executing contract to the code address,
+        // so record all of them
+        self.addresses.insert(inputs.context.caller);
+        self.addresses.insert(inputs.context.address);
+        self.addresses.insert(inputs.context.code_address);
+
+        (InstructionResult::Continue, Gas::new(0), Bytes::new())
+    }
+
+    fn create_end(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        inputs: &CreateInputs,
+        status: InstructionResult,
+        address: Option<Address>,
+        gas: Gas,
+        retdata: Bytes,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        self.addresses.insert(inputs.caller);
+        self.addresses.extend(address);
+
+        (status, address, gas, retdata)
+    }
+
+    fn selfdestruct(&mut self, contract: Address, target: Address, _value: U256) {
+        self.addresses.insert(contract);
+        self.addresses.insert(target);
+    }
+}
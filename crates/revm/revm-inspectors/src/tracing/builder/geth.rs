@@ -1,14 +1,19 @@
 //! Geth trace builder
 
 use crate::tracing::{
+    config::TraceStyle,
     types::{CallTraceNode, CallTraceStepStackItem},
     utils::load_account_code,
-    TracingInspectorConfig,
+    ParityTraceBuilder, TracingInspectorConfig,
 };
 use reth_primitives::{Address, Bytes, B256, U256};
-use reth_rpc_types::trace::geth::{
-    AccountChangeKind, AccountState, CallConfig, CallFrame, DefaultFrame, DiffMode,
-    GethDefaultTracingOptions, PreStateConfig, PreStateFrame, PreStateMode, StructLog,
+use reth_rpc_types::{
+    trace::geth::{
+        AccountChangeKind, AccountState, CallConfig, CallFrame, DefaultFrame, DiffMode,
+        FlatCallConfig, FlatCallFrame, GethDefaultTracingOptions, PreStateConfig, PreStateFrame,
+        PreStateMode, StructLog,
+    },
+    TransactionInfo,
 };
 use revm::{db::DatabaseRef, primitives::ResultAndState};
 use std::collections::{btree_map::Entry, BTreeMap, HashMap, VecDeque};
@@ -169,6 +174,28 @@ impl GethTraceBuilder {
         }
     }
 
+    /// Generate the traces for the flat call tracer.
+    ///
+    /// These are the parity style traces of all recorded calls. Calls to precompiles are only
+    /// included if they were recorded, see
+    /// [TracingInspectorConfig::exclude_precompile_calls].
+    ///
+    /// This expects the gas used of the
+    /// [ExecutionResult](revm::primitives::ExecutionResult) of the executed transaction.
+    pub fn geth_flat_call_traces(
+        &self,
+        opts: FlatCallConfig,
+        info: TransactionInfo,
+        gas_used: u64,
+    ) -> FlatCallFrame {
+        let error_style =
+            if opts.is_convert_parity_errors() { TraceStyle::Parity } else { TraceStyle::Geth };
+        ParityTraceBuilder::new(self.nodes.clone(), None, self._config)
+            .with_error_style(error_style)
+            .with_transaction_gas_used(gas_used)
+            .into_localized_transaction_traces(info)
+    }
+
     ///  Returns the accounts necessary for transaction execution.
     ///
     /// The prestate mode returns the accounts necessary to execute a given transaction.
@@ -1,5 +1,6 @@
 use super::walker::CallTraceNodeWalkerBF;
 use crate::tracing::{
+    config::TraceStyle,
     types::{CallTraceNode, CallTraceStep},
     utils::load_account_code,
     TracingInspectorConfig,
@@ -22,6 +23,8 @@ pub struct ParityTraceBuilder {
     nodes: Vec<CallTraceNode>,
     /// The spec id of the EVM.
     spec_id: Option<SpecId>,
+    /// How error messages of the call traces are formatted.
+    error_style: TraceStyle,
 
     /// How the traces were recorded
     _config: TracingInspectorConfig,
@@ -34,7 +37,15 @@ impl ParityTraceBuilder {
         spec_id: Option<SpecId>,
         _config: TracingInspectorConfig,
     ) -> Self {
-        Self { nodes, spec_id, _config }
+        Self { nodes, spec_id, error_style: TraceStyle::Parity, _config }
+    }
+
+    /// Sets how error messages of the call traces are formatted.
+    ///
+    /// Parity style error messages are used by default.
+    pub(crate) fn with_error_style(mut self, error_style: TraceStyle) -> Self {
+        self.error_style = error_style;
+        self
     }
 
     /// Returns a list of all addresses that appeared as callers.
@@ -237,7 +248,7 @@ impl ParityTraceBuilder {
             let trace_address = self.trace_address(node.idx);
 
             if with_traces {
-                let trace = node.parity_transaction_trace(trace_address);
+                let trace = node.parity_transaction_trace(trace_address, self.error_style);
                 traces.push(trace);
 
                 // check if the trace node is a selfdestruct
@@ -270,6 +281,7 @@ impl ParityTraceBuilder {
     /// Returns an iterator over all recorded traces  for `trace_transaction`
     pub fn into_transaction_traces_iter(self) -> impl Iterator<Item = TransactionTrace> {
         let trace_addresses = self.trace_addresses();
+        let error_style = self.error_style;
         TransactionTraceIter {
             next_selfdestruct: None,
             iter: self
@@ -277,7 +289,9 @@ impl ParityTraceBuilder {
                 .into_iter()
                 .zip(trace_addresses)
                 .filter(|(node, _)| !node.is_precompile())
-                .map(|(node, trace_address)| (node.parity_transaction_trace(trace_address), node)),
+                .map(|(node, trace_address)| {
+                    (node.parity_transaction_trace(trace_address, error_style), node)
+                }),
         }
     }
 
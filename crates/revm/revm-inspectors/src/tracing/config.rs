@@ -8,7 +8,6 @@ pub(crate) enum TraceStyle {
     /// Parity style tracer
     Parity,
     /// Geth style tracer
-    #[allow(unused)]
     Geth,
 }
 
@@ -0,0 +1,353 @@
+//! EIP-3155 tracing inspector
+//!
+//! [EIP-3155](https://eips.ethereum.org/EIPS/eip-3155) defines a trace format of JSON lines, one
+//! for every executed opcode followed by a summary of the transaction. It's supported by most
+//! clients, which makes it useful to diff the execution of a transaction between them. For
+//! example:
+//!
+//! ```json
+//! {"pc":0,"op":96,"gas":"0x13498","gasCost":"0x3","memSize":0,"stack":[],"depth":1,"returnData":"0x","refund":"0x0","opName":"PUSH1"}
+//! {"pc":2,"op":0,"gas":"0x13495","gasCost":"0x0","memSize":0,"stack":["0x1"],"depth":1,"returnData":"0x","refund":"0x0","opName":"STOP"}
+//! {"output":"0x","gasUsed":"0x5208","pass":true}
+//! ```
+//!
+//! See also <https://eips.ethereum.org/EIPS/eip-3155>
+
+use reth_primitives::{Address, Bytes, B256, U256, U64};
+use revm::{
+    inspectors::GasInspector,
+    interpreter::{CallInputs, CreateInputs, Gas, InstructionResult, Interpreter, OpCode},
+    primitives::ExecutionResult,
+    Database, EVMData, Inspector,
+};
+use serde::Serialize;
+use std::io::{self, Write};
+
+/// An inspector that writes an [EIP-3155](https://eips.ethereum.org/EIPS/eip-3155) trace of the
+/// executed opcodes to a [Write]r.
+///
+/// Every step is written as soon as its gas cost is known, so the trace is never kept in memory. A
+/// step that enters a new call frame is written before the first step of that frame, with the gas
+/// forwarded to the frame as its gas cost.
+///
+/// Because inspector hooks can't fail, the first error of the writer is kept and returned by
+/// [Eip3155Inspector::write_summary], which is expected to be called after the transaction was
+/// executed.
+#[derive(Debug)]
+pub struct Eip3155Inspector<W> {
+    /// The writer the trace is written to.
+    writer: W,
+    /// Whether to include the memory in every step.
+    include_memory: bool,
+    /// Whether to include the stack in every step.
+    include_stack: bool,
+    /// The gas inspector used to track remaining gas.
+    gas_inspector: GasInspector,
+    /// The steps that were started but not written yet, one for every active call frame.
+    ///
+    /// This is `None` if the step of the frame was already written because it entered a new
+    /// frame.
+    pending_steps: Vec<Option<PendingStep>>,
+    /// The first error that occurred while writing the trace.
+    error: Option<io::Error>,
+}
+
+// === impl Eip3155Inspector ===
+
+impl<W: Write> Eip3155Inspector<W> {
+    /// Returns a new inspector that writes the trace to the given writer.
+    ///
+    /// By default, the stack is included in every step and the memory is not.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            include_memory: false,
+            include_stack: true,
+            gas_inspector: Default::default(),
+            pending_steps: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Configures whether the memory is included in every step.
+    pub fn with_memory(mut self, include_memory: bool) -> Self {
+        self.include_memory = include_memory;
+        self
+    }
+
+    /// Configures whether the stack is included in every step.
+    pub fn with_stack(mut self, include_stack: bool) -> Self {
+        self.include_stack = include_stack;
+        self
+    }
+
+    /// Writes the summary line for the result of the executed transaction and flushes the writer.
+    ///
+    /// The state root after the transaction is optional, since it's usually not computed when
+    /// replaying transactions.
+    ///
+    /// Returns the first error that occurred while writing the trace, if any.
+    pub fn write_summary(
+        &mut self,
+        result: &ExecutionResult,
+        state_root: Option<B256>,
+    ) -> io::Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err)
+        }
+
+        let summary = Summary {
+            state_root,
+            output: result.output().cloned().unwrap_or_default(),
+            gas_used: U64::from(result.gas_used()),
+            pass: result.is_success(),
+        };
+        self.write_line(&summary)?;
+        self.writer.flush()
+    }
+
+    /// Consumes the inspector and returns the writer.
+    pub fn into_writer(self) -> W {
+        self.writer
+    }
+
+    /// Writes the pending step of the current call frame, if it wasn't written yet.
+    ///
+    /// Invoked when the step enters a new call frame, the step's gas cost is the gas forwarded to
+    /// the frame.
+    fn write_pending_step(&mut self, gas_cost: u64) {
+        if let Some(PendingStep { mut step, .. }) =
+            self.pending_steps.last_mut().and_then(Option::take)
+        {
+            step.gas_cost = U64::from(gas_cost);
+            self.write_step(&step);
+        }
+    }
+
+    /// Writes the step, unless writing the trace already failed.
+    fn write_step(&mut self, step: &Step) {
+        if self.error.is_none() {
+            if let Err(err) = self.write_line(step) {
+                self.error = Some(err);
+            }
+        }
+    }
+
+    /// Writes the value as a single JSON line.
+    fn write_line<T: Serialize>(&mut self, value: &T) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, value)?;
+        self.writer.write_all(b"\n")
+    }
+}
+
+impl<DB, W> Inspector<DB> for Eip3155Inspector<W>
+where
+    DB: Database,
+    W: Write,
+{
+    fn initialize_interp(&mut self, interp: &mut Interpreter<'_>, data: &mut EVMData<'_, DB>) {
+        self.gas_inspector.initialize_interp(interp, data)
+    }
+
+    fn step(&mut self, interp: &mut Interpreter<'_>, data: &mut EVMData<'_, DB>) {
+        self.gas_inspector.step(interp, data);
+
+        let op = interp.current_opcode();
+        let memory = interp.shared_memory.context_memory();
+        let gas_remaining = self.gas_inspector.gas_remaining();
+        let step = Step {
+            pc: interp.program_counter() as u64,
+            op,
+            gas: U64::from(gas_remaining),
+            // filled when the step is written
+            gas_cost: U64::ZERO,
+            memory: self.include_memory.then(|| Bytes::copy_from_slice(memory)),
+            mem_size: memory.len() as u64,
+            stack: self.include_stack.then(|| interp.stack.data().clone()),
+            depth: data.journaled_state.depth(),
+            return_data: interp.return_data_buffer.clone(),
+            refund: U64::from(interp.gas.refunded() as u64),
+            op_name: OpCode::new(op)
+                .map(|op| op.to_string())
+                .unwrap_or_else(|| format!("opcode {op:#04x} not defined")),
+            error: None,
+        };
+        self.pending_steps.push(Some(PendingStep { step, gas_remaining }));
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter<'_>, data: &mut EVMData<'_, DB>) {
+        self.gas_inspector.step_end(interp, data);
+
+        let Some(PendingStep { mut step, gas_remaining }) = self.pending_steps.pop().flatten()
+        else {
+            // the step entered a new call frame and was already written
+            return
+        };
+        step.gas_cost = U64::from(gas_remaining.saturating_sub(self.gas_inspector.gas_remaining()));
+        if interp.instruction_result as u8 >= InstructionResult::Revert as u8 {
+            step.error = Some(format!("{:?}", interp.instruction_result));
+        }
+        self.write_step(&step);
+    }
+
+    fn call(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &mut CallInputs,
+    ) -> (InstructionResult, Gas, Bytes) {
+        self.gas_inspector.call(data, inputs);
+        self.write_pending_step(inputs.gas_limit);
+
+        (InstructionResult::Continue, Gas::new(inputs.gas_limit), Bytes::new())
+    }
+
+    fn call_end(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &CallInputs,
+        gas: Gas,
+        ret: InstructionResult,
+        out: Bytes,
+    ) -> (InstructionResult, Gas, Bytes) {
+        self.gas_inspector.call_end(data, inputs, gas, ret, out.clone());
+
+        (ret, gas, out)
+    }
+
+    fn create(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &mut CreateInputs,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        self.gas_inspector.create(data, inputs);
+        self.write_pending_step(inputs.gas_limit);
+
+        (InstructionResult::Continue, None, Gas::new(inputs.gas_limit), Bytes::new())
+    }
+
+    fn create_end(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &CreateInputs,
+        status: InstructionResult,
+        address: Option<Address>,
+        gas: Gas,
+        retdata: Bytes,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        self.gas_inspector.create_end(data, inputs, status, address, gas, retdata.clone());
+
+        (status, address, gas, retdata)
+    }
+}
+
+/// A step that was started but not written yet.
+#[derive(Debug)]
+struct PendingStep {
+    /// The step, without its gas cost.
+    step: Step,
+    /// Remaining gas before the step was executed.
+    gas_remaining: u64,
+}
+
+/// A single line of an EIP-3155 trace, representing an executed opcode.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Step {
+    /// Program counter
+    pc: u64,
+    /// Opcode to be executed
+    op: u8,
+    /// Remaining gas before the opcode is executed
+    gas: U64,
+    /// Gas cost of the opcode
+    gas_cost: U64,
+    /// Memory of the current call frame, if enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<Bytes>,
+    /// Size of the memory of the current call frame
+    mem_size: u64,
+    /// Stack of the current call frame, if enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stack: Option<Vec<U256>>,
+    /// Depth of the call stack, starting at 1
+    depth: u64,
+    /// Data returned by the last call of the current call frame
+    return_data: Bytes,
+    /// Global gas refund counter
+    refund: U64,
+    /// Name of the opcode
+    op_name: String,
+    /// Error of the opcode, if it failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// The summary line that ends an EIP-3155 trace of a transaction.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Summary {
+    /// State root after the transaction, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state_root: Option<B256>,
+    /// Output of the transaction
+    output: Bytes,
+    /// Gas used by the transaction
+    gas_used: U64,
+    /// Whether the transaction succeeded
+    pass: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::keccak256;
+    use revm::{
+        db::{CacheDB, EmptyDB},
+        primitives::{AccountInfo, Bytecode, TransactTo},
+        EVM,
+    };
+    use serde_json::{json, Value};
+
+    #[test]
+    fn writes_step_and_summary_lines() {
+        // PUSH1 0x01 PUSH1 0x02 ADD STOP
+        let code = Bytes::from_static(&[0x60, 0x01, 0x60, 0x02, 0x01, 0x00]);
+        let contract = Address::with_last_byte(1);
+
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            contract,
+            AccountInfo {
+                balance: U256::ZERO,
+                nonce: 1,
+                code_hash: keccak256(&code),
+                code: Some(Bytecode::new_raw(code)),
+            },
+        );
+
+        let mut evm = EVM::new();
+        evm.database(db);
+        evm.env.tx.transact_to = TransactTo::Call(contract);
+        evm.env.tx.gas_limit = 100_000;
+
+        let mut inspector = Eip3155Inspector::new(Vec::new());
+        let res = evm.inspect(&mut inspector).unwrap();
+        inspector.write_summary(&res.result, None).unwrap();
+
+        let trace = String::from_utf8(inspector.into_writer()).unwrap();
+        let lines =
+            trace.lines().map(|line| serde_json::from_str(line).unwrap()).collect::<Vec<Value>>();
+
+        // the intrinsic gas is spent before the first step
+        assert_eq!(
+            lines,
+            vec![
+                json!({"pc":0,"op":0x60,"gas":"0x13498","gasCost":"0x3","memSize":0,"stack":[],"depth":1,"returnData":"0x","refund":"0x0","opName":"PUSH1"}),
+                json!({"pc":2,"op":0x60,"gas":"0x13495","gasCost":"0x3","memSize":0,"stack":["0x1"],"depth":1,"returnData":"0x","refund":"0x0","opName":"PUSH1"}),
+                json!({"pc":4,"op":0x01,"gas":"0x13492","gasCost":"0x3","memSize":0,"stack":["0x1","0x2"],"depth":1,"returnData":"0x","refund":"0x0","opName":"ADD"}),
+                json!({"pc":5,"op":0x00,"gas":"0x1348f","gasCost":"0x0","memSize":0,"stack":["0x3"],"depth":1,"returnData":"0x","refund":"0x0","opName":"STOP"}),
+                json!({"output":"0x","gasUsed":"0x5211","pass":true}),
+            ]
+        );
+    }
+}
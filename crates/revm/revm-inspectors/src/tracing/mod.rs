@@ -18,7 +18,9 @@ use types::{CallTrace, CallTraceStep};
 mod arena;
 mod builder;
 mod config;
+mod eip3155;
 mod fourbyte;
+mod mux;
 mod opcount;
 mod types;
 mod utils;
@@ -32,7 +34,9 @@ pub use builder::{
     parity::{self, ParityTraceBuilder},
 };
 pub use config::TracingInspectorConfig;
+pub use eip3155::Eip3155Inspector;
 pub use fourbyte::FourByteInspector;
+pub use mux::{MuxError, MuxInspector};
 pub use opcount::OpcodeCountInspector;
 
 #[cfg(feature = "js-tracer")]
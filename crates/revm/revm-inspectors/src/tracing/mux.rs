@@ -0,0 +1,377 @@
+//! Mux tracing inspector
+//!
+//! The muxTracer runs multiple built-in tracers in one go. It's configured with a map of tracers
+//! to their config and returns a map of tracers to their result. For example:
+//!
+//! ```json
+//! {
+//!   "tracer": "muxTracer",
+//!   "tracerConfig": {
+//!     "4byteTracer": {},
+//!     "callTracer": { "onlyTopCall": true }
+//!   }
+//! }
+//! ```
+//!
+//! See also <https://geth.ethereum.org/docs/developers/evm-tracing/built-in-tracers>
+
+use crate::{
+    stack::InspectorStack,
+    tracing::{FourByteInspector, TracingInspector, TracingInspectorConfig},
+};
+use reth_primitives::{Address, Bytes, B256, U256};
+use reth_rpc_types::{
+    trace::geth::{
+        CallConfig, FlatCallConfig, FourByteFrame, GethDebugBuiltInTracerType, MuxConfig, MuxFrame,
+        NoopFrame, PreStateConfig,
+    },
+    TransactionInfo,
+};
+use revm::{
+    db::DatabaseRef,
+    interpreter::{CallInputs, CreateInputs, Gas, InstructionResult, Interpreter},
+    primitives::ResultAndState,
+    Database, EVMData, Inspector,
+};
+use std::{collections::HashMap, fmt};
+
+/// An inspector that runs the inspectors of all tracers configured by a [MuxConfig] on an
+/// [InspectorStack], so that a single execution of the transaction yields the frames of all of
+/// them.
+///
+/// Every tracer that is built from recorded call traces gets its own [TracingInspector] in the
+/// stack, which records exactly what the tracer needs.
+#[derive(Debug, Default)]
+pub struct MuxInspector {
+    /// The stack that runs the inspectors of all tracers.
+    stack: InspectorStack,
+    /// Config of the `callTracer` and the index of its inspector in the stack.
+    call: Option<(usize, CallConfig)>,
+    /// Config of the `prestateTracer` and the index of its inspector in the stack.
+    pre_state: Option<(usize, PreStateConfig)>,
+    /// Config of the `flatCallTracer` and the index of its inspector in the stack.
+    flat_call: Option<(usize, FlatCallConfig)>,
+    /// Whether the `noopTracer` is configured.
+    noop: bool,
+}
+
+impl MuxInspector {
+    /// Creates the inspectors for all tracers of the given [MuxConfig].
+    pub fn try_from_config(config: MuxConfig) -> Result<Self, MuxError> {
+        let mut mux = Self::default();
+
+        for (tracer, config) in config.0 {
+            let config = config.unwrap_or_default();
+            let invalid_config = |_| MuxError::InvalidTracerConfig(tracer.clone());
+            match tracer {
+                GethDebugBuiltInTracerType::FourByteTracer => {
+                    mux.stack.four_byte = Some(FourByteInspector::default());
+                }
+                GethDebugBuiltInTracerType::CallTracer => {
+                    let call_config = config.into_call_config().map_err(invalid_config)?;
+                    let index = mux.push_tracer(
+                        TracingInspectorConfig::default_parity()
+                            .set_exclude_precompile_calls(false)
+                            .set_record_logs(call_config.with_log.unwrap_or_default()),
+                    );
+                    mux.call = Some((index, call_config));
+                }
+                GethDebugBuiltInTracerType::PreStateTracer => {
+                    let prestate_config = config.into_pre_state_config().map_err(invalid_config)?;
+                    let index = mux.push_tracer(
+                        TracingInspectorConfig::default_parity()
+                            .set_exclude_precompile_calls(false)
+                            // if in default mode, we need to return all touched storages, for
+                            // which we need to record steps and statediff
+                            .set_steps_and_state_diffs(prestate_config.is_default_mode()),
+                    );
+                    mux.pre_state = Some((index, prestate_config));
+                }
+                GethDebugBuiltInTracerType::FlatCallTracer => {
+                    let flat_call_config =
+                        config.into_flat_call_config().map_err(invalid_config)?;
+                    let index = mux.push_tracer(
+                        TracingInspectorConfig::default_parity().set_exclude_precompile_calls(
+                            !flat_call_config.is_include_precompiles(),
+                        ),
+                    );
+                    mux.flat_call = Some((index, flat_call_config));
+                }
+                GethDebugBuiltInTracerType::NoopTracer => {
+                    mux.noop = true;
+                }
+                GethDebugBuiltInTracerType::MuxTracer => return Err(MuxError::NestedMuxTracer),
+            }
+        }
+
+        Ok(mux)
+    }
+
+    /// Adds a [TracingInspector] with the given config to the stack and returns its index.
+    fn push_tracer(&mut self, config: TracingInspectorConfig) -> usize {
+        self.stack.tracers.push(TracingInspector::new(config));
+        self.stack.tracers.len() - 1
+    }
+
+    /// Consumes the inspector and returns the frames of all configured tracers.
+    ///
+    /// This expects the result of the executed transaction and the database the transaction was
+    /// executed on, see also [GethTraceBuilder](crate::tracing::GethTraceBuilder).
+    pub fn try_into_mux_frame<DB: DatabaseRef>(
+        self,
+        result: &ResultAndState,
+        db: DB,
+        info: TransactionInfo,
+    ) -> Result<MuxFrame, DB::Error> {
+        let gas_used = result.result.gas_used();
+        let mut tracers = self.stack.tracers.into_iter().map(Some).collect::<Vec<_>>();
+        let mut take_tracer =
+            |index: usize| tracers[index].take().expect("every tracer has its own inspector");
+        let mut frames = HashMap::new();
+
+        if let Some(inspector) = self.stack.four_byte {
+            frames.insert(
+                GethDebugBuiltInTracerType::FourByteTracer,
+                FourByteFrame::from(inspector).into(),
+            );
+        }
+        if let Some((index, config)) = self.call {
+            let frame = take_tracer(index).into_geth_builder().geth_call_traces(config, gas_used);
+            frames.insert(GethDebugBuiltInTracerType::CallTracer, frame.into());
+        }
+        if let Some((index, config)) = self.pre_state {
+            let frame =
+                take_tracer(index).into_geth_builder().geth_prestate_traces(result, config, db)?;
+            frames.insert(GethDebugBuiltInTracerType::PreStateTracer, frame.into());
+        }
+        if let Some((index, config)) = self.flat_call {
+            let frame = take_tracer(index)
+                .into_geth_builder()
+                .geth_flat_call_traces(config, info, gas_used);
+            frames.insert(GethDebugBuiltInTracerType::FlatCallTracer, frame.into());
+        }
+        if self.noop {
+            frames.insert(GethDebugBuiltInTracerType::NoopTracer, NoopFrame::default().into());
+        }
+
+        Ok(MuxFrame(frames))
+    }
+}
+
+impl<DB> Inspector<DB> for MuxInspector
+where
+    DB: Database,
+{
+    fn initialize_interp(&mut self, interpreter: &mut Interpreter<'_>, data: &mut EVMData<'_, DB>) {
+        self.stack.initialize_interp(interpreter, data)
+    }
+
+    fn step(&mut self, interpreter: &mut Interpreter<'_>, data: &mut EVMData<'_, DB>) {
+        self.stack.step(interpreter, data)
+    }
+
+    fn log(
+        &mut self,
+        evm_data: &mut EVMData<'_, DB>,
+        address: &Address,
+        topics: &[B256],
+        data: &Bytes,
+    ) {
+        self.stack.log(evm_data, address, topics, data)
+    }
+
+    fn step_end(&mut self, interpreter: &mut Interpreter<'_>, data: &mut EVMData<'_, DB>) {
+        self.stack.step_end(interpreter, data)
+    }
+
+    fn call(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &mut CallInputs,
+    ) -> (InstructionResult, Gas, Bytes) {
+        self.stack.call(data, inputs)
+    }
+
+    fn call_end(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &CallInputs,
+        remaining_gas: Gas,
+        ret: InstructionResult,
+        out: Bytes,
+    ) -> (InstructionResult, Gas, Bytes) {
+        self.stack.call_end(data, inputs, remaining_gas, ret, out)
+    }
+
+    fn create(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &mut CreateInputs,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        self.stack.create(data, inputs)
+    }
+
+    fn create_end(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &CreateInputs,
+        ret: InstructionResult,
+        address: Option<Address>,
+        remaining_gas: Gas,
+        out: Bytes,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        self.stack.create_end(data, inputs, ret, address, remaining_gas, out)
+    }
+
+    fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        Inspector::<DB>::selfdestruct(&mut self.stack, contract, target, value)
+    }
+}
+
+/// Errors that can occur when configuring a [MuxInspector].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MuxError {
+    /// The config of the tracer could not be parsed.
+    InvalidTracerConfig(GethDebugBuiltInTracerType),
+    /// The mux tracer can't be nested.
+    NestedMuxTracer,
+}
+
+impl fmt::Display for MuxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidTracerConfig(tracer) => write!(f, "invalid config for {tracer:?}"),
+            Self::NestedMuxTracer => f.write_str("muxTracer can't be nested"),
+        }
+    }
+}
+
+impl std::error::Error for MuxError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::keccak256;
+    use reth_rpc_types::trace::geth::{GethDefaultTracingOptions, GethTrace};
+    use revm::{
+        db::{CacheDB, EmptyDB},
+        primitives::{AccountInfo, Bytecode, TransactTo},
+        EVM,
+    };
+    use serde_json::json;
+
+    /// Returns a database with a contract that writes to its storage, calls a second contract
+    /// that writes to its own storage, and emits a log.
+    fn create_db() -> (CacheDB<EmptyDB>, Address) {
+        let caller_code = Bytes::from_static(&[
+            // SSTORE(0, 1)
+            0x60, 0x01, 0x60, 0x00, 0x55, //
+            // MSTORE(0, 0xdeadbeef << 224)
+            0x63, 0xde, 0xad, 0xbe, 0xef, 0x60, 0xe0, 0x1b, 0x60, 0x00, 0x52, //
+            // POP(CALL(GAS, 0x02, 0, 0, 4, 0, 0))
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x04, 0x60, 0x00, 0x60, 0x00, 0x60, 0x02, 0x5a, 0xf1,
+            0x50, //
+            // LOG0(0, 0) STOP
+            0x60, 0x00, 0x60, 0x00, 0xa0, 0x00,
+        ]);
+        // SSTORE(0, 42) STOP
+        let callee_code = Bytes::from_static(&[0x60, 0x2a, 0x60, 0x00, 0x55, 0x00]);
+
+        let mut db = CacheDB::new(EmptyDB::default());
+        for (address, code) in [(1, caller_code), (2, callee_code)] {
+            db.insert_account_info(
+                Address::with_last_byte(address),
+                AccountInfo {
+                    balance: U256::ZERO,
+                    nonce: 1,
+                    code_hash: keccak256(&code),
+                    code: Some(Bytecode::new_raw(code)),
+                },
+            );
+        }
+        (db, Address::with_last_byte(1))
+    }
+
+    /// Executes the call to the contract with the inspector.
+    fn inspect<I: Inspector<CacheDB<EmptyDB>>>(
+        db: &CacheDB<EmptyDB>,
+        contract: Address,
+        inspector: &mut I,
+    ) -> ResultAndState {
+        let mut evm = EVM::new();
+        evm.database(db.clone());
+        evm.env.tx.transact_to = TransactTo::Call(contract);
+        evm.env.tx.data = Bytes::from_static(&[0x12, 0x34, 0x56, 0x78]);
+        evm.env.tx.gas_limit = 100_000;
+        evm.inspect(inspector).unwrap()
+    }
+
+    #[test]
+    fn mux_matches_individual_tracers() {
+        let (db, contract) = create_db();
+        let call_config = CallConfig { with_log: Some(true), ..Default::default() };
+
+        // run every tracer on its own, like `debug_traceTransaction` does
+        let geth_config =
+            TracingInspectorConfig::from_geth_config(&GethDefaultTracingOptions::default());
+        let mut four_byte = FourByteInspector::default();
+        inspect(&db, contract, &mut four_byte);
+
+        let mut call = TracingInspector::new(geth_config.set_record_logs(true));
+        let res = inspect(&db, contract, &mut call);
+        let call_frame =
+            call.into_geth_builder().geth_call_traces(call_config.clone(), res.result.gas_used());
+
+        let mut pre_state = TracingInspector::new(geth_config.set_steps_and_state_diffs(true));
+        let res = inspect(&db, contract, &mut pre_state);
+        let pre_state_frame = pre_state
+            .into_geth_builder()
+            .geth_prestate_traces(&res, PreStateConfig::default(), &db)
+            .unwrap();
+
+        let mut flat_call = TracingInspector::new(
+            TracingInspectorConfig::default_parity().set_exclude_precompile_calls(true),
+        );
+        let res = inspect(&db, contract, &mut flat_call);
+        let flat_call_frame = flat_call.into_geth_builder().geth_flat_call_traces(
+            FlatCallConfig::default(),
+            TransactionInfo::default(),
+            res.result.gas_used(),
+        );
+
+        // run all of them at once
+        let config = MuxConfig(HashMap::from([
+            (GethDebugBuiltInTracerType::FourByteTracer, None),
+            (GethDebugBuiltInTracerType::CallTracer, Some(json!({ "withLog": true }).into())),
+            (GethDebugBuiltInTracerType::PreStateTracer, None),
+            (GethDebugBuiltInTracerType::FlatCallTracer, None),
+            (GethDebugBuiltInTracerType::NoopTracer, None),
+        ]));
+        let mut mux = MuxInspector::try_from_config(config).unwrap();
+        let res = inspect(&db, contract, &mut mux);
+        let frame = mux.try_into_mux_frame(&res, &db, TransactionInfo::default()).unwrap();
+
+        let expected: HashMap<_, GethTrace> = HashMap::from([
+            (GethDebugBuiltInTracerType::FourByteTracer, FourByteFrame::from(four_byte).into()),
+            (GethDebugBuiltInTracerType::CallTracer, call_frame.into()),
+            (GethDebugBuiltInTracerType::PreStateTracer, pre_state_frame.into()),
+            (GethDebugBuiltInTracerType::FlatCallTracer, flat_call_frame.into()),
+            (GethDebugBuiltInTracerType::NoopTracer, NoopFrame::default().into()),
+        ]);
+        assert_eq!(frame.0, expected);
+
+        // the call to the second contract and the log are traced
+        let GethTrace::CallTracer(call_frame) = &frame.0[&GethDebugBuiltInTracerType::CallTracer]
+        else {
+            panic!("expected a call frame")
+        };
+        assert_eq!(call_frame.calls.len(), 1);
+        assert_eq!(call_frame.logs.len(), 1);
+    }
+
+    #[test]
+    fn rejects_nested_mux() {
+        let config = MuxConfig(HashMap::from([(GethDebugBuiltInTracerType::MuxTracer, None)]));
+        assert_eq!(MuxInspector::try_from_config(config).unwrap_err(), MuxError::NestedMuxTracer);
+    }
+}
@@ -331,7 +331,13 @@ impl CallTraceNode {
     }
 
     /// Converts this node into a parity `TransactionTrace`
-    pub(crate) fn parity_transaction_trace(&self, trace_address: Vec<usize>) -> TransactionTrace {
+    ///
+    /// The error message is formatted according to the given [TraceStyle].
+    pub(crate) fn parity_transaction_trace(
+        &self,
+        trace_address: Vec<usize>,
+        error_style: TraceStyle,
+    ) -> TransactionTrace {
         let action = self.parity_action();
         let result = if self.trace.is_error() && !self.trace.is_revert() {
             // if the trace is a selfdestruct or an error that is not a revert, the result is None
@@ -339,7 +345,7 @@ impl CallTraceNode {
         } else {
             Some(self.parity_trace_output())
         };
-        let error = self.trace.as_error_msg(TraceStyle::Parity);
+        let error = self.trace.as_error_msg(error_style);
         TransactionTrace { action, error, result, trace_address, subtraces: self.children.len() }
     }
 
@@ -6,7 +6,7 @@ use crate::{
 };
 use reth_interfaces::{
     executor::{BlockExecutionError, BlockValidationError},
-    RethError,
+    RethError, RethResult,
 };
 use reth_primitives::{
     revm::env::{fill_cfg_and_block_env, fill_tx_env},
@@ -14,21 +14,20 @@ use reth_primitives::{
     PruneModes, PruneSegmentError, Receipt, ReceiptWithBloom, Receipts, TransactionSigned, B256,
     MINIMUM_PRUNING_DISTANCE, U256,
 };
-use reth_provider::{BlockExecutor, BlockExecutorStats, PrunableBlockExecutor, StateProvider};
+use reth_provider::{
+    BlockExecutor, BlockExecutorStats, BundleStateWithReceipts, PrunableBlockExecutor,
+    StateProvider, StateRootProvider,
+};
 use revm::{
     db::{states::bundle_state::BundleRetention, StateDBBox},
     primitives::ResultAndState,
-    State, EVM,
+    DatabaseCommit, State, EVM,
 };
 use std::{sync::Arc, time::Instant};
 
 #[cfg(not(feature = "optimism"))]
 use reth_primitives::revm::compat::into_reth_log;
 #[cfg(not(feature = "optimism"))]
-use reth_provider::BundleStateWithReceipts;
-#[cfg(not(feature = "optimism"))]
-use revm::DatabaseCommit;
-#[cfg(not(feature = "optimism"))]
 use tracing::{debug, trace};
 
 /// EVMProcessor is a block executor that uses revm to execute blocks or multiple blocks.
@@ -267,7 +266,15 @@ impl<'a> EVMProcessor<'a> {
         let hash = transaction.hash();
         let out = if self.stack.should_inspect(&self.evm.env, hash) {
             // execution with inspector.
+            if let Err(err) = self.stack.start_transaction(&self.evm.env, hash) {
+                tracing::warn!(target: "evm", ?hash, %err, "Failed to start inspecting transaction");
+            }
             let output = self.evm.inspect(&mut self.stack);
+            if let Ok(ResultAndState { result, .. }) = &output {
+                if let Err(err) = self.stack.end_transaction(result) {
+                    tracing::warn!(target: "evm", ?hash, %err, "Failed to finish inspecting transaction");
+                }
+            }
             tracing::trace!(
                 target: "evm",
                 ?hash, ?output, ?transaction, env = ?self.evm.env,
@@ -281,6 +288,48 @@ impl<'a> EVMProcessor<'a> {
         out.map_err(|e| BlockValidationError::EVM { hash, error: e.into() }.into())
     }
 
+    /// Executes the block and returns the state root after each of its transactions.
+    ///
+    /// The roots are computed by the given [StateRootProvider] from the state changes made since
+    /// the start of the block, so it must be backed by the same state as the processor's database.
+    ///
+    /// NOTE: Unlike geth, the root after the last transaction includes the post-block state
+    /// changes, i.e. block rewards and withdrawals, which makes it equal to the block's state root.
+    pub fn execute_and_compute_intermediate_roots(
+        &mut self,
+        block: &Block,
+        total_difficulty: U256,
+        senders: Option<Vec<Address>>,
+        state_root_provider: impl StateRootProvider,
+    ) -> RethResult<Vec<B256>> {
+        self.init_env(&block.header, total_difficulty);
+        self.apply_beacon_root_contract_call(block)?;
+
+        let senders = self.recover_senders(&block.body, senders)?;
+
+        let mut roots = Vec::with_capacity(block.body.len());
+        let mut transactions = block.body.iter().zip(senders).peekable();
+        while let Some((transaction, sender)) = transactions.next() {
+            let ResultAndState { state, .. } = self.transact(transaction, sender)?;
+            self.db_mut().commit(state);
+
+            if transactions.peek().is_none() {
+                self.apply_post_execution_state_change(block, total_difficulty)?;
+            }
+
+            // the bundle state accumulates all changes made since the start of the block
+            self.db_mut().merge_transitions(BundleRetention::PlainState);
+            let bundle_state = BundleStateWithReceipts::new(
+                self.db_mut().bundle_state.clone(),
+                Receipts::default(),
+                block.number,
+            );
+            roots.push(state_root_provider.state_root(&bundle_state)?);
+        }
+
+        Ok(roots)
+    }
+
     /// Execute the block, verify gas usage and apply post-block state changes.
     pub(crate) fn execute_inner(
         &mut self,
@@ -562,8 +611,11 @@ mod tests {
         bytes,
         constants::{BEACON_ROOTS_ADDRESS, SYSTEM_ADDRESS},
         keccak256,
+        proofs::genesis_state_root,
+        revm::compat::into_reth_acc,
         trie::AccountProof,
-        Account, Bytecode, Bytes, ChainSpecBuilder, ForkCondition, StorageKey, MAINNET,
+        Account, Bytecode, Bytes, ChainSpecBuilder, ForkCondition, GenesisAccount, Signature,
+        StorageKey, Transaction, TransactionKind, TxLegacy, Withdrawal, MAINNET,
     };
     use reth_provider::{
         AccountReader, BlockHashReader, BundleStateWithReceipts, StateRootProvider,
@@ -596,6 +648,32 @@ mod tests {
             }
             self.accounts.insert(address, (storage, account));
         }
+
+        /// Computes the state root of the accounts.
+        fn root(&self) -> B256 {
+            let alloc = self
+                .accounts
+                .iter()
+                .map(|(address, (storage, account))| {
+                    let account = GenesisAccount {
+                        nonce: Some(account.nonce),
+                        balance: account.balance,
+                        code: account
+                            .bytecode_hash
+                            .and_then(|hash| self.contracts.get(&hash))
+                            .map(|code| code.original_bytes()),
+                        storage: Some(
+                            storage
+                                .iter()
+                                .map(|(slot, value)| (*slot, B256::from(*value)))
+                                .collect(),
+                        ),
+                    };
+                    (*address, account)
+                })
+                .collect();
+            genesis_state_root(&alloc)
+        }
     }
 
     impl AccountReader for StateProviderTest {
@@ -625,8 +703,29 @@ mod tests {
     }
 
     impl StateRootProvider for StateProviderTest {
-        fn state_root(&self, _bundle_state: &BundleStateWithReceipts) -> RethResult<B256> {
-            unimplemented!("state root computation is not supported")
+        fn state_root(&self, bundle_state: &BundleStateWithReceipts) -> RethResult<B256> {
+            let mut state = self.clone();
+            for (address, account) in bundle_state.state().state() {
+                let Some(info) = &account.info else {
+                    state.accounts.remove(address);
+                    continue
+                };
+                let (storage, reth_account) = state.accounts.entry(*address).or_default();
+                if account.status.was_destroyed() {
+                    storage.clear();
+                }
+                storage.extend(
+                    account
+                        .storage
+                        .iter()
+                        .map(|(slot, value)| (B256::from(*slot), value.present_value)),
+                );
+                *reth_account = into_reth_acc(info.clone());
+            }
+            for (hash, code) in &bundle_state.state().contracts {
+                state.contracts.insert(*hash, Bytecode(code.clone()));
+            }
+            Ok(state.root())
         }
     }
 
@@ -649,6 +748,23 @@ mod tests {
         fn proof(&self, _address: Address, _keys: &[B256]) -> RethResult<AccountProof> {
             unimplemented!("proof generation is not supported")
         }
+
+        fn account_range(
+            &self,
+            _start: Address,
+            _limit: usize,
+        ) -> RethResult<(Vec<(Address, Account)>, Option<Address>)> {
+            unimplemented!("account ranges are not supported")
+        }
+
+        fn storage_range(
+            &self,
+            _address: Address,
+            _start: StorageKey,
+            _limit: usize,
+        ) -> RethResult<(Vec<reth_primitives::StorageEntry>, Option<StorageKey>)> {
+            unimplemented!("storage ranges are not supported")
+        }
     }
 
     #[test]
@@ -972,4 +1088,102 @@ mod tests {
             .unwrap();
         assert_eq!(parent_beacon_block_root_storage, U256::from(0x69));
     }
+
+    #[test]
+    fn intermediate_roots() {
+        let sender = Address::with_last_byte(1);
+        let recipient = Address::with_last_byte(2);
+        let withdrawal_recipient = Address::with_last_byte(3);
+        let initial_balance = U256::from(10).pow(U256::from(18));
+
+        let mut db = StateProviderTest::default();
+        db.insert_account(
+            sender,
+            Account { balance: initial_balance, ..Default::default() },
+            None,
+            HashMap::new(),
+        );
+
+        let chain_spec = Arc::new(ChainSpecBuilder::from(&*MAINNET).shanghai_activated().build());
+
+        let transfer = |nonce| {
+            TransactionSigned::from_transaction_and_signature(
+                Transaction::Legacy(TxLegacy {
+                    chain_id: Some(chain_spec.chain.id()),
+                    nonce,
+                    gas_price: 0,
+                    gas_limit: 21_000,
+                    to: TransactionKind::Call(recipient),
+                    value: U256::from(1).into(),
+                    input: Bytes::default(),
+                }),
+                Signature::default(),
+            )
+        };
+
+        // the expected state after each transaction, the withdrawal is applied after the last one
+        let mut expected = db.clone();
+        expected.insert_account(
+            sender,
+            Account { nonce: 1, balance: initial_balance - U256::from(1), ..Default::default() },
+            None,
+            HashMap::new(),
+        );
+        expected.insert_account(
+            recipient,
+            Account { balance: U256::from(1), ..Default::default() },
+            None,
+            HashMap::new(),
+        );
+        let first_root = expected.root();
+        expected.insert_account(
+            sender,
+            Account { nonce: 2, balance: initial_balance - U256::from(2), ..Default::default() },
+            None,
+            HashMap::new(),
+        );
+        expected.insert_account(
+            recipient,
+            Account { balance: U256::from(2), ..Default::default() },
+            None,
+            HashMap::new(),
+        );
+        expected.insert_account(
+            withdrawal_recipient,
+            Account { balance: U256::from(1_000_000_000), ..Default::default() },
+            None,
+            HashMap::new(),
+        );
+
+        let block = Block {
+            header: Header {
+                number: 1,
+                gas_limit: 30_000_000,
+                base_fee_per_gas: Some(0),
+                state_root: expected.root(),
+                ..Header::default()
+            },
+            body: vec![transfer(0), transfer(1)],
+            ommers: vec![],
+            withdrawals: Some(vec![Withdrawal {
+                index: 0,
+                validator_index: 0,
+                address: withdrawal_recipient,
+                amount: 1,
+            }]),
+        };
+
+        let mut executor =
+            EVMProcessor::new_with_db(chain_spec.clone(), StateProviderDatabase::new(&db));
+        let roots = executor
+            .execute_and_compute_intermediate_roots(
+                &block,
+                U256::ZERO,
+                Some(vec![sender, sender]),
+                &db,
+            )
+            .unwrap();
+
+        assert_eq!(roots, vec![first_root, block.header.state_root]);
+    }
 }
@@ -28,6 +28,25 @@ pub trait AdminApi {
     #[method(name = "removeTrustedPeer")]
     fn remove_trusted_peer(&self, record: NodeRecord) -> RpcResult<bool>;
 
+    /// Returns the directives of the filter applied to the node's logs.
+    #[method(name = "logFilter")]
+    fn log_filter(&self) -> RpcResult<String>;
+
+    /// Replaces the filter applied to the node's logs with the given directives, e.g.
+    /// `info,net::session=trace`, keeping the overrides set with `debug_verbosity` and
+    /// `debug_vmodule`.
+    ///
+    /// Returns true if the filter was replaced.
+    #[method(name = "setLogFilter")]
+    fn set_log_filter(&self, filter: String) -> RpcResult<bool>;
+
+    /// Reverts all changes made to the filter of the node's logs, restoring the filter the node
+    /// was started with.
+    ///
+    /// Returns true if the filter was restored.
+    #[method(name = "resetLogFilter")]
+    fn reset_log_filter(&self) -> RpcResult<bool>;
+
     /// The peers administrative property can be queried for all the information known about the
     /// connected remote nodes at the networking granularity. These include general information
     /// about the nodes themselves as participants of the devp2p P2P overlay protocol, as well as
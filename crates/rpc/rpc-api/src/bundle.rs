@@ -1,14 +1,10 @@
 use jsonrpsee::proc_macros::rpc;
 use reth_primitives::{Bytes, B256};
 use reth_rpc_types::{
-    CancelBundleRequest, CancelPrivateTransactionRequest, EthBundleHash, EthCallBundleResponse,
-    EthCallBundleTransactionResult, EthSendBundle, PrivateTransactionRequest,
+    CancelBundleRequest, CancelPrivateTransactionRequest, EthBundleHash, EthCallBundle,
+    EthCallBundleResponse, EthSendBundle, PrivateTransactionRequest,
 };
 
-/// Eth bundle rpc interface.
-///
-/// See also <https://docs.flashbots.net/flashbots-auction/searchers/advanced/rpc-endpoint>
-
 /// Eth bundle rpc interface.
 ///
 /// See also <https://docs.flashbots.net/flashbots-auction/searchers/advanced/rpc-endpoint>
@@ -26,8 +22,8 @@ pub trait EthBundleApi {
     #[method(name = "callBundle")]
     async fn call_bundle(
         &self,
-        request: EthCallBundleResponse,
-    ) -> jsonrpsee::core::RpcResult<EthCallBundleTransactionResult>;
+        request: EthCallBundle,
+    ) -> jsonrpsee::core::RpcResult<EthCallBundleResponse>;
 
     /// `eth_cancelBundle` is used to prevent a submitted bundle from being included on-chain. See [bundle cancellations](https://docs.flashbots.net/flashbots-auction/searchers/advanced/bundle-cancellations) for more information.
     #[method(name = "cancelBundle")]
@@ -3,9 +3,9 @@ use reth_primitives::{Address, BlockId, BlockNumberOrTag, Bytes, B256};
 use reth_rpc_types::{
     trace::geth::{
         BlockTraceResult, GethDebugTracingCallOptions, GethDebugTracingOptions, GethTrace,
-        TraceResult,
+        StandardTraceConfig, TraceResult,
     },
-    Bundle, CallRequest, RichBlock, StateContext,
+    AccountRange, BadBlock, Bundle, CallRequest, StateContext, StorageRangeResult,
 };
 
 /// Debug rpc interface.
@@ -29,17 +29,29 @@ pub trait DebugApi {
     async fn raw_receipts(&self, block_id: BlockId) -> RpcResult<Vec<Bytes>>;
 
     /// Returns an array of recent bad blocks that the client has seen on the network.
+    ///
+    /// Each entry contains both the RLP encoded and the decoded block, together with the error
+    /// the block was rejected with.
     #[method(name = "getBadBlocks")]
-    async fn bad_blocks(&self) -> RpcResult<Vec<RichBlock>>;
+    async fn bad_blocks(&self) -> RpcResult<Vec<BadBlock>>;
 
     /// Returns the structured logs created during the execution of EVM between two blocks
     /// (excluding start) as a JSON object.
-    #[method(name = "traceChain")]
+    ///
+    /// This is a subscription that emits one [BlockTraceResult] per block in ascending order, and
+    /// is therefore only available on transports that support subscriptions (ws, ipc). If a block
+    /// can't be traced, its result contains a single error trace and the subscription is closed.
+    #[subscription(
+        name = "traceChain" => "subscription",
+        unsubscribe = "unsubscribeTraceChain",
+        item = BlockTraceResult
+    )]
     async fn debug_trace_chain(
         &self,
         start_exclusive: BlockNumberOrTag,
         end_inclusive: BlockNumberOrTag,
-    ) -> RpcResult<Vec<BlockTraceResult>>;
+        opts: Option<GethDebugTracingOptions>,
+    ) -> jsonrpsee::core::SubscriptionResult;
 
     /// The `debug_traceBlock` method will return a full stack trace of all invoked opcodes of all
     /// transaction that were included in this block.
@@ -133,10 +145,11 @@ pub trait DebugApi {
     async fn debug_backtrace_at(&self, location: &str) -> RpcResult<()>;
 
     /// Enumerates all accounts at a given block with paging capability. `maxResults` are returned
-    /// in the page and the items have keys that come after the `start` key (hashed address).
+    /// in the page and the items have keys that come after the `start` key.
     ///
-    /// If incompletes is false, then accounts for which the key preimage (i.e: the address) doesn't
-    /// exist in db are skipped. NB: geth by default does not store preimages.
+    /// Unlike geth, accounts are paged in order of their address, so `start` is an address (or a
+    /// prefix of one) rather than a hashed address. Since the address of every account is known,
+    /// `incompletes` has no effect.
     #[method(name = "accountRange")]
     async fn debug_account_range(
         &self,
@@ -146,7 +159,7 @@ pub trait DebugApi {
         nocode: bool,
         nostorage: bool,
         incompletes: bool,
-    ) -> RpcResult<()>;
+    ) -> RpcResult<AccountRange>;
 
     /// Turns on block profiling for the given duration and writes profile data to disk. It uses a
     /// profile rate of 1 for most accurate information. If a different rate is desired, set the
@@ -213,21 +226,26 @@ pub trait DebugApi {
     /// Returns all accounts that have changed between the two blocks specified. A change is defined
     /// as a difference in nonce, balance, code hash, or storage hash. With one parameter, returns
     /// the list of accounts modified in the specified block.
+    ///
+    /// The changes of the start block itself are not included.
     #[method(name = "getModifiedAccountsByHash")]
     async fn debug_get_modified_accounts_by_hash(
         &self,
         start_hash: B256,
-        end_hash: B256,
-    ) -> RpcResult<()>;
+        end_hash: Option<B256>,
+    ) -> RpcResult<Vec<Address>>;
 
     /// Returns all accounts that have changed between the two blocks specified. A change is defined
-    /// as a difference in nonce, balance, code hash or storage hash.
+    /// as a difference in nonce, balance, code hash or storage hash. With one parameter, returns
+    /// the list of accounts modified in the specified block.
+    ///
+    /// The changes of the start block itself are not included.
     #[method(name = "getModifiedAccountsByNumber")]
     async fn debug_get_modified_accounts_by_number(
         &self,
         start_number: u64,
-        end_number: u64,
-    ) -> RpcResult<()>;
+        end_number: Option<u64>,
+    ) -> RpcResult<Vec<Address>>;
 
     /// Turns on Go runtime tracing for the given duration and writes trace data to disk.
     #[method(name = "goTrace")]
@@ -235,12 +253,15 @@ pub trait DebugApi {
 
     /// Executes a block (bad- or canon- or side-), and returns a list of intermediate roots: the
     /// stateroot after each transaction.
+    ///
+    /// Unlike geth, the root after the last transaction includes the block rewards and
+    /// withdrawals, so it's equal to the state root of the block if it's valid.
     #[method(name = "intermediateRoots")]
     async fn debug_intermediate_roots(
         &self,
         block_hash: B256,
         opts: Option<GethDebugTracingCallOptions>,
-    ) -> RpcResult<()>;
+    ) -> RpcResult<Vec<B256>>;
 
     /// Returns detailed runtime memory statistics.
     #[method(name = "memStats")]
@@ -293,22 +314,25 @@ pub trait DebugApi {
     #[method(name = "stacks")]
     async fn debug_stacks(&self) -> RpcResult<()>;
 
-    /// Used to obtain info about a block.
+    /// This method is similar to `debug_standardTraceBlockToFile`, but can be used to obtain info
+    /// about a block which has been rejected as invalid (for some reason).
     #[method(name = "standardTraceBadBlockToFile")]
     async fn debug_standard_trace_bad_block_to_file(
         &self,
-        block: BlockNumberOrTag,
-        opts: Option<GethDebugTracingCallOptions>,
-    ) -> RpcResult<()>;
+        block_hash: B256,
+        opts: Option<StandardTraceConfig>,
+    ) -> RpcResult<Vec<String>>;
 
-    /// This method is similar to `debug_standardTraceBlockToFile`, but can be used to obtain info
-    /// about a block which has been rejected as invalid (for some reason).
+    /// Replays the block with the given hash and writes an EIP-3155 trace of each of its
+    /// transactions to a file in the temporary directory.
+    ///
+    /// Returns the paths of the written files.
     #[method(name = "standardTraceBlockToFile")]
     async fn debug_standard_trace_block_to_file(
         &self,
-        block: BlockNumberOrTag,
-        opts: Option<GethDebugTracingCallOptions>,
-    ) -> RpcResult<()>;
+        block_hash: B256,
+        opts: Option<StandardTraceConfig>,
+    ) -> RpcResult<Vec<String>>;
 
     /// Turns on CPU profiling indefinitely, writing to the given file.
     #[method(name = "startCPUProfile")]
@@ -328,7 +352,10 @@ pub trait DebugApi {
 
     /// Returns the storage at the given block height and transaction index. The result can be
     /// paged by providing a `maxResult` to cap the number of storage slots returned as well as
-    /// specifying the offset via `keyStart` (hash of storage key).
+    /// specifying the offset via `keyStart`.
+    ///
+    /// Unlike geth, storage slots are paged in order of their key, so `keyStart` and the returned
+    /// `nextKey` are storage keys rather than hashed storage keys.
     #[method(name = "storageRangeAt")]
     async fn debug_storage_range_at(
         &self,
@@ -337,7 +364,7 @@ pub trait DebugApi {
         contract_address: Address,
         key_start: B256,
         max_result: u64,
-    ) -> RpcResult<()>;
+    ) -> RpcResult<StorageRangeResult>;
 
     /// Returns the structured logs created during the execution of EVM against a block pulled
     /// from the pool of bad ones and returns them as a JSON object. For the second parameter see
@@ -351,10 +378,16 @@ pub trait DebugApi {
 
     /// Sets the logging verbosity ceiling. Log messages with level up to and including the given
     /// level will be printed.
+    ///
+    /// The levels are `0` (silent), `1` (error), `2` (warn), `3` (info), `4` (debug) and `5`
+    /// (trace).
     #[method(name = "verbosity")]
     async fn debug_verbosity(&self, level: usize) -> RpcResult<()>;
 
-    /// Sets the logging verbosity pattern.
+    /// Sets the logging verbosity pattern, made of comma separated per-target directives such as
+    /// `net::session=trace,sync=debug`, which take precedence over the verbosity ceiling.
+    ///
+    /// This replaces the previously set pattern, an empty pattern removes it.
     #[method(name = "vmodule")]
     async fn debug_vmodule(&self, pattern: String) -> RpcResult<()>;
 
@@ -72,6 +72,22 @@ pub trait TraceApi {
     #[method(name = "filter")]
     async fn trace_filter(&self, filter: TraceFilter) -> RpcResult<Vec<LocalizedTransactionTrace>>;
 
+    /// Streams the traces matching the given filter.
+    ///
+    /// This is the subscription variant of `trace_filter`, it emits the same traces in the same
+    /// order, honouring `after` and `count`, without limiting the number of traced blocks or
+    /// returned traces. It is therefore only available on transports that support subscriptions
+    /// (ws, ipc). If a block can't be traced, the subscription is closed.
+    #[subscription(
+        name = "subscribeFilter" => "subscription",
+        unsubscribe = "unsubscribeFilter",
+        item = LocalizedTransactionTrace
+    )]
+    async fn trace_subscribe_filter(
+        &self,
+        filter: TraceFilter,
+    ) -> jsonrpsee::core::SubscriptionResult;
+
     /// Returns transaction trace at given index.
     ///
     /// `indices` represent the index positions of the traces.
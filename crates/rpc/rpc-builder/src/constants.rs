@@ -16,6 +16,9 @@ pub const DEFAULT_MAX_LOGS_PER_RESPONSE: usize = 20_000;
 /// The default maximum number of concurrently executed tracing calls
 pub const DEFAULT_MAX_TRACING_REQUESTS: u32 = 25;
 
+/// The default maximum number of blocks a `debug_traceChain` subscription traces concurrently
+pub const DEFAULT_TRACE_CHAIN_WINDOW: usize = 16;
+
 /// The default IPC endpoint
 #[cfg(windows)]
 pub const DEFAULT_IPC_ENDPOINT: &str = r"\\.\pipe\reth.ipc";
@@ -1,15 +1,17 @@
 use crate::constants::{
     DEFAULT_MAX_BLOCKS_PER_FILTER, DEFAULT_MAX_LOGS_PER_RESPONSE, DEFAULT_MAX_TRACING_REQUESTS,
+    DEFAULT_TRACE_CHAIN_WINDOW,
 };
 use reth_rpc::{
     eth::{
         cache::{EthStateCache, EthStateCacheConfig},
         gas_oracle::GasPriceOracleConfig,
-        EthFilterConfig, RPC_DEFAULT_GAS_CAP,
+        EthFilterConfig, DEFAULT_ETH_PROOF_WINDOW, RPC_DEFAULT_GAS_CAP,
     },
     BlockingTaskPool, EthApi, EthFilter, EthPubSub,
 };
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 /// All handlers for the `eth` namespace
 #[derive(Debug, Clone)]
@@ -35,6 +37,11 @@ pub struct EthConfig {
     pub gas_oracle: GasPriceOracleConfig,
     /// The maximum number of tracing calls that can be executed in concurrently.
     pub max_tracing_requests: u32,
+    /// Maximum number of blocks a `debug_traceChain` subscription traces concurrently and buffers
+    /// before they are sent to the subscriber.
+    pub trace_chain_window: usize,
+    /// Directory the `debug` profiling methods write to, profiling is disabled if unset.
+    pub profile_dir: Option<PathBuf>,
     /// Maximum number of blocks that could be scanned per filter request in `eth_getLogs` calls.
     pub max_blocks_per_filter: u64,
     /// Maximum number of logs that can be returned in a single response in `eth_getLogs` calls.
@@ -43,6 +50,10 @@ pub struct EthConfig {
     ///
     /// Defaults to [RPC_DEFAULT_GAS_CAP]
     pub rpc_gas_cap: u64,
+    /// Maximum number of blocks `eth_getProof` can look back from the latest block.
+    ///
+    /// Defaults to [DEFAULT_ETH_PROOF_WINDOW]
+    pub eth_proof_window: u64,
     ///
     /// Sets TTL for stale filters
     pub stale_filter_ttl: std::time::Duration,
@@ -67,9 +78,12 @@ impl Default for EthConfig {
             cache: EthStateCacheConfig::default(),
             gas_oracle: GasPriceOracleConfig::default(),
             max_tracing_requests: DEFAULT_MAX_TRACING_REQUESTS,
+            trace_chain_window: DEFAULT_TRACE_CHAIN_WINDOW,
+            profile_dir: None,
             max_blocks_per_filter: DEFAULT_MAX_BLOCKS_PER_FILTER,
             max_logs_per_response: DEFAULT_MAX_LOGS_PER_RESPONSE,
             rpc_gas_cap: RPC_DEFAULT_GAS_CAP.into(),
+            eth_proof_window: DEFAULT_ETH_PROOF_WINDOW,
             stale_filter_ttl: DEFAULT_STALE_FILTER_TTL,
         }
     }
@@ -94,6 +108,18 @@ impl EthConfig {
         self
     }
 
+    /// Configures the maximum number of blocks in flight per `debug_traceChain` subscription
+    pub fn trace_chain_window(mut self, window: usize) -> Self {
+        self.trace_chain_window = window;
+        self
+    }
+
+    /// Configures the directory the `debug` profiling methods write to
+    pub fn profile_dir(mut self, profile_dir: Option<PathBuf>) -> Self {
+        self.profile_dir = profile_dir;
+        self
+    }
+
     /// Configures the maximum block length to scan per `eth_getLogs` request
     pub fn max_blocks_per_filter(mut self, max_blocks: u64) -> Self {
         self.max_blocks_per_filter = max_blocks;
@@ -111,4 +137,10 @@ impl EthConfig {
         self.rpc_gas_cap = rpc_gas_cap;
         self
     }
+
+    /// Configures the maximum number of blocks `eth_getProof` can look back
+    pub fn eth_proof_window(mut self, eth_proof_window: u64) -> Self {
+        self.eth_proof_window = eth_proof_window;
+        self
+    }
 }
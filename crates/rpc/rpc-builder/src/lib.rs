@@ -19,8 +19,8 @@
 //! ```
 //! use reth_network_api::{NetworkInfo, Peers};
 //! use reth_provider::{
-//!     AccountReader, BlockReaderIdExt, CanonStateSubscriptions, ChainSpecProvider,
-//!     ChangeSetReader, EvmEnvProvider, StateProviderFactory,
+//!     AccountReader, BadBlockReader, BlockReaderIdExt, CanonStateSubscriptions,
+//!     ChainSpecProvider, ChangeSetReader, EvmEnvProvider, HistoryReader, StateProviderFactory,
 //! };
 //! use reth_rpc_builder::{
 //!     RethRpcModule, RpcModuleBuilder, RpcServerConfig, ServerBuilder, TransportRpcModuleConfig,
@@ -37,6 +37,8 @@
 //!         + BlockReaderIdExt
 //!         + ChainSpecProvider
 //!         + ChangeSetReader
+//!         + HistoryReader
+//!         + BadBlockReader
 //!         + StateProviderFactory
 //!         + EvmEnvProvider
 //!         + Clone
@@ -70,8 +72,8 @@
 //! ```
 //! use reth_network_api::{NetworkInfo, Peers};
 //! use reth_provider::{
-//!     AccountReader, BlockReaderIdExt, CanonStateSubscriptions, ChainSpecProvider,
-//!     ChangeSetReader, EvmEnvProvider, StateProviderFactory,
+//!     AccountReader, BadBlockReader, BlockReaderIdExt, CanonStateSubscriptions,
+//!     ChainSpecProvider, ChangeSetReader, EvmEnvProvider, HistoryReader, StateProviderFactory,
 //! };
 //! use reth_rpc::JwtSecret;
 //! use reth_rpc_api::EngineApiServer;
@@ -93,6 +95,8 @@
 //!         + BlockReaderIdExt
 //!         + ChainSpecProvider
 //!         + ChangeSetReader
+//!         + HistoryReader
+//!         + BadBlockReader
 //!         + StateProviderFactory
 //!         + EvmEnvProvider
 //!         + Clone
@@ -148,9 +152,10 @@ use jsonrpsee::{
 };
 use reth_ipc::server::IpcServer;
 use reth_network_api::{NetworkInfo, Peers};
+use reth_payload_builder::bundle::BundlePool;
 use reth_provider::{
-    AccountReader, BlockReader, BlockReaderIdExt, CanonStateSubscriptions, ChainSpecProvider,
-    ChangeSetReader, EvmEnvProvider, StateProviderFactory,
+    AccountReader, BadBlockReader, BlockReader, BlockReaderIdExt, CanonStateSubscriptions,
+    ChainSpecProvider, ChangeSetReader, EvmEnvProvider, HistoryReader, StateProviderFactory,
 };
 use reth_rpc::{
     eth::{
@@ -158,8 +163,8 @@ use reth_rpc::{
         gas_oracle::GasPriceOracle,
     },
     AdminApi, AuthLayer, BlockingTaskGuard, BlockingTaskPool, Claims, DebugApi, EngineEthApi,
-    EthApi, EthFilter, EthPubSub, EthSubscriptionIdProvider, JwtAuthValidator, JwtSecret, NetApi,
-    OtterscanApi, RPCApi, RethApi, TraceApi, TxPoolApi, Web3Api,
+    EthApi, EthFilter, EthPubSub, EthSubscriptionIdProvider, JwtAuthValidator, JwtSecret, MevApi,
+    MevBundlePool, NetApi, OtterscanApi, RPCApi, RethApi, TraceApi, TxPoolApi, Web3Api,
 };
 use reth_rpc_api::{servers::*, EngineApiServer};
 use reth_tasks::{TaskSpawner, TokioTaskExecutor};
@@ -220,6 +225,8 @@ where
         + EvmEnvProvider
         + ChainSpecProvider
         + ChangeSetReader
+        + HistoryReader
+        + BadBlockReader
         + Clone
         + Unpin
         + 'static,
@@ -251,6 +258,8 @@ pub struct RpcModuleBuilder<Provider, Pool, Network, Tasks, Events> {
     executor: Tasks,
     /// Provides access to chain events, such as new blocks, required by pubsub.
     events: Events,
+    /// The pool bundles sent via `eth_sendBundle` are added to.
+    bundle_pool: BundlePool,
 }
 
 // === impl RpcBuilder ===
@@ -266,7 +275,7 @@ impl<Provider, Pool, Network, Tasks, Events>
         executor: Tasks,
         events: Events,
     ) -> Self {
-        Self { provider, pool, network, executor, events }
+        Self { provider, pool, network, executor, events, bundle_pool: Default::default() }
     }
 
     /// Configure the provider instance.
@@ -274,8 +283,8 @@ impl<Provider, Pool, Network, Tasks, Events>
     where
         P: BlockReader + StateProviderFactory + EvmEnvProvider + 'static,
     {
-        let Self { pool, network, executor, events, .. } = self;
-        RpcModuleBuilder { provider, network, pool, executor, events }
+        let Self { pool, network, executor, events, bundle_pool, .. } = self;
+        RpcModuleBuilder { provider, network, pool, executor, events, bundle_pool }
     }
 
     /// Configure the transaction pool instance.
@@ -283,8 +292,8 @@ impl<Provider, Pool, Network, Tasks, Events>
     where
         P: TransactionPool + 'static,
     {
-        let Self { provider, network, executor, events, .. } = self;
-        RpcModuleBuilder { provider, network, pool, executor, events }
+        let Self { provider, network, executor, events, bundle_pool, .. } = self;
+        RpcModuleBuilder { provider, network, pool, executor, events, bundle_pool }
     }
 
     /// Configure a [NoopTransactionPool] instance.
@@ -295,13 +304,14 @@ impl<Provider, Pool, Network, Tasks, Events>
     pub fn with_noop_pool(
         self,
     ) -> RpcModuleBuilder<Provider, NoopTransactionPool, Network, Tasks, Events> {
-        let Self { provider, executor, events, network, .. } = self;
+        let Self { provider, executor, events, network, bundle_pool, .. } = self;
         RpcModuleBuilder {
             provider,
             executor,
             events,
             network,
             pool: NoopTransactionPool::default(),
+            bundle_pool,
         }
     }
 
@@ -310,8 +320,8 @@ impl<Provider, Pool, Network, Tasks, Events>
     where
         N: NetworkInfo + Peers + 'static,
     {
-        let Self { provider, pool, executor, events, .. } = self;
-        RpcModuleBuilder { provider, network, pool, executor, events }
+        let Self { provider, pool, executor, events, bundle_pool, .. } = self;
+        RpcModuleBuilder { provider, network, pool, executor, events, bundle_pool }
     }
 
     /// Configure a [NoopNetwork] instance.
@@ -320,8 +330,15 @@ impl<Provider, Pool, Network, Tasks, Events>
     /// This is only intended for allow easier setup of namespaces that depend on the [EthApi] which
     /// requires a [NetworkInfo] implementation.
     pub fn with_noop_network(self) -> RpcModuleBuilder<Provider, Pool, NoopNetwork, Tasks, Events> {
-        let Self { provider, pool, executor, events, .. } = self;
-        RpcModuleBuilder { provider, pool, executor, events, network: NoopNetwork::default() }
+        let Self { provider, pool, executor, events, bundle_pool, .. } = self;
+        RpcModuleBuilder {
+            provider,
+            pool,
+            executor,
+            events,
+            network: NoopNetwork::default(),
+            bundle_pool,
+        }
     }
 
     /// Configure the task executor to use for additional tasks.
@@ -332,8 +349,8 @@ impl<Provider, Pool, Network, Tasks, Events>
     where
         T: TaskSpawner + 'static,
     {
-        let Self { pool, network, provider, events, .. } = self;
-        RpcModuleBuilder { provider, network, pool, executor, events }
+        let Self { pool, network, provider, events, bundle_pool, .. } = self;
+        RpcModuleBuilder { provider, network, pool, executor, events, bundle_pool }
     }
 
     /// Configure [TokioTaskExecutor] as the task executor to use for additional tasks.
@@ -343,8 +360,24 @@ impl<Provider, Pool, Network, Tasks, Events>
     pub fn with_tokio_executor(
         self,
     ) -> RpcModuleBuilder<Provider, Pool, Network, TokioTaskExecutor, Events> {
-        let Self { pool, network, provider, events, .. } = self;
-        RpcModuleBuilder { provider, network, pool, events, executor: TokioTaskExecutor::default() }
+        let Self { pool, network, provider, events, bundle_pool, .. } = self;
+        RpcModuleBuilder {
+            provider,
+            network,
+            pool,
+            events,
+            executor: TokioTaskExecutor::default(),
+            bundle_pool,
+        }
+    }
+
+    /// Configure the pool bundles sent via `eth_sendBundle` are added to.
+    ///
+    /// This should be the pool of the payload builder, see
+    /// `reth_basic_payload_builder::EthereumBundlePayloadBuilder`.
+    pub fn with_bundle_pool(mut self, bundle_pool: BundlePool) -> Self {
+        self.bundle_pool = bundle_pool;
+        self
     }
 
     /// Configure the event subscriber instance
@@ -352,8 +385,8 @@ impl<Provider, Pool, Network, Tasks, Events>
     where
         E: CanonStateSubscriptions + 'static,
     {
-        let Self { provider, pool, executor, network, .. } = self;
-        RpcModuleBuilder { provider, network, pool, executor, events }
+        let Self { provider, pool, executor, network, bundle_pool, .. } = self;
+        RpcModuleBuilder { provider, network, pool, executor, events, bundle_pool }
     }
 }
 
@@ -366,6 +399,8 @@ where
         + EvmEnvProvider
         + ChainSpecProvider
         + ChangeSetReader
+        + HistoryReader
+        + BadBlockReader
         + Clone
         + Unpin
         + 'static,
@@ -393,7 +428,7 @@ where
     {
         let mut modules = TransportRpcModules::default();
 
-        let Self { provider, pool, network, executor, events } = self;
+        let Self { provider, pool, network, executor, events, bundle_pool } = self;
 
         let TransportRpcModuleConfig { http, ws, ipc, config } = module_config.clone();
 
@@ -404,7 +439,8 @@ where
             executor,
             events,
             config.unwrap_or_default(),
-        );
+        )
+        .with_bundle_pool(bundle_pool);
 
         modules.config = module_config;
         modules.http = registry.maybe_module(http.as_ref());
@@ -423,7 +459,7 @@ where
     pub fn build(self, module_config: TransportRpcModuleConfig) -> TransportRpcModules<()> {
         let mut modules = TransportRpcModules::default();
 
-        let Self { provider, pool, network, executor, events } = self;
+        let Self { provider, pool, network, executor, events, bundle_pool } = self;
 
         if !module_config.is_empty() {
             let TransportRpcModuleConfig { http, ws, ipc, config } = module_config.clone();
@@ -435,7 +471,8 @@ where
                 executor,
                 events,
                 config.unwrap_or_default(),
-            );
+            )
+            .with_bundle_pool(bundle_pool);
 
             modules.config = module_config;
             modules.http = registry.maybe_module(http.as_ref());
@@ -621,6 +658,8 @@ impl RpcModuleSelection {
             + EvmEnvProvider
             + ChainSpecProvider
             + ChangeSetReader
+            + HistoryReader
+            + BadBlockReader
             + Clone
             + Unpin
             + 'static,
@@ -732,6 +771,10 @@ pub enum RethRpcModule {
     Reth,
     /// `ots_` module
     Ots,
+    /// `mev_` module
+    Mev,
+    /// `eth_` bundle methods: `eth_sendBundle`, `eth_callBundle` and `eth_cancelBundle`
+    EthBundle,
 }
 
 // === impl RethRpcModule ===
@@ -772,6 +815,10 @@ pub struct RethModuleRegistry<Provider, Pool, Network, Tasks, Events> {
     eth: Option<EthHandlers<Provider, Pool, Network, Events>>,
     /// to put trace calls behind semaphore
     blocking_pool_guard: BlockingTaskGuard,
+    /// Bundles sent via `mev_sendBundle`
+    mev_bundle_pool: MevBundlePool,
+    /// Bundles sent via `eth_sendBundle`
+    bundle_pool: BundlePool,
     /// Contains the [Methods] of a module
     modules: HashMap<RethRpcModule, Methods>,
 }
@@ -798,6 +845,8 @@ impl<Provider, Pool, Network, Tasks, Events>
             executor,
             modules: Default::default(),
             blocking_pool_guard: BlockingTaskGuard::new(config.eth.max_tracing_requests),
+            mev_bundle_pool: Default::default(),
+            bundle_pool: Default::default(),
             config,
             events,
         }
@@ -823,6 +872,24 @@ impl<Provider, Pool, Network, Tasks, Events>
         &self.provider
     }
 
+    /// Returns a reference to the pool of bundles sent via `mev_sendBundle`
+    pub fn mev_bundle_pool(&self) -> &MevBundlePool {
+        &self.mev_bundle_pool
+    }
+
+    /// Returns a reference to the pool of bundles sent via `eth_sendBundle`
+    pub fn bundle_pool(&self) -> &BundlePool {
+        &self.bundle_pool
+    }
+
+    /// Configures the pool bundles sent via `eth_sendBundle` are added to.
+    ///
+    /// Note: this only affects modules that are created afterwards.
+    pub fn with_bundle_pool(mut self, bundle_pool: BundlePool) -> Self {
+        self.bundle_pool = bundle_pool;
+        self
+    }
+
     /// Returns all installed methods
     pub fn methods(&self) -> Vec<Methods> {
         self.modules.values().cloned().collect()
@@ -877,6 +944,8 @@ where
         + EvmEnvProvider
         + ChainSpecProvider
         + ChangeSetReader
+        + HistoryReader
+        + BadBlockReader
         + Clone
         + Unpin
         + 'static,
@@ -913,6 +982,20 @@ where
         self
     }
 
+    /// Register the `eth_` bundle methods
+    pub fn register_eth_bundle(&mut self) -> &mut Self {
+        let bundle_api = self.bundle_api();
+        self.modules.insert(RethRpcModule::EthBundle, bundle_api.into_rpc().into());
+        self
+    }
+
+    /// Register Mev Namespace
+    pub fn register_mev(&mut self) -> &mut Self {
+        let mev_api = self.mev_api();
+        self.modules.insert(RethRpcModule::Mev, mev_api.into_rpc().into());
+        self
+    }
+
     /// Configures the auth module that includes the
     ///   * `engine_` namespace
     ///   * `api_` namespace
@@ -994,14 +1077,19 @@ where
                         RethRpcModule::Admin => {
                             AdminApi::new(self.network.clone()).into_rpc().into()
                         }
-                        RethRpcModule::Debug => DebugApi::new(
-                            self.provider.clone(),
-                            eth_api.clone(),
-                            Box::new(self.executor.clone()),
-                            self.blocking_pool_guard.clone(),
-                        )
-                        .into_rpc()
-                        .into(),
+                        RethRpcModule::Debug => {
+                            let mut debug_api = DebugApi::new(
+                                self.provider.clone(),
+                                eth_api.clone(),
+                                Box::new(self.executor.clone()),
+                                self.blocking_pool_guard.clone(),
+                                self.config.eth.trace_chain_window,
+                            );
+                            if let Some(profile_dir) = &self.config.eth.profile_dir {
+                                debug_api = debug_api.with_profile_dir(profile_dir.clone());
+                            }
+                            debug_api.into_rpc().into()
+                        }
                         RethRpcModule::Eth => {
                             // merge all eth handlers
                             let mut module = eth_api.clone().into_rpc();
@@ -1016,6 +1104,7 @@ where
                         RethRpcModule::Trace => TraceApi::new(
                             self.provider.clone(),
                             eth_api.clone(),
+                            Box::new(self.executor.clone()),
                             self.blocking_pool_guard.clone(),
                         )
                         .into_rpc()
@@ -1032,12 +1121,33 @@ where
                         )
                         .into_rpc()
                         .into(),
-                        RethRpcModule::Ots => OtterscanApi::new(eth_api.clone()).into_rpc().into(),
+                        RethRpcModule::Ots => {
+                            OtterscanApi::new(self.provider.clone(), eth_api.clone())
+                                .into_rpc()
+                                .into()
+                        }
                         RethRpcModule::Reth => {
                             RethApi::new(self.provider.clone(), Box::new(self.executor.clone()))
                                 .into_rpc()
                                 .into()
                         }
+                        RethRpcModule::EthBundle => EthBundle::new(
+                            eth_api.clone(),
+                            self.blocking_pool_guard.clone(),
+                            self.bundle_pool.clone(),
+                        )
+                        .into_rpc()
+                        .into(),
+                        RethRpcModule::Mev => MevApi::new(
+                            EthBundle::new(
+                                eth_api.clone(),
+                                self.blocking_pool_guard.clone(),
+                                self.bundle_pool.clone(),
+                            ),
+                            self.mev_bundle_pool.clone(),
+                        )
+                        .into_rpc()
+                        .into(),
                     })
                     .clone()
             })
@@ -1087,6 +1197,7 @@ where
                 cache.clone(),
                 gas_oracle,
                 self.config.eth.rpc_gas_cap,
+                self.config.eth.eth_proof_window,
                 executor.clone(),
                 blocking_task_pool.clone(),
             );
@@ -1124,30 +1235,46 @@ where
     /// Instantiates TraceApi
     pub fn trace_api(&mut self) -> TraceApi<Provider, EthApi<Provider, Pool, Network>> {
         let eth = self.eth_handlers();
-        TraceApi::new(self.provider.clone(), eth.api, self.blocking_pool_guard.clone())
+        TraceApi::new(
+            self.provider.clone(),
+            eth.api,
+            Box::new(self.executor.clone()),
+            self.blocking_pool_guard.clone(),
+        )
     }
 
     /// Instantiates [EthBundle] Api
     pub fn bundle_api(&mut self) -> EthBundle<EthApi<Provider, Pool, Network>> {
         let eth_api = self.eth_api();
-        EthBundle::new(eth_api, self.blocking_pool_guard.clone())
+        EthBundle::new(eth_api, self.blocking_pool_guard.clone(), self.bundle_pool.clone())
+    }
+
+    /// Instantiates MevApi
+    pub fn mev_api(&mut self) -> MevApi<EthApi<Provider, Pool, Network>> {
+        let bundle_api = self.bundle_api();
+        MevApi::new(bundle_api, self.mev_bundle_pool.clone())
     }
 
     /// Instantiates OtterscanApi
-    pub fn otterscan_api(&mut self) -> OtterscanApi<EthApi<Provider, Pool, Network>> {
+    pub fn otterscan_api(&mut self) -> OtterscanApi<Provider, EthApi<Provider, Pool, Network>> {
         let eth_api = self.eth_api();
-        OtterscanApi::new(eth_api)
+        OtterscanApi::new(self.provider.clone(), eth_api)
     }
 
     /// Instantiates DebugApi
     pub fn debug_api(&mut self) -> DebugApi<Provider, EthApi<Provider, Pool, Network>> {
         let eth_api = self.eth_api();
-        DebugApi::new(
+        let debug_api = DebugApi::new(
             self.provider.clone(),
             eth_api,
             Box::new(self.executor.clone()),
             self.blocking_pool_guard.clone(),
-        )
+            self.config.eth.trace_chain_window,
+        );
+        match &self.config.eth.profile_dir {
+            Some(profile_dir) => debug_api.with_profile_dir(profile_dir.clone()),
+            None => debug_api,
+        }
     }
 
     /// Instantiates NetApi
@@ -2099,6 +2226,8 @@ mod tests {
                 "rpc" => RethRpcModule::Rpc,
                 "ots" => RethRpcModule::Ots,
                 "reth" => RethRpcModule::Reth,
+                "mev" => RethRpcModule::Mev,
+                "eth-bundle" => RethRpcModule::EthBundle,
             );
     }
 
@@ -9,17 +9,17 @@ use jsonrpsee::{
 };
 use reth_primitives::{
     hex_literal::hex, Address, BlockId, BlockNumberOrTag, Bytes, NodeRecord, TxHash, B256, B64,
-    U256,
+    U256, U64,
 };
 use reth_rpc_api::{
     clients::{AdminApiClient, EthApiClient},
-    DebugApiClient, EthFilterApiClient, NetApiClient, OtterscanClient, TraceApiClient,
-    Web3ApiClient,
+    DebugApiClient, EthBundleApiClient, EthFilterApiClient, NetApiClient, OtterscanClient,
+    TraceApiClient, Web3ApiClient,
 };
 use reth_rpc_builder::RethRpcModule;
 use reth_rpc_types::{
-    trace::filter::TraceFilter, CallRequest, Filter, Index, PendingTransactionFilterKind,
-    TransactionRequest,
+    trace::filter::TraceFilter, CallRequest, CancelBundleRequest, EthSendBundle, Filter, Index,
+    PendingTransactionFilterKind, TransactionRequest,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -199,7 +199,7 @@ where
     DebugApiClient::raw_block(client, block_id).await.unwrap();
     DebugApiClient::raw_transaction(client, B256::default()).await.unwrap();
     DebugApiClient::raw_receipts(client, block_id).await.unwrap();
-    assert!(is_unimplemented(DebugApiClient::bad_blocks(client).await.err().unwrap()));
+    assert!(DebugApiClient::bad_blocks(client).await.unwrap().is_empty());
 }
 
 async fn test_basic_net_calls<C>(client: &C)
@@ -267,47 +267,52 @@ where
 
     OtterscanClient::get_api_level(client).await.unwrap();
 
-    assert!(is_unimplemented(
-        OtterscanClient::get_internal_operations(client, tx_hash).await.err().unwrap()
-    ));
-    assert!(is_unimplemented(
-        OtterscanClient::get_transaction_error(client, tx_hash).await.err().unwrap()
-    ));
-    assert!(is_unimplemented(
-        OtterscanClient::trace_transaction(client, tx_hash).await.err().unwrap()
-    ));
+    assert!(OtterscanClient::get_internal_operations(client, tx_hash).await.unwrap().is_none());
+    assert!(OtterscanClient::get_transaction_error(client, tx_hash).await.unwrap().is_none());
+    assert!(OtterscanClient::trace_transaction(client, tx_hash).await.unwrap().is_none());
 
     OtterscanClient::get_block_details(client, block_number).await.unwrap();
 
     OtterscanClient::get_block_details_by_hash(client, block_hash).await.unwrap();
 
-    assert!(is_unimplemented(
-        OtterscanClient::get_block_transactions(client, block_number, page_number, page_size,)
-            .await
-            .err()
-            .unwrap()
-    ));
-    assert!(is_unimplemented(
-        OtterscanClient::search_transactions_before(client, address, block_number, page_size,)
-            .await
-            .err()
-            .unwrap()
-    ));
-    assert!(is_unimplemented(
-        OtterscanClient::search_transactions_after(client, address, block_number, page_size,)
-            .await
-            .err()
-            .unwrap()
-    ));
-    assert!(is_unimplemented(
-        OtterscanClient::get_transaction_by_sender_and_nonce(client, sender, nonce,)
-            .await
-            .err()
-            .unwrap()
-    ));
-    assert!(is_unimplemented(
-        OtterscanClient::get_contract_creator(client, address).await.err().unwrap()
-    ));
+    assert!(OtterscanClient::get_block_transactions(client, block_number, page_number, page_size)
+        .await
+        .is_err());
+
+    let txs = OtterscanClient::search_transactions_before(client, address, block_number, page_size)
+        .await
+        .unwrap();
+    assert!(txs.txs.is_empty());
+    let txs = OtterscanClient::search_transactions_after(client, address, block_number, page_size)
+        .await
+        .unwrap();
+    assert!(txs.txs.is_empty());
+
+    assert!(OtterscanClient::get_transaction_by_sender_and_nonce(client, sender, nonce)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(OtterscanClient::get_contract_creator(client, address).await.unwrap().is_none());
+}
+
+async fn test_basic_eth_bundle_calls<C>(client: &C)
+where
+    C: ClientT + SubscriptionClientT + Sync,
+{
+    // bundles must contain transactions
+    let bundle = EthSendBundle { block_number: U64::from(1), ..Default::default() };
+    assert!(EthBundleApiClient::send_bundle(client, bundle).await.is_err());
+
+    // bundles can't target blocks far ahead of the tip
+    let bundle = EthSendBundle {
+        txs: vec![Bytes::from_static(&[0x01])],
+        block_number: U64::from(u64::MAX),
+        ..Default::default()
+    };
+    assert!(EthBundleApiClient::send_bundle(client, bundle).await.is_err());
+
+    let cancel = CancelBundleRequest { replacement_uuid: "unknown".to_string() };
+    EthBundleApiClient::cancel_bundle(client, cancel).await.unwrap();
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -391,6 +396,24 @@ async fn test_call_debug_functions_ws() {
     test_basic_debug_calls(&client).await;
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_debug_trace_chain_ws() {
+    reth_tracing::init_test_tracing();
+
+    let handle = launch_ws(vec![RethRpcModule::Debug]).await;
+    let client = handle.ws_client().await.unwrap();
+
+    // the end block must come after the start block
+    let res = DebugApiClient::debug_trace_chain(
+        &client,
+        BlockNumberOrTag::Number(1),
+        BlockNumberOrTag::Number(1),
+        None,
+    )
+    .await;
+    assert!(res.is_err());
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_call_debug_functions_http_and_ws() {
     reth_tracing::init_test_tracing();
@@ -507,6 +530,15 @@ async fn test_call_otterscan_functions_http_and_ws() {
     let client = handle.http_client().unwrap();
     test_basic_otterscan_calls(&client).await;
 }
+#[tokio::test(flavor = "multi_thread")]
+async fn test_call_eth_bundle_functions_http() {
+    reth_tracing::init_test_tracing();
+
+    let handle = launch_http(vec![RethRpcModule::EthBundle]).await;
+    let client = handle.http_client().unwrap();
+    test_basic_eth_bundle_calls(&client).await;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
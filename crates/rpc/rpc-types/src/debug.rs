@@ -0,0 +1,77 @@
+use crate::RichBlock;
+use alloy_primitives::{Address, Bytes, B256, U64};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A block that was rejected by the node because it failed validation, as returned by
+/// `debug_getBadBlocks`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BadBlock {
+    /// The hash of the block
+    pub hash: B256,
+    /// The decoded block
+    pub block: RichBlock,
+    /// The RLP encoded block
+    pub rlp: Bytes,
+    /// The error the block was rejected with
+    pub validation_error: String,
+    /// The unix timestamp in seconds at which the block was rejected
+    pub rejected_at: U64,
+}
+
+/// A page of the accounts of the state at a block, as returned by `debug_accountRange`.
+///
+/// Accounts are paged in ascending order of their address.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountRange {
+    /// The state root of the block
+    pub root: B256,
+    /// The accounts of the page, by address
+    pub accounts: BTreeMap<Address, DumpAccount>,
+    /// The address to start the next page at, if there are more accounts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<Bytes>,
+}
+
+/// An account of an [AccountRange].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpAccount {
+    /// The balance of the account, as a decimal string
+    pub balance: String,
+    /// The nonce of the account
+    pub nonce: u64,
+    /// The hash of the code of the account
+    pub code_hash: B256,
+    /// The code of the account, unless it's omitted from the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<Bytes>,
+    /// The non-zero storage slots of the account, unless they're omitted from the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage: Option<BTreeMap<B256, B256>>,
+    /// The hashed address of the account, which is the key of the account in the state trie
+    pub key: B256,
+}
+
+/// A page of the storage of an account, as returned by `debug_storageRangeAt`.
+///
+/// Storage slots are paged in ascending order of their key.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageRangeResult {
+    /// The storage slots of the page, by hashed key
+    pub storage: BTreeMap<B256, StorageRangeEntry>,
+    /// The key to start the next page at, if there are more storage slots
+    pub next_key: Option<B256>,
+}
+
+/// A storage slot of a [StorageRangeResult].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageRangeEntry {
+    /// The key of the storage slot
+    pub key: Option<B256>,
+    /// The value of the storage slot
+    pub value: B256,
+}
@@ -1,5 +1,8 @@
 //! `trace_filter` types and support
-use crate::serde_helpers::num::u64_hex_or_decimal_opt;
+use crate::{
+    serde_helpers::num::u64_hex_or_decimal_opt,
+    trace::parity::{Action, TraceOutput, TransactionTrace},
+};
 use alloy_primitives::Address;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
@@ -63,19 +66,62 @@ pub struct TraceFilterMatcher {
 impl TraceFilterMatcher {
     /// Returns `true` if the given `from` and `to` addresses match this filter.
     pub fn matches(&self, from: Address, to: Option<Address>) -> bool {
+        self.matches_addresses(Some(from), to)
+    }
+
+    /// Returns `true` if the `from` and `to` addresses of the trace's action match this filter.
+    ///
+    /// The `to` address of a create is the created contract, the `from` and `to` addresses of a
+    /// selfdestruct are the destroyed contract and the refund address and a reward only has the
+    /// author as its `to` address.
+    pub fn matches_trace(&self, trace: &TransactionTrace) -> bool {
+        let (from, to) = match &trace.action {
+            Action::Call(call) => (Some(call.from), Some(call.to)),
+            Action::Create(create) => {
+                let to = match &trace.result {
+                    Some(TraceOutput::Create(output)) => Some(output.address),
+                    _ => None,
+                };
+                (Some(create.from), to)
+            }
+            Action::Selfdestruct(selfdestruct) => {
+                (Some(selfdestruct.address), Some(selfdestruct.refund_address))
+            }
+            Action::Reward(reward) => (None, Some(reward.author)),
+        };
+        self.matches_addresses(from, to)
+    }
+
+    /// Returns `true` if this filter matches all addresses.
+    pub fn matches_all(&self) -> bool {
+        self.from_addresses.is_empty() && self.to_addresses.is_empty()
+    }
+
+    /// Returns the `from` addresses of this filter.
+    pub fn from_addresses(&self) -> &HashSet<Address> {
+        &self.from_addresses
+    }
+
+    /// Returns the `to` addresses of this filter.
+    pub fn to_addresses(&self) -> &HashSet<Address> {
+        &self.to_addresses
+    }
+
+    /// Returns the filter mode.
+    pub fn mode(&self) -> TraceFilterMode {
+        self.mode
+    }
+
+    fn matches_addresses(&self, from: Option<Address>, to: Option<Address>) -> bool {
+        let from_matches = || from.map_or(false, |from| self.from_addresses.contains(&from));
+        let to_matches = || to.map_or(false, |to_addr| self.to_addresses.contains(&to_addr));
         match (self.from_addresses.is_empty(), self.to_addresses.is_empty()) {
             (true, true) => true,
-            (false, true) => self.from_addresses.contains(&from),
-            (true, false) => to.map_or(false, |to_addr| self.to_addresses.contains(&to_addr)),
+            (false, true) => from_matches(),
+            (true, false) => to_matches(),
             (false, false) => match self.mode {
-                TraceFilterMode::Union => {
-                    self.from_addresses.contains(&from) ||
-                        to.map_or(false, |to_addr| self.to_addresses.contains(&to_addr))
-                }
-                TraceFilterMode::Intersection => {
-                    self.from_addresses.contains(&from) &&
-                        to.map_or(false, |to_addr| self.to_addresses.contains(&to_addr))
-                }
+                TraceFilterMode::Union => from_matches() || to_matches(),
+                TraceFilterMode::Intersection => from_matches() && to_matches(),
             },
         }
     }
@@ -84,6 +130,7 @@ impl TraceFilterMatcher {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::trace::parity::{CreateAction, CreateOutput, SelfdestructAction};
     use serde_json::json;
 
     #[test]
@@ -180,4 +227,46 @@ mod tests {
         assert!(!matcher.matches(test_addr_d8, Some(test_addr_d8)));
         assert!(!matcher.matches(test_addr_d8, Some(test_addr_16)));
     }
+
+    #[test]
+    fn test_filter_matcher_traces() {
+        let test_addr_d8 = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045".parse().unwrap();
+        let test_addr_16 = "0x160f5f00288e9e1cc8655b327e081566e580a71d".parse().unwrap();
+        let filter_json = json!({
+            "toAddress": [test_addr_d8],
+        });
+        let filter: TraceFilter = serde_json::from_value(filter_json).unwrap();
+        let matcher = filter.matcher();
+
+        let create = TransactionTrace {
+            action: Action::Create(CreateAction {
+                from: test_addr_16,
+                value: Default::default(),
+                gas: Default::default(),
+                init: Default::default(),
+            }),
+            error: None,
+            result: Some(TraceOutput::Create(CreateOutput {
+                gas_used: Default::default(),
+                code: Default::default(),
+                address: test_addr_d8,
+            })),
+            subtraces: 0,
+            trace_address: vec![],
+        };
+        assert!(matcher.matches_trace(&create));
+
+        let selfdestruct = TransactionTrace {
+            action: Action::Selfdestruct(SelfdestructAction {
+                address: test_addr_d8,
+                refund_address: test_addr_16,
+                balance: Default::default(),
+            }),
+            error: None,
+            result: None,
+            subtraces: 0,
+            trace_address: vec![0],
+        };
+        assert!(!matcher.matches_trace(&selfdestruct));
+    }
 }
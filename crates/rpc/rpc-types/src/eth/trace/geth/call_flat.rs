@@ -0,0 +1,101 @@
+use crate::trace::parity::LocalizedTransactionTrace;
+use serde::{Deserialize, Serialize};
+
+/// The response object for `debug_traceTransaction` with `"tracer": "flatCallTracer"`, the parity
+/// style traces of all calls of the transaction.
+///
+/// <https://github.com/ethereum/go-ethereum/blob/master/eth/tracers/native/call_flat.go>
+pub type FlatCallFrame = Vec<LocalizedTransactionTrace>;
+
+/// The config for the flat call tracer.
+///
+/// <https://github.com/ethereum/go-ethereum/blob/master/eth/tracers/native/call_flat.go>
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlatCallConfig {
+    /// If true, call errors are reported in the parity format instead of the geth format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub convert_parity_errors: Option<bool>,
+    /// If true, calls to precompiles are included in the traces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_precompiles: Option<bool>,
+}
+
+impl FlatCallConfig {
+    /// Reports call errors in the parity format.
+    pub fn convert_parity_errors(mut self) -> Self {
+        self.convert_parity_errors = Some(true);
+        self
+    }
+
+    /// Includes calls to precompiles in the traces.
+    pub fn include_precompiles(mut self) -> Self {
+        self.include_precompiles = Some(true);
+        self
+    }
+
+    /// Returns true if call errors are reported in the parity format.
+    pub fn is_convert_parity_errors(&self) -> bool {
+        self.convert_parity_errors.unwrap_or_default()
+    }
+
+    /// Returns true if calls to precompiles are included in the traces.
+    pub fn is_include_precompiles(&self) -> bool {
+        self.include_precompiles.unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::geth::*;
+
+    const DEFAULT: &str = r#"[
+        {
+            "action": {
+                "callType": "call",
+                "from": "0xb436ba50d378d4bbc8660d312a13df6af6e89dfb",
+                "gas": "0x10738",
+                "input": "0x63e4bff40000000000000000000000000024f658a46fbb89d8ac105e98d7ac7cbbaf27c5",
+                "to": "0x3b873a919aa0512d5a0f09e6dcceaa4a6727fafe",
+                "value": "0x0"
+            },
+            "blockHash": "0x6b2c9d8c5ec1e4f2b3b4c1e36d1b5d1a7f7a5cb4a0c8a1b1a8d0e4b5a2c9d8e7",
+            "blockNumber": 1,
+            "result": {
+                "gasUsed": "0x9751",
+                "output": "0x0000000000000000000000000000000000000000000000000000000000000001"
+            },
+            "subtraces": 0,
+            "traceAddress": [],
+            "transactionHash": "0x2c5a4c8a9b1bfbab5d5a2e6b9a8e1a0b2a5b2b1a0c5b6d8e1f2a3b4c5d6e7f80",
+            "transactionPosition": 0,
+            "type": "call"
+        }
+    ]"#;
+
+    #[test]
+    fn test_serialize_flat_call_config() {
+        let mut opts = GethDebugTracingCallOptions::default();
+        opts.tracing_options.tracer =
+            Some(GethDebugTracerType::BuiltInTracer(GethDebugBuiltInTracerType::FlatCallTracer));
+        opts.tracing_options = opts
+            .tracing_options
+            .flat_call_config(FlatCallConfig::default().convert_parity_errors());
+
+        assert_eq!(
+            serde_json::to_string(&opts).unwrap(),
+            r#"{"tracer":"flatCallTracer","tracerConfig":{"convertParityErrors":true}}"#,
+        );
+    }
+
+    #[test]
+    fn test_deserialize_flat_call_trace() {
+        let trace: FlatCallFrame = serde_json::from_str(DEFAULT).unwrap();
+        assert_eq!(trace.len(), 1);
+        assert!(matches!(
+            serde_json::from_str::<GethTrace>(DEFAULT).unwrap(),
+            GethTrace::FlatCallTracer(_)
+        ));
+    }
+}
@@ -9,7 +9,9 @@ use std::{collections::BTreeMap, time::Duration};
 // re-exports
 pub use self::{
     call::{CallConfig, CallFrame, CallLogFrame},
+    call_flat::{FlatCallConfig, FlatCallFrame},
     four_byte::FourByteFrame,
+    mux::{MuxConfig, MuxFrame},
     noop::NoopFrame,
     pre_state::{
         AccountChangeKind, AccountState, DiffMode, DiffStateKind, PreStateConfig, PreStateFrame,
@@ -18,7 +20,9 @@ pub use self::{
 };
 
 mod call;
+mod call_flat;
 mod four_byte;
+mod mux;
 mod noop;
 mod pre_state;
 
@@ -112,6 +116,10 @@ pub enum GethTrace {
     PreStateTracer(PreStateFrame),
     /// An empty json response
     NoopTracer(NoopFrame),
+    /// The response for flat call tracer
+    FlatCallTracer(FlatCallFrame),
+    /// The response for mux tracer
+    MuxTracer(MuxFrame),
     /// Any other trace response, such as custom javascript response objects
     JS(serde_json::Value),
 }
@@ -146,10 +154,22 @@ impl From<NoopFrame> for GethTrace {
     }
 }
 
+impl From<FlatCallFrame> for GethTrace {
+    fn from(value: FlatCallFrame) -> Self {
+        GethTrace::FlatCallTracer(value)
+    }
+}
+
+impl From<MuxFrame> for GethTrace {
+    fn from(value: MuxFrame) -> Self {
+        GethTrace::MuxTracer(value)
+    }
+}
+
 /// Available built-in tracers
 ///
 /// See <https://geth.ethereum.org/docs/developers/evm-tracing/built-in-tracers>
-#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Deserialize, Serialize)]
 pub enum GethDebugBuiltInTracerType {
     /// The 4byteTracer collects the function selectors of every function executed in the lifetime
     /// of a transaction, along with the size of the supplied call data. The result is a
@@ -175,6 +195,15 @@ pub enum GethDebugBuiltInTracerType {
     /// This tracer is noop. It returns an empty object and is only meant for testing the setup.
     #[serde(rename = "noopTracer")]
     NoopTracer,
+    /// The flatCallTracer reports the same call frames as the callTracer, but as a flat list of
+    /// parity style traces. The result is a [FlatCallFrame].
+    #[serde(rename = "flatCallTracer")]
+    FlatCallTracer,
+    /// The muxTracer runs multiple tracers in one go. It's configured with a [MuxConfig] that
+    /// maps the tracers to their config and returns a [MuxFrame] with the result of every
+    /// tracer.
+    #[serde(rename = "muxTracer")]
+    MuxTracer,
 }
 
 /// Available tracers
@@ -236,6 +265,22 @@ impl GethDebugTracerConfig {
         }
         self.from_value()
     }
+
+    /// Returns the [FlatCallConfig] if it is a flat call config.
+    pub fn into_flat_call_config(self) -> Result<FlatCallConfig, serde_json::Error> {
+        if self.0.is_null() {
+            return Ok(Default::default())
+        }
+        self.from_value()
+    }
+
+    /// Returns the [MuxConfig] if it is a mux config.
+    pub fn into_mux_config(self) -> Result<MuxConfig, serde_json::Error> {
+        if self.0.is_null() {
+            return Ok(Default::default())
+        }
+        self.from_value()
+    }
 }
 
 impl From<serde_json::Value> for GethDebugTracerConfig {
@@ -264,7 +309,8 @@ pub struct GethDebugTracingOptions {
     /// tracerConfig is slated for Geth v1.11.0
     /// See <https://github.com/ethereum/go-ethereum/issues/26513>
     ///
-    /// This could be [CallConfig] or [PreStateConfig] depending on the tracer.
+    /// This could be [CallConfig], [PreStateConfig], [FlatCallConfig] or [MuxConfig] depending on
+    /// the tracer.
     #[serde(default, skip_serializing_if = "GethDebugTracerConfig::is_null")]
     pub tracer_config: GethDebugTracerConfig,
     /// A string of decimal integers that overrides the JavaScript-based tracing calls default
@@ -299,6 +345,20 @@ impl GethDebugTracingOptions {
             GethDebugTracerConfig(serde_json::to_value(config).expect("is serializable"));
         self
     }
+
+    /// Configures a [FlatCallConfig]
+    pub fn flat_call_config(mut self, config: FlatCallConfig) -> Self {
+        self.tracer_config =
+            GethDebugTracerConfig(serde_json::to_value(config).expect("is serializable"));
+        self
+    }
+
+    /// Configures a [MuxConfig]
+    pub fn mux_config(mut self, config: MuxConfig) -> Self {
+        self.tracer_config =
+            GethDebugTracerConfig(serde_json::to_value(config).expect("is serializable"));
+        self
+    }
 }
 
 /// Default tracing options for the struct looger.
@@ -470,6 +530,21 @@ pub struct GethDebugTracingCallOptions {
     pub block_overrides: Option<BlockOverrides>,
 }
 
+/// Bindings for `debug_standardTraceBlockToFile` and `debug_standardTraceBadBlockToFile` options
+///
+/// See <https://geth.ethereum.org/docs/rpc/ns-debug#debug_standardtraceblocktofile>
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StandardTraceConfig {
+    #[serde(default, flatten)]
+    pub config: GethDefaultTracingOptions,
+    /// Only trace the transaction with this hash.
+    ///
+    /// If `None` then all transactions of the block are traced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<B256>,
+}
+
 /// Serializes a storage map as a list of key-value pairs _without_ 0x-prefix
 fn serialize_string_storage_map_opt<S: Serializer>(
     storage: &Option<BTreeMap<B256, B256>>,
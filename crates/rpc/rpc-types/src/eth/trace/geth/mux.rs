@@ -0,0 +1,83 @@
+use crate::trace::geth::{GethDebugBuiltInTracerType, GethDebugTracerConfig, GethTrace};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The config for the mux tracer, a map of built-in tracers to their (optional) config.
+///
+/// <https://github.com/ethereum/go-ethereum/blob/master/eth/tracers/native/mux.go>
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MuxConfig(pub HashMap<GethDebugBuiltInTracerType, Option<GethDebugTracerConfig>>);
+
+/// The response object for `debug_traceTransaction` with `"tracer": "muxTracer"`, the frames of
+/// all configured tracers keyed by their tracer type.
+///
+/// <https://github.com/ethereum/go-ethereum/blob/master/eth/tracers/native/mux.go>
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MuxFrame(pub HashMap<GethDebugBuiltInTracerType, GethTrace>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::geth::*;
+
+    const FOUR_BYTE_FRAME: &str = r#"{
+        "0x27dc297e-128": 1,
+        "0x38cc4831-0": 2
+    }"#;
+
+    #[test]
+    fn test_serialize_mux_tracer_config() {
+        let mut opts = GethDebugTracingCallOptions::default();
+        opts.tracing_options.tracer =
+            Some(GethDebugTracerType::BuiltInTracer(GethDebugBuiltInTracerType::MuxTracer));
+        opts.tracing_options = opts.tracing_options.mux_config(MuxConfig(HashMap::from([(
+            GethDebugBuiltInTracerType::CallTracer,
+            Some(GethDebugTracerConfig(serde_json::json!({ "withLog": true }))),
+        )])));
+
+        assert_eq!(
+            serde_json::to_string(&opts).unwrap(),
+            r#"{"tracer":"muxTracer","tracerConfig":{"callTracer":{"withLog":true}}}"#,
+        );
+    }
+
+    #[test]
+    fn test_deserialize_mux_tracer_config() {
+        let s = r#"{"tracer":"muxTracer","tracerConfig":{"4byteTracer":null,"callTracer":{"onlyTopCall":true},"prestateTracer":{"diffMode":true}}}"#;
+        let opts = serde_json::from_str::<GethDebugTracingOptions>(s).unwrap();
+        assert_eq!(
+            opts.tracer,
+            Some(GethDebugTracerType::BuiltInTracer(GethDebugBuiltInTracerType::MuxTracer))
+        );
+
+        let MuxConfig(config) = opts.tracer_config.into_mux_config().unwrap();
+        assert_eq!(config.len(), 3);
+        assert_eq!(config[&GethDebugBuiltInTracerType::FourByteTracer], None);
+        let call_config = config[&GethDebugBuiltInTracerType::CallTracer]
+            .clone()
+            .unwrap()
+            .into_call_config()
+            .unwrap();
+        assert_eq!(call_config.only_top_call, Some(true));
+        let prestate_config = config[&GethDebugBuiltInTracerType::PreStateTracer]
+            .clone()
+            .unwrap()
+            .into_pre_state_config()
+            .unwrap();
+        assert_eq!(prestate_config.diff_mode, Some(true));
+    }
+
+    #[test]
+    fn test_mux_frame_roundtrip() {
+        let four_byte: FourByteFrame = serde_json::from_str(FOUR_BYTE_FRAME).unwrap();
+        let frame = MuxFrame(HashMap::from([
+            (GethDebugBuiltInTracerType::FourByteTracer, four_byte.into()),
+            (GethDebugBuiltInTracerType::FlatCallTracer, FlatCallFrame::default().into()),
+        ]));
+
+        let trace = GethTrace::from(frame);
+        let s = serde_json::to_string(&trace).unwrap();
+        let decoded: GethTrace = serde_json::from_str(&s).unwrap();
+        assert_eq!(decoded, trace);
+    }
+}
@@ -61,7 +61,7 @@ impl Inclusion {
     }
 }
 
-/// A bundle tx, which can either be a transaction hash, or a full tx.
+/// A bundle tx, which can either be a transaction hash, a full tx or a nested bundle.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(untagged)]
 #[serde(rename_all = "camelCase")]
@@ -79,6 +79,11 @@ pub enum BundleItem {
         /// If true, the transaction can revert without the bundle being considered invalid.
         can_revert: bool,
     },
+    /// A nested bundle, e.g. the bundle of a user transaction that is backrun.
+    Bundle {
+        /// The nested bundle.
+        bundle: SendBundleRequest,
+    },
 }
 
 /// Requirements for the bundle to be included in the block.
@@ -330,11 +335,11 @@ pub struct SimBundleResponse {
     /// The block number of the simulated block.
     pub state_block: U64,
     /// The gas price of the simulated block.
-    pub mev_gas_price: U64,
+    pub mev_gas_price: U256,
     /// The profit of the simulated block.
-    pub profit: U64,
+    pub profit: U256,
     /// The refundable value of the simulated block.
-    pub refundable_value: U64,
+    pub refundable_value: U256,
     /// The gas used by the simulated block.
     pub gas_used: U64,
     /// Logs returned by mev_simBundle.
@@ -374,10 +379,10 @@ impl SendBundleRequest {
 
 /// Request for `eth_cancelBundle`
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
 pub struct CancelBundleRequest {
-    /// Bundle hash of the bundle to be canceled
-    pub bundle_hash: String,
+    /// The replacement UUID of the bundle to be canceled, see [EthSendBundle::replacement_uuid]
+    #[serde(rename = "replacementUuid")]
+    pub replacement_uuid: String,
 }
 
 /// Request for `eth_sendPrivateTransaction`
@@ -1,9 +1,14 @@
 use crate::{Block, BlockTransactions, Rich, Transaction, TransactionReceipt};
 use alloy_primitives::{Address, Bytes, U256};
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{Error, Unexpected},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
 /// Operation type enum for `InternalOperation` struct
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+///
+/// Otterscan expects the numeric discriminant of the operation type.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum OperationType {
     /// Operation Transfer
     OpTransfer = 0,
@@ -15,24 +20,61 @@ pub enum OperationType {
     OpCreate2 = 3,
 }
 
+impl Serialize for OperationType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+impl<'de> Deserialize<'de> for OperationType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match u8::deserialize(deserializer)? {
+            0 => Ok(OperationType::OpTransfer),
+            1 => Ok(OperationType::OpSelfDestruct),
+            2 => Ok(OperationType::OpCreate),
+            3 => Ok(OperationType::OpCreate2),
+            other => Err(D::Error::invalid_value(
+                Unexpected::Unsigned(other as u64),
+                &"an operation type between 0 and 3",
+            )),
+        }
+    }
+}
+
 /// Custom struct for otterscan `getInternalOperations` RPC response
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct InternalOperation {
-    r#type: OperationType,
-    from: Address,
-    to: Address,
-    value: u128,
+    /// The kind of the operation
+    pub r#type: OperationType,
+    /// The address that initiated the operation
+    pub from: Address,
+    /// The receiver of the transfer, the created contract or the selfdestruct beneficiary
+    pub to: Address,
+    /// The transferred value
+    pub value: U256,
 }
 
 /// Custom struct for otterscan `traceTransaction` RPC response
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct TraceEntry {
-    r#type: String,
-    depth: u32,
-    from: Address,
-    to: Address,
-    value: u128,
-    input: Bytes,
+    /// The kind of the call frame, e.g. `CALL` or `CREATE2`
+    pub r#type: String,
+    /// The depth of the call frame, the top-level call has depth 0
+    pub depth: u32,
+    /// The caller
+    pub from: Address,
+    /// The callee or the created contract
+    pub to: Address,
+    /// The value transferred with the call, `None` for static and delegate calls
+    pub value: Option<U256>,
+    /// The calldata or init code
+    pub input: Bytes,
 }
 
 /// Internal issuance struct for `BlockDetails` struct
@@ -48,9 +90,11 @@ pub struct InternalIssuance {
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OtsBlock {
+    /// The block
     #[serde(flatten)]
-    block: Block,
-    transaction_count: usize,
+    pub block: Block,
+    /// The number of transactions in the block
+    pub transaction_count: usize,
 }
 
 /// Custom struct for otterscan `getBlockDetails` RPC response
@@ -66,16 +110,20 @@ pub struct BlockDetails {
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OtsTransactionReceipt {
+    /// The receipt
     #[serde(flatten)]
-    receipt: TransactionReceipt,
-    timestamp: u64,
+    pub receipt: TransactionReceipt,
+    /// The timestamp of the block the transaction was included in
+    pub timestamp: u64,
 }
 
 /// Custom struct for otterscan `getBlockTransactions` RPC response
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct OtsBlockTransactions {
-    fullblock: OtsBlock,
-    receipts: Vec<OtsTransactionReceipt>,
+    /// The block with the transactions of the requested page
+    pub fullblock: OtsBlock,
+    /// The receipts of the transactions of the requested page
+    pub receipts: Vec<OtsTransactionReceipt>,
 }
 
 /// Custom struct for otterscan `searchTransactionsAfter`and `searchTransactionsBefore` RPC
@@ -83,17 +131,23 @@ pub struct OtsBlockTransactions {
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TransactionsWithReceipts {
-    txs: Vec<Transaction>,
-    receipts: Vec<OtsTransactionReceipt>,
-    first_page: bool,
-    last_page: bool,
+    /// The matching transactions, most recent first
+    pub txs: Vec<Transaction>,
+    /// The receipts of the matching transactions
+    pub receipts: Vec<OtsTransactionReceipt>,
+    /// Whether this page contains the most recent transactions
+    pub first_page: bool,
+    /// Whether this page contains the oldest transactions
+    pub last_page: bool,
 }
 
 /// Custom struct for otterscan `getContractCreator` RPC responses
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ContractCreator {
-    tx: Transaction,
-    creator: Address,
+    /// The transaction that created the contract
+    pub tx: Transaction,
+    /// The address that executed the create operation
+    pub creator: Address,
 }
 
 impl From<Block> for OtsBlock {
@@ -117,3 +171,25 @@ impl From<Rich<Block>> for BlockDetails {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde_internal_operation() {
+        let op = InternalOperation {
+            r#type: OperationType::OpCreate2,
+            from: Address::with_last_byte(1),
+            to: Address::with_last_byte(2),
+            value: U256::from(10),
+        };
+        let s = serde_json::to_string(&op).unwrap();
+        assert_eq!(
+            s,
+            r#"{"type":3,"from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","value":"0xa"}"#
+        );
+        let de: InternalOperation = serde_json::from_str(&s).unwrap();
+        assert_eq!(de, op);
+    }
+}
@@ -1,10 +1,11 @@
-use crate::result::ToRpcResult;
+use crate::result::{internal_rpc_err, log_filter_rpc_err, ToRpcResult};
 use async_trait::async_trait;
 use jsonrpsee::core::RpcResult;
 use reth_network_api::{NetworkInfo, PeerKind, Peers};
 use reth_primitives::NodeRecord;
 use reth_rpc_api::AdminApiServer;
 use reth_rpc_types::{NodeInfo, PeerEthProtocolInfo, PeerInfo, PeerNetworkInfo, PeerProtocolsInfo};
+use reth_tracing::LogFilterHandle;
 
 /// `admin` API implementation.
 ///
@@ -50,6 +51,23 @@ where
         Ok(true)
     }
 
+    /// Handler for `admin_logFilter`
+    fn log_filter(&self) -> RpcResult<String> {
+        Ok(log_filter()?.current())
+    }
+
+    /// Handler for `admin_setLogFilter`
+    fn set_log_filter(&self, filter: String) -> RpcResult<bool> {
+        log_filter()?.set_filter(&filter).map_err(log_filter_rpc_err)?;
+        Ok(true)
+    }
+
+    /// Handler for `admin_resetLogFilter`
+    fn reset_log_filter(&self) -> RpcResult<bool> {
+        log_filter()?.reset().map_err(log_filter_rpc_err)?;
+        Ok(true)
+    }
+
     async fn peers(&self) -> RpcResult<Vec<PeerInfo>> {
         let peers = self.network.get_all_peers().await.to_rpc_result()?;
         let peers = peers
@@ -96,6 +114,12 @@ where
     }
 }
 
+/// Returns the handle to the filter of the node's logs, see [reth_tracing::log_filter].
+pub(crate) fn log_filter() -> RpcResult<&'static LogFilterHandle> {
+    reth_tracing::log_filter()
+        .ok_or_else(|| internal_rpc_err("the log filter can't be changed at runtime"))
+}
+
 impl<N> std::fmt::Debug for AdminApi<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AdminApi").finish_non_exhaustive()
@@ -1,4 +1,5 @@
 use crate::{
+    admin::log_filter,
     eth::{
         error::{EthApiError, EthResult},
         revm_utils::{
@@ -7,65 +8,181 @@ use crate::{
         },
         EthTransactions, TransactionSource,
     },
-    result::{internal_rpc_err, ToRpcResult},
+    profile::{self, ProfileError},
+    result::{internal_rpc_err, invalid_params_rpc_err, log_filter_rpc_err, ToRpcResult},
     BlockingTaskGuard, EthApiSpec,
 };
 use alloy_rlp::{Decodable, Encodable};
 use async_trait::async_trait;
-use jsonrpsee::core::RpcResult;
+use jsonrpsee::{
+    core::{RpcResult, SubscriptionResult},
+    server::SubscriptionMessage,
+    types::ErrorObject,
+    PendingSubscriptionSink, RpcModule, SubscriptionSink,
+};
+use reth_beacon_consensus::{BeaconConsensusEngineHandle, BeaconSetHeadError};
+use reth_interfaces::RethError;
 use reth_primitives::{
-    revm::env::tx_env_with_recovered, Account, Address, Block, BlockId, BlockNumberOrTag, Bytes,
-    TransactionSigned, B256,
+    keccak256, revm::env::tx_env_with_recovered, Account, Address, Block, BlockId,
+    BlockNumberOrTag, Bytes, SealedBlock, TransactionSigned, B256, U256, U64,
+};
+use reth_provider::{
+    BadBlockReader, BlockHashReader, BlockIdReader, BlockNumReader, BlockReaderIdExt,
+    ChainSpecProvider, ChangeSetReader, HeaderProvider, StateProvider, StateProviderBox,
 };
-use reth_provider::{BlockReaderIdExt, HeaderProvider, StateProviderBox};
 use reth_revm::{
     database::{StateProviderDatabase, SubState},
+    processor::EVMProcessor,
     tracing::{
         js::{JsDbRequest, JsInspector},
-        FourByteInspector, TracingInspector, TracingInspectorConfig,
+        Eip3155Inspector, FourByteInspector, MuxInspector, TracingInspector,
+        TracingInspectorConfig,
     },
 };
 use reth_rpc_api::DebugApiServer;
 use reth_rpc_types::{
     trace::geth::{
         BlockTraceResult, FourByteFrame, GethDebugBuiltInTracerType, GethDebugTracerType,
-        GethDebugTracingCallOptions, GethDebugTracingOptions, GethTrace, NoopFrame, TraceResult,
+        GethDebugTracingCallOptions, GethDebugTracingOptions, GethTrace, NoopFrame,
+        StandardTraceConfig, TraceResult,
     },
-    BlockError, Bundle, CallRequest, RichBlock, StateContext,
+    AccountRange, BadBlock, BlockError, BlockTransactionsKind, Bundle, CallRequest, DumpAccount,
+    StateContext, StorageRangeEntry, StorageRangeResult, TransactionInfo,
 };
+use reth_rpc_types_compat::block::{from_block, from_block_with_tx_hashes};
 use reth_tasks::TaskSpawner;
 use revm::{
-    db::{CacheDB, EmptyDB},
+    db::{AccountState, CacheDB, EmptyDB},
     primitives::Env,
 };
 use revm_primitives::{
     db::{DatabaseCommit, DatabaseRef},
     BlockEnv, CfgEnv,
 };
-use std::sync::Arc;
-use tokio::sync::{mpsc, AcquireError, OwnedSemaphorePermit};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::RangeInclusive,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, UNIX_EPOCH},
+};
+use tokio::sync::{mpsc, oneshot, AcquireError, OwnedSemaphorePermit};
 use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use tracing::{debug, level_filters::LevelFilter};
+
+/// The maximum number of accounts returned by a `debug_accountRange` request, same as geth.
+const ACCOUNT_RANGE_MAX_RESULTS: u64 = 256;
 
 /// `debug` API implementation.
 ///
 /// This type provides the functionality for handling `debug` related requests.
 pub struct DebugApi<Provider, Eth> {
     inner: Arc<DebugApiInner<Provider, Eth>>,
+    /// Handle to the consensus engine, required by `debug_setHead`.
+    beacon_engine: Option<BeaconConsensusEngineHandle>,
+    /// The directory the profiling methods write to, profiling is disabled if unset.
+    profile_dir: Option<Arc<PathBuf>>,
 }
 
 // === impl DebugApi ===
 
 impl<Provider, Eth> DebugApi<Provider, Eth> {
     /// Create a new instance of the [DebugApi]
+    ///
+    /// The `trace_chain_window` is the maximum number of blocks a `debug_traceChain` subscription
+    /// traces concurrently and buffers before they are sent to the subscriber.
     pub fn new(
         provider: Provider,
         eth: Eth,
         task_spawner: Box<dyn TaskSpawner>,
         blocking_task_guard: BlockingTaskGuard,
+        trace_chain_window: usize,
     ) -> Self {
-        let inner =
-            Arc::new(DebugApiInner { provider, eth_api: eth, task_spawner, blocking_task_guard });
-        Self { inner }
+        let inner = Arc::new(DebugApiInner {
+            provider,
+            eth_api: eth,
+            task_spawner,
+            blocking_task_guard,
+            trace_chain_window: trace_chain_window.max(1),
+        });
+        Self { inner, beacon_engine: None, profile_dir: None }
+    }
+
+    /// Sets the handle to the consensus engine which enables `debug_setHead`.
+    ///
+    /// Since unwinding the chain is destructive, this should only be set for the API served on
+    /// the authenticated endpoint.
+    pub fn with_beacon_engine(mut self, beacon_engine: BeaconConsensusEngineHandle) -> Self {
+        self.beacon_engine = Some(beacon_engine);
+        self
+    }
+
+    /// Sets the directory the profiling methods, e.g. `debug_cpuProfile`, write their files to.
+    ///
+    /// The file names passed to these methods are resolved relative to this directory and can't
+    /// point outside of it.
+    pub fn with_profile_dir(mut self, profile_dir: impl Into<PathBuf>) -> Self {
+        self.profile_dir = Some(Arc::new(profile_dir.into()));
+        self
+    }
+
+    /// Returns the path inside the profile directory the given profile file is written to.
+    fn profile_path(&self, file: &str) -> RpcResult<PathBuf> {
+        profile::profile_path(self.profile_dir.as_deref().map(PathBuf::as_path), file)
+            .map_err(|err| invalid_params_rpc_err(err.to_string()))
+    }
+
+    /// Rewinds the canonical chain to the given block, see `debug_setHead`.
+    pub async fn set_head(&self, number: u64) -> RpcResult<()> {
+        let Some(beacon_engine) = &self.beacon_engine else {
+            return Err(internal_rpc_err(
+                "debug_setHead is only available on the authenticated endpoint",
+            ))
+        };
+        beacon_engine.set_head(number).await.map_err(|err| match err {
+            BeaconSetHeadError::AboveCanonicalHead { .. } => {
+                invalid_params_rpc_err(err.to_string())
+            }
+            err => internal_rpc_err(err.to_string()),
+        })
+    }
+
+    /// Runs the profiler operation on a blocking task, since writing a profile is CPU and disk
+    /// intensive.
+    async fn on_profile_task<T, F>(&self, f: F) -> RpcResult<T>
+    where
+        F: FnOnce() -> Result<T, ProfileError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.inner.task_spawner.spawn_blocking(Box::pin(async move {
+            let _ = tx.send(f());
+        }));
+        rx.await
+            .map_err(|_| internal_rpc_err("profile task dropped"))?
+            .map_err(|err| internal_rpc_err(err.to_string()))
+    }
+}
+
+impl<Provider, Eth> DebugApi<Provider, Eth>
+where
+    Provider: Send + Sync + 'static,
+    Eth: Send + Sync + 'static,
+{
+    /// Returns the module that serves `debug_setHead`, and no other method of the `debug_`
+    /// namespace.
+    ///
+    /// This is meant for the authenticated endpoint, which shouldn't expose the other, e.g.
+    /// expensive tracing methods.
+    pub fn into_set_head_rpc(self) -> RpcModule<Self> {
+        let mut module = RpcModule::new(self);
+        module
+            .register_async_method("debug_setHead", |params, debug_api| async move {
+                let number = params.one::<u64>()?;
+                debug_api.set_head(number).await
+            })
+            .expect("debug_setHead is registered once");
+        module
     }
 }
 
@@ -73,7 +190,12 @@ impl<Provider, Eth> DebugApi<Provider, Eth> {
 
 impl<Provider, Eth> DebugApi<Provider, Eth>
 where
-    Provider: BlockReaderIdExt + HeaderProvider + 'static,
+    Provider: BlockReaderIdExt
+        + HeaderProvider
+        + ChainSpecProvider
+        + ChangeSetReader
+        + BadBlockReader
+        + 'static,
     Eth: EthTransactions + 'static,
 {
     /// Acquires a permit to execute a tracing call.
@@ -85,6 +207,7 @@ where
     async fn trace_block_with(
         &self,
         at: BlockId,
+        block_hash: B256,
         transactions: Vec<TransactionSigned>,
         cfg: CfgEnv,
         block_env: BlockEnv,
@@ -98,13 +221,20 @@ where
                 let mut results = Vec::with_capacity(transactions.len());
                 let mut db = CacheDB::new(StateProviderDatabase::new(state));
 
-                let mut transactions = transactions.into_iter().peekable();
-                while let Some(tx) = transactions.next() {
+                let mut transactions = transactions.into_iter().enumerate().peekable();
+                while let Some((index, tx)) = transactions.next() {
                     let tx = tx.into_ecrecovered().ok_or(BlockError::InvalidSignature)?;
+                    let tx_info = TransactionInfo {
+                        hash: Some(tx.hash()),
+                        index: Some(index as u64),
+                        block_hash: Some(block_hash),
+                        block_number: Some(block_env.number.to()),
+                        base_fee: Some(block_env.basefee.to()),
+                    };
                     let tx = tx_env_with_recovered(&tx);
                     let env = Env { cfg: cfg.clone(), block: block_env.clone(), tx };
                     let (result, state_changes) =
-                        this.trace_transaction(opts.clone(), env, at, &mut db)?;
+                        this.trace_transaction(opts.clone(), env, at, tx_info, &mut db)?;
                     results.push(TraceResult::Success { result });
 
                     if transactions.peek().is_some() {
@@ -136,7 +266,8 @@ where
 
         // we trace on top the block's parent block
         let parent = block.parent_hash;
-        self.trace_block_with(parent.into(), block.body, cfg, block_env, opts).await
+        let block_hash = block.header.hash_slow();
+        self.trace_block_with(parent.into(), block_hash, block.body, cfg, block_env, opts).await
     }
 
     /// Replays a block and returns the trace of each transaction.
@@ -161,7 +292,99 @@ where
         // its parent block's state
         let state_at = block.parent_hash;
 
-        self.trace_block_with(state_at.into(), block.body, cfg, block_env, opts).await
+        self.trace_block_with(state_at.into(), block_hash, block.body, cfg, block_env, opts).await
+    }
+
+    /// Resolves the `(start, end]` block range of a `debug_traceChain` request.
+    fn trace_chain_range(
+        &self,
+        start_exclusive: BlockNumberOrTag,
+        end_inclusive: BlockNumberOrTag,
+    ) -> EthResult<RangeInclusive<u64>> {
+        let start = self
+            .inner
+            .provider
+            .convert_block_number(start_exclusive)?
+            .ok_or(EthApiError::UnknownBlockNumber)?;
+        let end = self
+            .inner
+            .provider
+            .convert_block_number(end_inclusive)?
+            .ok_or(EthApiError::UnknownBlockNumber)?;
+        if start >= end {
+            return Err(EthApiError::InvalidParams(format!(
+                "end block (#{end}) needs to come after start block (#{start})"
+            )))
+        }
+        Ok(start + 1..=end)
+    }
+
+    /// Traces all blocks in the range and sends the results to the subscriber in ascending order.
+    ///
+    /// Up to `trace_chain_window` blocks are traced concurrently, each of them on the blocking
+    /// task pool. Since the results are forwarded in order, this also bounds the number of traced
+    /// blocks that are buffered while the subscriber is consuming them.
+    ///
+    /// If a block can't be traced, a result for that block with a single error trace is sent and
+    /// the subscription is closed, so the subscriber can tell that the remaining blocks are
+    /// missing.
+    async fn trace_chain(
+        self,
+        sink: SubscriptionSink,
+        range: RangeInclusive<u64>,
+        opts: GethDebugTracingOptions,
+    ) -> Result<(), jsonrpsee::core::Error> {
+        let blocks = futures::stream::iter(range).map(|number| {
+            let this = self.clone();
+            let opts = opts.clone();
+            async move {
+                let _permit = this.acquire_trace_permit().await;
+                let result = async {
+                    let hash = this
+                        .inner
+                        .provider
+                        .block_hash(number)?
+                        .ok_or(EthApiError::UnknownBlockNumber)?;
+                    let traces = this.debug_trace_block(hash.into(), opts).await?;
+                    EthResult::Ok(BlockTraceResult { block: U256::from(number), hash, traces })
+                }
+                .await;
+                (number, result)
+            }
+        });
+        let mut results = futures::StreamExt::buffered(blocks, self.inner.trace_chain_window);
+
+        loop {
+            tokio::select! {
+                _ = sink.closed() => {
+                    // connection dropped
+                    break Ok(())
+                },
+                maybe_result = results.next() => {
+                    let (result, failed) = match maybe_result {
+                        Some((_, Ok(result))) => (result, false),
+                        Some((number, Err(err))) => {
+                            debug!(target: "rpc::debug", %err, number, "Failed to trace chain");
+                            let hash = self.inner.provider.block_hash(number).ok().flatten();
+                            let result = BlockTraceResult {
+                                block: U256::from(number),
+                                hash: hash.unwrap_or_default(),
+                                traces: vec![TraceResult::Error { error: err.to_string() }],
+                            };
+                            (result, true)
+                        }
+                        None => {
+                            // all blocks traced
+                            break Ok(())
+                        }
+                    };
+                    let msg = SubscriptionMessage::from_json(&result)?;
+                    if sink.send(msg).await.is_err() || failed {
+                        break Ok(())
+                    }
+                }
+            }
+        }
     }
 
     /// Trace the transaction according to the provided options.
@@ -188,7 +411,7 @@ where
             .eth_api
             .spawn_with_state_at_block(state_at, move |state| {
                 // configure env for the target transaction
-                let tx = transaction.into_recovered();
+                let (tx, tx_info) = transaction.split();
 
                 let mut db = CacheDB::new(StateProviderDatabase::new(state));
                 // replay all transactions prior to the targeted transaction
@@ -201,7 +424,8 @@ where
                 )?;
 
                 let env = Env { cfg, block: block_env, tx: tx_env_with_recovered(&tx) };
-                this.trace_transaction(opts, env, state_at, &mut db).map(|(trace, _)| trace)
+                this.trace_transaction(opts, env, state_at, tx_info, &mut db)
+                    .map(|(trace, _)| trace)
             })
             .await
     }
@@ -284,6 +508,53 @@ where
                         return Ok(frame.into())
                     }
                     GethDebugBuiltInTracerType::NoopTracer => Ok(NoopFrame::default().into()),
+                    GethDebugBuiltInTracerType::FlatCallTracer => {
+                        let flat_call_config = tracer_config
+                            .into_flat_call_config()
+                            .map_err(|_| EthApiError::InvalidTracerConfig)?;
+                        let mut inspector = TracingInspector::new(
+                            TracingInspectorConfig::default_parity().set_exclude_precompile_calls(
+                                !flat_call_config.is_include_precompiles(),
+                            ),
+                        );
+
+                        let frame = self
+                            .inner
+                            .eth_api
+                            .spawn_with_call_at(call, at, overrides, move |db, env| {
+                                let (res, _) = inspect(db, env, &mut inspector)?;
+                                let frame = inspector.into_geth_builder().geth_flat_call_traces(
+                                    flat_call_config,
+                                    TransactionInfo::default(),
+                                    res.result.gas_used(),
+                                );
+                                Ok(frame.into())
+                            })
+                            .await?;
+                        return Ok(frame)
+                    }
+                    GethDebugBuiltInTracerType::MuxTracer => {
+                        let mux_config = tracer_config
+                            .into_mux_config()
+                            .map_err(|_| EthApiError::InvalidTracerConfig)?;
+                        let mut inspector = MuxInspector::try_from_config(mux_config)
+                            .map_err(|_| EthApiError::InvalidTracerConfig)?;
+
+                        let frame = self
+                            .inner
+                            .eth_api
+                            .spawn_with_call_at(call, at, overrides, move |db, env| {
+                                let (res, _, db) = inspect_and_return_db(db, env, &mut inspector)?;
+                                let frame = inspector.try_into_mux_frame(
+                                    &res,
+                                    &db,
+                                    TransactionInfo::default(),
+                                )?;
+                                Ok(frame.into())
+                            })
+                            .await?;
+                        return Ok(frame)
+                    }
                 },
                 GethDebugTracerType::JsTracer(code) => {
                     let config = tracer_config.into_json();
@@ -428,6 +699,7 @@ where
                             tracing_options.clone(),
                             env,
                             target_block,
+                            TransactionInfo::default(),
                             &mut db,
                         )?;
 
@@ -446,6 +718,344 @@ where
             .await
     }
 
+    /// Returns a page of the accounts of the state at the given block.
+    ///
+    /// Accounts are paged in order of their address, `start` is the address (or a prefix of it)
+    /// of the first account of the page.
+    pub async fn debug_account_range(
+        &self,
+        block_number: BlockNumberOrTag,
+        start: Bytes,
+        max_results: u64,
+        nocode: bool,
+        nostorage: bool,
+    ) -> EthResult<AccountRange> {
+        if start.len() > Address::len_bytes() {
+            return Err(EthApiError::InvalidParams(format!(
+                "start key must not be longer than an address, got {} bytes",
+                start.len()
+            )))
+        }
+        let mut start_address = [0u8; 20];
+        start_address[..start.len()].copy_from_slice(&start);
+        let start = Address::from(start_address);
+
+        let limit = if max_results == 0 || max_results > ACCOUNT_RANGE_MAX_RESULTS {
+            ACCOUNT_RANGE_MAX_RESULTS
+        } else {
+            max_results
+        } as usize;
+
+        let header = self
+            .inner
+            .provider
+            .header_by_number_or_tag(block_number)?
+            .ok_or(EthApiError::UnknownBlockNumber)?;
+
+        self.inner
+            .eth_api
+            .spawn_with_state_at_block(header.number.into(), move |state| {
+                let (accounts, next) = state.account_range(start, limit)?;
+                let next = next.map(|address| Bytes::copy_from_slice(address.as_slice()));
+
+                let accounts = accounts
+                    .into_iter()
+                    .map(|(address, account)| {
+                        let code = if nocode {
+                            None
+                        } else {
+                            state.account_code(address)?.map(|code| code.original_bytes())
+                        };
+                        let storage = if nostorage {
+                            None
+                        } else {
+                            // the provider may return the storage in several pages
+                            let mut storage = BTreeMap::new();
+                            let mut start = Some(B256::ZERO);
+                            while let Some(key) = start {
+                                let (entries, next) =
+                                    state.storage_range(address, key, usize::MAX)?;
+                                storage.extend(
+                                    entries
+                                        .into_iter()
+                                        .map(|entry| (entry.key, B256::from(entry.value))),
+                                );
+                                start = next;
+                            }
+                            Some(storage)
+                        };
+                        let account = DumpAccount {
+                            balance: account.balance.to_string(),
+                            nonce: account.nonce,
+                            code_hash: account.get_bytecode_hash(),
+                            code,
+                            storage,
+                            key: keccak256(address),
+                        };
+                        Ok((address, account))
+                    })
+                    .collect::<EthResult<_>>()?;
+
+                Ok(AccountRange { root: header.state_root, accounts, next })
+            })
+            .await
+    }
+
+    /// Returns a page of the storage of the account in the state right before the transaction at
+    /// the given index of the block was executed.
+    ///
+    /// Storage slots are paged in order of their key, `key_start` is the key of the first slot of
+    /// the page.
+    pub async fn debug_storage_range_at(
+        &self,
+        block_hash: B256,
+        tx_idx: usize,
+        contract_address: Address,
+        key_start: B256,
+        max_result: u64,
+    ) -> EthResult<StorageRangeResult> {
+        let ((cfg, block_env, _), block) = futures::try_join!(
+            self.inner.eth_api.evm_env_at(block_hash.into()),
+            self.inner.eth_api.block_by_id(block_hash.into()),
+        )?;
+        let block = block.ok_or(EthApiError::UnknownBlockNumber)?;
+
+        if tx_idx > 0 && tx_idx >= block.body.len() {
+            return Err(EthApiError::InvalidParams(format!(
+                "transaction index {tx_idx} out of range for block {block_hash}"
+            )))
+        }
+        // the transactions are replayed until the transaction at the index, or not at all if the
+        // block is empty
+        let target_tx_hash = block.body.get(tx_idx).map(|tx| tx.hash).unwrap_or_default();
+        let limit = usize::try_from(max_result).unwrap_or(usize::MAX);
+
+        self.inner
+            .eth_api
+            .spawn_with_state_at_block(block.parent_hash.into(), move |state| {
+                let mut db = CacheDB::new(StateProviderDatabase::new(state));
+                replay_transactions_until(&mut db, cfg, block_env, block.body, target_tx_hash)?;
+                storage_range_at(&db, contract_address, key_start, limit)
+            })
+            .await
+    }
+
+    /// Returns the addresses of the accounts modified in the blocks after `start` up to and
+    /// including `end`.
+    ///
+    /// If no end block is given, the accounts modified in the start block are returned.
+    pub async fn debug_get_modified_accounts_by_number(
+        &self,
+        start: u64,
+        end: Option<u64>,
+    ) -> EthResult<Vec<Address>> {
+        let (start, end) = match end {
+            Some(end) => (start, end),
+            None => {
+                let parent = start.checked_sub(1).ok_or_else(|| {
+                    EthApiError::InvalidParams("the genesis block has no parent".to_string())
+                })?;
+                (parent, start)
+            }
+        };
+        if start >= end {
+            return Err(EthApiError::InvalidParams(format!(
+                "start block height ({start}) must be less than end block height ({end})"
+            )))
+        }
+        if end > self.inner.provider.best_block_number()? {
+            return Err(EthApiError::UnknownBlockNumber)
+        }
+
+        // this can read many changesets, so it's done on a blocking task
+        let (tx, rx) = oneshot::channel();
+        let this = self.clone();
+        self.inner.task_spawner.spawn_blocking(Box::pin(async move {
+            let res = (start + 1..=end).try_fold(
+                BTreeSet::new(),
+                |mut accounts, block_number| -> EthResult<_> {
+                    let changeset = this.inner.provider.account_block_changeset(block_number)?;
+                    accounts.extend(changeset.into_iter().map(|account| account.address));
+                    Ok(accounts)
+                },
+            );
+            let _ = tx.send(res);
+        }));
+        let accounts = rx.await.map_err(|_| EthApiError::InternalEthError)??;
+        Ok(accounts.into_iter().collect())
+    }
+
+    /// Same as [Self::debug_get_modified_accounts_by_number] but with the blocks identified by
+    /// their hashes.
+    pub async fn debug_get_modified_accounts_by_hash(
+        &self,
+        start: B256,
+        end: Option<B256>,
+    ) -> EthResult<Vec<Address>> {
+        let start = self.block_number_by_hash(start)?;
+        let end = match end {
+            Some(end) => Some(self.block_number_by_hash(end)?),
+            None => None,
+        };
+        self.debug_get_modified_accounts_by_number(start, end).await
+    }
+
+    /// Returns the number of the canonical block with the given hash.
+    fn block_number_by_hash(&self, hash: B256) -> EthResult<u64> {
+        self.inner.provider.block_number(hash)?.ok_or(EthApiError::UnknownBlockNumber)
+    }
+
+    /// Replays the canonical block with the given hash and writes an EIP-3155 trace of its
+    /// transactions to files in the temporary directory.
+    ///
+    /// Returns the paths of the written files.
+    pub async fn debug_standard_trace_block_to_file(
+        &self,
+        block_hash: B256,
+        opts: StandardTraceConfig,
+    ) -> EthResult<Vec<String>> {
+        let block = self
+            .inner
+            .eth_api
+            .block_by_id(block_hash.into())
+            .await?
+            .ok_or(EthApiError::UnknownBlockNumber)?;
+        self.standard_trace_block_to_file(block, opts).await
+    }
+
+    /// Same as [Self::debug_standard_trace_block_to_file] but for a block that was recently
+    /// rejected as invalid.
+    pub async fn debug_standard_trace_bad_block_to_file(
+        &self,
+        block_hash: B256,
+        opts: StandardTraceConfig,
+    ) -> EthResult<Vec<String>> {
+        let block = self
+            .inner
+            .provider
+            .bad_blocks()?
+            .into_iter()
+            .find(|bad| bad.hash() == block_hash)
+            .map(|bad| bad.block)
+            .ok_or(EthApiError::UnknownBlockNumber)?;
+        self.standard_trace_block_to_file(block, opts).await
+    }
+
+    /// Replays the block on top of its parent's state and writes an EIP-3155 trace of each traced
+    /// transaction to a new `block_<block_hash>-<index>-<tx_hash>-<random>.jsonl` file in the
+    /// temporary directory.
+    ///
+    /// If a transaction hash is configured, only that transaction is traced and the replay stops
+    /// after it.
+    async fn standard_trace_block_to_file(
+        &self,
+        block: SealedBlock,
+        opts: StandardTraceConfig,
+    ) -> EthResult<Vec<String>> {
+        let StandardTraceConfig { config, tx_hash } = opts;
+        if let Some(tx_hash) = tx_hash {
+            if !block.body.iter().any(|tx| tx.hash() == tx_hash) {
+                return Err(EthApiError::TransactionNotFound)
+            }
+        }
+
+        let (cfg, block_env) = self.inner.eth_api.evm_env_for_raw_block(&block.header).await?;
+        let block_hash = block.hash();
+        let parent = block.parent_hash;
+        let transactions = block.unseal().body;
+
+        self.inner
+            .eth_api
+            .spawn_with_state_at_block(parent.into(), move |state| {
+                let io_err =
+                    |err: std::io::Error| EthApiError::Internal(RethError::Custom(err.to_string()));
+                let mut files = Vec::new();
+                let mut db = CacheDB::new(StateProviderDatabase::new(state));
+
+                for (index, tx) in transactions.into_iter().enumerate() {
+                    let tx = tx.into_ecrecovered().ok_or(BlockError::InvalidSignature)?;
+                    let hash = tx.hash();
+                    let env = Env {
+                        cfg: cfg.clone(),
+                        block: block_env.clone(),
+                        tx: tx_env_with_recovered(&tx),
+                    };
+
+                    let res = if tx_hash.map_or(true, |tx_hash| tx_hash == hash) {
+                        // unique file names, so concurrent calls don't overwrite each other's
+                        let (file, path) = tempfile::Builder::new()
+                            .prefix(&format!("block_{block_hash:?}-{index}-{hash:?}-"))
+                            .suffix(".jsonl")
+                            .tempfile()
+                            .map_err(io_err)?
+                            .keep()
+                            .map_err(|err| io_err(err.error))?;
+                        let mut inspector = Eip3155Inspector::new(std::io::BufWriter::new(file))
+                            .with_memory(config.is_memory_enabled())
+                            .with_stack(config.is_stack_enabled());
+                        let (res, _) = inspect(&mut db, env, &mut inspector)?;
+                        inspector.write_summary(&res.result, None).map_err(io_err)?;
+                        files.push(path.display().to_string());
+                        res
+                    } else {
+                        transact(&mut db, env)?.0
+                    };
+
+                    if tx_hash == Some(hash) {
+                        break
+                    }
+                    db.commit(res.state);
+                }
+
+                Ok(files)
+            })
+            .await
+    }
+
+    /// Re-executes the block on top of its parent's state and returns the state root after each
+    /// of its transactions.
+    ///
+    /// The block is looked up among the canonical blocks first and then among the recently
+    /// rejected ones.
+    pub async fn debug_intermediate_roots(&self, block_hash: B256) -> EthResult<Vec<B256>> {
+        let block = match self.inner.eth_api.block_by_id(block_hash.into()).await? {
+            Some(block) => block,
+            None => self
+                .inner
+                .provider
+                .bad_blocks()?
+                .into_iter()
+                .find(|bad| bad.hash() == block_hash)
+                .map(|bad| bad.block)
+                .ok_or(EthApiError::UnknownBlockNumber)?,
+        };
+
+        // the block may not be part of the chain, so its total difficulty is derived from its
+        // parent
+        let total_difficulty = self
+            .inner
+            .provider
+            .header_td(&block.parent_hash)?
+            .ok_or(EthApiError::UnknownBlockNumber)? +
+            block.difficulty;
+        let chain_spec = self.inner.provider.chain_spec();
+
+        self.inner
+            .eth_api
+            .spawn_with_state_at_block(block.parent_hash.into(), move |state| {
+                let mut executor =
+                    EVMProcessor::new_with_db(chain_spec, StateProviderDatabase::new(&state));
+                let roots = executor.execute_and_compute_intermediate_roots(
+                    &block.unseal(),
+                    total_difficulty,
+                    None,
+                    &state,
+                )?;
+                Ok(roots)
+            })
+            .await
+    }
+
     /// Executes the configured transaction with the environment on the given database.
     ///
     /// Returns the trace frame and the state that got updated after executing the transaction.
@@ -458,6 +1068,7 @@ where
         opts: GethDebugTracingOptions,
         env: Env,
         at: BlockId,
+        tx_info: TransactionInfo,
         db: &mut SubState<StateProviderBox<'_>>,
     ) -> EthResult<(GethTrace, revm_primitives::State)> {
         let GethDebugTracingOptions { config, tracer, tracer_config, .. } = opts;
@@ -512,6 +1123,40 @@ where
                     GethDebugBuiltInTracerType::NoopTracer => {
                         Ok((NoopFrame::default().into(), Default::default()))
                     }
+                    GethDebugBuiltInTracerType::FlatCallTracer => {
+                        let flat_call_config = tracer_config
+                            .into_flat_call_config()
+                            .map_err(|_| EthApiError::InvalidTracerConfig)?;
+
+                        let mut inspector = TracingInspector::new(
+                            TracingInspectorConfig::default_parity().set_exclude_precompile_calls(
+                                !flat_call_config.is_include_precompiles(),
+                            ),
+                        );
+
+                        let (res, _) = inspect(db, env, &mut inspector)?;
+
+                        let frame = inspector.into_geth_builder().geth_flat_call_traces(
+                            flat_call_config,
+                            tx_info,
+                            res.result.gas_used(),
+                        );
+
+                        return Ok((frame.into(), res.state))
+                    }
+                    GethDebugBuiltInTracerType::MuxTracer => {
+                        let mux_config = tracer_config
+                            .into_mux_config()
+                            .map_err(|_| EthApiError::InvalidTracerConfig)?;
+
+                        let mut inspector = MuxInspector::try_from_config(mux_config)
+                            .map_err(|_| EthApiError::InvalidTracerConfig)?;
+                        let (res, _) = inspect(&mut *db, env, &mut inspector)?;
+
+                        let frame = inspector.try_into_mux_frame(&res, &*db, tx_info)?;
+
+                        return Ok((frame.into(), res.state))
+                    }
                 },
                 GethDebugTracerType::JsTracer(code) => {
                     let config = tracer_config.into_json();
@@ -641,7 +1286,12 @@ where
 #[async_trait]
 impl<Provider, Eth> DebugApiServer for DebugApi<Provider, Eth>
 where
-    Provider: BlockReaderIdExt + HeaderProvider + 'static,
+    Provider: BlockReaderIdExt
+        + HeaderProvider
+        + ChainSpecProvider
+        + ChangeSetReader
+        + BadBlockReader
+        + 'static,
     Eth: EthApiSpec + 'static,
 {
     /// Handler for `debug_getRawHeader`
@@ -673,14 +1323,16 @@ where
 
     async fn debug_account_range(
         &self,
-        _block_number: BlockNumberOrTag,
-        _start: Bytes,
-        _max_results: u64,
-        _nocode: bool,
-        _nostorage: bool,
+        block_number: BlockNumberOrTag,
+        start: Bytes,
+        max_results: u64,
+        nocode: bool,
+        nostorage: bool,
         _incompletes: bool,
-    ) -> RpcResult<()> {
-        Ok(())
+    ) -> RpcResult<AccountRange> {
+        let _permit = self.acquire_trace_permit().await;
+        Ok(DebugApi::debug_account_range(self, block_number, start, max_results, nocode, nostorage)
+            .await?)
     }
 
     async fn debug_block_profile(&self, _file: String, _seconds: u64) -> RpcResult<()> {
@@ -695,7 +1347,26 @@ where
         Ok(())
     }
 
-    async fn debug_cpu_profile(&self, _file: String, _seconds: u64) -> RpcResult<()> {
+    /// Handler for `debug_cpuProfile`
+    async fn debug_cpu_profile(&self, file: String, seconds: u64) -> RpcResult<()> {
+        let file = self.profile_path(&file)?;
+        let id =
+            profile::start_cpu_profile(file).map_err(|err| internal_rpc_err(err.to_string()))?;
+
+        // the profile is stopped by a separate task, so it's also stopped if the request is dropped
+        let (tx, rx) = oneshot::channel();
+        self.inner.task_spawner.spawn(Box::pin(async move {
+            tokio::time::sleep(Duration::from_secs(seconds)).await;
+            let res = tokio::task::spawn_blocking(move || profile::stop_cpu_profile_with_id(id))
+                .await
+                .unwrap_or_else(|err| {
+                    Err(std::io::Error::new(std::io::ErrorKind::Other, err).into())
+                });
+            let _ = tx.send(res);
+        }));
+        rx.await
+            .map_err(|_| internal_rpc_err("profile task dropped"))?
+            .map_err(|err| internal_rpc_err(err.to_string()))?;
         Ok(())
     }
 
@@ -737,18 +1408,18 @@ where
 
     async fn debug_get_modified_accounts_by_hash(
         &self,
-        _start_hash: B256,
-        _end_hash: B256,
-    ) -> RpcResult<()> {
-        Ok(())
+        start_hash: B256,
+        end_hash: Option<B256>,
+    ) -> RpcResult<Vec<Address>> {
+        Ok(DebugApi::debug_get_modified_accounts_by_hash(self, start_hash, end_hash).await?)
     }
 
     async fn debug_get_modified_accounts_by_number(
         &self,
-        _start_number: u64,
-        _end_number: u64,
-    ) -> RpcResult<()> {
-        Ok(())
+        start_number: u64,
+        end_number: Option<u64>,
+    ) -> RpcResult<Vec<Address>> {
+        Ok(DebugApi::debug_get_modified_accounts_by_number(self, start_number, end_number).await?)
     }
 
     async fn debug_go_trace(&self, _file: String, _seconds: u64) -> RpcResult<()> {
@@ -757,10 +1428,11 @@ where
 
     async fn debug_intermediate_roots(
         &self,
-        _block_hash: B256,
+        block_hash: B256,
         _opts: Option<GethDebugTracingCallOptions>,
-    ) -> RpcResult<()> {
-        Ok(())
+    ) -> RpcResult<Vec<B256>> {
+        let _permit = self.acquire_trace_permit().await;
+        Ok(DebugApi::debug_intermediate_roots(self, block_hash).await?)
     }
 
     async fn debug_mem_stats(&self) -> RpcResult<()> {
@@ -791,8 +1463,9 @@ where
         Ok(())
     }
 
-    async fn debug_set_head(&self, _number: u64) -> RpcResult<()> {
-        Ok(())
+    /// Handler for `debug_setHead`
+    async fn debug_set_head(&self, number: u64) -> RpcResult<()> {
+        self.set_head(number).await
     }
 
     async fn debug_set_mutex_profile_fraction(&self, _rate: i32) -> RpcResult<()> {
@@ -809,21 +1482,32 @@ where
 
     async fn debug_standard_trace_bad_block_to_file(
         &self,
-        _block: BlockNumberOrTag,
-        _opts: Option<GethDebugTracingCallOptions>,
-    ) -> RpcResult<()> {
-        Ok(())
+        block_hash: B256,
+        opts: Option<StandardTraceConfig>,
+    ) -> RpcResult<Vec<String>> {
+        let _permit = self.acquire_trace_permit().await;
+        Ok(DebugApi::debug_standard_trace_bad_block_to_file(
+            self,
+            block_hash,
+            opts.unwrap_or_default(),
+        )
+        .await?)
     }
 
     async fn debug_standard_trace_block_to_file(
         &self,
-        _block: BlockNumberOrTag,
-        _opts: Option<GethDebugTracingCallOptions>,
-    ) -> RpcResult<()> {
-        Ok(())
+        block_hash: B256,
+        opts: Option<StandardTraceConfig>,
+    ) -> RpcResult<Vec<String>> {
+        let _permit = self.acquire_trace_permit().await;
+        Ok(DebugApi::debug_standard_trace_block_to_file(self, block_hash, opts.unwrap_or_default())
+            .await?)
     }
 
-    async fn debug_start_cpu_profile(&self, _file: String) -> RpcResult<()> {
+    /// Handler for `debug_startCPUProfile`
+    async fn debug_start_cpu_profile(&self, file: String) -> RpcResult<()> {
+        let file = self.profile_path(&file)?;
+        profile::start_cpu_profile(file).map_err(|err| internal_rpc_err(err.to_string()))?;
         Ok(())
     }
 
@@ -831,7 +1515,9 @@ where
         Ok(())
     }
 
+    /// Handler for `debug_stopCPUProfile`
     async fn debug_stop_cpu_profile(&self) -> RpcResult<()> {
+        self.on_profile_task(profile::stop_cpu_profile).await?;
         Ok(())
     }
 
@@ -841,13 +1527,22 @@ where
 
     async fn debug_storage_range_at(
         &self,
-        _block_hash: B256,
-        _tx_idx: usize,
-        _contract_address: Address,
-        _key_start: B256,
-        _max_result: u64,
-    ) -> RpcResult<()> {
-        Ok(())
+        block_hash: B256,
+        tx_idx: usize,
+        contract_address: Address,
+        key_start: B256,
+        max_result: u64,
+    ) -> RpcResult<StorageRangeResult> {
+        let _permit = self.acquire_trace_permit().await;
+        Ok(DebugApi::debug_storage_range_at(
+            self,
+            block_hash,
+            tx_idx,
+            contract_address,
+            key_start,
+            max_result,
+        )
+        .await?)
     }
 
     async fn debug_trace_bad_block(
@@ -858,11 +1553,21 @@ where
         Ok(())
     }
 
-    async fn debug_verbosity(&self, _level: usize) -> RpcResult<()> {
+    async fn debug_verbosity(&self, level: usize) -> RpcResult<()> {
+        let level = match level {
+            0 => LevelFilter::OFF,
+            1 => LevelFilter::ERROR,
+            2 => LevelFilter::WARN,
+            3 => LevelFilter::INFO,
+            4 => LevelFilter::DEBUG,
+            _ => LevelFilter::TRACE,
+        };
+        log_filter()?.set_verbosity(level).map_err(log_filter_rpc_err)?;
         Ok(())
     }
 
-    async fn debug_vmodule(&self, _pattern: String) -> RpcResult<()> {
+    async fn debug_vmodule(&self, pattern: String) -> RpcResult<()> {
+        log_filter()?.set_vmodule(&pattern).map_err(log_filter_rpc_err)?;
         Ok(())
     }
 
@@ -870,8 +1575,10 @@ where
         Ok(())
     }
 
-    async fn debug_write_mem_profile(&self, _file: String) -> RpcResult<()> {
-        Ok(())
+    /// Handler for `debug_writeMemProfile`
+    async fn debug_write_mem_profile(&self, file: String) -> RpcResult<()> {
+        let file = self.profile_path(&file)?;
+        self.on_profile_task(move || profile::write_mem_profile(&file)).await
     }
 
     async fn debug_write_mutex_profile(&self, _file: String) -> RpcResult<()> {
@@ -921,17 +1628,75 @@ where
     }
 
     /// Handler for `debug_getBadBlocks`
-    async fn bad_blocks(&self) -> RpcResult<Vec<RichBlock>> {
-        Err(internal_rpc_err("unimplemented"))
+    async fn bad_blocks(&self) -> RpcResult<Vec<BadBlock>> {
+        let bad_blocks = self.inner.provider.bad_blocks().to_rpc_result()?;
+
+        let mut res = Vec::with_capacity(bad_blocks.len());
+        for bad in bad_blocks {
+            let hash = bad.hash();
+            let block = bad.block.unseal();
+
+            // the block isn't part of the chain, so its total difficulty is derived from its parent
+            let total_difficulty = self
+                .inner
+                .provider
+                .header_td(&block.parent_hash)
+                .to_rpc_result()?
+                .unwrap_or_default() +
+                block.difficulty;
+
+            let mut rlp = Vec::new();
+            block.encode(&mut rlp);
+
+            // the block may have been rejected because of an invalid signature, in which case
+            // only the transaction hashes can be returned
+            let decoded = match from_block(
+                block.clone(),
+                total_difficulty,
+                BlockTransactionsKind::Full,
+                Some(hash),
+            ) {
+                Ok(decoded) => decoded,
+                Err(_) => from_block_with_tx_hashes(block, total_difficulty, Some(hash)),
+            };
+
+            let rejected_at =
+                bad.rejected_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            res.push(BadBlock {
+                hash,
+                block: decoded.into(),
+                rlp: rlp.into(),
+                validation_error: bad.error,
+                rejected_at: U64::from(rejected_at),
+            });
+        }
+
+        Ok(res)
     }
 
     /// Handler for `debug_traceChain`
     async fn debug_trace_chain(
         &self,
-        _start_exclusive: BlockNumberOrTag,
-        _end_inclusive: BlockNumberOrTag,
-    ) -> RpcResult<Vec<BlockTraceResult>> {
-        Err(internal_rpc_err("unimplemented"))
+        pending: PendingSubscriptionSink,
+        start_exclusive: BlockNumberOrTag,
+        end_inclusive: BlockNumberOrTag,
+        opts: Option<GethDebugTracingOptions>,
+    ) -> SubscriptionResult {
+        let range = match self.trace_chain_range(start_exclusive, end_inclusive) {
+            Ok(range) => range,
+            Err(err) => {
+                pending.reject(ErrorObject::from(err)).await;
+                return Ok(())
+            }
+        };
+
+        let sink = pending.accept().await?;
+        let this = self.clone();
+        self.inner.task_spawner.spawn(Box::pin(async move {
+            let _ = this.trace_chain(sink, range, opts.unwrap_or_default()).await;
+        }));
+
+        Ok(())
     }
 
     /// Handler for `debug_traceBlock`
@@ -997,6 +1762,61 @@ where
     }
 }
 
+/// Returns up to `limit` non-zero storage slots of the account, starting at the `start` key.
+///
+/// This merges the storage changes of the replayed transactions in the [CacheDB] with the storage
+/// of the underlying state.
+fn storage_range_at(
+    db: &SubState<StateProviderBox<'_>>,
+    address: Address,
+    start: B256,
+    limit: usize,
+) -> EthResult<StorageRangeResult> {
+    let (mut changed, storage_cleared) = match db.accounts.get(&address) {
+        Some(account) => (
+            account
+                .storage
+                .iter()
+                .map(|(key, value)| (B256::from(*key), *value))
+                .filter(|(key, _)| *key >= start)
+                .collect::<BTreeMap<_, _>>(),
+            matches!(
+                account.account_state,
+                AccountState::StorageCleared | AccountState::NotExisting
+            ),
+        ),
+        None => Default::default(),
+    };
+
+    let mut storage = BTreeMap::new();
+    let mut stored_next = None;
+    if !storage_cleared {
+        // at most all changed slots were cleared by the replayed transactions, so this is enough to
+        // fill the page
+        let (stored, next) =
+            db.db.state().storage_range(address, start, limit.saturating_add(changed.len()))?;
+        storage.extend(stored.into_iter().map(|entry| (entry.key, entry.value)));
+        stored_next = next;
+    }
+    if let Some(stored_next) = stored_next {
+        // the changed slots from where the stored storage stopped belong to the following pages
+        changed.retain(|key, _| *key < stored_next);
+    }
+    storage.extend(changed);
+
+    let mut slots = storage.into_iter().filter(|(_, value)| !value.is_zero());
+    let page = slots
+        .by_ref()
+        .take(limit)
+        .map(|(key, value)| {
+            (keccak256(key), StorageRangeEntry { key: Some(key), value: B256::from(value) })
+        })
+        .collect();
+    let next_key = slots.next().map(|(key, _)| key).or(stored_next);
+
+    Ok(StorageRangeResult { storage: page, next_key })
+}
+
 impl<Provider, Eth> std::fmt::Debug for DebugApi<Provider, Eth> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("DebugApi").finish_non_exhaustive()
@@ -1005,7 +1825,11 @@ impl<Provider, Eth> std::fmt::Debug for DebugApi<Provider, Eth> {
 
 impl<Provider, Eth> Clone for DebugApi<Provider, Eth> {
     fn clone(&self) -> Self {
-        Self { inner: Arc::clone(&self.inner) }
+        Self {
+            inner: Arc::clone(&self.inner),
+            beacon_engine: self.beacon_engine.clone(),
+            profile_dir: self.profile_dir.clone(),
+        }
     }
 }
 
@@ -1018,4 +1842,97 @@ struct DebugApiInner<Provider, Eth> {
     blocking_task_guard: BlockingTaskGuard,
     /// The type that can spawn tasks which would otherwise block.
     task_spawner: Box<dyn TaskSpawner>,
+    /// The maximum number of blocks a `debug_traceChain` subscription keeps in flight.
+    trace_chain_window: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        eth::{cache::EthStateCache, gas_oracle::GasPriceOracle},
+        BlockingTaskPool, EthApi,
+    };
+    use assert_matches::assert_matches;
+    use reth_interfaces::test_utils::generators::{self, random_block_range};
+    use reth_network_api::noop::NoopNetwork;
+    use reth_primitives::{constants::ETHEREUM_BLOCK_GAS_LIMIT, SealedBlock};
+    use reth_provider::test_utils::MockEthProvider;
+    use reth_tasks::TokioTaskExecutor;
+    use reth_transaction_pool::test_utils::{testing_pool, TestPool};
+
+    type TestDebugApi = DebugApi<MockEthProvider, EthApi<MockEthProvider, TestPool, NoopNetwork>>;
+
+    /// Creates the debug API for a provider that holds the given blocks.
+    fn debug_api<'a>(blocks: impl IntoIterator<Item = &'a SealedBlock>) -> TestDebugApi {
+        let provider = MockEthProvider::default();
+        provider
+            .extend_blocks(blocks.into_iter().map(|block| (block.hash, block.clone().unseal())));
+        let cache = EthStateCache::spawn(provider.clone(), Default::default());
+        let eth_api = EthApi::new(
+            provider.clone(),
+            testing_pool(),
+            NoopNetwork::default(),
+            cache.clone(),
+            GasPriceOracle::new(provider.clone(), Default::default(), cache),
+            ETHEREUM_BLOCK_GAS_LIMIT,
+            BlockingTaskPool::build().expect("failed to build tracing pool"),
+        );
+        DebugApi::new(
+            provider,
+            eth_api,
+            Box::<TokioTaskExecutor>::default(),
+            BlockingTaskGuard::new(4),
+            2,
+        )
+    }
+
+    /// Subscribes to `debug_traceChain` and collects all results until the subscription is
+    /// closed.
+    async fn trace_chain(api: TestDebugApi, start: u64, end: u64) -> Vec<BlockTraceResult> {
+        let module = api.into_rpc();
+        let params = (
+            BlockNumberOrTag::Number(start),
+            BlockNumberOrTag::Number(end),
+            None::<GethDebugTracingOptions>,
+        );
+        let mut subscription =
+            module.subscribe_unbounded("debug_traceChain", params).await.unwrap();
+
+        let mut results = Vec::new();
+        while let Some(result) = subscription.next::<BlockTraceResult>().await {
+            results.push(result.unwrap().0);
+        }
+        results
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn trace_chain_streams_blocks_in_order() {
+        let mut rng = generators::rng();
+        let blocks = random_block_range(&mut rng, 0..=10, B256::ZERO, 0..1);
+
+        let results = trace_chain(debug_api(&blocks), 2, 10).await;
+        assert_eq!(results.len(), 8);
+        for (result, block) in results.iter().zip(&blocks[3..]) {
+            assert_eq!(result.block, U256::from(block.number));
+            assert_eq!(result.hash, block.hash);
+            assert!(result.traces.is_empty());
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn trace_chain_stops_at_failed_block() {
+        let mut rng = generators::rng();
+        let blocks = random_block_range(&mut rng, 0..=10, B256::ZERO, 0..1);
+
+        // block 5 is missing, so it can't be traced
+        let results = trace_chain(debug_api(blocks.iter().filter(|b| b.number != 5)), 0, 10).await;
+        assert_eq!(results.len(), 5);
+        for (result, block) in results.iter().zip(&blocks[1..5]) {
+            assert_eq!(result.hash, block.hash);
+            assert!(result.traces.is_empty());
+        }
+        assert_eq!(results[4].block, U256::from(5));
+        assert_matches!(results[4].traces.as_slice(), [TraceResult::Error { .. }]);
+    }
 }
@@ -102,6 +102,7 @@ where
             eth_cache,
             gas_oracle,
             gas_cap.into().into(),
+            DEFAULT_ETH_PROOF_WINDOW,
             Box::<TokioTaskExecutor>::default(),
             blocking_task_pool,
         )
@@ -116,6 +117,7 @@ where
         eth_cache: EthStateCache,
         gas_oracle: GasPriceOracle<Provider>,
         gas_cap: u64,
+        eth_proof_window: u64,
         task_spawner: Box<dyn TaskSpawner>,
         blocking_task_pool: BlockingTaskPool,
     ) -> Self {
@@ -135,6 +137,7 @@ where
             eth_cache,
             gas_oracle,
             gas_cap,
+            eth_proof_window,
             starting_block: U256::from(latest_block),
             task_spawner,
             pending_block: Default::default(),
@@ -180,6 +183,11 @@ where
         self.inner.gas_cap
     }
 
+    /// Returns the maximum number of blocks `eth_getProof` can look back
+    pub fn eth_proof_window(&self) -> u64 {
+        self.inner.eth_proof_window
+    }
+
     /// Returns the inner `Provider`
     pub fn provider(&self) -> &Provider {
         &self.inner.provider
@@ -402,6 +410,13 @@ where
 /// more complex calls.
 pub const RPC_DEFAULT_GAS_CAP: GasCap = GasCap(50_000_000);
 
+/// The default maximum number of blocks between the latest block and the block `eth_getProof` is
+/// requested for, which is 28 days of 12 second blocks.
+///
+/// Historical proofs are generated by reverting the changesets of all blocks after the requested
+/// one, so the further back the block, the more changesets have to be loaded into memory.
+pub const DEFAULT_ETH_PROOF_WINDOW: u64 = 28 * 24 * 60 * 60 / 12;
+
 /// The wrapper type for gas limit
 #[derive(Debug, Clone, Copy)]
 pub struct GasCap(u64);
@@ -440,6 +455,8 @@ struct EthApiInner<Provider, Pool, Network> {
     gas_oracle: GasPriceOracle<Provider>,
     /// Maximum gas limit for `eth_call` and call tracing RPC methods.
     gas_cap: u64,
+    /// Maximum number of blocks `eth_getProof` can look back from the latest block.
+    eth_proof_window: u64,
     /// The block number at which the node started
     starting_block: U256,
     /// The type that can spawn tasks which would otherwise block.
@@ -8,7 +8,8 @@ use reth_primitives::{
     serde_helper::JsonStorageKey, Address, BlockId, BlockNumberOrTag, Bytes, B256, U256,
 };
 use reth_provider::{
-    BlockReaderIdExt, ChainSpecProvider, EvmEnvProvider, StateProvider, StateProviderFactory,
+    BlockIdReader, BlockNumReader, BlockReaderIdExt, ChainSpecProvider, EvmEnvProvider,
+    StateProvider, StateProviderFactory,
 };
 use reth_rpc_types::EIP1186AccountProofResponse;
 use reth_rpc_types_compat::proof::from_primitive_account_proof;
@@ -89,22 +90,14 @@ where
         keys: Vec<JsonStorageKey>,
         block_id: Option<BlockId>,
     ) -> EthResult<EIP1186AccountProofResponse> {
-        let chain_info = self.provider().chain_info()?;
         let block_id = block_id.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest));
 
-        // if we are trying to create a proof for the latest block, but have a BlockId as input
-        // that is not BlockNumberOrTag::Latest, then we need to figure out whether or not the
-        // BlockId corresponds to the latest block
-        let is_latest_block = match block_id {
-            BlockId::Number(BlockNumberOrTag::Number(num)) => num == chain_info.best_number,
-            BlockId::Hash(hash) => hash == chain_info.best_hash.into(),
-            BlockId::Number(BlockNumberOrTag::Latest) => true,
-            _ => false,
-        };
-
-        // TODO: remove when HistoricalStateProviderRef::proof is implemented
-        if !is_latest_block {
-            return Err(EthApiError::InvalidBlockRange)
+        // blocks that aren't canonical yet, e.g. the pending block, are always within the window
+        if let Some(block_number) = self.provider().block_number_for_id(block_id)? {
+            let best_block_number = self.provider().best_block_number()?;
+            if best_block_number.saturating_sub(block_number) > self.eth_proof_window() {
+                return Err(EthApiError::ExceedsMaxProofWindow)
+            }
         }
 
         let this = self.clone();
@@ -125,12 +118,27 @@ where
 mod tests {
     use super::*;
     use crate::{
-        eth::{cache::EthStateCache, gas_oracle::GasPriceOracle},
+        eth::{cache::EthStateCache, gas_oracle::GasPriceOracle, DEFAULT_ETH_PROOF_WINDOW},
         BlockingTaskPool,
     };
-    use reth_primitives::{constants::ETHEREUM_BLOCK_GAS_LIMIT, StorageKey, StorageValue};
-    use reth_provider::test_utils::{ExtendedAccount, MockEthProvider, NoopProvider};
+    use assert_matches::assert_matches;
+    use reth_blockchain_tree::noop::NoopBlockchainTree;
+    use reth_db::{
+        models::AccountBeforeTx, tables, test_utils::create_test_rw_db, transaction::DbTxMut,
+    };
+    use reth_primitives::{
+        constants::ETHEREUM_BLOCK_GAS_LIMIT,
+        keccak256,
+        stage::{StageCheckpoint, StageId},
+        Account, Header, StorageEntry, StorageKey, StorageValue, MAINNET,
+    };
+    use reth_provider::{
+        providers::BlockchainProvider,
+        test_utils::{ExtendedAccount, MockEthProvider, NoopProvider},
+        ProviderFactory, StageCheckpointWriter,
+    };
     use reth_transaction_pool::test_utils::testing_pool;
+    use reth_trie::StateRoot;
     use std::collections::HashMap;
 
     #[tokio::test]
@@ -175,4 +183,120 @@ mod tests {
         let storage = eth_api.storage_at(address, storage_key.into(), None).unwrap();
         assert_eq!(storage, storage_value.to_be_bytes());
     }
+
+    #[tokio::test]
+    async fn test_historical_proof() {
+        let factory = ProviderFactory::new(create_test_rw_db(), MAINNET.clone());
+        let address = Address::random();
+        let hashed_address = keccak256(address);
+        let slot = B256::random();
+
+        // The account is created with the slot set to 1 at block 0, block 1 bumps its nonce and
+        // sets the slot to 2.
+        let mut expected_proofs = Vec::new();
+        for block_number in 0..=1u64 {
+            let provider = factory.provider_rw().unwrap();
+            let tx = provider.tx_ref();
+            let header = Header { number: block_number, ..Default::default() }.seal_slow();
+            tx.put::<tables::CanonicalHeaders>(block_number, header.hash()).unwrap();
+            tx.put::<tables::HeaderNumbers>(header.hash(), block_number).unwrap();
+            tx.put::<tables::Headers>(block_number, header.unseal()).unwrap();
+
+            let account =
+                Account { nonce: block_number, balance: U256::from(1), bytecode_hash: None };
+            let value = U256::from(block_number + 1);
+            if block_number > 0 {
+                tx.put::<tables::AccountChangeSet>(
+                    block_number,
+                    AccountBeforeTx {
+                        address,
+                        info: Some(Account { nonce: block_number - 1, ..account }),
+                    },
+                )
+                .unwrap();
+                tx.put::<tables::StorageChangeSet>(
+                    (block_number, address).into(),
+                    StorageEntry { key: slot, value: U256::from(block_number) },
+                )
+                .unwrap();
+                tx.delete::<tables::HashedStorage>(
+                    hashed_address,
+                    Some(StorageEntry { key: keccak256(slot), value: U256::from(block_number) }),
+                )
+                .unwrap();
+            }
+            tx.put::<tables::HashedAccount>(hashed_address, account).unwrap();
+            tx.put::<tables::HashedStorage>(
+                hashed_address,
+                StorageEntry { key: keccak256(slot), value },
+            )
+            .unwrap();
+
+            let (_, updates) = if block_number == 0 {
+                StateRoot::new(tx).root_with_updates().unwrap()
+            } else {
+                StateRoot::incremental_root_with_updates(tx, block_number..=block_number).unwrap()
+            };
+            updates.flush(tx).unwrap();
+
+            for stage_id in [
+                StageId::AccountHashing,
+                StageId::StorageHashing,
+                StageId::MerkleExecute,
+                StageId::Finish,
+            ] {
+                provider
+                    .save_stage_checkpoint(stage_id, StageCheckpoint::new(block_number))
+                    .unwrap();
+            }
+            provider.commit().unwrap();
+
+            let proof = factory.latest().unwrap().proof(address, &[slot]).unwrap();
+            expected_proofs.push(from_primitive_account_proof(proof));
+        }
+        assert_ne!(expected_proofs[0], expected_proofs[1]);
+
+        let provider =
+            BlockchainProvider::new(factory.clone(), NoopBlockchainTree::default()).unwrap();
+        let cache = EthStateCache::spawn(provider.clone(), Default::default());
+        let eth_api = EthApi::new(
+            provider.clone(),
+            testing_pool(),
+            (),
+            cache.clone(),
+            GasPriceOracle::new(provider, Default::default(), cache),
+            ETHEREUM_BLOCK_GAS_LIMIT,
+            BlockingTaskPool::build().expect("failed to build tracing pool"),
+        );
+
+        let keys = vec![JsonStorageKey(slot)];
+        for (block_number, expected_proof) in expected_proofs.iter().enumerate() {
+            let block_id = Some(BlockId::Number((block_number as u64).into()));
+            let proof = eth_api.get_proof(address, keys.clone(), block_id).await.unwrap();
+            assert_eq!(&proof, expected_proof);
+        }
+
+        // the trie lags behind the hashed state, so the changesets can't be reverted on top of it
+        let provider = factory.provider_rw().unwrap();
+        provider.save_stage_checkpoint(StageId::MerkleExecute, StageCheckpoint::new(0)).unwrap();
+        provider.commit().unwrap();
+        assert!(eth_api
+            .get_proof(address, keys.clone(), Some(BlockId::Number(0.into())))
+            .await
+            .is_err());
+
+        // the block is too far behind the latest one
+        let provider = factory.provider_rw().unwrap();
+        provider
+            .save_stage_checkpoint(
+                StageId::Finish,
+                StageCheckpoint::new(DEFAULT_ETH_PROOF_WINDOW + 1),
+            )
+            .unwrap();
+        provider.commit().unwrap();
+        assert_matches!(
+            eth_api.get_proof(address, keys, Some(BlockId::Number(0.into()))).await,
+            Err(EthApiError::ExceedsMaxProofWindow)
+        );
+    }
 }
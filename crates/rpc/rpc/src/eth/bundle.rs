@@ -7,17 +7,48 @@ use crate::{
         utils::recover_raw_transaction,
         EthTransactions,
     },
-    BlockingTaskGuard,
+    BlockingTaskGuard, EthApiSpec,
+};
+use async_trait::async_trait;
+use jsonrpsee::core::RpcResult;
+use reth_payload_builder::bundle::{BundlePool, PoolBundle};
+use reth_primitives::{
+    keccak256, Address, BlockId, BlockNumberOrTag, Bytes, TransactionSignedEcRecovered, B256, U256,
+    U64,
 };
-use reth_primitives::{keccak256, U256};
 use reth_revm::database::StateProviderDatabase;
-use reth_rpc_types::{EthCallBundle, EthCallBundleResponse, EthCallBundleTransactionResult};
+use reth_rpc_api::EthBundleApiServer;
+use reth_rpc_types::{
+    BundleItem, CancelBundleRequest, CancelPrivateTransactionRequest, EthBundleHash, EthCallBundle,
+    EthCallBundleResponse, EthCallBundleTransactionResult, EthSendBundle, Inclusion, Log,
+    PrivateTransactionRequest, Refund, RefundConfig, SendBundleRequest, SimBundleLogs,
+    SimBundleOverrides, SimBundleResponse,
+};
 use revm::{
     db::CacheDB,
-    primitives::{Env, ResultAndState, TxEnv},
+    primitives::{EVMError, Env, ResultAndState, TxEnv},
+};
+use revm_primitives::db::{Database, DatabaseCommit, DatabaseRef};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
 };
-use revm_primitives::db::{DatabaseCommit, DatabaseRef};
-use std::sync::Arc;
+
+/// The maximum nesting depth of mev-share bundles, same as the MEV-share node.
+pub(crate) const MAX_NESTED_BUNDLE_DEPTH: usize = 1;
+
+/// The maximum number of body items of a mev-share bundle, same as the MEV-share node.
+pub(crate) const MAX_BUNDLE_BODY_SIZE: usize = 50;
+
+/// The gas reserved for each refund payout transaction of a mev-share bundle.
+const REFUND_PAYOUT_GAS: u64 = 30_000;
+
+/// The maximum number of blocks a bundle sent via `eth_sendBundle` or `mev_sendBundle` can target
+/// ahead of the tip.
+pub(crate) const MAX_BUNDLE_BLOCKS_AHEAD: u64 = 25;
+
+/// The default timeout of a `mev_simBundle` simulation.
+const DEFAULT_SIM_BUNDLE_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// `Eth` bundle implementation.
 pub struct EthBundle<Eth> {
@@ -27,8 +58,102 @@ pub struct EthBundle<Eth> {
 
 impl<Eth> EthBundle<Eth> {
     /// Create a new `EthBundle` instance.
-    pub fn new(eth_api: Eth, blocking_task_guard: BlockingTaskGuard) -> Self {
-        Self { inner: Arc::new(EthBundleInner { eth_api, blocking_task_guard }) }
+    ///
+    /// Bundles sent via `eth_sendBundle` are added to the given [BundlePool].
+    pub fn new(
+        eth_api: Eth,
+        blocking_task_guard: BlockingTaskGuard,
+        bundle_pool: BundlePool,
+    ) -> Self {
+        Self { inner: Arc::new(EthBundleInner { eth_api, blocking_task_guard, bundle_pool }) }
+    }
+
+    /// Returns the `eth` API the bundles are simulated with.
+    pub fn eth_api(&self) -> &Eth {
+        &self.inner.eth_api
+    }
+
+    /// Returns the pool of bundles sent via `eth_sendBundle`.
+    pub fn bundle_pool(&self) -> &BundlePool {
+        &self.inner.bundle_pool
+    }
+
+    /// Removes the bundle with the replacement UUID of the request from the pool.
+    pub fn cancel_bundle(&self, request: CancelBundleRequest) {
+        self.inner.bundle_pool.cancel_bundle(&request.replacement_uuid);
+    }
+}
+
+impl<Eth> EthBundle<Eth>
+where
+    Eth: EthApiSpec + 'static,
+{
+    /// Validates the bundle and adds it to the [BundlePool], from which it is included in payloads
+    /// built for its target block.
+    ///
+    /// The bundle must target one of the next [MAX_BUNDLE_BLOCKS_AHEAD] blocks. A bundle with a
+    /// replacement UUID replaces the bundle that was sent with the same UUID.
+    pub fn send_bundle(&self, bundle: EthSendBundle) -> EthResult<EthBundleHash> {
+        let EthSendBundle {
+            txs,
+            block_number,
+            min_timestamp,
+            max_timestamp,
+            reverting_tx_hashes,
+            replacement_uuid,
+        } = bundle;
+        if txs.is_empty() {
+            return Err(EthApiError::InvalidParams(
+                EthBundleError::EmptyBundleTransactions.to_string(),
+            ))
+        }
+        let block_number = block_number.to::<u64>();
+        if block_number == 0 {
+            return Err(EthApiError::InvalidParams(
+                EthBundleError::BundleMissingBlockNumber.to_string(),
+            ))
+        }
+        if matches!((min_timestamp, max_timestamp), (Some(min), Some(max)) if min > max) {
+            return Err(EthApiError::InvalidParams(
+                EthBundleError::InvalidTimestampRange.to_string(),
+            ))
+        }
+
+        let best_number = self.inner.eth_api.chain_info()?.best_number;
+        if block_number <= best_number {
+            return Err(EthApiError::InvalidParams(
+                EthBundleError::BundleNotIncludable(block_number).to_string(),
+            ))
+        }
+        if block_number - best_number > MAX_BUNDLE_BLOCKS_AHEAD {
+            return Err(EthApiError::InvalidParams(
+                EthBundleError::BundleTooFarAhead(MAX_BUNDLE_BLOCKS_AHEAD).to_string(),
+            ))
+        }
+
+        let txs = txs
+            .into_iter()
+            .map(|tx| recover_raw_transaction(tx).map(|tx| tx.into_ecrecovered_transaction()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(tx) = txs.iter().find(|tx| tx.is_eip4844()) {
+            return Err(EthApiError::InvalidParams(
+                EthBundleError::BlobTransactionNotSupported(tx.hash).to_string(),
+            ))
+        }
+
+        let pool = &self.inner.bundle_pool;
+        pool.remove_expired(best_number);
+        let bundle_hash = pool
+            .add_bundle(PoolBundle {
+                txs,
+                block_number,
+                min_timestamp,
+                max_timestamp,
+                reverting_tx_hashes,
+                replacement_uuid,
+            })
+            .map_err(|err| EthApiError::InvalidParams(err.to_string()))?;
+        Ok(EthBundleHash { bundle_hash })
     }
 }
 
@@ -170,6 +295,451 @@ where
             })
             .await
     }
+
+    /// Simulates a mev-share bundle on top of the given parent block, see `mev_simBundle`.
+    ///
+    /// Nested bundles are executed in place. The value a bundle pays to the coinbase is refunded
+    /// to the body items listed in its `validity.refund`: to the addresses of the refunded
+    /// bundle's `refundConfig`, or to the sender of the refunded transaction.
+    ///
+    /// Only fully matched bundles, that don't contain any transaction hashes, can be simulated.
+    /// If the bundle fails, e.g. because a transaction that is not allowed to revert reverted,
+    /// the error is returned as part of the response.
+    pub async fn sim_bundle(
+        &self,
+        bundle: SendBundleRequest,
+        overrides: SimBundleOverrides,
+    ) -> EthResult<SimBundleResponse> {
+        validate_mev_bundle(&bundle, 0)
+            .map_err(|err| EthApiError::InvalidParams(err.to_string()))?;
+        let bundle = MevBundle::try_from_request(bundle)?;
+
+        let SimBundleOverrides {
+            parent_block,
+            block_number,
+            coinbase,
+            timestamp,
+            gas_limit,
+            base_fee,
+            timeout,
+        } = overrides;
+        let parent_block = parent_block.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest));
+        let (cfg, mut block_env, at) = self.inner.eth_api.evm_env_at(parent_block).await?;
+
+        // the block header is derived from the parent block by default
+        let state_block = block_env.number.to::<u64>();
+        let block_number = block_number.map(|number| number.to()).unwrap_or(state_block + 1);
+        block_env.number = U256::from(block_number);
+        block_env.timestamp = match timestamp {
+            Some(timestamp) => U256::from(timestamp.to::<u64>()),
+            None => block_env.timestamp + U256::from(12),
+        };
+        if let Some(coinbase) = coinbase {
+            block_env.coinbase = coinbase;
+        }
+        if let Some(gas_limit) = gas_limit {
+            block_env.gas_limit = U256::from(gas_limit.to::<u64>());
+        }
+        if let Some(base_fee) = base_fee {
+            block_env.basefee = U256::from(base_fee.to::<u64>());
+        }
+
+        let timeout = timeout
+            .map(|timeout| Duration::from_secs(timeout.to()))
+            .unwrap_or(DEFAULT_SIM_BUNDLE_TIMEOUT);
+        let ctx = MevSimContext {
+            block_number,
+            coinbase: block_env.coinbase,
+            basefee: block_env.basefee,
+            deadline: Instant::now() + timeout,
+            timeout,
+        };
+
+        let res = self
+            .inner
+            .eth_api
+            .spawn_with_state_at_block(at, move |state| {
+                let env = Env { cfg, block: block_env, tx: TxEnv::default() };
+                let mut evm = revm::EVM::with_env(env);
+                evm.database(CacheDB::new(StateProviderDatabase::new(state)));
+
+                match ctx.simulate(&mut evm, &bundle) {
+                    Ok(outcome) => Ok(Ok(outcome)),
+                    Err(MevSimError::Bundle(err)) => Ok(Err(err)),
+                    Err(MevSimError::Eth(err)) => Err(err),
+                }
+            })
+            .await?;
+
+        let response = match res {
+            Ok(outcome) => SimBundleResponse {
+                success: true,
+                error: None,
+                state_block: U64::from(state_block),
+                mev_gas_price: outcome
+                    .profit
+                    .checked_div(U256::from(outcome.gas_used))
+                    .unwrap_or_default(),
+                profit: outcome.profit,
+                refundable_value: outcome.refundable_value,
+                gas_used: U64::from(outcome.gas_used),
+                logs: Some(outcome.logs),
+            },
+            Err(err) => SimBundleResponse {
+                success: false,
+                error: Some(err.to_string()),
+                state_block: U64::from(state_block),
+                mev_gas_price: U256::ZERO,
+                profit: U256::ZERO,
+                refundable_value: U256::ZERO,
+                gas_used: U64::ZERO,
+                logs: None,
+            },
+        };
+        Ok(response)
+    }
+}
+
+/// Returns the hash of a mev-share bundle, which is the hash of the hashes of its body items.
+pub(crate) fn mev_bundle_hash(bundle: &SendBundleRequest) -> B256 {
+    let mut hash_bytes = Vec::with_capacity(32 * bundle.bundle_body.len());
+    for item in &bundle.bundle_body {
+        let hash = match item {
+            BundleItem::Hash { hash } => *hash,
+            BundleItem::Tx { tx, .. } => keccak256(tx),
+            BundleItem::Bundle { bundle } => mev_bundle_hash(bundle),
+        };
+        hash_bytes.extend_from_slice(hash.as_slice());
+    }
+    keccak256(&hash_bytes)
+}
+
+/// Checks that the mev-share bundle at the given nesting depth is well-formed.
+pub(crate) fn validate_mev_bundle(
+    bundle: &SendBundleRequest,
+    depth: usize,
+) -> Result<(), EthBundleError> {
+    if depth > MAX_NESTED_BUNDLE_DEPTH {
+        return Err(EthBundleError::BundleTooDeep)
+    }
+    if bundle.bundle_body.is_empty() {
+        return Err(EthBundleError::EmptyBundleTransactions)
+    }
+    if bundle.bundle_body.len() > MAX_BUNDLE_BODY_SIZE {
+        return Err(EthBundleError::BundleTooLarge)
+    }
+
+    let Inclusion { block, max_block } = bundle.inclusion;
+    if block == U64::ZERO {
+        return Err(EthBundleError::BundleMissingBlockNumber)
+    }
+    if max_block.is_some_and(|max_block| max_block < block) {
+        return Err(EthBundleError::InvalidInclusion)
+    }
+
+    if let Some(validity) = &bundle.validity {
+        let refunds = validity.refund.as_deref().unwrap_or_default();
+        if refunds.iter().any(|refund| refund.body_idx as usize >= bundle.bundle_body.len()) {
+            return Err(EthBundleError::InvalidRefund)
+        }
+        if refunds.iter().map(|refund| refund.percent).sum::<u64>() > 100 {
+            return Err(EthBundleError::InvalidRefund)
+        }
+        if let Some(configs) = validity.refund_config.as_deref().filter(|c| !c.is_empty()) {
+            if configs.iter().map(|config| config.percent).sum::<u64>() != 100 {
+                return Err(EthBundleError::InvalidRefundConfig)
+            }
+        }
+    }
+
+    for item in &bundle.bundle_body {
+        if let BundleItem::Bundle { bundle } = item {
+            validate_mev_bundle(bundle, depth + 1)?;
+        }
+    }
+    Ok(())
+}
+
+/// A fully matched mev-share bundle with recovered transactions.
+#[derive(Debug)]
+struct MevBundle {
+    /// The blocks the bundle is valid for.
+    inclusion: Inclusion,
+    /// The transactions and nested bundles of the bundle.
+    body: Vec<MevBundleItem>,
+    /// The body items that receive a share of the bundle's value.
+    refunds: Vec<Refund>,
+    /// The addresses the refund of this bundle is paid to, if it's nested in another bundle.
+    refund_config: Vec<RefundConfig>,
+}
+
+/// An item of a [MevBundle].
+#[derive(Debug)]
+enum MevBundleItem {
+    /// A transaction.
+    Tx {
+        /// The recovered transaction.
+        tx: TransactionSignedEcRecovered,
+        /// Whether the transaction is allowed to revert.
+        can_revert: bool,
+    },
+    /// A nested bundle.
+    Bundle(MevBundle),
+}
+
+impl MevBundle {
+    /// Recovers all transactions of the bundle.
+    ///
+    /// Returns an error if the bundle contains transaction hashes.
+    fn try_from_request(bundle: SendBundleRequest) -> EthResult<Self> {
+        let SendBundleRequest { inclusion, bundle_body, validity, .. } = bundle;
+        let body = bundle_body
+            .into_iter()
+            .map(|item| match item {
+                BundleItem::Hash { hash } => Err(EthApiError::InvalidParams(
+                    EthBundleError::UnmatchedBundleTransaction(hash).to_string(),
+                )),
+                BundleItem::Tx { tx, can_revert } => Ok(MevBundleItem::Tx {
+                    tx: recover_raw_transaction(tx)?.into_ecrecovered_transaction(),
+                    can_revert,
+                }),
+                BundleItem::Bundle { bundle } => {
+                    Self::try_from_request(bundle).map(MevBundleItem::Bundle)
+                }
+            })
+            .collect::<EthResult<_>>()?;
+        let validity = validity.unwrap_or_default();
+        Ok(Self {
+            inclusion,
+            body,
+            refunds: validity.refund.unwrap_or_default(),
+            refund_config: validity.refund_config.unwrap_or_default(),
+        })
+    }
+
+    /// Returns the addresses and their share of the refunds paid to this bundle.
+    ///
+    /// Defaults to the sender of the bundle's first transaction.
+    fn refund_recipients(&self) -> Vec<RefundConfig> {
+        if !self.refund_config.is_empty() {
+            return self.refund_config.clone()
+        }
+        self.body
+            .first()
+            .map(|item| match item {
+                MevBundleItem::Tx { tx, .. } => {
+                    vec![RefundConfig { address: tx.signer(), percent: 100 }]
+                }
+                MevBundleItem::Bundle(bundle) => bundle.refund_recipients(),
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// The environment a [MevBundle] is simulated in.
+#[derive(Debug, Clone, Copy)]
+struct MevSimContext {
+    /// The number of the simulated block.
+    block_number: u64,
+    /// The coinbase of the simulated block.
+    coinbase: Address,
+    /// The base fee of the simulated block.
+    basefee: U256,
+    /// The simulation is aborted after this instant.
+    deadline: Instant,
+    /// The configured timeout, for error reporting.
+    timeout: Duration,
+}
+
+/// The result of a simulated [MevBundle].
+#[derive(Debug, Default)]
+struct MevSimOutcome {
+    /// The gas used by the transactions of the bundle and the refund payouts.
+    gas_used: u64,
+    /// The value paid to the coinbase, minus refunds.
+    profit: U256,
+    /// The value paid to the coinbase by the body items that don't receive a refund.
+    refundable_value: U256,
+    /// The logs of all body items.
+    logs: Vec<SimBundleLogs>,
+}
+
+/// Errors that can occur while simulating a [MevBundle].
+#[derive(Debug)]
+enum MevSimError {
+    /// The bundle failed, this is reported in the response.
+    Bundle(EthBundleError),
+    /// The simulation failed.
+    Eth(EthApiError),
+}
+
+impl From<EthBundleError> for MevSimError {
+    fn from(err: EthBundleError) -> Self {
+        MevSimError::Bundle(err)
+    }
+}
+
+impl From<EthApiError> for MevSimError {
+    fn from(err: EthApiError) -> Self {
+        MevSimError::Eth(err)
+    }
+}
+
+impl MevSimContext {
+    /// Executes the bundle and commits its state changes.
+    fn simulate<DB>(
+        &self,
+        evm: &mut revm::EVM<CacheDB<DB>>,
+        bundle: &MevBundle,
+    ) -> Result<MevSimOutcome, MevSimError>
+    where
+        DB: DatabaseRef,
+        DB::Error: Into<EthApiError>,
+    {
+        let Inclusion { block, max_block } = bundle.inclusion;
+        if self.block_number < block.to::<u64>() ||
+            self.block_number > max_block.unwrap_or(block).to::<u64>()
+        {
+            return Err(EthBundleError::BundleNotIncludable(self.block_number).into())
+        }
+
+        let mut outcome = MevSimOutcome::default();
+        for (idx, item) in bundle.body.iter().enumerate() {
+            let (profit, gas_used, logs) = match item {
+                MevBundleItem::Tx { tx, can_revert } => {
+                    if Instant::now() > self.deadline {
+                        return Err(EthBundleError::SimulationTimedOut(self.timeout).into())
+                    }
+                    let db = evm.db.as_mut().expect("is set");
+                    let coinbase_balance_before = Database::basic(db, self.coinbase)
+                        .map_err(Into::into)?
+                        .map(|acc| acc.balance)
+                        .unwrap_or_default();
+
+                    tx.try_fill_tx_env(&mut evm.env.tx)?;
+                    let ResultAndState { result, state } = match evm.transact() {
+                        Ok(res) => res,
+                        Err(err @ EVMError::Transaction(_)) => {
+                            let reason = EthApiError::from(err).to_string();
+                            return Err(
+                                EthBundleError::InvalidBundleTransaction(tx.hash(), reason).into()
+                            )
+                        }
+                        Err(err) => return Err(EthApiError::from(err).into()),
+                    };
+                    if !result.is_success() && !can_revert {
+                        return Err(EthBundleError::BundleTransactionFailed(tx.hash()).into())
+                    }
+
+                    // coinbase is always present in the result state
+                    let coinbase_balance_after =
+                        state.get(&self.coinbase).map(|acc| acc.info.balance).unwrap_or_default();
+                    let logs = result
+                        .logs()
+                        .into_iter()
+                        .map(|log| Log {
+                            address: log.address,
+                            topics: log.topics,
+                            data: log.data,
+                            block_hash: None,
+                            block_number: Some(U256::from(self.block_number)),
+                            transaction_hash: Some(tx.hash()),
+                            transaction_index: None,
+                            log_index: None,
+                            removed: false,
+                        })
+                        .collect();
+                    let gas_used = result.gas_used();
+                    evm.db.as_mut().expect("is set").commit(state);
+
+                    (
+                        coinbase_balance_after.saturating_sub(coinbase_balance_before),
+                        gas_used,
+                        SimBundleLogs { tx_logs: Some(logs), bundle_logs: None },
+                    )
+                }
+                MevBundleItem::Bundle(nested) => {
+                    let nested = self.simulate(evm, nested)?;
+                    (
+                        nested.profit,
+                        nested.gas_used,
+                        SimBundleLogs { tx_logs: None, bundle_logs: Some(nested.logs) },
+                    )
+                }
+            };
+
+            outcome.profit += profit;
+            outcome.gas_used += gas_used;
+            outcome.logs.push(logs);
+            // the value of refunded items is not shared with them
+            if !bundle.refunds.iter().any(|refund| refund.body_idx as usize == idx) {
+                outcome.refundable_value += profit;
+            }
+        }
+
+        for refund in &bundle.refunds {
+            let recipients = match &bundle.body[refund.body_idx as usize] {
+                MevBundleItem::Tx { tx, .. } => {
+                    vec![RefundConfig { address: tx.signer(), percent: 100 }]
+                }
+                MevBundleItem::Bundle(nested) => nested.refund_recipients(),
+            };
+            let payout_gas = REFUND_PAYOUT_GAS * recipients.len() as u64;
+            let payout = outcome.refundable_value * U256::from(refund.percent) / U256::from(100);
+            // the payout transactions are paid for from the refund
+            if payout < self.basefee * U256::from(payout_gas) {
+                return Err(EthBundleError::RefundNotCovered.into())
+            }
+            outcome.profit =
+                outcome.profit.checked_sub(payout).ok_or(EthBundleError::RefundNotCovered)?;
+            outcome.gas_used += payout_gas;
+        }
+
+        Ok(outcome)
+    }
+}
+
+#[async_trait]
+impl<Eth> EthBundleApiServer for EthBundle<Eth>
+where
+    Eth: EthTransactions + EthApiSpec + 'static,
+{
+    /// Handler for `eth_sendBundle`
+    async fn send_bundle(&self, bundle: EthSendBundle) -> RpcResult<EthBundleHash> {
+        Ok(EthBundle::send_bundle(self, bundle)?)
+    }
+
+    /// Handler for `eth_callBundle`
+    async fn call_bundle(&self, request: EthCallBundle) -> RpcResult<EthCallBundleResponse> {
+        Ok(EthBundle::call_bundle(self, request).await?)
+    }
+
+    /// Handler for `eth_cancelBundle`
+    async fn cancel_bundle(&self, request: CancelBundleRequest) -> RpcResult<()> {
+        EthBundle::cancel_bundle(self, request);
+        Ok(())
+    }
+
+    /// Handler for `eth_sendPrivateTransaction`
+    async fn send_private_transaction(
+        &self,
+        _request: PrivateTransactionRequest,
+    ) -> RpcResult<B256> {
+        Err(EthApiError::Unsupported("eth_sendPrivateTransaction is not supported").into())
+    }
+
+    /// Handler for `eth_sendPrivateRawTransaction`
+    async fn send_private_raw_transaction(&self, _bytes: Bytes) -> RpcResult<B256> {
+        Err(EthApiError::Unsupported("eth_sendPrivateRawTransaction is not supported").into())
+    }
+
+    /// Handler for `eth_cancelPrivateTransaction`
+    async fn cancel_private_transaction(
+        &self,
+        _request: CancelPrivateTransactionRequest,
+    ) -> RpcResult<bool> {
+        Err(EthApiError::Unsupported("eth_cancelPrivateTransaction is not supported").into())
+    }
 }
 
 /// Container type for  `EthBundle` internals
@@ -180,6 +750,8 @@ struct EthBundleInner<Eth> {
     // restrict the number of concurrent tracing calls.
     #[allow(unused)]
     blocking_task_guard: BlockingTaskGuard,
+    /// Bundles sent via `eth_sendBundle`.
+    bundle_pool: BundlePool,
 }
 
 impl<Eth> std::fmt::Debug for EthBundle<Eth> {
@@ -203,4 +775,46 @@ pub enum EthBundleError {
     /// Thrown if the bundle does not contain a block number, or block number is 0.
     #[error("bundle missing blockNumber")]
     BundleMissingBlockNumber,
+    /// Thrown if the bundle's max block is lower than its block.
+    #[error("invalid inclusion")]
+    InvalidInclusion,
+    /// Thrown if the bundle has too many body items.
+    #[error("bundle body is too large")]
+    BundleTooLarge,
+    /// Thrown if the bundle contains too many levels of nested bundles.
+    #[error("bundle is too deep")]
+    BundleTooDeep,
+    /// Thrown if a refund refers to a missing body item or the refund percentages exceed 100.
+    #[error("invalid refund")]
+    InvalidRefund,
+    /// Thrown if the refund config percentages don't sum up to 100.
+    #[error("invalid refund config")]
+    InvalidRefundConfig,
+    /// Thrown if a bundle to simulate contains a transaction hash that can't be matched.
+    #[error("unmatched bundle transaction {0}")]
+    UnmatchedBundleTransaction(B256),
+    /// Thrown if the simulated block is not in the inclusion range of a bundle.
+    #[error("bundle is not valid for block {0}")]
+    BundleNotIncludable(u64),
+    /// Thrown if a bundle transaction is invalid.
+    #[error("invalid bundle transaction {0}: {1}")]
+    InvalidBundleTransaction(B256, String),
+    /// Thrown if a bundle transaction that is not allowed to revert failed.
+    #[error("bundle transaction {0} failed")]
+    BundleTransactionFailed(B256),
+    /// Thrown if a refund does not cover the cost of its payout transactions.
+    #[error("refund does not cover payout cost")]
+    RefundNotCovered,
+    /// Thrown if the simulation took longer than the timeout.
+    #[error("simulation timed out after {0:?}")]
+    SimulationTimedOut(Duration),
+    /// Thrown if the bundle's min timestamp is greater than its max timestamp.
+    #[error("invalid timestamp range")]
+    InvalidTimestampRange,
+    /// Thrown if a bundle sent for inclusion targets a block too far ahead of the tip.
+    #[error("bundle blockNumber is more than {0} blocks ahead")]
+    BundleTooFarAhead(u64),
+    /// Thrown if a bundle sent for inclusion contains a blob transaction.
+    #[error("blob transaction {0} can't be bundled")]
+    BlobTransactionNotSupported(B256),
 }
@@ -78,6 +78,9 @@ pub enum EthApiError {
     /// General purpose error for invalid params
     #[error("{0}")]
     InvalidParams(String),
+    /// Thrown when `eth_getProof` is requested for a block too far behind the latest block
+    #[error("distance to target block exceeds maximum proof window")]
+    ExceedsMaxProofWindow,
     /// When tracer config does not match the tracer
     #[error("invalid tracer config")]
     InvalidTracerConfig,
@@ -129,6 +132,7 @@ impl From<EthApiError> for ErrorObject<'static> {
             EthApiError::ConflictingFeeFieldsInRequest |
             EthApiError::Signing(_) |
             EthApiError::BothStateAndStateDiffInOverride(_) |
+            EthApiError::ExceedsMaxProofWindow |
             EthApiError::InvalidTracerConfig => invalid_params_rpc_err(error.to_string()),
             EthApiError::InvalidTransaction(err) => err.into(),
             EthApiError::PoolError(err) => err.into(),
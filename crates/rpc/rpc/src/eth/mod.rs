@@ -13,7 +13,10 @@ pub mod revm_utils;
 mod signer;
 pub(crate) mod utils;
 
-pub use api::{EthApi, EthApiSpec, EthTransactions, TransactionSource, RPC_DEFAULT_GAS_CAP};
+pub use api::{
+    EthApi, EthApiSpec, EthTransactions, TransactionSource, DEFAULT_ETH_PROOF_WINDOW,
+    RPC_DEFAULT_GAS_CAP,
+};
 pub use bundle::EthBundle;
 pub use filter::{EthFilter, EthFilterConfig};
 pub use id_provider::EthSubscriptionIdProvider;
@@ -31,8 +31,10 @@ mod debug;
 mod engine;
 pub mod eth;
 mod layers;
+mod mev;
 mod net;
 mod otterscan;
+mod profile;
 mod reth;
 mod rpc;
 mod trace;
@@ -44,6 +46,7 @@ pub use debug::DebugApi;
 pub use engine::{EngineApi, EngineEthApi};
 pub use eth::{EthApi, EthApiSpec, EthFilter, EthPubSub, EthSubscriptionIdProvider};
 pub use layers::{AuthLayer, AuthValidator, Claims, JwtAuthValidator, JwtError, JwtSecret};
+pub use mev::{MevApi, MevBundlePool};
 pub use net::NetApi;
 pub use otterscan::OtterscanApi;
 pub use reth::RethApi;
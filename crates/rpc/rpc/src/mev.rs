@@ -0,0 +1,435 @@
+use crate::{
+    eth::{
+        bundle::{
+            mev_bundle_hash, validate_mev_bundle, EthBundleError, MAX_BUNDLE_BLOCKS_AHEAD,
+            MAX_NESTED_BUNDLE_DEPTH,
+        },
+        error::{EthApiError, EthResult},
+        utils::recover_raw_transaction,
+        EthBundle, EthTransactions,
+    },
+    EthApiSpec,
+};
+use async_trait::async_trait;
+use futures::{future::BoxFuture, FutureExt};
+use jsonrpsee::core::RpcResult;
+use parking_lot::RwLock;
+use reth_payload_builder::bundle::{BundlePoolError, BundlePoolLimits};
+use reth_primitives::{Address, BlockNumber, B256};
+use reth_rpc_api::MevApiServer;
+use reth_rpc_types::{
+    BundleItem, SendBundleRequest, SendBundleResponse, SimBundleOverrides, SimBundleResponse,
+};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::Arc,
+};
+
+/// `mev` API implementation.
+///
+/// This type provides the functionality for handling MEV-share `mev` related requests against
+/// local state.
+pub struct MevApi<Eth> {
+    inner: Arc<MevApiInner<Eth>>,
+}
+
+// === impl MevApi ===
+
+impl<Eth> MevApi<Eth> {
+    /// Creates a new instance of the [MevApi] that simulates bundles with the given [EthBundle]
+    /// API and keeps sent bundles in the given pool.
+    pub fn new(bundle_api: EthBundle<Eth>, pool: MevBundlePool) -> Self {
+        Self { inner: Arc::new(MevApiInner { bundle_api, pool }) }
+    }
+
+    /// Returns the pool of bundles sent via `mev_sendBundle`.
+    pub fn pool(&self) -> &MevBundlePool {
+        &self.inner.pool
+    }
+}
+
+impl<Eth> MevApi<Eth>
+where
+    Eth: EthTransactions + EthApiSpec + 'static,
+{
+    /// Validates the bundle and adds it to the pool.
+    ///
+    /// The last block the bundle is valid for must be one of the next [MAX_BUNDLE_BLOCKS_AHEAD]
+    /// blocks. Returns the hash of the bundle.
+    async fn send_bundle(&self, bundle: SendBundleRequest) -> EthResult<B256> {
+        validate_mev_bundle(&bundle, 0)
+            .map_err(|err| EthApiError::InvalidParams(err.to_string()))?;
+        let sender = recover_bundle_transactions(&bundle)?;
+
+        let best_number = self.inner.bundle_api.eth_api().chain_info()?.best_number;
+        let max_block =
+            bundle.inclusion.max_block_number().unwrap_or(bundle.inclusion.block_number());
+        if max_block <= best_number {
+            return Err(EthApiError::InvalidParams(format!(
+                "bundle max block {max_block} is not above the latest block {best_number}"
+            )))
+        }
+        if max_block - best_number > MAX_BUNDLE_BLOCKS_AHEAD {
+            return Err(EthApiError::InvalidParams(
+                EthBundleError::BundleTooFarAhead(MAX_BUNDLE_BLOCKS_AHEAD).to_string(),
+            ))
+        }
+
+        self.inner.pool.remove_expired(best_number);
+        let bundle_hash = mev_bundle_hash(&bundle);
+        self.inner
+            .pool
+            .insert(bundle_hash, bundle, sender)
+            .map_err(|err| EthApiError::InvalidParams(err.to_string()))?;
+        Ok(bundle_hash)
+    }
+
+    /// Replaces the transaction hashes in the bundle with the matching bundles of the pool or
+    /// transactions, if known.
+    ///
+    /// Hashes that can't be matched are left as is.
+    fn match_bundle(
+        &self,
+        mut bundle: SendBundleRequest,
+        depth: usize,
+    ) -> BoxFuture<'_, EthResult<SendBundleRequest>> {
+        async move {
+            // deeper bundles are rejected anyway
+            if depth > MAX_NESTED_BUNDLE_DEPTH {
+                return Ok(bundle)
+            }
+            for item in bundle.bundle_body.iter_mut() {
+                match item {
+                    BundleItem::Hash { hash } => {
+                        if let Some(matched) = self.inner.pool.get(hash) {
+                            let matched = self.match_bundle(matched, depth + 1).await?;
+                            *item = BundleItem::Bundle { bundle: matched };
+                        } else if let Some(tx) =
+                            self.inner.bundle_api.eth_api().transaction_by_hash(*hash).await?
+                        {
+                            let tx = tx.into_recovered().into_signed().envelope_encoded();
+                            *item = BundleItem::Tx { tx, can_revert: false };
+                        }
+                    }
+                    BundleItem::Bundle { bundle } => {
+                        let nested = std::mem::take(bundle);
+                        *bundle = self.match_bundle(nested, depth + 1).await?;
+                    }
+                    BundleItem::Tx { .. } => {}
+                }
+            }
+            Ok(bundle)
+        }
+        .boxed()
+    }
+}
+
+#[async_trait]
+impl<Eth> MevApiServer for MevApi<Eth>
+where
+    Eth: EthTransactions + EthApiSpec + 'static,
+{
+    /// Handler for `mev_sendBundle`
+    async fn send_bundle(&self, request: SendBundleRequest) -> RpcResult<SendBundleResponse> {
+        let bundle_hash = MevApi::send_bundle(self, request).await?;
+        Ok(SendBundleResponse { bundle_hash })
+    }
+
+    /// Handler for `mev_simBundle`
+    async fn sim_bundle(
+        &self,
+        bundle: SendBundleRequest,
+        sim_overrides: SimBundleOverrides,
+    ) -> RpcResult<SimBundleResponse> {
+        let bundle = self.match_bundle(bundle, 0).await?;
+        Ok(self.inner.bundle_api.sim_bundle(bundle, sim_overrides).await?)
+    }
+}
+
+impl<Eth> std::fmt::Debug for MevApi<Eth> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MevApi").field("pool", &self.inner.pool).finish_non_exhaustive()
+    }
+}
+
+impl<Eth> Clone for MevApi<Eth> {
+    fn clone(&self) -> Self {
+        Self { inner: Arc::clone(&self.inner) }
+    }
+}
+
+/// Container type for `MevApi` internals
+struct MevApiInner<Eth> {
+    /// Simulates bundles.
+    bundle_api: EthBundle<Eth>,
+    /// Bundles sent via `mev_sendBundle`.
+    pool: MevBundlePool,
+}
+
+/// Recovers all transactions of the bundle to make sure they are valid.
+///
+/// Returns the signer of the first transaction, which is the sender of the bundle.
+fn recover_bundle_transactions(bundle: &SendBundleRequest) -> EthResult<Option<Address>> {
+    let mut sender = None;
+    for item in &bundle.bundle_body {
+        let signer = match item {
+            BundleItem::Tx { tx, .. } => Some(recover_raw_transaction(tx.clone())?.signer()),
+            BundleItem::Bundle { bundle } => recover_bundle_transactions(bundle)?,
+            BundleItem::Hash { .. } => None,
+        };
+        sender = sender.or(signer);
+    }
+    Ok(sender)
+}
+
+/// Returns the number of transactions of the bundle, including those of nested bundles.
+fn bundle_tx_count(bundle: &SendBundleRequest) -> usize {
+    bundle
+        .bundle_body
+        .iter()
+        .map(|item| match item {
+            BundleItem::Tx { .. } => 1,
+            BundleItem::Bundle { bundle } => bundle_tx_count(bundle),
+            BundleItem::Hash { .. } => 0,
+        })
+        .sum()
+}
+
+/// A local pool of the bundles sent via `mev_sendBundle`, keyed by bundle hash.
+///
+/// Bundles are kept until the last block they are valid for is mined. They can be referenced
+/// by their hash in the body of other bundles, e.g. to backrun them.
+///
+/// The size of the pool is bounded by its [BundlePoolLimits], new bundles are rejected if the
+/// pool is full or their sender has too many bundles in the pool.
+#[derive(Debug, Clone, Default)]
+pub struct MevBundlePool {
+    inner: Arc<RwLock<MevBundlePoolInner>>,
+}
+
+// === impl MevBundlePool ===
+
+impl MevBundlePool {
+    /// Creates a new pool with the given limits.
+    pub fn new(limits: BundlePoolLimits) -> Self {
+        let inner = MevBundlePoolInner { limits, ..Default::default() };
+        Self { inner: Arc::new(RwLock::new(inner)) }
+    }
+
+    /// Adds the bundle with the given hash and sender to the pool. A bundle with the same hash
+    /// that is already in the pool is kept.
+    ///
+    /// Returns an error if the pool would exceed its [BundlePoolLimits].
+    pub fn insert(
+        &self,
+        bundle_hash: B256,
+        bundle: SendBundleRequest,
+        sender: Option<Address>,
+    ) -> Result<(), BundlePoolError> {
+        let mut inner = self.inner.write();
+        if inner.bundles.contains_key(&bundle_hash) {
+            return Ok(())
+        }
+
+        let num_txs = bundle_tx_count(&bundle);
+        if inner.bundles.len() >= inner.limits.max_bundles ||
+            inner.num_txs + num_txs > inner.limits.max_txs
+        {
+            return Err(BundlePoolError::PoolFull)
+        }
+        if let Some(sender) = sender {
+            let sender_bundles = inner.by_sender.get(&sender).copied().unwrap_or_default();
+            if sender_bundles >= inner.limits.max_bundles_per_sender {
+                return Err(BundlePoolError::SenderLimitExceeded(sender))
+            }
+            *inner.by_sender.entry(sender).or_default() += 1;
+        }
+
+        inner.num_txs += num_txs;
+        inner.bundles.insert(bundle_hash, MevPoolBundle { bundle, sender, num_txs });
+        Ok(())
+    }
+
+    /// Returns the bundle with the given hash.
+    pub fn get(&self, bundle_hash: &B256) -> Option<SendBundleRequest> {
+        self.inner.read().bundles.get(bundle_hash).map(|entry| entry.bundle.clone())
+    }
+
+    /// Returns all bundles that are valid for the given block.
+    pub fn bundles_at(&self, block_number: BlockNumber) -> Vec<(B256, SendBundleRequest)> {
+        self.inner
+            .read()
+            .bundles
+            .iter()
+            .filter(|(_, entry)| {
+                let inclusion = &entry.bundle.inclusion;
+                inclusion.block_number() <= block_number &&
+                    block_number <=
+                        inclusion.max_block_number().unwrap_or(inclusion.block_number())
+            })
+            .map(|(hash, entry)| (*hash, entry.bundle.clone()))
+            .collect()
+    }
+
+    /// Removes all bundles that are not valid after the given block.
+    pub fn remove_expired(&self, block_number: BlockNumber) {
+        let mut inner = self.inner.write();
+        let expired = inner
+            .bundles
+            .iter()
+            .filter(|(_, entry)| {
+                let inclusion = &entry.bundle.inclusion;
+                inclusion.max_block_number().unwrap_or(inclusion.block_number()) <= block_number
+            })
+            .map(|(hash, _)| *hash)
+            .collect::<Vec<_>>();
+        for hash in expired {
+            inner.remove(&hash);
+        }
+    }
+
+    /// Returns the number of bundles in the pool.
+    pub fn len(&self) -> usize {
+        self.inner.read().bundles.len()
+    }
+
+    /// Returns `true` if the pool contains no bundles.
+    pub fn is_empty(&self) -> bool {
+        self.inner.read().bundles.is_empty()
+    }
+}
+
+/// A bundle of the [MevBundlePool].
+#[derive(Debug)]
+struct MevPoolBundle {
+    bundle: SendBundleRequest,
+    /// The signer of the first transaction of the bundle.
+    sender: Option<Address>,
+    /// The number of transactions of the bundle, including those of nested bundles.
+    num_txs: usize,
+}
+
+/// Container type for [MevBundlePool] internals.
+#[derive(Debug, Default)]
+struct MevBundlePoolInner {
+    /// All bundles by hash.
+    bundles: HashMap<B256, MevPoolBundle>,
+    /// The number of bundles per sender.
+    by_sender: HashMap<Address, usize>,
+    /// The number of transactions of all bundles.
+    num_txs: usize,
+    /// The limits of the pool.
+    limits: BundlePoolLimits,
+}
+
+impl MevBundlePoolInner {
+    /// Removes the bundle with the given hash.
+    fn remove(&mut self, hash: &B256) -> Option<SendBundleRequest> {
+        let entry = self.bundles.remove(hash)?;
+        self.num_txs -= entry.num_txs;
+        if let Some(sender) = entry.sender {
+            if let Entry::Occupied(mut count) = self.by_sender.entry(sender) {
+                *count.get_mut() -= 1;
+                if *count.get() == 0 {
+                    count.remove();
+                }
+            }
+        }
+        Some(entry.bundle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::U64;
+    use reth_rpc_types::Inclusion;
+
+    fn bundle(block: u64, max_block: Option<u64>) -> SendBundleRequest {
+        SendBundleRequest {
+            inclusion: Inclusion { block: U64::from(block), max_block: max_block.map(U64::from) },
+            bundle_body: vec![BundleItem::Hash { hash: B256::with_last_byte(block as u8) }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pool_tracks_inclusion() {
+        let pool = MevBundlePool::default();
+        let single = bundle(2, None);
+        let range = bundle(3, Some(5));
+        pool.insert(mev_bundle_hash(&single), single.clone(), None).unwrap();
+        pool.insert(mev_bundle_hash(&range), range.clone(), None).unwrap();
+
+        assert_eq!(pool.bundles_at(1), vec![]);
+        assert_eq!(pool.bundles_at(2), vec![(mev_bundle_hash(&single), single)]);
+        assert_eq!(pool.bundles_at(4), vec![(mev_bundle_hash(&range), range.clone())]);
+
+        pool.remove_expired(2);
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.get(&mev_bundle_hash(&range)), Some(range));
+
+        pool.remove_expired(5);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn pool_limits() {
+        let pool = MevBundlePool::new(BundlePoolLimits {
+            max_bundles: 2,
+            max_txs: 2,
+            max_bundles_per_sender: 1,
+        });
+        let (alice, bob) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let tx = BundleItem::Tx { tx: Default::default(), can_revert: false };
+
+        let mut first = bundle(1, None);
+        first.bundle_body = vec![tx.clone()];
+        pool.insert(mev_bundle_hash(&first), first.clone(), Some(alice)).unwrap();
+        // the same bundle can be sent again
+        pool.insert(mev_bundle_hash(&first), first, Some(alice)).unwrap();
+
+        // too many bundles from the same sender
+        let second = bundle(2, None);
+        assert_eq!(
+            pool.insert(mev_bundle_hash(&second), second.clone(), Some(alice)),
+            Err(BundlePoolError::SenderLimitExceeded(alice))
+        );
+
+        // too many transactions
+        let mut nested = bundle(2, None);
+        nested.bundle_body = vec![tx.clone(), tx];
+        let mut outer = bundle(2, None);
+        outer.bundle_body.push(BundleItem::Bundle { bundle: nested });
+        assert_eq!(
+            pool.insert(mev_bundle_hash(&outer), outer, Some(bob)),
+            Err(BundlePoolError::PoolFull)
+        );
+
+        // too many bundles
+        pool.insert(mev_bundle_hash(&second), second, Some(bob)).unwrap();
+        let third = bundle(3, None);
+        assert_eq!(
+            pool.insert(mev_bundle_hash(&third), third, None),
+            Err(BundlePoolError::PoolFull)
+        );
+
+        // expired bundles free up space and the limits of their senders
+        pool.remove_expired(1);
+        assert_eq!(pool.len(), 1);
+        let fourth = bundle(4, None);
+        pool.insert(mev_bundle_hash(&fourth), fourth, Some(alice)).unwrap();
+    }
+
+    #[test]
+    fn rejects_deeply_nested_bundles() {
+        let nested = bundle(1, None);
+        let mut outer = bundle(1, None);
+        outer.bundle_body.push(BundleItem::Bundle { bundle: nested });
+        assert!(validate_mev_bundle(&outer, 0).is_ok());
+
+        let mut too_deep = bundle(1, None);
+        too_deep.bundle_body.push(BundleItem::Bundle { bundle: outer });
+        assert!(validate_mev_bundle(&too_deep, 0).is_err());
+    }
+}
@@ -1,33 +1,413 @@
-#![allow(dead_code, unused_variables)]
-use crate::result::internal_rpc_err;
+use crate::eth::{
+    error::{EthApiError, EthResult},
+    EthTransactions,
+};
 use async_trait::async_trait;
 use jsonrpsee::core::RpcResult;
-use reth_primitives::{Address, BlockId, BlockNumberOrTag, TxHash, B256};
+use reth_primitives::{Address, BlockId, BlockNumber, BlockNumberOrTag, Bytes, TxHash, B256, U256};
+use reth_provider::{BlockReaderIdExt, HistoryReader, StateProviderFactory};
+use reth_revm::tracing::TracingInspectorConfig;
 use reth_rpc_api::{EthApiServer, OtterscanServer};
 use reth_rpc_types::{
-    BlockDetails, ContractCreator, InternalOperation, OtsBlockTransactions, TraceEntry,
-    Transaction, TransactionsWithReceipts,
+    trace::geth::{CallConfig, CallFrame},
+    BlockDetails, BlockTransactions, ContractCreator, InternalOperation, OperationType, OtsBlock,
+    OtsBlockTransactions, OtsTransactionReceipt, TraceEntry, Transaction, TransactionsWithReceipts,
+};
+use revm_primitives::ExecutionResult;
+use std::{
+    collections::BTreeSet,
+    ops::{Range, RangeInclusive},
 };
 
 const API_LEVEL: u64 = 8;
 
+/// The maximum number of blocks that are traced for one page of `ots_searchTransactionsBefore`
+/// and `ots_searchTransactionsAfter`.
+const MAX_SEARCH_BLOCKS_PER_PAGE: usize = 100;
+
 /// Otterscan Api
 #[derive(Debug)]
-pub struct OtterscanApi<Eth> {
+pub struct OtterscanApi<Provider, Eth> {
+    provider: Provider,
     eth: Eth,
 }
 
-impl<Eth> OtterscanApi<Eth> {
+impl<Provider, Eth> OtterscanApi<Provider, Eth> {
     /// Creates a new instance of `Otterscan`.
-    pub fn new(eth: Eth) -> Self {
-        Self { eth }
+    pub fn new(provider: Provider, eth: Eth) -> Self {
+        Self { provider, eth }
+    }
+}
+
+// === impl OtterscanApi ===
+
+impl<Provider, Eth> OtterscanApi<Provider, Eth>
+where
+    Provider: BlockReaderIdExt + HistoryReader + StateProviderFactory + 'static,
+    Eth: EthApiServer + EthTransactions + 'static,
+{
+    /// Returns all internal ETH transfers, contract creations and self-destructs of the given
+    /// transaction.
+    async fn internal_operations(
+        &self,
+        tx_hash: TxHash,
+    ) -> EthResult<Option<Vec<InternalOperation>>> {
+        self.eth
+            .spawn_trace_transaction_in_block(
+                tx_hash,
+                TracingInspectorConfig::default_parity(),
+                move |_, inspector, res, _| {
+                    let frame = inspector
+                        .into_geth_builder()
+                        .geth_call_traces(CallConfig::default(), res.result.gas_used());
+                    Ok(internal_operations(&frame))
+                },
+            )
+            .await
+    }
+
+    /// Returns the raw revert output of the given transaction, or empty bytes if the transaction
+    /// did not revert.
+    async fn transaction_error(&self, tx_hash: TxHash) -> EthResult<Option<Bytes>> {
+        self.eth
+            .spawn_trace_transaction_in_block(
+                tx_hash,
+                TracingInspectorConfig::default_parity(),
+                move |_, _, res, _| match res.result {
+                    ExecutionResult::Revert { output, .. } => Ok(output),
+                    _ => Ok(Bytes::default()),
+                },
+            )
+            .await
+    }
+
+    /// Returns the flattened call tree of the given transaction.
+    async fn trace_entries(&self, tx_hash: TxHash) -> EthResult<Option<Vec<TraceEntry>>> {
+        self.eth
+            .spawn_trace_transaction_in_block(
+                tx_hash,
+                TracingInspectorConfig::default_parity(),
+                move |_, inspector, res, _| {
+                    let frame = inspector
+                        .into_geth_builder()
+                        .geth_call_traces(CallConfig::default(), res.result.gas_used());
+                    Ok(trace_entries(&frame))
+                },
+            )
+            .await
+    }
+
+    /// Returns a page of the block's transactions and their receipts.
+    ///
+    /// Pages are counted from the end of the block, in other words page `0` contains the last
+    /// transactions of the block.
+    async fn block_transactions(
+        &self,
+        block_number: BlockNumberOrTag,
+        page_number: usize,
+        page_size: usize,
+    ) -> RpcResult<OtsBlockTransactions> {
+        let block = EthApiServer::block_by_number(&self.eth, block_number, true).await?;
+        let receipts =
+            EthApiServer::block_receipts(&self.eth, BlockId::Number(block_number)).await?;
+        let (mut block, receipts) = match (block, receipts) {
+            (Some(block), Some(receipts)) => (block.inner, receipts),
+            _ => return Err(EthApiError::UnknownBlockNumber.into()),
+        };
+
+        let transaction_count = receipts.len();
+        let page = block_page(transaction_count, page_number, page_size);
+
+        if let BlockTransactions::Full(transactions) = &mut block.transactions {
+            *transactions = transactions
+                .drain(page.clone())
+                .map(|mut tx| {
+                    // only the selector is displayed
+                    tx.input = Bytes::copy_from_slice(&tx.input[..tx.input.len().min(4)]);
+                    tx
+                })
+                .collect();
+        }
+
+        let timestamp = block.header.timestamp.saturating_to::<u64>();
+        let receipts = receipts
+            .into_iter()
+            .skip(page.start)
+            .take(page.len())
+            .map(|mut receipt| {
+                // logs are not displayed on this page
+                receipt.logs = Vec::new();
+                receipt.logs_bloom = Default::default();
+                OtsTransactionReceipt { receipt, timestamp }
+            })
+            .collect();
+
+        Ok(OtsBlockTransactions { fullblock: OtsBlock { block, transaction_count }, receipts })
+    }
+
+    /// Resolves the given block and returns it together with the range of blocks to search.
+    fn search_range(
+        &self,
+        block_number: BlockNumberOrTag,
+        before: bool,
+    ) -> EthResult<(BlockNumber, RangeInclusive<BlockNumber>)> {
+        let best = self.provider.best_block_number()?;
+        let block_number = self
+            .provider
+            .convert_block_number(block_number)?
+            .ok_or(EthApiError::UnknownBlockNumber)?;
+
+        // the genesis block has no transactions
+        let range = if !before {
+            (block_number + 1).max(1)..=best
+        } else if block_number == 0 {
+            1..=best
+        } else {
+            1..=(block_number - 1).min(best)
+        };
+        Ok((block_number, range))
+    }
+
+    /// Returns the blocks of the range that may contain transactions involving the address, in
+    /// the order they are searched.
+    ///
+    /// The blocks are only looked up in the indices. Blocks covered by the trace address index
+    /// are looked up in it, which includes internal calls. For the remaining blocks, only those in
+    /// which the account or its storage changed are found, so transactions and internal calls that
+    /// don't change the state of the address are only found through the trace address index.
+    fn candidate_blocks(
+        &self,
+        address: Address,
+        range: RangeInclusive<BlockNumber>,
+        before: bool,
+    ) -> EthResult<Vec<BlockNumber>> {
+        let (start, end) = (*range.start(), *range.end());
+        let mut blocks = BTreeSet::new();
+        let mut unindexed_start = start;
+        if let Some(indexed_tip) =
+            self.provider.trace_address_index_tip()?.filter(|tip| *tip >= start)
+        {
+            let indexed_end = end.min(indexed_tip);
+            blocks.extend(self.provider.trace_address_blocks(address, start..=indexed_end)?);
+            unindexed_start = indexed_end + 1;
+        }
+        if unindexed_start <= end {
+            blocks.extend(self.provider.account_history_blocks(address, unindexed_start..=end)?);
+            blocks.extend(self.provider.storage_history_blocks(address, unindexed_start..=end)?);
+        }
+
+        Ok(if before { blocks.into_iter().rev().collect() } else { blocks.into_iter().collect() })
+    }
+
+    /// Returns all transactions of the block, and their receipts, that involve the address as
+    /// sender, recipient or participant of an internal call.
+    ///
+    /// The transactions are returned in the order they appear in the block.
+    async fn block_transactions_touching(
+        &self,
+        block_number: BlockNumber,
+        address: Address,
+    ) -> RpcResult<Vec<(Transaction, OtsTransactionReceipt)>> {
+        let indices = self
+            .eth
+            .trace_block_with(
+                block_number.into(),
+                TracingInspectorConfig::default_parity(),
+                move |tx_info, inspector, res, _, _| {
+                    let frame = inspector
+                        .into_geth_builder()
+                        .geth_call_traces(CallConfig::default(), res.gas_used());
+                    Ok(frame_touches(&frame, address).then_some(tx_info.index).flatten())
+                },
+            )
+            .await?
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .map(|idx| idx as usize)
+            .collect::<Vec<_>>();
+        if indices.is_empty() {
+            return Ok(Vec::new())
+        }
+
+        let number = BlockNumberOrTag::Number(block_number);
+        let block = EthApiServer::block_by_number(&self.eth, number, true).await?;
+        let receipts = EthApiServer::block_receipts(&self.eth, BlockId::Number(number)).await?;
+        let (block, receipts) = match (block, receipts) {
+            (Some(block), Some(receipts)) => (block.inner, receipts),
+            _ => return Err(EthApiError::UnknownBlockNumber.into()),
+        };
+        let transactions = match block.transactions {
+            BlockTransactions::Full(transactions) => transactions,
+            _ => return Err(EthApiError::InternalEthError.into()),
+        };
+
+        let timestamp = block.header.timestamp.saturating_to::<u64>();
+        let mut transactions = transactions.into_iter().zip(receipts).enumerate();
+        let mut matches = Vec::with_capacity(indices.len());
+        for idx in indices {
+            if let Some((_, (tx, receipt))) = transactions.find(|(tx_idx, _)| *tx_idx == idx) {
+                matches.push((tx, OtsTransactionReceipt { receipt, timestamp }));
+            }
+        }
+        Ok(matches)
+    }
+
+    /// Searches for transactions that involve the address.
+    ///
+    /// If `before` is true, this walks backwards from the given block (exclusive), otherwise it
+    /// walks forwards from the given block (exclusive). A block number of `0` means "start at
+    /// the tip" for backward searches and "start at genesis" for forward searches.
+    ///
+    /// Blocks are never split across pages, so a page can contain more than `page_size`
+    /// transactions. Transactions are always returned most recent first.
+    ///
+    /// At most [MAX_SEARCH_BLOCKS_PER_PAGE] candidate blocks are traced per page, so a page can
+    /// contain fewer than `page_size` transactions even if it's not the last one.
+    async fn search_transactions(
+        &self,
+        address: Address,
+        block_number: BlockNumberOrTag,
+        page_size: usize,
+        before: bool,
+    ) -> RpcResult<TransactionsWithReceipts> {
+        let (block_number, range) = self.search_range(block_number, before)?;
+        let blocks = self.candidate_blocks(address, range, before)?;
+
+        let mut txs = Vec::new();
+        let mut receipts = Vec::new();
+        let mut searched = 0;
+        while txs.len() < page_size && searched < MAX_SEARCH_BLOCKS_PER_PAGE {
+            let Some(&block) = blocks.get(searched) else { break };
+            searched += 1;
+            let mut matches = self.block_transactions_touching(block, address).await?;
+            if before {
+                matches.reverse();
+            }
+            for (tx, receipt) in matches {
+                txs.push(tx);
+                receipts.push(receipt);
+            }
+        }
+        let has_more = searched < blocks.len();
+
+        let (first_page, last_page) = if before {
+            (block_number == 0, !has_more)
+        } else {
+            // forward searches collect oldest first
+            txs.reverse();
+            receipts.reverse();
+            (!has_more, block_number == 0)
+        };
+
+        Ok(TransactionsWithReceipts { txs, receipts, first_page, last_page })
+    }
+
+    /// Returns the first block in the ascending list of blocks for which `predicate` holds.
+    ///
+    /// The predicate must be monotonic over the list: once it holds for a block it must hold for
+    /// all later blocks.
+    fn first_block_where(
+        &self,
+        blocks: &[BlockNumber],
+        mut predicate: impl FnMut(BlockNumber) -> EthResult<bool>,
+    ) -> EthResult<Option<BlockNumber>> {
+        let (mut low, mut high) = (0, blocks.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if predicate(blocks[mid])? {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        Ok(blocks.get(low).copied())
+    }
+
+    /// Returns the block in which the sender's nonce was incremented past the given nonce.
+    ///
+    /// This performs a binary search over the account history of the sender.
+    fn nonce_block(&self, sender: Address, nonce: u64) -> EthResult<Option<BlockNumber>> {
+        let latest_nonce = self.provider.latest()?.account_nonce(sender)?.unwrap_or_default();
+        if latest_nonce <= nonce {
+            return Ok(None)
+        }
+
+        let best = self.provider.best_block_number()?;
+        let blocks = self.provider.account_history_blocks(sender, 0..=best)?;
+        self.first_block_where(&blocks, |block| {
+            let nonce_after =
+                self.provider.history_by_block_number(block)?.account_nonce(sender)?;
+            Ok(nonce_after.unwrap_or_default() > nonce)
+        })
+    }
+
+    /// Returns the block in which code was deployed to the given address.
+    ///
+    /// This performs a binary search over the account history of the contract.
+    fn deployment_block(&self, address: Address) -> EthResult<Option<BlockNumber>> {
+        if self.provider.latest()?.account_code(address)?.is_none() {
+            return Ok(None)
+        }
+
+        let best = self.provider.best_block_number()?;
+        let blocks = self.provider.account_history_blocks(address, 0..=best)?;
+        self.first_block_where(&blocks, |block| {
+            Ok(self.provider.history_by_block_number(block)?.account_code(address)?.is_some())
+        })
+    }
+
+    /// Finds the transaction sent by `sender` with the given nonce.
+    async fn transaction_by_sender_and_nonce(
+        &self,
+        sender: Address,
+        nonce: u64,
+    ) -> RpcResult<Option<Transaction>> {
+        let Some(block) = self.nonce_block(sender, nonce)? else { return Ok(None) };
+
+        let block =
+            EthApiServer::block_by_number(&self.eth, BlockNumberOrTag::Number(block), true).await?;
+        let tx = block.and_then(|block| match block.inner.transactions {
+            BlockTransactions::Full(transactions) => transactions
+                .into_iter()
+                .find(|tx| tx.from == sender && tx.nonce.to::<u64>() == nonce),
+            _ => None,
+        });
+        Ok(tx)
+    }
+
+    /// Finds the transaction that created the contract at the given address.
+    ///
+    /// This traces the block in which the code was deployed to find the creating call.
+    async fn contract_creator(&self, address: Address) -> RpcResult<Option<ContractCreator>> {
+        let Some(block) = self.deployment_block(address)? else { return Ok(None) };
+
+        let creation = self
+            .eth
+            .trace_block_with(
+                block.into(),
+                TracingInspectorConfig::default_parity(),
+                move |tx_info, inspector, res, _, _| {
+                    let frame = inspector
+                        .into_geth_builder()
+                        .geth_call_traces(CallConfig::default(), res.gas_used());
+                    Ok(find_creator(&frame, address).zip(tx_info.hash))
+                },
+            )
+            .await?
+            .and_then(|creations| creations.into_iter().flatten().next());
+        let Some((creator, tx_hash)) = creation else { return Ok(None) };
+
+        let tx = EthApiServer::transaction_by_hash(&self.eth, tx_hash).await?;
+        Ok(tx.map(|tx| ContractCreator { tx, creator }))
     }
 }
 
 #[async_trait]
-impl<Eth> OtterscanServer for OtterscanApi<Eth>
+impl<Provider, Eth> OtterscanServer for OtterscanApi<Provider, Eth>
 where
-    Eth: EthApiServer,
+    Provider: BlockReaderIdExt + HistoryReader + StateProviderFactory + 'static,
+    Eth: EthApiServer + EthTransactions + 'static,
 {
     /// Handler for `ots_hasCode`
     async fn has_code(&self, address: Address, block_number: Option<BlockId>) -> RpcResult<bool> {
@@ -40,18 +420,21 @@ where
     }
 
     /// Handler for `ots_getInternalOperations`
-    async fn get_internal_operations(&self, tx_hash: TxHash) -> RpcResult<Vec<InternalOperation>> {
-        Err(internal_rpc_err("unimplemented"))
+    async fn get_internal_operations(
+        &self,
+        tx_hash: TxHash,
+    ) -> RpcResult<Option<Vec<InternalOperation>>> {
+        Ok(self.internal_operations(tx_hash).await?)
     }
 
     /// Handler for `ots_getTransactionError`
-    async fn get_transaction_error(&self, tx_hash: TxHash) -> RpcResult<String> {
-        Err(internal_rpc_err("unimplemented"))
+    async fn get_transaction_error(&self, tx_hash: TxHash) -> RpcResult<Option<Bytes>> {
+        Ok(self.transaction_error(tx_hash).await?)
     }
 
     /// Handler for `ots_traceTransaction`
-    async fn trace_transaction(&self, tx_hash: TxHash) -> RpcResult<TraceEntry> {
-        Err(internal_rpc_err("unimplemented"))
+    async fn trace_transaction(&self, tx_hash: TxHash) -> RpcResult<Option<Vec<TraceEntry>>> {
+        Ok(self.trace_entries(tx_hash).await?)
     }
 
     /// Handler for `ots_getBlockDetails`
@@ -76,7 +459,7 @@ where
         page_number: usize,
         page_size: usize,
     ) -> RpcResult<OtsBlockTransactions> {
-        Err(internal_rpc_err("unimplemented"))
+        self.block_transactions(block_number, page_number, page_size).await
     }
 
     /// Handler for `searchTransactionsBefore`
@@ -86,7 +469,7 @@ where
         block_number: BlockNumberOrTag,
         page_size: usize,
     ) -> RpcResult<TransactionsWithReceipts> {
-        Err(internal_rpc_err("unimplemented"))
+        self.search_transactions(address, block_number, page_size, true).await
     }
 
     /// Handler for `searchTransactionsAfter`
@@ -96,7 +479,7 @@ where
         block_number: BlockNumberOrTag,
         page_size: usize,
     ) -> RpcResult<TransactionsWithReceipts> {
-        Err(internal_rpc_err("unimplemented"))
+        self.search_transactions(address, block_number, page_size, false).await
     }
 
     /// Handler for `getTransactionBySenderAndNonce`
@@ -105,11 +488,504 @@ where
         sender: Address,
         nonce: u64,
     ) -> RpcResult<Option<Transaction>> {
-        Err(internal_rpc_err("unimplemented"))
+        self.transaction_by_sender_and_nonce(sender, nonce).await
     }
 
     /// Handler for `getContractCreator`
     async fn get_contract_creator(&self, address: Address) -> RpcResult<Option<ContractCreator>> {
-        Err(internal_rpc_err("unimplemented"))
+        self.contract_creator(address).await
+    }
+}
+
+/// Returns the range of transaction indices of the requested page.
+///
+/// Otterscan counts pages from the end of the block, so page `0` holds the last `page_size`
+/// transactions.
+fn block_page(transaction_count: usize, page_number: usize, page_size: usize) -> Range<usize> {
+    let end = transaction_count.saturating_sub(page_number.saturating_mul(page_size));
+    let start = end.saturating_sub(page_size);
+    start..end
+}
+
+/// Collects all value transfers, creations and self-destructs below the top-level call.
+///
+/// Operations of failed calls are skipped, since their effects were reverted.
+fn internal_operations(root: &CallFrame) -> Vec<InternalOperation> {
+    let mut operations = Vec::new();
+    let mut stack = root.calls.iter().rev().collect::<Vec<_>>();
+    while let Some(frame) = stack.pop() {
+        if frame.error.is_some() {
+            continue
+        }
+        let value = frame.value.unwrap_or_default();
+        let r#type = match frame.typ.as_str() {
+            "CALL" if value > U256::ZERO => Some(OperationType::OpTransfer),
+            "CREATE" => Some(OperationType::OpCreate),
+            "CREATE2" => Some(OperationType::OpCreate2),
+            "SELFDESTRUCT" => Some(OperationType::OpSelfDestruct),
+            _ => None,
+        };
+        if let Some(r#type) = r#type {
+            operations.push(InternalOperation {
+                r#type,
+                from: frame.from,
+                to: frame.to.unwrap_or_default(),
+                value,
+            });
+        }
+        stack.extend(frame.calls.iter().rev());
+    }
+    operations
+}
+
+/// Flattens the call tree into trace entries in the order the calls were made.
+fn trace_entries(root: &CallFrame) -> Vec<TraceEntry> {
+    let mut entries = Vec::new();
+    let mut stack = vec![(root, 0u32)];
+    while let Some((frame, depth)) = stack.pop() {
+        let value = match frame.typ.as_str() {
+            "DELEGATECALL" | "STATICCALL" => None,
+            _ => frame.value,
+        };
+        entries.push(TraceEntry {
+            r#type: frame.typ.clone(),
+            depth,
+            from: frame.from,
+            to: frame.to.unwrap_or_default(),
+            value,
+            input: frame.input.clone(),
+        });
+        stack.extend(frame.calls.iter().rev().map(|call| (call, depth + 1)));
+    }
+    entries
+}
+
+/// Returns true if the address is the caller or callee of any frame in the call tree.
+fn frame_touches(root: &CallFrame, address: Address) -> bool {
+    let mut stack = vec![root];
+    while let Some(frame) = stack.pop() {
+        if frame.from == address || frame.to == Some(address) {
+            return true
+        }
+        stack.extend(frame.calls.iter());
+    }
+    false
+}
+
+/// Returns the address that executed the successful creation of the contract at `address`.
+fn find_creator(root: &CallFrame, address: Address) -> Option<Address> {
+    let mut stack = vec![root];
+    while let Some(frame) = stack.pop() {
+        if frame.error.is_some() {
+            continue
+        }
+        if matches!(frame.typ.as_str(), "CREATE" | "CREATE2") && frame.to == Some(address) {
+            return Some(frame.from)
+        }
+        stack.extend(frame.calls.iter());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        eth::{cache::EthStateCache, gas_oracle::GasPriceOracle},
+        BlockingTaskPool, EthApi,
+    };
+    use reth_blockchain_tree::noop::NoopBlockchainTree;
+    use reth_db::{
+        models::AccountBeforeTx,
+        tables,
+        test_utils::{create_test_rw_db, TempDatabase},
+        transaction::DbTxMut,
+        DatabaseEnv,
+    };
+    use reth_interfaces::test_utils::generators::{self, generate_keys, sign_tx_with_key_pair};
+    use reth_network_api::noop::NoopNetwork;
+    use reth_primitives::{
+        address,
+        constants::ETHEREUM_BLOCK_GAS_LIMIT,
+        hex, public_key_to_address,
+        stage::{StageCheckpoint, StageId},
+        Account, Block, Bytecode, ChainSpecBuilder, Genesis, Header, Transaction, TransactionKind,
+        TxLegacy, MAINNET,
+    };
+    use reth_provider::{
+        providers::BlockchainProvider, BlockExecutor, BlockWriter, HistoryWriter,
+        OriginalValuesKnown, ProviderFactory, StageCheckpointWriter,
+    };
+    use reth_revm::{database::StateProviderDatabase, processor::EVMProcessor};
+    use reth_transaction_pool::test_utils::{testing_pool, TestPool};
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        sync::Arc,
+    };
+
+    type TestDb = Arc<TempDatabase<DatabaseEnv>>;
+    type TestProvider = BlockchainProvider<TestDb, NoopBlockchainTree>;
+    type TestOtterscanApi = OtterscanApi<TestProvider, EthApi<TestProvider, TestPool, NoopNetwork>>;
+
+    /// Contract whose code is a single `STOP`.
+    const TARGET: Address = address!("00000000000000000000000000000000000000aa");
+    /// Contract that calls [TARGET] with the value it received.
+    const FORWARDER: Address = address!("00000000000000000000000000000000000000bb");
+
+    /// A chain of executed blocks with a single transaction each, sent by `alice`:
+    ///
+    /// 1. a transfer of 1 wei to `bob`
+    /// 2. a call to [FORWARDER] without value, which changes neither of the contracts
+    /// 3. a call to [FORWARDER] with 5 wei, which are forwarded to [TARGET]
+    /// 4. the creation of the `created` contract
+    struct TestChain {
+        api: TestOtterscanApi,
+        factory: ProviderFactory<TestDb>,
+        alice: Address,
+        bob: Address,
+        created: Address,
+        /// The transaction hashes of blocks 1 to 4.
+        tx_hashes: Vec<TxHash>,
+    }
+
+    fn test_chain() -> TestChain {
+        let mut rng = generators::rng();
+        let [alice_key, bob_key] = generate_keys(&mut rng, 2)[..] else { unreachable!() };
+        let alice = public_key_to_address(alice_key.public_key());
+        let bob = public_key_to_address(bob_key.public_key());
+        let created = alice.create(3);
+
+        let chain_spec = Arc::new(
+            ChainSpecBuilder::default()
+                .chain(MAINNET.chain)
+                .genesis(Genesis::default())
+                .istanbul_activated()
+                .build(),
+        );
+        let factory = ProviderFactory::new(create_test_rw_db(), chain_spec.clone());
+
+        // genesis block and state
+        let mut forwarder_code = hex!("600060006000600034").to_vec();
+        forwarder_code.push(0x73);
+        forwarder_code.extend_from_slice(TARGET.as_slice());
+        forwarder_code.extend_from_slice(&hex!("5af100"));
+        let genesis_state = [
+            (alice, U256::from(1_000_000_000_000_000_000u64), None),
+            (TARGET, U256::ZERO, Some(Bytes::from_static(&[0x00]))),
+            (FORWARDER, U256::ZERO, Some(Bytes::from(forwarder_code))),
+        ];
+
+        let provider = factory.provider_rw().unwrap();
+        let genesis = Block {
+            header: Header { gas_limit: ETHEREUM_BLOCK_GAS_LIMIT, ..Default::default() },
+            ..Default::default()
+        }
+        .seal_slow();
+        let mut parent_hash = genesis.hash;
+        provider.insert_block(genesis, None, None).unwrap();
+        for (address, balance, code) in genesis_state.clone() {
+            let bytecode_hash = code.map(|code| {
+                let bytecode = Bytecode::new_raw(code);
+                let hash = bytecode.hash_slow();
+                provider.tx_ref().put::<tables::Bytecodes>(hash, bytecode).unwrap();
+                hash
+            });
+            let account = Account { nonce: 0, balance, bytecode_hash };
+            provider.tx_ref().put::<tables::PlainAccountState>(address, account).unwrap();
+            provider
+                .tx_ref()
+                .put::<tables::AccountChangeSet>(0, AccountBeforeTx { address, info: None })
+                .unwrap();
+        }
+        provider
+            .insert_account_history_index(
+                genesis_state.iter().map(|(address, _, _)| (*address, vec![0])).collect(),
+            )
+            .unwrap();
+        provider.commit().unwrap();
+
+        let transactions = [
+            (TransactionKind::Call(bob), 1, Bytes::default()),
+            (TransactionKind::Call(FORWARDER), 0, Bytes::default()),
+            (TransactionKind::Call(FORWARDER), 5, Bytes::default()),
+            // returns the code `STOP`
+            (TransactionKind::Create, 0, Bytes::from_static(&hex!("600060005360016000f3"))),
+        ];
+        let mut tx_hashes = Vec::new();
+        for (nonce, (to, value, input)) in transactions.into_iter().enumerate() {
+            let number = nonce as u64 + 1;
+            let transaction = sign_tx_with_key_pair(
+                alice_key,
+                Transaction::Legacy(TxLegacy {
+                    chain_id: Some(chain_spec.chain.id()),
+                    nonce: nonce as u64,
+                    gas_price: 1,
+                    gas_limit: 100_000,
+                    to,
+                    value: U256::from(value).into(),
+                    input,
+                }),
+            );
+            tx_hashes.push(transaction.hash());
+            let mut block = Block {
+                header: Header {
+                    parent_hash,
+                    number,
+                    gas_limit: ETHEREUM_BLOCK_GAS_LIMIT,
+                    timestamp: number,
+                    ..Default::default()
+                },
+                body: vec![transaction],
+                ..Default::default()
+            };
+
+            // execute the block once to learn the gas it uses
+            let state = factory.latest().unwrap();
+            let mut executor =
+                EVMProcessor::new_with_db(chain_spec.clone(), StateProviderDatabase::new(&state));
+            block.header.gas_used =
+                executor.execute_transactions(&block, U256::ZERO, None).unwrap().1;
+            let mut executor =
+                EVMProcessor::new_with_db(chain_spec.clone(), StateProviderDatabase::new(&state));
+            executor.set_first_block(number);
+            executor.execute(&block, U256::ZERO, None).unwrap();
+            let bundle_state = executor.take_output_state();
+
+            let block = block.seal_slow();
+            parent_hash = block.hash;
+            let provider = factory.provider_rw().unwrap();
+            provider.insert_block(block, None, None).unwrap();
+            bundle_state.write_to_db(provider.tx_ref(), OriginalValuesKnown::No).unwrap();
+            provider.update_history_indices(number..=number).unwrap();
+            provider.update_pipeline_stages(number, false).unwrap();
+            provider.commit().unwrap();
+        }
+
+        let provider =
+            BlockchainProvider::new(factory.clone(), NoopBlockchainTree::default()).unwrap();
+        let cache = EthStateCache::spawn(provider.clone(), Default::default());
+        let eth_api = EthApi::new(
+            provider.clone(),
+            testing_pool(),
+            NoopNetwork::default(),
+            cache.clone(),
+            GasPriceOracle::new(provider.clone(), Default::default(), cache),
+            ETHEREUM_BLOCK_GAS_LIMIT,
+            BlockingTaskPool::build().expect("failed to build tracing pool"),
+        );
+
+        TestChain {
+            api: OtterscanApi::new(provider, eth_api),
+            factory,
+            alice,
+            bob,
+            created,
+            tx_hashes,
+        }
+    }
+
+    /// Returns the hashes of the transactions found by a search.
+    fn hashes(txs: &TransactionsWithReceipts) -> Vec<TxHash> {
+        txs.txs.iter().map(|tx| tx.hash).collect()
+    }
+
+    fn frame(typ: &str, from: u8, to: u8, value: u64, calls: Vec<CallFrame>) -> CallFrame {
+        CallFrame {
+            typ: typ.to_string(),
+            from: Address::with_last_byte(from),
+            to: Some(Address::with_last_byte(to)),
+            value: Some(U256::from(value)),
+            calls,
+            ..Default::default()
+        }
+    }
+
+    fn call_tree() -> CallFrame {
+        let mut reverted = frame("CALL", 2, 6, 7, vec![frame("CREATE", 6, 7, 0, vec![])]);
+        reverted.error = Some("execution reverted".to_string());
+        frame(
+            "CALL",
+            1,
+            2,
+            100,
+            vec![
+                frame("CALL", 2, 3, 50, vec![frame("SELFDESTRUCT", 3, 4, 10, vec![])]),
+                frame("STATICCALL", 2, 3, 0, vec![]),
+                frame("DELEGATECALL", 2, 5, 100, vec![frame("CREATE2", 2, 8, 1, vec![])]),
+                reverted,
+            ],
+        )
+    }
+
+    #[test]
+    fn test_block_page() {
+        assert_eq!(block_page(25, 0, 10), 15..25);
+        assert_eq!(block_page(25, 1, 10), 5..15);
+        assert_eq!(block_page(25, 2, 10), 0..5);
+        assert_eq!(block_page(25, 3, 10), 0..0);
+        assert_eq!(block_page(0, 0, 10), 0..0);
+        assert_eq!(block_page(5, usize::MAX, usize::MAX), 0..0);
+    }
+
+    #[test]
+    fn test_internal_operations() {
+        let ops = internal_operations(&call_tree());
+        let kinds = ops.iter().map(|op| op.r#type).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                OperationType::OpTransfer,
+                OperationType::OpSelfDestruct,
+                OperationType::OpCreate2
+            ]
+        );
+        assert_eq!(ops[0].to, Address::with_last_byte(3));
+        assert_eq!(ops[0].value, U256::from(50));
+        assert_eq!(ops[2].to, Address::with_last_byte(8));
+    }
+
+    #[test]
+    fn test_trace_entries() {
+        let entries = trace_entries(&call_tree());
+        let summary =
+            entries.iter().map(|entry| (entry.r#type.as_str(), entry.depth)).collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("CALL", 0),
+                ("CALL", 1),
+                ("SELFDESTRUCT", 2),
+                ("STATICCALL", 1),
+                ("DELEGATECALL", 1),
+                ("CREATE2", 2),
+                ("CALL", 1),
+                ("CREATE", 2),
+            ]
+        );
+        assert_eq!(entries[3].value, None);
+        assert_eq!(entries[4].value, None);
+        assert_eq!(entries[1].value, Some(U256::from(50)));
+    }
+
+    #[test]
+    fn test_frame_touches_and_creator() {
+        let tree = call_tree();
+        assert!(frame_touches(&tree, Address::with_last_byte(4)));
+        assert!(frame_touches(&tree, Address::with_last_byte(7)));
+        assert!(!frame_touches(&tree, Address::with_last_byte(9)));
+
+        assert_eq!(
+            find_creator(&tree, Address::with_last_byte(8)),
+            Some(Address::with_last_byte(2))
+        );
+        // created in a reverted call
+        assert_eq!(find_creator(&tree, Address::with_last_byte(7)), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_search_transactions() {
+        let chain = test_chain();
+        let (api, tx_hashes) = (&chain.api, &chain.tx_hashes);
+
+        // the sender appears in every block, pages are filled most recent first
+        let page = api.search_transactions_before(chain.alice, 0.into(), 2).await.unwrap();
+        assert_eq!(hashes(&page), vec![tx_hashes[3], tx_hashes[2]]);
+        assert_eq!(page.receipts.len(), 2);
+        assert!(page.first_page);
+        assert!(!page.last_page);
+        let page = api.search_transactions_before(chain.alice, 3.into(), 2).await.unwrap();
+        assert_eq!(hashes(&page), vec![tx_hashes[1], tx_hashes[0]]);
+        assert!(!page.first_page);
+        assert!(page.last_page);
+
+        let page = api.search_transactions_after(chain.alice, 1.into(), 10).await.unwrap();
+        assert_eq!(hashes(&page), vec![tx_hashes[3], tx_hashes[2], tx_hashes[1]]);
+        assert!(page.first_page);
+        assert!(!page.last_page);
+
+        let page = api.search_transactions_before(chain.bob, 0.into(), 10).await.unwrap();
+        assert_eq!(hashes(&page), vec![tx_hashes[0]]);
+
+        // the forwarder is called without its state changing, which is only found through the
+        // trace address index
+        let page = api.search_transactions_before(FORWARDER, 0.into(), 10).await.unwrap();
+        assert!(page.txs.is_empty());
+        assert!(page.last_page);
+
+        // without the trace address index, only the internal call that changes the target's
+        // balance is found
+        let page = api.search_transactions_before(TARGET, 0.into(), 10).await.unwrap();
+        assert_eq!(hashes(&page), vec![tx_hashes[2]]);
+
+        // index the traces of blocks 1 to 3
+        let provider = chain.factory.provider_rw().unwrap();
+        provider
+            .insert_trace_address_index(BTreeMap::from([
+                (1, BTreeSet::from([chain.alice, chain.bob])),
+                (2, BTreeSet::from([chain.alice, FORWARDER, TARGET])),
+                (3, BTreeSet::from([chain.alice, FORWARDER, TARGET])),
+            ]))
+            .unwrap();
+        provider
+            .save_stage_checkpoint(StageId::IndexTraceAddresses, StageCheckpoint::new(3))
+            .unwrap();
+        provider.commit().unwrap();
+
+        let page = api.search_transactions_before(TARGET, 0.into(), 10).await.unwrap();
+        assert_eq!(hashes(&page), vec![tx_hashes[2], tx_hashes[1]]);
+        let page = api.search_transactions_before(FORWARDER, 0.into(), 10).await.unwrap();
+        assert_eq!(hashes(&page), vec![tx_hashes[2], tx_hashes[1]]);
+        // block 4 is searched without the index
+        let page = api.search_transactions_after(chain.alice, 2.into(), 10).await.unwrap();
+        assert_eq!(hashes(&page), vec![tx_hashes[3], tx_hashes[2]]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_transaction_handlers() {
+        let chain = test_chain();
+        let (api, tx_hashes) = (&chain.api, &chain.tx_hashes);
+
+        assert_eq!(
+            api.get_internal_operations(tx_hashes[2]).await.unwrap(),
+            Some(vec![InternalOperation {
+                r#type: OperationType::OpTransfer,
+                from: FORWARDER,
+                to: TARGET,
+                value: U256::from(5),
+            }])
+        );
+        assert_eq!(api.get_internal_operations(tx_hashes[1]).await.unwrap(), Some(Vec::new()));
+        assert_eq!(api.get_internal_operations(TxHash::random()).await.unwrap(), None);
+
+        let entries = api.trace_transaction(tx_hashes[1]).await.unwrap().unwrap();
+        let summary = entries
+            .iter()
+            .map(|entry| (entry.r#type.as_str(), entry.depth, entry.from, entry.to))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![("CALL", 0, chain.alice, FORWARDER), ("CALL", 1, FORWARDER, TARGET)]
+        );
+        assert_eq!(api.trace_transaction(TxHash::random()).await.unwrap(), None);
+
+        assert_eq!(api.get_transaction_error(tx_hashes[0]).await.unwrap(), Some(Bytes::default()));
+        assert_eq!(api.get_transaction_error(TxHash::random()).await.unwrap(), None);
+
+        let tx = api.get_transaction_by_sender_and_nonce(chain.alice, 1).await.unwrap().unwrap();
+        assert_eq!(tx.hash, tx_hashes[1]);
+        assert_eq!(api.get_transaction_by_sender_and_nonce(chain.alice, 4).await.unwrap(), None);
+        assert_eq!(api.get_transaction_by_sender_and_nonce(chain.bob, 0).await.unwrap(), None);
+
+        let creator = api.get_contract_creator(chain.created).await.unwrap().unwrap();
+        assert_eq!(creator.tx.hash, tx_hashes[3]);
+        assert_eq!(creator.creator, chain.alice);
+        // deployed at genesis
+        assert_eq!(api.get_contract_creator(FORWARDER).await.unwrap(), None);
+        assert_eq!(api.get_contract_creator(chain.bob).await.unwrap(), None);
+
+        let block = api.get_block_transactions(3.into(), 0, 10).await.unwrap();
+        assert_eq!(block.fullblock.transaction_count, 1);
+        assert_eq!(block.receipts.len(), 1);
+        assert_eq!(block.receipts[0].receipt.transaction_hash, Some(tx_hashes[2]));
     }
 }
@@ -0,0 +1,223 @@
+//! In-process CPU and heap profiling used by the `debug` namespace.
+
+use std::path::{Component, Path, PathBuf};
+
+/// The sampling frequency of the CPU profiler, in samples per second.
+#[cfg(unix)]
+const CPU_PROFILE_FREQUENCY: i32 = 99;
+
+/// The CPU profile that is currently running, if any.
+///
+/// There can only be a single CPU profiler per process, so this is shared by all `debug` API
+/// instances.
+#[cfg(unix)]
+static CPU_PROFILE: std::sync::Mutex<Option<CpuProfile>> = std::sync::Mutex::new(None);
+
+/// The id of the next CPU profile that is started.
+#[cfg(unix)]
+static NEXT_CPU_PROFILE_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Errors returned by the profiler.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ProfileError {
+    /// A CPU profile is already running.
+    #[error("CPU profiling already in progress")]
+    AlreadyRunning,
+    /// No CPU profile is running.
+    #[error("CPU profiling not in progress")]
+    NotRunning,
+    /// Profiling is not supported by this build.
+    #[error("{0}")]
+    Unsupported(&'static str),
+    /// No directory to write profiles to is configured.
+    #[error("profiling is disabled, no profile directory configured")]
+    NoProfileDir,
+    /// The requested file is outside of the profile directory.
+    #[error("invalid profile file {0:?}, expected a relative path inside the profile directory")]
+    InvalidPath(String),
+    /// The sampling profiler failed.
+    #[cfg(unix)]
+    #[error(transparent)]
+    Pprof(#[from] pprof::Error),
+    /// Failed to dump the heap profile.
+    #[cfg(all(feature = "jemalloc", unix))]
+    #[error("failed to dump heap profile: {0}")]
+    Jemalloc(jemalloc_ctl::Error),
+    /// Failed to write the profile.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// A running CPU profile.
+#[cfg(unix)]
+struct CpuProfile {
+    /// Identifies the profile, see [stop_cpu_profile_with_id].
+    id: u64,
+    /// Collects samples until dropped.
+    guard: pprof::ProfilerGuard<'static>,
+    /// The file the profile is written to once it's stopped.
+    file: PathBuf,
+}
+
+/// Resolves the file a profile is written to inside the profile directory.
+///
+/// Only relative paths that stay inside the directory are accepted, so the profiling methods can't
+/// be used to write to arbitrary locations.
+pub(crate) fn profile_path(dir: Option<&Path>, file: &str) -> Result<PathBuf, ProfileError> {
+    let dir = dir.ok_or(ProfileError::NoProfileDir)?;
+    let path = Path::new(file);
+    if file.is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(ProfileError::InvalidPath(file.to_string()))
+    }
+    Ok(dir.join(path))
+}
+
+/// Starts a sampling CPU profile, which is written to the given file in pprof format once it's
+/// stopped with [stop_cpu_profile].
+///
+/// Returns the id of the started profile.
+#[cfg(unix)]
+pub(crate) fn start_cpu_profile(file: impl Into<PathBuf>) -> Result<u64, ProfileError> {
+    let mut profile = CPU_PROFILE.lock().unwrap();
+    if profile.is_some() {
+        return Err(ProfileError::AlreadyRunning)
+    }
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(CPU_PROFILE_FREQUENCY)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()?;
+    let id = NEXT_CPU_PROFILE_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    *profile = Some(CpuProfile { id, guard, file: file.into() });
+    Ok(id)
+}
+
+/// Stops the running CPU profile and writes it to the file it was started with.
+///
+/// Returns the path of the written file.
+///
+/// Note: this symbolizes all sampled frames, which is CPU intensive.
+#[cfg(unix)]
+pub(crate) fn stop_cpu_profile() -> Result<PathBuf, ProfileError> {
+    let profile = CPU_PROFILE.lock().unwrap().take().ok_or(ProfileError::NotRunning)?;
+    write_cpu_profile(profile)
+}
+
+/// Stops the running CPU profile if it's the one with the given id, see [stop_cpu_profile].
+///
+/// This way a profile that was already stopped can't stop a profile that was started later.
+#[cfg(unix)]
+pub(crate) fn stop_cpu_profile_with_id(id: u64) -> Result<PathBuf, ProfileError> {
+    let profile = {
+        let mut profile = CPU_PROFILE.lock().unwrap();
+        if profile.as_ref().map(|profile| profile.id) != Some(id) {
+            return Err(ProfileError::NotRunning)
+        }
+        profile.take().expect("profile is running")
+    };
+    write_cpu_profile(profile)
+}
+
+/// Writes the stopped CPU profile to its file.
+#[cfg(unix)]
+fn write_cpu_profile(CpuProfile { guard, file, .. }: CpuProfile) -> Result<PathBuf, ProfileError> {
+    use pprof::protos::Message;
+
+    let profile = guard.report().build()?.pprof()?;
+    create_parent_dir(&file)?;
+    std::fs::write(&file, profile.encode_to_vec())?;
+    Ok(file)
+}
+
+/// Starts a sampling CPU profile.
+#[cfg(not(unix))]
+pub(crate) fn start_cpu_profile(_file: impl Into<PathBuf>) -> Result<u64, ProfileError> {
+    Err(ProfileError::Unsupported("CPU profiling is not supported on this platform"))
+}
+
+/// Stops the running CPU profile.
+#[cfg(not(unix))]
+pub(crate) fn stop_cpu_profile() -> Result<PathBuf, ProfileError> {
+    Err(ProfileError::NotRunning)
+}
+
+/// Stops the running CPU profile with the given id.
+#[cfg(not(unix))]
+pub(crate) fn stop_cpu_profile_with_id(_id: u64) -> Result<PathBuf, ProfileError> {
+    Err(ProfileError::NotRunning)
+}
+
+/// Dumps a jemalloc heap profile to the given file.
+///
+/// This requires the node to run with jemalloc heap profiling enabled, e.g. by building with the
+/// `jemalloc-prof` feature and starting it with `_RJEM_MALLOC_CONF=prof:true`. The profile can be
+/// analyzed with `jeprof`.
+#[cfg(all(feature = "jemalloc", unix))]
+pub(crate) fn write_mem_profile(file: &Path) -> Result<(), ProfileError> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    create_parent_dir(file)?;
+    let path = CString::new(file.as_os_str().as_bytes())
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+    // SAFETY: `prof.dump` takes a nul-terminated path which outlives the call
+    unsafe { jemalloc_ctl::raw::write(b"prof.dump\0", path.as_ptr()) }
+        .map_err(ProfileError::Jemalloc)
+}
+
+/// Dumps a heap profile to the given file.
+#[cfg(not(all(feature = "jemalloc", unix)))]
+pub(crate) fn write_mem_profile(_file: &Path) -> Result<(), ProfileError> {
+    Err(ProfileError::Unsupported("heap profiling requires the jemalloc allocator"))
+}
+
+/// Creates the parent directories of the given file, if missing.
+#[cfg(unix)]
+fn create_parent_dir(file: &Path) -> std::io::Result<()> {
+    match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => std::fs::create_dir_all(dir),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_profile_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("profiles").join("cpu.pprof");
+
+        assert!(matches!(stop_cpu_profile(), Err(ProfileError::NotRunning)));
+
+        let id = start_cpu_profile(&file).unwrap();
+        assert!(matches!(start_cpu_profile(&file), Err(ProfileError::AlreadyRunning)));
+
+        // burn some cycles so there's something to sample
+        let mut acc = 0u64;
+        for i in 0..10_000_000u64 {
+            acc = acc.wrapping_mul(31).wrapping_add(i);
+        }
+        std::hint::black_box(acc);
+
+        assert_eq!(stop_cpu_profile().unwrap(), file);
+        assert!(file.exists());
+        assert!(matches!(stop_cpu_profile(), Err(ProfileError::NotRunning)));
+
+        // a stale id doesn't stop a newer profile
+        let next = start_cpu_profile(&file).unwrap();
+        assert!(matches!(stop_cpu_profile_with_id(id), Err(ProfileError::NotRunning)));
+        assert_eq!(stop_cpu_profile_with_id(next).unwrap(), file);
+    }
+
+    #[test]
+    fn profile_path_stays_in_dir() {
+        let dir = Path::new("/data/profiles");
+        assert_eq!(profile_path(Some(dir), "cpu.pprof").unwrap(), dir.join("cpu.pprof"));
+        assert_eq!(profile_path(Some(dir), "a/b.pprof").unwrap(), dir.join("a/b.pprof"));
+
+        for file in ["", "/etc/passwd", "../cpu.pprof", "a/../../cpu.pprof", "./cpu.pprof"] {
+            assert!(matches!(profile_path(Some(dir), file), Err(ProfileError::InvalidPath(_))));
+        }
+        assert!(matches!(profile_path(None, "cpu.pprof"), Err(ProfileError::NoProfileDir)));
+    }
+}
@@ -5,6 +5,7 @@ use jsonrpsee::core::RpcResult;
 use reth_interfaces::RethResult;
 use reth_primitives::Block;
 use reth_rpc_types::engine::PayloadError;
+use reth_tracing::LogFilterError;
 use std::fmt::Display;
 
 /// Helper trait to easily convert various `Result` types into [`RpcResult`]
@@ -127,6 +128,17 @@ impl ToRpcResultExt for RethResult<Option<Block>> {
     }
 }
 
+/// Converts a [LogFilterError] into an invalid params error if the given directives are invalid,
+/// and into an internal error otherwise.
+pub(crate) fn log_filter_rpc_err(
+    err: LogFilterError,
+) -> jsonrpsee::types::error::ErrorObject<'static> {
+    match err {
+        LogFilterError::Parse(err) => invalid_params_rpc_err(err.to_string()),
+        LogFilterError::Reload(err) => internal_rpc_err(err.to_string()),
+    }
+}
+
 /// Constructs an invalid params JSON-RPC error.
 pub(crate) fn invalid_params_rpc_err(
     msg: impl Into<String>,
@@ -8,12 +8,20 @@ use crate::{
     BlockingTaskGuard,
 };
 use async_trait::async_trait;
-use jsonrpsee::core::RpcResult as Result;
+use jsonrpsee::{
+    core::{RpcResult as Result, SubscriptionResult},
+    server::SubscriptionMessage,
+    types::ErrorObject,
+    PendingSubscriptionSink, SubscriptionSink,
+};
 use reth_consensus_common::calc::{base_block_reward, block_reward};
 use reth_primitives::{
-    revm::env::tx_env_with_recovered, BlockId, BlockNumberOrTag, Bytes, SealedHeader, B256, U256,
+    revm::env::tx_env_with_recovered, Address, BlockId, BlockNumber, BlockNumberOrTag, Bytes,
+    SealedHeader, B256, U256,
+};
+use reth_provider::{
+    BlockReader, ChainSpecProvider, EvmEnvProvider, HistoryReader, StateProviderFactory,
 };
-use reth_provider::{BlockReader, ChainSpecProvider, EvmEnvProvider, StateProviderFactory};
 use reth_revm::{
     database::StateProviderDatabase,
     tracing::{parity::populate_state_diff, TracingInspector, TracingInspectorConfig},
@@ -21,13 +29,34 @@ use reth_revm::{
 use reth_rpc_api::TraceApiServer;
 use reth_rpc_types::{
     state::StateOverride,
-    trace::{filter::TraceFilter, parity::*, tracerequest::TraceCallRequest},
-    BlockError, BlockOverrides, CallRequest, Index,
+    trace::{
+        filter::{TraceFilter, TraceFilterMatcher, TraceFilterMode},
+        parity::*,
+        tracerequest::TraceCallRequest,
+    },
+    BlockOverrides, CallRequest, Index,
 };
+use reth_tasks::TaskSpawner;
 use revm::{db::CacheDB, primitives::Env};
 use revm_primitives::db::DatabaseCommit;
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    collections::{BTreeSet, HashSet},
+    sync::Arc,
+};
 use tokio::sync::{AcquireError, OwnedSemaphorePermit};
+use tracing::debug;
+
+/// The maximum number of blocks that are traced by `trace_filter` without the trace address index.
+const MAX_UNINDEXED_TRACE_FILTER_BLOCKS: u64 = 100;
+
+/// The maximum number of blocks that are traced for a single `trace_filter` request.
+const MAX_TRACE_FILTER_BLOCKS: usize = 1_000;
+
+/// The maximum number of traces that are returned by a single `trace_filter` request.
+const MAX_TRACE_FILTER_RESULTS: u64 = 10_000;
+
+/// The number of blocks `trace_filter` traces concurrently.
+const TRACE_FILTER_BLOCK_BATCH_SIZE: usize = 10;
 
 /// `trace` API implementation.
 ///
@@ -45,8 +74,14 @@ impl<Provider, Eth> TraceApi<Provider, Eth> {
     }
 
     /// Create a new instance of the [TraceApi]
-    pub fn new(provider: Provider, eth_api: Eth, blocking_task_guard: BlockingTaskGuard) -> Self {
-        let inner = Arc::new(TraceApiInner { provider, eth_api, blocking_task_guard });
+    pub fn new(
+        provider: Provider,
+        eth_api: Eth,
+        task_spawner: Box<dyn TaskSpawner>,
+        blocking_task_guard: BlockingTaskGuard,
+    ) -> Self {
+        let inner =
+            Arc::new(TraceApiInner { provider, eth_api, task_spawner, blocking_task_guard });
         Self { inner }
     }
 
@@ -62,7 +97,12 @@ impl<Provider, Eth> TraceApi<Provider, Eth> {
 
 impl<Provider, Eth> TraceApi<Provider, Eth>
 where
-    Provider: BlockReader + StateProviderFactory + EvmEnvProvider + ChainSpecProvider + 'static,
+    Provider: BlockReader
+        + StateProviderFactory
+        + EvmEnvProvider
+        + ChainSpecProvider
+        + HistoryReader
+        + 'static,
     Eth: EthTransactions + 'static,
 {
     /// Executes the given call and returns a number of possible traces for it.
@@ -233,14 +273,120 @@ where
 
     /// Returns all transaction traces that match the given filter.
     ///
-    /// This is similar to [Self::trace_block] but only returns traces for transactions that match
-    /// the filter.
+    /// This is similar to [Self::trace_block] but only returns traces whose action matches the
+    /// filter. The candidate blocks are traced in order, in small batches, until `count` traces
+    /// after the first `after` matching traces are collected.
+    ///
+    /// At most [MAX_TRACE_FILTER_BLOCKS] blocks are traced and at most
+    /// [MAX_TRACE_FILTER_RESULTS] traces are returned, larger requests fail. All traces of a large
+    /// request can be streamed with `trace_subscribeFilter`.
     pub async fn trace_filter(
         &self,
         filter: TraceFilter,
     ) -> EthResult<Vec<LocalizedTransactionTrace>> {
+        if filter.count.is_some_and(|count| count > MAX_TRACE_FILTER_RESULTS) {
+            return Err(EthApiError::InvalidParams(format!(
+                "count is limited to {MAX_TRACE_FILTER_RESULTS} traces"
+            )))
+        }
+        let TraceFilterRequest { matcher, blocks, mut skip, count } =
+            self.trace_filter_request(filter)?;
+        // without a count, one more trace than allowed is collected to detect too large results
+        let limit = count.unwrap_or(MAX_TRACE_FILTER_RESULTS + 1);
+
+        let mut all_traces = Vec::new();
+        if limit == 0 {
+            return Ok(all_traces)
+        }
+
+        // trace the candidate blocks in batches, so we can stop as soon as we have enough traces
+        for (idx, batch) in blocks.chunks(TRACE_FILTER_BLOCK_BATCH_SIZE).enumerate() {
+            if idx * TRACE_FILTER_BLOCK_BATCH_SIZE + batch.len() > MAX_TRACE_FILTER_BLOCKS {
+                return Err(EthApiError::InvalidParams(format!(
+                    "Too many matching blocks; currently limited to {MAX_TRACE_FILTER_BLOCKS} traced blocks, use a smaller block range or trace_subscribeFilter"
+                )))
+            }
+
+            for trace in self.trace_filter_batch(&matcher, batch).await? {
+                if skip > 0 {
+                    skip -= 1;
+                    continue
+                }
+                all_traces.push(trace);
+                if all_traces.len() as u64 == limit {
+                    if count.is_none() {
+                        return Err(EthApiError::InvalidParams(format!(
+                            "Too many traces; currently limited to {MAX_TRACE_FILTER_RESULTS} traces, paginate with after and count or use trace_subscribeFilter"
+                        )))
+                    }
+                    return Ok(all_traces)
+                }
+            }
+        }
+
+        Ok(all_traces)
+    }
+
+    /// Sends all transaction traces that match the given filter to the subscriber, in the same
+    /// order as [Self::trace_filter].
+    ///
+    /// The traces are sent as soon as the batch of blocks they belong to is traced, so neither
+    /// the number of traced blocks nor the number of traces is limited. If a block can't be
+    /// traced, the subscription is closed.
+    async fn trace_filter_stream(
+        self,
+        sink: SubscriptionSink,
+        request: TraceFilterRequest,
+    ) -> std::result::Result<(), jsonrpsee::core::Error> {
+        let TraceFilterRequest { matcher, blocks, mut skip, count } = request;
+        let mut remaining = count.unwrap_or(u64::MAX);
+
+        for batch in blocks.chunks(TRACE_FILTER_BLOCK_BATCH_SIZE) {
+            if remaining == 0 {
+                break
+            }
+
+            let traces = tokio::select! {
+                _ = sink.closed() => {
+                    // connection dropped
+                    return Ok(())
+                },
+                traces = async {
+                    let _permit = self.acquire_trace_permit().await;
+                    self.trace_filter_batch(&matcher, batch).await
+                } => traces,
+            };
+            let traces = match traces {
+                Ok(traces) => traces,
+                Err(err) => {
+                    debug!(target: "rpc::trace", %err, ?batch, "Failed to trace filtered blocks");
+                    return Ok(())
+                }
+            };
+
+            for trace in traces {
+                if skip > 0 {
+                    skip -= 1;
+                    continue
+                }
+                let msg = SubscriptionMessage::from_json(&trace)?;
+                if sink.send(msg).await.is_err() {
+                    return Ok(())
+                }
+                remaining -= 1;
+                if remaining == 0 {
+                    break
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolves the block range of the filter and the blocks that need to be traced.
+    fn trace_filter_request(&self, filter: TraceFilter) -> EthResult<TraceFilterRequest> {
         let matcher = filter.matcher();
-        let TraceFilter { from_block, to_block, after: _after, count: _count, .. } = filter;
+        let TraceFilter { from_block, to_block, after, count, .. } = filter;
         let start = from_block.unwrap_or(0);
         let end = if let Some(to_block) = to_block {
             to_block
@@ -248,68 +394,96 @@ where
             self.provider().best_block_number()?
         };
 
-        // ensure that the range is not too large, since we need to fetch all blocks in the range
-        let distance = end.saturating_sub(start);
-        if distance > 100 {
+        if start > end {
             return Err(EthApiError::InvalidParams(
-                "Block range too large; currently limited to 100 blocks".to_string(),
+                "invalid parameters: fromBlock cannot be greater than toBlock".to_string(),
             ))
         }
 
-        // fetch all blocks in that range
-        let blocks = self.provider().block_range(start..=end)?;
-
-        // find relevant blocks to trace
-        let mut target_blocks = Vec::new();
-        for block in blocks {
-            let mut transaction_indices = HashSet::new();
-            let mut highest_matching_index = 0;
-            for (tx_idx, tx) in block.body.iter().enumerate() {
-                let from = tx.recover_signer().ok_or(BlockError::InvalidSignature)?;
-                let to = tx.to();
-                if matcher.matches(from, to) {
-                    let idx = tx_idx as u64;
-                    transaction_indices.insert(idx);
-                    highest_matching_index = idx;
-                }
-            }
-            if !transaction_indices.is_empty() {
-                target_blocks.push((block.number, transaction_indices, highest_matching_index));
-            }
-        }
+        let blocks = self.trace_filter_blocks(&matcher, start, end)?;
+        Ok(TraceFilterRequest { matcher, blocks, skip: after.unwrap_or(0), count })
+    }
 
-        // trace all relevant blocks
-        let mut block_traces = Vec::with_capacity(target_blocks.len());
-        for (num, indices, highest_idx) in target_blocks {
-            let traces = self.inner.eth_api.trace_block_until(
-                num.into(),
-                Some(highest_idx),
+    /// Traces the given blocks concurrently and returns their traces that match the filter, in
+    /// block order.
+    async fn trace_filter_batch(
+        &self,
+        matcher: &TraceFilterMatcher,
+        batch: &[BlockNumber],
+    ) -> EthResult<Vec<LocalizedTransactionTrace>> {
+        let block_traces = batch.iter().map(|num| {
+            let matcher = matcher.clone();
+            self.inner.eth_api.trace_block_with(
+                (*num).into(),
                 TracingInspectorConfig::default_parity(),
                 move |tx_info, inspector, res, _, _| {
-                    if let Some(idx) = tx_info.index {
-                        if !indices.contains(&idx) {
-                            // only record traces for relevant transactions
-                            return Ok(None)
-                        }
-                    }
                     let traces = inspector
                         .with_transaction_gas_used(res.gas_used())
                         .into_parity_builder()
-                        .into_localized_transaction_traces(tx_info);
-                    Ok(Some(traces))
+                        .into_localized_transaction_traces(tx_info)
+                        .into_iter()
+                        // only record traces that match the filter
+                        .filter(|trace| matcher.matches_trace(&trace.trace))
+                        .collect::<Vec<_>>();
+                    Ok(traces)
                 },
-            );
-            block_traces.push(traces);
-        }
+            )
+        });
 
         let block_traces = futures::future::try_join_all(block_traces).await?;
-        let all_traces = block_traces
-            .into_iter()
-            .flatten()
-            .flat_map(|traces| traces.into_iter().flatten().flat_map(|traces| traces.into_iter()))
-            .collect();
+        Ok(block_traces.into_iter().flatten().flatten().flatten().collect())
+    }
 
-        Ok(all_traces)
+    /// Returns the blocks in the range `start..=end` that can contain traces matching the filter,
+    /// in ascending order.
+    ///
+    /// If the trace address index is maintained, only the indexed blocks in which one of the
+    /// filtered addresses appears are returned. Blocks that are not covered by the index yet are
+    /// always returned, and their number is limited to [MAX_UNINDEXED_TRACE_FILTER_BLOCKS].
+    fn trace_filter_blocks(
+        &self,
+        matcher: &TraceFilterMatcher,
+        start: BlockNumber,
+        end: BlockNumber,
+    ) -> EthResult<Vec<BlockNumber>> {
+        let indexed_tip = if matcher.matches_all() {
+            // every block matches, so the index is of no use
+            None
+        } else {
+            self.provider().trace_address_index_tip()?.filter(|tip| *tip >= start)
+        };
+
+        let Some(indexed_tip) = indexed_tip else {
+            ensure_unindexed_trace_filter_range(start, end)?;
+            return Ok((start..=end).collect())
+        };
+
+        let indexed_end = end.min(indexed_tip);
+        let indexed_blocks = |addresses: &HashSet<Address>| -> EthResult<BTreeSet<BlockNumber>> {
+            let mut blocks = BTreeSet::new();
+            for address in addresses {
+                blocks.extend(self.provider().trace_address_blocks(*address, start..=indexed_end)?);
+            }
+            Ok(blocks)
+        };
+        let from_blocks = indexed_blocks(matcher.from_addresses())?;
+        let to_blocks = indexed_blocks(matcher.to_addresses())?;
+
+        let mut blocks = if matcher.mode() == TraceFilterMode::Intersection &&
+            !matcher.from_addresses().is_empty() &&
+            !matcher.to_addresses().is_empty()
+        {
+            from_blocks.intersection(&to_blocks).copied().collect::<Vec<_>>()
+        } else {
+            from_blocks.union(&to_blocks).copied().collect::<Vec<_>>()
+        };
+
+        if end > indexed_end {
+            ensure_unindexed_trace_filter_range(indexed_end + 1, end)?;
+            blocks.extend(indexed_end + 1..=end);
+        }
+
+        Ok(blocks)
     }
 
     /// Returns all traces for the given transaction hash
@@ -428,7 +602,12 @@ where
 #[async_trait]
 impl<Provider, Eth> TraceApiServer for TraceApi<Provider, Eth>
 where
-    Provider: BlockReader + StateProviderFactory + EvmEnvProvider + ChainSpecProvider + 'static,
+    Provider: BlockReader
+        + StateProviderFactory
+        + EvmEnvProvider
+        + ChainSpecProvider
+        + HistoryReader
+        + 'static,
     Eth: EthTransactions + 'static,
 {
     /// Executes the given call and returns a number of possible traces for it.
@@ -503,11 +682,40 @@ where
     /// This is similar to `eth_getLogs` but for traces.
     ///
     /// # Limitations
-    /// This currently requires block filter fields, since reth does not have address indices yet.
+    /// Without the trace address index, the block range is limited to
+    /// [MAX_UNINDEXED_TRACE_FILTER_BLOCKS] blocks. With the index, only the blocks that are not
+    /// indexed yet count towards that limit.
+    ///
+    /// At most [MAX_TRACE_FILTER_BLOCKS] blocks are traced and at most
+    /// [MAX_TRACE_FILTER_RESULTS] traces are returned per request. Large result sets should be
+    /// paginated with `after` and `count`, or streamed with `trace_subscribeFilter`.
     async fn trace_filter(&self, filter: TraceFilter) -> Result<Vec<LocalizedTransactionTrace>> {
         Ok(TraceApi::trace_filter(self, filter).await?)
     }
 
+    /// Handler for `trace_subscribeFilter`
+    async fn trace_subscribe_filter(
+        &self,
+        pending: PendingSubscriptionSink,
+        filter: TraceFilter,
+    ) -> SubscriptionResult {
+        let request = match self.trace_filter_request(filter) {
+            Ok(request) => request,
+            Err(err) => {
+                pending.reject(ErrorObject::from(err)).await;
+                return Ok(())
+            }
+        };
+
+        let sink = pending.accept().await?;
+        let this = self.clone();
+        self.inner.task_spawner.spawn(Box::pin(async move {
+            let _ = this.trace_filter_stream(sink, request).await;
+        }));
+
+        Ok(())
+    }
+
     /// Returns transaction trace at given index.
     /// Handler for `trace_get`
     async fn trace_get(
@@ -545,10 +753,24 @@ struct TraceApiInner<Provider, Eth> {
     provider: Provider,
     /// Access to commonly used code of the `eth` namespace
     eth_api: Eth,
+    /// The type that can spawn tasks, used for `trace_subscribeFilter`.
+    task_spawner: Box<dyn TaskSpawner>,
     // restrict the number of concurrent calls to `trace_*`
     blocking_task_guard: BlockingTaskGuard,
 }
 
+/// A resolved `trace_filter` request.
+struct TraceFilterRequest {
+    /// Matches the traces to return.
+    matcher: TraceFilterMatcher,
+    /// The blocks that can contain matching traces, in ascending order.
+    blocks: Vec<BlockNumber>,
+    /// The number of matching traces to skip.
+    skip: u64,
+    /// The maximum number of traces to return, if any.
+    count: Option<u64>,
+}
+
 /// Returns the [TracingInspectorConfig] depending on the enabled [TraceType]s
 ///
 /// Note: the parity statediffs can be populated entirely via the execution result, so we don't need
@@ -561,6 +783,17 @@ fn tracing_config(trace_types: &HashSet<TraceType>) -> TracingInspectorConfig {
         .set_memory_snapshots(needs_vm_trace)
 }
 
+/// Ensures that the range of blocks that are not covered by the trace address index is not too
+/// large, since all of them need to be traced.
+fn ensure_unindexed_trace_filter_range(start: BlockNumber, end: BlockNumber) -> EthResult<()> {
+    if end.saturating_sub(start) > MAX_UNINDEXED_TRACE_FILTER_BLOCKS {
+        return Err(EthApiError::InvalidParams(format!(
+            "Block range too large; currently limited to {MAX_UNINDEXED_TRACE_FILTER_BLOCKS} blocks that are not covered by the trace address index"
+        )))
+    }
+    Ok(())
+}
+
 /// Helper to construct a [`LocalizedTransactionTrace`] that describes a reward to the block
 /// beneficiary.
 fn reward_trace(header: &SealedHeader, reward: RewardAction) -> LocalizedTransactionTrace {
@@ -582,6 +815,46 @@ fn reward_trace(header: &SealedHeader, reward: RewardAction) -> LocalizedTransac
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        eth::{cache::EthStateCache, gas_oracle::GasPriceOracle},
+        BlockingTaskPool, EthApi,
+    };
+    use assert_matches::assert_matches;
+    use reth_interfaces::test_utils::generators::{self, random_block_range};
+    use reth_network_api::noop::NoopNetwork;
+    use reth_primitives::constants::ETHEREUM_BLOCK_GAS_LIMIT;
+    use reth_provider::test_utils::MockEthProvider;
+    use reth_tasks::TokioTaskExecutor;
+    use reth_transaction_pool::test_utils::{testing_pool, TestPool};
+
+    type TestTraceApi = TraceApi<MockEthProvider, EthApi<MockEthProvider, TestPool, NoopNetwork>>;
+
+    /// Creates the trace API for a provider that holds blocks 0 to 10 without transactions.
+    fn trace_api() -> TestTraceApi {
+        let mut rng = generators::rng();
+        let provider = MockEthProvider::default();
+        provider.extend_blocks(
+            random_block_range(&mut rng, 0..=10, B256::ZERO, 0..1)
+                .into_iter()
+                .map(|block| (block.hash, block.unseal())),
+        );
+        let cache = EthStateCache::spawn(provider.clone(), Default::default());
+        let eth_api = EthApi::new(
+            provider.clone(),
+            testing_pool(),
+            NoopNetwork::default(),
+            cache.clone(),
+            GasPriceOracle::new(provider.clone(), Default::default(), cache),
+            ETHEREUM_BLOCK_GAS_LIMIT,
+            BlockingTaskPool::build().expect("failed to build tracing pool"),
+        );
+        TraceApi::new(
+            provider,
+            eth_api,
+            Box::<TokioTaskExecutor>::default(),
+            BlockingTaskGuard::new(4),
+        )
+    }
 
     #[test]
     fn test_parity_config() {
@@ -606,4 +879,43 @@ mod tests {
         // not required for StateDiff
         assert!(!config.record_state_diff);
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn trace_filter_limits_count() {
+        let api = trace_api();
+        let filter = |count| TraceFilter {
+            from_block: Some(0),
+            to_block: Some(10),
+            count: Some(count),
+            ..Default::default()
+        };
+
+        assert_matches!(
+            api.trace_filter(filter(MAX_TRACE_FILTER_RESULTS + 1)).await,
+            Err(EthApiError::InvalidParams(_))
+        );
+        assert_matches!(api.trace_filter(filter(MAX_TRACE_FILTER_RESULTS)).await, Ok(traces) if traces.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn trace_subscribe_filter() {
+        let module = trace_api().into_rpc();
+
+        let filter = TraceFilter { from_block: Some(2), to_block: Some(10), ..Default::default() };
+        let mut subscription =
+            module.subscribe_unbounded("trace_subscribeFilter", [filter]).await.unwrap();
+        // the blocks don't have any transactions, so the subscription closes without traces
+        assert!(subscription.next::<LocalizedTransactionTrace>().await.is_none());
+
+        let filter = TraceFilter { from_block: Some(10), to_block: Some(2), ..Default::default() };
+        assert!(module.subscribe_unbounded("trace_subscribeFilter", [filter]).await.is_err());
+    }
+
+    #[test]
+    fn test_unindexed_trace_filter_range() {
+        assert!(ensure_unindexed_trace_filter_range(0, MAX_UNINDEXED_TRACE_FILTER_BLOCKS).is_ok());
+        assert!(
+            ensure_unindexed_trace_filter_range(0, MAX_UNINDEXED_TRACE_FILTER_BLOCKS + 1).is_err()
+        );
+    }
 }
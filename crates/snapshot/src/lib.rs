@@ -14,7 +14,7 @@ pub mod segments;
 mod snapshotter;
 
 pub use error::SnapshotterError;
+pub use reth_primitives::snapshot::HighestSnapshots;
 pub use snapshotter::{
-    HighestSnapshots, HighestSnapshotsTracker, SnapshotTargets, Snapshotter, SnapshotterResult,
-    SnapshotterWithResult,
+    HighestSnapshotsTracker, SnapshotTargets, Snapshotter, SnapshotterResult, SnapshotterWithResult,
 };
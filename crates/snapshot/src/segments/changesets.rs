@@ -0,0 +1,184 @@
+use crate::segments::{prepare_jar, Segment};
+use reth_db::{
+    cursor::DbCursorRO,
+    database::Database,
+    models::{AccountChangeSetBlock, BlockNumberAddress, StorageBeforeTx, StorageChangeSetBlock},
+    table::Compress,
+    tables,
+    transaction::DbTx,
+    DatabaseError,
+};
+use reth_interfaces::RethResult;
+use reth_primitives::{
+    snapshot::{Compression, Filters},
+    BlockNumber, SnapshotSegment,
+};
+use reth_provider::{DatabaseProviderRO, DatabaseProviderRW};
+use std::{ops::RangeInclusive, path::Path};
+
+/// Snapshot segment responsible for [SnapshotSegment::AccountChangeSets] part of data.
+///
+/// Every row of the snapshot holds the [`AccountChangeSetBlock`] of a single block, so the row
+/// offsets of the jar double as a per-block index.
+#[derive(Debug)]
+pub struct AccountChangeSets {
+    compression: Compression,
+}
+
+impl AccountChangeSets {
+    /// Creates new instance of [AccountChangeSets] snapshot segment.
+    pub fn new(compression: Compression) -> Self {
+        Self { compression }
+    }
+}
+
+impl Segment for AccountChangeSets {
+    fn snapshot<DB: Database>(
+        &self,
+        provider: &DatabaseProviderRO<'_, DB>,
+        directory: impl AsRef<Path>,
+        block_range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<()> {
+        let range_len = block_range.clone().count();
+        let mut cursor = provider.tx_ref().cursor_read::<tables::AccountChangeSet>()?;
+
+        let mut jar = prepare_jar::<DB, 1>(
+            provider,
+            directory,
+            SnapshotSegment::AccountChangeSets,
+            Filters::WithoutFilters,
+            self.compression,
+            block_range.clone(),
+            range_len,
+            || {
+                let mut cursor = provider.tx_ref().cursor_read::<tables::AccountChangeSet>()?;
+                Ok([dataset_range(&block_range)
+                    .map(|block| {
+                        account_changeset_block(&mut cursor, block).map(Compress::compress)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?])
+            },
+        )?;
+
+        let rows = block_range.map(|block| {
+            account_changeset_block(&mut cursor, block).map(Compress::compress).map_err(Into::into)
+        });
+        jar.freeze(vec![rows], range_len as u64)?;
+
+        Ok(())
+    }
+
+    fn prune<DB: Database>(
+        &self,
+        provider: &DatabaseProviderRW<'_, DB>,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<()> {
+        provider.prune_table_with_range::<tables::AccountChangeSet>(
+            range,
+            usize::MAX,
+            |_| false,
+            |_| {},
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Snapshot segment responsible for [SnapshotSegment::StorageChangeSets] part of data.
+///
+/// Every row of the snapshot holds the [`StorageChangeSetBlock`] of a single block, so the row
+/// offsets of the jar double as a per-block index.
+#[derive(Debug)]
+pub struct StorageChangeSets {
+    compression: Compression,
+}
+
+impl StorageChangeSets {
+    /// Creates new instance of [StorageChangeSets] snapshot segment.
+    pub fn new(compression: Compression) -> Self {
+        Self { compression }
+    }
+}
+
+impl Segment for StorageChangeSets {
+    fn snapshot<DB: Database>(
+        &self,
+        provider: &DatabaseProviderRO<'_, DB>,
+        directory: impl AsRef<Path>,
+        block_range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<()> {
+        let range_len = block_range.clone().count();
+        let mut cursor = provider.tx_ref().cursor_read::<tables::StorageChangeSet>()?;
+
+        let mut jar = prepare_jar::<DB, 1>(
+            provider,
+            directory,
+            SnapshotSegment::StorageChangeSets,
+            Filters::WithoutFilters,
+            self.compression,
+            block_range.clone(),
+            range_len,
+            || {
+                let mut cursor = provider.tx_ref().cursor_read::<tables::StorageChangeSet>()?;
+                Ok([dataset_range(&block_range)
+                    .map(|block| {
+                        storage_changeset_block(&mut cursor, block).map(Compress::compress)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?])
+            },
+        )?;
+
+        let rows = block_range.map(|block| {
+            storage_changeset_block(&mut cursor, block).map(Compress::compress).map_err(Into::into)
+        });
+        jar.freeze(vec![rows], range_len as u64)?;
+
+        Ok(())
+    }
+
+    fn prune<DB: Database>(
+        &self,
+        provider: &DatabaseProviderRW<'_, DB>,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<()> {
+        provider.prune_table_with_range::<tables::StorageChangeSet>(
+            BlockNumberAddress::range(range),
+            usize::MAX,
+            |_| false,
+            |_| {},
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Returns the most recent blocks of the range (at most 1000) to train a zstd dictionary with.
+fn dataset_range(block_range: &RangeInclusive<BlockNumber>) -> RangeInclusive<BlockNumber> {
+    block_range.end().saturating_sub(999).max(*block_range.start())..=*block_range.end()
+}
+
+/// Reads all account changes of the block. Entries are sorted by address.
+fn account_changeset_block(
+    cursor: &mut impl DbCursorRO<tables::AccountChangeSet>,
+    block: BlockNumber,
+) -> Result<AccountChangeSetBlock, DatabaseError> {
+    cursor
+        .walk_range(block..=block)?
+        .map(|entry| entry.map(|(_, account)| account))
+        .collect::<Result<_, _>>()
+        .map(AccountChangeSetBlock)
+}
+
+/// Reads all storage changes of the block. Entries are sorted by address and storage key.
+fn storage_changeset_block(
+    cursor: &mut impl DbCursorRO<tables::StorageChangeSet>,
+    block: BlockNumber,
+) -> Result<StorageChangeSetBlock, DatabaseError> {
+    cursor
+        .walk_range(BlockNumberAddress::range(block..=block))?
+        .map(|entry| {
+            entry.map(|(key, storage)| StorageBeforeTx { address: key.address(), entry: storage })
+        })
+        .collect::<Result<_, _>>()
+        .map(StorageChangeSetBlock)
+}
@@ -8,8 +8,8 @@ use reth_primitives::{
     snapshot::{Compression, Filters},
     BlockNumber, SnapshotSegment,
 };
-use reth_provider::DatabaseProviderRO;
-use std::ops::RangeInclusive;
+use reth_provider::{DatabaseProviderRO, DatabaseProviderRW};
+use std::{ops::RangeInclusive, path::Path};
 
 /// Snapshot segment responsible for [SnapshotSegment::Headers] part of data.
 #[derive(Debug)]
@@ -29,11 +29,13 @@ impl Segment for Headers {
     fn snapshot<DB: Database>(
         &self,
         provider: &DatabaseProviderRO<'_, DB>,
+        directory: impl AsRef<Path>,
         range: RangeInclusive<BlockNumber>,
     ) -> RethResult<()> {
         let range_len = range.clone().count();
         let mut jar = prepare_jar::<DB, 3>(
             provider,
+            directory,
             SnapshotSegment::Headers,
             self.filters,
             self.compression,
@@ -85,4 +87,27 @@ impl Segment for Headers {
 
         Ok(())
     }
+
+    fn prune<DB: Database>(
+        &self,
+        provider: &DatabaseProviderRW<'_, DB>,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<()> {
+        // Canonical hashes are kept in the database, as they're read directly from it, e.g. to
+        // find the canonical tip.
+        provider.prune_table_with_range::<tables::Headers>(
+            range.clone(),
+            usize::MAX,
+            |_| false,
+            |_| {},
+        )?;
+        provider.prune_table_with_range::<tables::HeaderTD>(
+            range,
+            usize::MAX,
+            |_| false,
+            |_| {},
+        )?;
+
+        Ok(())
+    }
 }
@@ -9,26 +9,41 @@ pub use headers::Headers;
 mod receipts;
 pub use receipts::Receipts;
 
+mod changesets;
+pub use changesets::{AccountChangeSets, StorageChangeSets};
+
 use reth_db::{
     cursor::DbCursorRO, database::Database, table::Table, transaction::DbTx, RawKey, RawTable,
 };
-use reth_interfaces::RethResult;
-use reth_nippy_jar::NippyJar;
+use reth_interfaces::{provider::ProviderError, RethResult};
+use reth_nippy_jar::{
+    compression::{Compressors, Lz4, Zstd},
+    NippyJar,
+};
 use reth_primitives::{
     snapshot::{Compression, Filters, InclusionFilter, PerfectHashingFunction, SegmentHeader},
-    BlockNumber, SnapshotSegment,
+    BlockNumber, SnapshotSegment, TxNumber,
 };
-use reth_provider::{DatabaseProviderRO, TransactionsProviderExt};
-use std::ops::RangeInclusive;
+use reth_provider::{BlockReader, DatabaseProviderRO, DatabaseProviderRW, TransactionsProviderExt};
+use std::{ops::RangeInclusive, path::Path};
 
 pub(crate) type Rows<const COLUMNS: usize> = [Vec<Vec<u8>>; COLUMNS];
 
 /// A segment represents a snapshotting of some portion of the data.
 pub trait Segment {
-    /// Snapshot data using the provided range.
+    /// Snapshot data using the provided range, writing the snapshot into the given directory.
     fn snapshot<DB: Database>(
         &self,
         provider: &DatabaseProviderRO<'_, DB>,
+        directory: impl AsRef<Path>,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<()>;
+
+    /// Deletes the data of the given range from the database, once it was snapshotted and can be
+    /// served from the snapshot.
+    fn prune<DB: Database>(
+        &self,
+        provider: &DatabaseProviderRW<'_, DB>,
         range: RangeInclusive<BlockNumber>,
     ) -> RethResult<()>;
 
@@ -51,6 +66,7 @@ pub trait Segment {
 /// Returns a [`NippyJar`] according to the desired configuration.
 pub(crate) fn prepare_jar<DB: Database, const COLUMNS: usize>(
     provider: &DatabaseProviderRO<'_, DB>,
+    directory: impl AsRef<Path>,
     segment: SnapshotSegment,
     filters: Filters,
     compression: Compression,
@@ -61,7 +77,11 @@ pub(crate) fn prepare_jar<DB: Database, const COLUMNS: usize>(
     let tx_range = provider.transaction_range_by_block_range(block_range.clone())?;
     let mut nippy_jar = NippyJar::new(
         COLUMNS,
-        &segment.filename_with_configuration(filters, compression, &block_range),
+        &directory.as_ref().join(segment.filename_with_configuration(
+            filters,
+            compression,
+            &block_range,
+        )),
         SegmentHeader::new(block_range, tx_range, segment),
     );
 
@@ -76,6 +96,20 @@ pub(crate) fn prepare_jar<DB: Database, const COLUMNS: usize>(
             nippy_jar
         }
         Compression::Uncompressed => nippy_jar,
+        Compression::PerColumn => {
+            let layout = segment.column_compression();
+            debug_assert_eq!(layout.len(), COLUMNS, "{segment:?} column layout");
+
+            nippy_jar = nippy_jar
+                .with_column_compressors(layout.iter().copied().map(column_compressor).collect());
+            if layout
+                .iter()
+                .any(|compression| matches!(compression, Compression::ZstdWithDictionary))
+            {
+                nippy_jar.prepare_compression(prepare_compression()?.to_vec())?;
+            }
+            nippy_jar
+        }
     };
 
     if let Filters::WithFilters(inclusion_filter, phf) = filters {
@@ -90,3 +124,39 @@ pub(crate) fn prepare_jar<DB: Database, const COLUMNS: usize>(
 
     Ok(nippy_jar)
 }
+
+/// Returns the compressor of a single column of a [`Compression::PerColumn`] jar, or `None` if
+/// the column is left uncompressed.
+fn column_compressor(compression: Compression) -> Option<Compressors> {
+    match compression {
+        Compression::Lz4 => Some(Compressors::Lz4(Lz4::default())),
+        Compression::Zstd => Some(Compressors::Zstd(Zstd::new(false, 0, 1))),
+        Compression::ZstdWithDictionary => Some(Compressors::Zstd(Zstd::new(true, 5_000_000, 1))),
+        Compression::Uncompressed | Compression::PerColumn => None,
+    }
+}
+
+/// Deletes the rows of a table keyed by transaction number that belong to the given block range.
+pub(crate) fn prune_transaction_table<DB: Database, T: Table<Key = TxNumber>>(
+    provider: &DatabaseProviderRW<'_, DB>,
+    block_range: RangeInclusive<BlockNumber>,
+) -> RethResult<()> {
+    let first_tx_num = provider
+        .block_body_indices(*block_range.start())?
+        .ok_or(ProviderError::BlockBodyIndicesNotFound(*block_range.start()))?
+        .first_tx_num();
+    // Blocks without transactions have an empty range, unlike with `last_tx_num`.
+    let next_tx_num = provider
+        .block_body_indices(*block_range.end())?
+        .ok_or(ProviderError::BlockBodyIndicesNotFound(*block_range.end()))?
+        .next_tx_num();
+
+    provider.prune_table_with_range::<T>(
+        first_tx_num..next_tx_num,
+        usize::MAX,
+        |_| false,
+        |_| {},
+    )?;
+
+    Ok(())
+}
@@ -1,12 +1,12 @@
-use crate::segments::{prepare_jar, Segment};
+use crate::segments::{prepare_jar, prune_transaction_table, Segment};
 use reth_db::{database::Database, snapshot::create_snapshot_T1, tables};
 use reth_interfaces::RethResult;
 use reth_primitives::{
     snapshot::{Compression, Filters, SegmentHeader},
     BlockNumber, SnapshotSegment, TxNumber,
 };
-use reth_provider::{DatabaseProviderRO, TransactionsProviderExt};
-use std::ops::RangeInclusive;
+use reth_provider::{DatabaseProviderRO, DatabaseProviderRW, TransactionsProviderExt};
+use std::{ops::RangeInclusive, path::Path};
 
 /// Snapshot segment responsible for [SnapshotSegment::Transactions] part of data.
 #[derive(Debug)]
@@ -26,6 +26,7 @@ impl Segment for Transactions {
     fn snapshot<DB: Database>(
         &self,
         provider: &DatabaseProviderRO<'_, DB>,
+        directory: impl AsRef<Path>,
         block_range: RangeInclusive<BlockNumber>,
     ) -> RethResult<()> {
         let tx_range = provider.transaction_range_by_block_range(block_range.clone())?;
@@ -33,6 +34,7 @@ impl Segment for Transactions {
 
         let mut jar = prepare_jar::<DB, 1>(
             provider,
+            directory,
             SnapshotSegment::Transactions,
             self.filters,
             self.compression,
@@ -71,4 +73,12 @@ impl Segment for Transactions {
 
         Ok(())
     }
+
+    fn prune<DB: Database>(
+        &self,
+        provider: &DatabaseProviderRW<'_, DB>,
+        block_range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<()> {
+        prune_transaction_table::<DB, tables::Transactions>(provider, block_range)
+    }
 }
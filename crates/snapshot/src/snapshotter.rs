@@ -1,13 +1,23 @@
 //! Support for snapshotting.
 
-use crate::SnapshotterError;
+use crate::{segments, segments::Segment, SnapshotterError};
 use reth_db::database::Database;
 use reth_interfaces::{RethError, RethResult};
-use reth_primitives::{BlockNumber, ChainSpec, TxNumber};
+use reth_primitives::{
+    snapshot::HighestSnapshots, BlockNumber, ChainSpec, PruneModes, SnapshotSegment, TxNumber,
+};
 use reth_provider::{BlockReader, DatabaseProviderRO, ProviderFactory};
-use std::{collections::HashMap, ops::RangeInclusive, sync::Arc};
+use std::{
+    collections::HashMap,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use tokio::sync::watch;
-use tracing::warn;
+use tracing::{debug, warn};
+
+/// Number of snapshotted blocks deleted from the database in a single write transaction.
+const PRUNE_BATCH_SIZE: u64 = 10_000;
 
 /// Result of [Snapshotter::run] execution.
 pub type SnapshotterResult = Result<SnapshotTargets, SnapshotterError>;
@@ -19,41 +29,37 @@ pub type SnapshotterWithResult<DB> = (Snapshotter<DB>, SnapshotterResult);
 #[derive(Debug)]
 pub struct Snapshotter<DB> {
     provider_factory: ProviderFactory<DB>,
+    /// Directory where the snapshots are stored.
+    snapshots_path: PathBuf,
     highest_snapshots: HighestSnapshots,
     highest_snapshots_tracker: watch::Sender<Option<HighestSnapshots>>,
     /// Block interval after which the snapshot is taken.
     block_interval: u64,
+    /// Pruning configuration of the node. Segments with pruned data are not snapshotted.
+    prune_modes: PruneModes,
 }
 
 /// Tracker for the latest [`HighestSnapshots`] value.
 pub type HighestSnapshotsTracker = watch::Receiver<Option<HighestSnapshots>>;
 
-/// Highest snapshotted block numbers, per data part.
-#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
-pub struct HighestSnapshots {
-    /// Highest snapshotted block of headers, inclusive.
-    /// If [`None`], no snapshot is available.
-    pub headers: Option<BlockNumber>,
-    /// Highest snapshotted block of receipts, inclusive.
-    /// If [`None`], no snapshot is available.
-    pub receipts: Option<BlockNumber>,
-    /// Highest snapshotted block of transactions, inclusive.
-    /// If [`None`], no snapshot is available.
-    pub transactions: Option<BlockNumber>,
-}
-
 /// Snapshot targets, per data part, measured in [`BlockNumber`] and [`TxNumber`], if applicable.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct SnapshotTargets {
     headers: Option<RangeInclusive<BlockNumber>>,
     receipts: Option<(RangeInclusive<BlockNumber>, RangeInclusive<TxNumber>)>,
     transactions: Option<(RangeInclusive<BlockNumber>, RangeInclusive<TxNumber>)>,
+    account_changesets: Option<RangeInclusive<BlockNumber>>,
+    storage_changesets: Option<RangeInclusive<BlockNumber>>,
 }
 
 impl SnapshotTargets {
     /// Returns `true` if any of the targets are [Some].
     pub fn any(&self) -> bool {
-        self.headers.is_some() || self.receipts.is_some() || self.transactions.is_some()
+        self.headers.is_some() ||
+            self.receipts.is_some() ||
+            self.transactions.is_some() ||
+            self.account_changesets.is_some() ||
+            self.storage_changesets.is_some()
     }
 
     /// Returns `true` if all targets are either [None] or multiple of `block_interval`.
@@ -62,6 +68,8 @@ impl SnapshotTargets {
             self.headers.as_ref(),
             self.receipts.as_ref().map(|(blocks, _)| blocks),
             self.transactions.as_ref().map(|(blocks, _)| blocks),
+            self.account_changesets.as_ref(),
+            self.storage_changesets.as_ref(),
         ]
         .iter()
         .all(|blocks| blocks.map_or(true, |blocks| (blocks.end() + 1) % block_interval == 0))
@@ -74,6 +82,8 @@ impl SnapshotTargets {
             (self.headers.as_ref(), snapshots.headers),
             (self.receipts.as_ref().map(|(blocks, _)| blocks), snapshots.receipts),
             (self.transactions.as_ref().map(|(blocks, _)| blocks), snapshots.transactions),
+            (self.account_changesets.as_ref(), snapshots.account_changesets),
+            (self.storage_changesets.as_ref(), snapshots.storage_changesets),
         ]
         .iter()
         .all(|(target, highest)| {
@@ -87,24 +97,48 @@ impl SnapshotTargets {
 }
 
 impl<DB: Database> Snapshotter<DB> {
-    /// Creates a new [Snapshotter].
+    /// Creates a new [Snapshotter] that stores the snapshots in the given directory.
+    ///
+    /// The highest snapshotted blocks are restored from the snapshots that already exist in the
+    /// directory.
     pub fn new(
         db: DB,
+        snapshots_path: impl AsRef<Path>,
         chain_spec: Arc<ChainSpec>,
         block_interval: u64,
         highest_snapshots_tracker: watch::Sender<Option<HighestSnapshots>>,
-    ) -> Self {
+    ) -> RethResult<Self> {
+        let snapshots_path = snapshots_path.as_ref().to_path_buf();
+        let highest_snapshots = std::fs::create_dir_all(&snapshots_path)
+            .and_then(|_| HighestSnapshots::from_directory(&snapshots_path))
+            .map_err(|err| RethError::Custom(format!("failed to load snapshots: {err}")))?;
+
         let snapshotter = Self {
             provider_factory: ProviderFactory::new(db, chain_spec),
-            // TODO(alexey): fill from on-disk snapshot data
-            highest_snapshots: HighestSnapshots::default(),
+            snapshots_path,
+            highest_snapshots,
             highest_snapshots_tracker,
             block_interval,
+            prune_modes: PruneModes::none(),
         };
 
         snapshotter.update_highest_snapshots_tracker();
 
-        snapshotter
+        Ok(snapshotter)
+    }
+
+    /// Sets the pruning configuration of the node.
+    ///
+    /// Snapshots always start at the genesis block, so receipts and changesets that are pruned
+    /// from the database are not snapshotted at all.
+    pub fn with_prune_modes(mut self, prune_modes: PruneModes) -> Self {
+        self.prune_modes = prune_modes;
+        self
+    }
+
+    /// Returns the highest snapshotted block numbers.
+    pub fn highest_snapshots(&self) -> HighestSnapshots {
+        self.highest_snapshots
     }
 
     #[cfg(test)]
@@ -118,6 +152,12 @@ impl<DB: Database> Snapshotter<DB> {
         if let Some((block_number, _)) = &targets.transactions {
             self.highest_snapshots.transactions = Some(*block_number.end());
         }
+        if let Some(block_number) = &targets.account_changesets {
+            self.highest_snapshots.account_changesets = Some(*block_number.end());
+        }
+        if let Some(block_number) = &targets.storage_changesets {
+            self.highest_snapshots.storage_changesets = Some(*block_number.end());
+        }
     }
 
     fn update_highest_snapshots_tracker(&self) {
@@ -127,17 +167,102 @@ impl<DB: Database> Snapshotter<DB> {
     }
 
     /// Run the snapshotter
+    ///
+    /// Snapshots the data of each segment according to the targets with the default
+    /// configuration of the segment, and updates the highest snapshotted blocks once the snapshot
+    /// of a segment was written. The snapshotted data is then deleted from the database.
     pub fn run(&mut self, targets: SnapshotTargets) -> SnapshotterResult {
         debug_assert!(targets.is_multiple_of_block_interval(self.block_interval));
         debug_assert!(targets.is_contiguous_to_highest_snapshots(self.highest_snapshots));
 
-        // TODO(alexey): snapshot logic
+        if let Some(block_range) = targets.headers.clone() {
+            let (filters, compression) = SnapshotSegment::Headers.config();
+            self.run_segment(
+                SnapshotSegment::Headers,
+                segments::Headers::new(compression, filters),
+                block_range,
+            )?;
+        }
+
+        // Receipts are snapshotted before transactions, because their filters are built from the
+        // hashes of the transactions that are deleted from the database once snapshotted.
+        if let Some((block_range, _)) = targets.receipts.clone() {
+            let (filters, compression) = SnapshotSegment::Receipts.config();
+            self.run_segment(
+                SnapshotSegment::Receipts,
+                segments::Receipts::new(compression, filters),
+                block_range,
+            )?;
+        }
 
-        self.update_highest_snapshots_tracker();
+        if let Some((block_range, _)) = targets.transactions.clone() {
+            let (filters, compression) = SnapshotSegment::Transactions.config();
+            self.run_segment(
+                SnapshotSegment::Transactions,
+                segments::Transactions::new(compression, filters),
+                block_range,
+            )?;
+        }
+
+        if let Some(block_range) = targets.account_changesets.clone() {
+            let (_, compression) = SnapshotSegment::AccountChangeSets.config();
+            self.run_segment(
+                SnapshotSegment::AccountChangeSets,
+                segments::AccountChangeSets::new(compression),
+                block_range,
+            )?;
+        }
+
+        if let Some(block_range) = targets.storage_changesets.clone() {
+            let (_, compression) = SnapshotSegment::StorageChangeSets.config();
+            self.run_segment(
+                SnapshotSegment::StorageChangeSets,
+                segments::StorageChangeSets::new(compression),
+                block_range,
+            )?;
+        }
 
         Ok(targets)
     }
 
+    /// Snapshots the given block range of a single segment and updates the highest snapshotted
+    /// block of the segment.
+    ///
+    /// The range is deleted from the database only after the highest snapshots tracker was
+    /// updated, so that providers serve it from the new snapshot from then on.
+    fn run_segment(
+        &mut self,
+        segment: SnapshotSegment,
+        snapshot: impl Segment,
+        block_range: RangeInclusive<BlockNumber>,
+    ) -> Result<(), SnapshotterError> {
+        debug!(target: "snapshot", ?segment, ?block_range, "Snapshotting segment");
+
+        snapshot.snapshot::<DB>(
+            &self.provider_factory.provider()?,
+            &self.snapshots_path,
+            block_range.clone(),
+        )?;
+
+        *self.highest_snapshots.as_mut(segment) = Some(*block_range.end());
+        self.update_highest_snapshots_tracker();
+
+        // Delete in batches, so that the database write lock isn't held for the whole range.
+        let mut batch_start = *block_range.start();
+        while batch_start <= *block_range.end() {
+            let batch_end = (batch_start + PRUNE_BATCH_SIZE - 1).min(*block_range.end());
+
+            let provider = self.provider_factory.provider_rw()?;
+            snapshot.prune::<DB>(&provider, batch_start..=batch_end)?;
+            provider.commit()?;
+
+            batch_start = batch_end + 1;
+        }
+
+        debug!(target: "snapshot", ?segment, ?block_range, "Finished snapshotting segment");
+        Ok(())
+    }
+
     /// Returns a snapshot targets at the provided finalized block number, respecting the block
     /// interval. The target is determined by the check against last snapshots.
     pub fn get_snapshot_targets(
@@ -159,6 +284,14 @@ impl<DB: Database> Snapshotter<DB> {
             self.get_snapshot_target_block_range(to_block_number, self.highest_snapshots.receipts);
         let transactions_block_range = self
             .get_snapshot_target_block_range(to_block_number, self.highest_snapshots.transactions);
+        let account_changesets_block_range = self.get_snapshot_target_block_range(
+            to_block_number,
+            self.highest_snapshots.account_changesets,
+        );
+        let storage_changesets_block_range = self.get_snapshot_target_block_range(
+            to_block_number,
+            self.highest_snapshots.storage_changesets,
+        );
 
         // Calculate transaction ranges to snapshot
         let mut block_to_tx_number_cache = HashMap::default();
@@ -175,6 +308,13 @@ impl<DB: Database> Snapshotter<DB> {
             &transactions_block_range,
         )?;
 
+        // Snapshots always start at the genesis block, so segments with data pruned from the
+        // database are never snapshotted.
+        let receipts_pruned =
+            self.prune_modes.receipts.is_some() || !self.prune_modes.receipts_log_filter.is_empty();
+        let account_history_pruned = self.prune_modes.account_history.is_some();
+        let storage_history_pruned = self.prune_modes.storage_history.is_some();
+
         Ok(SnapshotTargets {
             headers: headers_block_range
                 .size_hint()
@@ -187,13 +327,28 @@ impl<DB: Database> Snapshotter<DB> {
                 .1
                 .expect("finalized block should be >= last receipts snapshot")
                 .ge(&(self.block_interval as usize))
-                .then_some((receipts_block_range, receipts_tx_range)),
+                .then_some((receipts_block_range, receipts_tx_range))
+                .filter(|_| !receipts_pruned),
             transactions: transactions_block_range
                 .size_hint()
                 .1
                 .expect("finalized block should be >= last transactions snapshot")
                 .ge(&(self.block_interval as usize))
                 .then_some((transactions_block_range, transactions_tx_range)),
+            account_changesets: account_changesets_block_range
+                .size_hint()
+                .1
+                .expect("finalized block should be >= last account changesets snapshot")
+                .ge(&(self.block_interval as usize))
+                .then_some(account_changesets_block_range)
+                .filter(|_| !account_history_pruned),
+            storage_changesets: storage_changesets_block_range
+                .size_hint()
+                .1
+                .expect("finalized block should be >= last storage changesets snapshot")
+                .ge(&(self.block_interval as usize))
+                .then_some(storage_changesets_block_range)
+                .filter(|_| !storage_history_pruned),
         })
     }
 
@@ -242,25 +397,205 @@ impl<DB: Database> Snapshotter<DB> {
 mod tests {
     use crate::{snapshotter::SnapshotTargets, HighestSnapshots, Snapshotter};
     use assert_matches::assert_matches;
+    use reth_db::{table::Table, tables};
     use reth_interfaces::{
-        test_utils::{generators, generators::random_block_range},
+        test_utils::{
+            generators,
+            generators::{
+                random_block_range, random_changeset_range, random_eoa_account_range,
+                random_receipt,
+            },
+        },
         RethError,
     };
-    use reth_primitives::{B256, MAINNET};
+    use reth_primitives::{PruneMode, PruneModes, SnapshotSegment, B256, MAINNET};
+    use reth_provider::{
+        ChangeSetReader, HeaderProvider, ProviderFactory, ReceiptProvider, TransactionsProvider,
+    };
     use reth_stages::test_utils::TestTransaction;
     use tokio::sync::watch;
 
     #[test]
     fn new() {
         let tx = TestTransaction::default();
+        let snapshots_dir = tempfile::TempDir::new().unwrap();
 
         let (highest_snapshots_tx, highest_snapshots_rx) = watch::channel(None);
         assert_eq!(*highest_snapshots_rx.borrow(), None);
 
-        Snapshotter::new(tx.inner_raw(), MAINNET.clone(), 2, highest_snapshots_tx);
+        Snapshotter::new(
+            tx.inner_raw(),
+            snapshots_dir.path(),
+            MAINNET.clone(),
+            2,
+            highest_snapshots_tx,
+        )
+        .expect("new snapshotter");
         assert_eq!(*highest_snapshots_rx.borrow(), Some(HighestSnapshots::default()));
     }
 
+    #[test]
+    fn run() {
+        let tx = TestTransaction::default();
+        let snapshots_dir = tempfile::TempDir::new().unwrap();
+        let mut rng = generators::rng();
+
+        let blocks = random_block_range(&mut rng, 0..=3, B256::ZERO, 0..1);
+        tx.insert_blocks(blocks.iter(), None).expect("insert blocks");
+        tx.insert_headers_with_td(blocks.iter().map(|block| &block.header))
+            .expect("insert headers");
+
+        let accounts = random_eoa_account_range(&mut rng, 0..2)
+            .into_iter()
+            .map(|(address, account)| (address, (account, Vec::new())));
+        let (changesets, _) = random_changeset_range(&mut rng, blocks.iter(), accounts, 0..2, 0..4);
+        tx.insert_changesets(changesets, None).expect("insert changesets");
+
+        let (highest_snapshots_tx, highest_snapshots_rx) = watch::channel(None);
+        let mut snapshotter = Snapshotter::new(
+            tx.inner_raw(),
+            snapshots_dir.path(),
+            MAINNET.clone(),
+            2,
+            highest_snapshots_tx,
+        )
+        .expect("new snapshotter");
+
+        let targets = SnapshotTargets {
+            headers: Some(0..=1),
+            receipts: None,
+            transactions: None,
+            account_changesets: Some(0..=1),
+            storage_changesets: Some(0..=1),
+        };
+        let account_changesets = tx.table::<tables::AccountChangeSet>().unwrap();
+        assert_eq!(snapshotter.run(targets.clone()).expect("run snapshotter"), targets);
+        assert!(snapshots_dir.path().join(SnapshotSegment::Headers.filename(&(0..=1))).exists());
+        for segment in [SnapshotSegment::AccountChangeSets, SnapshotSegment::StorageChangeSets] {
+            assert!(snapshots_dir.path().join(segment.filename(&(0..=1))).exists());
+        }
+
+        // Snapshotted changesets are deleted from the database, but still served by the provider
+        assert!(tx
+            .table::<tables::AccountChangeSet>()
+            .unwrap()
+            .iter()
+            .all(|(block, _)| *block > 1));
+        assert!(tx
+            .table::<tables::StorageChangeSet>()
+            .unwrap()
+            .iter()
+            .all(|(key, _)| key.block_number() > 1));
+        let factory = ProviderFactory::new(tx.inner_raw(), MAINNET.clone())
+            .with_snapshots(snapshots_dir.path(), None)
+            .expect("provider factory with snapshots");
+        let provider = factory.provider().expect("provider");
+        for block in 0..=3 {
+            assert_eq!(
+                provider.account_block_changeset(block).unwrap(),
+                account_changesets
+                    .iter()
+                    .filter(|(changeset_block, _)| *changeset_block == block)
+                    .map(|(_, account_before)| account_before.clone())
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        let highest_snapshots = HighestSnapshots {
+            headers: Some(1),
+            account_changesets: Some(1),
+            storage_changesets: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(snapshotter.highest_snapshots(), highest_snapshots);
+        assert_eq!(*highest_snapshots_rx.borrow(), Some(highest_snapshots));
+
+        // Highest snapshots are restored from the snapshots directory
+        let snapshotter = Snapshotter::new(
+            tx.inner_raw(),
+            snapshots_dir.path(),
+            MAINNET.clone(),
+            2,
+            watch::channel(None).0,
+        )
+        .expect("new snapshotter");
+        assert_eq!(snapshotter.highest_snapshots(), highest_snapshots);
+    }
+
+    #[test]
+    fn run_deletes_snapshotted_data() {
+        let tx = TestTransaction::default();
+        let snapshots_dir = tempfile::TempDir::new().unwrap();
+        let mut rng = generators::rng();
+
+        let blocks = random_block_range(&mut rng, 0..=3, B256::ZERO, 1..3);
+        tx.insert_blocks(blocks.iter(), None).expect("insert blocks");
+        tx.insert_headers_with_td(blocks.iter().map(|block| &block.header))
+            .expect("insert headers");
+
+        let transactions = blocks.iter().flat_map(|block| block.body.clone()).collect::<Vec<_>>();
+        let receipts = transactions
+            .iter()
+            .map(|transaction| random_receipt(&mut rng, transaction, Some(0)))
+            .collect::<Vec<_>>();
+        tx.insert_receipts(receipts.iter().cloned().enumerate().map(|(n, r)| (n as u64, r)))
+            .expect("insert receipts");
+
+        let mut snapshotter = Snapshotter::new(
+            tx.inner_raw(),
+            snapshots_dir.path(),
+            MAINNET.clone(),
+            2,
+            watch::channel(None).0,
+        )
+        .expect("new snapshotter");
+
+        let snapshotted_txs = (blocks[0].body.len() + blocks[1].body.len()) as u64;
+        let targets = SnapshotTargets {
+            headers: Some(0..=1),
+            receipts: Some((0..=1, 0..=snapshotted_txs - 1)),
+            transactions: Some((0..=1, 0..=snapshotted_txs - 1)),
+            account_changesets: None,
+            storage_changesets: None,
+        };
+        snapshotter.run(targets).expect("run snapshotter");
+
+        // Snapshotted rows are deleted from the database
+        fn keys<T: Table<Key = u64>>(tx: &TestTransaction) -> Vec<u64> {
+            tx.table::<T>().unwrap().into_iter().map(|(key, _)| key).collect()
+        }
+        assert_eq!(keys::<tables::Headers>(&tx), vec![2, 3]);
+        assert_eq!(keys::<tables::HeaderTD>(&tx), vec![2, 3]);
+        assert_eq!(
+            keys::<tables::Transactions>(&tx),
+            (snapshotted_txs..transactions.len() as u64).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            keys::<tables::Receipts>(&tx),
+            (snapshotted_txs..receipts.len() as u64).collect::<Vec<_>>()
+        );
+        assert_eq!(tx.table::<tables::CanonicalHeaders>().unwrap().len(), blocks.len());
+
+        // ...but still served by the provider from the snapshots
+        let factory = ProviderFactory::new(tx.inner_raw(), MAINNET.clone())
+            .with_snapshots(snapshots_dir.path(), None)
+            .expect("provider factory with snapshots");
+        let provider = factory.provider().expect("provider");
+        for block in &blocks {
+            assert_eq!(
+                provider.header_by_number(block.number).unwrap(),
+                Some(block.header.clone().unseal())
+            );
+        }
+        for (tx_num, (transaction, receipt)) in transactions.iter().zip(&receipts).enumerate() {
+            assert_eq!(
+                provider.transaction_by_id(tx_num as u64).unwrap().as_ref(),
+                Some(transaction)
+            );
+            assert_eq!(provider.receipt(tx_num as u64).unwrap().as_ref(), Some(receipt));
+        }
+    }
+
     #[test]
     fn get_snapshot_targets() {
         let tx = TestTransaction::default();
@@ -269,8 +604,15 @@ mod tests {
         let blocks = random_block_range(&mut rng, 0..=3, B256::ZERO, 2..3);
         tx.insert_blocks(blocks.iter(), None).expect("insert blocks");
 
-        let mut snapshotter =
-            Snapshotter::new(tx.inner_raw(), MAINNET.clone(), 2, watch::channel(None).0);
+        let snapshots_dir = tempfile::TempDir::new().unwrap();
+        let mut snapshotter = Snapshotter::new(
+            tx.inner_raw(),
+            snapshots_dir.path(),
+            MAINNET.clone(),
+            2,
+            watch::channel(None).0,
+        )
+        .expect("new snapshotter");
 
         // Snapshot targets has data per part up to the passed finalized block number,
         // respecting the block interval
@@ -280,7 +622,9 @@ mod tests {
             SnapshotTargets {
                 headers: Some(0..=1),
                 receipts: Some((0..=1, 0..=3)),
-                transactions: Some((0..=1, 0..=3))
+                transactions: Some((0..=1, 0..=3)),
+                account_changesets: Some(0..=1),
+                storage_changesets: Some(0..=1),
             }
         );
         assert!(targets.is_multiple_of_block_interval(snapshotter.block_interval));
@@ -291,7 +635,13 @@ mod tests {
         // Nothing to snapshot, last snapshots state of snapshotter doesn't pass the thresholds
         assert_eq!(
             snapshotter.get_snapshot_targets(2),
-            Ok(SnapshotTargets { headers: None, receipts: None, transactions: None })
+            Ok(SnapshotTargets {
+                headers: None,
+                receipts: None,
+                transactions: None,
+                account_changesets: None,
+                storage_changesets: None,
+            })
         );
 
         // Snapshot targets has data per part up to the passed finalized block number,
@@ -302,7 +652,9 @@ mod tests {
             SnapshotTargets {
                 headers: Some(2..=3),
                 receipts: Some((2..=3, 4..=7)),
-                transactions: Some((2..=3, 4..=7))
+                transactions: Some((2..=3, 4..=7)),
+                account_changesets: Some(2..=3),
+                storage_changesets: Some(2..=3),
             }
         );
         assert!(targets.is_multiple_of_block_interval(snapshotter.block_interval));
@@ -313,4 +665,41 @@ mod tests {
         // Block body indices not found
         assert_matches!(snapshotter.get_snapshot_targets(5), Err(RethError::Custom(_)));
     }
+
+    #[test]
+    fn get_snapshot_targets_pruned() {
+        let tx = TestTransaction::default();
+        let mut rng = generators::rng();
+
+        let blocks = random_block_range(&mut rng, 0..=1, B256::ZERO, 2..3);
+        tx.insert_blocks(blocks.iter(), None).expect("insert blocks");
+
+        let snapshots_dir = tempfile::TempDir::new().unwrap();
+        let snapshotter = Snapshotter::new(
+            tx.inner_raw(),
+            snapshots_dir.path(),
+            MAINNET.clone(),
+            2,
+            watch::channel(None).0,
+        )
+        .expect("new snapshotter")
+        .with_prune_modes(PruneModes {
+            receipts: Some(PruneMode::Distance(64)),
+            account_history: Some(PruneMode::Distance(64)),
+            storage_history: Some(PruneMode::Distance(64)),
+            ..PruneModes::none()
+        });
+
+        // Receipts and changesets are pruned from the database, so they're not snapshotted
+        assert_eq!(
+            snapshotter.get_snapshot_targets(1),
+            Ok(SnapshotTargets {
+                headers: Some(0..=1),
+                receipts: None,
+                transactions: Some((0..=1, 0..=3)),
+                account_changesets: None,
+                storage_changesets: None,
+            })
+        );
+    }
 }
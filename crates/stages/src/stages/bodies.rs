@@ -87,8 +87,12 @@ impl<DB: Database, D: BodyDownloader> Stage<DB> for BodyStage<D> {
         let mut ommers_cursor = tx.cursor_write::<tables::BlockOmmers>()?;
         let mut withdrawals_cursor = tx.cursor_write::<tables::BlockWithdrawals>()?;
 
-        // Get id for the next tx_num of zero if there are no transactions.
-        let mut next_tx_num = tx_cursor.last()?.map(|(id, _)| id + 1).unwrap_or_default();
+        // Get id for the next tx_num of zero if there are no transactions. Snapshotted
+        // transactions are deleted from the database, so it's taken from the block body indices.
+        let mut next_tx_num = block_indices_cursor
+            .last()?
+            .map(|(_, indices)| indices.next_tx_num())
+            .unwrap_or_default();
 
         debug!(target: "sync::stages::bodies", stage_progress = from_block, target = to_block, start_tx_id = next_tx_num, "Commencing sync");
 
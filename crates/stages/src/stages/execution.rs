@@ -19,7 +19,7 @@ use reth_primitives::{
 };
 use reth_provider::{
     BlockReader, DatabaseProviderRW, ExecutorFactory, HeaderProvider, LatestStateProviderRef,
-    OriginalValuesKnown, ProviderError, TransactionVariant,
+    OriginalValuesKnown, ProviderError, SnapSyncedState, TransactionVariant,
 };
 use std::{
     ops::RangeInclusive,
@@ -43,6 +43,10 @@ use tracing::*;
 /// - [tables::Bytecodes]
 /// - [tables::PlainStorageState]
 ///
+/// If the state was synced via [`SnapSyncStage`][crate::stages::SnapSyncStage], accounts and
+/// storage slots that are missing in the plain state are read from [tables::HashedAccount] and
+/// [tables::HashedStorage], see [SnapSyncedState].
+///
 /// Tables updated after state finishes execution:
 /// - [tables::PlainAccountState]
 /// - [tables::PlainStorageState]
@@ -122,8 +126,11 @@ impl<EF: ExecutorFactory> ExecutionStage<EF> {
         let prune_modes = self.adjust_prune_modes(provider, start_block, max_block)?;
 
         // Build executor
-        let mut executor =
-            self.executor_factory.with_state(LatestStateProviderRef::new(provider.tx_ref()));
+        let snap_synced_state = SnapSyncedState::load(provider.tx_ref())?;
+        let mut executor = self.executor_factory.with_state(
+            LatestStateProviderRef::new(provider.tx_ref())
+                .with_snap_synced_state(snap_synced_state.as_ref()),
+        );
         executor.set_prune_modes(prune_modes);
         executor.set_tip(max_block);
 
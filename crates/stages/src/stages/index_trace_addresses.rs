@@ -0,0 +1,190 @@
+use crate::{BlockErrorKind, ExecInput, ExecOutput, Stage, StageError, UnwindInput, UnwindOutput};
+use reth_db::{database::Database, transaction::DbTx};
+use reth_interfaces::executor::{BlockExecutionError, BlockValidationError};
+use reth_primitives::{
+    revm::env::{fill_cfg_and_block_env, tx_env_with_recovered},
+    stage::{StageCheckpoint, StageId},
+    Address, BlockNumber, ChainSpec, TransactionSignedEcRecovered,
+};
+use reth_provider::{
+    BlockReader, DatabaseProvider, DatabaseProviderRW, HeaderProvider, HistoricalStateProviderRef,
+    HistoryWriter, ProviderError, TransactionVariant,
+};
+use reth_revm::{
+    database::StateProviderDatabase, state_change::apply_beacon_root_contract_call,
+    trace_addresses::TraceAddressInspector,
+};
+use revm::{db::CacheDB, DatabaseCommit, EVM};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::RangeInclusive,
+    sync::Arc,
+};
+use tracing::*;
+
+/// Stage that indexes the addresses that appear in the call traces of every block.
+///
+/// Every block is re-executed on top of its historical state with a [TraceAddressInspector], which
+/// records the sender and recipient of every call, create and selfdestruct, including internal
+/// ones. For more information on index sharding take a look at
+/// [`reth_db::tables::TraceAddressHistory`].
+///
+/// This stage is optional and not part of the default stages. Since it reads historical state, it
+/// must run after the [`IndexAccountHistoryStage`][crate::stages::IndexAccountHistoryStage] and
+/// [`IndexStorageHistoryStage`][crate::stages::IndexStorageHistoryStage].
+#[derive(Debug)]
+pub struct IndexTraceAddressesStage {
+    /// The chain specification used to configure the EVM.
+    chain_spec: Arc<ChainSpec>,
+    /// Number of blocks after which the control
+    /// flow will be returned to the pipeline for commit.
+    pub commit_threshold: u64,
+}
+
+impl IndexTraceAddressesStage {
+    /// Create new instance of [IndexTraceAddressesStage].
+    pub fn new(chain_spec: Arc<ChainSpec>, commit_threshold: u64) -> Self {
+        Self { chain_spec, commit_threshold }
+    }
+
+    /// Indexes the next range of blocks of the input, up to the commit threshold.
+    ///
+    /// This is a synchronous version of [Stage::execute] that can be used to keep the index up to
+    /// date outside of the pipeline.
+    pub fn execute_inner<DB: Database>(
+        &self,
+        provider: &DatabaseProviderRW<'_, &DB>,
+        input: ExecInput,
+    ) -> Result<ExecOutput, StageError> {
+        if input.target_reached() {
+            return Ok(ExecOutput::done(input.checkpoint()))
+        }
+
+        let (range, is_final_range) = input.next_block_range_with_threshold(self.commit_threshold);
+        debug!(target: "sync::stages::index_trace_addresses", start = range.start(), end = range.end(), "Indexing range");
+
+        let block_addresses = self.trace_block_range(&provider.0, range.clone())?;
+        provider.insert_trace_address_index(block_addresses)?;
+
+        Ok(ExecOutput { checkpoint: StageCheckpoint::new(*range.end()), done: is_final_range })
+    }
+
+    /// Unwinds the index for the given range of blocks.
+    pub fn unwind_inner<DB: Database>(
+        &self,
+        provider: &DatabaseProviderRW<'_, &DB>,
+        range: RangeInclusive<BlockNumber>,
+    ) -> Result<(), StageError> {
+        provider.unwind_trace_address_indices(range)?;
+        Ok(())
+    }
+
+    /// Re-executes the blocks of the range and returns all addresses that appear in the call
+    /// traces of each block.
+    ///
+    /// This only reads from the database, so it can be used to compute the index entries with a
+    /// read-only transaction and write them later.
+    pub fn trace_block_range<TX: DbTx>(
+        &self,
+        provider: &DatabaseProvider<TX>,
+        range: RangeInclusive<BlockNumber>,
+    ) -> Result<BTreeMap<BlockNumber, BTreeSet<Address>>, StageError> {
+        range
+            .map(|block_number| Ok((block_number, self.trace_addresses(provider, block_number)?)))
+            .collect()
+    }
+
+    /// Re-executes the block and returns all addresses that appear in its call traces.
+    fn trace_addresses<TX: DbTx>(
+        &self,
+        provider: &DatabaseProvider<TX>,
+        block_number: BlockNumber,
+    ) -> Result<BTreeSet<Address>, StageError> {
+        let td = provider
+            .header_td_by_number(block_number)?
+            .ok_or_else(|| ProviderError::HeaderNotFound(block_number.into()))?;
+        let block = provider
+            .block_with_senders(block_number.into(), TransactionVariant::WithHash)?
+            .ok_or_else(|| ProviderError::BlockNotFound(block_number.into()))?;
+        let (block, senders) = block.into_components();
+
+        // the historical state at the given block number is the state before the block
+        let state = HistoricalStateProviderRef::new(provider.tx_ref(), block_number)
+            .with_snapshot_provider(provider.snapshot_provider().map(Arc::as_ref));
+        let mut evm = EVM::new();
+        evm.database(CacheDB::new(StateProviderDatabase::new(state)));
+        fill_cfg_and_block_env(
+            &mut evm.env.cfg,
+            &mut evm.env.block,
+            &self.chain_spec,
+            &block.header,
+            td,
+        );
+
+        let block_error = |error: BlockExecutionError| StageError::Block {
+            block: Box::new(block.header.clone().seal_slow()),
+            error: BlockErrorKind::Execution(error),
+        };
+
+        apply_beacon_root_contract_call(
+            &self.chain_spec,
+            block.timestamp,
+            block.number,
+            block.parent_beacon_block_root,
+            &mut evm,
+        )
+        .map_err(block_error)?;
+
+        let mut inspector = TraceAddressInspector::default();
+        let mut addresses = BTreeSet::new();
+        for (transaction, sender) in block.body.iter().zip(senders) {
+            // the top level sender and recipient are always part of the trace
+            addresses.insert(sender);
+            addresses.extend(transaction.to());
+
+            let hash = transaction.hash();
+            let transaction =
+                TransactionSignedEcRecovered::from_signed_transaction(transaction.clone(), sender);
+            evm.env.tx = tx_env_with_recovered(&transaction);
+
+            let res = evm.inspect(&mut inspector).map_err(|error| {
+                block_error(BlockValidationError::EVM { hash, error: error.into() }.into())
+            })?;
+            evm.db().expect("db to not be moved").commit(res.state);
+        }
+        addresses.extend(inspector.into_addresses());
+
+        Ok(addresses)
+    }
+}
+
+#[async_trait::async_trait]
+impl<DB: Database> Stage<DB> for IndexTraceAddressesStage {
+    /// Return the id of the stage
+    fn id(&self) -> StageId {
+        StageId::IndexTraceAddresses
+    }
+
+    /// Execute the stage.
+    async fn execute(
+        &mut self,
+        provider: &DatabaseProviderRW<'_, &DB>,
+        input: ExecInput,
+    ) -> Result<ExecOutput, StageError> {
+        self.execute_inner(provider, input)
+    }
+
+    /// Unwind the stage.
+    async fn unwind(
+        &mut self,
+        provider: &DatabaseProviderRW<'_, &DB>,
+        input: UnwindInput,
+    ) -> Result<UnwindOutput, StageError> {
+        let (range, unwind_progress, _) =
+            input.unwind_block_range_with_threshold(self.commit_threshold);
+
+        self.unwind_inner(provider, range)?;
+
+        Ok(UnwindOutput { checkpoint: StageCheckpoint::new(unwind_progress) })
+    }
+}
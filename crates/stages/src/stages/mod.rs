@@ -14,10 +14,14 @@ mod headers;
 mod index_account_history;
 /// Index history of storage changes
 mod index_storage_history;
+/// Index addresses that appear in call traces
+mod index_trace_addresses;
 /// Stage for computing state root.
 mod merkle;
 /// The sender recovery stage.
 mod sender_recovery;
+/// The snap sync stage.
+mod snap_sync;
 /// The total difficulty stage
 mod total_difficulty;
 /// The transaction lookup stage
@@ -31,8 +35,10 @@ pub use hashing_storage::*;
 pub use headers::*;
 pub use index_account_history::*;
 pub use index_storage_history::*;
+pub use index_trace_addresses::*;
 pub use merkle::*;
 pub use sender_recovery::*;
+pub use snap_sync::*;
 pub use total_difficulty::*;
 pub use tx_lookup::*;
 
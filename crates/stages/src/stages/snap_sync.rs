@@ -0,0 +1,1328 @@
+use crate::{ExecInput, ExecOutput, Stage, StageError, UnwindInput, UnwindOutput};
+use alloy_rlp::{Decodable, Encodable, Header};
+use reth_db::{
+    cursor::{DbCursorRO, DbCursorRW, DbDupCursorRO, DbDupCursorRW},
+    database::Database,
+    tables,
+    transaction::{DbTx, DbTxMut},
+};
+use reth_eth_wire::{
+    AccountData, AccountRange, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges,
+    GetTrieNodes, StorageData, StorageRanges, TrieNodes,
+};
+use reth_interfaces::{
+    db::DatabaseError,
+    p2p::snap::client::{SnapClient, SnapFut},
+};
+use reth_primitives::{
+    keccak256,
+    stage::{EntitiesCheckpoint, MerkleCheckpoint, SnapSyncCheckpoint, StageCheckpoint, StageId},
+    trie::{Nibbles, StoredSubNode},
+    BlockNumber, Bytecode, Bytes, PruneCheckpoint, PruneMode, PruneSegment, StorageEntry, B256,
+    EMPTY_ROOT_HASH, KECCAK_EMPTY, U256,
+};
+use reth_provider::{
+    DatabaseProviderRW, HeaderProvider, ProviderError, PruneCheckpointWriter,
+    StageCheckpointReader, StageCheckpointWriter,
+};
+use reth_trie::{
+    prefix_set::PrefixSetMut, IntermediateStateRootState, Proof, StateRoot, StateRootProgress,
+    StorageRoot,
+};
+use std::{
+    collections::{BTreeSet, VecDeque},
+    time::Duration,
+};
+use tracing::*;
+
+use super::MerkleStage;
+
+/// The number of blocks the pivot of the snap sync lags behind the target of the stage.
+///
+/// The blocks after the pivot are executed, so that reorgs of up to this depth can be unwound. It
+/// is well below the 128 blocks whose state is served by peers.
+pub const SNAP_SYNC_PIVOT_DISTANCE: u64 = 64;
+
+/// The soft limit for the size of the responses requested from peers.
+const RESPONSE_BYTES: u64 = 512 * 1024;
+
+/// The maximum number of accounts whose storage is requested at once.
+const MAX_STORAGE_ACCOUNTS: usize = 128;
+
+/// The maximum number of bytecodes requested at once.
+const MAX_BYTECODES: usize = 64;
+
+/// The maximum number of trie nodes requested at once.
+const MAX_TRIE_NODES: usize = 512;
+
+/// The number of attempts after which a request is given up.
+const MAX_REQUEST_ATTEMPTS: usize = 8;
+
+/// The delay before a request that failed is sent again.
+const REQUEST_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Errors of the [SnapSyncStage].
+#[derive(Debug, thiserror::Error)]
+pub enum SnapSyncError {
+    /// No peer answered the request.
+    #[error("no peer answered the snap request")]
+    NoResponse,
+    /// The downloaded storage of the account does not match its storage root.
+    #[error("the downloaded storage of account {0} does not match its storage root")]
+    StorageRootMismatch(B256),
+    /// No peer served the bytecode.
+    #[error("no peer served the bytecode {0}")]
+    MissingBytecode(B256),
+    /// The state below the pivot is not available.
+    #[error("can't unwind to block {unwind_to} below the snap sync pivot {pivot}")]
+    UnwindBelowPivot {
+        /// The pivot of the snap sync.
+        pivot: BlockNumber,
+        /// The block to unwind to.
+        unwind_to: BlockNumber,
+    },
+}
+
+/// The snap sync stage downloads the state of a pivot block from peers via the `snap` protocol,
+/// instead of building it by executing all blocks since genesis.
+///
+/// The pivot lags [SNAP_SYNC_PIVOT_DISTANCE] blocks behind the target of the stage. The stage
+/// - downloads the accounts of the pivot state in ranges, together with their storage and
+///   bytecodes, into the [`HashedAccount`][reth_db::tables::HashedAccount],
+///   [`HashedStorage`][reth_db::tables::HashedStorage] and
+///   [`Bytecodes`][reth_db::tables::Bytecodes] tables. The storage of every account is verified
+///   against its storage root.
+/// - builds the state trie and compares its root with the state root of the pivot.
+/// - heals the state if the roots differ, which happens if the pivot moved during the download or a
+///   peer served an incomplete range. Starting at the root, the trie nodes of the pivot are fetched
+///   with `GetTrieNodes` and compared with the local ones, and the accounts below every node that
+///   differs are downloaded again.
+///
+/// Once the roots match, the checkpoints of the execution, hashing and merkle stages are moved to
+/// the pivot, so the pipeline continues by executing the blocks after it.
+///
+/// Snap sync only provides the hashed state, the plain state of the node stays empty. Reads of
+/// the state fall back to the hashed tables if the plain state doesn't contain a key, see
+/// [`SnapSyncedState`][reth_provider::SnapSyncedState]. Neither changesets nor history exist for
+/// the blocks up to the pivot, so the history prune checkpoints are set to the pivot as well.
+///
+/// If the pivot is not served by any peer anymore, the stage finishes without progress, so that
+/// the next run of the pipeline picks a new pivot. The download continues where it left off and
+/// the state is healed afterwards.
+///
+/// This stage is optional and only runs on a node without any executed blocks.
+#[derive(Debug)]
+pub struct SnapSyncStage<C> {
+    /// The client to request the state from.
+    client: C,
+    /// The minimum number of accounts to download before committing progress to the database.
+    pub commit_threshold: u64,
+}
+
+impl<C> SnapSyncStage<C> {
+    /// Create new instance of [SnapSyncStage].
+    pub fn new(client: C, commit_threshold: u64) -> Self {
+        Self { client, commit_threshold }
+    }
+}
+
+/// The outcome of a single run of the [SnapSyncStage].
+#[derive(Debug, PartialEq, Eq)]
+enum SyncOutcome {
+    /// The state is not complete yet.
+    InProgress,
+    /// The state matches the state root of the pivot.
+    Complete,
+    /// The pivot is not served by peers anymore.
+    Stale,
+}
+
+/// The outcome of the download of an account range.
+#[derive(Debug, PartialEq, Eq)]
+enum RangeOutcome {
+    /// The range is downloaded up to the account before the given hash.
+    Partial(B256),
+    /// The range is downloaded completely.
+    Complete,
+    /// The pivot is not served by peers anymore.
+    Stale,
+}
+
+/// The result of the validation of a response.
+#[derive(Debug, PartialEq, Eq)]
+enum ResponseCheck {
+    /// The response is valid.
+    Valid,
+    /// The peer doesn't serve the requested state.
+    Unavailable,
+    /// The response violates the protocol.
+    Invalid,
+}
+
+impl<C: SnapClient> SnapSyncStage<C> {
+    /// Sends the request until a peer serves it, at most [MAX_REQUEST_ATTEMPTS] times.
+    ///
+    /// Returns [None] if peers answered that they don't serve the requested state. Peers that send
+    /// invalid responses are penalized.
+    async fn request<T: Send>(
+        &self,
+        send: impl Fn() -> SnapFut<T> + Send,
+        check: impl Fn(&T) -> ResponseCheck + Send,
+    ) -> Result<Option<T>, StageError> {
+        let mut unavailable = false;
+        for _ in 0..MAX_REQUEST_ATTEMPTS {
+            match send().await {
+                Ok(response) => {
+                    let (peer_id, response) = response.split();
+                    match check(&response) {
+                        ResponseCheck::Valid => return Ok(Some(response)),
+                        ResponseCheck::Unavailable => unavailable = true,
+                        ResponseCheck::Invalid => {
+                            debug!(target: "sync::stages::snap_sync", ?peer_id, "Invalid snap response");
+                            self.client.report_bad_message(peer_id)
+                        }
+                    }
+                }
+                Err(error) => {
+                    debug!(target: "sync::stages::snap_sync", %error, "Snap request failed");
+                    tokio::time::sleep(REQUEST_RETRY_DELAY).await;
+                }
+            }
+        }
+
+        if unavailable {
+            Ok(None)
+        } else {
+            Err(StageError::Recoverable(Box::new(SnapSyncError::NoResponse)))
+        }
+    }
+
+    /// Downloads the accounts of the state with the given root from `origin` up to `limit`,
+    /// together with their storage and bytecodes, and replaces the local accounts of the covered
+    /// range with them.
+    ///
+    /// Returns after at least `max_accounts` accounts. The hashed addresses of all deleted and
+    /// written accounts are added to the `changed` prefix set.
+    async fn download_accounts<DB: Database>(
+        &self,
+        provider: &DatabaseProviderRW<'_, &DB>,
+        root: B256,
+        mut origin: B256,
+        limit: B256,
+        max_accounts: u64,
+        mut changed: Option<&mut PrefixSetMut>,
+    ) -> Result<RangeOutcome, StageError> {
+        let mut downloaded = 0;
+        loop {
+            let request = GetAccountRange {
+                request_id: 0,
+                root_hash: root,
+                starting_hash: origin,
+                limit_hash: limit,
+                response_bytes: RESPONSE_BYTES,
+            };
+            let Some(response) = self
+                .request(
+                    || self.client.get_account_range(request.clone()),
+                    |response| check_account_range(response, origin),
+                )
+                .await?
+            else {
+                return Ok(RangeOutcome::Stale)
+            };
+
+            // the response may include the first account past the limit to prove the range
+            let covered = match response.accounts.last() {
+                Some(last) if last.hash < limit => last.hash,
+                _ => limit,
+            };
+            let accounts = response
+                .accounts
+                .into_iter()
+                .take_while(|account| account.hash <= limit)
+                .collect::<Vec<_>>();
+
+            let tx = provider.tx_ref();
+            delete_accounts(tx, origin, covered, changed.as_deref_mut())?;
+            for account in &accounts {
+                tx.put::<tables::HashedAccount>(account.hash, account.body.account())?;
+                if let Some(changed) = changed.as_deref_mut() {
+                    changed.insert(Nibbles::unpack(account.hash));
+                }
+            }
+            if !self.download_storages(provider, root, &accounts).await? {
+                return Ok(RangeOutcome::Stale)
+            }
+            self.download_bytecodes(provider, &accounts).await?;
+            downloaded += accounts.len() as u64;
+
+            match next_hash(covered) {
+                Some(next) if covered < limit => {
+                    origin = next;
+                    if downloaded >= max_accounts {
+                        return Ok(RangeOutcome::Partial(next))
+                    }
+                }
+                _ => return Ok(RangeOutcome::Complete),
+            }
+        }
+    }
+
+    /// Downloads the storage of the accounts and verifies it against their storage roots.
+    ///
+    /// Returns `false` if the pivot is not served by peers anymore.
+    async fn download_storages<DB: Database>(
+        &self,
+        provider: &DatabaseProviderRW<'_, &DB>,
+        root: B256,
+        accounts: &[AccountData],
+    ) -> Result<bool, StageError> {
+        let tx = provider.tx_ref();
+        let mut pending = accounts
+            .iter()
+            .filter(|account| account.body.storage_root != EMPTY_ROOT_HASH)
+            .map(|account| (account.hash, account.body.storage_root))
+            .collect::<VecDeque<_>>();
+        // the hash of the first slot to request for the first pending account
+        let mut start = B256::ZERO;
+        let mut mismatches = 0;
+        while !pending.is_empty() {
+            let hashes = pending
+                .iter()
+                .take(MAX_STORAGE_ACCOUNTS)
+                .map(|(hash, _)| *hash)
+                .collect::<Vec<_>>();
+            let request = GetStorageRanges {
+                request_id: 0,
+                root_hash: root,
+                account_hashes: hashes.clone(),
+                starting_hash: Bytes::copy_from_slice(start.as_slice()),
+                limit_hash: Bytes::new(),
+                response_bytes: RESPONSE_BYTES,
+            };
+            let Some(response) = self
+                .request(
+                    || self.client.get_storage_ranges(request.clone()),
+                    |response| check_storage_ranges(response, hashes.len(), start),
+                )
+                .await?
+            else {
+                return Ok(false)
+            };
+
+            let served = response.slots.len();
+            // a proof is attached if the storage of the last account may be incomplete
+            let proven = !response.proof.is_empty();
+            for (idx, slots) in response.slots.into_iter().enumerate() {
+                let (hashed_address, storage_root) = pending[0];
+                insert_storage(tx, hashed_address, &slots)?;
+
+                if idx + 1 == served && proven {
+                    if let Some(next) = slots.last().and_then(|slot| next_hash(slot.hash)) {
+                        start = next;
+                        break
+                    }
+                }
+
+                start = B256::ZERO;
+                pending.pop_front();
+                let downloaded_root = StorageRoot::new_hashed(tx, hashed_address)
+                    .root()
+                    .map_err(|e| StageError::Fatal(Box::new(e)))?;
+                if downloaded_root != storage_root {
+                    mismatches += 1;
+                    if mismatches >= MAX_REQUEST_ATTEMPTS {
+                        return Err(StageError::Recoverable(Box::new(
+                            SnapSyncError::StorageRootMismatch(hashed_address),
+                        )))
+                    }
+                    debug!(target: "sync::stages::snap_sync", ?hashed_address, "Downloading storage again");
+                    delete_storage(tx, hashed_address)?;
+                    pending.push_front((hashed_address, storage_root));
+                    break
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Downloads the bytecodes of the accounts that are missing in the database.
+    async fn download_bytecodes<DB: Database>(
+        &self,
+        provider: &DatabaseProviderRW<'_, &DB>,
+        accounts: &[AccountData],
+    ) -> Result<(), StageError> {
+        let tx = provider.tx_ref();
+        let mut missing = BTreeSet::new();
+        for account in accounts {
+            let code_hash = account.body.code_hash;
+            if code_hash != KECCAK_EMPTY && tx.get::<tables::Bytecodes>(code_hash)?.is_none() {
+                missing.insert(code_hash);
+            }
+        }
+
+        while !missing.is_empty() {
+            let hashes = missing.iter().take(MAX_BYTECODES).copied().collect::<Vec<_>>();
+            let request = GetByteCodes {
+                request_id: 0,
+                hashes: hashes.clone(),
+                response_bytes: RESPONSE_BYTES,
+            };
+            let Some(response) = self
+                .request(
+                    || self.client.get_byte_codes(request.clone()),
+                    |response| check_byte_codes(response, &hashes),
+                )
+                .await?
+            else {
+                return Err(StageError::Recoverable(Box::new(SnapSyncError::MissingBytecode(
+                    hashes[0],
+                ))))
+            };
+
+            for code in response.codes {
+                let code_hash = keccak256(&code);
+                tx.put::<tables::Bytecodes>(code_hash, Bytecode::new_raw(code))?;
+                missing.remove(&code_hash);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Heals the local state towards the state with the given root and returns the new local
+    /// state root, or [None] if the pivot is not served by peers anymore.
+    ///
+    /// The trie nodes of the pivot are compared with the local ones from the root towards the
+    /// leaves. The children of branch nodes are only descended into if they differ, the accounts
+    /// below any other differing node are downloaded again.
+    async fn heal<DB: Database>(
+        &self,
+        provider: &DatabaseProviderRW<'_, &DB>,
+        root: B256,
+    ) -> Result<Option<B256>, StageError> {
+        let tx = provider.tx_ref();
+        let mut queue = VecDeque::from([(Nibbles::default(), root)]);
+        let mut refetch = Vec::new();
+        while !queue.is_empty() {
+            let batch = queue.drain(..queue.len().min(MAX_TRIE_NODES)).collect::<Vec<_>>();
+            let request = GetTrieNodes {
+                request_id: 0,
+                root_hash: root,
+                paths: batch
+                    .iter()
+                    .map(|(path, _)| Vec::from([Bytes::from(path.encode_path_leaf(false))]))
+                    .collect(),
+                response_bytes: RESPONSE_BYTES,
+            };
+            let Some(response) = self
+                .request(
+                    || self.client.get_trie_nodes(request.clone()),
+                    |response| check_trie_nodes(response, &batch),
+                )
+                .await?
+            else {
+                return Ok(None)
+            };
+
+            // the nodes that were not served are requested again
+            let served = response.nodes.len();
+            queue.extend(batch[served..].iter().cloned());
+
+            let paths = batch[..served].iter().map(|(path, _)| path.clone()).collect();
+            let (_, local_nodes) = Proof::new(tx)
+                .account_proof_nodes(paths)
+                .map_err(|e| StageError::Fatal(Box::new(e)))?;
+            for ((path, _), node) in batch.into_iter().zip(response.nodes) {
+                let local = local_nodes.get(&path);
+                if local == Some(&node) {
+                    continue
+                }
+
+                match (branch_children(&node), local.and_then(|local| branch_children(local))) {
+                    (Some(children), Some(local_children)) => {
+                        for (nibble, (child, local_child)) in
+                            children.into_iter().zip(local_children).take(16).enumerate()
+                        {
+                            if child == local_child {
+                                continue
+                            }
+                            let child_path = path.join(&Nibbles::from_hex(vec![nibble as u8]));
+                            match child_hash(child) {
+                                Some(hash) => queue.push_back((child_path, hash)),
+                                // empty and embedded children are downloaded directly
+                                None => refetch.push(child_path),
+                            }
+                        }
+                    }
+                    _ => refetch.push(path),
+                }
+            }
+        }
+
+        debug!(target: "sync::stages::snap_sync", ranges = refetch.len(), "Healing state");
+        let mut changed = PrefixSetMut::default();
+        let mut outcome = RangeOutcome::Complete;
+        let mut result = Ok(());
+        for prefix in refetch {
+            let (start, end) = prefix_range(&prefix);
+            let mut origin = start;
+            outcome = loop {
+                match self
+                    .download_accounts(provider, root, origin, end, u64::MAX, Some(&mut changed))
+                    .await
+                {
+                    Ok(RangeOutcome::Partial(next)) => origin = next,
+                    Ok(outcome) => break outcome,
+                    Err(err) => {
+                        result = Err(err);
+                        break RangeOutcome::Stale
+                    }
+                }
+            };
+            if outcome == RangeOutcome::Stale {
+                break
+            }
+        }
+
+        // the trie is updated even if the healing is interrupted, so that it stays consistent
+        // with the hashed state
+        let (state_root, updates) = StateRoot::new(tx)
+            .with_changed_account_prefixes(changed.freeze())
+            .root_with_updates()
+            .map_err(|e| StageError::Fatal(Box::new(e)))?;
+        updates.flush(tx)?;
+        result?;
+
+        Ok((outcome != RangeOutcome::Stale).then_some(state_root))
+    }
+
+    /// Downloads, verifies and heals the state of the pivot.
+    async fn sync_state<DB: Database>(
+        &self,
+        provider: &DatabaseProviderRW<'_, &DB>,
+        root: B256,
+        checkpoint: &mut SnapSyncCheckpoint,
+    ) -> Result<SyncOutcome, StageError> {
+        if let Some(origin) = checkpoint.next_account {
+            let outcome = if root == EMPTY_ROOT_HASH {
+                RangeOutcome::Complete
+            } else {
+                self.download_accounts(
+                    provider,
+                    root,
+                    origin,
+                    B256::repeat_byte(0xff),
+                    self.commit_threshold,
+                    None,
+                )
+                .await?
+            };
+            match outcome {
+                RangeOutcome::Partial(next) => {
+                    checkpoint.next_account = Some(next);
+                    checkpoint.progress.processed = hash_progress(next);
+                    return Ok(SyncOutcome::InProgress)
+                }
+                RangeOutcome::Complete => {
+                    info!(target: "sync::stages::snap_sync", pivot = checkpoint.pivot, "Downloaded state, building trie");
+                    checkpoint.next_account = None;
+                    checkpoint.progress.processed = checkpoint.progress.total;
+                    MerkleStage::default_execution().save_execution_checkpoint(provider, None)?;
+                    provider.tx_ref().clear::<tables::AccountsTrie>()?;
+                    provider.tx_ref().clear::<tables::StoragesTrie>()?;
+                }
+                RangeOutcome::Stale => return Ok(SyncOutcome::Stale),
+            }
+        }
+
+        let Some(state_root) = build_trie(provider, checkpoint.pivot)? else {
+            return Ok(SyncOutcome::InProgress)
+        };
+        if state_root == root {
+            return Ok(SyncOutcome::Complete)
+        }
+
+        info!(target: "sync::stages::snap_sync", pivot = checkpoint.pivot, "State root mismatch, healing state");
+        Ok(match self.heal(provider, root).await? {
+            Some(state_root) if state_root == root => SyncOutcome::Complete,
+            Some(_) => SyncOutcome::InProgress,
+            None => SyncOutcome::Stale,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl<DB: Database, C: SnapClient> Stage<DB> for SnapSyncStage<C> {
+    /// Return the id of the stage
+    fn id(&self) -> StageId {
+        StageId::SnapSync
+    }
+
+    /// Download the state of the pivot.
+    async fn execute(
+        &mut self,
+        provider: &DatabaseProviderRW<'_, &DB>,
+        input: ExecInput,
+    ) -> Result<ExecOutput, StageError> {
+        if input.target_reached() {
+            return Ok(ExecOutput::done(input.checkpoint()))
+        }
+
+        let target = input.target();
+        let pivot = target.saturating_sub(SNAP_SYNC_PIVOT_DISTANCE);
+        let mut checkpoint = match input.checkpoint().snap_sync_stage_checkpoint() {
+            Some(checkpoint) if checkpoint.complete => {
+                return Ok(ExecOutput::done(
+                    StageCheckpoint::new(target).with_snap_sync_stage_checkpoint(checkpoint),
+                ))
+            }
+            Some(checkpoint) => checkpoint,
+            None => {
+                let executed = provider
+                    .get_stage_checkpoint(StageId::Execution)?
+                    .unwrap_or_default()
+                    .block_number;
+                if executed > 0 || pivot == 0 {
+                    info!(target: "sync::stages::snap_sync", executed, target, "Skipping snap sync");
+                    return Ok(ExecOutput::done(StageCheckpoint::new(target)))
+                }
+
+                info!(target: "sync::stages::snap_sync", pivot, "Starting snap sync");
+                let tx = provider.tx_ref();
+                tx.clear::<tables::PlainAccountState>()?;
+                tx.clear::<tables::PlainStorageState>()?;
+                tx.clear::<tables::HashedAccount>()?;
+                tx.clear::<tables::HashedStorage>()?;
+                tx.clear::<tables::AccountsTrie>()?;
+                tx.clear::<tables::StoragesTrie>()?;
+                SnapSyncCheckpoint {
+                    pivot,
+                    next_account: Some(B256::ZERO),
+                    complete: false,
+                    progress: EntitiesCheckpoint { processed: 0, total: u64::MAX },
+                }
+            }
+        };
+
+        if checkpoint.pivot != pivot {
+            info!(target: "sync::stages::snap_sync", from = checkpoint.pivot, to = pivot, "Moving snap sync pivot");
+            checkpoint.pivot = pivot;
+        }
+        let root = provider
+            .header_by_number(pivot)?
+            .ok_or_else(|| ProviderError::HeaderNotFound(pivot.into()))?
+            .state_root;
+
+        match self.sync_state(provider, root, &mut checkpoint).await? {
+            SyncOutcome::InProgress => Ok(ExecOutput {
+                checkpoint: input.checkpoint().with_snap_sync_stage_checkpoint(checkpoint),
+                done: false,
+            }),
+            SyncOutcome::Stale => {
+                warn!(target: "sync::stages::snap_sync", pivot, "Snap sync pivot is not served by peers anymore");
+                Ok(ExecOutput {
+                    checkpoint: input.checkpoint().with_snap_sync_stage_checkpoint(checkpoint),
+                    done: true,
+                })
+            }
+            SyncOutcome::Complete => {
+                info!(target: "sync::stages::snap_sync", pivot, "Snap sync finished");
+                finish_snap_sync(provider, pivot)?;
+                checkpoint.complete = true;
+                Ok(ExecOutput::done(
+                    StageCheckpoint::new(target).with_snap_sync_stage_checkpoint(checkpoint),
+                ))
+            }
+        }
+    }
+
+    /// Unwind the stage.
+    async fn unwind(
+        &mut self,
+        _provider: &DatabaseProviderRW<'_, &DB>,
+        input: UnwindInput,
+    ) -> Result<UnwindOutput, StageError> {
+        let mut checkpoint = StageCheckpoint::new(input.unwind_to);
+        if let Some(snap_checkpoint) = input.checkpoint.snap_sync_stage_checkpoint() {
+            if snap_checkpoint.complete && input.unwind_to < snap_checkpoint.pivot {
+                return Err(StageError::Fatal(Box::new(SnapSyncError::UnwindBelowPivot {
+                    pivot: snap_checkpoint.pivot,
+                    unwind_to: input.unwind_to,
+                })))
+            }
+            checkpoint = checkpoint.with_snap_sync_stage_checkpoint(snap_checkpoint);
+        }
+
+        Ok(UnwindOutput { checkpoint })
+    }
+}
+
+/// Builds the state trie from the hashed state and returns its root, or [None] if the trie is not
+/// complete yet.
+///
+/// The intermediate state of the computation is saved in the checkpoint of the
+/// [MerkleStage::Execution]. If there's no intermediate state, the trie is complete and its root
+/// is computed from the trie tables.
+fn build_trie<DB: Database>(
+    provider: &DatabaseProviderRW<'_, &DB>,
+    pivot: BlockNumber,
+) -> Result<Option<B256>, StageError> {
+    let mut merkle = MerkleStage::default_execution();
+    let checkpoint = merkle.get_execution_checkpoint(provider)?;
+    let tx = provider.tx_ref();
+    let progress = StateRoot::new(tx)
+        .with_intermediate_state(checkpoint.map(IntermediateStateRootState::from))
+        .root_with_progress()
+        .map_err(|e| StageError::Fatal(Box::new(e)))?;
+    match progress {
+        StateRootProgress::Progress(state, _, updates) => {
+            updates.flush(tx)?;
+            let checkpoint = MerkleCheckpoint::new(
+                pivot,
+                state.last_account_key,
+                state.walker_stack.into_iter().map(StoredSubNode::from).collect(),
+                state.hash_builder.into(),
+            );
+            merkle.save_execution_checkpoint(provider, Some(checkpoint))?;
+            Ok(None)
+        }
+        StateRootProgress::Complete(root, _, updates) => {
+            updates.flush(tx)?;
+            merkle.save_execution_checkpoint(provider, None)?;
+            Ok(Some(root))
+        }
+    }
+}
+
+/// Moves the checkpoints of the stages that build the state to the pivot, and marks the history
+/// up to the pivot as pruned since no changesets exist for it.
+fn finish_snap_sync<DB: Database>(
+    provider: &DatabaseProviderRW<'_, &DB>,
+    pivot: BlockNumber,
+) -> Result<(), StageError> {
+    for stage_id in [
+        StageId::Execution,
+        StageId::AccountHashing,
+        StageId::StorageHashing,
+        StageId::MerkleExecute,
+    ] {
+        provider.save_stage_checkpoint(stage_id, StageCheckpoint::new(pivot))?;
+    }
+    for segment in [PruneSegment::AccountHistory, PruneSegment::StorageHistory] {
+        provider.save_prune_checkpoint(
+            segment,
+            PruneCheckpoint {
+                block_number: Some(pivot),
+                tx_number: None,
+                prune_mode: PruneMode::Before(pivot + 1),
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// Deletes the hashed accounts in the range, together with their storage and storage tries.
+fn delete_accounts<TX: DbTx + DbTxMut>(
+    tx: &TX,
+    start: B256,
+    end: B256,
+    mut changed: Option<&mut PrefixSetMut>,
+) -> Result<(), DatabaseError> {
+    let mut accounts = tx.cursor_write::<tables::HashedAccount>()?;
+    let mut walker = accounts.walk_range(start..=end)?;
+    while let Some((hashed_address, _)) = walker.next().transpose()? {
+        walker.delete_current()?;
+        delete_storage(tx, hashed_address)?;
+        if let Some(changed) = changed.as_deref_mut() {
+            changed.insert(Nibbles::unpack(hashed_address));
+        }
+    }
+    Ok(())
+}
+
+/// Deletes the hashed storage and the storage trie of the account.
+fn delete_storage<TX: DbTx + DbTxMut>(tx: &TX, hashed_address: B256) -> Result<(), DatabaseError> {
+    let mut storage = tx.cursor_dup_write::<tables::HashedStorage>()?;
+    if storage.seek_exact(hashed_address)?.is_some() {
+        storage.delete_current_duplicates()?;
+    }
+    let mut storage_trie = tx.cursor_dup_write::<tables::StoragesTrie>()?;
+    if storage_trie.seek_exact(hashed_address)?.is_some() {
+        storage_trie.delete_current_duplicates()?;
+    }
+    Ok(())
+}
+
+/// Writes the downloaded slots of the account to the hashed storage.
+fn insert_storage<TX: DbTx + DbTxMut>(
+    tx: &TX,
+    hashed_address: B256,
+    slots: &[StorageData],
+) -> Result<(), DatabaseError> {
+    let mut storage = tx.cursor_dup_write::<tables::HashedStorage>()?;
+    for slot in slots {
+        let Some(value) = decode_slot(&slot.data) else { continue };
+        if storage
+            .seek_by_key_subkey(hashed_address, slot.hash)?
+            .filter(|entry| entry.key == slot.hash)
+            .is_some()
+        {
+            storage.delete_current()?;
+        }
+        storage.upsert(hashed_address, StorageEntry { key: slot.hash, value })?;
+    }
+    Ok(())
+}
+
+/// Checks that the accounts of the response are ordered and start at the origin.
+///
+/// An empty response without a proof means that the peer doesn't serve the state root.
+fn check_account_range(response: &AccountRange, origin: B256) -> ResponseCheck {
+    let Some(first) = response.accounts.first() else {
+        return if response.proof.is_empty() {
+            ResponseCheck::Unavailable
+        } else {
+            ResponseCheck::Valid
+        }
+    };
+    let ordered = response.accounts.windows(2).all(|pair| pair[0].hash < pair[1].hash);
+    if ordered && first.hash >= origin {
+        ResponseCheck::Valid
+    } else {
+        ResponseCheck::Invalid
+    }
+}
+
+/// Checks that the response contains ordered, valid slots of the requested accounts.
+fn check_storage_ranges(response: &StorageRanges, requested: usize, start: B256) -> ResponseCheck {
+    if response.slots.is_empty() {
+        return ResponseCheck::Unavailable
+    }
+    if response.slots.len() > requested {
+        return ResponseCheck::Invalid
+    }
+    for (idx, slots) in response.slots.iter().enumerate() {
+        let ordered = slots.windows(2).all(|pair| pair[0].hash < pair[1].hash);
+        let in_range = idx > 0 || slots.first().map_or(true, |slot| slot.hash >= start);
+        if !ordered || !in_range || slots.iter().any(|slot| decode_slot(&slot.data).is_none()) {
+            return ResponseCheck::Invalid
+        }
+    }
+    ResponseCheck::Valid
+}
+
+/// Checks that the response only contains requested bytecodes.
+fn check_byte_codes(response: &ByteCodes, requested: &[B256]) -> ResponseCheck {
+    if response.codes.is_empty() {
+        return ResponseCheck::Unavailable
+    }
+    if response.codes.iter().all(|code| requested.contains(&keccak256(code))) {
+        ResponseCheck::Valid
+    } else {
+        ResponseCheck::Invalid
+    }
+}
+
+/// Checks that the served nodes hash to the expected hashes of the requested paths.
+fn check_trie_nodes(response: &TrieNodes, requested: &[(Nibbles, B256)]) -> ResponseCheck {
+    if response.nodes.is_empty() {
+        return ResponseCheck::Unavailable
+    }
+    if response.nodes.len() <= requested.len() &&
+        response.nodes.iter().zip(requested).all(|(node, (_, hash))| keccak256(node) == *hash)
+    {
+        ResponseCheck::Valid
+    } else {
+        ResponseCheck::Invalid
+    }
+}
+
+/// Decodes the RLP encoded value of a storage slot, which must be canonical and not zero.
+fn decode_slot(data: &[u8]) -> Option<U256> {
+    let mut buf = data;
+    let value = U256::decode(&mut buf).ok()?;
+    (buf.is_empty() && value != U256::ZERO && value.length() == data.len()).then_some(value)
+}
+
+/// Returns the 17 RLP encoded items of a branch node, or [None] if the node is not a branch node.
+fn branch_children(node: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut buf = node;
+    let header = Header::decode(&mut buf).ok()?;
+    if !header.list || buf.len() != header.payload_length {
+        return None
+    }
+
+    let mut items = Vec::with_capacity(17);
+    while !buf.is_empty() {
+        let item = buf;
+        let header = Header::decode(&mut buf).ok()?;
+        let len = item.len() - buf.len() + header.payload_length;
+        if len > item.len() {
+            return None
+        }
+        items.push(&item[..len]);
+        buf = &item[len..];
+    }
+    (items.len() == 17).then_some(items)
+}
+
+/// Returns the hash of a child referenced by its hash in a branch node.
+fn child_hash(item: &[u8]) -> Option<B256> {
+    (item.len() == 33 && item[0] == 0xa0).then(|| B256::from_slice(&item[1..]))
+}
+
+/// Returns the first and the last hash with the given prefix.
+fn prefix_range(prefix: &Nibbles) -> (B256, B256) {
+    let mut start = prefix.to_vec();
+    start.resize(64, 0);
+    let mut end = prefix.to_vec();
+    end.resize(64, 0xf);
+    (
+        B256::from_slice(&Nibbles::from_hex(start).pack()),
+        B256::from_slice(&Nibbles::from_hex(end).pack()),
+    )
+}
+
+/// Returns the hash following the given one, or [None] for the maximum hash.
+fn next_hash(hash: B256) -> Option<B256> {
+    U256::from_be_bytes(hash.0)
+        .checked_add(U256::from(1))
+        .map(|next| B256::from(next.to_be_bytes::<32>()))
+}
+
+/// Returns the download progress of the account range up to the hash, out of [u64::MAX].
+fn hash_progress(hash: B256) -> u64 {
+    u64::from_be_bytes(hash[..8].try_into().expect("8 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestTransaction;
+    use alloy_rlp::EMPTY_STRING_CODE;
+    use assert_matches::assert_matches;
+    use reth_db::DatabaseEnv;
+    use reth_eth_wire::SlimAccount;
+    use reth_interfaces::{
+        p2p::download::DownloadClient,
+        test_utils::generators::{self, random_header},
+    };
+    use reth_primitives::{Account, Address, PeerId, WithPeerId, MAINNET};
+    use reth_provider::{AccountReader, ProviderFactory, StateProvider};
+    use std::{collections::HashSet, sync::Mutex};
+
+    /// Serves the state of a remote database, in small responses to exercise paging.
+    #[derive(Debug)]
+    struct TestSnapClient {
+        remote: TestTransaction,
+        root: B256,
+        /// Accounts that are left out of the first account range that contains them.
+        omitted: Mutex<HashSet<B256>>,
+    }
+
+    impl TestSnapClient {
+        fn respond<T: Send + 'static>(response: T) -> SnapFut<T> {
+            Box::pin(std::future::ready(Ok(WithPeerId::new(PeerId::random(), response))))
+        }
+    }
+
+    impl DownloadClient for TestSnapClient {
+        fn report_bad_message(&self, _peer_id: PeerId) {}
+
+        fn num_connected_peers(&self) -> usize {
+            1
+        }
+    }
+
+    impl SnapClient for TestSnapClient {
+        fn get_account_range(&self, request: GetAccountRange) -> SnapFut<AccountRange> {
+            let mut accounts = Vec::new();
+            let mut proof = Vec::new();
+            if request.root_hash == self.root {
+                let provider = self.remote.inner();
+                let tx = provider.tx_ref();
+                let mut omitted = self.omitted.lock().unwrap();
+                let mut cursor = tx.cursor_read::<tables::HashedAccount>().unwrap();
+                for entry in cursor.walk(Some(request.starting_hash)).unwrap() {
+                    let (hash, account) = entry.unwrap();
+                    if omitted.remove(&hash) {
+                        continue
+                    }
+                    let storage_root = StorageRoot::new_hashed(tx, hash).root().unwrap();
+                    accounts
+                        .push(AccountData { hash, body: SlimAccount::new(account, storage_root) });
+                    if hash >= request.limit_hash || accounts.len() == 3 {
+                        break
+                    }
+                }
+                // range proofs are not verified by the stage
+                proof.push(Bytes::from_static(&[EMPTY_STRING_CODE]));
+            }
+            Self::respond(AccountRange { request_id: request.request_id, accounts, proof })
+        }
+
+        fn get_storage_ranges(&self, request: GetStorageRanges) -> SnapFut<StorageRanges> {
+            let mut slots = Vec::new();
+            let mut proof = Vec::new();
+            if request.root_hash == self.root {
+                let provider = self.remote.inner();
+                let mut cursor =
+                    provider.tx_ref().cursor_dup_read::<tables::HashedStorage>().unwrap();
+                let mut budget = 4;
+                for (idx, hashed_address) in request.account_hashes.iter().enumerate() {
+                    let start = if idx == 0 { request.starting_hash() } else { B256::ZERO };
+                    let mut account_slots = cursor
+                        .walk_dup(Some(*hashed_address), Some(start))
+                        .unwrap()
+                        .map(|entry| {
+                            let (_, slot) = entry.unwrap();
+                            StorageData {
+                                hash: slot.key,
+                                data: alloy_rlp::encode_fixed_size(&slot.value).to_vec().into(),
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    let complete = account_slots.len() <= budget;
+                    account_slots.truncate(budget);
+                    budget -= account_slots.len();
+                    slots.push(account_slots);
+
+                    if !complete || start != B256::ZERO {
+                        proof.push(Bytes::from_static(&[EMPTY_STRING_CODE]));
+                        break
+                    }
+                    if budget == 0 {
+                        break
+                    }
+                }
+            }
+            Self::respond(StorageRanges { request_id: request.request_id, slots, proof })
+        }
+
+        fn get_byte_codes(&self, request: GetByteCodes) -> SnapFut<ByteCodes> {
+            let provider = self.remote.inner();
+            let codes = request
+                .hashes
+                .iter()
+                .filter_map(|hash| provider.tx_ref().get::<tables::Bytecodes>(*hash).unwrap())
+                .map(|bytecode| bytecode.original_bytes())
+                .collect();
+            Self::respond(ByteCodes { request_id: request.request_id, codes })
+        }
+
+        fn get_trie_nodes(&self, request: GetTrieNodes) -> SnapFut<TrieNodes> {
+            let mut nodes = Vec::new();
+            if request.root_hash == self.root {
+                let paths = request
+                    .paths
+                    .iter()
+                    .map(|path_set| decode_compact_path(&path_set[0]))
+                    .collect::<Vec<_>>();
+                let provider = self.remote.inner();
+                let (_, proof_nodes) =
+                    Proof::new(provider.tx_ref()).account_proof_nodes(paths.clone()).unwrap();
+                for path in paths {
+                    let Some(node) = proof_nodes.get(&path) else { break };
+                    nodes.push(node.clone());
+                }
+            }
+            Self::respond(TrieNodes { request_id: request.request_id, nodes })
+        }
+    }
+
+    /// Decodes a compact encoded path without the leaf flag.
+    fn decode_compact_path(path: &[u8]) -> Nibbles {
+        let nibbles = Nibbles::unpack(path);
+        // the flag nibble is followed by a padding nibble if the path has an even length
+        nibbles.slice_from(if nibbles[0] & 1 == 1 { 1 } else { 2 })
+    }
+
+    /// Accounts, every fifth with bytecode and a growing number of storage slots.
+    fn test_accounts() -> Vec<(Address, Account, Vec<StorageEntry>, Option<Bytes>)> {
+        (1..=40u8)
+            .map(|i| {
+                let code = (i % 5 == 0).then(|| Bytes::from(vec![0x60, i, 0x00]));
+                let account = Account {
+                    nonce: i as u64,
+                    balance: U256::from(i),
+                    bytecode_hash: code.as_ref().map(keccak256),
+                };
+                let storage = (1..=(i / 5 * 2) * u8::from(code.is_some()))
+                    .map(|slot| StorageEntry {
+                        key: B256::with_last_byte(slot),
+                        value: U256::from(slot as u64 * i as u64),
+                    })
+                    .collect();
+                (Address::with_last_byte(i), account, storage, code)
+            })
+            .collect()
+    }
+
+    /// Creates the client serving the state of the accounts.
+    fn create_client(
+        accounts: &[(Address, Account, Vec<StorageEntry>, Option<Bytes>)],
+    ) -> TestSnapClient {
+        let remote = TestTransaction::default();
+        remote
+            .insert_accounts_and_storages(
+                accounts
+                    .iter()
+                    .map(|(address, account, storage, _)| (*address, (*account, storage.clone()))),
+            )
+            .unwrap();
+        remote
+            .commit(|tx| {
+                for code in accounts.iter().filter_map(|(_, _, _, code)| code.clone()) {
+                    tx.put::<tables::Bytecodes>(keccak256(&code), Bytecode::new_raw(code))?;
+                }
+                Ok(())
+            })
+            .unwrap();
+        let root = remote.query(|tx| Ok(StateRoot::new(tx).root().unwrap())).unwrap();
+        TestSnapClient { remote, root, omitted: Default::default() }
+    }
+
+    /// Inserts the pivot header with the state root into the local database.
+    fn insert_pivot(local: &TestTransaction, pivot: BlockNumber, state_root: B256) {
+        let mut header = random_header(&mut generators::rng(), pivot, None).unseal();
+        header.state_root = state_root;
+        local.insert_headers(std::iter::once(&header.seal_slow())).unwrap();
+    }
+
+    /// Runs the stage until it's done and commits after every run, like the pipeline.
+    async fn run_stage(
+        stage: &mut SnapSyncStage<TestSnapClient>,
+        local: &TestTransaction,
+        target: BlockNumber,
+    ) -> Result<ExecOutput, StageError> {
+        let factory = ProviderFactory::new(local.tx.db(), MAINNET.clone());
+        let mut checkpoint = StageCheckpoint::default();
+        loop {
+            let provider = factory.provider_rw().unwrap();
+            let output = Stage::<DatabaseEnv>::execute(
+                stage,
+                &provider,
+                ExecInput { target: Some(target), checkpoint: Some(checkpoint) },
+            )
+            .await?;
+            provider.save_stage_checkpoint(StageId::SnapSync, output.checkpoint).unwrap();
+            provider.commit().unwrap();
+            if output.done {
+                return Ok(output)
+            }
+            checkpoint = output.checkpoint;
+        }
+    }
+
+    /// Asserts that the local state matches the state served by the client.
+    fn assert_synced_state(local: &TestTransaction, client: &TestSnapClient) {
+        assert_eq!(
+            local.table::<tables::HashedAccount>().unwrap(),
+            client.remote.table::<tables::HashedAccount>().unwrap()
+        );
+        assert_eq!(
+            local.table::<tables::HashedStorage>().unwrap(),
+            client.remote.table::<tables::HashedStorage>().unwrap()
+        );
+        assert_eq!(
+            local.table::<tables::Bytecodes>().unwrap(),
+            client.remote.table::<tables::Bytecodes>().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn sync_pivot_state() {
+        let accounts = test_accounts();
+        let client = create_client(&accounts);
+        let local = TestTransaction::default();
+        let target = 100;
+        let pivot = target - SNAP_SYNC_PIVOT_DISTANCE;
+        insert_pivot(&local, pivot, client.root);
+
+        let mut stage = SnapSyncStage::new(client, 10);
+        let output = run_stage(&mut stage, &local, target).await.unwrap();
+        assert_eq!(output.checkpoint.block_number, target);
+        assert_matches!(
+            output.checkpoint.snap_sync_stage_checkpoint(),
+            Some(SnapSyncCheckpoint { pivot: p, next_account: None, complete: true, .. }) if p == pivot
+        );
+        assert_synced_state(&local, &stage.client);
+
+        // the pipeline continues after the pivot
+        let provider = local.inner();
+        for stage_id in [
+            StageId::Execution,
+            StageId::AccountHashing,
+            StageId::StorageHashing,
+            StageId::MerkleExecute,
+        ] {
+            assert_eq!(
+                provider.get_stage_checkpoint(stage_id).unwrap(),
+                Some(StageCheckpoint::new(pivot))
+            );
+        }
+        drop(provider);
+
+        // the state is read from the hashed tables
+        let state = local.factory.latest().unwrap();
+        for (address, account, storage, _) in accounts {
+            assert_eq!(state.basic_account(address).unwrap(), Some(account));
+            for entry in storage {
+                assert_eq!(state.storage(address, entry.key).unwrap(), Some(entry.value));
+            }
+        }
+
+        // a complete snap sync passes the checkpoint through
+        let output = run_stage(&mut stage, &local, target + 10).await.unwrap();
+        assert_eq!(output.checkpoint.block_number, target + 10);
+    }
+
+    #[tokio::test]
+    async fn heal_incomplete_ranges() {
+        let accounts = test_accounts();
+        let client = create_client(&accounts);
+        client
+            .omitted
+            .lock()
+            .unwrap()
+            .extend([1u8, 10, 25].map(|i| keccak256(Address::with_last_byte(i))));
+        let local = TestTransaction::default();
+        let target = 100;
+        insert_pivot(&local, target - SNAP_SYNC_PIVOT_DISTANCE, client.root);
+
+        let mut stage = SnapSyncStage::new(client, 10);
+        let output = run_stage(&mut stage, &local, target).await.unwrap();
+        assert_eq!(output.checkpoint.block_number, target);
+        assert!(stage.client.omitted.lock().unwrap().is_empty());
+        assert_synced_state(&local, &stage.client);
+    }
+
+    #[tokio::test]
+    async fn moved_pivot() {
+        let mut accounts = test_accounts();
+        let client = create_client(&accounts);
+        let local = TestTransaction::default();
+        let target = 100;
+        insert_pivot(&local, target - SNAP_SYNC_PIVOT_DISTANCE, client.root);
+
+        // download part of the state of the first pivot
+        let factory = ProviderFactory::new(local.tx.db(), MAINNET.clone());
+        let mut stage = SnapSyncStage::new(client, 10);
+        let provider = factory.provider_rw().unwrap();
+        let input = ExecInput { target: Some(target), checkpoint: None };
+        let output = Stage::<DatabaseEnv>::execute(&mut stage, &provider, input).await.unwrap();
+        assert!(!output.done);
+        provider.save_stage_checkpoint(StageId::SnapSync, output.checkpoint).unwrap();
+        provider.commit().unwrap();
+
+        // the state of the new pivot differs in an account that was already downloaded
+        let downloaded = local.table::<tables::HashedAccount>().unwrap()[0].0;
+        let (_, account, _, _) =
+            accounts.iter_mut().find(|(address, ..)| keccak256(address) == downloaded).unwrap();
+        account.nonce += 1;
+        stage.client = create_client(&accounts);
+        insert_pivot(&local, target + 1 - SNAP_SYNC_PIVOT_DISTANCE, stage.client.root);
+
+        let factory = ProviderFactory::new(local.tx.db(), MAINNET.clone());
+        let mut checkpoint = output.checkpoint;
+        let output = loop {
+            let provider = factory.provider_rw().unwrap();
+            let input = ExecInput { target: Some(target + 1), checkpoint: Some(checkpoint) };
+            let output = Stage::<DatabaseEnv>::execute(&mut stage, &provider, input).await.unwrap();
+            provider.commit().unwrap();
+            if output.done {
+                break output
+            }
+            checkpoint = output.checkpoint;
+        };
+        assert_matches!(
+            output.checkpoint.snap_sync_stage_checkpoint(),
+            Some(SnapSyncCheckpoint { complete: true, .. })
+        );
+        assert_synced_state(&local, &stage.client);
+    }
+
+    #[tokio::test]
+    async fn stale_pivot() {
+        let client = create_client(&test_accounts());
+        let local = TestTransaction::default();
+        let target = 100;
+        let pivot = target - SNAP_SYNC_PIVOT_DISTANCE;
+        insert_pivot(&local, pivot, B256::random());
+
+        let mut stage = SnapSyncStage::new(client, 10);
+        let output = run_stage(&mut stage, &local, target).await.unwrap();
+        assert_eq!(output.checkpoint.block_number, 0);
+        assert_matches!(
+            output.checkpoint.snap_sync_stage_checkpoint(),
+            Some(SnapSyncCheckpoint { pivot: p, next_account: Some(B256::ZERO), complete: false, .. }) if p == pivot
+        );
+    }
+
+    #[tokio::test]
+    async fn skip_executed_node() {
+        let client = create_client(&test_accounts());
+        let local = TestTransaction::default();
+        let provider = local.inner_rw();
+        provider.save_stage_checkpoint(StageId::Execution, StageCheckpoint::new(1)).unwrap();
+        provider.commit().unwrap();
+
+        let mut stage = SnapSyncStage::new(client, 10);
+        let output = run_stage(&mut stage, &local, 100).await.unwrap();
+        assert_eq!(output.checkpoint, StageCheckpoint::new(100));
+        assert!(local.table_is_empty::<tables::HashedAccount>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn unwind_below_pivot() {
+        let client = create_client(&test_accounts());
+        let local = TestTransaction::default();
+        let factory = ProviderFactory::new(local.tx.db(), MAINNET.clone());
+        let provider = factory.provider_rw().unwrap();
+        let mut stage = SnapSyncStage::new(client, 10);
+        let checkpoint =
+            StageCheckpoint::new(100).with_snap_sync_stage_checkpoint(SnapSyncCheckpoint {
+                pivot: 36,
+                complete: true,
+                ..Default::default()
+            });
+
+        let input = UnwindInput { checkpoint, unwind_to: 36, bad_block: None };
+        let output = Stage::<DatabaseEnv>::unwind(&mut stage, &provider, input).await.unwrap();
+        assert_eq!(output.checkpoint.block_number, 36);
+        assert!(output.checkpoint.snap_sync_stage_checkpoint().is_some());
+
+        let input = UnwindInput { checkpoint, unwind_to: 35, bad_block: None };
+        assert_matches!(
+            Stage::<DatabaseEnv>::unwind(&mut stage, &provider, input).await,
+            Err(StageError::Fatal(_))
+        );
+    }
+
+    #[test]
+    fn trie_helpers() {
+        let branch = alloy_rlp::encode(
+            (0..17)
+                .map(|i| if i == 3 { Bytes::from(vec![0xab; 32]) } else { Bytes::new() })
+                .collect::<Vec<_>>(),
+        );
+        let children = branch_children(&branch).unwrap();
+        assert_eq!(child_hash(children[3]), Some(B256::repeat_byte(0xab)));
+        assert_eq!(child_hash(children[4]), None);
+        assert_eq!(branch_children(&alloy_rlp::encode(vec![Bytes::new(); 2])), None);
+
+        let (start, end) = prefix_range(&Nibbles::from_hex(vec![0xa, 0xb, 0xc]));
+        assert_eq!(start[..2], [0xab, 0xc0]);
+        assert!(start[2..].iter().all(|byte| *byte == 0));
+        assert_eq!(end[..2], [0xab, 0xcf]);
+        assert!(end[2..].iter().all(|byte| *byte == 0xff));
+
+        assert_eq!(next_hash(B256::ZERO), Some(B256::with_last_byte(1)));
+        assert_eq!(next_hash(B256::repeat_byte(0xff)), None);
+        assert_eq!(decode_slot(&alloy_rlp::encode(U256::from(5))), Some(U256::from(5)));
+        assert_eq!(decode_slot(&[0x80]), None);
+        assert_eq!(decode_slot(&[0x81, 0x05]), None);
+    }
+}
@@ -1,6 +1,7 @@
-use super::{ReceiptMask, TransactionMask};
+use super::{AccountChangeSetMask, ReceiptMask, StorageChangeSetMask, TransactionMask};
 use crate::{
     add_snapshot_mask,
+    models::{AccountChangeSetBlock, StorageChangeSetBlock},
     snapshot::mask::{ColumnSelectorOne, ColumnSelectorTwo, HeaderMask},
     table::Table,
     CanonicalHeaders, HeaderTD, Receipts, Transactions,
@@ -26,3 +27,7 @@ add_snapshot_mask!(ReceiptMask, <Receipts as Table>::Value, 0b1);
 
 // TRANSACTION MASKS
 add_snapshot_mask!(TransactionMask, <Transactions as Table>::Value, 0b1);
+
+// CHANGESET MASKS
+add_snapshot_mask!(AccountChangeSetMask, AccountChangeSetBlock, 0b1);
+add_snapshot_mask!(StorageChangeSetMask, StorageChangeSetBlock, 0b1);
@@ -46,6 +46,8 @@ impl_compression_for_compact!(
     StoredBlockWithdrawals,
     Bytecode,
     AccountBeforeTx,
+    AccountChangeSetBlock,
+    StorageChangeSetBlock,
     TransactionSignedNoHash,
     CompactU256,
     StageCheckpoint,
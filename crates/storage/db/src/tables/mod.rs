@@ -51,7 +51,7 @@ pub enum TableType {
 }
 
 /// Number of tables that should be present inside database.
-pub const NUM_TABLES: usize = 26;
+pub const NUM_TABLES: usize = 28;
 
 /// The general purpose of this is to use with a combination of Tables enum,
 /// by implementing a `TableViewer` trait you can operate on db tables in an abstract way.
@@ -184,7 +184,9 @@ tables!([
     (TxSenders, TableType::Table),
     (SyncStage, TableType::Table),
     (SyncStageProgress, TableType::Table),
-    (PruneCheckpoints, TableType::Table)
+    (PruneCheckpoints, TableType::Table),
+    (TraceAddressHistory, TableType::Table),
+    (BlockTraceAddresses, TableType::DupSort)
 ]);
 
 /// Macro to declare key value table.
@@ -414,6 +416,22 @@ table!(
     ( PruneCheckpoints ) PruneSegment | PruneCheckpoint
 );
 
+table!(
+    /// Stores pointers to the blocks in which an address appears in a call, create or selfdestruct
+    /// trace, either as sender, recipient or internal call participant.
+    ///
+    /// Shards are laid out the same way as in [`AccountHistory`]. The table is only populated if the
+    /// optional trace address index stage is enabled.
+    ( TraceAddressHistory ) ShardedKey<Address> | BlockNumberList
+);
+
+dupsort!(
+    /// Stores all addresses that appear in the traces of a block.
+    ///
+    /// This is the reverse of [`TraceAddressHistory`] and is used to unwind it.
+    ( BlockTraceAddresses ) BlockNumber | [Address] Address
+);
+
 /// Alias Types
 
 /// List with transaction numbers.
@@ -453,6 +471,8 @@ mod tests {
         (TableType::Table, SyncStage::NAME),
         (TableType::Table, SyncStageProgress::NAME),
         (TableType::Table, PruneCheckpoints::NAME),
+        (TableType::Table, TraceAddressHistory::NAME),
+        (TableType::DupSort, BlockTraceAddresses::NAME),
     ];
 
     #[test]
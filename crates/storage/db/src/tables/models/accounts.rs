@@ -8,7 +8,7 @@ use crate::{
     DatabaseError,
 };
 use reth_codecs::{derive_arbitrary, Compact};
-use reth_primitives::{Account, Address, BlockNumber, Buf};
+use reth_primitives::{Account, Address, BlockNumber, Buf, StorageEntry};
 use serde::{Deserialize, Serialize};
 
 /// Account as it is saved inside [`AccountChangeSet`][crate::tables::AccountChangeSet].
@@ -59,6 +59,73 @@ impl Compact for AccountBeforeTx {
     }
 }
 
+/// Storage entry as it is saved inside [`StorageChangeSet`][crate::tables::StorageChangeSet],
+/// together with the address of the account it belongs to.
+///
+/// Used as an entry of a [`StorageChangeSetBlock`].
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct StorageBeforeTx {
+    /// Address of the account the storage slot belongs to.
+    pub address: Address,
+    /// Storage slot and its value before the transaction.
+    pub entry: StorageEntry,
+}
+
+impl Compact for StorageBeforeTx {
+    fn to_compact<B>(self, buf: &mut B) -> usize
+    where
+        B: bytes::BufMut + AsMut<[u8]>,
+    {
+        buf.put_slice(self.address.as_slice());
+        self.entry.to_compact(buf) + 20
+    }
+
+    fn from_compact(mut buf: &[u8], len: usize) -> (Self, &[u8])
+    where
+        Self: Sized,
+    {
+        let address = Address::from_slice(&buf[..20]);
+        buf.advance(20);
+
+        let (entry, buf) = StorageEntry::from_compact(buf, len - 20);
+        (Self { address, entry }, buf)
+    }
+}
+
+/// All account changes of a single block, sorted by address.
+///
+/// Row of an [`AccountChangeSets`][reth_primitives::SnapshotSegment::AccountChangeSets] snapshot.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct AccountChangeSetBlock(pub Vec<AccountBeforeTx>);
+
+/// All storage changes of a single block, sorted by address and storage key.
+///
+/// Row of a [`StorageChangeSets`][reth_primitives::SnapshotSegment::StorageChangeSets] snapshot.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct StorageChangeSetBlock(pub Vec<StorageBeforeTx>);
+
+macro_rules! impl_compact_for_changeset_block {
+    ($($name:tt),+) => {
+        $(
+            impl Compact for $name {
+                fn to_compact<B>(self, buf: &mut B) -> usize
+                where
+                    B: bytes::BufMut + AsMut<[u8]>,
+                {
+                    self.0.to_compact(buf)
+                }
+
+                fn from_compact(buf: &[u8], len: usize) -> (Self, &[u8]) {
+                    let (entries, buf) = Vec::from_compact(buf, len);
+                    (Self(entries), buf)
+                }
+            }
+        )+
+    };
+}
+
+impl_compact_for_changeset_block!(AccountChangeSetBlock, StorageChangeSetBlock);
+
 /// [`BlockNumber`] concatenated with [`Address`]. Used as the key for
 /// [`StorageChangeSet`](crate::tables::StorageChangeSet)
 ///
@@ -129,6 +196,7 @@ impl_fixed_arbitrary!(BlockNumberAddress, 28);
 mod test {
     use super::*;
     use rand::{thread_rng, Rng};
+    use reth_primitives::{B256, U256};
     use std::str::FromStr;
 
     #[test]
@@ -155,4 +223,24 @@ mod test {
         let key = BlockNumberAddress::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
         assert_eq!(bytes, Encode::encode(key));
     }
+
+    #[test]
+    fn storage_changeset_block_roundtrip() {
+        let block = StorageChangeSetBlock(vec![
+            StorageBeforeTx {
+                address: Address::with_last_byte(1),
+                entry: StorageEntry::new(B256::with_last_byte(1), U256::ZERO),
+            },
+            StorageBeforeTx {
+                address: Address::with_last_byte(2),
+                entry: StorageEntry::new(B256::with_last_byte(2), U256::from(42)),
+            },
+        ]);
+
+        let mut buf = Vec::new();
+        block.clone().to_compact(&mut buf);
+        let (decoded, rest) = StorageChangeSetBlock::from_compact(&buf, buf.len());
+        assert_eq!(decoded, block);
+        assert!(rest.is_empty());
+    }
 }
@@ -0,0 +1,131 @@
+use crate::NippyJarError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// User-defined compression algorithm, which can be set on jar columns with
+/// [`Compressors::Custom`](crate::compression::Compressors::Custom).
+///
+/// Only the [`name`](CustomCompression::name) and the [`state`](CustomCompression::state) are
+/// stored in the jar configuration. When loading the jar, the algorithm is restored with the
+/// [`CustomCompressionLoader`] that was registered under the same name in
+/// [`NippyJar::load_with_custom_compression`](crate::NippyJar::load_with_custom_compression).
+pub trait CustomCompression: Send + Sync + std::fmt::Debug {
+    /// Unique name of the algorithm.
+    fn name(&self) -> &str;
+
+    /// Appends decompressed data to the dest buffer.
+    fn decompress_to(&self, value: &[u8], dest: &mut Vec<u8>) -> Result<(), NippyJarError>;
+
+    /// Appends compressed data from `src` to `dest`.
+    ///
+    /// Returns number of bytes written to `dest`.
+    fn compress_to(&self, src: &[u8], dest: &mut Vec<u8>) -> Result<usize, NippyJarError>;
+
+    /// Returns `true` if it's ready to compress.
+    fn is_ready(&self) -> bool {
+        true
+    }
+
+    /// If required, prepares the algorithm with an early pass on the data of every column it's
+    /// set on. Example: training a dictionary.
+    fn prepare_compression(&mut self, _columns: Vec<Vec<Vec<u8>>>) -> Result<(), NippyJarError> {
+        Ok(())
+    }
+
+    /// Returns the state that is required to decompress data, e.g. a trained dictionary.
+    fn state(&self) -> Vec<u8> {
+        Vec::new()
+    }
+}
+
+/// Restores a [`CustomCompression`] from its [`state`](CustomCompression::state) when loading a
+/// jar.
+pub type CustomCompressionLoader =
+    fn(state: &[u8]) -> Result<Box<dyn CustomCompression>, NippyJarError>;
+
+/// Wrapper around a [`CustomCompression`] that is stored in the jar configuration.
+#[derive(Debug)]
+pub struct CustomCompressor {
+    /// Name of the algorithm.
+    name: String,
+    /// State of the algorithm, as of serialization or deserialization.
+    state: Vec<u8>,
+    /// The algorithm. Only missing if it was deserialized and not restored yet.
+    inner: Option<Box<dyn CustomCompression>>,
+}
+
+impl CustomCompressor {
+    /// Creates new [`CustomCompressor`].
+    pub fn new(inner: Box<dyn CustomCompression>) -> Self {
+        Self { name: inner.name().to_string(), state: Vec::new(), inner: Some(inner) }
+    }
+
+    /// Returns the name of the algorithm.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns `true` if the algorithm is available, i.e. it wasn't deserialized or it was
+    /// restored already.
+    pub fn is_loaded(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Restores the algorithm with the loader registered under its name, if it wasn't already.
+    pub(crate) fn load(
+        &mut self,
+        loaders: &[(&str, CustomCompressionLoader)],
+    ) -> Result<(), NippyJarError> {
+        if self.inner.is_none() {
+            let (_, loader) = loaders
+                .iter()
+                .find(|(name, _)| *name == self.name)
+                .ok_or_else(|| NippyJarError::CustomCompressionNotLoaded(self.name.clone()))?;
+            self.inner = Some(loader(&self.state)?);
+        }
+        Ok(())
+    }
+
+    /// Returns a reference to the algorithm.
+    pub fn inner(&self) -> Result<&dyn CustomCompression, NippyJarError> {
+        self.inner
+            .as_deref()
+            .ok_or_else(|| NippyJarError::CustomCompressionNotLoaded(self.name.clone()))
+    }
+
+    /// Returns a mutable reference to the algorithm.
+    pub fn inner_mut(&mut self) -> Result<&mut (dyn CustomCompression + 'static), NippyJarError> {
+        self.inner
+            .as_deref_mut()
+            .ok_or_else(|| NippyJarError::CustomCompressionNotLoaded(self.name.clone()))
+    }
+}
+
+impl Serialize for CustomCompressor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let state = self.inner.as_ref().map(|inner| inner.state());
+        (&self.name, state.as_ref().unwrap_or(&self.state)).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CustomCompressor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (name, state) = <(String, Vec<u8>)>::deserialize(deserializer)?;
+        Ok(Self { name, state, inner: None })
+    }
+}
+
+#[cfg(test)]
+impl PartialEq for CustomCompressor {
+    fn eq(&self, other: &Self) -> bool {
+        let state = |compressor: &Self| {
+            compressor.inner.as_ref().map(|inner| inner.state()).unwrap_or(compressor.state.clone())
+        };
+        self.name == other.name && state(self) == state(other)
+    }
+}
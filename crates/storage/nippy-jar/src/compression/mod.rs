@@ -2,9 +2,11 @@ use crate::NippyJarError;
 use serde::{Deserialize, Serialize};
 
 mod zstd;
-pub use self::zstd::{DecoderDictionary, Decompressor, Zstd, ZstdState};
+pub use self::zstd::{DecoderDictionary, Decompressor, RawDictionary, Zstd, ZstdState};
 mod lz4;
 pub use self::lz4::Lz4;
+mod custom;
+pub use self::custom::{CustomCompression, CustomCompressionLoader, CustomCompressor};
 
 /// Trait that will compress column values
 pub trait Compression: Serialize + for<'a> Deserialize<'a> {
@@ -45,6 +47,14 @@ pub trait Compression: Serialize + for<'a> Deserialize<'a> {
 pub enum Compressors {
     Zstd(Zstd),
     Lz4(Lz4),
+    Custom(CustomCompressor),
+}
+
+impl Compressors {
+    /// Creates a [`Compressors::Custom`] out of a user-defined compression algorithm.
+    pub fn custom(compression: impl CustomCompression + 'static) -> Self {
+        Compressors::Custom(CustomCompressor::new(Box::new(compression)))
+    }
 }
 
 impl Compression for Compressors {
@@ -52,12 +62,19 @@ impl Compression for Compressors {
         match self {
             Compressors::Zstd(zstd) => zstd.decompress_to(value, dest),
             Compressors::Lz4(lz4) => lz4.decompress_to(value, dest),
+            Compressors::Custom(custom) => custom.inner()?.decompress_to(value, dest),
         }
     }
+
     fn decompress(&self, value: &[u8]) -> Result<Vec<u8>, NippyJarError> {
         match self {
             Compressors::Zstd(zstd) => zstd.decompress(value),
             Compressors::Lz4(lz4) => lz4.decompress(value),
+            Compressors::Custom(custom) => {
+                let mut decompressed = Vec::with_capacity(value.len() * 2);
+                custom.inner()?.decompress_to(value, &mut decompressed)?;
+                Ok(decompressed)
+            }
         }
     }
 
@@ -67,6 +84,7 @@ impl Compression for Compressors {
             let result = match self {
                 Compressors::Zstd(zstd) => zstd.compress_to(src, dest),
                 Compressors::Lz4(lz4) => lz4.compress_to(src, dest),
+                Compressors::Custom(custom) => custom.inner()?.compress_to(src, dest),
             };
 
             match result {
@@ -85,6 +103,11 @@ impl Compression for Compressors {
         match self {
             Compressors::Zstd(zstd) => zstd.compress(src),
             Compressors::Lz4(lz4) => lz4.compress(src),
+            Compressors::Custom(custom) => {
+                let mut compressed = Vec::with_capacity(src.len());
+                custom.inner()?.compress_to(src, &mut compressed)?;
+                Ok(compressed)
+            }
         }
     }
 
@@ -92,6 +115,7 @@ impl Compression for Compressors {
         match self {
             Compressors::Zstd(zstd) => zstd.is_ready(),
             Compressors::Lz4(lz4) => lz4.is_ready(),
+            Compressors::Custom(custom) => custom.inner().map_or(false, |inner| inner.is_ready()),
         }
     }
 
@@ -102,6 +126,9 @@ impl Compression for Compressors {
         match self {
             Compressors::Zstd(zstd) => zstd.prepare_compression(columns),
             Compressors::Lz4(lz4) => lz4.prepare_compression(columns),
+            Compressors::Custom(custom) => custom.inner_mut()?.prepare_compression(
+                columns.into_iter().map(|column| column.into_iter().collect()).collect(),
+            ),
         }
     }
 }
@@ -10,7 +10,8 @@ use tracing::*;
 use zstd::bulk::Compressor;
 pub use zstd::{bulk::Decompressor, dict::DecoderDictionary};
 
-type RawDictionary = Vec<u8>;
+/// A zstd dictionary as it's stored in the jar configuration.
+pub type RawDictionary = Vec<u8>;
 
 #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
 pub enum ZstdState {
@@ -56,6 +57,43 @@ impl Zstd {
         self
     }
 
+    /// Uses pre-trained dictionaries, one per column, instead of training them on the data during
+    /// [`Compression::prepare_compression`].
+    ///
+    /// Allows sharing a dictionary between jars of the same kind of data, eg. receipts.
+    pub fn with_dictionaries(
+        mut self,
+        dictionaries: Vec<RawDictionary>,
+    ) -> Result<Self, NippyJarError> {
+        if dictionaries.len() != self.columns {
+            return Err(NippyJarError::ColumnLenMismatch(self.columns, dictionaries.len()))
+        }
+
+        self.use_dict = true;
+        self.dictionaries = Some(Arc::new(ZstdDictionaries::new(dictionaries)));
+        self.state = ZstdState::Ready;
+        Ok(self)
+    }
+
+    /// Trains a dictionary of at most `max_dict_size` bytes on the values of a column.
+    pub fn train_dictionary(
+        column: impl IntoIterator<Item = Vec<u8>>,
+        max_dict_size: usize,
+    ) -> Result<RawDictionary, NippyJarError> {
+        // ZSTD requires all training data to be continuous in memory, alongside the size of
+        // each entry
+        let mut sizes = vec![];
+        let data: Vec<_> = column
+            .into_iter()
+            .flat_map(|data| {
+                sizes.push(data.len());
+                data
+            })
+            .collect();
+
+        Ok(zstd::dict::from_continuous(&data, &sizes, max_dict_size)?)
+    }
+
     /// Creates a list of [`Decompressor`] if using dictionaries.
     pub fn decompressors(&self) -> Result<Vec<Decompressor<'_>>, NippyJarError> {
         if let Some(dictionaries) = &self.dictionaries {
@@ -190,7 +228,7 @@ impl Compression for Zstd {
         &mut self,
         columns: Vec<impl IntoIterator<Item = Vec<u8>>>,
     ) -> Result<(), NippyJarError> {
-        if !self.use_dict {
+        if !self.use_dict || self.is_ready() {
             return Ok(())
         }
 
@@ -211,18 +249,7 @@ impl Compression for Zstd {
         // TODO: parallel calculation
         let mut dictionaries = vec![];
         for column in columns {
-            // ZSTD requires all training data to be continuous in memory, alongside the size of
-            // each entry
-            let mut sizes = vec![];
-            let data: Vec<_> = column
-                .into_iter()
-                .flat_map(|data| {
-                    sizes.push(data.len());
-                    data
-                })
-                .collect();
-
-            dictionaries.push(zstd::dict::from_continuous(&data, &sizes, self.max_dict_size)?);
+            dictionaries.push(Self::train_dictionary(column, self.max_dict_size)?);
         }
 
         debug_assert_eq!(dictionaries.len(), self.columns);
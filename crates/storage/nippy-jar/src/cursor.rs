@@ -229,7 +229,7 @@ where
             value_offset..next_value_offset
         };
 
-        if let Some(compression) = self.jar.compressor() {
+        if let Some((compression, dictionary)) = self.jar.column_compressor(column) {
             let from = self.internal_buffer.len();
             match compression {
                 Compressors::Zstd(z) if z.use_dict => {
@@ -237,7 +237,7 @@ where
                     // loaded (happens during deserialization). Otherwise, there's an issue
                     // somewhere else and we can't recover here anyway.
                     let dictionaries = z.dictionaries.as_ref().expect("dictionaries to exist")
-                        [column]
+                        [dictionary]
                         .loaded()
                         .expect("dictionary to be loaded");
                     let mut decompressor = Decompressor::with_prepared_dictionary(dictionaries)?;
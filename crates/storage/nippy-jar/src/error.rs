@@ -41,4 +41,8 @@ pub enum NippyJarError {
     DictionaryNotLoaded,
     #[error("It's not possible to generate a compressor after loading a dictionary.")]
     CompressorNotAllowed,
+    #[error("nippy jar version {0} is not supported")]
+    UnsupportedVersion(usize),
+    #[error("custom compression `{0}` is not loaded")]
+    CustomCompressionNotLoaded(String),
 }
@@ -33,7 +33,7 @@ pub mod filter;
 use filter::{Cuckoo, InclusionFilter, InclusionFilters};
 
 pub mod compression;
-use compression::{Compression, Compressors};
+use compression::{Compression, Compressors, CustomCompressionLoader};
 
 pub mod phf;
 pub use phf::PHFKey;
@@ -45,7 +45,8 @@ pub use error::NippyJarError;
 mod cursor;
 pub use cursor::NippyJarCursor;
 
-const NIPPY_JAR_VERSION: usize = 1;
+/// Version 2 added [`NippyJar::with_column_compressors`].
+const NIPPY_JAR_VERSION: usize = 2;
 
 /// A [`RefRow`] is a list of column value slices pointing to either an internal buffer or a
 /// memory-mapped file.
@@ -90,6 +91,8 @@ pub struct NippyJar<H = ()> {
     columns: usize,
     /// Optional compression algorithm applied to the data.
     compressor: Option<Compressors>,
+    /// Optional compression algorithm per column. If set, it takes the place of `compressor`.
+    column_compressors: Vec<Option<Compressors>>,
     /// Optional filter function for data membership checks.
     filter: Option<InclusionFilters>,
     /// Optional Perfect Hashing Function (PHF) for unique offset mapping.
@@ -116,6 +119,7 @@ impl<H: std::fmt::Debug> std::fmt::Debug for NippyJar<H> {
             .field("user_header", &self.user_header)
             .field("columns", &self.columns)
             .field("compressor", &self.compressor)
+            .field("column_compressors", &self.column_compressors)
             .field("filter", &self.filter)
             .field("phf", &self.phf)
             .field("offsets_index (len)", &self.offsets_index.len())
@@ -157,6 +161,7 @@ where
             columns,
             max_row_size: 0,
             compressor: None,
+            column_compressors: Vec::new(),
             filter: None,
             phf: None,
             offsets: EliasFano::default(),
@@ -165,16 +170,28 @@ where
         }
     }
 
-    /// Adds [`compression::Zstd`] compression.
+    /// Adds [`compression::Zstd`] compression to all columns.
     pub fn with_zstd(mut self, use_dict: bool, max_dict_size: usize) -> Self {
         self.compressor =
             Some(Compressors::Zstd(compression::Zstd::new(use_dict, max_dict_size, self.columns)));
+        self.column_compressors.clear();
         self
     }
 
-    /// Adds [`compression::Lz4`] compression.
+    /// Adds [`compression::Lz4`] compression to all columns.
     pub fn with_lz4(mut self) -> Self {
         self.compressor = Some(Compressors::Lz4(compression::Lz4::default()));
+        self.column_compressors.clear();
+        self
+    }
+
+    /// Adds a compression algorithm per column, or leaves the column uncompressed if it's `None`.
+    ///
+    /// Each [`compression::Zstd`] is set on a single column, so it should be created with `1` as
+    /// the number of columns.
+    pub fn with_column_compressors(mut self, compressors: Vec<Option<Compressors>>) -> Self {
+        self.compressor = None;
+        self.column_compressors = compressors;
         self
     }
 
@@ -201,6 +218,16 @@ where
         &self.user_header
     }
 
+    /// Gets the number of columns of each row.
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Gets the number of rows in the jar.
+    pub fn rows(&self) -> usize {
+        self.offsets.len().checked_div(self.columns).unwrap_or_default()
+    }
+
     /// Gets a reference to the compressor.
     pub fn compressor(&self) -> Option<&Compressors> {
         self.compressor.as_ref()
@@ -211,6 +238,40 @@ where
         self.compressor.as_mut()
     }
 
+    /// Gets a reference to the compressor of a column, alongside the index of the column within
+    /// the compressor (eg. to pick its zstd dictionary).
+    pub fn column_compressor(&self, column: usize) -> Option<(&Compressors, usize)> {
+        if self.column_compressors.is_empty() {
+            self.compressor.as_ref().map(|compressor| (compressor, column))
+        } else {
+            self.column_compressors.get(column)?.as_ref().map(|compressor| (compressor, 0))
+        }
+    }
+
+    /// Gets a mutable reference to the compressor of a column, if it was set with
+    /// [`NippyJar::with_column_compressors`].
+    pub fn column_compressor_mut(&mut self, column: usize) -> Option<&mut Compressors> {
+        self.column_compressors.get_mut(column)?.as_mut()
+    }
+
+    /// Returns the size in bytes of each column in the data file, as it's stored (eg. compressed).
+    pub fn column_sizes(&self) -> Result<Vec<u64>, NippyJarError> {
+        let data_len = std::fs::metadata(self.data_path())?.len();
+        let mut sizes = vec![0; self.columns];
+
+        for position in 0..self.offsets.len() {
+            let start = self.offsets.select(position).expect("should exist") as u64;
+            let end = if position + 1 == self.offsets.len() {
+                data_len
+            } else {
+                self.offsets.select(position + 1).expect("should exist") as u64
+            };
+            sizes[position % self.columns] += end - start;
+        }
+
+        Ok(sizes)
+    }
+
     /// Loads the file configuration and returns [`Self`].
     ///
     /// **The user must ensure the header type matches the one used during the jar's creation.**
@@ -220,7 +281,12 @@ where
 
         // SAFETY: File is read-only and its descriptor is kept alive as long as the mmap handle.
         let data_reader = unsafe { memmap2::Mmap::map(&data_file)? };
-        let mut obj: Self = bincode::deserialize_from(data_reader.as_ref())?;
+        // The version is the first serialized field, and tells the layout of the remaining ones.
+        let mut obj: Self = match bincode::deserialize_from::<_, usize>(data_reader.as_ref())? {
+            1 => bincode::deserialize_from::<_, NippyJarV1<H>>(data_reader.as_ref())?.into(),
+            NIPPY_JAR_VERSION => bincode::deserialize_from(data_reader.as_ref())?,
+            version => return Err(NippyJarError::UnsupportedVersion(version)),
+        };
         obj.path = Some(path.to_path_buf());
 
         // Read the offsets lists located at the index file.
@@ -236,6 +302,27 @@ where
         Ok(obj)
     }
 
+    /// Loads the file configuration and returns [`Self`], restoring any
+    /// [`compression::CustomCompression`] with the loader registered under its name.
+    ///
+    /// **The user must ensure the header type matches the one used during the jar's creation.**
+    pub fn load_with_custom_compression(
+        path: &Path,
+        loaders: &[(&str, CustomCompressionLoader)],
+    ) -> Result<Self, NippyJarError> {
+        let mut obj = Self::load(path)?;
+
+        for compressor in
+            obj.compressor.iter_mut().chain(obj.column_compressors.iter_mut().flatten())
+        {
+            if let Compressors::Custom(custom) = compressor {
+                custom.load(loaders)?;
+            }
+        }
+
+        Ok(obj)
+    }
+
     /// Returns the path from the data file
     pub fn data_path(&self) -> PathBuf {
         self.path.clone().expect("exists")
@@ -264,6 +351,17 @@ where
         if let Some(compression) = &mut self.compressor {
             debug!(target: "nippy-jar", columns=columns.len(), "Preparing compression.");
             compression.prepare_compression(columns)?;
+        } else if !self.column_compressors.is_empty() {
+            if columns.len() != self.columns {
+                return Err(NippyJarError::ColumnLenMismatch(self.columns, columns.len()))
+            }
+
+            debug!(target: "nippy-jar", columns=columns.len(), "Preparing column compression.");
+            for (compression, column) in self.column_compressors.iter_mut().zip(columns) {
+                if let Some(compression) = compression {
+                    compression.prepare_compression(vec![column])?;
+                }
+            }
         }
         Ok(())
     }
@@ -329,9 +427,17 @@ where
         // Special case for zstd that might use custom dictionaries/compressors per column
         // If any other compression algorithm is added and uses a similar flow, then revisit
         // implementation
-        let mut maybe_zstd_compressors = None;
+        let mut zstd_compressors = (0..self.columns).map(|_| None).collect::<Vec<_>>();
         if let Some(Compressors::Zstd(zstd)) = &self.compressor {
-            maybe_zstd_compressors = zstd.compressors()?;
+            if let Some(compressors) = zstd.compressors()? {
+                zstd_compressors = compressors.into_iter().map(Some).collect();
+            }
+        }
+        for (column, compressor) in self.column_compressors.iter().enumerate() {
+            if let Some(Compressors::Zstd(zstd)) = compressor {
+                zstd_compressors[column] =
+                    zstd.compressors()?.and_then(|compressors| compressors.into_iter().next());
+            }
         }
 
         // Temporary buffer to avoid multiple reallocations if compressing to a buffer (eg. zstd w/
@@ -340,11 +446,12 @@ where
 
         // Write all rows while taking all row start offsets
         let mut row_number = 0u64;
+        let mut max_row_size = 0;
         let mut offsets = Vec::with_capacity(total_rows as usize * self.columns);
         let mut column_iterators =
             columns.into_iter().map(|v| v.into_iter()).collect::<Vec<_>>().into_iter();
 
-        debug!(target: "nippy-jar", compressor=?self.compressor, "Writing rows.");
+        debug!(target: "nippy-jar", compressor=?self.compressor, column_compressors=?self.column_compressors, "Writing rows.");
 
         loop {
             let mut iterators = Vec::with_capacity(self.columns);
@@ -359,16 +466,15 @@ where
                     Some(Ok(value)) => {
                         uncompressed_row_size += value.len();
 
-                        if let Some(compression) = &self.compressor {
+                        if let Some((compression, _)) = self.column_compressor(column_number) {
                             // Special zstd case with dictionaries
-                            if let (Some(dict_compressors), Compressors::Zstd(_)) =
-                                (maybe_zstd_compressors.as_mut(), compression)
+                            if let Some(dict_compressor) = zstd_compressors[column_number].as_mut()
                             {
                                 compression::Zstd::compress_with_dictionary(
                                     &value,
                                     &mut tmp_buf,
                                     &mut file,
-                                    Some(dict_compressors.get_mut(column_number).expect("exists")),
+                                    Some(dict_compressor),
                                 )?;
                             } else {
                                 let before = tmp_buf.len();
@@ -393,7 +499,7 @@ where
 
             tmp_buf.clear();
             row_number += 1;
-            self.max_row_size = self.max_row_size.max(uncompressed_row_size);
+            max_row_size = max_row_size.max(uncompressed_row_size);
 
             if row_number == total_rows {
                 break
@@ -403,7 +509,8 @@ where
         }
 
         // drops immutable borrow
-        drop(maybe_zstd_compressors);
+        drop(zstd_compressors);
+        self.max_row_size = self.max_row_size.max(max_row_size);
 
         // Write offsets and offset index to file
         self.freeze_offsets(offsets)?;
@@ -445,7 +552,14 @@ where
             return Err(NippyJarError::ColumnLenMismatch(self.columns, columns.len()))
         }
 
-        if let Some(compression) = &self.compressor {
+        if !self.column_compressors.is_empty() && self.column_compressors.len() != self.columns {
+            return Err(NippyJarError::ColumnLenMismatch(
+                self.columns,
+                self.column_compressors.len(),
+            ))
+        }
+
+        for compression in self.compressor.iter().chain(self.column_compressors.iter().flatten()) {
             if !compression.is_ready() {
                 return Err(NippyJarError::CompressorNotReady)
             }
@@ -469,6 +583,36 @@ where
     }
 }
 
+/// Configuration of a [`NippyJar`] written before per-column compression, which can still be
+/// loaded.
+#[derive(Serialize, Deserialize)]
+struct NippyJarV1<H> {
+    version: usize,
+    user_header: H,
+    columns: usize,
+    compressor: Option<Compressors>,
+    filter: Option<InclusionFilters>,
+    phf: Option<Functions>,
+}
+
+impl<H> From<NippyJarV1<H>> for NippyJar<H> {
+    fn from(jar: NippyJarV1<H>) -> Self {
+        NippyJar {
+            version: jar.version,
+            user_header: jar.user_header,
+            columns: jar.columns,
+            max_row_size: 0,
+            compressor: jar.compressor,
+            column_compressors: Vec::new(),
+            filter: jar.filter,
+            phf: jar.phf,
+            offsets: EliasFano::default(),
+            offsets_index: PrefixSummedEliasFano::default(),
+            path: None,
+        }
+    }
+}
+
 impl<H> InclusionFilter for NippyJar<H>
 where
     H: Send + Sync + Serialize + for<'a> Deserialize<'a>,
@@ -733,6 +877,8 @@ mod tests {
 
         let loaded_nippy = NippyJar::load_without_header(file_path.path()).unwrap();
         assert_eq!(nippy, loaded_nippy);
+        assert_eq!(loaded_nippy.columns(), num_columns);
+        assert_eq!(loaded_nippy.rows(), num_rows as usize);
 
         if let Some(Compressors::Lz4(_)) = loaded_nippy.compressor() {
             let mut cursor = NippyJarCursor::new(&loaded_nippy).unwrap();
@@ -789,6 +935,166 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_column_compression() {
+        let (col1, col2) = test_data(None);
+        let num_rows = col1.len() as u64;
+        let num_columns = 2;
+        let file_path = tempfile::NamedTempFile::new().unwrap();
+
+        // First column is left uncompressed, second one uses a pre-trained dictionary
+        let dictionary = compression::Zstd::train_dictionary(col2.clone(), 5000).unwrap();
+        let zstd = compression::Zstd::new(false, 0, 1).with_dictionaries(vec![dictionary]).unwrap();
+        let mut nippy = NippyJar::new_without_header(num_columns, file_path.path())
+            .with_column_compressors(vec![None, Some(Compressors::Zstd(zstd))]);
+        assert!(nippy.compressor().is_none());
+        assert!(nippy.column_compressor(0).is_none());
+        assert!(matches!(nippy.column_compressor(1), Some((Compressors::Zstd(_), 0))));
+
+        // Pre-trained dictionaries are kept
+        nippy.prepare_compression(vec![col1.clone(), col2.clone()]).unwrap();
+        nippy.freeze(vec![clone_with_result(&col1), clone_with_result(&col2)], num_rows).unwrap();
+
+        let loaded_nippy = NippyJar::load_without_header(file_path.path()).unwrap();
+        assert_eq!(loaded_nippy.column_compressors.len(), num_columns);
+        assert_eq!(
+            loaded_nippy.column_sizes().unwrap()[0],
+            col1.iter().map(Vec::len).sum::<usize>() as u64
+        );
+
+        let mut cursor = NippyJarCursor::new(&loaded_nippy).unwrap();
+        let mut row_index = 0usize;
+        while let Some(row) = cursor.next_row().unwrap() {
+            assert_eq!((row[0], row[1]), (col1[row_index].as_slice(), col2[row_index].as_slice()));
+            row_index += 1;
+        }
+        assert_eq!(row_index, num_rows as usize);
+    }
+
+    #[test]
+    fn test_custom_compression() {
+        #[derive(Debug)]
+        struct Xor(u8);
+
+        impl compression::CustomCompression for Xor {
+            fn name(&self) -> &str {
+                "xor"
+            }
+
+            fn decompress_to(&self, value: &[u8], dest: &mut Vec<u8>) -> Result<(), NippyJarError> {
+                dest.extend(value.iter().map(|byte| byte ^ self.0));
+                Ok(())
+            }
+
+            fn compress_to(&self, src: &[u8], dest: &mut Vec<u8>) -> Result<usize, NippyJarError> {
+                dest.extend(src.iter().map(|byte| byte ^ self.0));
+                Ok(src.len())
+            }
+
+            fn prepare_compression(
+                &mut self,
+                columns: Vec<Vec<Vec<u8>>>,
+            ) -> Result<(), NippyJarError> {
+                self.0 = columns[0][0][0];
+                Ok(())
+            }
+
+            fn state(&self) -> Vec<u8> {
+                vec![self.0]
+            }
+        }
+
+        fn load_xor(
+            state: &[u8],
+        ) -> Result<Box<dyn compression::CustomCompression>, NippyJarError> {
+            Ok(Box::new(Xor(state[0])))
+        }
+
+        let (col1, col2) = test_data(None);
+        let num_rows = col1.len() as u64;
+        let num_columns = 2;
+        let file_path = tempfile::NamedTempFile::new().unwrap();
+
+        let mut nippy = NippyJar::new_without_header(num_columns, file_path.path())
+            .with_column_compressors(vec![Some(Compressors::custom(Xor(0))), None]);
+        nippy.prepare_compression(vec![col1.clone(), col2.clone()]).unwrap();
+        nippy.freeze(vec![clone_with_result(&col1), clone_with_result(&col2)], num_rows).unwrap();
+
+        // Can't be read without restoring the custom compression
+        let loaded_nippy = NippyJar::load_without_header(file_path.path()).unwrap();
+        let mut cursor = NippyJarCursor::new(&loaded_nippy).unwrap();
+        assert!(matches!(
+            cursor.next_row(),
+            Err(NippyJarError::CustomCompressionNotLoaded(name)) if name == "xor"
+        ));
+
+        let loaded_nippy =
+            NippyJar::load_with_custom_compression(file_path.path(), &[("xor", load_xor)]).unwrap();
+        assert_eq!(nippy, loaded_nippy);
+
+        let mut cursor = NippyJarCursor::new(&loaded_nippy).unwrap();
+        let mut row_index = 0usize;
+        while let Some(row) = cursor.next_row().unwrap() {
+            assert_eq!((row[0], row[1]), (col1[row_index].as_slice(), col2[row_index].as_slice()));
+            row_index += 1;
+        }
+        assert_eq!(row_index, num_rows as usize);
+    }
+
+    #[test]
+    fn test_load_v1() {
+        let (col1, col2) = test_data(None);
+        let num_rows = col1.len() as u64;
+        let num_columns = 2;
+        let file_path = tempfile::NamedTempFile::new().unwrap();
+        let v1_file_path = tempfile::NamedTempFile::new().unwrap();
+
+        let mut nippy = NippyJar::new_without_header(num_columns, file_path.path()).with_lz4();
+        nippy.freeze(vec![clone_with_result(&col1), clone_with_result(&col2)], num_rows).unwrap();
+
+        // Rewrites the jar with the configuration of version 1, which shifts the data and offsets
+        let v1_config = bincode::serialize(&NippyJarV1 {
+            version: 1,
+            user_header: (),
+            columns: num_columns,
+            compressor: Some(Compressors::Lz4(compression::Lz4::default())),
+            filter: None,
+            phf: None,
+        })
+        .unwrap();
+        let config_len = bincode::serialized_size(&nippy).unwrap() as usize;
+        let data = std::fs::read(file_path.path()).unwrap();
+        std::fs::write(v1_file_path.path(), [v1_config.as_slice(), &data[config_len..]].concat())
+            .unwrap();
+
+        let shift = config_len - v1_config.len();
+        let offsets = (0..nippy.offsets.len())
+            .map(|position| nippy.offsets.select(position).unwrap() - shift)
+            .collect();
+        nippy.path = Some(v1_file_path.path().to_path_buf());
+        nippy.freeze_offsets(offsets).unwrap();
+
+        let loaded_nippy = NippyJar::load_without_header(v1_file_path.path()).unwrap();
+        assert_eq!(loaded_nippy.version, 1);
+        assert!(loaded_nippy.column_compressors.is_empty());
+        assert!(matches!(loaded_nippy.compressor(), Some(Compressors::Lz4(_))));
+
+        let mut cursor = NippyJarCursor::new(&loaded_nippy).unwrap();
+        let mut row_index = 0usize;
+        while let Some(row) = cursor.next_row().unwrap() {
+            assert_eq!((row[0], row[1]), (col1[row_index].as_slice(), col2[row_index].as_slice()));
+            row_index += 1;
+        }
+        assert_eq!(row_index, num_rows as usize);
+
+        // Unknown versions are rejected
+        std::fs::write(v1_file_path.path(), bincode::serialize(&3usize).unwrap()).unwrap();
+        assert!(matches!(
+            NippyJar::load_without_header(v1_file_path.path()),
+            Err(NippyJarError::UnsupportedVersion(3))
+        ));
+    }
+
     /// Tests NippyJar with everything enabled: compression, filter, offset list and offset index.
     #[test]
     fn test_full_nippy_jar() {
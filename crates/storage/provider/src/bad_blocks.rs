@@ -0,0 +1,282 @@
+//! Bounded in-memory store of recently rejected blocks.
+
+use alloy_rlp::{Decodable, Encodable};
+use parking_lot::RwLock;
+use reth_primitives::{Block, SealedBlock, B256};
+use std::{
+    collections::VecDeque,
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::{debug, warn};
+
+/// The default number of bad blocks kept in the [BadBlockStore].
+pub const DEFAULT_MAX_BAD_BLOCKS: usize = 10;
+
+/// A block that was rejected because it failed validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BadBlock {
+    /// The full sealed block that was rejected.
+    pub block: SealedBlock,
+    /// The validation error the block was rejected with.
+    pub error: String,
+    /// The time the block was rejected at.
+    pub rejected_at: SystemTime,
+}
+
+impl BadBlock {
+    /// Returns the hash of the rejected block.
+    pub fn hash(&self) -> B256 {
+        self.block.hash
+    }
+}
+
+/// A bounded store of the most recently rejected blocks.
+///
+/// The store is cheap to clone and shared between the components that reject blocks (the engine
+/// and the blockchain tree) and the RPC layer that serves them via `debug_getBadBlocks`.
+///
+/// If configured with a dump directory, every newly inserted block is also written to disk as
+/// `<number>_<hash>.rlp`, alongside a `<number>_<hash>.txt` file containing the validation error,
+/// and the blocks stored there are loaded back when the store is configured, e.g. on restart.
+#[derive(Debug, Clone)]
+pub struct BadBlockStore {
+    inner: Arc<RwLock<BadBlockStoreInner>>,
+}
+
+#[derive(Debug)]
+struct BadBlockStoreInner {
+    /// The rejected blocks, most recent first.
+    blocks: VecDeque<BadBlock>,
+    /// The maximum number of blocks to keep.
+    max_blocks: usize,
+    /// Directory rejected blocks are dumped to, if any.
+    dump_dir: Option<PathBuf>,
+}
+
+impl BadBlockStore {
+    /// Creates a new store that keeps at most `max_blocks` blocks.
+    pub fn new(max_blocks: usize) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(BadBlockStoreInner {
+                blocks: VecDeque::with_capacity(max_blocks),
+                max_blocks,
+                dump_dir: None,
+            })),
+        }
+    }
+
+    /// Configures the directory rejected blocks are dumped to, and loads the most recent blocks
+    /// that were already dumped there.
+    pub fn with_dump_dir(self, dump_dir: impl Into<PathBuf>) -> Self {
+        let dump_dir = dump_dir.into();
+        let loaded = match load_bad_blocks(&dump_dir) {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                if err.kind() != io::ErrorKind::NotFound {
+                    warn!(target: "provider::bad_blocks", %err, dir = ?dump_dir, "Failed to load bad blocks");
+                }
+                Vec::new()
+            }
+        };
+
+        {
+            let mut inner = self.inner.write();
+            // loaded blocks were rejected before any block that was inserted since
+            for bad in loaded {
+                if !inner.blocks.iter().any(|known| known.hash() == bad.hash()) {
+                    inner.blocks.push_back(bad);
+                }
+            }
+            let max_blocks = inner.max_blocks;
+            inner.blocks.truncate(max_blocks);
+            inner.dump_dir = Some(dump_dir);
+        }
+        self
+    }
+
+    /// Records a rejected block together with the error it was rejected with.
+    ///
+    /// Blocks that are already in the store are ignored. If the store is full, the oldest block
+    /// is evicted.
+    pub fn insert(&self, block: SealedBlock, error: impl fmt::Display) {
+        let dump = {
+            let mut inner = self.inner.write();
+            if inner.max_blocks == 0 || inner.blocks.iter().any(|bad| bad.hash() == block.hash) {
+                return
+            }
+
+            let bad = BadBlock { block, error: error.to_string(), rejected_at: SystemTime::now() };
+            debug!(target: "provider::bad_blocks", number = bad.block.number, hash = ?bad.hash(), error = %bad.error, "Recording bad block");
+
+            let dump = inner.dump_dir.clone().map(|dir| (dir, bad.clone()));
+            inner.blocks.push_front(bad);
+            let max_blocks = inner.max_blocks;
+            inner.blocks.truncate(max_blocks);
+            dump
+        };
+
+        // written after releasing the lock, so readers aren't blocked on the file system
+        if let Some((dir, bad)) = dump {
+            if let Err(err) = dump_bad_block(&dir, &bad) {
+                warn!(target: "provider::bad_blocks", %err, hash = ?bad.hash(), "Failed to dump bad block");
+            }
+        }
+    }
+
+    /// Returns the rejected blocks, most recent first.
+    pub fn blocks(&self) -> Vec<BadBlock> {
+        self.inner.read().blocks.iter().cloned().collect()
+    }
+
+    /// Returns the number of blocks in the store.
+    pub fn len(&self) -> usize {
+        self.inner.read().blocks.len()
+    }
+
+    /// Returns `true` if the store contains no blocks.
+    pub fn is_empty(&self) -> bool {
+        self.inner.read().blocks.is_empty()
+    }
+}
+
+impl Default for BadBlockStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_BAD_BLOCKS)
+    }
+}
+
+/// Writes the RLP encoded block and its validation error to the given directory.
+fn dump_bad_block(dir: &Path, bad: &BadBlock) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let name = format!("{}_{:?}", bad.block.number, bad.hash());
+
+    let mut rlp = Vec::new();
+    bad.block.clone().unseal().encode(&mut rlp);
+    std::fs::write(dir.join(format!("{name}.rlp")), rlp)?;
+
+    let rejected_at = bad.rejected_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    std::fs::write(
+        dir.join(format!("{name}.txt")),
+        format!("rejected at: {rejected_at}\nerror: {}\n", bad.error),
+    )
+}
+
+/// Loads the blocks dumped to the given directory by [dump_bad_block], most recent first.
+///
+/// Files that can't be read or decoded are skipped.
+fn load_bad_blocks(dir: &Path) -> io::Result<Vec<BadBlock>> {
+    let mut blocks = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().map_or(true, |extension| extension != "rlp") {
+            continue
+        }
+        match load_bad_block(&path) {
+            Ok(bad) => blocks.push(bad),
+            Err(err) => {
+                warn!(target: "provider::bad_blocks", %err, ?path, "Failed to load bad block")
+            }
+        }
+    }
+
+    blocks.sort_unstable_by(|a, b| {
+        b.rejected_at.cmp(&a.rejected_at).then_with(|| b.block.number.cmp(&a.block.number))
+    });
+    Ok(blocks)
+}
+
+/// Loads a single block dumped by [dump_bad_block], given the path of its RLP file.
+fn load_bad_block(path: &Path) -> io::Result<BadBlock> {
+    let rlp = std::fs::read(path)?;
+    let block = Block::decode(&mut rlp.as_slice())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?
+        .seal_slow();
+
+    let txt = std::fs::read_to_string(path.with_extension("txt"))?;
+    let (header, error) = txt.split_once("error: ").unwrap_or((&txt, ""));
+    let rejected_at = header
+        .strip_prefix("rejected at: ")
+        .and_then(|secs| secs.trim().parse().ok())
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+        .unwrap_or(UNIX_EPOCH);
+
+    Ok(BadBlock { block, error: error.trim_end().to_string(), rejected_at })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_interfaces::test_utils::generators::{self, random_block};
+
+    #[test]
+    fn bounded_most_recent_first() {
+        let mut rng = generators::rng();
+        let store = BadBlockStore::new(2);
+
+        let blocks =
+            (0..3).map(|n| random_block(&mut rng, n, None, Some(1), None)).collect::<Vec<_>>();
+        for block in &blocks {
+            store.insert(block.clone(), "invalid");
+        }
+        // duplicates are ignored
+        store.insert(blocks[2].clone(), "invalid");
+
+        let stored = store.blocks();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].block, blocks[2]);
+        assert_eq!(stored[1].block, blocks[1]);
+        assert_eq!(stored[0].error, "invalid");
+    }
+
+    #[test]
+    fn dumps_to_dir() {
+        let mut rng = generators::rng();
+        let dir = tempfile::tempdir().unwrap();
+        let store = BadBlockStore::new(1).with_dump_dir(dir.path());
+
+        let block = random_block(&mut rng, 7, None, Some(1), None);
+        store.insert(block.clone(), "invalid state root");
+
+        let name = format!("7_{:?}", block.hash);
+        let rlp = std::fs::read(dir.path().join(format!("{name}.rlp"))).unwrap();
+        let decoded: reth_primitives::Block =
+            alloy_rlp::Decodable::decode(&mut rlp.as_slice()).unwrap();
+        assert_eq!(decoded.seal_slow(), block);
+
+        let txt = std::fs::read_to_string(dir.path().join(format!("{name}.txt"))).unwrap();
+        assert!(txt.contains("invalid state root"));
+    }
+
+    #[test]
+    fn loads_dumped_blocks() {
+        let mut rng = generators::rng();
+        let dir = tempfile::tempdir().unwrap();
+        let store = BadBlockStore::new(3).with_dump_dir(dir.path());
+        assert!(store.is_empty());
+
+        let blocks =
+            (0..3).map(|n| random_block(&mut rng, n, None, Some(1), None)).collect::<Vec<_>>();
+        for block in &blocks {
+            store.insert(block.clone(), format!("invalid block {}", block.number));
+        }
+
+        // only the most recent blocks are loaded
+        let reloaded = BadBlockStore::new(2).with_dump_dir(dir.path()).blocks();
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(reloaded[0].block, blocks[2]);
+        assert_eq!(reloaded[1].block, blocks[1]);
+        assert_eq!(reloaded[1].error, "invalid block 1");
+
+        // blocks inserted before configuring the directory are more recent
+        let store = BadBlockStore::new(2);
+        store.insert(blocks[0].clone(), "invalid");
+        let reloaded = store.with_dump_dir(dir.path()).blocks();
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(reloaded[0].block, blocks[0]);
+        assert_eq!(reloaded[0].error, "invalid");
+        assert_eq!(reloaded[1].block, blocks[2]);
+    }
+}
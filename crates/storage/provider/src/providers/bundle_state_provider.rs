@@ -95,4 +95,21 @@ impl<SP: StateProvider, BSDP: BundleStateDataProvider> StateProvider
     fn proof(&self, _address: Address, _keys: &[B256]) -> RethResult<AccountProof> {
         Err(ProviderError::StateRootNotAvailableForHistoricalBlock.into())
     }
+
+    fn account_range(
+        &self,
+        _start: Address,
+        _limit: usize,
+    ) -> RethResult<(Vec<(Address, reth_primitives::Account)>, Option<Address>)> {
+        Err(ProviderError::UnsupportedProvider.into())
+    }
+
+    fn storage_range(
+        &self,
+        _address: Address,
+        _start: reth_primitives::StorageKey,
+        _limit: usize,
+    ) -> RethResult<(Vec<reth_primitives::StorageEntry>, Option<reth_primitives::StorageKey>)> {
+        Err(ProviderError::UnsupportedProvider.into())
+    }
 }
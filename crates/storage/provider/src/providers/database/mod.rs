@@ -1,13 +1,17 @@
 use crate::{
-    providers::state::{historical::HistoricalStateProvider, latest::LatestStateProvider},
+    providers::{
+        state::{historical::HistoricalStateProvider, latest::LatestStateProvider},
+        SnapSyncedStateCache, SnapshotProvider,
+    },
     traits::{BlockSource, ReceiptProvider},
     BlockHashReader, BlockNumReader, BlockReader, ChainSpecProvider, EvmEnvProvider,
-    HeaderProvider, ProviderError, PruneCheckpointReader, StageCheckpointReader, StateProviderBox,
-    TransactionVariant, TransactionsProvider, WithdrawalsProvider,
+    HeaderProvider, HistoryReader, ProviderError, PruneCheckpointReader, StageCheckpointReader,
+    StateProviderBox, TransactionVariant, TransactionsProvider, WithdrawalsProvider,
 };
 use reth_db::{database::Database, init_db, models::StoredBlockBodyIndices, DatabaseEnv};
 use reth_interfaces::{db::LogLevel, RethError, RethResult};
 use reth_primitives::{
+    snapshot::HighestSnapshots,
     stage::{StageCheckpoint, StageId},
     Address, Block, BlockHash, BlockHashOrNumber, BlockNumber, BlockWithSenders, ChainInfo,
     ChainSpec, Header, PruneCheckpoint, PruneSegment, Receipt, SealedBlock, SealedHeader,
@@ -17,8 +21,10 @@ use reth_primitives::{
 use revm::primitives::{BlockEnv, CfgEnv};
 use std::{
     ops::{RangeBounds, RangeInclusive},
+    path::Path,
     sync::Arc,
 };
+use tokio::sync::watch;
 use tracing::trace;
 
 mod metrics;
@@ -35,6 +41,10 @@ pub struct ProviderFactory<DB> {
     db: DB,
     /// Chain spec
     chain_spec: Arc<ChainSpec>,
+    /// Snapshot provider
+    snapshot_provider: Option<Arc<SnapshotProvider>>,
+    /// The state below the plain state, if the state was synced via snap sync.
+    snap_synced_state: SnapSyncedStateCache,
 }
 
 impl<DB: Database> ProviderFactory<DB> {
@@ -42,7 +52,13 @@ impl<DB: Database> ProviderFactory<DB> {
     /// database using different types of providers. Example: [`HeaderProvider`]
     /// [`BlockHashReader`]. This may fail if the inner read database transaction fails to open.
     pub fn provider(&self) -> RethResult<DatabaseProviderRO<'_, DB>> {
-        Ok(DatabaseProvider::new(self.db.tx()?, self.chain_spec.clone()))
+        let mut provider = DatabaseProvider::new(self.db.tx()?, self.chain_spec.clone());
+
+        if let Some(snapshot_provider) = &self.snapshot_provider {
+            provider = provider.with_snapshot_provider(snapshot_provider.clone());
+        }
+
+        Ok(provider)
     }
 
     /// Returns a provider with a created `DbTxMut` inside, which allows fetching and updating
@@ -50,14 +66,36 @@ impl<DB: Database> ProviderFactory<DB> {
     /// [`BlockHashReader`].  This may fail if the inner read/write database transaction fails to
     /// open.
     pub fn provider_rw(&self) -> RethResult<DatabaseProviderRW<'_, DB>> {
-        Ok(DatabaseProviderRW(DatabaseProvider::new_rw(self.db.tx_mut()?, self.chain_spec.clone())))
+        let mut provider = DatabaseProvider::new_rw(self.db.tx_mut()?, self.chain_spec.clone());
+
+        if let Some(snapshot_provider) = &self.snapshot_provider {
+            provider = provider.with_snapshot_provider(snapshot_provider.clone());
+        }
+
+        Ok(DatabaseProviderRW(provider))
     }
 }
 
 impl<DB> ProviderFactory<DB> {
     /// create new database provider
     pub fn new(db: DB, chain_spec: Arc<ChainSpec>) -> Self {
-        Self { db, chain_spec }
+        Self { db, chain_spec, snapshot_provider: None, snap_synced_state: Default::default() }
+    }
+
+    /// Serves the headers, transactions and receipts that are moved out of the database from the
+    /// snapshots located in the given directory.
+    ///
+    /// The highest snapshot tracker is used to pick up the snapshots produced while the node is
+    /// running.
+    pub fn with_snapshots(
+        mut self,
+        snapshots_path: impl AsRef<Path>,
+        highest_snapshot_tracker: Option<watch::Receiver<Option<HighestSnapshots>>>,
+    ) -> RethResult<Self> {
+        self.snapshot_provider = Some(Arc::new(
+            SnapshotProvider::new(snapshots_path)?.with_highest_tracker(highest_snapshot_tracker),
+        ));
+        Ok(self)
     }
 }
 
@@ -72,13 +110,20 @@ impl<DB: Database> ProviderFactory<DB> {
         Ok(ProviderFactory::<DatabaseEnv> {
             db: init_db(path, log_level).map_err(|e| RethError::Custom(e.to_string()))?,
             chain_spec,
+            snapshot_provider: None,
+            snap_synced_state: Default::default(),
         })
     }
 }
 
 impl<DB: Clone> Clone for ProviderFactory<DB> {
     fn clone(&self) -> Self {
-        Self { db: self.db.clone(), chain_spec: Arc::clone(&self.chain_spec) }
+        Self {
+            db: self.db.clone(),
+            chain_spec: Arc::clone(&self.chain_spec),
+            snapshot_provider: self.snapshot_provider.clone(),
+            snap_synced_state: self.snap_synced_state.clone(),
+        }
     }
 }
 
@@ -86,7 +131,9 @@ impl<DB: Database> ProviderFactory<DB> {
     /// Storage provider for latest block
     pub fn latest(&self) -> RethResult<StateProviderBox<'_>> {
         trace!(target: "providers::db", "Returning latest state provider");
-        Ok(Box::new(LatestStateProvider::new(self.db.tx()?)))
+        let tx = self.db.tx()?;
+        let snap_synced_state = self.snap_synced_state.load(&tx)?;
+        Ok(Box::new(LatestStateProvider::new(tx).with_snap_synced_state(snap_synced_state)))
     }
 
     /// Storage provider for state at that given block
@@ -95,11 +142,15 @@ impl<DB: Database> ProviderFactory<DB> {
         mut block_number: BlockNumber,
     ) -> RethResult<StateProviderBox<'_>> {
         let provider = self.provider()?;
+        let snap_synced_state = self.snap_synced_state.load(provider.tx_ref())?;
 
         if block_number == provider.best_block_number().unwrap_or_default() &&
             block_number == provider.last_block_number().unwrap_or_default()
         {
-            return Ok(Box::new(LatestStateProvider::new(provider.into_tx())))
+            return Ok(Box::new(
+                LatestStateProvider::new(provider.into_tx())
+                    .with_snap_synced_state(snap_synced_state),
+            ))
         }
 
         // +1 as the changeset that we want is the one that was applied after this block.
@@ -110,7 +161,12 @@ impl<DB: Database> ProviderFactory<DB> {
         let storage_history_prune_checkpoint =
             provider.get_prune_checkpoint(PruneSegment::StorageHistory)?;
 
-        let mut state_provider = HistoricalStateProvider::new(provider.into_tx(), block_number);
+        let mut state_provider = HistoricalStateProvider::new(provider.into_tx(), block_number)
+            .with_snap_synced_state(snap_synced_state);
+
+        if let Some(snapshot_provider) = &self.snapshot_provider {
+            state_provider = state_provider.with_snapshot_provider(snapshot_provider.clone());
+        }
 
         // If we pruned account or storage history, we can't return state on every historical block.
         // Instead, we should cap it at the latest prune checkpoint for corresponding prune segment.
@@ -415,10 +471,43 @@ impl<DB: Database> PruneCheckpointReader for ProviderFactory<DB> {
     }
 }
 
+impl<DB: Database> HistoryReader for ProviderFactory<DB> {
+    fn account_history_blocks(
+        &self,
+        address: Address,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>> {
+        self.provider()?.account_history_blocks(address, range)
+    }
+
+    fn storage_history_blocks(
+        &self,
+        address: Address,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>> {
+        self.provider()?.storage_history_blocks(address, range)
+    }
+
+    fn trace_address_index_tip(&self) -> RethResult<Option<BlockNumber>> {
+        self.provider()?.trace_address_index_tip()
+    }
+
+    fn trace_address_blocks(
+        &self,
+        address: Address,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>> {
+        self.provider()?.trace_address_blocks(address, range)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::ProviderFactory;
-    use crate::{BlockHashReader, BlockNumReader, BlockWriter, TransactionsProvider};
+    use crate::{
+        BlockHashReader, BlockNumReader, BlockWriter, HistoryReader, HistoryWriter,
+        TransactionsProvider,
+    };
     use alloy_rlp::Decodable;
     use assert_matches::assert_matches;
     use reth_db::{
@@ -428,9 +517,10 @@ mod tests {
     };
     use reth_interfaces::test_utils::{generators, generators::random_block};
     use reth_primitives::{
-        hex_literal::hex, ChainSpecBuilder, PruneMode, PruneModes, SealedBlock, TxNumber, B256,
+        hex_literal::hex, Address, ChainSpecBuilder, PruneMode, PruneModes, SealedBlock, TxNumber,
+        B256,
     };
-    use std::{ops::RangeInclusive, sync::Arc};
+    use std::{collections::BTreeMap, ops::RangeInclusive, sync::Arc};
 
     #[test]
     fn common_history_provider() {
@@ -559,4 +649,40 @@ mod tests {
             )
         }
     }
+
+    #[test]
+    fn history_blocks_in_range() {
+        let chain_spec = ChainSpecBuilder::mainnet().build();
+        let db = create_test_rw_db();
+        let factory = ProviderFactory::new(db, Arc::new(chain_spec));
+
+        let address = Address::with_last_byte(1);
+        let other = Address::with_last_byte(2);
+        {
+            let provider = factory.provider_rw().unwrap();
+            provider
+                .insert_account_history_index(BTreeMap::from([
+                    (address, vec![1, 5, 10, 20]),
+                    (other, vec![2, 6]),
+                ]))
+                .unwrap();
+            provider
+                .insert_storage_history_index(BTreeMap::from([
+                    ((address, B256::with_last_byte(1)), vec![3, 10]),
+                    ((address, B256::with_last_byte(2)), vec![10, 15]),
+                    ((other, B256::with_last_byte(1)), vec![4]),
+                ]))
+                .unwrap();
+            provider.commit().unwrap();
+        }
+
+        assert_eq!(factory.account_history_blocks(address, 0..=u64::MAX), Ok(vec![1, 5, 10, 20]));
+        assert_eq!(factory.account_history_blocks(address, 5..=10), Ok(vec![5, 10]));
+        assert_eq!(factory.account_history_blocks(address, 21..=30), Ok(vec![]));
+        assert_eq!(factory.account_history_blocks(other, 0..=5), Ok(vec![2]));
+
+        assert_eq!(factory.storage_history_blocks(address, 0..=u64::MAX), Ok(vec![3, 10, 15]));
+        assert_eq!(factory.storage_history_blocks(address, 4..=14), Ok(vec![10]));
+        assert_eq!(factory.storage_history_blocks(other, 0..=u64::MAX), Ok(vec![4]));
+    }
 }
@@ -1,19 +1,22 @@
 use crate::{
     bundle_state::{BundleStateInit, BundleStateWithReceipts, RevertsInit},
-    providers::database::metrics,
+    providers::{
+        database::metrics,
+        snapshot::{to_range, SnapshotJarProvider, SnapshotProvider},
+    },
     traits::{
         AccountExtReader, BlockSource, ChangeSetReader, ReceiptProvider, StageCheckpointWriter,
     },
     AccountReader, BlockExecutionWriter, BlockHashReader, BlockNumReader, BlockReader, BlockWriter,
-    Chain, EvmEnvProvider, HashingWriter, HeaderProvider, HistoryWriter, OriginalValuesKnown,
-    ProviderError, PruneCheckpointReader, PruneCheckpointWriter, StageCheckpointReader,
-    StorageReader, TransactionVariant, TransactionsProvider, TransactionsProviderExt,
-    WithdrawalsProvider,
+    Chain, EvmEnvProvider, HashingWriter, HeaderProvider, HistoryReader, HistoryWriter,
+    OriginalValuesKnown, ProviderError, PruneCheckpointReader, PruneCheckpointWriter,
+    StageCheckpointReader, StorageReader, TransactionVariant, TransactionsProvider,
+    TransactionsProviderExt, WithdrawalsProvider,
 };
 use itertools::{izip, Itertools};
 use reth_db::{
     common::KeyValue,
-    cursor::{DbCursorRO, DbCursorRW, DbDupCursorRO},
+    cursor::{DbCursorRO, DbCursorRW, DbDupCursorRO, DbDupCursorRW},
     database::{Database, DatabaseGAT},
     models::{
         sharded_key, storage_sharded_key::StorageShardedKey, AccountBeforeTx, BlockNumberAddress,
@@ -39,9 +42,9 @@ use reth_primitives::{
     trie::Nibbles,
     Account, Address, Block, BlockHash, BlockHashOrNumber, BlockNumber, BlockWithSenders,
     ChainInfo, ChainSpec, GotExpected, Hardfork, Head, Header, PruneCheckpoint, PruneModes,
-    PruneSegment, Receipt, SealedBlock, SealedBlockWithSenders, SealedHeader, StorageEntry,
-    TransactionMeta, TransactionSigned, TransactionSignedEcRecovered, TransactionSignedNoHash,
-    TxHash, TxNumber, Withdrawal, B256, U256,
+    PruneSegment, Receipt, SealedBlock, SealedBlockWithSenders, SealedHeader, SnapshotSegment,
+    StorageEntry, TransactionMeta, TransactionSigned, TransactionSignedEcRecovered,
+    TransactionSignedNoHash, TxHash, TxNumber, Withdrawal, B256, U256,
 };
 use reth_trie::{prefix_set::PrefixSetMut, StateRoot};
 use revm::primitives::{BlockEnv, CfgEnv, SpecId};
@@ -100,12 +103,14 @@ pub struct DatabaseProvider<TX> {
     tx: TX,
     /// Chain spec
     chain_spec: Arc<ChainSpec>,
+    /// Snapshot provider serving the data that was moved out of the database.
+    snapshot_provider: Option<Arc<SnapshotProvider>>,
 }
 
 impl<TX: DbTxMut> DatabaseProvider<TX> {
     /// Creates a provider with an inner read-write transaction.
     pub fn new_rw(tx: TX, chain_spec: Arc<ChainSpec>) -> Self {
-        Self { tx, chain_spec }
+        Self { tx, chain_spec, snapshot_provider: None }
     }
 }
 
@@ -160,7 +165,19 @@ where
 impl<TX: DbTx> DatabaseProvider<TX> {
     /// Creates a provider with an inner read-only transaction.
     pub fn new(tx: TX, chain_spec: Arc<ChainSpec>) -> Self {
-        Self { tx, chain_spec }
+        Self { tx, chain_spec, snapshot_provider: None }
+    }
+
+    /// Sets the snapshot provider. Headers, transactions and receipts that are snapshotted are
+    /// read from the snapshots instead of the database.
+    pub fn with_snapshot_provider(mut self, snapshot_provider: Arc<SnapshotProvider>) -> Self {
+        self.snapshot_provider = Some(snapshot_provider);
+        self
+    }
+
+    /// Returns the snapshot provider, if set.
+    pub fn snapshot_provider(&self) -> Option<&Arc<SnapshotProvider>> {
+        self.snapshot_provider.as_ref()
     }
 
     /// Consume `DbTx` or `DbTxMut`.
@@ -188,6 +205,59 @@ impl<TX: DbTx> DatabaseProvider<TX> {
             .walk(Some(T::Key::default()))?
             .collect::<Result<Vec<_>, DatabaseError>>()
     }
+
+    /// Fetches the data of the given row number (block or transaction, depending on the segment)
+    /// from the snapshot that contains it, or from the database if it's not snapshotted.
+    fn get_with_snapshot<T>(
+        &self,
+        segment: SnapshotSegment,
+        number: u64,
+        fetch_from_snapshot: impl FnOnce(SnapshotJarProvider<'_>) -> RethResult<Option<T>>,
+        fetch_from_database: impl FnOnce() -> RethResult<Option<T>>,
+    ) -> RethResult<Option<T>> {
+        if let Some(snapshot_provider) = &self.snapshot_provider {
+            if let Some(jar) = snapshot_provider.get_segment_provider_for_row(segment, number)? {
+                return fetch_from_snapshot(jar)
+            }
+        }
+        fetch_from_database()
+    }
+
+    /// Fetches the data of the given range of row numbers (blocks or transactions, depending on
+    /// the segment).
+    ///
+    /// The range is split at the snapshot boundaries: the snapshotted part is read from the
+    /// snapshots that contain it, and the rest from the database.
+    fn get_range_with_snapshot<T>(
+        &self,
+        segment: SnapshotSegment,
+        range: impl RangeBounds<u64>,
+        fetch_from_snapshot: impl Fn(&SnapshotJarProvider<'_>, Range<u64>) -> RethResult<Vec<T>>,
+        fetch_from_database: impl FnOnce(Range<u64>) -> RethResult<Vec<T>>,
+    ) -> RethResult<Vec<T>> {
+        let mut range = to_range(range);
+        let mut data = Vec::new();
+
+        if let Some(snapshot_provider) = &self.snapshot_provider {
+            while !range.is_empty() {
+                let Some(jar) =
+                    snapshot_provider.get_segment_provider_for_row(segment, range.start)?
+                else {
+                    break
+                };
+
+                let end = range.end.min(jar.user_header().end() + 1);
+                data.extend(fetch_from_snapshot(&jar, range.start..end)?);
+                range.start = end;
+            }
+        }
+
+        if !range.is_empty() {
+            data.extend(fetch_from_database(range)?);
+        }
+
+        Ok(data)
+    }
 }
 
 impl<TX: DbTxMut + DbTx> DatabaseProvider<TX> {
@@ -851,15 +921,24 @@ impl<TX: DbTx> ChangeSetReader for DatabaseProvider<TX> {
         &self,
         block_number: BlockNumber,
     ) -> RethResult<Vec<AccountBeforeTx>> {
-        let range = block_number..=block_number;
-        self.tx
-            .cursor_read::<tables::AccountChangeSet>()?
-            .walk_range(range)?
-            .map(|result| -> RethResult<_> {
-                let (_, account_before) = result?;
-                Ok(account_before)
-            })
-            .collect()
+        let changeset = self.get_with_snapshot(
+            SnapshotSegment::AccountChangeSets,
+            block_number,
+            |snapshot| snapshot.account_changeset(block_number).map(Some),
+            || {
+                let range = block_number..=block_number;
+                self.tx
+                    .cursor_read::<tables::AccountChangeSet>()?
+                    .walk_range(range)?
+                    .map(|result| -> RethResult<_> {
+                        let (_, account_before) = result?;
+                        Ok(account_before)
+                    })
+                    .collect::<RethResult<_>>()
+                    .map(Some)
+            },
+        )?;
+        Ok(changeset.unwrap_or_default())
     }
 }
 
@@ -873,7 +952,12 @@ impl<TX: DbTx> HeaderProvider for DatabaseProvider<TX> {
     }
 
     fn header_by_number(&self, num: BlockNumber) -> RethResult<Option<Header>> {
-        Ok(self.tx.get::<tables::Headers>(num)?)
+        self.get_with_snapshot(
+            SnapshotSegment::Headers,
+            num,
+            |snapshot| snapshot.header_by_number(num),
+            || Ok(self.tx.get::<tables::Headers>(num)?),
+        )
     }
 
     fn header_td(&self, block_hash: &BlockHash) -> RethResult<Option<U256>> {
@@ -891,30 +975,49 @@ impl<TX: DbTx> HeaderProvider for DatabaseProvider<TX> {
             return Ok(Some(td))
         }
 
-        Ok(self.tx.get::<tables::HeaderTD>(number)?.map(|td| td.0))
+        self.get_with_snapshot(
+            SnapshotSegment::Headers,
+            number,
+            |snapshot| snapshot.header_td_by_number(number),
+            || Ok(self.tx.get::<tables::HeaderTD>(number)?.map(|td| td.0)),
+        )
     }
 
     fn headers_range(&self, range: impl RangeBounds<BlockNumber>) -> RethResult<Vec<Header>> {
-        let mut cursor = self.tx.cursor_read::<tables::Headers>()?;
-        cursor
-            .walk_range(range)?
-            .map(|result| result.map(|(_, header)| header).map_err(Into::into))
-            .collect::<RethResult<Vec<_>>>()
+        self.get_range_with_snapshot(
+            SnapshotSegment::Headers,
+            range,
+            |snapshot, range| snapshot.headers_range(range),
+            |range| {
+                let mut cursor = self.tx.cursor_read::<tables::Headers>()?;
+                cursor
+                    .walk_range(range)?
+                    .map(|result| result.map(|(_, header)| header).map_err(Into::into))
+                    .collect::<RethResult<Vec<_>>>()
+            },
+        )
     }
 
     fn sealed_headers_range(
         &self,
         range: impl RangeBounds<BlockNumber>,
     ) -> RethResult<Vec<SealedHeader>> {
-        let mut headers = vec![];
-        for entry in self.tx.cursor_read::<tables::Headers>()?.walk_range(range)? {
-            let (number, header) = entry?;
-            let hash = self
-                .block_hash(number)?
-                .ok_or_else(|| ProviderError::HeaderNotFound(number.into()))?;
-            headers.push(header.seal(hash));
-        }
-        Ok(headers)
+        self.get_range_with_snapshot(
+            SnapshotSegment::Headers,
+            range,
+            |snapshot, range| snapshot.sealed_headers_range(range),
+            |range| {
+                let mut headers = vec![];
+                for entry in self.tx.cursor_read::<tables::Headers>()?.walk_range(range)? {
+                    let (number, header) = entry?;
+                    let hash = self
+                        .block_hash(number)?
+                        .ok_or_else(|| ProviderError::HeaderNotFound(number.into()))?;
+                    headers.push(header.seal(hash));
+                }
+                Ok(headers)
+            },
+        )
     }
 
     fn sealed_header(&self, number: BlockNumber) -> RethResult<Option<SealedHeader>> {
@@ -931,7 +1034,12 @@ impl<TX: DbTx> HeaderProvider for DatabaseProvider<TX> {
 
 impl<TX: DbTx> BlockHashReader for DatabaseProvider<TX> {
     fn block_hash(&self, number: u64) -> RethResult<Option<B256>> {
-        Ok(self.tx.get::<tables::CanonicalHeaders>(number)?)
+        self.get_with_snapshot(
+            SnapshotSegment::Headers,
+            number,
+            |snapshot| snapshot.block_hash(number),
+            || Ok(self.tx.get::<tables::CanonicalHeaders>(number)?),
+        )
     }
 
     fn canonical_hashes_range(
@@ -939,12 +1047,18 @@ impl<TX: DbTx> BlockHashReader for DatabaseProvider<TX> {
         start: BlockNumber,
         end: BlockNumber,
     ) -> RethResult<Vec<B256>> {
-        let range = start..end;
-        let mut cursor = self.tx.cursor_read::<tables::CanonicalHeaders>()?;
-        cursor
-            .walk_range(range)?
-            .map(|result| result.map(|(_, hash)| hash).map_err(Into::into))
-            .collect::<RethResult<Vec<_>>>()
+        self.get_range_with_snapshot(
+            SnapshotSegment::Headers,
+            start..end,
+            |snapshot, range| snapshot.canonical_hashes_range(range.start, range.end),
+            |range| {
+                let mut cursor = self.tx.cursor_read::<tables::CanonicalHeaders>()?;
+                cursor
+                    .walk_range(range)?
+                    .map(|result| result.map(|(_, hash)| hash).map_err(Into::into))
+                    .collect::<RethResult<Vec<_>>>()
+            },
+        )
     }
 }
 
@@ -1098,51 +1212,46 @@ impl<TX: DbTx> BlockReader for DatabaseProvider<TX> {
         let len = range.end().saturating_sub(*range.start()) as usize;
         let mut blocks = Vec::with_capacity(len);
 
-        let mut headers_cursor = self.tx.cursor_read::<tables::Headers>()?;
+        let headers = self.headers_range(range)?;
         let mut ommers_cursor = self.tx.cursor_read::<tables::BlockOmmers>()?;
         let mut withdrawals_cursor = self.tx.cursor_read::<tables::BlockWithdrawals>()?;
         let mut block_body_cursor = self.tx.cursor_read::<tables::BlockBodyIndices>()?;
-        let mut tx_cursor = self.tx.cursor_read::<tables::Transactions>()?;
 
-        for num in range {
-            if let Some((_, header)) = headers_cursor.seek_exact(num)? {
-                // If the body indices are not found, this means that the transactions either do
-                // not exist in the database yet, or they do exit but are
-                // not indexed. If they exist but are not indexed, we don't
-                // have enough information to return the block anyways, so
-                // we skip the block.
-                if let Some((_, block_body_indices)) = block_body_cursor.seek_exact(num)? {
-                    let tx_range = block_body_indices.tx_num_range();
-                    let body = if tx_range.is_empty() {
-                        Vec::new()
-                    } else {
-                        tx_cursor
-                            .walk_range(tx_range)?
-                            .map(|result| result.map(|(_, tx)| tx.into()))
-                            .collect::<Result<Vec<_>, _>>()?
-                    };
+        for header in headers {
+            let num = header.number;
+            // If the body indices are not found, this means that the transactions either do
+            // not exist in the database yet, or they do exit but are
+            // not indexed. If they exist but are not indexed, we don't
+            // have enough information to return the block anyways, so
+            // we skip the block.
+            if let Some((_, block_body_indices)) = block_body_cursor.seek_exact(num)? {
+                let tx_range = block_body_indices.tx_num_range();
+                let body = if tx_range.is_empty() {
+                    Vec::new()
+                } else {
+                    self.transactions_by_tx_range(tx_range)?.into_iter().map(Into::into).collect()
+                };
 
-                    // If we are past shanghai, then all blocks should have a withdrawal list,
-                    // even if empty
-                    let withdrawals =
-                        if self.chain_spec.is_shanghai_active_at_timestamp(header.timestamp) {
-                            Some(
-                                withdrawals_cursor
-                                    .seek_exact(num)?
-                                    .map(|(_, w)| w.withdrawals)
-                                    .unwrap_or_default(),
-                            )
-                        } else {
-                            None
-                        };
-                    let ommers = if self.chain_spec.final_paris_total_difficulty(num).is_some() {
-                        Vec::new()
+                // If we are past shanghai, then all blocks should have a withdrawal list,
+                // even if empty
+                let withdrawals =
+                    if self.chain_spec.is_shanghai_active_at_timestamp(header.timestamp) {
+                        Some(
+                            withdrawals_cursor
+                                .seek_exact(num)?
+                                .map(|(_, w)| w.withdrawals)
+                                .unwrap_or_default(),
+                        )
                     } else {
-                        ommers_cursor.seek_exact(num)?.map(|(_, o)| o.ommers).unwrap_or_default()
+                        None
                     };
+                let ommers = if self.chain_spec.final_paris_total_difficulty(num).is_some() {
+                    Vec::new()
+                } else {
+                    ommers_cursor.seek_exact(num)?.map(|(_, o)| o.ommers).unwrap_or_default()
+                };
 
-                    blocks.push(Block { header, body, ommers, withdrawals });
-                }
+                blocks.push(Block { header, body, ommers, withdrawals });
             }
         }
         Ok(blocks)
@@ -1214,14 +1323,19 @@ impl<TX: DbTx> TransactionsProvider for DatabaseProvider<TX> {
     }
 
     fn transaction_by_id(&self, id: TxNumber) -> RethResult<Option<TransactionSigned>> {
-        Ok(self.tx.get::<tables::Transactions>(id)?.map(Into::into))
+        Ok(self.transaction_by_id_no_hash(id)?.map(Into::into))
     }
 
     fn transaction_by_id_no_hash(
         &self,
         id: TxNumber,
     ) -> RethResult<Option<TransactionSignedNoHash>> {
-        Ok(self.tx.get::<tables::Transactions>(id)?)
+        self.get_with_snapshot(
+            SnapshotSegment::Transactions,
+            id,
+            |snapshot| snapshot.transaction_by_id_no_hash(id),
+            || Ok(self.tx.get::<tables::Transactions>(id)?),
+        )
     }
 
     fn transaction_by_hash(&self, hash: TxHash) -> RethResult<Option<TransactionSigned>> {
@@ -1289,17 +1403,17 @@ impl<TX: DbTx> TransactionsProvider for DatabaseProvider<TX> {
         &self,
         id: BlockHashOrNumber,
     ) -> RethResult<Option<Vec<TransactionSigned>>> {
-        let mut tx_cursor = self.tx.cursor_read::<tables::Transactions>()?;
         if let Some(block_number) = self.convert_hash_or_number(id)? {
             if let Some(body) = self.block_body_indices(block_number)? {
                 let tx_range = body.tx_num_range();
                 return if tx_range.is_empty() {
                     Ok(Some(Vec::new()))
                 } else {
-                    let transactions = tx_cursor
-                        .walk_range(tx_range)?
-                        .map(|result| result.map(|(_, tx)| tx.into()))
-                        .collect::<Result<Vec<_>, _>>()?;
+                    let transactions = self
+                        .transactions_by_tx_range(tx_range)?
+                        .into_iter()
+                        .map(Into::into)
+                        .collect();
                     Ok(Some(transactions))
                 }
             }
@@ -1313,7 +1427,6 @@ impl<TX: DbTx> TransactionsProvider for DatabaseProvider<TX> {
     ) -> RethResult<Vec<Vec<TransactionSigned>>> {
         let mut results = Vec::new();
         let mut body_cursor = self.tx.cursor_read::<tables::BlockBodyIndices>()?;
-        let mut tx_cursor = self.tx.cursor_read::<tables::Transactions>()?;
         for entry in body_cursor.walk_range(range)? {
             let (_, body) = entry?;
             let tx_num_range = body.tx_num_range();
@@ -1321,10 +1434,10 @@ impl<TX: DbTx> TransactionsProvider for DatabaseProvider<TX> {
                 results.push(Vec::new());
             } else {
                 results.push(
-                    tx_cursor
-                        .walk_range(tx_num_range)?
-                        .map(|result| result.map(|(_, tx)| tx.into()))
-                        .collect::<Result<Vec<_>, _>>()?,
+                    self.transactions_by_tx_range(tx_num_range)?
+                        .into_iter()
+                        .map(Into::into)
+                        .collect(),
                 );
             }
         }
@@ -1335,31 +1448,75 @@ impl<TX: DbTx> TransactionsProvider for DatabaseProvider<TX> {
         &self,
         range: impl RangeBounds<TxNumber>,
     ) -> RethResult<Vec<TransactionSignedNoHash>> {
-        Ok(self
-            .tx
-            .cursor_read::<tables::Transactions>()?
-            .walk_range(range)?
-            .map(|entry| entry.map(|tx| tx.1))
-            .collect::<Result<Vec<_>, _>>()?)
+        self.get_range_with_snapshot(
+            SnapshotSegment::Transactions,
+            range,
+            |snapshot, range| snapshot.transactions_by_tx_range(range),
+            |range| {
+                Ok(self
+                    .tx
+                    .cursor_read::<tables::Transactions>()?
+                    .walk_range(range)?
+                    .map(|entry| entry.map(|tx| tx.1))
+                    .collect::<Result<Vec<_>, _>>()?)
+            },
+        )
     }
 
     fn senders_by_tx_range(&self, range: impl RangeBounds<TxNumber>) -> RethResult<Vec<Address>> {
-        Ok(self
+        let range = to_range(range);
+        let senders = self
             .tx
             .cursor_read::<tables::TxSenders>()?
-            .walk_range(range)?
+            .walk_range(range.clone())?
             .map(|entry| entry.map(|sender| sender.1))
-            .collect::<Result<Vec<_>, _>>()?)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Senders are not snapshotted, but they might be pruned from the database. In that case,
+        // recover them from the snapshotted transactions.
+        if self.snapshot_provider.is_none() || senders.len() as u64 == range.end - range.start {
+            return Ok(senders)
+        }
+
+        self.get_range_with_snapshot(
+            SnapshotSegment::Transactions,
+            range,
+            |snapshot, range| snapshot.senders_by_tx_range(range),
+            |range| {
+                Ok(self
+                    .tx
+                    .cursor_read::<tables::TxSenders>()?
+                    .walk_range(range)?
+                    .map(|entry| entry.map(|sender| sender.1))
+                    .collect::<Result<Vec<_>, _>>()?)
+            },
+        )
     }
 
     fn transaction_sender(&self, id: TxNumber) -> RethResult<Option<Address>> {
-        Ok(self.tx.get::<tables::TxSenders>(id)?)
+        if let Some(sender) = self.tx.get::<tables::TxSenders>(id)? {
+            return Ok(Some(sender))
+        }
+
+        // Senders are not snapshotted, but they might be pruned from the database. In that case,
+        // recover it from the snapshotted transaction.
+        self.get_with_snapshot(
+            SnapshotSegment::Transactions,
+            id,
+            |snapshot| snapshot.transaction_sender(id),
+            || Ok(None),
+        )
     }
 }
 
 impl<TX: DbTx> ReceiptProvider for DatabaseProvider<TX> {
     fn receipt(&self, id: TxNumber) -> RethResult<Option<Receipt>> {
-        Ok(self.tx.get::<tables::Receipts>(id)?)
+        self.get_with_snapshot(
+            SnapshotSegment::Receipts,
+            id,
+            |snapshot| snapshot.receipt(id),
+            || Ok(self.tx.get::<tables::Receipts>(id)?),
+        )
     }
 
     fn receipt_by_hash(&self, hash: TxHash) -> RethResult<Option<Receipt>> {
@@ -1377,11 +1534,18 @@ impl<TX: DbTx> ReceiptProvider for DatabaseProvider<TX> {
                 return if tx_range.is_empty() {
                     Ok(Some(Vec::new()))
                 } else {
-                    let mut receipts_cursor = self.tx.cursor_read::<tables::Receipts>()?;
-                    let receipts = receipts_cursor
-                        .walk_range(tx_range)?
-                        .map(|result| result.map(|(_, receipt)| receipt))
-                        .collect::<Result<Vec<_>, _>>()?;
+                    let receipts = self.get_range_with_snapshot(
+                        SnapshotSegment::Receipts,
+                        tx_range,
+                        |snapshot, range| snapshot.receipts_by_tx_range(range),
+                        |range| {
+                            let mut receipts_cursor = self.tx.cursor_read::<tables::Receipts>()?;
+                            Ok(receipts_cursor
+                                .walk_range(range)?
+                                .map(|result| result.map(|(_, receipt)| receipt))
+                                .collect::<Result<Vec<_>, _>>()?)
+                        },
+                    )?;
                     Ok(Some(receipts))
                 }
             }
@@ -1838,6 +2002,97 @@ impl<TX: DbTxMut + DbTx> HashingWriter for DatabaseProvider<TX> {
     }
 }
 
+impl<TX: DbTx> DatabaseProvider<TX> {
+    /// Returns all block numbers in the given range that are indexed for the address in a history
+    /// table sharded by address, such as [tables::AccountHistory].
+    ///
+    /// The block numbers are returned in ascending order.
+    fn address_history_blocks<T>(
+        &self,
+        address: Address,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>>
+    where
+        T: Table<Key = ShardedKey<Address>, Value = BlockNumberList>,
+    {
+        let mut cursor = self.tx.cursor_read::<T>()?;
+        let mut blocks = Vec::new();
+
+        // The first shard that can contain the start of the range is the one with the lowest
+        // highest block number that is greater or equal to the start.
+        let mut item = cursor.seek(ShardedKey::new(address, *range.start()))?;
+        while let Some((sharded_key, list)) = item {
+            if sharded_key.key != address {
+                break
+            }
+            for block in list.iter(0).map(|block| block as BlockNumber) {
+                if block > *range.end() {
+                    return Ok(blocks)
+                }
+                if block >= *range.start() {
+                    blocks.push(block);
+                }
+            }
+            item = cursor.next()?;
+        }
+
+        Ok(blocks)
+    }
+}
+
+impl<TX: DbTx> HistoryReader for DatabaseProvider<TX> {
+    fn account_history_blocks(
+        &self,
+        address: Address,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>> {
+        self.address_history_blocks::<tables::AccountHistory>(address, range)
+    }
+
+    fn storage_history_blocks(
+        &self,
+        address: Address,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>> {
+        let mut cursor = self.tx.cursor_read::<tables::StorageHistory>()?;
+        let mut blocks = BTreeSet::new();
+
+        // Storage history is keyed by address first, so all shards of all slots of the account
+        // are stored next to each other.
+        let mut item = cursor.seek(StorageShardedKey::new(address, B256::ZERO, 0))?;
+        while let Some((sharded_key, list)) = item {
+            if sharded_key.address != address {
+                break
+            }
+            if sharded_key.sharded_key.highest_block_number >= *range.start() {
+                blocks.extend(
+                    list.iter(0)
+                        .map(|block| block as BlockNumber)
+                        .skip_while(|block| *block < *range.start())
+                        .take_while(|block| *block <= *range.end()),
+                );
+            }
+            item = cursor.next()?;
+        }
+
+        Ok(blocks.into_iter().collect())
+    }
+
+    fn trace_address_index_tip(&self) -> RethResult<Option<BlockNumber>> {
+        Ok(self
+            .get_stage_checkpoint(StageId::IndexTraceAddresses)?
+            .map(|checkpoint| checkpoint.block_number))
+    }
+
+    fn trace_address_blocks(
+        &self,
+        address: Address,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>> {
+        self.address_history_blocks::<tables::TraceAddressHistory>(address, range)
+    }
+}
+
 impl<TX: DbTxMut + DbTx> HistoryWriter for DatabaseProvider<TX> {
     fn update_history_indices(&self, range: RangeInclusive<BlockNumber>) -> RethResult<()> {
         // account history stage
@@ -1968,6 +2223,61 @@ impl<TX: DbTxMut + DbTx> HistoryWriter for DatabaseProvider<TX> {
 
         Ok(changesets)
     }
+
+    fn insert_trace_address_index(
+        &self,
+        block_addresses: BTreeMap<BlockNumber, BTreeSet<Address>>,
+    ) -> RethResult<()> {
+        let mut cursor = self.tx.cursor_dup_write::<tables::BlockTraceAddresses>()?;
+        let mut indices = BTreeMap::<Address, Vec<u64>>::new();
+        for (block_number, addresses) in block_addresses {
+            for address in addresses {
+                cursor.append_dup(block_number, address)?;
+                indices.entry(address).or_default().push(block_number);
+            }
+        }
+
+        self.append_history_index::<_, tables::TraceAddressHistory>(indices, ShardedKey::new)
+    }
+
+    fn unwind_trace_address_indices(
+        &self,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<usize> {
+        let mut cursor = self.tx.cursor_dup_write::<tables::BlockTraceAddresses>()?;
+
+        // Collect the lowest block number of every address in the range and remove the entries.
+        let mut last_indices = BTreeMap::<Address, BlockNumber>::new();
+        let mut walker = cursor.walk_range(range)?;
+        let mut entries = 0;
+        while let Some((block_number, address)) = walker.next().transpose()? {
+            last_indices.entry(address).or_insert(block_number);
+            walker.delete_current()?;
+            entries += 1;
+        }
+
+        // Unwind the trace address index.
+        let mut cursor = self.tx.cursor_write::<tables::TraceAddressHistory>()?;
+        for (address, rem_index) in last_indices {
+            let partial_shard = unwind_history_shards::<_, tables::TraceAddressHistory, _>(
+                &mut cursor,
+                ShardedKey::last(address),
+                rem_index,
+                |sharded_key| sharded_key.key == address,
+            )?;
+
+            // Check the last returned partial shard.
+            // If it's not empty, the shard needs to be reinserted.
+            if !partial_shard.is_empty() {
+                cursor.insert(
+                    ShardedKey::last(address),
+                    BlockNumberList::new_pre_sorted(partial_shard),
+                )?;
+            }
+        }
+
+        Ok(entries)
+    }
 }
 
 impl<TX: DbTxMut + DbTx> BlockExecutionWriter for DatabaseProvider<TX> {
@@ -2013,6 +2323,9 @@ impl<TX: DbTxMut + DbTx> BlockExecutionWriter for DatabaseProvider<TX> {
             // Unwind storage history indices.
             self.unwind_storage_history_indices(storage_range)?;
 
+            // Unwind trace address indices, if the blocks were indexed.
+            self.unwind_trace_address_indices(range.clone())?;
+
             // Calculate the reverted merkle root.
             // This is the same as `StateRoot::incremental_root_with_updates`, only the prefix sets
             // are pre-loaded.
@@ -2061,6 +2374,17 @@ impl<TX: DbTxMut + DbTx> BlockExecutionWriter for DatabaseProvider<TX> {
             // Update pipeline progress
             if let Some(fork_number) = unwind_to {
                 self.update_pipeline_stages(fork_number, true)?;
+
+                // The trace address index is optional and not part of the default stages, so it is
+                // only moved back if it has indexed the unwound blocks.
+                if let Some(checkpoint) = self.get_stage_checkpoint(StageId::IndexTraceAddresses)? {
+                    if checkpoint.block_number > fork_number {
+                        self.save_stage_checkpoint(
+                            StageId::IndexTraceAddresses,
+                            StageCheckpoint::new(fork_number),
+                        )?;
+                    }
+                }
             }
         }
 
@@ -2111,11 +2435,13 @@ impl<TX: DbTxMut + DbTx> BlockWriter for DatabaseProvider<TX> {
             durations_recorder.record_relative(metrics::Action::InsertBlockOmmers);
         }
 
+        // Snapshotted transactions are deleted from the database, so the next transaction number
+        // is taken from the block body indices.
         let mut next_tx_num = self
             .tx
-            .cursor_read::<tables::Transactions>()?
+            .cursor_read::<tables::BlockBodyIndices>()?
             .last()?
-            .map(|(n, _)| n + 1)
+            .map(|(_, indices)| indices.next_tx_num())
             .unwrap_or_default();
         durations_recorder.record_relative(metrics::Action::GetNextTxNum);
         let first_tx_num = next_tx_num;
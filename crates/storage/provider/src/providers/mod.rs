@@ -1,10 +1,11 @@
 use crate::{
-    AccountReader, BlockHashReader, BlockIdReader, BlockNumReader, BlockReader, BlockReaderIdExt,
-    BlockchainTreePendingStateProvider, BundleStateDataProvider, CanonChainTracker,
-    CanonStateNotifications, CanonStateSubscriptions, ChainSpecProvider, ChangeSetReader,
-    EvmEnvProvider, HeaderProvider, ProviderError, PruneCheckpointReader, ReceiptProvider,
-    ReceiptProviderIdExt, StageCheckpointReader, StateProviderBox, StateProviderFactory,
-    TransactionVariant, TransactionsProvider, WithdrawalsProvider,
+    AccountReader, BadBlock, BadBlockReader, BadBlockStore, BlockHashReader, BlockIdReader,
+    BlockNumReader, BlockReader, BlockReaderIdExt, BlockchainTreePendingStateProvider,
+    BundleStateDataProvider, CanonChainTracker, CanonStateNotifications, CanonStateSubscriptions,
+    ChainSpecProvider, ChangeSetReader, EvmEnvProvider, HeaderProvider, HistoryReader,
+    ProviderError, PruneCheckpointReader, ReceiptProvider, ReceiptProviderIdExt,
+    StageCheckpointReader, StateProviderBox, StateProviderFactory, TransactionVariant,
+    TransactionsProvider, WithdrawalsProvider,
 };
 use reth_db::{database::Database, models::StoredBlockBodyIndices};
 use reth_interfaces::{
@@ -30,7 +31,7 @@ use tracing::trace;
 
 pub use state::{
     historical::{HistoricalStateProvider, HistoricalStateProviderRef},
-    latest::{LatestStateProvider, LatestStateProviderRef},
+    latest::{LatestStateProvider, LatestStateProviderRef, SnapSyncedState, SnapSyncedStateCache},
 };
 
 mod bundle_state_provider;
@@ -52,6 +53,9 @@ use reth_interfaces::blockchain_tree::{
 /// This type serves as the main entry point for interacting with the blockchain and provides data
 /// from database storage and from the blockchain tree (pending state etc.) It is a simple wrapper
 /// type that holds an instance of the database and the blockchain tree.
+///
+/// If the [ProviderFactory] is configured with snapshots (see [ProviderFactory::with_snapshots]),
+/// headers, transactions and receipts of snapshotted blocks are read from the snapshots.
 #[derive(Clone, Debug)]
 pub struct BlockchainProvider<DB, Tree> {
     /// Provider type used to access the database.
@@ -60,13 +64,29 @@ pub struct BlockchainProvider<DB, Tree> {
     tree: Tree,
     /// Tracks the chain info wrt forkchoice updates
     chain_info: ChainInfoTracker,
+    /// Recently rejected blocks.
+    bad_blocks: BadBlockStore,
 }
 
 impl<DB, Tree> BlockchainProvider<DB, Tree> {
     /// Create new  provider instance that wraps the database and the blockchain tree, using the
     /// provided latest header to initialize the chain info tracker.
     pub fn with_latest(database: ProviderFactory<DB>, tree: Tree, latest: SealedHeader) -> Self {
-        Self { database, tree, chain_info: ChainInfoTracker::new(latest) }
+        Self {
+            database,
+            tree,
+            chain_info: ChainInfoTracker::new(latest),
+            bad_blocks: BadBlockStore::default(),
+        }
+    }
+
+    /// Sets the store of recently rejected blocks that is served via [BadBlockReader].
+    ///
+    /// This should be the same store the engine and the blockchain tree record rejected blocks
+    /// to.
+    pub fn with_bad_block_store(mut self, bad_blocks: BadBlockStore) -> Self {
+        self.bad_blocks = bad_blocks;
+        self
     }
 }
 
@@ -601,6 +621,10 @@ where
     fn unwind(&self, unwind_to: BlockNumber) -> RethResult<()> {
         self.tree.unwind(unwind_to)
     }
+
+    fn reset_canonical_chain(&self) -> RethResult<()> {
+        self.tree.reset_canonical_chain()
+    }
 }
 
 impl<DB, Tree> BlockchainTreeViewer for BlockchainProvider<DB, Tree>
@@ -819,6 +843,50 @@ where
     }
 }
 
+impl<DB, Tree> BadBlockReader for BlockchainProvider<DB, Tree>
+where
+    DB: Send + Sync,
+    Tree: Send + Sync,
+{
+    fn bad_blocks(&self) -> RethResult<Vec<BadBlock>> {
+        Ok(self.bad_blocks.blocks())
+    }
+}
+
+impl<DB, Tree> HistoryReader for BlockchainProvider<DB, Tree>
+where
+    DB: Database,
+    Tree: Sync + Send,
+{
+    fn account_history_blocks(
+        &self,
+        address: Address,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>> {
+        self.database.provider()?.account_history_blocks(address, range)
+    }
+
+    fn storage_history_blocks(
+        &self,
+        address: Address,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>> {
+        self.database.provider()?.storage_history_blocks(address, range)
+    }
+
+    fn trace_address_index_tip(&self) -> RethResult<Option<BlockNumber>> {
+        self.database.provider()?.trace_address_index_tip()
+    }
+
+    fn trace_address_blocks(
+        &self,
+        address: Address,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>> {
+        self.database.provider()?.trace_address_blocks(address, range)
+    }
+}
+
 impl<DB, Tree> AccountReader for BlockchainProvider<DB, Tree>
 where
     DB: Database + Sync + Send,
@@ -1,10 +1,14 @@
-use super::LoadedJarRef;
+use super::{to_range, LoadedJarRef};
 use crate::{
     BlockHashReader, BlockNumReader, HeaderProvider, ReceiptProvider, TransactionsProvider,
 };
 use reth_db::{
     codecs::CompactU256,
-    snapshot::{HeaderMask, ReceiptMask, SnapshotCursor, TransactionMask},
+    models::{AccountBeforeTx, AccountChangeSetBlock, StorageBeforeTx, StorageChangeSetBlock},
+    snapshot::{
+        AccountChangeSetMask, HeaderMask, ReceiptMask, SnapshotCursor, StorageChangeSetMask,
+        TransactionMask,
+    },
 };
 use reth_interfaces::{
     executor::{BlockExecutionError, BlockValidationError},
@@ -13,9 +17,10 @@ use reth_interfaces::{
 };
 use reth_primitives::{
     Address, BlockHash, BlockHashOrNumber, BlockNumber, ChainInfo, Header, Receipt, SealedHeader,
-    TransactionMeta, TransactionSigned, TransactionSignedNoHash, TxHash, TxNumber, B256, U256,
+    StorageEntry, StorageKey, TransactionMeta, TransactionSigned, TransactionSignedNoHash, TxHash,
+    TxNumber, B256, U256,
 };
-use std::ops::{Deref, Range, RangeBounds};
+use std::ops::{Deref, RangeBounds};
 
 /// Provider over a specific `NippyJar` and range.
 #[derive(Debug)]
@@ -53,6 +58,73 @@ impl<'a> SnapshotJarProvider<'a> {
         self.auxiliar_jar = Some(Box::new(auxiliar_jar));
         self
     }
+
+    /// Returns the receipts of the given transaction range.
+    pub fn receipts_by_tx_range(
+        &self,
+        range: impl RangeBounds<TxNumber>,
+    ) -> RethResult<Vec<Receipt>> {
+        let range = to_range(range);
+        let mut cursor = self.cursor()?;
+        let mut receipts = Vec::with_capacity((range.end - range.start) as usize);
+
+        for num in range {
+            if let Some(receipt) = cursor.get_one::<ReceiptMask<Receipt>>(num.into())? {
+                receipts.push(receipt)
+            }
+        }
+        Ok(receipts)
+    }
+
+    /// Returns all account changes of the given block, sorted by address.
+    pub fn account_changeset(&self, block: BlockNumber) -> RethResult<Vec<AccountBeforeTx>> {
+        Ok(self
+            .cursor()?
+            .get_one::<AccountChangeSetMask<AccountChangeSetBlock>>(block.into())?
+            .map(|AccountChangeSetBlock(changes)| changes)
+            .unwrap_or_default())
+    }
+
+    /// Returns all storage changes of the given block, sorted by address and storage key.
+    pub fn storage_changeset(&self, block: BlockNumber) -> RethResult<Vec<StorageBeforeTx>> {
+        Ok(self
+            .cursor()?
+            .get_one::<StorageChangeSetMask<StorageChangeSetBlock>>(block.into())?
+            .map(|StorageChangeSetBlock(changes)| changes)
+            .unwrap_or_default())
+    }
+
+    /// Returns the state of the account before the given block changed it, if the block changed
+    /// it.
+    pub fn account_before(
+        &self,
+        block: BlockNumber,
+        address: Address,
+    ) -> RethResult<Option<AccountBeforeTx>> {
+        let mut changes = self.account_changeset(block)?;
+        Ok(changes
+            .binary_search_by_key(&address, |change| change.address)
+            .ok()
+            .map(|index| changes.swap_remove(index)))
+    }
+
+    /// Returns the value of the storage slot before the given block changed it, if the block
+    /// changed it.
+    pub fn storage_before(
+        &self,
+        block: BlockNumber,
+        address: Address,
+        storage_key: StorageKey,
+    ) -> RethResult<Option<StorageEntry>> {
+        let mut changes = self.storage_changeset(block)?;
+
+        Ok(changes
+            .binary_search_by_key(&(address, storage_key), |change| {
+                (change.address, change.entry.key)
+            })
+            .ok()
+            .map(|index| changes.swap_remove(index).entry))
+    }
 }
 
 impl<'a> HeaderProvider for SnapshotJarProvider<'a> {
@@ -282,19 +354,3 @@ impl<'a> ReceiptProvider for SnapshotJarProvider<'a> {
         Err(ProviderError::UnsupportedProvider.into())
     }
 }
-
-fn to_range<R: RangeBounds<u64>>(bounds: R) -> Range<u64> {
-    let start = match bounds.start_bound() {
-        std::ops::Bound::Included(&v) => v,
-        std::ops::Bound::Excluded(&v) => v + 1,
-        std::ops::Bound::Unbounded => 0,
-    };
-
-    let end = match bounds.end_bound() {
-        std::ops::Bound::Included(&v) => v + 1,
-        std::ops::Bound::Excluded(&v) => v,
-        std::ops::Bound::Unbounded => u64::MAX,
-    };
-
-    start..end
-}
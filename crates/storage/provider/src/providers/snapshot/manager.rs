@@ -1,48 +1,229 @@
 use super::{LoadedJar, SnapshotJarProvider};
 use crate::{BlockHashReader, BlockNumReader, HeaderProvider, TransactionsProvider};
 use dashmap::DashMap;
-use reth_interfaces::RethResult;
+use parking_lot::RwLock;
+use reth_interfaces::{provider::ProviderError, RethError, RethResult};
 use reth_nippy_jar::NippyJar;
 use reth_primitives::{
-    snapshot::BLOCKS_PER_SNAPSHOT, Address, BlockHash, BlockHashOrNumber, BlockNumber, ChainInfo,
-    Header, SealedHeader, SnapshotSegment, TransactionMeta, TransactionSigned,
-    TransactionSignedNoHash, TxHash, TxNumber, B256, U256,
+    snapshot::{HighestSnapshots, SegmentHeader},
+    Address, BlockHash, BlockHashOrNumber, BlockNumber, ChainInfo, Header, SealedHeader,
+    SnapshotSegment, TransactionMeta, TransactionSigned, TransactionSignedNoHash, TxHash, TxNumber,
+    B256, U256,
 };
-use std::{ops::RangeBounds, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::{RangeBounds, RangeInclusive},
+    path::{Path, PathBuf},
+};
+use tokio::sync::watch;
+use tracing::trace;
 
 /// SnapshotProvider
 #[derive(Debug, Default)]
 pub struct SnapshotProvider {
     /// Maintains a map which allows for concurrent access to different `NippyJars`, over different
-    /// segments and ranges.
+    /// segments and ranges. Keyed by the first block of the snapshot.
     map: DashMap<(BlockNumber, SnapshotSegment), LoadedJar>,
+    /// Directory where the snapshots are located.
+    path: Option<PathBuf>,
+    /// Snapshots available in [`Self::path`], per segment.
+    index: RwLock<HashMap<SnapshotSegment, SegmentIndex>>,
+    /// Tracker of the highest snapshotted blocks, used to detect snapshots that were produced
+    /// after the index was built.
+    highest_tracker: Option<watch::Receiver<Option<HighestSnapshots>>>,
+}
+
+/// Snapshots of a single segment.
+#[derive(Debug, Default)]
+struct SegmentIndex {
+    /// First block of each snapshot, keyed by its last block.
+    blocks: BTreeMap<BlockNumber, BlockNumber>,
+    /// Row range and first block of each snapshot, keyed by its last row number (block or
+    /// transaction, depending on the segment).
+    rows: BTreeMap<u64, (RangeInclusive<u64>, BlockNumber)>,
+}
+
+impl SegmentIndex {
+    /// Returns the first block of the snapshot that contains the given block.
+    fn find_block(&self, block: BlockNumber) -> Option<BlockNumber> {
+        self.blocks
+            .range(block..)
+            .next()
+            .filter(|(_, block_start)| **block_start <= block)
+            .map(|(_, block_start)| *block_start)
+    }
+
+    /// Returns the first block of the snapshot that contains the given row number.
+    fn find_row(&self, number: u64) -> Option<BlockNumber> {
+        self.rows
+            .range(number..)
+            .next()
+            .filter(|(_, (rows, _))| rows.contains(&number))
+            .map(|(_, (_, block_start))| *block_start)
+    }
+
+    /// Returns the highest block covered by the snapshots.
+    fn highest_block(&self) -> Option<BlockNumber> {
+        self.blocks.last_key_value().map(|(block_end, _)| *block_end)
+    }
+
+    fn insert(&mut self, header: &SegmentHeader) {
+        let block_range = header.block_range();
+        self.blocks.insert(*block_range.end(), *block_range.start());
+
+        // Transaction based snapshots of blocks without transactions have no rows.
+        let rows = header.start()..=header.end();
+        if !rows.is_empty() {
+            self.rows.insert(*rows.end(), (rows, header.block_start()));
+        }
+    }
 }
 
 impl SnapshotProvider {
-    /// Gets the provider of the requested segment and range.
+    /// Creates a new [`SnapshotProvider`] serving the snapshots located in the given directory.
+    pub fn new(path: impl AsRef<Path>) -> RethResult<Self> {
+        let provider = Self { path: Some(path.as_ref().to_path_buf()), ..Default::default() };
+        provider.update_index()?;
+        Ok(provider)
+    }
+
+    /// Sets the tracker of the highest snapshotted blocks.
+    ///
+    /// Whenever it reports a snapshot that is not indexed yet, the snapshot directory is scanned
+    /// again.
+    pub fn with_highest_tracker(
+        mut self,
+        highest_tracker: Option<watch::Receiver<Option<HighestSnapshots>>>,
+    ) -> Self {
+        self.highest_tracker = highest_tracker;
+        self
+    }
+
+    /// Scans the snapshot directory and indexes the snapshots that weren't loaded yet.
+    pub fn update_index(&self) -> RethResult<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+
+        let entries = match std::fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(RethError::Custom(err.to_string())),
+        };
+
+        let mut index = self.index.write();
+        for entry in entries {
+            let entry = entry.map_err(|err| RethError::Custom(err.to_string()))?;
+            let Some((segment, block_range)) =
+                entry.file_name().to_str().and_then(SnapshotSegment::parse_filename)
+            else {
+                continue
+            };
+
+            let key = (*block_range.start(), segment);
+            if !self.map.contains_key(&key) {
+                trace!(target: "providers::snapshot", ?segment, ?block_range, "Loading snapshot");
+                self.map.insert(key, LoadedJar::new(NippyJar::load(&entry.path())?)?);
+            }
+
+            if let Some(jar) = self.map.get(&key) {
+                index.entry(segment).or_default().insert(jar.user_header());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the highest block covered by the snapshots of the given segment.
+    pub fn highest_snapshot_block(&self, segment: SnapshotSegment) -> Option<BlockNumber> {
+        self.index.read().get(&segment).and_then(SegmentIndex::highest_block)
+    }
+
+    /// Gets the provider of the snapshot that contains the given row number of the segment, if
+    /// it's snapshotted.
+    ///
+    /// The row number is a transaction number for [`SnapshotSegment::Transactions`] and
+    /// [`SnapshotSegment::Receipts`], and a block number for the other segments.
+    pub fn get_segment_provider_for_row(
+        &self,
+        segment: SnapshotSegment,
+        number: u64,
+    ) -> RethResult<Option<SnapshotJarProvider<'_>>> {
+        let Some(block_start) = self.find(segment, |index| index.find_row(number))? else {
+            return Ok(None)
+        };
+
+        self.map
+            .get(&(block_start, segment))
+            .map(|jar| Some(jar.into()))
+            .ok_or_else(|| ProviderError::MissingSnapshot(segment, number).into())
+    }
+
+    /// Looks up the first block of a snapshot of the segment in the index.
+    ///
+    /// If nothing is found but the index is outdated, the snapshot directory is scanned again
+    /// before retrying.
+    fn find(
+        &self,
+        segment: SnapshotSegment,
+        lookup: impl Fn(&SegmentIndex) -> Option<BlockNumber>,
+    ) -> RethResult<Option<BlockNumber>> {
+        let find = || self.index.read().get(&segment).and_then(&lookup);
+
+        if let Some(block_start) = find() {
+            return Ok(Some(block_start))
+        }
+
+        if self.is_index_outdated(segment) {
+            self.update_index()?;
+            return Ok(find())
+        }
+
+        Ok(None)
+    }
+
+    /// Returns `true` if the highest snapshot tracker reports a higher snapshot than the indexed
+    /// one.
+    fn is_index_outdated(&self, segment: SnapshotSegment) -> bool {
+        let Some(tracker) = &self.highest_tracker else { return false };
+        let highest_snapshots = *tracker.borrow();
+        highest_snapshots.and_then(|highest| highest.highest(segment)) >
+            self.highest_snapshot_block(segment)
+    }
+
+    /// Gets the provider of the requested segment and block.
+    ///
+    /// If `path` is provided, the snapshot is loaded from it. Otherwise, the snapshot that
+    /// contains the block is looked up in the snapshot directory.
     pub fn get_segment_provider(
         &self,
         segment: SnapshotSegment,
         block: BlockNumber,
-        mut path: Option<PathBuf>,
+        path: Option<PathBuf>,
     ) -> RethResult<SnapshotJarProvider<'_>> {
-        // TODO this invalidates custom length snapshots.
-        let snapshot = block / BLOCKS_PER_SNAPSHOT;
-        let key = (snapshot, segment);
-
-        if let Some(jar) = self.map.get(&key) {
-            return Ok(jar.into())
+        if let Some(path) = path {
+            let jar = NippyJar::<SegmentHeader>::load(&path)?;
+            let key = (jar.user_header().block_start(), segment);
+            if !self.map.contains_key(&key) {
+                self.map.insert(key, LoadedJar::new(jar)?);
+            }
+            return self
+                .map
+                .get(&key)
+                .map(Into::into)
+                .ok_or_else(|| ProviderError::MissingSnapshot(segment, block).into())
         }
 
-        if let Some(path) = &path {
-            self.map.insert(key, LoadedJar::new(NippyJar::load(path)?)?);
-        } else {
-            path = Some(segment.filename(
-                &((snapshot * BLOCKS_PER_SNAPSHOT)..=((snapshot + 1) * BLOCKS_PER_SNAPSHOT - 1)),
-            ));
+        let mut block_start = self.find(segment, |index| index.find_block(block))?;
+        if block_start.is_none() {
+            // The snapshot might have been produced without updating the highest snapshots
+            // tracker, e.g. by the `db snapshot` command.
+            self.update_index()?;
+            block_start = self.index.read().get(&segment).and_then(|index| index.find_block(block));
         }
 
-        self.get_segment_provider(segment, block, path)
+        block_start
+            .and_then(|block_start| self.map.get(&(block_start, segment)))
+            .map(Into::into)
+            .ok_or_else(|| ProviderError::MissingSnapshot(segment, block).into())
     }
 }
 
@@ -52,7 +233,8 @@ impl HeaderProvider for SnapshotProvider {
     }
 
     fn header_by_number(&self, num: BlockNumber) -> RethResult<Option<Header>> {
-        self.get_segment_provider(SnapshotSegment::Headers, num, None)?.header_by_number(num)
+        self.get_segment_provider_for_row(SnapshotSegment::Headers, num)?
+            .map_or(Ok(None), |provider| provider.header_by_number(num))
     }
 
     fn header_td(&self, _block_hash: &BlockHash) -> RethResult<Option<U256>> {
@@ -117,10 +299,8 @@ impl TransactionsProvider for SnapshotProvider {
     }
 
     fn transaction_by_id(&self, num: TxNumber) -> RethResult<Option<TransactionSigned>> {
-        // TODO `num` is provided after checking the index
-        let block_num = num;
-        self.get_segment_provider(SnapshotSegment::Transactions, block_num, None)?
-            .transaction_by_id(num)
+        self.get_segment_provider_for_row(SnapshotSegment::Transactions, num)?
+            .map_or(Ok(None), |provider| provider.transaction_by_id(num))
     }
 
     fn transaction_by_id_no_hash(
@@ -7,7 +7,7 @@ pub use jar::SnapshotJarProvider;
 use reth_interfaces::RethResult;
 use reth_nippy_jar::NippyJar;
 use reth_primitives::{snapshot::SegmentHeader, SnapshotSegment};
-use std::ops::Deref;
+use std::ops::{Deref, Range, RangeBounds};
 
 /// Alias type for each specific `NippyJar`.
 type LoadedJarRef<'a> = dashmap::mapref::one::Ref<'a, (u64, SnapshotSegment), LoadedJar>;
@@ -38,22 +38,53 @@ impl Deref for LoadedJar {
     }
 }
 
+/// Converts the given range bounds into a [`Range`].
+pub(crate) fn to_range<R: RangeBounds<u64>>(bounds: R) -> Range<u64> {
+    let start = match bounds.start_bound() {
+        std::ops::Bound::Included(&v) => v,
+        std::ops::Bound::Excluded(&v) => v + 1,
+        std::ops::Bound::Unbounded => 0,
+    };
+
+    let end = match bounds.end_bound() {
+        std::ops::Bound::Included(&v) => v + 1,
+        std::ops::Bound::Excluded(&v) => v,
+        std::ops::Bound::Unbounded => u64::MAX,
+    };
+
+    start..end
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{HeaderProvider, ProviderFactory};
+    use crate::{
+        providers::state::historical::HistoricalStateProviderRef, AccountReader, BlockHashReader,
+        BlockReader, HeaderProvider, ProviderFactory, ReceiptProvider, StateProvider,
+        TransactionsProvider,
+    };
     use rand::{self, seq::SliceRandom};
     use reth_db::{
         cursor::DbCursorRO,
         database::Database,
-        snapshot::create_snapshot_T1_T2_T3,
+        models::{
+            storage_sharded_key::StorageShardedKey, AccountBeforeTx, AccountChangeSetBlock,
+            ShardedKey, StorageBeforeTx, StorageChangeSetBlock, StoredBlockBodyIndices,
+        },
+        snapshot::{create_snapshot_T1, create_snapshot_T1_T2_T3},
+        table::Compress,
+        tables,
         test_utils::create_test_rw_db,
         transaction::{DbTx, DbTxMut},
-        CanonicalHeaders, DatabaseError, HeaderNumbers, HeaderTD, Headers, RawTable,
+        BlockBodyIndices, BlockNumberList, CanonicalHeaders, DatabaseError, HeaderNumbers,
+        HeaderTD, Headers, RawTable, Transactions,
+    };
+    use reth_interfaces::test_utils::generators::{self, random_block_range, random_header_range};
+    use reth_nippy_jar::{ColumnResult, NippyJar};
+    use reth_primitives::{
+        stage::{StageCheckpoint, StageId},
+        Account, Address, BlockNumber, Receipt, StorageEntry, TxNumber, B256, MAINNET, U256,
     };
-    use reth_interfaces::test_utils::generators::{self, random_header_range};
-    use reth_nippy_jar::NippyJar;
-    use reth_primitives::{BlockNumber, B256, MAINNET, U256};
 
     #[test]
     fn test_snap() {
@@ -160,4 +191,395 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_snapshot_boundary() {
+        // Blocks up to `snapshot_end` are moved to snapshots, the rest stays in the database.
+        let snapshot_end = 3u64;
+        let blocks = random_block_range(&mut generators::rng(), 0..=5, B256::ZERO, 1..3);
+
+        let db = create_test_rw_db();
+        let snapshots_dir = tempfile::tempdir().unwrap();
+
+        // Setup data
+        let snapshot_tx_end = db
+            .update(|tx| -> Result<TxNumber, DatabaseError> {
+                let mut next_tx_num = 0;
+                let mut snapshot_tx_end = 0;
+                for block in &blocks {
+                    tx.put::<CanonicalHeaders>(block.number, block.hash)?;
+                    tx.put::<Headers>(block.number, block.header.clone().unseal())?;
+                    tx.put::<HeaderTD>(block.number, U256::from(block.number).into())?;
+                    tx.put::<HeaderNumbers>(block.hash, block.number)?;
+
+                    let indices = StoredBlockBodyIndices {
+                        first_tx_num: next_tx_num,
+                        tx_count: block.body.len() as u64,
+                    };
+                    if block.number == snapshot_end {
+                        snapshot_tx_end = indices.last_tx_num();
+                    }
+                    tx.put::<BlockBodyIndices>(block.number, indices)?;
+
+                    for transaction in &block.body {
+                        tx.put::<Transactions>(next_tx_num, transaction.clone().into())?;
+                        tx.put::<tables::Receipts>(next_tx_num, boundary_receipt(next_tx_num))?;
+                        tx.put::<tables::TxHashNumber>(transaction.hash(), next_tx_num)?;
+                        next_tx_num += 1;
+                    }
+                }
+                Ok(snapshot_tx_end)
+            })
+            .unwrap()
+            .unwrap();
+
+        // Create snapshots
+        {
+            let tx = db.tx().unwrap();
+            let block_range = 0..=snapshot_end;
+            let tx_range = 0..=snapshot_tx_end;
+
+            let mut headers_jar = NippyJar::new(
+                3,
+                &snapshots_dir.path().join(SnapshotSegment::Headers.filename(&block_range)),
+                SegmentHeader::new(block_range.clone(), tx_range.clone(), SnapshotSegment::Headers),
+            );
+            create_snapshot_T1_T2_T3::<
+                Headers,
+                HeaderTD,
+                CanonicalHeaders,
+                BlockNumber,
+                SegmentHeader,
+            >(
+                &tx,
+                block_range.clone(),
+                None,
+                None::<Vec<std::vec::IntoIter<Vec<u8>>>>,
+                None::<std::vec::IntoIter<ColumnResult<B256>>>,
+                block_range.clone().count(),
+                &mut headers_jar,
+            )
+            .unwrap();
+
+            let mut transactions_jar = NippyJar::new(
+                1,
+                &snapshots_dir.path().join(SnapshotSegment::Transactions.filename(&block_range)),
+                SegmentHeader::new(
+                    block_range.clone(),
+                    tx_range.clone(),
+                    SnapshotSegment::Transactions,
+                ),
+            );
+            create_snapshot_T1::<Transactions, TxNumber, SegmentHeader>(
+                &tx,
+                tx_range.clone(),
+                None,
+                None::<Vec<std::vec::IntoIter<Vec<u8>>>>,
+                None::<std::vec::IntoIter<ColumnResult<B256>>>,
+                tx_range.clone().count(),
+                &mut transactions_jar,
+            )
+            .unwrap();
+
+            let mut receipts_jar = NippyJar::new(
+                1,
+                &snapshots_dir.path().join(SnapshotSegment::Receipts.filename(&block_range)),
+                SegmentHeader::new(block_range, tx_range.clone(), SnapshotSegment::Receipts),
+            );
+            create_snapshot_T1::<tables::Receipts, TxNumber, SegmentHeader>(
+                &tx,
+                tx_range.clone(),
+                None,
+                None::<Vec<std::vec::IntoIter<Vec<u8>>>>,
+                None::<std::vec::IntoIter<ColumnResult<B256>>>,
+                tx_range.count(),
+                &mut receipts_jar,
+            )
+            .unwrap();
+        }
+
+        // Remove snapshotted data from the database
+        db.update(|tx| -> Result<(), DatabaseError> {
+            for number in 0..=snapshot_end {
+                tx.delete::<CanonicalHeaders>(number, None)?;
+                tx.delete::<Headers>(number, None)?;
+                tx.delete::<HeaderTD>(number, None)?;
+            }
+            for tx_num in 0..=snapshot_tx_end {
+                tx.delete::<Transactions>(tx_num, None)?;
+                tx.delete::<tables::Receipts>(tx_num, None)?;
+            }
+            Ok(())
+        })
+        .unwrap()
+        .unwrap();
+
+        let db_provider = ProviderFactory::new(&db, MAINNET.clone()).provider().unwrap();
+        assert_eq!(db_provider.header_by_number(snapshot_end).unwrap(), None);
+        assert_eq!(db_provider.transaction_by_id(snapshot_tx_end).unwrap(), None);
+        assert_eq!(db_provider.receipt(snapshot_tx_end).unwrap(), None);
+
+        let factory = ProviderFactory::new(&db, MAINNET.clone())
+            .with_snapshots(snapshots_dir.path(), None)
+            .unwrap();
+        let provider = factory.provider().unwrap();
+
+        // Edge blocks on both sides of the boundary
+        for block in &blocks[snapshot_end as usize..=snapshot_end as usize + 1] {
+            assert_eq!(provider.sealed_header(block.number).unwrap(), Some(block.header.clone()));
+            assert_eq!(provider.header(&block.hash).unwrap(), Some(block.header.clone().unseal()));
+            assert_eq!(
+                provider.header_td_by_number(block.number).unwrap(),
+                Some(U256::from(block.number))
+            );
+            assert_eq!(provider.block_hash(block.number).unwrap(), Some(block.hash));
+
+            let found = provider.block(block.number.into()).unwrap().unwrap();
+            assert_eq!(found.header, block.header.clone().unseal());
+            assert_eq!(found.body, block.body);
+        }
+
+        // Edge transactions on both sides of the boundary
+        let transactions = blocks.iter().flat_map(|block| block.body.clone()).collect::<Vec<_>>();
+        for tx_num in snapshot_tx_end..=snapshot_tx_end + 1 {
+            assert_eq!(
+                provider.transaction_by_id(tx_num).unwrap().as_ref(),
+                transactions.get(tx_num as usize)
+            );
+            assert_eq!(provider.receipt(tx_num).unwrap(), Some(boundary_receipt(tx_num)));
+            assert_eq!(
+                provider.receipt_by_hash(transactions[tx_num as usize].hash()).unwrap(),
+                Some(boundary_receipt(tx_num))
+            );
+        }
+
+        // Ranges crossing the boundary
+        let range = snapshot_end - 1..=snapshot_end + 2;
+        let expected = &blocks[*range.start() as usize..=*range.end() as usize];
+        assert_eq!(
+            provider.sealed_headers_range(range.clone()).unwrap(),
+            expected.iter().map(|block| block.header.clone()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            provider.canonical_hashes_range(*range.start(), *range.end() + 1).unwrap(),
+            expected.iter().map(|block| block.hash).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            provider.transactions_by_block_range(range.clone()).unwrap(),
+            expected.iter().map(|block| block.body.clone()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            provider
+                .block_range(range.clone())
+                .unwrap()
+                .into_iter()
+                .map(|block| (block.header, block.body))
+                .collect::<Vec<_>>(),
+            expected
+                .iter()
+                .map(|block| (block.header.clone().unseal(), block.body.clone()))
+                .collect::<Vec<_>>()
+        );
+        let mut next_tx_num = 0;
+        let expected_receipts = blocks
+            .iter()
+            .map(|block| {
+                let first_tx_num = next_tx_num;
+                next_tx_num += block.body.len() as u64;
+                (first_tx_num..next_tx_num).map(boundary_receipt).collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            range
+                .clone()
+                .map(|number| provider.receipts_by_block(number.into()).unwrap())
+                .collect::<Vec<_>>(),
+            expected_receipts[*range.start() as usize..=*range.end() as usize]
+                .iter()
+                .cloned()
+                .map(Some)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            provider.transactions_by_tx_range(snapshot_tx_end - 1..=snapshot_tx_end + 1).unwrap(),
+            transactions[snapshot_tx_end as usize - 1..=snapshot_tx_end as usize + 1]
+                .iter()
+                .cloned()
+                .map(Into::into)
+                .collect::<Vec<reth_primitives::TransactionSignedNoHash>>()
+        );
+    }
+
+    /// Returns a receipt that is unique to the transaction number.
+    fn boundary_receipt(tx_num: TxNumber) -> Receipt {
+        Receipt { success: tx_num % 2 == 0, cumulative_gas_used: tx_num, ..Default::default() }
+    }
+
+    #[test]
+    fn test_changeset_snapshots() {
+        // Changesets up to `snapshot_end` are moved to snapshots, the rest stays in the database.
+        let snapshot_end = 1u64;
+        let (address, other_address) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let storage_key = B256::with_last_byte(1);
+        let account = |nonce| Account { nonce, ..Default::default() };
+
+        // `address` and its storage slot change in every block, `other_address` only in block 1.
+        let account_changesets = (0..=2)
+            .map(|block| {
+                let mut changes = vec![AccountBeforeTx { address, info: Some(account(block + 1)) }];
+                if block == 1 {
+                    changes.push(AccountBeforeTx { address: other_address, info: None });
+                }
+                AccountChangeSetBlock(changes)
+            })
+            .collect::<Vec<_>>();
+        let storage_changesets = (0..=2)
+            .map(|block| {
+                let mut changes = vec![StorageBeforeTx {
+                    address,
+                    entry: StorageEntry::new(storage_key, U256::from(block + 1)),
+                }];
+                if block == 1 {
+                    changes.push(StorageBeforeTx {
+                        address: other_address,
+                        entry: StorageEntry::new(storage_key, U256::from(10)),
+                    });
+                }
+                StorageChangeSetBlock(changes)
+            })
+            .collect::<Vec<_>>();
+
+        let db = create_test_rw_db();
+        let snapshots_dir = tempfile::tempdir().unwrap();
+
+        // Setup data, the changesets of snapshotted blocks are never written to the database
+        db.update(|tx| -> Result<(), DatabaseError> {
+            for (block, changes) in account_changesets.iter().enumerate().skip(2) {
+                for change in &changes.0 {
+                    tx.put::<tables::AccountChangeSet>(block as u64, change.clone())?;
+                }
+            }
+            for (block, changes) in storage_changesets.iter().enumerate().skip(2) {
+                for change in &changes.0 {
+                    tx.put::<tables::StorageChangeSet>(
+                        (block as u64, change.address).into(),
+                        change.entry,
+                    )?;
+                }
+            }
+
+            tx.put::<tables::AccountHistory>(
+                ShardedKey::new(address, u64::MAX),
+                BlockNumberList::new([0, 1, 2]).unwrap(),
+            )?;
+            tx.put::<tables::AccountHistory>(
+                ShardedKey::new(other_address, u64::MAX),
+                BlockNumberList::new([1]).unwrap(),
+            )?;
+            tx.put::<tables::StorageHistory>(
+                StorageShardedKey::new(address, storage_key, u64::MAX),
+                BlockNumberList::new([0, 1, 2]).unwrap(),
+            )?;
+            tx.put::<tables::StorageHistory>(
+                StorageShardedKey::new(other_address, storage_key, u64::MAX),
+                BlockNumberList::new([1]).unwrap(),
+            )?;
+
+            // The hashed state and the trie are at block 2, so proofs revert the changesets
+            for id in [StageId::AccountHashing, StageId::StorageHashing, StageId::MerkleExecute] {
+                tx.put::<tables::SyncStage>(id.to_string(), StageCheckpoint::new(2))?;
+            }
+
+            tx.put::<tables::PlainAccountState>(address, account(4))?;
+            tx.put::<tables::PlainAccountState>(other_address, account(11))?;
+            tx.put::<tables::PlainStorageState>(
+                address,
+                StorageEntry::new(storage_key, U256::from(4)),
+            )?;
+            tx.put::<tables::PlainStorageState>(
+                other_address,
+                StorageEntry::new(storage_key, U256::from(11)),
+            )?;
+            Ok(())
+        })
+        .unwrap()
+        .unwrap();
+
+        // Create snapshots
+        let block_range = 0..=snapshot_end;
+        let mut accounts_jar = NippyJar::new(
+            1,
+            &snapshots_dir.path().join(SnapshotSegment::AccountChangeSets.filename(&block_range)),
+            SegmentHeader::new(block_range.clone(), 0..=0, SnapshotSegment::AccountChangeSets),
+        );
+        accounts_jar
+            .freeze(
+                vec![account_changesets[..=snapshot_end as usize]
+                    .iter()
+                    .map(|changes| -> ColumnResult<Vec<u8>> { Ok(changes.clone().compress()) })],
+                block_range.clone().count() as u64,
+            )
+            .unwrap();
+
+        let mut storages_jar = NippyJar::new(
+            1,
+            &snapshots_dir.path().join(SnapshotSegment::StorageChangeSets.filename(&block_range)),
+            SegmentHeader::new(block_range.clone(), 0..=0, SnapshotSegment::StorageChangeSets),
+        );
+        storages_jar
+            .freeze(
+                vec![storage_changesets[..=snapshot_end as usize]
+                    .iter()
+                    .map(|changes| -> ColumnResult<Vec<u8>> { Ok(changes.clone().compress()) })],
+                block_range.count() as u64,
+            )
+            .unwrap();
+
+        let snapshot_provider = SnapshotProvider::new(snapshots_dir.path()).unwrap();
+        let tx = db.tx().unwrap();
+
+        // Snapshotted, database and plain state lookups on both sides of the boundary
+        for block in 0..=3 {
+            let provider = HistoricalStateProviderRef::new(&tx, block)
+                .with_snapshot_provider(Some(&snapshot_provider));
+
+            assert_eq!(provider.basic_account(address).unwrap(), Some(account(block + 1)));
+            assert_eq!(
+                provider.storage(address, storage_key).unwrap(),
+                Some(U256::from(block + 1))
+            );
+
+            let (other_account, other_storage) = match block {
+                0 => (None, None),
+                1 => (None, Some(U256::from(10))),
+                _ => (Some(account(11)), Some(U256::from(11))),
+            };
+            assert_eq!(provider.basic_account(other_address).unwrap(), other_account);
+            assert_eq!(provider.storage(other_address, storage_key).unwrap(), other_storage);
+        }
+
+        // Without snapshots, the snapshotted changesets are missing
+        let provider = HistoricalStateProviderRef::new(&tx, 0);
+        assert!(provider.basic_account(address).is_err());
+        assert!(provider.storage(address, storage_key).is_err());
+
+        // Proofs revert the changesets of snapshotted blocks too
+        for block in 0..=2 {
+            let proof = HistoricalStateProviderRef::new(&tx, block)
+                .with_snapshot_provider(Some(&snapshot_provider))
+                .proof(address, &[storage_key])
+                .unwrap();
+            assert_eq!(proof.info, Some(account(block + 1)));
+            assert_eq!(proof.storage_proofs[0].value, U256::from(block + 1));
+        }
+        let proof = HistoricalStateProviderRef::new(&tx, 1)
+            .with_snapshot_provider(Some(&snapshot_provider))
+            .proof(other_address, &[storage_key])
+            .unwrap();
+        assert_eq!(proof.info, None);
+
+        // Without snapshots, only the changesets in the database are reverted
+        let proof = provider.proof(address, &[storage_key]).unwrap();
+        assert_eq!(proof.info, Some(account(3)));
+    }
 }
@@ -1,19 +1,35 @@
 use crate::{
-    providers::state::macros::delegate_provider_impls, AccountReader, BlockHashReader,
-    BundleStateWithReceipts, ProviderError, StateProvider, StateRootProvider,
+    providers::{
+        state::macros::delegate_provider_impls, SnapSyncedState, SnapshotJarProvider,
+        SnapshotProvider,
+    },
+    AccountReader, BlockHashReader, BundleStateWithReceipts, ProviderError, StateProvider,
+    StateRootProvider,
 };
+use itertools::Itertools;
 use reth_db::{
     cursor::{DbCursorRO, DbDupCursorRO},
-    models::{storage_sharded_key::StorageShardedKey, ShardedKey},
+    models::{storage_sharded_key::StorageShardedKey, BlockNumberAddress, ShardedKey},
     table::Table,
     tables,
     transaction::DbTx,
     BlockNumberList,
 };
-use reth_interfaces::RethResult;
+use reth_interfaces::{RethError, RethResult};
 use reth_primitives::{
-    trie::AccountProof, Account, Address, BlockNumber, Bytecode, StorageKey, StorageValue, B256,
+    stage::StageId, trie::AccountProof, Account, Address, BlockNumber, Bytecode, SnapshotSegment,
+    StorageEntry, StorageKey, StorageValue, B256,
 };
+use reth_trie::{
+    hashed_cursor::{HashedPostState, HashedPostStateCursorFactory},
+    proof::Proof,
+    StateRoot,
+};
+use std::sync::Arc;
+
+/// Maximum number of keys examined by a single [StateProvider::account_range] or
+/// [StateProvider::storage_range] call of a historical state provider.
+const MAX_RANGE_SCANNED_KEYS: usize = 10_000;
 
 /// State provider for a given block number which takes a tx reference.
 ///
@@ -26,6 +42,13 @@ use reth_primitives::{
 /// - [tables::StorageHistory]
 /// - [tables::AccountChangeSet]
 /// - [tables::StorageChangeSet]
+///
+/// If a snapshot provider is set, changesets of snapshotted blocks are read from the
+/// [SnapshotSegment::AccountChangeSets] and [SnapshotSegment::StorageChangeSets] snapshots
+/// instead.
+///
+/// If the state was synced via snap sync, accounts and storage slots that are missing in the plain
+/// state are read from the [SnapSyncedState].
 #[derive(Debug)]
 pub struct HistoricalStateProviderRef<'b, TX: DbTx> {
     /// Transaction
@@ -34,6 +57,10 @@ pub struct HistoricalStateProviderRef<'b, TX: DbTx> {
     block_number: BlockNumber,
     /// Lowest blocks at which different parts of the state are available.
     lowest_available_blocks: LowestAvailableBlocks,
+    /// Snapshots of the changesets, if any.
+    snapshot_provider: Option<&'b SnapshotProvider>,
+    /// The state below the plain state, if the state was synced via snap sync.
+    snap_synced_state: Option<&'b SnapSyncedState>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -47,7 +74,13 @@ pub enum HistoryInfo {
 impl<'b, TX: DbTx> HistoricalStateProviderRef<'b, TX> {
     /// Create new StateProvider for historical block number
     pub fn new(tx: &'b TX, block_number: BlockNumber) -> Self {
-        Self { tx, block_number, lowest_available_blocks: Default::default() }
+        Self {
+            tx,
+            block_number,
+            lowest_available_blocks: Default::default(),
+            snapshot_provider: None,
+            snap_synced_state: None,
+        }
     }
 
     /// Create new StateProvider for historical block number and lowest block numbers at which
@@ -57,7 +90,129 @@ impl<'b, TX: DbTx> HistoricalStateProviderRef<'b, TX> {
         block_number: BlockNumber,
         lowest_available_blocks: LowestAvailableBlocks,
     ) -> Self {
-        Self { tx, block_number, lowest_available_blocks }
+        Self {
+            tx,
+            block_number,
+            lowest_available_blocks,
+            snapshot_provider: None,
+            snap_synced_state: None,
+        }
+    }
+
+    /// Sets the snapshot provider that changesets of snapshotted blocks are read from.
+    pub fn with_snapshot_provider(
+        mut self,
+        snapshot_provider: Option<&'b SnapshotProvider>,
+    ) -> Self {
+        self.snapshot_provider = snapshot_provider;
+        self
+    }
+
+    /// Sets the snap synced state that accounts and storage slots missing in the plain state are
+    /// read from.
+    pub fn with_snap_synced_state(mut self, state: Option<&'b SnapSyncedState>) -> Self {
+        self.snap_synced_state = state;
+        self
+    }
+
+    /// Returns the provider of the changeset snapshot that contains the block, if it's
+    /// snapshotted.
+    fn changeset_snapshot(
+        &self,
+        segment: SnapshotSegment,
+        block_number: BlockNumber,
+    ) -> RethResult<Option<SnapshotJarProvider<'b>>> {
+        match self.snapshot_provider {
+            Some(snapshot_provider) => {
+                snapshot_provider.get_segment_provider_for_row(segment, block_number)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the block the hashed state and the state trie are at, checking that the changesets
+    /// from the provided block number up to it can be reverted on top of them.
+    ///
+    /// The canonical headers can't be used for this, since the pipeline writes them well ahead of
+    /// the hashing and merkle stages.
+    fn hashed_state_tip(&self) -> RethResult<BlockNumber> {
+        let checkpoint = |id: StageId| -> RethResult<BlockNumber> {
+            Ok(self
+                .tx
+                .get::<tables::SyncStage>(id.to_string())?
+                .map(|checkpoint| checkpoint.block_number)
+                .unwrap_or_default())
+        };
+
+        let tip = checkpoint(StageId::MerkleExecute)?;
+        if checkpoint(StageId::AccountHashing)? != tip ||
+            checkpoint(StageId::StorageHashing)? != tip ||
+            self.block_number > tip + 1
+        {
+            return Err(ProviderError::HashedStateNotAvailable(self.block_number).into())
+        }
+        Ok(tip)
+    }
+
+    /// Returns the post state that reverts the hashed state at the tip to the state at the start
+    /// of the provided block number.
+    ///
+    /// Changesets of snapshotted blocks are read only from the snapshots, since they're deleted
+    /// from the database once snapshotted.
+    fn reverted_hashed_state(&self, tip: BlockNumber) -> RethResult<HashedPostState> {
+        let (account_snapshot_changes, accounts_from) = self.snapshotted_changesets(
+            SnapshotSegment::AccountChangeSets,
+            tip,
+            |snapshot, block| snapshot.account_changeset(block),
+        )?;
+        let (storage_snapshot_changes, storages_from) = self.snapshotted_changesets(
+            SnapshotSegment::StorageChangeSets,
+            tip,
+            |snapshot, block| snapshot.storage_changeset(block),
+        )?;
+
+        let mut account_changeset_cursor = self.tx.cursor_read::<tables::AccountChangeSet>()?;
+        let mut storage_changeset_cursor = self.tx.cursor_read::<tables::StorageChangeSet>()?;
+        let hashed_state = HashedPostState::from_reverts(
+            account_snapshot_changes.into_iter().map(Ok).chain(
+                account_changeset_cursor
+                    .walk_range(accounts_from..=tip)?
+                    .map(|entry| entry.map(|(_, change)| change)),
+            ),
+            storage_snapshot_changes
+                .into_iter()
+                .map(|change| Ok((change.address, change.entry)))
+                .chain(
+                    storage_changeset_cursor
+                        .walk_range(BlockNumberAddress::range(storages_from..=tip))?
+                        .map(|entry| {
+                            entry
+                                .map(|(BlockNumberAddress((_, address)), change)| (address, change))
+                        }),
+                ),
+        )?;
+        Ok(hashed_state)
+    }
+
+    /// Reads the changesets of the segment from the provided block number up to the tip for as
+    /// long as the blocks are snapshotted.
+    ///
+    /// Snapshots always start at the genesis block, so the snapshotted blocks are a prefix of the
+    /// range. Returns the changes and the first block that is not snapshotted.
+    fn snapshotted_changesets<T>(
+        &self,
+        segment: SnapshotSegment,
+        tip: BlockNumber,
+        read: impl Fn(&SnapshotJarProvider<'b>, BlockNumber) -> RethResult<Vec<T>>,
+    ) -> RethResult<(Vec<T>, BlockNumber)> {
+        let mut changes = Vec::new();
+        let mut block = self.block_number;
+        while block <= tip {
+            let Some(snapshot) = self.changeset_snapshot(segment, block)? else { break };
+            changes.extend(read(&snapshot, block)?);
+            block += 1;
+        }
+        Ok((changes, block))
     }
 
     /// Lookup an account in the AccountHistory table
@@ -149,6 +304,84 @@ impl<'b, TX: DbTx> HistoricalStateProviderRef<'b, TX> {
             Ok(HistoryInfo::NotYetWritten)
         }
     }
+
+    /// Returns up to `limit` accounts that existed at the block, and the address the next page
+    /// starts at.
+    ///
+    /// Every such account is either still in the plain state or was changed after the block, in
+    /// which case it's in the account history index, so the addresses of both are visited. Most of
+    /// them may not exist at an early block, so the scan stops after `max_scanned` addresses, even
+    /// if fewer than `limit` accounts were found.
+    fn account_range_with_max_scanned(
+        &self,
+        start: Address,
+        limit: usize,
+        max_scanned: usize,
+    ) -> RethResult<(Vec<(Address, Account)>, Option<Address>)> {
+        if self.snap_synced_state.is_some() {
+            return Err(ProviderError::PlainStateIncomplete.into())
+        }
+        let mut plain_cursor = self.tx.cursor_read::<tables::PlainAccountState>()?;
+        let mut history_cursor = self.tx.cursor_read::<tables::AccountHistory>()?;
+        let addresses = merge_sorted_keys(
+            plain_cursor.walk(Some(start))?.map(|entry| entry.map(|(address, _)| address)),
+            history_cursor
+                .walk(Some(ShardedKey::new(start, 0)))?
+                .map(|entry| entry.map(|(key, _)| key.key)),
+        );
+
+        let mut accounts = Vec::new();
+        for (scanned, address) in addresses.enumerate() {
+            let address = address?;
+            if accounts.len() == limit || scanned == max_scanned {
+                return Ok((accounts, Some(address)))
+            }
+            if let Some(account) = self.basic_account(address)? {
+                accounts.push((address, account));
+            }
+        }
+        Ok((accounts, None))
+    }
+
+    /// Returns up to `limit` storage slots of the account that were non-zero at the block, and the
+    /// key the next page starts at.
+    ///
+    /// Like [Self::account_range_with_max_scanned], this visits the keys of both the plain state
+    /// and the storage history index, and stops after `max_scanned` keys.
+    fn storage_range_with_max_scanned(
+        &self,
+        address: Address,
+        start: StorageKey,
+        limit: usize,
+        max_scanned: usize,
+    ) -> RethResult<(Vec<StorageEntry>, Option<StorageKey>)> {
+        if self.snap_synced_state.is_some() {
+            return Err(ProviderError::PlainStateIncomplete.into())
+        }
+        let mut plain_cursor = self.tx.cursor_dup_read::<tables::PlainStorageState>()?;
+        let mut history_cursor = self.tx.cursor_read::<tables::StorageHistory>()?;
+        let keys = merge_sorted_keys(
+            plain_cursor
+                .walk_dup(Some(address), Some(start))?
+                .map(|entry| entry.map(|(_, entry)| entry.key)),
+            history_cursor
+                .walk(Some(StorageShardedKey::new(address, start, 0)))?
+                .take_while(|entry| entry.as_ref().map_or(true, |(key, _)| key.address == address))
+                .map(|entry| entry.map(|(key, _)| key.sharded_key.key)),
+        );
+
+        let mut entries = Vec::new();
+        for (scanned, key) in keys.enumerate() {
+            let key = key?;
+            if entries.len() == limit || scanned == max_scanned {
+                return Ok((entries, Some(key)))
+            }
+            if let Some(value) = self.storage(address, key)?.filter(|value| !value.is_zero()) {
+                entries.push(StorageEntry { key, value });
+            }
+        }
+        Ok((entries, None))
+    }
 }
 
 impl<'b, TX: DbTx> AccountReader for HistoricalStateProviderRef<'b, TX> {
@@ -156,18 +389,32 @@ impl<'b, TX: DbTx> AccountReader for HistoricalStateProviderRef<'b, TX> {
     fn basic_account(&self, address: Address) -> RethResult<Option<Account>> {
         match self.account_history_lookup(address)? {
             HistoryInfo::NotYetWritten => Ok(None),
-            HistoryInfo::InChangeset(changeset_block_number) => Ok(self
-                .tx
-                .cursor_dup_read::<tables::AccountChangeSet>()?
-                .seek_by_key_subkey(changeset_block_number, address)?
-                .filter(|acc| acc.address == address)
-                .ok_or(ProviderError::AccountChangesetNotFound {
-                    block_number: changeset_block_number,
-                    address,
-                })?
-                .info),
+            HistoryInfo::InChangeset(changeset_block_number) => {
+                let account_before = match self.changeset_snapshot(
+                    SnapshotSegment::AccountChangeSets,
+                    changeset_block_number,
+                )? {
+                    Some(snapshot) => snapshot.account_before(changeset_block_number, address)?,
+                    None => self
+                        .tx
+                        .cursor_dup_read::<tables::AccountChangeSet>()?
+                        .seek_by_key_subkey(changeset_block_number, address)?
+                        .filter(|acc| acc.address == address),
+                };
+
+                Ok(account_before
+                    .ok_or(ProviderError::AccountChangesetNotFound {
+                        block_number: changeset_block_number,
+                        address,
+                    })?
+                    .info)
+            }
             HistoryInfo::InPlainState | HistoryInfo::MaybeInPlainState => {
-                Ok(self.tx.get::<tables::PlainAccountState>(address)?)
+                let account = self.tx.get::<tables::PlainAccountState>(address)?;
+                match self.snap_synced_state {
+                    Some(state) if account.is_none() => state.basic_account(self.tx, address),
+                    _ => Ok(account),
+                }
             }
         }
     }
@@ -198,8 +445,29 @@ impl<'b, TX: DbTx> BlockHashReader for HistoricalStateProviderRef<'b, TX> {
 }
 
 impl<'b, TX: DbTx> StateRootProvider for HistoricalStateProviderRef<'b, TX> {
-    fn state_root(&self, _post_state: &BundleStateWithReceipts) -> RethResult<B256> {
-        Err(ProviderError::StateRootNotAvailableForHistoricalBlock.into())
+    /// Computes the state root of the post state on top of the state at the block.
+    ///
+    /// The changesets starting from the provided block number are reverted on top of the latest
+    /// hashed state and the post state is applied on top of the reverted one.
+    fn state_root(&self, post_state: &BundleStateWithReceipts) -> RethResult<B256> {
+        if !self.lowest_available_blocks.is_account_history_available(self.block_number) ||
+            !self.lowest_available_blocks.is_storage_history_available(self.block_number)
+        {
+            return Err(ProviderError::StateAtBlockPruned(self.block_number).into())
+        }
+
+        let tip = self.hashed_state_tip()?;
+        let mut hashed_state = self.reverted_hashed_state(tip)?;
+        hashed_state.extend(post_state.hash_state_slow());
+        hashed_state.sort();
+
+        let (account_prefix_set, storage_prefix_set) = hashed_state.construct_prefix_sets();
+        StateRoot::new(self.tx)
+            .with_hashed_cursor_factory(HashedPostStateCursorFactory::new(self.tx, &hashed_state))
+            .with_changed_account_prefixes(account_prefix_set)
+            .with_changed_storage_prefixes(storage_prefix_set)
+            .root()
+            .map_err(|err| RethError::Database(err.into()))
     }
 }
 
@@ -212,25 +480,46 @@ impl<'b, TX: DbTx> StateProvider for HistoricalStateProviderRef<'b, TX> {
     ) -> RethResult<Option<StorageValue>> {
         match self.storage_history_lookup(address, storage_key)? {
             HistoryInfo::NotYetWritten => Ok(None),
-            HistoryInfo::InChangeset(changeset_block_number) => Ok(Some(
-                self.tx
-                    .cursor_dup_read::<tables::StorageChangeSet>()?
-                    .seek_by_key_subkey((changeset_block_number, address).into(), storage_key)?
+            HistoryInfo::InChangeset(changeset_block_number) => {
+                let storage_before = match self.changeset_snapshot(
+                    SnapshotSegment::StorageChangeSets,
+                    changeset_block_number,
+                )? {
+                    Some(snapshot) => {
+                        snapshot.storage_before(changeset_block_number, address, storage_key)?
+                    }
+                    None => self
+                        .tx
+                        .cursor_dup_read::<tables::StorageChangeSet>()?
+                        .seek_by_key_subkey((changeset_block_number, address).into(), storage_key)?
+                        .filter(|entry| entry.key == storage_key),
+                };
+
+                Ok(Some(
+                    storage_before
+                        .ok_or(ProviderError::StorageChangesetNotFound {
+                            block_number: changeset_block_number,
+                            address,
+                            storage_key,
+                        })?
+                        .value,
+                ))
+            }
+            HistoryInfo::InPlainState | HistoryInfo::MaybeInPlainState => {
+                let value = self
+                    .tx
+                    .cursor_dup_read::<tables::PlainStorageState>()?
+                    .seek_by_key_subkey(address, storage_key)?
                     .filter(|entry| entry.key == storage_key)
-                    .ok_or(ProviderError::StorageChangesetNotFound {
-                        block_number: changeset_block_number,
-                        address,
-                        storage_key,
-                    })?
-                    .value,
-            )),
-            HistoryInfo::InPlainState | HistoryInfo::MaybeInPlainState => Ok(self
-                .tx
-                .cursor_dup_read::<tables::PlainStorageState>()?
-                .seek_by_key_subkey(address, storage_key)?
-                .filter(|entry| entry.key == storage_key)
-                .map(|entry| entry.value)
-                .or(Some(StorageValue::ZERO))),
+                    .map(|entry| entry.value);
+                let value = match self.snap_synced_state {
+                    Some(state) if value.is_none() => {
+                        state.storage(self.tx, address, storage_key)?
+                    }
+                    _ => value,
+                };
+                Ok(value.or(Some(StorageValue::ZERO)))
+            }
         }
     }
 
@@ -240,11 +529,62 @@ impl<'b, TX: DbTx> StateProvider for HistoricalStateProviderRef<'b, TX> {
     }
 
     /// Get account and storage proofs.
-    fn proof(&self, _address: Address, _keys: &[B256]) -> RethResult<AccountProof> {
-        Err(ProviderError::StateRootNotAvailableForHistoricalBlock.into())
+    ///
+    /// The proofs are generated by reverting the changesets starting from the provided block
+    /// number on top of the latest hashed state and trie.
+    fn proof(&self, address: Address, keys: &[B256]) -> RethResult<AccountProof> {
+        if !self.lowest_available_blocks.is_account_history_available(self.block_number) ||
+            !self.lowest_available_blocks.is_storage_history_available(self.block_number)
+        {
+            return Err(ProviderError::StateAtBlockPruned(self.block_number).into())
+        }
+
+        let reverted_state = self.reverted_hashed_state(self.hashed_state_tip()?)?;
+        Proof::historical_account_proof(self.tx, &reverted_state, address, keys)
+            .map_err(|err| RethError::Database(err.into()))
+    }
+
+    /// Returns up to `limit` accounts that existed at the block.
+    ///
+    /// At most `MAX_RANGE_SCANNED_KEYS` addresses are examined, so fewer accounts may be returned
+    /// alongside the next address.
+    fn account_range(
+        &self,
+        start: Address,
+        limit: usize,
+    ) -> RethResult<(Vec<(Address, Account)>, Option<Address>)> {
+        self.account_range_with_max_scanned(start, limit, MAX_RANGE_SCANNED_KEYS)
+    }
+
+    /// Returns up to `limit` storage slots of the account that were non-zero at the block.
+    ///
+    /// At most `MAX_RANGE_SCANNED_KEYS` keys are examined, so fewer slots may be returned alongside
+    /// the next key.
+    fn storage_range(
+        &self,
+        address: Address,
+        start: StorageKey,
+        limit: usize,
+    ) -> RethResult<(Vec<StorageEntry>, Option<StorageKey>)> {
+        self.storage_range_with_max_scanned(address, start, limit, MAX_RANGE_SCANNED_KEYS)
     }
 }
 
+/// Merges two iterators over keys in ascending order into one iterator over the keys of both, in
+/// ascending order and without duplicates.
+fn merge_sorted_keys<K: Ord, E>(
+    left: impl Iterator<Item = Result<K, E>>,
+    right: impl Iterator<Item = Result<K, E>>,
+) -> impl Iterator<Item = Result<K, E>> {
+    left.merge_by(right, |left, right| match (left, right) {
+        (Ok(left), Ok(right)) => left <= right,
+        // surface errors as early as possible
+        (Err(_), _) => true,
+        (_, Err(_)) => false,
+    })
+    .dedup_by(|left, right| matches!((left, right), (Ok(left), Ok(right)) if left == right))
+}
+
 /// State provider for a given block number.
 /// For more detailed description, see [HistoricalStateProviderRef].
 #[derive(Debug)]
@@ -255,12 +595,35 @@ pub struct HistoricalStateProvider<TX: DbTx> {
     block_number: BlockNumber,
     /// Lowest blocks at which different parts of the state are available.
     lowest_available_blocks: LowestAvailableBlocks,
+    /// Snapshots of the changesets, if any.
+    snapshot_provider: Option<Arc<SnapshotProvider>>,
+    /// The state below the plain state, if the state was synced via snap sync.
+    snap_synced_state: Option<Arc<SnapSyncedState>>,
 }
 
 impl<TX: DbTx> HistoricalStateProvider<TX> {
     /// Create new StateProvider for historical block number
     pub fn new(tx: TX, block_number: BlockNumber) -> Self {
-        Self { tx, block_number, lowest_available_blocks: Default::default() }
+        Self {
+            tx,
+            block_number,
+            lowest_available_blocks: Default::default(),
+            snapshot_provider: None,
+            snap_synced_state: None,
+        }
+    }
+
+    /// Set the snapshot provider that changesets of snapshotted blocks are read from.
+    pub fn with_snapshot_provider(mut self, snapshot_provider: Arc<SnapshotProvider>) -> Self {
+        self.snapshot_provider = Some(snapshot_provider);
+        self
+    }
+
+    /// Set the snap synced state that accounts and storage slots missing in the plain state are
+    /// read from.
+    pub fn with_snap_synced_state(mut self, state: Option<Arc<SnapSyncedState>>) -> Self {
+        self.snap_synced_state = state;
+        self
     }
 
     /// Set the lowest block number at which the account history is available.
@@ -289,6 +652,8 @@ impl<TX: DbTx> HistoricalStateProvider<TX> {
             self.block_number,
             self.lowest_available_blocks,
         )
+        .with_snapshot_provider(self.snapshot_provider.as_deref())
+        .with_snap_synced_state(self.snap_synced_state.as_deref())
     }
 }
 
@@ -549,6 +914,155 @@ mod tests {
         );
     }
 
+    #[test]
+    fn history_provider_account_range() {
+        let db = create_test_rw_db();
+        let tx = db.tx_mut().unwrap();
+
+        let acc_plain = Account { nonce: 100, balance: U256::ZERO, bytecode_hash: None };
+        let higher_acc_at6 = Account { nonce: 6, balance: U256::ZERO, bytecode_hash: None };
+
+        // `ADDRESS` is created at block 5, `HIGHER_ADDRESS` is created at block 2 and destroyed at
+        // block 6
+        tx.put::<tables::AccountHistory>(
+            ShardedKey { key: ADDRESS, highest_block_number: u64::MAX },
+            BlockNumberList::new([5]).unwrap(),
+        )
+        .unwrap();
+        tx.put::<tables::AccountHistory>(
+            ShardedKey { key: HIGHER_ADDRESS, highest_block_number: u64::MAX },
+            BlockNumberList::new([2, 6]).unwrap(),
+        )
+        .unwrap();
+        tx.put::<tables::AccountChangeSet>(
+            2,
+            AccountBeforeTx { address: HIGHER_ADDRESS, info: None },
+        )
+        .unwrap();
+        tx.put::<tables::AccountChangeSet>(5, AccountBeforeTx { address: ADDRESS, info: None })
+            .unwrap();
+        tx.put::<tables::AccountChangeSet>(
+            6,
+            AccountBeforeTx { address: HIGHER_ADDRESS, info: Some(higher_acc_at6) },
+        )
+        .unwrap();
+        tx.put::<tables::PlainAccountState>(ADDRESS, acc_plain).unwrap();
+        tx.commit().unwrap();
+
+        let tx = db.tx().unwrap();
+
+        assert_eq!(
+            HistoricalStateProviderRef::new(&tx, 3).account_range(Address::ZERO, 10),
+            Ok((vec![(HIGHER_ADDRESS, higher_acc_at6)], None))
+        );
+        assert_eq!(
+            HistoricalStateProviderRef::new(&tx, 7).account_range(Address::ZERO, 10),
+            Ok((vec![(ADDRESS, acc_plain)], None))
+        );
+        assert_eq!(
+            HistoricalStateProviderRef::new(&tx, 7).account_range(HIGHER_ADDRESS, 10),
+            Ok((vec![], None))
+        );
+        assert_eq!(
+            HistoricalStateProviderRef::new(&tx, 3).account_range(Address::ZERO, 0),
+            Ok((vec![], Some(ADDRESS)))
+        );
+        assert_eq!(
+            HistoricalStateProviderRef::new(&tx, 7).account_range(Address::ZERO, 1),
+            Ok((vec![(ADDRESS, acc_plain)], Some(HIGHER_ADDRESS)))
+        );
+
+        // The scan stops after the maximum number of examined addresses, even if `ADDRESS` didn't
+        // exist at block 3
+        assert_eq!(
+            HistoricalStateProviderRef::new(&tx, 3).account_range_with_max_scanned(
+                Address::ZERO,
+                10,
+                1
+            ),
+            Ok((vec![], Some(HIGHER_ADDRESS)))
+        );
+        assert_eq!(
+            HistoricalStateProviderRef::new(&tx, 3).account_range_with_max_scanned(
+                HIGHER_ADDRESS,
+                10,
+                1
+            ),
+            Ok((vec![(HIGHER_ADDRESS, higher_acc_at6)], None))
+        );
+    }
+
+    #[test]
+    fn history_provider_storage_range() {
+        let db = create_test_rw_db();
+        let tx = db.tx_mut().unwrap();
+
+        let higher_storage = B256::with_last_byte(5);
+        let entry_plain = StorageEntry { key: STORAGE, value: U256::from(100) };
+
+        // `STORAGE` is set at block 5, `higher_storage` is set at block 2 and cleared at block 6
+        tx.put::<tables::StorageHistory>(
+            StorageShardedKey {
+                address: ADDRESS,
+                sharded_key: ShardedKey { key: STORAGE, highest_block_number: u64::MAX },
+            },
+            BlockNumberList::new([5]).unwrap(),
+        )
+        .unwrap();
+        tx.put::<tables::StorageHistory>(
+            StorageShardedKey {
+                address: ADDRESS,
+                sharded_key: ShardedKey { key: higher_storage, highest_block_number: u64::MAX },
+            },
+            BlockNumberList::new([2, 6]).unwrap(),
+        )
+        .unwrap();
+        tx.put::<tables::StorageChangeSet>(
+            (2, ADDRESS).into(),
+            StorageEntry { key: higher_storage, value: U256::ZERO },
+        )
+        .unwrap();
+        tx.put::<tables::StorageChangeSet>(
+            (5, ADDRESS).into(),
+            StorageEntry { key: STORAGE, value: U256::ZERO },
+        )
+        .unwrap();
+        tx.put::<tables::StorageChangeSet>(
+            (6, ADDRESS).into(),
+            StorageEntry { key: higher_storage, value: U256::from(6) },
+        )
+        .unwrap();
+        tx.put::<tables::PlainStorageState>(ADDRESS, entry_plain).unwrap();
+        tx.commit().unwrap();
+
+        let tx = db.tx().unwrap();
+
+        assert_eq!(
+            HistoricalStateProviderRef::new(&tx, 3).storage_range(ADDRESS, B256::ZERO, 10),
+            Ok((vec![StorageEntry { key: higher_storage, value: U256::from(6) }], None))
+        );
+        assert_eq!(
+            HistoricalStateProviderRef::new(&tx, 7).storage_range(ADDRESS, B256::ZERO, 10),
+            Ok((vec![entry_plain], None))
+        );
+        assert_eq!(
+            HistoricalStateProviderRef::new(&tx, 7).storage_range(HIGHER_ADDRESS, B256::ZERO, 10),
+            Ok((vec![], None))
+        );
+
+        // The scan stops after the maximum number of examined keys, even if `STORAGE` was zero at
+        // block 3
+        assert_eq!(
+            HistoricalStateProviderRef::new(&tx, 3).storage_range_with_max_scanned(
+                ADDRESS,
+                B256::ZERO,
+                10,
+                1
+            ),
+            Ok((vec![], Some(higher_storage)))
+        );
+    }
+
     #[test]
     fn history_provider_unavailable() {
         let db = create_test_rw_db();
@@ -572,6 +1086,10 @@ mod tests {
             provider.storage_history_lookup(ADDRESS, STORAGE),
             Err(ProviderError::StateAtBlockPruned(provider.block_number).into())
         );
+        assert_eq!(
+            provider.proof(ADDRESS, &[STORAGE]),
+            Err(ProviderError::StateAtBlockPruned(provider.block_number).into())
+        );
 
         // provider block_number == lowest available block number,
         // i.e. state at provider block is available
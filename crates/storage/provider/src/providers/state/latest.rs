@@ -1,36 +1,211 @@
 use crate::{
     providers::state::macros::delegate_provider_impls, AccountReader, BlockHashReader,
-    BundleStateWithReceipts, StateProvider, StateRootProvider,
+    BundleStateWithReceipts, ProviderError, StateProvider, StateRootProvider,
 };
+use parking_lot::Mutex;
 use reth_db::{
     cursor::{DbCursorRO, DbDupCursorRO},
+    models::{AccountBeforeTx, BlockNumberAddress},
     tables,
     transaction::DbTx,
 };
-use reth_interfaces::{provider::ProviderError, RethError, RethResult};
+use reth_interfaces::{RethError, RethResult};
 use reth_primitives::{
-    keccak256, trie::AccountProof, Account, Address, BlockNumber, Bytecode, StorageKey,
-    StorageValue, B256,
+    keccak256,
+    stage::{StageCheckpoint, StageId},
+    trie::AccountProof,
+    Account, Address, BlockNumber, Bytecode, StorageEntry, StorageKey, StorageValue, B256,
 };
+use reth_trie::proof::Proof;
+use std::{collections::HashSet, sync::Arc};
+
+/// The state of a node that downloaded the state of a pivot block via snap sync instead of
+/// executing the blocks up to it.
+///
+/// The `snap` protocol only serves the hashed state, so the plain state of such a node only holds
+/// the accounts and storage slots that changed after the pivot block. All other accounts and
+/// storage slots are read from the hashed state instead.
+///
+/// While the pipeline executes blocks, the hashed state lags behind the plain state. The accounts
+/// and storage slots that were removed by blocks which are not hashed yet are tracked, so that
+/// they are not read from the outdated hashed state.
+///
+/// Storage slots that only exist in the hashed state are not part of the changesets, so the
+/// storage of an account that was destroyed by a block which is not hashed yet may still be read
+/// from the hashed state. This only affects `SELFDESTRUCT` before the Cancun hardfork.
+#[derive(Debug, Clone, Default)]
+pub struct SnapSyncedState {
+    /// Accounts that were removed by blocks that are not hashed yet.
+    removed_accounts: HashSet<Address>,
+    /// Storage slots that were removed by blocks that are not hashed yet.
+    removed_storage: HashSet<(Address, StorageKey)>,
+}
+
+impl SnapSyncedState {
+    /// Loads the snap synced state, returns [None] if the state was not synced via snap sync.
+    ///
+    /// This walks the changesets of all blocks that are executed but not hashed yet, see
+    /// [SnapSyncedStateCache] to only do this when the stage checkpoints change.
+    pub fn load<TX: DbTx>(tx: &TX) -> RethResult<Option<Self>> {
+        SnapSyncedCheckpoints::load(tx)?
+            .map(|checkpoints| Self::load_at(tx, checkpoints))
+            .transpose()
+    }
+
+    /// Loads the snap synced state for the given stage checkpoints.
+    fn load_at<TX: DbTx>(tx: &TX, checkpoints: SnapSyncedCheckpoints) -> RethResult<Self> {
+        let SnapSyncedCheckpoints { executed, account_hashing, storage_hashing } = checkpoints;
+        let mut state = Self::default();
+
+        if account_hashing < executed {
+            let mut changesets = tx.cursor_read::<tables::AccountChangeSet>()?;
+            for entry in changesets.walk_range(account_hashing + 1..=executed)? {
+                let (_, AccountBeforeTx { address, .. }) = entry?;
+                if tx.get::<tables::PlainAccountState>(address)?.is_none() {
+                    state.removed_accounts.insert(address);
+                }
+            }
+        }
+
+        if storage_hashing < executed {
+            let mut changesets = tx.cursor_read::<tables::StorageChangeSet>()?;
+            let mut plain_storage = tx.cursor_dup_read::<tables::PlainStorageState>()?;
+            for entry in
+                changesets.walk_range(BlockNumberAddress::range(storage_hashing + 1..=executed))?
+            {
+                let (BlockNumberAddress((_, address)), StorageEntry { key, .. }) = entry?;
+                if plain_storage
+                    .seek_by_key_subkey(address, key)?
+                    .filter(|entry| entry.key == key)
+                    .is_none()
+                {
+                    state.removed_storage.insert((address, key));
+                }
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Returns the account that is missing in the plain state.
+    pub(crate) fn basic_account<TX: DbTx>(
+        &self,
+        tx: &TX,
+        address: Address,
+    ) -> RethResult<Option<Account>> {
+        if self.removed_accounts.contains(&address) {
+            return Ok(None)
+        }
+        Ok(tx.get::<tables::HashedAccount>(keccak256(address))?)
+    }
+
+    /// Returns the storage slot that is missing in the plain state.
+    pub(crate) fn storage<TX: DbTx>(
+        &self,
+        tx: &TX,
+        address: Address,
+        storage_key: StorageKey,
+    ) -> RethResult<Option<StorageValue>> {
+        if self.removed_accounts.contains(&address) ||
+            self.removed_storage.contains(&(address, storage_key))
+        {
+            return Ok(None)
+        }
+        let hashed_slot = keccak256(storage_key);
+        Ok(tx
+            .cursor_dup_read::<tables::HashedStorage>()?
+            .seek_by_key_subkey(keccak256(address), hashed_slot)?
+            .filter(|entry| entry.key == hashed_slot)
+            .map(|entry| entry.value))
+    }
+}
+
+/// The stage checkpoints that the [SnapSyncedState] depends on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SnapSyncedCheckpoints {
+    executed: BlockNumber,
+    account_hashing: BlockNumber,
+    storage_hashing: BlockNumber,
+}
+
+impl SnapSyncedCheckpoints {
+    /// Loads the checkpoints, returns [None] if the state was not synced via snap sync.
+    fn load<TX: DbTx>(tx: &TX) -> RethResult<Option<Self>> {
+        let checkpoint = |id: StageId| -> RethResult<StageCheckpoint> {
+            Ok(tx.get::<tables::SyncStage>(id.to_string())?.unwrap_or_default())
+        };
+
+        if checkpoint(StageId::SnapSync)?.snap_sync_stage_checkpoint().is_none() {
+            return Ok(None)
+        }
+
+        Ok(Some(Self {
+            executed: checkpoint(StageId::Execution)?.block_number,
+            account_hashing: checkpoint(StageId::AccountHashing)?.block_number,
+            storage_hashing: checkpoint(StageId::StorageHashing)?.block_number,
+        }))
+    }
+}
+
+/// Caches the [SnapSyncedState] of the stage checkpoints it was last loaded at.
+///
+/// The checkpoints only change when the pipeline commits, so the changesets are walked once per
+/// commit instead of once per state provider.
+#[derive(Debug, Clone, Default)]
+pub struct SnapSyncedStateCache {
+    inner: Arc<Mutex<Option<(SnapSyncedCheckpoints, Arc<SnapSyncedState>)>>>,
+}
+
+impl SnapSyncedStateCache {
+    /// Returns the snap synced state as of the given transaction, returns [None] if the state was
+    /// not synced via snap sync.
+    pub fn load<TX: DbTx>(&self, tx: &TX) -> RethResult<Option<Arc<SnapSyncedState>>> {
+        let Some(checkpoints) = SnapSyncedCheckpoints::load(tx)? else { return Ok(None) };
+
+        if let Some((cached, state)) = self.inner.lock().as_ref() {
+            if *cached == checkpoints {
+                return Ok(Some(state.clone()))
+            }
+        }
+
+        // the lock is not held while loading, concurrent callers may load the same state
+        let state = Arc::new(SnapSyncedState::load_at(tx, checkpoints)?);
+        *self.inner.lock() = Some((checkpoints, state.clone()));
+        Ok(Some(state))
+    }
+}
 
 /// State provider over latest state that takes tx reference.
 #[derive(Debug)]
 pub struct LatestStateProviderRef<'b, TX: DbTx> {
     /// database transaction
     db: &'b TX,
+    /// The state below the plain state, if the state was synced via snap sync.
+    snap_synced_state: Option<&'b SnapSyncedState>,
 }
 
 impl<'b, TX: DbTx> LatestStateProviderRef<'b, TX> {
     /// Create new state provider
     pub fn new(db: &'b TX) -> Self {
-        Self { db }
+        Self { db, snap_synced_state: None }
+    }
+
+    /// Sets the snap synced state that accounts and storage slots missing in the plain state are
+    /// read from.
+    pub fn with_snap_synced_state(mut self, state: Option<&'b SnapSyncedState>) -> Self {
+        self.snap_synced_state = state;
+        self
     }
 }
 
 impl<'b, TX: DbTx> AccountReader for LatestStateProviderRef<'b, TX> {
     /// Get basic account information.
     fn basic_account(&self, address: Address) -> RethResult<Option<Account>> {
-        self.db.get::<tables::PlainAccountState>(address).map_err(Into::into)
+        let account = self.db.get::<tables::PlainAccountState>(address)?;
+        match self.snap_synced_state {
+            Some(state) if account.is_none() => state.basic_account(self.db, address),
+            _ => Ok(account),
+        }
     }
 }
 
@@ -77,7 +252,10 @@ impl<'b, TX: DbTx> StateProvider for LatestStateProviderRef<'b, TX> {
                 return Ok(Some(entry.value))
             }
         }
-        Ok(None)
+        match self.snap_synced_state {
+            Some(state) => state.storage(self.db, account, storage_key),
+            None => Ok(None),
+        }
     }
 
     /// Get account code by its hash
@@ -85,17 +263,48 @@ impl<'b, TX: DbTx> StateProvider for LatestStateProviderRef<'b, TX> {
         self.db.get::<tables::Bytecodes>(code_hash).map_err(Into::into)
     }
 
-    fn proof(&self, address: Address, _keys: &[B256]) -> RethResult<AccountProof> {
-        let _hashed_address = keccak256(address);
-        let _root = self
+    fn proof(&self, address: Address, keys: &[B256]) -> RethResult<AccountProof> {
+        Proof::new(self.db)
+            .account_proof(address, keys)
+            .map_err(|err| RethError::Database(err.into()))
+    }
+
+    fn account_range(
+        &self,
+        start: Address,
+        limit: usize,
+    ) -> RethResult<(Vec<(Address, Account)>, Option<Address>)> {
+        if self.snap_synced_state.is_some() {
+            return Err(ProviderError::PlainStateIncomplete.into())
+        }
+        let mut accounts = self
             .db
-            .cursor_read::<tables::Headers>()?
-            .last()?
-            .ok_or_else(|| ProviderError::HeaderNotFound(0.into()))?
-            .1
-            .state_root;
+            .cursor_read::<tables::PlainAccountState>()?
+            .walk(Some(start))?
+            .take(limit.saturating_add(1))
+            .collect::<Result<Vec<_>, _>>()?;
+        let next = (accounts.len() > limit).then(|| accounts.pop()).flatten();
+        Ok((accounts, next.map(|(address, _)| address)))
+    }
 
-        unimplemented!()
+    fn storage_range(
+        &self,
+        address: Address,
+        start: StorageKey,
+        limit: usize,
+    ) -> RethResult<(Vec<StorageEntry>, Option<StorageKey>)> {
+        if self.snap_synced_state.is_some() {
+            return Err(ProviderError::PlainStateIncomplete.into())
+        }
+        let mut entries = self
+            .db
+            .cursor_dup_read::<tables::PlainStorageState>()?
+            .walk_dup(Some(address), Some(start))?
+            .take(limit.saturating_add(1))
+            .map(|entry| entry.map(|(_, entry)| entry))
+            .collect::<Result<Vec<_>, _>>()?;
+        let next = (entries.len() > limit).then(|| entries.pop()).flatten();
+        Ok((entries, next.map(|entry| entry.key)))
     }
 }
 
@@ -104,18 +313,28 @@ impl<'b, TX: DbTx> StateProvider for LatestStateProviderRef<'b, TX> {
 pub struct LatestStateProvider<TX: DbTx> {
     /// database transaction
     db: TX,
+    /// The state below the plain state, if the state was synced via snap sync.
+    snap_synced_state: Option<Arc<SnapSyncedState>>,
 }
 
 impl<TX: DbTx> LatestStateProvider<TX> {
     /// Create new state provider
     pub fn new(db: TX) -> Self {
-        Self { db }
+        Self { db, snap_synced_state: None }
+    }
+
+    /// Sets the snap synced state that accounts and storage slots missing in the plain state are
+    /// read from.
+    pub fn with_snap_synced_state(mut self, state: Option<Arc<SnapSyncedState>>) -> Self {
+        self.snap_synced_state = state;
+        self
     }
 
     /// Returns a new provider that takes the `TX` as reference
     #[inline(always)]
     fn as_ref(&self) -> LatestStateProviderRef<'_, TX> {
         LatestStateProviderRef::new(&self.db)
+            .with_snap_synced_state(self.snap_synced_state.as_deref())
     }
 }
 
@@ -125,10 +344,52 @@ delegate_provider_impls!(LatestStateProvider<TX> where [TX: DbTx]);
 #[cfg(test)]
 mod tests {
     use super::*;
+    use reth_db::{database::Database, test_utils::create_test_rw_db, transaction::DbTxMut};
+    use reth_primitives::stage::SnapSyncCheckpoint;
 
     fn assert_state_provider<T: StateProvider>() {}
     #[allow(unused)]
     fn assert_latest_state_provider<T: DbTx>() {
         assert_state_provider::<LatestStateProvider<T>>();
     }
+
+    #[test]
+    fn snap_synced_state_cache() {
+        let db = create_test_rw_db();
+        let cache = SnapSyncedStateCache::default();
+        let removed = Address::with_last_byte(1);
+
+        // not snap synced
+        assert!(cache.load(&db.tx().unwrap()).unwrap().is_none());
+
+        let tx = db.tx_mut().unwrap();
+        let snap_sync =
+            StageCheckpoint::new(0).with_snap_sync_stage_checkpoint(SnapSyncCheckpoint {
+                complete: true,
+                ..Default::default()
+            });
+        tx.put::<tables::SyncStage>(StageId::SnapSync.to_string(), snap_sync).unwrap();
+        tx.put::<tables::SyncStage>(StageId::Execution.to_string(), StageCheckpoint::new(2))
+            .unwrap();
+        tx.put::<tables::AccountChangeSet>(
+            2,
+            AccountBeforeTx { address: removed, info: Some(Account::default()) },
+        )
+        .unwrap();
+        tx.commit().unwrap();
+
+        let state = cache.load(&db.tx().unwrap()).unwrap().unwrap();
+        assert!(state.removed_accounts.contains(&removed));
+        // the checkpoints didn't change, so the state is not loaded again
+        assert!(Arc::ptr_eq(&state, &cache.load(&db.tx().unwrap()).unwrap().unwrap()));
+
+        let tx = db.tx_mut().unwrap();
+        tx.put::<tables::SyncStage>(StageId::AccountHashing.to_string(), StageCheckpoint::new(2))
+            .unwrap();
+        tx.commit().unwrap();
+
+        let hashed = cache.load(&db.tx().unwrap()).unwrap().unwrap();
+        assert!(!Arc::ptr_eq(&state, &hashed));
+        assert!(hashed.removed_accounts.is_empty());
+    }
 }
@@ -44,6 +44,8 @@ macro_rules! delegate_provider_impls {
                 fn storage(&self, account: reth_primitives::Address, storage_key: reth_primitives::StorageKey) -> reth_interfaces::RethResult<Option<reth_primitives::StorageValue>>;
                 fn proof(&self, address: reth_primitives::Address, keys: &[reth_primitives::B256]) -> reth_interfaces::RethResult<reth_primitives::trie::AccountProof>;
                 fn bytecode_by_hash(&self, code_hash: reth_primitives::B256) -> reth_interfaces::RethResult<Option<reth_primitives::Bytecode>>;
+                fn account_range(&self, start: reth_primitives::Address, limit: usize) -> reth_interfaces::RethResult<(Vec<(reth_primitives::Address, reth_primitives::Account)>, Option<reth_primitives::Address>)>;
+                fn storage_range(&self, address: reth_primitives::Address, start: reth_primitives::StorageKey, limit: usize) -> reth_interfaces::RethResult<(Vec<reth_primitives::StorageEntry>, Option<reth_primitives::StorageKey>)>;
             }
         );
     }
@@ -1,19 +1,21 @@
 use crate::{
     bundle_state::BundleStateWithReceipts,
     traits::{BlockSource, ReceiptProvider},
-    AccountReader, BlockHashReader, BlockIdReader, BlockNumReader, BlockReader, BlockReaderIdExt,
-    BundleStateDataProvider, ChainSpecProvider, ChangeSetReader, EvmEnvProvider, HeaderProvider,
-    ReceiptProviderIdExt, StateProvider, StateProviderBox, StateProviderFactory, StateRootProvider,
-    TransactionVariant, TransactionsProvider, WithdrawalsProvider,
+    AccountReader, BadBlock, BadBlockReader, BlockHashReader, BlockIdReader, BlockNumReader,
+    BlockReader, BlockReaderIdExt, BundleStateDataProvider, ChainSpecProvider, ChangeSetReader,
+    EvmEnvProvider, HeaderProvider, HistoryReader, ReceiptProviderIdExt, StateProvider,
+    StateProviderBox, StateProviderFactory, StateRootProvider, TransactionVariant,
+    TransactionsProvider, WithdrawalsProvider,
 };
+use itertools::Itertools;
 use parking_lot::Mutex;
 use reth_db::models::{AccountBeforeTx, StoredBlockBodyIndices};
 use reth_interfaces::{provider::ProviderError, RethResult};
 use reth_primitives::{
     keccak256, trie::AccountProof, Account, Address, Block, BlockHash, BlockHashOrNumber, BlockId,
     BlockNumber, BlockWithSenders, Bytecode, Bytes, ChainInfo, ChainSpec, Header, Receipt,
-    SealedBlock, SealedHeader, StorageKey, StorageValue, TransactionMeta, TransactionSigned,
-    TransactionSignedNoHash, TxHash, TxNumber, B256, U256,
+    SealedBlock, SealedHeader, StorageEntry, StorageKey, StorageValue, TransactionMeta,
+    TransactionSigned, TransactionSignedNoHash, TxHash, TxNumber, B256, U256,
 };
 use revm::primitives::{BlockEnv, CfgEnv};
 use std::{
@@ -514,6 +516,39 @@ impl StateProvider for MockEthProvider {
     fn proof(&self, _address: Address, _keys: &[B256]) -> RethResult<AccountProof> {
         todo!()
     }
+
+    fn account_range(
+        &self,
+        start: Address,
+        limit: usize,
+    ) -> RethResult<(Vec<(Address, Account)>, Option<Address>)> {
+        let lock = self.accounts.lock();
+        let mut accounts = lock
+            .iter()
+            .filter(|(address, _)| **address >= start)
+            .map(|(address, account)| (*address, account.account))
+            .sorted_by_key(|(address, _)| *address);
+        let page = accounts.by_ref().take(limit).collect();
+        Ok((page, accounts.next().map(|(address, _)| address)))
+    }
+
+    fn storage_range(
+        &self,
+        address: Address,
+        start: StorageKey,
+        limit: usize,
+    ) -> RethResult<(Vec<StorageEntry>, Option<StorageKey>)> {
+        let lock = self.accounts.lock();
+        let mut entries = lock
+            .get(&address)
+            .into_iter()
+            .flat_map(|account| account.storage.iter())
+            .filter(|(key, value)| **key >= start && !value.is_zero())
+            .map(|(key, value)| StorageEntry { key: *key, value: *value })
+            .sorted_by_key(|entry| entry.key);
+        let page = entries.by_ref().take(limit).collect();
+        Ok((page, entries.next().map(|entry| entry.key)))
+    }
 }
 
 impl EvmEnvProvider for MockEthProvider {
@@ -639,6 +674,42 @@ impl WithdrawalsProvider for MockEthProvider {
     }
 }
 
+impl BadBlockReader for MockEthProvider {
+    fn bad_blocks(&self) -> RethResult<Vec<BadBlock>> {
+        Ok(Vec::default())
+    }
+}
+
+impl HistoryReader for MockEthProvider {
+    fn account_history_blocks(
+        &self,
+        _address: Address,
+        _range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>> {
+        Ok(Vec::default())
+    }
+
+    fn storage_history_blocks(
+        &self,
+        _address: Address,
+        _range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>> {
+        Ok(Vec::default())
+    }
+
+    fn trace_address_index_tip(&self) -> RethResult<Option<BlockNumber>> {
+        Ok(None)
+    }
+
+    fn trace_address_blocks(
+        &self,
+        _address: Address,
+        _range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>> {
+        Ok(Vec::default())
+    }
+}
+
 impl ChangeSetReader for MockEthProvider {
     fn account_block_changeset(
         &self,
@@ -1,11 +1,11 @@
 use crate::{
     bundle_state::BundleStateWithReceipts,
     traits::{BlockSource, ReceiptProvider},
-    AccountReader, BlockHashReader, BlockIdReader, BlockNumReader, BlockReader, BlockReaderIdExt,
-    ChainSpecProvider, ChangeSetReader, EvmEnvProvider, HeaderProvider, PruneCheckpointReader,
-    ReceiptProviderIdExt, StageCheckpointReader, StateProvider, StateProviderBox,
-    StateProviderFactory, StateRootProvider, TransactionVariant, TransactionsProvider,
-    WithdrawalsProvider,
+    AccountReader, BadBlock, BadBlockReader, BlockHashReader, BlockIdReader, BlockNumReader,
+    BlockReader, BlockReaderIdExt, ChainSpecProvider, ChangeSetReader, EvmEnvProvider,
+    HeaderProvider, HistoryReader, PruneCheckpointReader, ReceiptProviderIdExt,
+    StageCheckpointReader, StateProvider, StateProviderBox, StateProviderFactory,
+    StateRootProvider, TransactionVariant, TransactionsProvider, WithdrawalsProvider,
 };
 use reth_db::models::{AccountBeforeTx, StoredBlockBodyIndices};
 use reth_interfaces::RethResult;
@@ -14,7 +14,7 @@ use reth_primitives::{
     trie::AccountProof,
     Account, Address, Block, BlockHash, BlockHashOrNumber, BlockId, BlockNumber, Bytecode,
     ChainInfo, ChainSpec, Header, PruneCheckpoint, PruneSegment, Receipt, SealedBlock,
-    SealedHeader, StorageKey, StorageValue, TransactionMeta, TransactionSigned,
+    SealedHeader, StorageEntry, StorageKey, StorageValue, TransactionMeta, TransactionSigned,
     TransactionSignedNoHash, TxHash, TxNumber, B256, MAINNET, U256,
 };
 use revm::primitives::{BlockEnv, CfgEnv};
@@ -253,6 +253,42 @@ impl AccountReader for NoopProvider {
     }
 }
 
+impl BadBlockReader for NoopProvider {
+    fn bad_blocks(&self) -> RethResult<Vec<BadBlock>> {
+        Ok(Vec::default())
+    }
+}
+
+impl HistoryReader for NoopProvider {
+    fn account_history_blocks(
+        &self,
+        _address: Address,
+        _range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>> {
+        Ok(Vec::default())
+    }
+
+    fn storage_history_blocks(
+        &self,
+        _address: Address,
+        _range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>> {
+        Ok(Vec::default())
+    }
+
+    fn trace_address_index_tip(&self) -> RethResult<Option<BlockNumber>> {
+        Ok(None)
+    }
+
+    fn trace_address_blocks(
+        &self,
+        _address: Address,
+        _range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>> {
+        Ok(Vec::default())
+    }
+}
+
 impl ChangeSetReader for NoopProvider {
     fn account_block_changeset(
         &self,
@@ -284,6 +320,23 @@ impl StateProvider for NoopProvider {
     fn proof(&self, _address: Address, _keys: &[B256]) -> RethResult<AccountProof> {
         Ok(AccountProof::default())
     }
+
+    fn account_range(
+        &self,
+        _start: Address,
+        _limit: usize,
+    ) -> RethResult<(Vec<(Address, Account)>, Option<Address>)> {
+        Ok((Vec::new(), None))
+    }
+
+    fn storage_range(
+        &self,
+        _address: Address,
+        _start: StorageKey,
+        _limit: usize,
+    ) -> RethResult<(Vec<StorageEntry>, Option<StorageKey>)> {
+        Ok((Vec::new(), None))
+    }
 }
 
 impl EvmEnvProvider for NoopProvider {
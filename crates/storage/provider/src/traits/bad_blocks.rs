@@ -0,0 +1,10 @@
+use crate::BadBlock;
+use auto_impl::auto_impl;
+use reth_interfaces::RethResult;
+
+/// Client trait for fetching blocks that were recently rejected because they failed validation.
+#[auto_impl(&, Arc, Box)]
+pub trait BadBlockReader: Send + Sync {
+    /// Returns the recently rejected blocks, most recent first.
+    fn bad_blocks(&self) -> RethResult<Vec<BadBlock>>;
+}
@@ -3,10 +3,49 @@ use reth_db::models::BlockNumberAddress;
 use reth_interfaces::RethResult;
 use reth_primitives::{Address, BlockNumber, B256};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     ops::{Range, RangeInclusive},
 };
 
+/// History Reader
+#[auto_impl(&, Arc, Box)]
+pub trait HistoryReader: Send + Sync {
+    /// Returns all block numbers in the given range at which the account changed, according to the
+    /// account history index.
+    ///
+    /// The block numbers are returned in ascending order.
+    fn account_history_blocks(
+        &self,
+        address: Address,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>>;
+
+    /// Returns all block numbers in the given range at which any storage slot of the account
+    /// changed, according to the storage history index.
+    ///
+    /// The block numbers are returned in ascending order and are deduplicated.
+    fn storage_history_blocks(
+        &self,
+        address: Address,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>>;
+
+    /// Returns the highest block that is covered by the trace address index, or `None` if the
+    /// index is not maintained.
+    fn trace_address_index_tip(&self) -> RethResult<Option<BlockNumber>>;
+
+    /// Returns all block numbers in the given range in which the address appears in a call, create
+    /// or selfdestruct trace, according to the trace address index.
+    ///
+    /// The block numbers are returned in ascending order. Only blocks up to
+    /// [HistoryReader::trace_address_index_tip] are indexed.
+    fn trace_address_blocks(
+        &self,
+        address: Address,
+        range: RangeInclusive<BlockNumber>,
+    ) -> RethResult<Vec<BlockNumber>>;
+}
+
 /// History Writer
 #[auto_impl(&, Arc, Box)]
 pub trait HistoryWriter: Send + Sync {
@@ -36,6 +75,19 @@ pub trait HistoryWriter: Send + Sync {
         storage_transitions: BTreeMap<(Address, B256), Vec<u64>>,
     ) -> RethResult<()>;
 
+    /// Insert the addresses that appear in the traces of each block into the trace address index.
+    /// Used inside IndexTraceAddresses stage
+    fn insert_trace_address_index(
+        &self,
+        block_addresses: BTreeMap<BlockNumber, BTreeSet<Address>>,
+    ) -> RethResult<()>;
+
+    /// Unwind and clear trace address indices.
+    ///
+    /// Returns number of unwound block addresses.
+    fn unwind_trace_address_indices(&self, range: RangeInclusive<BlockNumber>)
+        -> RethResult<usize>;
+
     /// Read account/storage changesets and update account/storage history indices.
     fn update_history_indices(&self, range: RangeInclusive<BlockNumber>) -> RethResult<()>;
 }
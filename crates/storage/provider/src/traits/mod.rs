@@ -12,6 +12,9 @@ pub use block::{
     TransactionVariant,
 };
 
+mod bad_blocks;
+pub use bad_blocks::BadBlockReader;
+
 mod block_hash;
 pub use block_hash::BlockHashReader;
 
@@ -61,7 +64,7 @@ mod hashing;
 pub use hashing::HashingWriter;
 
 mod history;
-pub use history::HistoryWriter;
+pub use history::{HistoryReader, HistoryWriter};
 
 mod prune_checkpoint;
 pub use prune_checkpoint::{PruneCheckpointReader, PruneCheckpointWriter};
@@ -3,8 +3,8 @@ use crate::{BlockHashReader, BlockIdReader, BundleStateWithReceipts};
 use auto_impl::auto_impl;
 use reth_interfaces::{provider::ProviderError, RethResult};
 use reth_primitives::{
-    trie::AccountProof, Address, BlockHash, BlockId, BlockNumHash, BlockNumber, BlockNumberOrTag,
-    Bytecode, StorageKey, StorageValue, B256, KECCAK_EMPTY, U256,
+    trie::AccountProof, Account, Address, BlockHash, BlockId, BlockNumHash, BlockNumber,
+    BlockNumberOrTag, Bytecode, StorageEntry, StorageKey, StorageValue, B256, KECCAK_EMPTY, U256,
 };
 
 /// Type alias of boxed [StateProvider].
@@ -26,6 +26,29 @@ pub trait StateProvider: BlockHashReader + AccountReader + StateRootProvider + S
     /// Get account and storage proofs.
     fn proof(&self, address: Address, keys: &[B256]) -> RethResult<AccountProof>;
 
+    /// Returns up to `limit` existing accounts, in ascending order of their address, starting at
+    /// the `start` address, and the address the next page starts at, if there is one.
+    ///
+    /// Fewer than `limit` accounts may be returned alongside a next address, e.g. to bound the
+    /// number of keys a single call examines.
+    fn account_range(
+        &self,
+        start: Address,
+        limit: usize,
+    ) -> RethResult<(Vec<(Address, Account)>, Option<Address>)>;
+
+    /// Returns up to `limit` non-zero storage slots of the account, in ascending order of their
+    /// key, starting at the `start` key, and the key the next page starts at, if there is one.
+    ///
+    /// Like [StateProvider::account_range], fewer than `limit` slots may be returned alongside a
+    /// next key.
+    fn storage_range(
+        &self,
+        address: Address,
+        start: StorageKey,
+        limit: usize,
+    ) -> RethResult<(Vec<StorageEntry>, Option<StorageKey>)>;
+
     /// Get account code by its address.
     ///
     /// Returns `None` if the account doesn't exist or account is not a contract
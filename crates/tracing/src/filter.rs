@@ -0,0 +1,171 @@
+//! Runtime control of the log filter.
+
+use std::sync::{Mutex, OnceLock};
+use tracing_subscriber::{
+    filter::{Directive, LevelFilter, ParseError},
+    reload, EnvFilter,
+};
+
+/// The handle of the filter installed by [`stdout()`](crate::stdout).
+static LOG_FILTER: OnceLock<LogFilterHandle> = OnceLock::new();
+
+/// Returns the handle to the filter of the stdout layer, if it was built with
+/// [`stdout()`](crate::stdout).
+pub fn log_filter() -> Option<&'static LogFilterHandle> {
+    LOG_FILTER.get()
+}
+
+/// Installs the handle returned by [`log_filter()`], unless one is installed already.
+pub(crate) fn install_log_filter(handle: LogFilterHandle) {
+    let _ = LOG_FILTER.set(handle);
+}
+
+/// Errors returned when changing the log filter.
+#[derive(Debug, thiserror::Error)]
+pub enum LogFilterError {
+    /// The given directives are invalid.
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    /// The filter could not be replaced.
+    #[error(transparent)]
+    Reload(#[from] reload::Error),
+}
+
+/// A handle to change the [EnvFilter] of a layer at runtime.
+///
+/// The filter is made of the directives it was created with, which can be replaced with
+/// [LogFilterHandle::set_filter], overridden by a global verbosity level and per-target
+/// directives. All changes can be reverted with [LogFilterHandle::reset].
+pub struct LogFilterHandle {
+    /// Replaces the filter of the layer.
+    reload: Box<dyn Fn(EnvFilter) -> Result<(), reload::Error> + Send + Sync>,
+    /// The directives the filter was created with.
+    initial: String,
+    /// The current state of the filter.
+    state: Mutex<FilterState>,
+}
+
+impl LogFilterHandle {
+    /// Creates a new handle from the reload handle of a filter created with the given directives.
+    pub fn new<S: 'static>(initial: String, handle: reload::Handle<EnvFilter, S>) -> Self {
+        Self {
+            reload: Box::new(move |filter| handle.reload(filter)),
+            state: Mutex::new(FilterState { base: initial.clone(), ..Default::default() }),
+            initial,
+        }
+    }
+
+    /// Returns the directives of the current filter.
+    pub fn current(&self) -> String {
+        self.state.lock().unwrap().build().map(|filter| filter.to_string()).unwrap_or_default()
+    }
+
+    /// Sets the maximum level of events of all targets that don't have a more specific directive.
+    pub fn set_verbosity(&self, level: LevelFilter) -> Result<(), LogFilterError> {
+        self.update(|state| state.verbosity = Some(level))
+    }
+
+    /// Sets per-target directives, e.g. `net::session=trace,sync=debug`, that take precedence over
+    /// the other directives.
+    ///
+    /// This replaces previously set per-target directives, an empty string removes them.
+    pub fn set_vmodule(&self, directives: &str) -> Result<(), LogFilterError> {
+        self.update(|state| state.vmodule = directives.to_string())
+    }
+
+    /// Replaces the directives the filter was created with, keeping the verbosity and per-target
+    /// overrides.
+    pub fn set_filter(&self, directives: &str) -> Result<(), LogFilterError> {
+        self.update(|state| state.base = directives.to_string())
+    }
+
+    /// Reverts all changes, restoring the filter the handle was created with.
+    pub fn reset(&self) -> Result<(), LogFilterError> {
+        let initial = self.initial.clone();
+        self.update(|state| *state = FilterState { base: initial, ..Default::default() })
+    }
+
+    /// Applies the change to the state and replaces the filter.
+    ///
+    /// The state is left unchanged if the new filter is invalid.
+    fn update(&self, f: impl FnOnce(&mut FilterState)) -> Result<(), LogFilterError> {
+        let mut state = self.state.lock().unwrap();
+        let mut new_state = state.clone();
+        f(&mut new_state);
+        (self.reload)(new_state.build()?)?;
+        *state = new_state;
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for LogFilterHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogFilterHandle")
+            .field("initial", &self.initial)
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The components of a filter that can be changed at runtime.
+#[derive(Debug, Clone, Default)]
+struct FilterState {
+    /// The base directives.
+    base: String,
+    /// The global level override.
+    verbosity: Option<LevelFilter>,
+    /// The per-target directives.
+    vmodule: String,
+}
+
+impl FilterState {
+    /// Builds the filter from the base directives with the overrides applied.
+    fn build(&self) -> Result<EnvFilter, ParseError> {
+        let mut filter = EnvFilter::builder().parse(&self.base)?;
+        if let Some(level) = self.verbosity {
+            filter = filter.add_directive(level.into());
+        }
+        for directive in self.vmodule.split(',').filter(|directive| !directive.trim().is_empty()) {
+            filter = filter.add_directive(directive.trim().parse::<Directive>()?);
+        }
+        Ok(filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use tracing_subscriber::Registry;
+
+    fn directives(handle: &LogFilterHandle) -> BTreeSet<String> {
+        handle.current().split(',').map(str::to_string).collect()
+    }
+
+    fn expected(directives: &[&str]) -> BTreeSet<String> {
+        directives.iter().map(|directive| directive.to_string()).collect()
+    }
+
+    #[test]
+    fn set_and_reset_filter() {
+        let filter = EnvFilter::builder().parse("info,hyper=off").unwrap();
+        let initial = filter.to_string();
+        let (_layer, reload_handle) = reload::Layer::<_, Registry>::new(filter);
+        let handle = LogFilterHandle::new(initial, reload_handle);
+        assert_eq!(directives(&handle), expected(&["hyper=off", "info"]));
+
+        handle.set_verbosity(LevelFilter::DEBUG).unwrap();
+        handle.set_vmodule("net::session=trace").unwrap();
+        assert_eq!(directives(&handle), expected(&["hyper=off", "debug", "net::session=trace"]));
+
+        // invalid directives leave the filter unchanged
+        assert!(handle.set_vmodule("net::session=loud").is_err());
+        assert_eq!(directives(&handle), expected(&["hyper=off", "debug", "net::session=trace"]));
+
+        handle.set_vmodule("").unwrap();
+        assert_eq!(directives(&handle), expected(&["hyper=off", "debug"]));
+
+        handle.reset().unwrap();
+        assert_eq!(directives(&handle), expected(&["hyper=off", "info"]));
+    }
+}
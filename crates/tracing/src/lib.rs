@@ -7,6 +7,9 @@
 //! - [`journald()`]
 //!
 //! As well as a simple way to initialize a subscriber: [`init`].
+//!
+//! The filter of the [`stdout()`] layer can be changed at runtime through the handle returned by
+//! [`log_filter()`].
 
 #![doc(
     html_logo_url = "https://raw.githubusercontent.com/paradigmxyz/reth/main/assets/reth-docs.png",
@@ -21,9 +24,12 @@ use rolling_file::{RollingConditionBasic, RollingFileAppender};
 use std::path::Path;
 use tracing::Subscriber;
 use tracing_subscriber::{
-    filter::Directive, prelude::*, registry::LookupSpan, EnvFilter, Layer, Registry,
+    filter::Directive, prelude::*, registry::LookupSpan, reload, EnvFilter, Layer, Registry,
 };
 
+mod filter;
+pub use filter::{log_filter, LogFilterError, LogFilterHandle};
+
 // Re-export tracing crates
 pub use tracing;
 pub use tracing_subscriber;
@@ -38,13 +44,14 @@ pub fn init(layers: Vec<BoxedLayer<Registry>>) {
 
 /// Builds a new tracing layer that writes to stdout.
 ///
-/// The events are filtered by `default_directive`, unless overridden by `RUST_LOG`.
+/// The events are filtered by `default_directive`, unless overridden by `RUST_LOG`. The filter can
+/// be changed at runtime through [`log_filter()`].
 ///
 /// Colors can be disabled with `RUST_LOG_STYLE=never`, and event targets can be displayed with
 /// `RUST_LOG_TARGET=1`.
 pub fn stdout<S>(default_directive: impl Into<Directive>, color: &str) -> BoxedLayer<S>
 where
-    S: Subscriber,
+    S: Subscriber + 'static,
     for<'a> S: LookupSpan<'a>,
 {
     // TODO: Auto-detect
@@ -54,6 +61,9 @@ where
 
     let filter =
         EnvFilter::builder().with_default_directive(default_directive.into()).from_env_lossy();
+    let initial = filter.to_string();
+    let (filter, handle) = reload::Layer::new(filter);
+    filter::install_log_filter(LogFilterHandle::new(initial, handle));
 
     tracing_subscriber::fmt::layer()
         .with_ansi(with_ansi)
@@ -0,0 +1,485 @@
+//! A file-system backed blob store.
+
+use crate::blobstore::{BlobStore, BlobStoreError, BlobStoreSize, BlobTransactionSidecar};
+use alloy_rlp::{Decodable, Encodable};
+use parking_lot::{Mutex, RwLock};
+use reth_primitives::{TxHash, B256};
+use schnellru::{ByLength, LruMap};
+use std::{collections::HashSet, fmt, fs, io, path::PathBuf, sync::Arc};
+use tracing::{debug, trace};
+
+/// How many [BlobTransactionSidecar] to cache in memory by default.
+pub const DEFAULT_MAX_CACHED_BLOBS: u32 = 100;
+
+/// A blob store that stores blob data on disk.
+///
+/// Every sidecar is stored RLP encoded in its own file, named after the hash of the transaction.
+/// Recently accessed sidecars are kept in an in-memory LRU cache.
+///
+/// Deletes are deferred: [BlobStore::delete] only marks the transaction, the files of all marked
+/// transactions are removed in one batch on [BlobStore::cleanup].
+#[derive(Clone, Debug)]
+pub struct DiskFileBlobStore {
+    inner: Arc<DiskFileBlobStoreInner>,
+}
+
+impl DiskFileBlobStore {
+    /// Opens and initializes a new disk file blob store according to the given options.
+    pub fn open(
+        blob_dir: impl Into<PathBuf>,
+        opts: DiskFileBlobStoreConfig,
+    ) -> Result<Self, DiskFileBlobStoreError> {
+        let blob_dir = blob_dir.into();
+        let DiskFileBlobStoreConfig { max_cached_entries, open } = opts;
+        let inner = DiskFileBlobStoreInner::new(blob_dir, max_cached_entries);
+
+        match open {
+            OpenDiskFileBlobStore::Clear => inner.delete_all()?,
+            OpenDiskFileBlobStore::ReIndex => inner.reindex()?,
+        }
+
+        Ok(Self { inner: Arc::new(inner) })
+    }
+
+    #[cfg(test)]
+    fn is_cached(&self, tx: &B256) -> bool {
+        self.inner.blob_cache.lock().get(tx).is_some()
+    }
+
+    #[cfg(test)]
+    fn clear_cache(&self) {
+        self.inner.blob_cache.lock().clear()
+    }
+}
+
+impl BlobStore for DiskFileBlobStore {
+    fn insert(&self, tx: B256, data: BlobTransactionSidecar) -> Result<(), BlobStoreError> {
+        self.inner.insert_one(tx, data)
+    }
+
+    fn insert_all(&self, txs: Vec<(B256, BlobTransactionSidecar)>) -> Result<(), BlobStoreError> {
+        if txs.is_empty() {
+            return Ok(())
+        }
+        self.inner.insert_many(txs)
+    }
+
+    fn delete(&self, tx: B256) -> Result<(), BlobStoreError> {
+        self.inner.txs_to_delete.write().insert(tx);
+        Ok(())
+    }
+
+    fn delete_all(&self, txs: Vec<B256>) -> Result<(), BlobStoreError> {
+        self.inner.txs_to_delete.write().extend(txs);
+        Ok(())
+    }
+
+    fn cleanup(&self) {
+        let txs_to_delete = std::mem::take(&mut *self.inner.txs_to_delete.write());
+        if txs_to_delete.is_empty() {
+            return
+        }
+        self.inner.remove_files(txs_to_delete);
+    }
+
+    fn get(&self, tx: B256) -> Result<Option<BlobTransactionSidecar>, BlobStoreError> {
+        self.inner.get_one(tx)
+    }
+
+    fn get_all(
+        &self,
+        txs: Vec<B256>,
+    ) -> Result<Vec<(B256, BlobTransactionSidecar)>, BlobStoreError> {
+        if txs.is_empty() {
+            return Ok(Vec::new())
+        }
+        self.inner.get_all(txs)
+    }
+
+    fn get_exact(&self, txs: Vec<B256>) -> Result<Vec<BlobTransactionSidecar>, BlobStoreError> {
+        if txs.is_empty() {
+            return Ok(Vec::new())
+        }
+        self.inner.get_exact(txs)
+    }
+
+    fn data_size_hint(&self) -> Option<usize> {
+        Some(self.inner.size_tracker.data_size())
+    }
+
+    fn blobs_len(&self) -> usize {
+        self.inner.size_tracker.blobs_len()
+    }
+}
+
+struct DiskFileBlobStoreInner {
+    /// The directory the blob files are stored in.
+    blob_dir: PathBuf,
+    /// Cache of recently accessed sidecars.
+    blob_cache: Mutex<LruMap<TxHash, BlobTransactionSidecar, ByLength>>,
+    /// Tracks the size of the blob files on disk.
+    size_tracker: BlobStoreSize,
+    /// Guards access to the blob files.
+    file_lock: RwLock<()>,
+    /// Transactions whose blob files are removed on the next cleanup.
+    txs_to_delete: RwLock<HashSet<B256>>,
+}
+
+impl DiskFileBlobStoreInner {
+    /// Creates a new empty disk file blob store with the given maximum length of the blob cache.
+    fn new(blob_dir: PathBuf, max_length: u32) -> Self {
+        Self {
+            blob_dir,
+            blob_cache: Mutex::new(LruMap::new(ByLength::new(max_length))),
+            size_tracker: Default::default(),
+            file_lock: Default::default(),
+            txs_to_delete: Default::default(),
+        }
+    }
+
+    /// Removes all existing blob files and creates an empty blob directory.
+    fn delete_all(&self) -> Result<(), DiskFileBlobStoreError> {
+        match fs::remove_dir_all(&self.blob_dir) {
+            Ok(_) => {
+                debug!(target: "txpool::blob", blob_dir = ?self.blob_dir, "Removed blob store directory");
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(DiskFileBlobStoreError::Open(self.blob_dir.clone(), err)),
+        }
+        self.create_blob_dir()
+    }
+
+    /// Recovers the size of an existing blob directory, creating it if it doesn't exist.
+    fn reindex(&self) -> Result<(), DiskFileBlobStoreError> {
+        self.create_blob_dir()?;
+
+        let entries = fs::read_dir(&self.blob_dir)
+            .map_err(|err| DiskFileBlobStoreError::Open(self.blob_dir.clone(), err))?;
+
+        let (mut num_blobs, mut data_size) = (0, 0);
+        for entry in entries {
+            let entry =
+                entry.map_err(|err| DiskFileBlobStoreError::Open(self.blob_dir.clone(), err))?;
+            let is_blob_file =
+                entry.file_name().to_str().map_or(false, |name| name.parse::<TxHash>().is_ok());
+            if !is_blob_file {
+                continue
+            }
+            let metadata = entry
+                .metadata()
+                .map_err(|err| DiskFileBlobStoreError::Open(self.blob_dir.clone(), err))?;
+            if metadata.is_file() {
+                num_blobs += 1;
+                data_size += metadata.len() as usize;
+            }
+        }
+
+        debug!(target: "txpool::blob", blob_dir = ?self.blob_dir, num_blobs, data_size, "Reindexed blob store");
+        self.size_tracker.update_len(num_blobs);
+        self.size_tracker.add_size(data_size);
+        Ok(())
+    }
+
+    /// Creates the blob directory if it doesn't exist.
+    fn create_blob_dir(&self) -> Result<(), DiskFileBlobStoreError> {
+        debug!(target: "txpool::blob", blob_dir = ?self.blob_dir, "Creating blob store");
+        fs::create_dir_all(&self.blob_dir)
+            .map_err(|err| DiskFileBlobStoreError::Open(self.blob_dir.clone(), err))
+    }
+
+    /// Returns the path to the blob file for the given transaction hash.
+    #[inline]
+    fn blob_disk_file(&self, tx: B256) -> PathBuf {
+        self.blob_dir.join(format!("{tx:x}"))
+    }
+
+    fn insert_one(&self, tx: B256, data: BlobTransactionSidecar) -> Result<(), BlobStoreError> {
+        let mut buf = Vec::with_capacity(data.fields_len());
+        data.encode(&mut buf);
+        self.write_one_encoded(tx, &buf)?;
+        self.blob_cache.lock().insert(tx, data);
+        Ok(())
+    }
+
+    fn insert_many(&self, txs: Vec<(B256, BlobTransactionSidecar)>) -> Result<(), BlobStoreError> {
+        let encoded = txs
+            .iter()
+            .map(|(tx, data)| {
+                let mut buf = Vec::with_capacity(data.fields_len());
+                data.encode(&mut buf);
+                (*tx, buf)
+            })
+            .collect::<Vec<_>>();
+
+        {
+            let mut cache = self.blob_cache.lock();
+            for (tx, data) in txs {
+                cache.insert(tx, data);
+            }
+        }
+
+        let _lock = self.file_lock.write();
+        for (tx, data) in encoded {
+            self.write_file(tx, &data)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the encoded sidecar of the given transaction to disk.
+    fn write_one_encoded(&self, tx: B256, data: &[u8]) -> Result<(), DiskFileBlobStoreError> {
+        let _lock = self.file_lock.write();
+        self.write_file(tx, data)
+    }
+
+    /// Writes the file and updates the size tracker, the caller must hold the file lock.
+    fn write_file(&self, tx: B256, data: &[u8]) -> Result<(), DiskFileBlobStoreError> {
+        trace!(target: "txpool::blob", ?tx, "Writing blob file");
+        // a re-inserted transaction must not be removed by a pending cleanup
+        self.txs_to_delete.write().remove(&tx);
+
+        let path = self.blob_disk_file(tx);
+        let existing = fs::metadata(&path).ok().map(|metadata| metadata.len() as usize);
+        fs::write(&path, data).map_err(|err| DiskFileBlobStoreError::WriteFile(tx, path, err))?;
+
+        match existing {
+            Some(size) => self.size_tracker.sub_size(size),
+            None => self.size_tracker.inc_len(1),
+        }
+        self.size_tracker.add_size(data.len());
+        Ok(())
+    }
+
+    /// Removes the blob files of the given transactions.
+    fn remove_files(&self, txs: HashSet<B256>) {
+        {
+            let mut cache = self.blob_cache.lock();
+            for tx in &txs {
+                cache.remove(tx);
+            }
+        }
+
+        let (mut num_removed, mut size_removed) = (0, 0);
+        let _lock = self.file_lock.write();
+        for tx in txs {
+            let path = self.blob_disk_file(tx);
+            let size = fs::metadata(&path).map(|metadata| metadata.len() as usize);
+            match fs::remove_file(&path) {
+                Ok(_) => {
+                    num_removed += 1;
+                    size_removed += size.unwrap_or_default();
+                }
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => {
+                    let err = DiskFileBlobStoreError::DeleteFile(tx, path, err);
+                    debug!(target: "txpool::blob", %err, "Failed to delete blob file");
+                }
+            }
+        }
+
+        self.size_tracker.sub_len(num_removed);
+        self.size_tracker.sub_size(size_removed);
+    }
+
+    /// Retrieves the sidecar for the given transaction from the cache or from disk.
+    fn get_one(&self, tx: B256) -> Result<Option<BlobTransactionSidecar>, BlobStoreError> {
+        if let Some(blob) = self.blob_cache.lock().get(&tx) {
+            return Ok(Some(blob.clone()))
+        }
+
+        let Some(data) = self.read_one(tx)? else { return Ok(None) };
+        let blob = BlobTransactionSidecar::decode(&mut data.as_slice())?;
+        self.blob_cache.lock().insert(tx, blob.clone());
+        Ok(Some(blob))
+    }
+
+    fn get_all(
+        &self,
+        txs: Vec<B256>,
+    ) -> Result<Vec<(B256, BlobTransactionSidecar)>, BlobStoreError> {
+        let mut res = Vec::with_capacity(txs.len());
+        for tx in txs {
+            if let Some(blob) = self.get_one(tx)? {
+                res.push((tx, blob));
+            }
+        }
+        Ok(res)
+    }
+
+    fn get_exact(&self, txs: Vec<B256>) -> Result<Vec<BlobTransactionSidecar>, BlobStoreError> {
+        let mut res = Vec::with_capacity(txs.len());
+        for tx in txs {
+            res.push(self.get_one(tx)?.ok_or(BlobStoreError::MissingSidecar(tx))?);
+        }
+        Ok(res)
+    }
+
+    /// Reads the encoded sidecar of the given transaction from disk.
+    ///
+    /// Returns `None` if there's no blob file for the transaction.
+    fn read_one(&self, tx: B256) -> Result<Option<Vec<u8>>, DiskFileBlobStoreError> {
+        let path = self.blob_disk_file(tx);
+        let _lock = self.file_lock.read();
+        match fs::read(&path) {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(DiskFileBlobStoreError::ReadFile(tx, path, err)),
+        }
+    }
+}
+
+impl fmt::Debug for DiskFileBlobStoreInner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiskFileBlobStoreInner")
+            .field("blob_dir", &self.blob_dir)
+            .field("cached_blobs", &self.blob_cache.lock().len())
+            .field("size_tracker", &self.size_tracker)
+            .field("txs_to_delete", &self.txs_to_delete.read().len())
+            .finish()
+    }
+}
+
+/// Errors that can occur when interacting with a disk file blob store.
+#[derive(Debug, thiserror::Error)]
+pub enum DiskFileBlobStoreError {
+    /// Thrown during [DiskFileBlobStore::open] if the blob store directory can't be opened.
+    #[error("failed to open blobstore at {0:?}: {1}")]
+    Open(PathBuf, io::Error),
+    /// Failure while reading a blob file.
+    #[error("[{0}] failed to read blob file at {1:?}: {2}")]
+    ReadFile(TxHash, PathBuf, io::Error),
+    /// Failure while writing a blob file.
+    #[error("[{0}] failed to write blob file at {1:?}: {2}")]
+    WriteFile(TxHash, PathBuf, io::Error),
+    /// Failure while deleting a blob file.
+    #[error("[{0}] failed to delete blob file at {1:?}: {2}")]
+    DeleteFile(TxHash, PathBuf, io::Error),
+}
+
+impl From<DiskFileBlobStoreError> for BlobStoreError {
+    fn from(value: DiskFileBlobStoreError) -> Self {
+        BlobStoreError::Other(Box::new(value))
+    }
+}
+
+/// Configuration for a disk file blob store.
+#[derive(Debug, Clone)]
+pub struct DiskFileBlobStoreConfig {
+    /// The maximum number of blobs to keep in the in-memory blob cache.
+    pub max_cached_entries: u32,
+    /// How to open the blob store.
+    pub open: OpenDiskFileBlobStore,
+}
+
+impl Default for DiskFileBlobStoreConfig {
+    fn default() -> Self {
+        Self { max_cached_entries: DEFAULT_MAX_CACHED_BLOBS, open: Default::default() }
+    }
+}
+
+impl DiskFileBlobStoreConfig {
+    /// Set the maximum number of blobs to keep in the in-memory blob cache.
+    pub const fn with_max_cached_entries(mut self, max_cached_entries: u32) -> Self {
+        self.max_cached_entries = max_cached_entries;
+        self
+    }
+
+    /// Set how the blob store is opened.
+    pub const fn with_open(mut self, open: OpenDiskFileBlobStore) -> Self {
+        self.open = open;
+        self
+    }
+}
+
+/// How to open a disk file blob store.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OpenDiskFileBlobStore {
+    /// Clear everything in the blob store.
+    Clear,
+    /// Keep the existing blob files and recover the size of the blob store from them.
+    ///
+    /// This is the default, so that the sidecars survive a restart. Blobs of a previous run are
+    /// still deleted once their transactions are included in a canonical block.
+    #[default]
+    ReIndex,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::{
+        prelude::*,
+        strategy::{Strategy, ValueTree},
+        test_runner::TestRunner,
+    };
+
+    fn tmp_store() -> (DiskFileBlobStore, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DiskFileBlobStore::open(dir.path(), Default::default()).unwrap();
+        (store, dir)
+    }
+
+    fn rng_blobs(num: usize) -> Vec<(TxHash, BlobTransactionSidecar)> {
+        let mut runner = TestRunner::new(Default::default());
+        prop::collection::vec(any::<(TxHash, BlobTransactionSidecar)>(), num)
+            .new_tree(&mut runner)
+            .unwrap()
+            .current()
+    }
+
+    #[test]
+    fn disk_insert_all_get_all() {
+        let (store, _dir) = tmp_store();
+
+        let blobs = rng_blobs(10);
+        let all_hashes = blobs.iter().map(|(tx, _)| *tx).collect::<Vec<_>>();
+        store.insert_all(blobs.clone()).unwrap();
+        assert_eq!(store.blobs_len(), blobs.len());
+
+        // all cached
+        for (tx, blob) in &blobs {
+            assert!(store.is_cached(tx));
+            assert_eq!(store.get(*tx).unwrap().unwrap(), *blob);
+        }
+        let all = store.get_all(all_hashes.clone()).unwrap();
+        for (tx, blob) in all {
+            assert!(blobs.contains(&(tx, blob)), "missing blob {tx:?}");
+        }
+
+        // read from disk
+        store.clear_cache();
+        let exact = store.get_exact(all_hashes.clone()).unwrap();
+        assert_eq!(exact, blobs.iter().map(|(_, blob)| blob.clone()).collect::<Vec<_>>());
+
+        // deletes are deferred until cleanup
+        store.delete_all(all_hashes.clone()).unwrap();
+        assert_eq!(store.blobs_len(), blobs.len());
+        store.cleanup();
+        assert_eq!(store.blobs_len(), 0);
+        assert_eq!(store.data_size_hint(), Some(0));
+        assert!(store.get_all(all_hashes).unwrap().is_empty());
+    }
+
+    #[test]
+    fn disk_reindex_on_open() {
+        let (store, dir) = tmp_store();
+
+        let blobs = rng_blobs(5);
+        store.insert_all(blobs.clone()).unwrap();
+        let data_size = store.data_size_hint();
+        drop(store);
+
+        // the store is reindexed by default
+        let store = DiskFileBlobStore::open(dir.path(), Default::default()).unwrap();
+        assert_eq!(store.blobs_len(), blobs.len());
+        assert_eq!(store.data_size_hint(), data_size);
+        for (tx, blob) in &blobs {
+            assert!(!store.is_cached(tx));
+            assert_eq!(store.get(*tx).unwrap().unwrap(), *blob);
+        }
+
+        let config = DiskFileBlobStoreConfig::default().with_open(OpenDiskFileBlobStore::Clear);
+        let store = DiskFileBlobStore::open(dir.path(), config).unwrap();
+        assert_eq!(store.blobs_len(), 0);
+        assert!(store.get(blobs[0].0).unwrap().is_none());
+    }
+}
@@ -1,10 +1,7 @@
-use crate::blobstore::{BlobStore, BlobStoreError, BlobTransactionSidecar};
+use crate::blobstore::{BlobStore, BlobStoreError, BlobStoreSize, BlobTransactionSidecar};
 use parking_lot::RwLock;
 use reth_primitives::B256;
-use std::{
-    collections::HashMap,
-    sync::{atomic::AtomicUsize, Arc},
-};
+use std::{collections::HashMap, sync::Arc};
 
 /// An in-memory blob store.
 #[derive(Clone, Debug, Default)]
@@ -16,31 +13,14 @@ pub struct InMemoryBlobStore {
 struct InMemoryBlobStoreInner {
     /// Storage for all blob data.
     store: RwLock<HashMap<B256, BlobTransactionSidecar>>,
-    data_size: AtomicUsize,
-    num_blobs: AtomicUsize,
-}
-
-impl InMemoryBlobStoreInner {
-    #[inline]
-    fn add_size(&self, add: usize) {
-        self.data_size.fetch_add(add, std::sync::atomic::Ordering::Relaxed);
-    }
-
-    #[inline]
-    fn sub_size(&self, sub: usize) {
-        self.data_size.fetch_sub(sub, std::sync::atomic::Ordering::Relaxed);
-    }
-
-    fn update_len(&self, len: usize) {
-        self.num_blobs.store(len, std::sync::atomic::Ordering::Relaxed);
-    }
+    size_tracker: BlobStoreSize,
 }
 
 impl BlobStore for InMemoryBlobStore {
     fn insert(&self, tx: B256, data: BlobTransactionSidecar) -> Result<(), BlobStoreError> {
         let mut store = self.inner.store.write();
-        self.inner.add_size(insert_size(&mut store, tx, data));
-        self.inner.update_len(store.len());
+        self.inner.size_tracker.add_size(insert_size(&mut store, tx, data));
+        self.inner.size_tracker.update_len(store.len());
         Ok(())
     }
 
@@ -54,16 +34,16 @@ impl BlobStore for InMemoryBlobStore {
             let add = insert_size(&mut store, tx, data);
             total_add += add;
         }
-        self.inner.add_size(total_add);
-        self.inner.update_len(store.len());
+        self.inner.size_tracker.add_size(total_add);
+        self.inner.size_tracker.update_len(store.len());
         Ok(())
     }
 
     fn delete(&self, tx: B256) -> Result<(), BlobStoreError> {
         let mut store = self.inner.store.write();
         let sub = remove_size(&mut store, &tx);
-        self.inner.sub_size(sub);
-        self.inner.update_len(store.len());
+        self.inner.size_tracker.sub_size(sub);
+        self.inner.size_tracker.update_len(store.len());
         Ok(())
     }
 
@@ -76,8 +56,8 @@ impl BlobStore for InMemoryBlobStore {
         for tx in txs {
             total_sub += remove_size(&mut store, &tx);
         }
-        self.inner.sub_size(total_sub);
-        self.inner.update_len(store.len());
+        self.inner.size_tracker.sub_size(total_sub);
+        self.inner.size_tracker.update_len(store.len());
         Ok(())
     }
 
@@ -117,11 +97,11 @@ impl BlobStore for InMemoryBlobStore {
     }
 
     fn data_size_hint(&self) -> Option<usize> {
-        Some(self.inner.data_size.load(std::sync::atomic::Ordering::Relaxed))
+        Some(self.inner.size_tracker.data_size())
     }
 
     fn blobs_len(&self) -> usize {
-        self.inner.num_blobs.load(std::sync::atomic::Ordering::Relaxed)
+        self.inner.size_tracker.blobs_len()
     }
 }
 
@@ -1,11 +1,19 @@
 //! Storage for blob data of EIP4844 transactions.
 
+pub use disk::{
+    DiskFileBlobStore, DiskFileBlobStoreConfig, DiskFileBlobStoreError, OpenDiskFileBlobStore,
+    DEFAULT_MAX_CACHED_BLOBS,
+};
 pub use mem::InMemoryBlobStore;
 pub use noop::NoopBlobStore;
 use reth_primitives::{BlobTransactionSidecar, B256};
-use std::fmt;
+use std::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 pub use tracker::{BlobStoreCanonTracker, BlobStoreUpdates};
 
+mod disk;
 mod mem;
 mod noop;
 mod tracker;
@@ -16,6 +24,7 @@ mod tracker;
 /// finalization).
 ///
 /// Note: this is Clone because it is expected to be wrapped in an Arc.
+#[auto_impl::auto_impl(Arc)]
 pub trait BlobStore: fmt::Debug + Send + Sync + 'static {
     /// Inserts the blob sidecar into the store
     fn insert(&self, tx: B256, data: BlobTransactionSidecar) -> Result<(), BlobStoreError>;
@@ -29,6 +38,13 @@ pub trait BlobStore: fmt::Debug + Send + Sync + 'static {
     /// Deletes multiple blob sidecars from the store
     fn delete_all(&self, txs: Vec<B256>) -> Result<(), BlobStoreError>;
 
+    /// Performs any pending maintenance, like removing the sidecars of deleted transactions.
+    ///
+    /// Stores that delete sidecars eagerly don't need to do anything here. This is invoked
+    /// periodically by the pool maintenance task, e.g. after blobs of finalized blocks were
+    /// deleted.
+    fn cleanup(&self) {}
+
     /// Retrieves the decoded blob data for the given transaction hash.
     fn get(&self, tx: B256) -> Result<Option<BlobTransactionSidecar>, BlobStoreError>;
 
@@ -54,6 +70,64 @@ pub trait BlobStore: fmt::Debug + Send + Sync + 'static {
     fn blobs_len(&self) -> usize;
 }
 
+/// Keeps track of the size of the blob store.
+///
+/// This is used by [BlobStore] implementations to report [BlobStore::data_size_hint] and
+/// [BlobStore::blobs_len], which are tracked by the pool's blob store metrics.
+#[derive(Debug, Default)]
+pub struct BlobStoreSize {
+    data_size: AtomicUsize,
+    num_blobs: AtomicUsize,
+}
+
+impl BlobStoreSize {
+    /// Increases the tracked data size by the given number of bytes.
+    #[inline]
+    pub fn add_size(&self, add: usize) {
+        self.data_size.fetch_add(add, Ordering::Relaxed);
+    }
+
+    /// Decreases the tracked data size by the given number of bytes.
+    #[inline]
+    pub fn sub_size(&self, sub: usize) {
+        let _ = self.data_size.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |size| {
+            Some(size.saturating_sub(sub))
+        });
+    }
+
+    /// Sets the number of blobs in the store.
+    #[inline]
+    pub fn update_len(&self, len: usize) {
+        self.num_blobs.store(len, Ordering::Relaxed);
+    }
+
+    /// Increases the number of blobs in the store by the given number.
+    #[inline]
+    pub fn inc_len(&self, add: usize) {
+        self.num_blobs.fetch_add(add, Ordering::Relaxed);
+    }
+
+    /// Decreases the number of blobs in the store by the given number.
+    #[inline]
+    pub fn sub_len(&self, sub: usize) {
+        let _ = self.num_blobs.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |len| {
+            Some(len.saturating_sub(sub))
+        });
+    }
+
+    /// Returns the tracked data size in bytes.
+    #[inline]
+    pub fn data_size(&self) -> usize {
+        self.data_size.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of blobs in the store.
+    #[inline]
+    pub fn blobs_len(&self) -> usize {
+        self.num_blobs.load(Ordering::Relaxed)
+    }
+}
+
 /// Error variants that can occur when interacting with a blob store.
 #[derive(Debug, thiserror::Error)]
 pub enum BlobStoreError {
@@ -172,6 +172,8 @@ pub async fn maintain_transaction_pool<Client, P, St, Tasks>(
                 BlobStoreUpdates::Finalized(blobs) => {
                     // remove all finalized blobs from the blob store
                     pool.delete_blobs(blobs);
+                    // and cleanup all deleted blobs
+                    pool.cleanup_blobs();
                 }
             }
         }
@@ -2,11 +2,18 @@ use super::{HashedAccountCursor, HashedCursorFactory, HashedStorageCursor};
 use crate::prefix_set::{PrefixSet, PrefixSetMut};
 use reth_db::{
     cursor::{DbCursorRO, DbDupCursorRO},
+    models::{AccountBeforeTx, BlockNumberAddress},
     tables,
     transaction::{DbTx, DbTxGAT},
+    DatabaseError,
+};
+use reth_primitives::{
+    keccak256, trie::Nibbles, Account, Address, BlockNumber, StorageEntry, B256, U256,
+};
+use std::{
+    collections::{hash_map, HashMap, HashSet},
+    ops::RangeInclusive,
 };
-use reth_primitives::{trie::Nibbles, Account, StorageEntry, B256, U256};
-use std::collections::{HashMap, HashSet};
 
 /// The post state account storage with hashed slots.
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -51,6 +58,29 @@ impl HashedStorage {
     pub fn insert_zero_valued_slot(&mut self, slot: B256) {
         self.zero_valued_slots.insert(slot);
     }
+
+    /// Extend the storage with the slots of another one, which take precedence.
+    ///
+    /// If the other storage was wiped, it replaces this one entirely.
+    pub fn extend(&mut self, other: Self) {
+        if other.wiped {
+            *self = other;
+            return
+        }
+
+        let updated_slots = other
+            .non_zero_valued_storage
+            .iter()
+            .map(|(slot, _)| *slot)
+            .chain(other.zero_valued_slots.iter().copied())
+            .collect::<HashSet<_>>();
+        self.non_zero_valued_storage.retain(|(slot, _)| !updated_slots.contains(slot));
+        self.zero_valued_slots.retain(|slot| !updated_slots.contains(slot));
+
+        self.non_zero_valued_storage.extend(other.non_zero_valued_storage);
+        self.zero_valued_slots.extend(other.zero_valued_slots);
+        self.sorted = false;
+    }
 }
 
 /// The post state with hashed addresses as keys.
@@ -78,6 +108,103 @@ impl Default for HashedPostState {
 }
 
 impl HashedPostState {
+    /// Initialize [HashedPostState] from the account and storage changesets in the given block
+    /// range.
+    ///
+    /// Applying the resulting post state on top of the current hashed state reverts it to the
+    /// state at the start of the first block in the range. See [Self::from_reverts].
+    pub fn from_revert_range<TX: DbTx>(
+        tx: &TX,
+        range: RangeInclusive<BlockNumber>,
+    ) -> Result<Self, DatabaseError> {
+        let mut account_changeset_cursor = tx.cursor_read::<tables::AccountChangeSet>()?;
+        let mut storage_changeset_cursor = tx.cursor_read::<tables::StorageChangeSet>()?;
+        Self::from_reverts(
+            account_changeset_cursor
+                .walk_range(range.clone())?
+                .map(|entry| entry.map(|(_, change)| change)),
+            storage_changeset_cursor.walk_range(BlockNumberAddress::range(range))?.map(|entry| {
+                entry.map(|(BlockNumberAddress((_, address)), change)| (address, change))
+            }),
+        )
+    }
+
+    /// Initialize [HashedPostState] from account and storage changeset entries, ordered by the
+    /// block they belong to.
+    ///
+    /// Only the first entry of each account and storage slot is taken into account, since it
+    /// holds the value prior to the changes.
+    pub fn from_reverts<E>(
+        account_changes: impl IntoIterator<Item = Result<AccountBeforeTx, E>>,
+        storage_changes: impl IntoIterator<Item = Result<(Address, StorageEntry), E>>,
+    ) -> Result<Self, E> {
+        // Collect the values of the changed accounts prior to the changes.
+        let mut accounts = HashMap::<Address, Option<Account>>::default();
+        for entry in account_changes {
+            let AccountBeforeTx { address, info } = entry?;
+            if let hash_map::Entry::Vacant(entry) = accounts.entry(address) {
+                entry.insert(info);
+            }
+        }
+
+        // Collect the values of the changed storage slots prior to the changes.
+        let mut storages = HashMap::<Address, HashMap<B256, U256>>::default();
+        for entry in storage_changes {
+            let (address, StorageEntry { key, value }) = entry?;
+            if let hash_map::Entry::Vacant(entry) = storages.entry(address).or_default().entry(key)
+            {
+                entry.insert(value);
+            }
+        }
+
+        let mut hashed_state = Self::default();
+        for (address, info) in accounts {
+            let hashed_address = keccak256(address);
+            match info {
+                Some(account) => hashed_state.insert_account(hashed_address, account),
+                None => hashed_state.insert_cleared_account(hashed_address),
+            }
+        }
+        for (address, storage) in storages {
+            let mut hashed_storage = HashedStorage::new(false);
+            for (slot, value) in storage {
+                let hashed_slot = keccak256(slot);
+                if value == U256::ZERO {
+                    hashed_storage.insert_zero_valued_slot(hashed_slot);
+                } else {
+                    hashed_storage.insert_non_zero_valued_storage(hashed_slot, value);
+                }
+            }
+            hashed_state.insert_hashed_storage(keccak256(address), hashed_storage);
+        }
+        Ok(hashed_state.sorted())
+    }
+
+    /// Extend the post state with the entries of another one, which take precedence.
+    ///
+    /// This allows layering the changes of a block on top of a reverted state, e.g. to compute
+    /// the state root after executing a block on top of a historical state.
+    pub fn extend(&mut self, other: Self) {
+        let updated_accounts = other
+            .accounts
+            .iter()
+            .map(|(hashed_address, _)| *hashed_address)
+            .chain(other.cleared_accounts.iter().copied())
+            .collect::<HashSet<_>>();
+        self.accounts.retain(|(hashed_address, _)| !updated_accounts.contains(hashed_address));
+        self.cleared_accounts.retain(|hashed_address| !updated_accounts.contains(hashed_address));
+        self.accounts.extend(other.accounts);
+        self.cleared_accounts.extend(other.cleared_accounts);
+
+        for (hashed_address, storage) in other.storages {
+            self.storages
+                .entry(hashed_address)
+                .or_insert_with(|| HashedStorage::new(false))
+                .extend(storage);
+        }
+        self.sorted = false;
+    }
+
     /// Sort and return self.
     pub fn sorted(mut self) -> Self {
         self.sort();
@@ -117,6 +244,16 @@ impl HashedPostState {
     /// The prefix sets contain the hashed account and storage keys that have been changed in the
     /// post state.
     pub fn construct_prefix_sets(&self) -> (PrefixSet, HashMap<B256, PrefixSet>) {
+        let (account_prefix_set, storage_prefix_sets) = self.construct_prefix_sets_mut();
+        (
+            account_prefix_set.freeze(),
+            storage_prefix_sets.into_iter().map(|(k, v)| (k, v.freeze())).collect(),
+        )
+    }
+
+    /// Construct mutable (PrefixSetMut)[PrefixSetMut] from hashed post state.
+    /// See [HashedPostState::construct_prefix_sets] for more info.
+    pub fn construct_prefix_sets_mut(&self) -> (PrefixSetMut, HashMap<B256, PrefixSetMut>) {
         // Initialize prefix sets.
         let mut account_prefix_set = PrefixSetMut::default();
         let mut storage_prefix_set: HashMap<B256, PrefixSetMut> = HashMap::default();
@@ -142,10 +279,7 @@ impl HashedPostState {
             }
         }
 
-        (
-            account_prefix_set.freeze(),
-            storage_prefix_set.into_iter().map(|(k, v)| (k, v.freeze())).collect(),
-        )
+        (account_prefix_set, storage_prefix_set)
     }
 }
 
@@ -976,6 +1110,59 @@ mod tests {
         assert_storage_cursor_order(&factory, expected);
     }
 
+    #[test]
+    fn extended_post_state_takes_precedence() {
+        let address = B256::with_last_byte(1);
+        let cleared_address = B256::with_last_byte(2);
+        let wiped_address = B256::with_last_byte(3);
+        let (slot1, slot2, slot3) =
+            (B256::with_last_byte(1), B256::with_last_byte(2), B256::with_last_byte(3));
+
+        let mut hashed_post_state = HashedPostState::default();
+        hashed_post_state.insert_account(address, Account { nonce: 1, ..Default::default() });
+        hashed_post_state.insert_account(cleared_address, Account::default());
+        let mut storage = HashedStorage::new(false);
+        storage.insert_non_zero_valued_storage(slot1, U256::from(1));
+        storage.insert_zero_valued_slot(slot2);
+        hashed_post_state.insert_hashed_storage(address, storage);
+        let mut storage = HashedStorage::new(false);
+        storage.insert_non_zero_valued_storage(slot1, U256::from(1));
+        hashed_post_state.insert_hashed_storage(wiped_address, storage);
+
+        let mut other = HashedPostState::default();
+        other.insert_account(address, Account { nonce: 2, ..Default::default() });
+        other.insert_cleared_account(cleared_address);
+        let mut storage = HashedStorage::new(false);
+        storage.insert_zero_valued_slot(slot1);
+        storage.insert_non_zero_valued_storage(slot2, U256::from(2));
+        storage.insert_non_zero_valued_storage(slot3, U256::from(3));
+        other.insert_hashed_storage(address, storage);
+        let mut storage = HashedStorage::new(true);
+        storage.insert_non_zero_valued_storage(slot2, U256::from(2));
+        other.insert_hashed_storage(wiped_address, storage);
+
+        hashed_post_state.extend(other);
+        hashed_post_state.sort();
+
+        assert_eq!(
+            hashed_post_state.accounts,
+            Vec::from([(address, Account { nonce: 2, ..Default::default() })])
+        );
+        assert_eq!(hashed_post_state.cleared_accounts, HashSet::from([cleared_address]));
+
+        let storage = &hashed_post_state.storages[&address];
+        assert!(!storage.wiped);
+        assert_eq!(
+            storage.non_zero_valued_storage,
+            Vec::from([(slot2, U256::from(2)), (slot3, U256::from(3))])
+        );
+        assert_eq!(storage.zero_valued_slots, HashSet::from([slot1]));
+
+        let storage = &hashed_post_state.storages[&wiped_address];
+        assert!(storage.wiped);
+        assert_eq!(storage.non_zero_valued_storage, Vec::from([(slot2, U256::from(2))]));
+    }
+
     #[test]
     fn fuzz_hashed_storage_cursor() {
         proptest!(ProptestConfig::with_cases(10),
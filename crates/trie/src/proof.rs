@@ -1,6 +1,8 @@
 use crate::{
     account::EthAccount,
-    hashed_cursor::{HashedCursorFactory, HashedStorageCursor},
+    hashed_cursor::{
+        HashedCursorFactory, HashedPostState, HashedPostStateCursorFactory, HashedStorageCursor,
+    },
     node_iter::{AccountNode, AccountNodeIter, StorageNode, StorageNodeIter},
     prefix_set::PrefixSetMut,
     trie_cursor::{AccountTrieCursor, StorageTrieCursor},
@@ -13,8 +15,9 @@ use reth_primitives::{
     constants::EMPTY_ROOT_HASH,
     keccak256,
     trie::{AccountProof, HashBuilder, Nibbles, StorageProof},
-    Address, B256,
+    Address, Bytes, B256,
 };
+use std::collections::{BTreeMap, HashMap};
 
 /// A struct for generating merkle proofs.
 ///
@@ -27,12 +30,68 @@ pub struct Proof<'a, TX, H> {
     tx: &'a TX,
     /// The factory for hashed cursors.
     hashed_cursor_factory: H,
+    /// A set of account prefixes that have changed relative to the intermediate trie nodes.
+    changed_account_prefixes: PrefixSetMut,
+    /// A map containing storage changes with the hashed address as key and a set of storage key
+    /// prefixes as the value.
+    changed_storage_prefixes: HashMap<B256, PrefixSetMut>,
+}
+
+impl<'a, TX, H> Proof<'a, TX, H> {
+    /// Set the changed account prefixes.
+    pub fn with_changed_account_prefixes(mut self, prefixes: PrefixSetMut) -> Self {
+        self.changed_account_prefixes = prefixes;
+        self
+    }
+
+    /// Set the changed storage prefixes.
+    pub fn with_changed_storage_prefixes(mut self, prefixes: HashMap<B256, PrefixSetMut>) -> Self {
+        self.changed_storage_prefixes = prefixes;
+        self
+    }
+
+    /// Set the hashed cursor factory.
+    pub fn with_hashed_cursor_factory<HF>(self, hashed_cursor_factory: HF) -> Proof<'a, TX, HF> {
+        Proof {
+            tx: self.tx,
+            hashed_cursor_factory,
+            changed_account_prefixes: self.changed_account_prefixes,
+            changed_storage_prefixes: self.changed_storage_prefixes,
+        }
+    }
 }
 
 impl<'a, TX> Proof<'a, TX, &'a TX> {
     /// Create a new [Proof] instance.
     pub fn new(tx: &'a TX) -> Self {
-        Self { tx, hashed_cursor_factory: tx }
+        Self {
+            tx,
+            hashed_cursor_factory: tx,
+            changed_account_prefixes: PrefixSetMut::default(),
+            changed_storage_prefixes: HashMap::default(),
+        }
+    }
+}
+
+impl<'a, TX: DbTx> Proof<'a, TX, &'a TX> {
+    /// Generate an account proof for the state reverted by the given post state.
+    ///
+    /// The reverted state, e.g. built with [HashedPostState::from_revert_range], is applied on top
+    /// of the current hashed state and the reverted keys are added to the prefix sets, so that the
+    /// intermediate trie nodes affected by the changes are recomputed instead of being loaded from
+    /// the database.
+    pub fn historical_account_proof(
+        tx: &'a TX,
+        reverted_state: &HashedPostState,
+        address: Address,
+        slots: &[B256],
+    ) -> Result<AccountProof, StateRootError> {
+        let (account_prefix_set, storage_prefix_sets) = reverted_state.construct_prefix_sets_mut();
+        Self::new(tx)
+            .with_hashed_cursor_factory(HashedPostStateCursorFactory::new(tx, reverted_state))
+            .with_changed_account_prefixes(account_prefix_set)
+            .with_changed_storage_prefixes(storage_prefix_sets)
+            .account_proof(address, slots)
     }
 }
 
@@ -55,7 +114,7 @@ where
         let trie_cursor = AccountTrieCursor::new(self.tx.cursor_read::<tables::AccountsTrie>()?);
 
         // Create the walker.
-        let mut prefix_set = PrefixSetMut::default();
+        let mut prefix_set = self.changed_account_prefixes.clone();
         prefix_set.insert(target_nibbles.clone());
         let walker = TrieWalker::new(trie_cursor, prefix_set.freeze());
 
@@ -97,6 +156,98 @@ where
         Ok(account_proof)
     }
 
+    /// Generate the account trie nodes on the paths from the root to the given targets and
+    /// return them together with the state root.
+    ///
+    /// The targets are the nibbles of hashed addresses or the paths of trie nodes. The retained
+    /// nodes are keyed by their path, so they are ordered from the root towards the leaves. The
+    /// nodes for the first and the last hashed address of a range of accounts prove the range.
+    pub fn account_proof_nodes(
+        &self,
+        targets: Vec<Nibbles>,
+    ) -> Result<(B256, BTreeMap<Nibbles, Bytes>), StateRootError> {
+        let hashed_account_cursor = self.hashed_cursor_factory.hashed_account_cursor()?;
+        let trie_cursor = AccountTrieCursor::new(self.tx.cursor_read::<tables::AccountsTrie>()?);
+
+        let mut prefix_set = self.changed_account_prefixes.clone();
+        for target in &targets {
+            prefix_set.insert(target.clone());
+        }
+        let walker = TrieWalker::new(trie_cursor, prefix_set.freeze());
+
+        let mut hash_builder = HashBuilder::default().with_proof_retainer(targets);
+
+        let mut account_rlp = Vec::with_capacity(128);
+        let mut account_node_iter = AccountNodeIter::new(walker, hashed_account_cursor);
+        while let Some(account_node) = account_node_iter.try_next()? {
+            match account_node {
+                AccountNode::Branch(node) => {
+                    hash_builder.add_branch(node.key, node.value, node.children_are_in_trie);
+                }
+                AccountNode::Leaf(hashed_address, account) => {
+                    let storage_root = self.storage_root(hashed_address)?;
+
+                    account_rlp.clear();
+                    let account = EthAccount::from(account).with_storage_root(storage_root);
+                    account.encode(&mut account_rlp as &mut dyn BufMut);
+
+                    hash_builder.add_leaf(Nibbles::unpack(hashed_address), &account_rlp);
+                }
+            }
+        }
+
+        let root = hash_builder.root();
+        Ok((root, hash_builder.take_proofs()))
+    }
+
+    /// Generate the storage trie nodes of the account on the paths from the root to the given
+    /// targets and return them together with the storage root.
+    ///
+    /// See [Self::account_proof_nodes] for more info.
+    pub fn storage_proof_nodes(
+        &self,
+        hashed_address: B256,
+        targets: Vec<Nibbles>,
+    ) -> Result<(B256, BTreeMap<Nibbles, Bytes>), StorageRootError> {
+        let mut hashed_storage_cursor = self.hashed_cursor_factory.hashed_storage_cursor()?;
+
+        // short circuit on empty storage
+        if hashed_storage_cursor.is_storage_empty(hashed_address)? {
+            return Ok((EMPTY_ROOT_HASH, BTreeMap::default()))
+        }
+
+        let mut prefix_set =
+            self.changed_storage_prefixes.get(&hashed_address).cloned().unwrap_or_default();
+        for target in &targets {
+            prefix_set.insert(target.clone());
+        }
+        let trie_cursor = StorageTrieCursor::new(
+            self.tx.cursor_dup_read::<tables::StoragesTrie>()?,
+            hashed_address,
+        );
+        let walker = TrieWalker::new(trie_cursor, prefix_set.freeze());
+
+        let mut hash_builder = HashBuilder::default().with_proof_retainer(targets);
+        let mut storage_node_iter =
+            StorageNodeIter::new(walker, hashed_storage_cursor, hashed_address);
+        while let Some(node) = storage_node_iter.try_next()? {
+            match node {
+                StorageNode::Branch(node) => {
+                    hash_builder.add_branch(node.key, node.value, node.children_are_in_trie);
+                }
+                StorageNode::Leaf(hashed_slot, value) => {
+                    hash_builder.add_leaf(
+                        Nibbles::unpack(hashed_slot),
+                        alloy_rlp::encode_fixed_size(&value).as_ref(),
+                    );
+                }
+            }
+        }
+
+        let root = hash_builder.root();
+        Ok((root, hash_builder.take_proofs()))
+    }
+
     /// Compute storage root.
     pub fn storage_root(&self, hashed_address: B256) -> Result<B256, StorageRootError> {
         let (storage_root, _) = self.storage_root_with_proofs(hashed_address, &[])?;
@@ -119,12 +270,16 @@ where
         }
 
         let target_nibbles = proofs.iter().map(|p| p.nibbles.clone()).collect::<Vec<_>>();
-        let prefix_set = PrefixSetMut::from(target_nibbles.clone()).freeze();
+        let mut prefix_set =
+            self.changed_storage_prefixes.get(&hashed_address).cloned().unwrap_or_default();
+        for nibbles in &target_nibbles {
+            prefix_set.insert(nibbles.clone());
+        }
         let trie_cursor = StorageTrieCursor::new(
             self.tx.cursor_dup_read::<tables::StoragesTrie>()?,
             hashed_address,
         );
-        let walker = TrieWalker::new(trie_cursor, prefix_set);
+        let walker = TrieWalker::new(trie_cursor, prefix_set.freeze());
 
         let mut hash_builder = HashBuilder::default().with_proof_retainer(target_nibbles);
         let mut storage_node_iter =
@@ -166,7 +321,12 @@ mod tests {
     use super::*;
     use crate::StateRoot;
     use once_cell::sync::Lazy;
-    use reth_db::{database::Database, test_utils::create_test_rw_db};
+    use reth_db::{
+        database::Database,
+        models::{AccountBeforeTx, BlockNumberAddress},
+        test_utils::create_test_rw_db,
+        transaction::DbTxMut,
+    };
     use reth_interfaces::RethResult;
     use reth_primitives::{Account, Bytes, Chain, ChainSpec, StorageEntry, HOLESKY, MAINNET, U256};
     use reth_provider::{HashingWriter, ProviderFactory};
@@ -438,4 +598,116 @@ mod tests {
         let account_proof = Proof::new(&tx).account_proof(target, &slots).unwrap();
         pretty_assertions::assert_eq!(account_proof, expected);
     }
+
+    #[test]
+    fn holesky_deposit_contract_historical_proof() {
+        // Create test database and insert genesis accounts.
+        let db = create_test_rw_db();
+        insert_genesis(db.clone(), HOLESKY.clone()).unwrap();
+
+        let target = Address::from_str("0x4242424242424242424242424242424242424242").unwrap();
+        let created = Address::repeat_byte(0x11);
+        let slot_22 =
+            B256::from_str("0x0000000000000000000000000000000000000000000000000000000000000022")
+                .unwrap();
+        let slot_100 =
+            B256::from_str("0x0000000000000000000000000000000000000000000000000000000000000100")
+                .unwrap();
+        let slots = Vec::from([slot_22, slot_100]);
+
+        // Generate the proofs before block 1 is applied.
+        let tx = db.tx().unwrap();
+        let expected_target_proof = Proof::new(&tx).account_proof(target, &slots).unwrap();
+        let expected_created_proof = Proof::new(&tx).account_proof(created, &[]).unwrap();
+        drop(tx);
+
+        // Apply block 1: update the deposit contract and create a new account.
+        let provider_factory = ProviderFactory::new(db.clone(), HOLESKY.clone());
+        let mut provider = provider_factory.provider_rw().unwrap();
+
+        let target_account = expected_target_proof.info.unwrap();
+        let updated_target_account = Account { balance: U256::from(1), ..target_account };
+        let created_account = Account { nonce: 1, balance: U256::from(10), bytecode_hash: None };
+        provider
+            .insert_account_for_hashing([
+                (target, Some(updated_target_account)),
+                (created, Some(created_account)),
+            ])
+            .unwrap();
+        provider
+            .insert_storage_for_hashing([(
+                target,
+                [
+                    StorageEntry { key: slot_22, value: U256::from(1) },
+                    StorageEntry { key: slot_100, value: U256::from(2) },
+                ],
+            )])
+            .unwrap();
+
+        let tx = provider.tx_ref();
+        tx.put::<tables::PlainAccountState>(target, updated_target_account).unwrap();
+        tx.put::<tables::PlainAccountState>(created, created_account).unwrap();
+        tx.put::<tables::AccountChangeSet>(1, AccountBeforeTx { address: created, info: None })
+            .unwrap();
+        tx.put::<tables::AccountChangeSet>(
+            1,
+            AccountBeforeTx { address: target, info: Some(target_account) },
+        )
+        .unwrap();
+        for (key, value) in
+            [(slot_22, expected_target_proof.storage_proofs[0].value), (slot_100, U256::ZERO)]
+        {
+            tx.put::<tables::StorageChangeSet>(
+                BlockNumberAddress((1, target)),
+                StorageEntry { key, value },
+            )
+            .unwrap();
+        }
+
+        let (_, updates) =
+            StateRoot::incremental_root_with_updates(provider.tx_ref(), 1..=1).unwrap();
+        updates.flush(provider.tx_mut()).unwrap();
+        provider.commit().unwrap();
+
+        let tx = db.tx().unwrap();
+        assert_ne!(Proof::new(&tx).account_proof(target, &slots).unwrap(), expected_target_proof);
+
+        // Reverting block 1 must yield the proofs at the genesis state.
+        let reverted_state = HashedPostState::from_revert_range(&tx, 1..=1).unwrap();
+        let target_proof =
+            Proof::historical_account_proof(&tx, &reverted_state, target, &slots).unwrap();
+        pretty_assertions::assert_eq!(target_proof, expected_target_proof);
+        let created_proof =
+            Proof::historical_account_proof(&tx, &reverted_state, created, &[]).unwrap();
+        pretty_assertions::assert_eq!(created_proof, expected_created_proof);
+    }
+
+    #[test]
+    fn testspec_proof_nodes() {
+        // Create test database and insert genesis accounts.
+        let db = create_test_rw_db();
+        insert_genesis(db.clone(), TEST_SPEC.clone()).unwrap();
+
+        let tx = db.tx().unwrap();
+        let first = Address::from_str("0x2031f89b3ea8014eb51a78c316e42af3e0d7695f").unwrap();
+        let last = Address::from_str("0x62b0dd4aab2b1a0a04e279e2b828791a10755528").unwrap();
+
+        let proof = Proof::new(&tx);
+        let (root, nodes) = proof
+            .account_proof_nodes(Vec::from([
+                Nibbles::unpack(keccak256(first)),
+                Nibbles::unpack(keccak256(last)),
+            ]))
+            .unwrap();
+        assert_eq!(root, StateRoot::new(&tx).root().unwrap());
+
+        // The nodes of a multiproof are the union of the nodes of the single proofs.
+        let mut expected = proof.account_proof(first, &[]).unwrap().proof;
+        expected.extend(proof.account_proof(last, &[]).unwrap().proof);
+        expected.sort();
+        expected.dedup();
+        let mut nodes = nodes.into_values().collect::<Vec<_>>();
+        nodes.sort();
+        pretty_assertions::assert_eq!(nodes, expected);
+    }
 }